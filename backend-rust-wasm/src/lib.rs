@@ -1,8 +1,8 @@
-use std::f32::consts::PI;
-
 use backend_rust::{
-    height_data::HeightGrid,
-    search::{search_from_height_grid, SearchQuery},
+    height_data::{DataSource, HeightGrid},
+    polar::parse_polar,
+    search::{search_from_height_grid, GlideModel, GridIxType, SearchQuery},
+    wind::parse_wind_layers,
 };
 use ndarray::Array2;
 use serde::{Deserialize, Serialize};
@@ -16,7 +16,7 @@ struct HeightMapInput {
     min_cell_size: f32,
     lat: [f32; 2],
     lon: [f32; 2],
-    start_ix: [u16; 2],
+    start_ix: [GridIxType; 2],
 }
 
 #[derive(Debug, Deserialize)]
@@ -29,6 +29,29 @@ struct SearchInput {
     trim_speed: f32,
     safety_margin: f32,
     start_distance: f32,
+    #[serde(default)]
+    ridge_lift: bool,
+    /// Speed polar as `speed:sink` pairs, see `parse_polar`.
+    #[serde(default)]
+    polar: Option<String>,
+    /// Wind by altitude as `altitude:speed:direction` triples, see
+    /// `parse_wind_layers`.
+    #[serde(default)]
+    wind_layers: Option<String>,
+    /// Bearings in degrees between which the cone may leave the start.
+    #[serde(default)]
+    sector_from: Option<f32>,
+    #[serde(default)]
+    sector_to: Option<f32>,
+    /// Search backwards for the heights needed to reach the start.
+    #[serde(default)]
+    reverse: bool,
+    /// Raise the terrain by the vertical error of the height map.
+    #[serde(default)]
+    terrain_error: bool,
+    /// `geometric` (default) or `energy`, see `GlideModel`.
+    #[serde(default)]
+    model: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -39,12 +62,15 @@ struct FlightConeRequest {
 
 #[derive(Debug, Serialize)]
 struct FlightConeNode {
-    index: [u16; 2],
-    reference: Option<[u16; 2]>,
+    index: [GridIxType; 2],
+    reference: Option<[GridIxType; 2]>,
     height: f32,
     distance: f32,
     agl: f32,
     in_safety_margin: bool,
+    /// Lowest height above ground along the path, `None` if the whole path
+    /// is within the start distance.
+    clearance: Option<f32>,
 }
 
 #[derive(Debug, Serialize)]
@@ -54,7 +80,7 @@ struct FlightConeResponse {
     min_cell_size: f32,
     lat: [f32; 2],
     lon: [f32; 2],
-    start_ix: [u16; 2],
+    start_ix: [GridIxType; 2],
     grid_shape: [usize; 2],
     start_height: f32,
 }
@@ -84,24 +110,62 @@ pub fn compute_flight_cone(request: JsValue) -> Result<JsValue, JsValue> {
         min_cell_size: request.height_map.min_cell_size,
         latitudes: (request.height_map.lat[0], request.height_map.lat[1]),
         longitudes: (request.height_map.lon[0], request.height_map.lon[1]),
+        data_source: DataSource::Srtm,
+        data_quality: None,
     };
 
-    let query = SearchQuery {
-        glide_ratio: 1.0 / request.search.glide_number,
-        trim_speed: request.search.trim_speed,
-        wind_direction: request.search.wind_direction / 180.0 * PI,
-        wind_speed: request.search.wind_speed,
-        start_height: request.search.start_height,
-        additional_height: request.search.additional_height,
-        safety_margin: request.search.safety_margin,
-        start_distance: request.search.start_distance,
+    let search = &request.search;
+    let mut builder = SearchQuery::builder()
+        .glide_number(search.glide_number)
+        .trim_speed_kmh(search.trim_speed)
+        .wind_speed_kmh(search.wind_speed)
+        .wind_direction_deg(search.wind_direction)
+        .additional_height_m(search.additional_height)
+        .safety_margin_m(search.safety_margin)
+        .start_distance_m(search.start_distance)
+        .ridge_lift(search.ridge_lift)
+        .reverse(search.reverse)
+        .terrain_error(search.terrain_error);
+    if let Some(start_height) = search.start_height {
+        builder = builder.start_height_m(start_height);
+    }
+    if let Some(polar) = search.polar.as_deref() {
+        builder = builder.polar(
+            parse_polar(polar).map_err(|e| JsValue::from_str(&format!("Invalid polar: {e}")))?,
+        );
+    }
+    if let Some(wind_layers) = search.wind_layers.as_deref() {
+        builder = builder.wind_layers(
+            parse_wind_layers(wind_layers)
+                .map_err(|e| JsValue::from_str(&format!("Invalid wind layers: {e}")))?,
+        );
+    }
+    if let Some(model) = search.model.as_deref() {
+        builder = builder.model(
+            model
+                .parse::<GlideModel>()
+                .map_err(|e| JsValue::from_str(&e))?,
+        );
+    }
+    builder = match (search.sector_from, search.sector_to) {
+        (Some(from), Some(to)) => builder.start_sector_deg(from, to),
+        (None, None) => builder,
+        _ => {
+            return Err(JsValue::from_str(
+                "Invalid sector: sector_from and sector_to have to be given together",
+            ))
+        }
     };
+    let query = builder
+        .build()
+        .map_err(|e| JsValue::from_str(&format!("Invalid search: {e}")))?;
 
     let start_ix = (
         request.height_map.start_ix[0],
         request.height_map.start_ix[1],
     );
-    let result = search_from_height_grid(grid, start_ix, query);
+    let result = search_from_height_grid(grid, start_ix, query)
+        .map_err(|e| JsValue::from_str(&e.to_string()))?;
 
     let start_height = result
         .explored
@@ -121,7 +185,8 @@ pub fn compute_flight_cone(request: JsValue) -> Result<JsValue, JsValue> {
             distance: node.distance,
             agl: node.height
                 - result.height_grid.heights[(node.ix.0 as usize, node.ix.1 as usize)] as f32,
-            in_safety_margin: node.in_safety_margin,
+            in_safety_margin: node.in_safety_margin(request.search.safety_margin),
+            clearance: node.clearance.is_finite().then_some(node.clearance),
         })
         .collect::<Vec<_>>();
 
@@ -4,17 +4,13 @@ use backend_rust::search::{prepare_search, search, SearchQuery};
 
 fn criterion_benchmark(c: &mut Criterion) {
     c.bench_function("search", |b| {
-        let query = SearchQuery {
-            glide_ratio: 1.0 / 8.0,
-            trim_speed: 38.0,
-            wind_direction: 0.0,
-            wind_speed: 0.0,
-            additional_height: 10.0,
-            safety_margin: 0.0,
-            start_distance: 0.0,
-            start_height: None,
-        };
-        let search_setup = prepare_search(47.4212, 10.9857, 30.0, query);
+        let query = SearchQuery::builder()
+            .glide_number(8.0)
+            .trim_speed_kmh(38.0)
+            .additional_height_m(10.0)
+            .build()
+            .unwrap();
+        let search_setup = prepare_search(47.4212, 10.9857, 30.0, query).unwrap();
         b.iter(|| {
             let _result = search(
                 black_box(search_setup.start_ix),
@@ -0,0 +1,84 @@
+const map = L.map("map").setView([47.42, 10.98], 10);
+L.tileLayer("/openstreetmap/a/{z}/{x}/{y}.png", {
+    maxZoom: 18,
+    attribution: "&copy; OpenStreetMap contributors",
+}).addTo(map);
+
+const form = document.getElementById("params");
+const status = document.getElementById("status");
+const responseView = document.getElementById("response");
+let marker = undefined;
+let overlay = undefined;
+
+map.on("click", (event) => {
+    form.lat.value = event.latlng.lat.toFixed(5);
+    form.lon.value = event.latlng.lng.toFixed(5);
+    if (marker === undefined) {
+        marker = L.marker(event.latlng).addTo(map);
+    } else {
+        marker.setLatLng(event.latlng);
+    }
+});
+
+function searchParams() {
+    const params = new URLSearchParams();
+    for (const element of form.elements) {
+        if (!element.name || element.name === "overlay") {
+            continue;
+        }
+        if (element.type === "checkbox") {
+            if (element.checked) {
+                params.set(element.name, "true");
+            }
+        } else if (element.value !== "") {
+            params.set(element.name, element.value);
+        }
+    }
+    return params;
+}
+
+async function fetchChecked(url) {
+    const response = await fetch(url);
+    if (!response.ok) {
+        throw new Error(`${response.status} ${await response.text()}`);
+    }
+    return response;
+}
+
+form.addEventListener("submit", async (event) => {
+    event.preventDefault();
+    const params = searchParams();
+    if (overlay !== undefined) {
+        map.removeLayer(overlay);
+        overlay = undefined;
+    }
+    status.textContent = "Searching...";
+    const started = performance.now();
+
+    try {
+        const cone = await (await fetchChecked(`/flight_cone_bounds?${params}`)).json();
+        responseView.textContent = JSON.stringify(cone, null, 2);
+
+        const kind = form.overlay.value;
+        if (kind === "flight_cone_geojson") {
+            const geojson = await (await fetchChecked(`/${kind}?${params}`)).json();
+            overlay = L.geoJSON(geojson, {
+                style: { weight: 0, fillOpacity: 0.4 },
+                onEachFeature: (feature, layer) =>
+                    layer.bindTooltip(JSON.stringify(feature.properties)),
+            });
+        } else {
+            const bounds = [
+                [cone.lat[0], cone.lon[0]],
+                [cone.lat[1], cone.lon[1]],
+            ];
+            overlay = L.imageOverlay(`/${kind}?${params}`, bounds, { opacity: 0.6 });
+        }
+        overlay.addTo(map);
+
+        const seconds = ((performance.now() - started) / 1000).toFixed(2);
+        status.textContent = `Done in ${seconds} s`;
+    } catch (error) {
+        status.textContent = `Failed: ${error.message}`;
+    }
+});
@@ -198,3 +198,40 @@ downloadZip "L45" "3"
 downloadZip "L46" "3"
 downloadZip "L47" "3"
 downloadZip "L48" "3"
+
+
+# Coarse global DEM
+
+echo "===================================================="
+echo ""
+echo ""
+echo "Coarse global DEM"
+echo ""
+echo ""
+echo "===================================================="
+
+# Used as fallback where no SRTM tiles are available and for cell sizes >= 500m.
+# Tiles are stored in the same format as the .hgt files (square, big endian i16)
+# but at a lower resolution (241x241 = 15 arc seconds). Bathymetry is clamped to 0,
+# since the search does not need it and large negative values are treated as voids.
+downloadCoarse () {
+    wget https://edcintl.cr.usgs.gov/downloads/sciweb1/shared/topo/downloads/GMTED/Grid_ZipFiles/$1.zip
+    unzip $1.zip -d $1
+    gdal_calc.py -A $1/*.tif --outfile=$1/clamped.tif --calc="maximum(A,0)" --type=Int16
+    mkdir -p ./coarse
+    for lat in $(seq -60 83); do
+        for lon in $(seq -180 179); do
+            name=$(printf "%s%02d%s%03d" $([ $lat -ge 0 ] && echo N || echo S) ${lat#-} $([ $lon -ge 0 ] && echo E || echo W) ${lon#-})
+            gdal_translate -q -of ENVI -ot Int16 -outsize 241 241 \
+                -projwin $lon $((lat + 1)) $((lon + 1)) $lat \
+                $1/clamped.tif ./coarse/$name.bil 2>/dev/null || continue
+            # ENVI writes little endian, hgt files are big endian
+            dd if=./coarse/$name.bil of=./coarse/$name.hgt conv=swab 2>/dev/null
+            rm -f ./coarse/$name.bil ./coarse/$name.hdr ./coarse/$name.bil.aux.xml
+        done
+    done
+    rm $1.zip
+    rm -rf $1/
+}
+
+downloadCoarse "mn15_grd"
@@ -0,0 +1,439 @@
+use std::{f32::consts::PI, str::FromStr};
+
+use ndarray::Array2;
+use serde::Serialize;
+
+use crate::{
+    geojson::{Feature, FeatureCollection, Geometry},
+    height_data::HeightGrid,
+    region::ring_contains,
+    search::GridIx,
+};
+
+/// Airspaces in OpenAir format in the data directory.
+pub const AIRSPACE_FILE_NAME: &str = "airspaces.txt";
+
+const FEET: f32 = 0.3048;
+const NAUTICAL_MILE: f32 = 1852.0;
+const METERS_PER_DEGREE: f32 = 111_320.0;
+/// Angle between the points of an arc or circle, in degrees.
+const ARC_STEP: f32 = 5.0;
+
+/// Classes that may not be entered without a clearance. Classes E to G,
+/// RMZ, TMZ and wave windows are free to fly for gliders.
+const RESTRICTED_CLASSES: [&str; 9] = ["A", "B", "C", "D", "CTR", "P", "Q", "R", "GP"];
+
+/// Lower or upper limit of an airspace, in meters.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AltitudeLimit {
+    /// Above sea level. Flight levels are converted assuming standard
+    /// pressure.
+    Msl(f32),
+    /// Above ground.
+    Agl(f32),
+}
+
+impl AltitudeLimit {
+    /// Altitude above sea level where the ground is at `ground_height`.
+    pub fn msl(&self, ground_height: f32) -> f32 {
+        match self {
+            AltitudeLimit::Msl(altitude) => *altitude,
+            AltitudeLimit::Agl(height) => ground_height + height,
+        }
+    }
+}
+
+/// Parses OpenAir altitudes like `GND`, `1500ft MSL`, `FL95`, `300m AGL` or
+/// `UNL`. Altitudes without unit are in feet, without reference above sea
+/// level.
+pub fn parse_altitude(value: &str) -> Result<AltitudeLimit, String> {
+    let upper = value.trim().to_uppercase();
+    if ["GND", "SFC", "AGL", "ASFC"].contains(&upper.as_str()) {
+        return Ok(AltitudeLimit::Agl(0.0));
+    }
+    if upper.starts_with("UNL") {
+        return Ok(AltitudeLimit::Msl(f32::INFINITY));
+    }
+    if let Some(level) = upper.strip_prefix("FL") {
+        let level = level
+            .trim()
+            .parse::<f32>()
+            .map_err(|e| format!("Bad flight level {value}: {e}"))?;
+        return Ok(AltitudeLimit::Msl(level * 100.0 * FEET));
+    }
+
+    let number_end = upper
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(upper.len());
+    let number = upper[..number_end]
+        .parse::<f32>()
+        .map_err(|e| format!("Bad altitude {value}: {e}"))?;
+    let rest = upper[number_end..].trim();
+    let (meters, reference) = match rest.strip_prefix('M') {
+        Some(reference) if !reference.starts_with("SL") => (number, reference),
+        _ => (
+            number * FEET,
+            rest.strip_prefix("FT")
+                .or(rest.strip_prefix('F'))
+                .unwrap_or(rest),
+        ),
+    };
+    match reference.trim() {
+        "AGL" | "AGND" | "GND" | "SFC" => Ok(AltitudeLimit::Agl(meters)),
+        "" | "MSL" | "AMSL" | "ALT" => Ok(AltitudeLimit::Msl(meters)),
+        _ => Err(format!("Unknown altitude reference in {value}")),
+    }
+}
+
+/// Parses OpenAir coordinates like `47:30:00 N 011:15:30 E` or
+/// `47:30.5N 11:15.5E` into `(latitude, longitude)`.
+pub fn parse_coordinate(value: &str) -> Result<(f32, f32), String> {
+    let upper = value.trim().to_uppercase();
+    let lat_end = upper
+        .find(['N', 'S'])
+        .ok_or_else(|| format!("Coordinate {value} has no latitude hemisphere"))?;
+    let lon_end = upper
+        .rfind(['E', 'W'])
+        .filter(|end| *end > lat_end)
+        .ok_or_else(|| format!("Coordinate {value} has no longitude hemisphere"))?;
+
+    let degrees = |part: &str| {
+        let mut result = 0.0;
+        for (i, component) in part.trim().split(':').enumerate() {
+            let component = component
+                .trim()
+                .parse::<f32>()
+                .map_err(|e| format!("Bad coordinate {value}: {e}"))?;
+            result += component / 60f32.powi(i as i32);
+        }
+        Ok::<f32, String>(result)
+    };
+    let mut lat = degrees(&upper[..lat_end])?;
+    let mut lon = degrees(&upper[lat_end + 1..lon_end])?;
+    if &upper[lat_end..lat_end + 1] == "S" {
+        lat = -lat;
+    }
+    if &upper[lon_end..lon_end + 1] == "W" {
+        lon = -lon;
+    }
+    Ok((lat, lon))
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Airspace {
+    pub name: String,
+    pub class: String,
+    pub floor: AltitudeLimit,
+    pub ceiling: AltitudeLimit,
+    /// Boundary as `[longitude, latitude]` positions, not closed.
+    pub polygon: Vec<[f32; 2]>,
+}
+
+impl Airspace {
+    pub fn is_restricted(&self) -> bool {
+        RESTRICTED_CLASSES.contains(&self.class.as_str())
+    }
+
+    /// `(south, north)` and `(west, east)` bounds of the boundary.
+    pub fn bounds(&self) -> ((f32, f32), (f32, f32)) {
+        self.polygon.iter().fold(
+            (
+                (f32::INFINITY, f32::NEG_INFINITY),
+                (f32::INFINITY, f32::NEG_INFINITY),
+            ),
+            |(lat, lon), [x, y]| {
+                (
+                    (lat.0.min(*y), lat.1.max(*y)),
+                    (lon.0.min(*x), lon.1.max(*x)),
+                )
+            },
+        )
+    }
+
+    pub fn intersects_bounds(&self, latitudes: (f32, f32), longitudes: (f32, f32)) -> bool {
+        let (lat, lon) = self.bounds();
+        lat.0 <= latitudes.1
+            && lat.1 >= latitudes.0
+            && lon.0 <= longitudes.1
+            && lon.1 >= longitudes.0
+    }
+
+    /// Whether the point lies inside the boundary.
+    pub fn contains(&self, latitude: f32, longitude: f32) -> bool {
+        ring_contains(&self.polygon, latitude, longitude)
+    }
+}
+
+/// Point at `distance` meters in direction `bearing` (radians, clockwise from
+/// north) from `center`, as `[longitude, latitude]`.
+fn offset(center: (f32, f32), bearing: f32, distance: f32) -> [f32; 2] {
+    let lat = center.0 + distance * bearing.cos() / METERS_PER_DEGREE;
+    let lon =
+        center.1 + distance * bearing.sin() / (METERS_PER_DEGREE * center.0.to_radians().cos());
+    [lon, lat]
+}
+
+/// Bearing (radians) and distance (meters) from `center` to `point`.
+fn polar_offset(center: (f32, f32), point: (f32, f32)) -> (f32, f32) {
+    let north = (point.0 - center.0) * METERS_PER_DEGREE;
+    let east = (point.1 - center.1) * METERS_PER_DEGREE * center.0.to_radians().cos();
+    (east.atan2(north), east.hypot(north))
+}
+
+/// Points of an arc from bearing `from` to `to` (radians), clockwise if
+/// `clockwise` is set.
+fn arc(center: (f32, f32), radius: f32, from: f32, to: f32, clockwise: bool) -> Vec<[f32; 2]> {
+    let sweep = if clockwise {
+        (to - from).rem_euclid(2.0 * PI)
+    } else {
+        -(from - to).rem_euclid(2.0 * PI)
+    };
+    let steps = ((sweep.abs() / ARC_STEP.to_radians()).ceil() as usize).max(1);
+    (0..=steps)
+        .map(|i| offset(center, from + sweep * i as f32 / steps as f32, radius))
+        .collect()
+}
+
+/// Airspace while its record is being parsed.
+#[derive(Default)]
+struct PartialAirspace {
+    name: String,
+    class: String,
+    floor: Option<AltitudeLimit>,
+    ceiling: Option<AltitudeLimit>,
+    polygon: Vec<[f32; 2]>,
+    center: Option<(f32, f32)>,
+    clockwise: bool,
+}
+
+impl PartialAirspace {
+    fn new(class: &str) -> PartialAirspace {
+        PartialAirspace {
+            class: class.to_string(),
+            clockwise: true,
+            ..Default::default()
+        }
+    }
+
+    fn center(&self) -> Result<(f32, f32), String> {
+        self.center
+            .ok_or("Arc or circle without center".to_string())
+    }
+
+    /// Airspaces without a proper boundary are dropped.
+    fn finish(self) -> Option<Airspace> {
+        if self.polygon.len() < 3 {
+            return None;
+        }
+        Some(Airspace {
+            name: self.name,
+            class: self.class,
+            floor: self.floor.unwrap_or(AltitudeLimit::Agl(0.0)),
+            ceiling: self.ceiling.unwrap_or(AltitudeLimit::Msl(f32::INFINITY)),
+            polygon: self.polygon,
+        })
+    }
+}
+
+/// Parses airspaces in OpenAir format. Supports polygons, circles and both
+/// kinds of arcs, records only used for display (labels, colors) are
+/// ignored.
+pub fn parse_openair(content: &str) -> Result<Vec<Airspace>, String> {
+    let mut airspaces = vec![];
+    let mut current: Option<PartialAirspace> = None;
+
+    for (line_number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('*') {
+            continue;
+        }
+        let (record, value) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let value = value.trim();
+        let error = |e: String| format!("Line {}: {e}", line_number + 1);
+
+        if record == "AC" {
+            airspaces.extend(current.take().and_then(PartialAirspace::finish));
+            current = Some(PartialAirspace::new(value));
+            continue;
+        }
+        let Some(airspace) = current.as_mut() else {
+            continue;
+        };
+        match record {
+            "AN" => airspace.name = value.to_string(),
+            "AL" => airspace.floor = Some(parse_altitude(value).map_err(error)?),
+            "AH" => airspace.ceiling = Some(parse_altitude(value).map_err(error)?),
+            "DP" => {
+                let (lat, lon) = parse_coordinate(value).map_err(error)?;
+                airspace.polygon.push([lon, lat]);
+            }
+            "V" => {
+                let (key, value) = value
+                    .split_once('=')
+                    .ok_or_else(|| error(format!("Bad variable {value}")))?;
+                match key.trim() {
+                    "X" => airspace.center = Some(parse_coordinate(value).map_err(error)?),
+                    "D" => airspace.clockwise = value.trim() != "-",
+                    _ => {}
+                }
+            }
+            "DC" => {
+                let radius = value
+                    .parse::<f32>()
+                    .map_err(|e| error(format!("Bad radius {value}: {e}")))?;
+                let center = airspace.center().map_err(error)?;
+                let steps = (360.0 / ARC_STEP) as usize;
+                airspace.polygon.extend((0..steps).map(|i| {
+                    offset(
+                        center,
+                        2.0 * PI * i as f32 / steps as f32,
+                        radius * NAUTICAL_MILE,
+                    )
+                }));
+            }
+            "DA" => {
+                let numbers = value
+                    .split(',')
+                    .map(|part| part.trim().parse::<f32>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| error(format!("Bad arc {value}: {e}")))?;
+                let [radius, from, to] = numbers[..] else {
+                    return Err(error(format!("Arc {value} needs radius, start and end")));
+                };
+                let center = airspace.center().map_err(error)?;
+                airspace.polygon.extend(arc(
+                    center,
+                    radius * NAUTICAL_MILE,
+                    from.to_radians(),
+                    to.to_radians(),
+                    airspace.clockwise,
+                ));
+            }
+            "DB" => {
+                let (from, to) = value
+                    .split_once(',')
+                    .ok_or_else(|| error(format!("Arc {value} needs two coordinates")))?;
+                let center = airspace.center().map_err(error)?;
+                let (from_bearing, radius) =
+                    polar_offset(center, parse_coordinate(from).map_err(error)?);
+                let (to_bearing, _) = polar_offset(center, parse_coordinate(to).map_err(error)?);
+                airspace.polygon.extend(arc(
+                    center,
+                    radius,
+                    from_bearing,
+                    to_bearing,
+                    airspace.clockwise,
+                ));
+            }
+            _ => {}
+        }
+    }
+    airspaces.extend(current.and_then(PartialAirspace::finish));
+    Ok(airspaces)
+}
+
+/// Whether an airspace is flagged or avoided by the search.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AirspaceMode {
+    /// Cells are unreachable where the glide would enter restricted airspace.
+    Avoid,
+    /// Cells in restricted airspace stay reachable but are marked.
+    Flag,
+}
+
+impl FromStr for AirspaceMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "avoid" => Ok(AirspaceMode::Avoid),
+            "flag" => Ok(AirspaceMode::Flag),
+            _ => Err(format!("Unknown airspace mode {s}")),
+        }
+    }
+}
+
+/// Vertical extent of the restricted airspace over each cell of a height
+/// grid, as `(floor, ceiling)` above sea level.
+#[derive(Clone)]
+pub struct AirspaceGrid {
+    bands: Array2<Vec<(f32, f32)>>,
+}
+
+impl AirspaceGrid {
+    pub fn new(airspaces: &[Airspace], grid: &HeightGrid) -> AirspaceGrid {
+        let (lats, lons) = grid.get_coordinates_for_indices();
+        let mut bands = Array2::from_elem(grid.heights.raw_dim(), vec![]);
+
+        for airspace in airspaces.iter().filter(|a| a.is_restricted()) {
+            let (lat_bounds, lon_bounds) = airspace.bounds();
+            let rows = lats.partition_point(|lat| *lat < lat_bounds.0)
+                ..lats.partition_point(|lat| *lat <= lat_bounds.1);
+            let cols = lons.partition_point(|lon| *lon < lon_bounds.0)
+                ..lons.partition_point(|lon| *lon <= lon_bounds.1);
+            for row in rows {
+                for col in cols.clone() {
+                    if airspace.contains(lats[row], lons[col]) {
+                        let ground = grid.heights[(row, col)] as f32;
+                        bands[(row, col)]
+                            .push((airspace.floor.msl(ground), airspace.ceiling.msl(ground)));
+                    }
+                }
+            }
+        }
+        AirspaceGrid { bands }
+    }
+
+    /// Whether `altitude` above sea level is inside restricted airspace at
+    /// `ix`.
+    pub fn contains(&self, ix: &GridIx, altitude: f32) -> bool {
+        self.bands[(ix.0 as usize, ix.1 as usize)]
+            .iter()
+            .any(|(floor, ceiling)| *floor <= altitude && altitude < *ceiling)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct AirspaceProperties {
+    pub name: String,
+    pub class: String,
+    pub floor: AltitudeLimit,
+    pub ceiling: AltitudeLimit,
+    pub restricted: bool,
+}
+
+/// Airspaces overlapping the given bounds as GeoJSON.
+pub fn airspace_features(
+    airspaces: &[Airspace],
+    latitudes: (f32, f32),
+    longitudes: (f32, f32),
+) -> FeatureCollection<AirspaceProperties> {
+    FeatureCollection {
+        features: airspaces
+            .iter()
+            .filter(|airspace| airspace.intersects_bounds(latitudes, longitudes))
+            .map(|airspace| {
+                let mut ring = airspace.polygon.clone();
+                ring.push(ring[0]);
+                Feature {
+                    geometry: Geometry::Polygon {
+                        coordinates: vec![ring],
+                    },
+                    properties: AirspaceProperties {
+                        name: airspace.name.clone(),
+                        class: airspace.class.clone(),
+                        floor: airspace.floor,
+                        ceiling: airspace.ceiling,
+                        restricted: airspace.is_restricted(),
+                    },
+                }
+            })
+            .collect(),
+        metadata: None,
+    }
+}
+
+#[cfg(test)]
+#[path = "./airspace_test.rs"]
+mod airspace_test;
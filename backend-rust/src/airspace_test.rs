@@ -0,0 +1,112 @@
+use approx::assert_relative_eq;
+use ndarray::Array2;
+
+use crate::height_data::{DataSource, HeightGrid};
+
+use super::{
+    airspace_features, parse_altitude, parse_coordinate, parse_openair, AirspaceGrid, AltitudeLimit,
+};
+
+const OPENAIR: &str = "* Test airspaces
+AC R
+AN Restricted square
+AL 1500ft MSL
+AH FL95
+DP 47:00:00 N 011:00:00 E
+DP 47:03:00 N 011:00:00 E
+DP 47:03:00 N 011:03:00 E
+DP 47:00:00 N 011:03:00 E
+
+AC E
+AN Circle
+AL 300m AGL
+AH UNL
+V X=47:30:00 N 011:30:00 E
+DC 2
+";
+
+#[test]
+fn test_parse_altitude() {
+    assert_eq!(parse_altitude("GND"), Ok(AltitudeLimit::Agl(0.0)));
+    assert_eq!(parse_altitude("300m AGL"), Ok(AltitudeLimit::Agl(300.0)));
+    assert_eq!(
+        parse_altitude("1000 MSL"),
+        Ok(AltitudeLimit::Msl(1000.0 * 0.3048))
+    );
+    assert_eq!(
+        parse_altitude("1000ft AMSL"),
+        Ok(AltitudeLimit::Msl(1000.0 * 0.3048))
+    );
+    assert_eq!(
+        parse_altitude("FL 100"),
+        Ok(AltitudeLimit::Msl(100.0 * 100.0 * 0.3048))
+    );
+    assert_eq!(
+        parse_altitude("UNLIM"),
+        Ok(AltitudeLimit::Msl(f32::INFINITY))
+    );
+    assert!(parse_altitude("high").is_err());
+    assert!(parse_altitude("1000ft QNH").is_err());
+}
+
+#[test]
+fn test_parse_coordinate() {
+    let (lat, lon) = parse_coordinate("47:30:00 N 011:15:36 E").unwrap();
+    assert_relative_eq!(lat, 47.5);
+    assert_relative_eq!(lon, 11.26);
+
+    let (lat, lon) = parse_coordinate("33:30.0S 70:45.0W").unwrap();
+    assert_relative_eq!(lat, -33.5);
+    assert_relative_eq!(lon, -70.75);
+
+    assert!(parse_coordinate("47:30:00 011:15:36").is_err());
+}
+
+#[test]
+fn test_parse_openair() {
+    let airspaces = parse_openair(OPENAIR).unwrap();
+    assert_eq!(airspaces.len(), 2);
+
+    let square = &airspaces[0];
+    assert_eq!(square.name, "Restricted square");
+    assert!(square.is_restricted());
+    assert_eq!(square.polygon.len(), 4);
+    assert!(square.contains(47.02, 11.02));
+    assert!(!square.contains(47.02, 11.08));
+
+    let circle = &airspaces[1];
+    assert!(!circle.is_restricted());
+    assert_eq!(circle.floor, AltitudeLimit::Agl(300.0));
+    assert!(circle.contains(47.5, 11.5));
+    // The radius is 2 nautical miles, about 0.033 degrees of latitude.
+    assert!(circle.contains(47.53, 11.5));
+    assert!(!circle.contains(47.54, 11.5));
+
+    assert!(parse_openair("AC R\nAL nowhere\n").is_err());
+    assert!(parse_openair("AC R\nDC 2\n").is_err());
+}
+
+#[test]
+fn test_airspace_grid() {
+    let airspaces = parse_openair(OPENAIR).unwrap();
+    let grid = HeightGrid {
+        heights: Array2::from_elem((11, 11), 1000),
+        cell_size: 1000.0,
+        min_cell_size: 1000.0,
+        latitudes: (47.0, 47.1),
+        longitudes: (11.0, 11.1),
+        data_source: DataSource::Srtm,
+        data_quality: None,
+    };
+    let airspace_grid = AirspaceGrid::new(&airspaces, &grid);
+
+    // Between 1500 ft (457 m) and FL95 (2896 m) over the south west.
+    assert!(airspace_grid.contains(&(3, 3), 1000.0));
+    assert!(!airspace_grid.contains(&(3, 3), 400.0));
+    assert!(!airspace_grid.contains(&(3, 3), 3000.0));
+    assert!(!airspace_grid.contains(&(8, 8), 1000.0));
+
+    let features = airspace_features(&airspaces, (47.2, 47.6), (11.2, 11.6));
+    assert_eq!(features.features.len(), 1);
+    assert_eq!(features.features[0].properties.name, "Circle");
+}
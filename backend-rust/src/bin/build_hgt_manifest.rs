@@ -0,0 +1,57 @@
+use std::{fs, path::PathBuf};
+
+use backend_rust::{
+    height_data::DataSource,
+    hgt_manifest::{
+        compute_entry, format_manifest, format_manifest_header, list_tiles, load_manifest,
+        verify_tiles, MANIFEST_FILE_NAME,
+    },
+};
+
+fn main() {
+    let verify = std::env::args().any(|a| a == "--verify");
+    let arg_value = |name: &str| {
+        std::env::args().find_map(|a| a.strip_prefix(&format!("--{name}=")).map(str::to_string))
+    };
+    let data_dir = &arg_value("data-dir").map_or(PathBuf::from("./data"), PathBuf::from);
+
+    if verify {
+        let manifest = load_manifest(data_dir)
+            .expect("No manifest found")
+            .expect("Could not parse manifest");
+        println!("Verifying {} tiles...", manifest.len());
+        let problems = verify_tiles(data_dir, &manifest, true);
+        for problem in problems.iter() {
+            println!("{:?}", problem);
+        }
+        if !problems.is_empty() {
+            std::process::exit(1);
+        }
+        println!("All tiles ok");
+        return;
+    }
+
+    let tiles = list_tiles(data_dir).unwrap();
+    println!("Computing checksums for {} tiles...", tiles.len());
+
+    let entries = tiles
+        .iter()
+        .map(|tile| compute_entry(data_dir, tile).unwrap())
+        .collect::<Vec<_>>();
+
+    let header = format_manifest_header(
+        &arg_value("dataset").unwrap_or("SRTM".to_string()),
+        &arg_value("release-date").unwrap_or("unknown".to_string()),
+        &arg_value("license").unwrap_or(DataSource::Srtm.license().to_string()),
+        &arg_value("attribution").unwrap_or(DataSource::Srtm.attribution().to_string()),
+    );
+    fs::write(
+        data_dir.join(MANIFEST_FILE_NAME),
+        header + format_manifest(&entries).as_str(),
+    )
+    .unwrap();
+    println!(
+        "Wrote manifest to {}",
+        data_dir.join(MANIFEST_FILE_NAME).display()
+    );
+}
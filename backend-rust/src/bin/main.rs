@@ -2,46 +2,760 @@
 use core::f32;
 use fs_extra::dir::get_size;
 use once_cell::sync::OnceCell;
+use rand::{rngs::OsRng, RngCore};
 use reqwest::{Client, ClientBuilder};
-use rocket_ws::{Stream, WebSocket};
-use std::sync::{Mutex, OnceLock};
+use rocket_ws::{
+    frame::{CloseCode, CloseFrame},
+    Stream, WebSocket,
+};
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    mpsc::{self, RecvTimeoutError},
+    Arc, Mutex, OnceLock, RwLock,
+};
 use std::{
-    cmp::{max, min, Ordering},
+    cmp::{max, min},
+    collections::{BTreeMap, HashMap, HashSet},
     f32::consts::PI,
     fs::{self, File},
+    future::Future,
     hash::{Hash, Hasher},
-    io::{BufRead, BufReader, Cursor},
+    io::{Cursor, IsTerminal, Write as _},
     path::Path,
+    pin::Pin,
+    str::FromStr,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 use backend_rust::{
+    airspace::{
+        airspace_features, parse_openair, Airspace, AirspaceGrid, AirspaceMode, AirspaceProperties,
+        AIRSPACE_FILE_NAME,
+    },
+    boundary::{
+        corner_coordinates, outer_boundary, reachable_component, region_polygons, Corner,
+        RegionPolygon,
+    },
+    brute_force::verify_search,
     btree::BTree,
-    colors::{f32_color_to_u8, lerp},
-    height_data::{cache_sizes, get_height_data_around_point, location_supported, HeightGrid},
-    search::{search_from_point, GridIx, Node, SearchQuery},
+    colors::{f32_color_to_u8, hatch, parse_color_ramp, shade, tint, ColorRamp},
+    compare::{
+        combined_coverage, coverage_extent, launch_wind_directions, parse_launch_ids,
+        range_per_direction, DIRECTIONS,
+    },
+    cone_cache::{cone_cache_key, decode_cone, encode_cone, CachedCone},
+    cone_stats::{
+        agl_band_of, agl_bands, agl_of, cone_stats, distance_ring, ring_radii, ConeStats,
+    },
+    config::{
+        data_dir, set_data_dir, ConeJobConfig, EndpointGroups, LogConfig, LogFormat,
+        ParameterLimits, PreloadConfig, RefreshConfig, ServerConfig, SiteDataSource,
+        StorageBackend, StorageConfig,
+    },
+    contours::{agl_contours, ContourProperties},
+    coordinates::{decode_plus_code, parse_coordinates},
+    czml::{cone_czml, Packet as CzmlPacket},
+    datasets::{
+        dataset_key, is_valid_region_name, parse_regions, slug, Region as DatasetRegion,
+        REGIONS_FILE_NAME,
+    },
+    dem_download::{parse_tile_name, store_tile, tile_url, tiles_to_download, DOWNLOAD_RADIUS_M},
+    distance_transform::BoundaryDistance,
+    drift::drifted_start,
+    flight_path::{glide_path, PathVertex},
+    geojson::{
+        reachable_area, reachable_outline, CellProperties, FeatureCollection, OutlineProperties,
+    },
+    height_data::{
+        cache_sizes, clear_tile_cache, forget_tile, get_bounds_around_point,
+        get_height_at_point_from, get_height_data_around_point_from,
+        get_interpolated_height_at_point_from, load_tile, location_supported, missing_tiles,
+        pin_tile, select_data_source, snap_cell_size, tile_exists, DataQuality, DataSource,
+        HeightGrid, Interpolation, PinnedTile, COARSE_HGT_CACHE_TILES, HGT_CACHE_TILES,
+    },
+    hgt_manifest::{
+        dem_version, load_manifest, provenance, verify_tile_shapes, verify_tiles, DemVersion,
+        Provenance, TileProblem,
+    },
+    hike::{
+        hike_isochrones, hiking_times, IsochroneProperties, MAX_HIKE_HOURS, MAX_HIKE_SPEED_KMH,
+    },
+    hillshade::{hillshade, Sun},
+    igc::{compare_track, parse_igc, TrackComparison},
+    job_queue::{JobQueue, JobState},
+    land_cover::{land_cover_file, land_cover_grid, LandCover, LandCoverTile, LAND_COVER_DIR},
+    landing::{aspect_at, detect_landing_zones, landability_at, reachable_nodes_at, Landability},
+    launch_score::{launch_scores, LaunchScores, ScoredLaunch, MAX_SCORED_LAUNCHES},
+    legend::legend,
+    load_test::{load_test_uris, uri_path},
+    lru_cache::{ByteLimitedCache, CacheStats},
+    margin::{parse_safety_margin_ramp, MarginPoint},
+    metrics::{
+        cache_metrics, collect_request_metrics, latency_metrics, record_cache_access,
+        set_current_endpoint, time_stage, CacheTier, RequestMetrics, Stage, OTHER_ENDPOINT,
+    },
+    mvt::{cone_tile, TileIndex},
+    node_frame::{encode_node_frame, StreamNode},
+    open_elevation::{lookup, parse_locations, LookupRequest, LookupResponse},
+    polar::{glide_ratio_at, parse_polar, PolarPoint},
+    profile::{
+        add_glide_heights, elevation_profile, parse_polyline, polyline_extent, ProfileSample,
+    },
+    raster_tile::{tile_cells, TILE_PIXELS},
+    region::{
+        boundary_crossings, region_mask, split_by_region, Region, RegionGeoJson,
+        NO_LANDING_FILE_NAME,
+    },
+    replay::{canonicalize_query, latency_summary, ReplayEntry, REPLAY_LOG_FILE_NAME},
+    route::{plan_route, Route as PlannedRoute, RouteCone, MAX_HIKE_DEFAULT},
+    search::{
+        l2_distance, load_search_grid, max_search_distance, parse_starts, parse_thermals,
+        prepare_search, reachable_in_transfer_order, reindex, search_from_point,
+        search_from_point_avoiding_airspace, search_from_point_with_thermals, search_from_points,
+        search_iter, search_traced, start_sources, CancellationToken, EdgeHook, GlideModel, GridIx,
+        GridIxType, Node, SearchError, SearchExtent, SearchQuery, SearchSetup, ALGORITHM_VERSION,
+        MAX_THERMALS,
+    },
+    sectors::{search_from_point_incremental, search_from_point_parallel, PreviousSearch},
+    storage::{hex_string, FileStorage, S3Storage, Storage},
+    synthetic::{search_synthetic, SyntheticTerrain},
     types::{Location, LocationWithQuery, SearchLocation},
+    usage::{current_day, usage_stats, PublicUsage, SearchParameters},
+    view::{mercator_rows, suggest_view, tiles_covering, MapView, RasterProjection},
+    waypoints::{format_cup, format_wpt, Waypoint},
+    wind::{parse_wind_layers, WindLayer},
+    zip::zip_stored,
 };
 
-use image::{DynamicImage, GenericImage, ImageFormat, Rgba};
+#[cfg(feature = "scripting")]
+use backend_rust::scripting::EdgeScript;
+use image::{DynamicImage, GenericImage, GenericImageView, ImageFormat, ImageReader, Rgba};
+use include_dir::{include_dir, Dir};
 use quick_xml::{
     events::{BytesEnd, BytesStart, BytesText, Event},
     Writer,
 };
 use rocket::{
+    data::{ByteUnit, Data},
+    fairing::{Fairing, Info, Kind},
     fs::FileServer,
-    http::{ContentType, Status},
-    response::Redirect,
+    http::{uri::Origin, ContentType, Header, Method, Status},
+    local::asynchronous::Client as LocalClient,
+    request::{FromRequest, Request},
+    response::{stream::TextStream, Redirect},
+    route::{Handler, Outcome, Route},
     serde::{json::Json, Serialize},
+    tokio::{
+        sync::{
+            mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+            Barrier, Notify,
+        },
+        task,
+        time::MissedTickBehavior,
+    },
+    Build, Orbit, Rocket,
 };
 
 use ndarray::{s, Array2};
+use tracing::{error, info, info_span, warn, Span};
+use tracing_subscriber::EnvFilter;
 
-use cached::{proc_macro::cached, Cached};
+use cached::{proc_macro::cached, Cached, Return, SizedCache};
 use serde::Deserialize;
 
 #[macro_use]
 extern crate rocket;
 
+/// Application settings, read from `Rocket.toml` or `ROCKET_` prefixed
+/// environment variables alongside the rocket settings.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct AppConfig {
+    /// Never write to disk (no tile fetching/caching) and don't mount admin
+    /// routes, for serving from read-only images.
+    read_only: bool,
+    /// Secret the `/admin` routes are called with, as `Authorization: Bearer
+    /// <token>`. Without it the admin routes are not mounted.
+    admin_token: Option<String>,
+    /// Store finished searches in the cache storage so they survive restarts
+    /// and can be shared between instances.
+    persistent_cone_cache: bool,
+    /// Mount the `/debug` routes, which expose internals of the search.
+    debug_endpoints: bool,
+    /// Search cones in parallel sectors with `search_from_point_parallel`,
+    /// which is faster on several cores but only approximates the cone.
+    parallel_search: bool,
+    /// Region from `regions.txt` in the data directory that `--check` requires
+    /// DEM coverage for and that is preloaded at startup, see `preload`.
+    home_region: Option<String>,
+    /// Append every served request with its timing to `replay.log` in the data
+    /// directory, to replay production traffic against a local build with
+    /// `replay`.
+    replay_log: bool,
+    /// Url template of missing SRTM tiles, see `tile_url`. Tiles around the
+    /// start of a request are downloaded to the data directory before it is
+    /// handled.
+    dem_url_template: Option<String>,
+    refresh: RefreshConfig,
+    preload: PreloadConfig,
+    endpoints: EndpointGroups,
+    storage: StorageConfig,
+    logging: LogConfig,
+    cone_jobs: ConeJobConfig,
+    #[serde(flatten)]
+    server: ServerConfig,
+}
+
+fn load_app_config() -> Result<AppConfig, String> {
+    rocket::Config::figment()
+        .extract::<AppConfig>()
+        .map_err(|e| format!("Invalid configuration: {e}"))
+}
+
+/// Filter of the logged events, from `RUST_LOG` if set.
+fn log_filter(config: &LogConfig) -> Result<EnvFilter, String> {
+    match std::env::var("RUST_LOG") {
+        Ok(filter) => {
+            EnvFilter::try_new(&filter).map_err(|e| format!("Invalid RUST_LOG {filter}: {e}"))
+        }
+        Err(_) => EnvFilter::try_new(&config.level)
+            .map_err(|e| format!("Invalid log level {}: {e}", config.level)),
+    }
+}
+
+/// Logs to stderr, so the output of `--check` and `--load-test` on stdout
+/// stays readable. Rocket's own log lines are logged as events as well.
+fn init_logging(config: &LogConfig) {
+    let (filter, invalid) = match log_filter(config) {
+        Ok(filter) => (filter, None),
+        Err(e) => (EnvFilter::new("info"), Some(e)),
+    };
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr);
+    let result = match config.format {
+        LogFormat::Text => builder
+            .with_ansi(std::io::stderr().is_terminal())
+            .try_init(),
+        LogFormat::Json => builder
+            .json()
+            .flatten_event(true)
+            .with_current_span(true)
+            .with_span_list(false)
+            .try_init(),
+    };
+    if result.is_err() {
+        return;
+    }
+    if let Some(e) = invalid {
+        warn!("{e}, logging at level info");
+    }
+}
+
+fn app_config() -> &'static AppConfig {
+    static INSTANCE: OnceCell<AppConfig> = OnceCell::new();
+    INSTANCE.get_or_init(|| load_app_config().unwrap())
+}
+
+fn limits() -> &'static ParameterLimits {
+    &app_config().server.parameter_limits
+}
+
+fn dem_version_slot() -> &'static RwLock<DemVersion> {
+    static INSTANCE: OnceCell<RwLock<DemVersion>> = OnceCell::new();
+    INSTANCE.get_or_init(|| RwLock::new(dem_version(data_dir())))
+}
+
+/// The version of the DEM tiles in the data dir, updated when tiles are
+/// downloaded.
+fn current_dem_version() -> DemVersion {
+    dem_version_slot().read().unwrap().clone()
+}
+
+/// File in the data dir counting the DEM refreshes that replaced tiles.
+const DEM_GENERATION_FILE_NAME: &str = "dem_generation";
+
+/// Part of the persistent cone cache keys next to the DEM version, which
+/// stays the same when a tile failing its checksum is replaced by the one in
+/// the manifest or a tile the manifest does not list is downloaded.
+fn dem_generation() -> &'static AtomicU64 {
+    static INSTANCE: OnceCell<AtomicU64> = OnceCell::new();
+    INSTANCE.get_or_init(|| {
+        let generation = fs::read_to_string(data_dir().join(DEM_GENERATION_FILE_NAME))
+            .ok()
+            .and_then(|content| content.trim().parse().ok())
+            .unwrap_or(0);
+        AtomicU64::new(generation)
+    })
+}
+
+fn bump_dem_generation() {
+    let generation = dem_generation().fetch_add(1, Ordering::SeqCst) + 1;
+    let path = data_dir().join(DEM_GENERATION_FILE_NAME);
+    if let Err(e) = fs::write(&path, generation.to_string()) {
+        warn!("Could not write {}: {e}", path.display());
+    }
+}
+
+/// Determines the DEM version again after tiles were stored, so the exports
+/// and the persistent cone cache keys show the new tiles, and drops the
+/// searches in memory, which used the missing or broken tiles. The DEM
+/// version only covers the tiles in the manifest, so the generation is bumped
+/// too.
+fn dem_tiles_stored() {
+    bump_dem_generation();
+    let version = dem_version(data_dir());
+    *dem_version_slot().write().unwrap() = version;
+    clear_search_caches();
+}
+
+/// Source and license of the heights of `grid`, for the metadata of exports.
+fn grid_provenance(grid: &HeightGrid) -> Provenance {
+    provenance(grid.data_source, &current_dem_version())
+}
+
+fn cache_storage() -> &'static dyn Storage {
+    static INSTANCE: OnceCell<Box<dyn Storage>> = OnceCell::new();
+    INSTANCE
+        .get_or_init(|| match app_config().storage.backend {
+            StorageBackend::S3 => {
+                info!("Using S3 cache storage");
+                Box::new(S3Storage::new(&app_config().storage))
+            }
+            StorageBackend::Filesystem => {
+                Box::new(FileStorage::new(app_config().server.tile_cache_dir()))
+            }
+        })
+        .as_ref()
+}
+
+/// Runtime the searches, which run synchronously, send their requests to the
+/// cache storage on, so they never block the runtime of rocket.
+fn storage_runtime() -> &'static rocket::tokio::runtime::Runtime {
+    static INSTANCE: OnceCell<rocket::tokio::runtime::Runtime> = OnceCell::new();
+    INSTANCE.get_or_init(|| {
+        rocket::tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .thread_name("cache-storage")
+            .enable_all()
+            .build()
+            .expect("Should be able to start the cache storage runtime")
+    })
+}
+
+/// Waits for a request to the cache storage from synchronous code.
+fn wait_for_storage<T: Send + 'static>(request: impl Future<Output = T> + Send + 'static) -> T {
+    let (sender, receiver) = mpsc::channel();
+    storage_runtime().spawn(async move {
+        let _ = sender.send(request.await);
+    });
+    receiver
+        .recv()
+        .expect("Cache storage requests should not panic")
+}
+
+/// Time at which rocket started handling a request.
+struct RequestStart(Option<Instant>);
+
+struct ReplayLogger {
+    file: Mutex<File>,
+}
+
+#[rocket::async_trait]
+impl Fairing for ReplayLogger {
+    fn info(&self) -> Info {
+        Info {
+            name: "Replay log",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, request: &mut rocket::Request<'_>, _: &mut rocket::Data<'_>) {
+        request.local_cache(|| RequestStart(Some(Instant::now())));
+    }
+
+    async fn on_response<'r>(
+        &self,
+        request: &'r rocket::Request<'_>,
+        response: &mut rocket::Response<'r>,
+    ) {
+        let Some(start) = request.local_cache(|| RequestStart(None)).0 else {
+            return;
+        };
+        // Static files and websocket upgrades can't be replayed meaningfully.
+        let path = request.uri().path().as_str();
+        if request.method() != Method::Get
+            || path.starts_with("/static")
+            || response.status() == Status::SwitchingProtocols
+        {
+            return;
+        }
+
+        let entry = ReplayEntry {
+            timestamp_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_millis() as u64,
+            status: response.status().code,
+            duration_us: start.elapsed().as_micros() as u64,
+            path: path.to_string(),
+            query: canonicalize_query(request.uri().query().map_or("", |q| q.as_str())),
+        };
+        let mut file = self.file.lock().unwrap();
+        if let Err(e) = writeln!(file, "{}", entry.to_line()) {
+            error!(parent: &request_span(request), "Could not write replay log: {e}");
+        }
+    }
+}
+
+/// Id of a request and the span its events are logged in. The id is sent
+/// back in `X-Request-Id`.
+struct RequestSpan(Option<(String, Span)>);
+
+/// Longest `X-Request-Id` of a proxy that is taken over as the request id.
+const MAX_REQUEST_ID_LENGTH: usize = 64;
+
+/// The `X-Request-Id` set by a reverse proxy, so log lines can be matched
+/// with its logs, or else a number counting the requests since the start.
+fn request_id(request: &Request<'_>) -> String {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+    let is_valid = |id: &&str| {
+        (1..=MAX_REQUEST_ID_LENGTH).contains(&id.len())
+            && id
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b"-_.:".contains(&b))
+    };
+    match request.headers().get_one("X-Request-Id").filter(is_valid) {
+        Some(id) => id.to_string(),
+        None => NEXT_ID.fetch_add(1, Ordering::Relaxed).to_string(),
+    }
+}
+
+/// Span of `request`, for the events of fairings. Disabled for requests the
+/// endpoint tracker has not seen.
+fn request_span(request: &Request<'_>) -> Span {
+    match &request.local_cache(|| RequestSpan(None)).0 {
+        Some((_, span)) => span.clone(),
+        None => Span::none(),
+    }
+}
+
+/// Endpoint and span of a request a task is handling.
+struct TaskRequest {
+    endpoint: String,
+    span: Span,
+}
+
+/// Request each task is handling, so cache lookups can be counted per
+/// endpoint and events are logged in the span of the request, see
+/// `current_endpoint` and `current_request_span`.
+fn task_requests() -> &'static Mutex<HashMap<task::Id, TaskRequest>> {
+    static INSTANCE: OnceLock<Mutex<HashMap<task::Id, TaskRequest>>> = OnceLock::new();
+    INSTANCE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Endpoint of the request handled by the current task, `None` outside of
+/// requests, e.g. on the threads of a parallel search.
+fn current_endpoint() -> Option<String> {
+    let id = task::try_id()?;
+    task_requests()
+        .lock()
+        .unwrap()
+        .get(&id)
+        .map(|request| request.endpoint.clone())
+}
+
+/// Span of the request handled by the current task, disabled outside of
+/// requests.
+fn current_request_span() -> Span {
+    task::try_id()
+        .and_then(|id| {
+            let requests = task_requests().lock().unwrap();
+            requests.get(&id).map(|request| request.span.clone())
+        })
+        .unwrap_or_else(Span::none)
+}
+
+/// First segment of a path, e.g. `/opentopomap` of `/opentopomap/a/1/2/3.png`.
+fn first_segment(path: &str) -> &str {
+    let end = path[1.min(path.len())..]
+        .find(['/', '<'])
+        .map_or(path.len(), |end| end + 1);
+    &path[..end]
+}
+
+/// Remembers the endpoint of each request for its task and records the
+/// latency of the request. Endpoints are the first segments of the mounted
+/// routes, so unknown paths do not add labels to the metrics.
+#[derive(Default)]
+struct EndpointTracker {
+    endpoints: OnceLock<HashSet<String>>,
+}
+
+impl EndpointTracker {
+    fn endpoint<'a>(&self, path: &'a str) -> &'a str {
+        let endpoint = first_segment(path);
+        let known = self
+            .endpoints
+            .get()
+            .is_some_and(|endpoints| endpoints.contains(endpoint));
+        if known {
+            endpoint
+        } else {
+            OTHER_ENDPOINT
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl Fairing for EndpointTracker {
+    fn info(&self) -> Info {
+        Info {
+            name: "Endpoint tracker",
+            kind: Kind::Liftoff | Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_liftoff(&self, rocket: &Rocket<Orbit>) {
+        let endpoints = rocket
+            .routes()
+            .map(|route| first_segment(route.uri.path()).to_string())
+            .collect();
+        let _ = self.endpoints.set(endpoints);
+    }
+
+    async fn on_request(&self, request: &mut rocket::Request<'_>, _: &mut rocket::Data<'_>) {
+        request.local_cache(|| RequestStart(Some(Instant::now())));
+        let request_id = request_id(request);
+        let span = info_span!(
+            "request",
+            id = %request_id,
+            method = %request.method(),
+            uri = %request.uri()
+        );
+        request.local_cache(|| RequestSpan(Some((request_id, span.clone()))));
+        let Some(id) = task::try_id() else {
+            return;
+        };
+        let endpoint = self.endpoint(request.uri().path().as_str());
+        task_requests().lock().unwrap().insert(
+            id,
+            TaskRequest {
+                endpoint: endpoint.to_string(),
+                span,
+            },
+        );
+    }
+
+    async fn on_response<'r>(
+        &self,
+        request: &'r rocket::Request<'_>,
+        response: &mut rocket::Response<'r>,
+    ) {
+        if let Some(id) = task::try_id() {
+            task_requests().lock().unwrap().remove(&id);
+        }
+        let Some(start) = request.local_cache(|| RequestStart(None)).0 else {
+            return;
+        };
+        let duration = start.elapsed();
+        latency_metrics().record(self.endpoint(request.uri().path().as_str()), duration);
+
+        if let Some((id, span)) = &request.local_cache(|| RequestSpan(None)).0 {
+            let status = response.status().code;
+            let duration_ms = duration.as_secs_f64() * 1000.0;
+            if status >= 500 {
+                error!(parent: span, status, duration_ms, "Request failed");
+            } else {
+                info!(parent: span, status, duration_ms, "Request finished");
+            }
+            response.set_raw_header("X-Request-Id", id.clone());
+        }
+    }
+}
+
+fn replay_logger() -> ReplayLogger {
+    let path = data_dir().join(REPLAY_LOG_FILE_NAME);
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .unwrap_or_else(|e| panic!("Could not open {}: {e}", path.display()));
+    ReplayLogger {
+        file: Mutex::new(file),
+    }
+}
+
+/// Time a DEM tile may take to download.
+const DEM_DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(120);
+/// Time until a tile is requested again after its first failed download. It
+/// doubles with every further failure, up to `DEM_RETRY_DELAY_MAX`.
+const DEM_RETRY_DELAY: Duration = Duration::from_secs(60);
+const DEM_RETRY_DELAY_MAX: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Downloads of a tile by the `DemDownloader`.
+#[derive(Default)]
+struct TileDownloads {
+    /// Held while the tile is downloaded, so concurrent requests download it
+    /// once.
+    running: Arc<rocket::tokio::sync::Mutex<()>>,
+    /// The server does not have the tile, e.g. over the sea. It is not
+    /// requested again.
+    unavailable: bool,
+    /// Failed downloads in a row, and when the tile may be requested again.
+    failures: u32,
+    retry_at: Option<Instant>,
+}
+
+impl TileDownloads {
+    fn may_download(&self, now: Instant) -> bool {
+        !self.unavailable && self.retry_at.is_none_or(|retry_at| now >= retry_at)
+    }
+
+    fn failed(&mut self, now: Instant) {
+        self.failures += 1;
+        let delay = DEM_RETRY_DELAY
+            .saturating_mul(1 << (self.failures - 1).min(16))
+            .min(DEM_RETRY_DELAY_MAX);
+        self.retry_at = Some(now + delay);
+    }
+}
+
+/// Downloads the SRTM tile of the start of a request from `dem_url_template`
+/// when it is missing, before the request is handled, so the handler finds
+/// it. The other missing tiles around the start are downloaded in the
+/// background.
+#[derive(Clone)]
+struct DemDownloader {
+    url_template: String,
+    tiles: Arc<Mutex<HashMap<(i32, i32), TileDownloads>>>,
+}
+
+impl DemDownloader {
+    fn new(url_template: String) -> Self {
+        DemDownloader {
+            url_template,
+            tiles: Arc::default(),
+        }
+    }
+
+    /// Downloads and stores a tile, and remembers when it failed.
+    async fn download(&self, latitude: i32, longitude: i32, span: &Span) -> Result<(), String> {
+        let url = tile_url(&self.url_template, latitude, longitude);
+        info!(parent: span, %url, "Fetching DEM tile");
+        let result = self.fetch(&url, latitude, longitude).await;
+        let mut tiles = self.tiles.lock().unwrap();
+        let downloads = tiles.entry((latitude, longitude)).or_default();
+        match &result {
+            Ok(()) => {
+                downloads.failures = 0;
+                downloads.retry_at = None;
+            }
+            Err(None) => downloads.unavailable = true,
+            Err(Some(_)) => downloads.failed(Instant::now()),
+        }
+        result.map_err(|e| e.unwrap_or_else(|| format!("{url} not found")))
+    }
+
+    /// Error `None` if the server does not have the tile.
+    async fn fetch(&self, url: &str, latitude: i32, longitude: i32) -> Result<(), Option<String>> {
+        let response = reqwest_client()
+            .get(url)
+            .timeout(DEM_DOWNLOAD_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| Some(e.to_string()))?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(None);
+        }
+        if !response.status().is_success() {
+            return Err(Some(format!(
+                "{url} failed with status {}",
+                response.status()
+            )));
+        }
+        let bytes = response.bytes().await.map_err(|e| Some(e.to_string()))?;
+        store_tile(data_dir(), latitude, longitude, &bytes).map_err(Some)
+    }
+
+    /// Downloads a missing tile unless another request is downloading it
+    /// already, in which case this waits for it, or its last download failed
+    /// recently. Returns whether the tile was stored.
+    async fn download_missing(&self, latitude: i32, longitude: i32, span: &Span) -> bool {
+        let may_download = |tiles: &mut HashMap<(i32, i32), TileDownloads>| {
+            let downloads = tiles.entry((latitude, longitude)).or_default();
+            downloads
+                .may_download(Instant::now())
+                .then(|| downloads.running.clone())
+        };
+        let Some(running) = may_download(&mut self.tiles.lock().unwrap()) else {
+            return false;
+        };
+        let _running = running.lock().await;
+        // Another request may have downloaded the tile or failed meanwhile.
+        if tile_exists(DataSource::Srtm, latitude, longitude)
+            || may_download(&mut self.tiles.lock().unwrap()).is_none()
+        {
+            return false;
+        }
+        match self.download(latitude, longitude, span).await {
+            Ok(()) => true,
+            Err(e) => {
+                warn!(parent: span, "Could not download DEM tile: {e}");
+                false
+            }
+        }
+    }
+
+    async fn download_all(&self, tiles: Vec<(i32, i32)>, span: Span) {
+        let mut downloaded = false;
+        for (latitude, longitude) in tiles {
+            downloaded |= self.download_missing(latitude, longitude, &span).await;
+        }
+        if downloaded {
+            // Lists all tiles without a manifest.
+            let _ = rocket::tokio::task::spawn_blocking(dem_tiles_stored).await;
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl Fairing for DemDownloader {
+    fn info(&self) -> Info {
+        Info {
+            name: "DEM downloader",
+            kind: Kind::Request,
+        }
+    }
+
+    async fn on_request(&self, request: &mut rocket::Request<'_>, _: &mut rocket::Data<'_>) {
+        let lat = request.query_value::<f32>("lat").and_then(Result::ok);
+        let lon = request.query_value::<f32>("lon").and_then(Result::ok);
+        let coord = request.query_value::<&str>("coord").and_then(Result::ok);
+        let olc = request.query_value::<&str>("olc").and_then(Result::ok);
+        // Invalid starts are rejected by the handler.
+        let Ok((lat, lon)) = start_from_request(lat, lon, coord, olc) else {
+            return;
+        };
+        if location_supported(lat, lon) {
+            return;
+        }
+
+        let start = (lat.floor() as i32, lon.floor() as i32);
+        let others = tiles_to_download(lat, lon, DOWNLOAD_RADIUS_M)
+            .into_iter()
+            .filter(|tile| *tile != start)
+            .collect::<Vec<_>>();
+        let span = request_span(request);
+        if !others.is_empty() {
+            let downloader = self.clone();
+            let span = span.clone();
+            rocket::tokio::spawn(async move { downloader.download_all(others, span).await });
+        }
+        self.download_all(vec![start], span).await;
+    }
+}
+
 fn num_index_accesses() -> &'static Mutex<usize> {
     static ARRAY: OnceLock<Mutex<usize>> = OnceLock::new();
     ARRAY.get_or_init(|| Mutex::new(0))
@@ -57,21 +771,11 @@ fn index() -> Redirect {
 }
 
 const CELL_SIZE_DEFAULT: f32 = 200.0;
-const CELL_SIZE_MINIMUM: f32 = 30.0;
-const CELL_SIZE_MAXIMUM: f32 = 500.0;
 const GLIDE_NUMBER_DEFAULT: f32 = 8.0;
-const GLIDE_NUMBER_MAXIMUM: f32 = 15.0;
-const GLIDE_NUMBER_MINIMUM: f32 = 1.0;
 const ADDITIONAL_HEIGHT_DEFAULT: f32 = 10.0;
-const ADDITIONAL_HEIGHT_MAXIMUM: f32 = 1000.0;
-const ADDITIONAL_HEIGHT_MINIMUM: f32 = 0.0;
 const WIND_SPEED_DEFAULT: f32 = 0.0;
-const WIND_SPEED_MINIMUM: f32 = 0.0;
-const WIND_SPEED_MAXIMUM: f32 = 50.0;
 const WIND_DIRECTION_DEFAULT: f32 = 0.0;
 const TRIM_SPEED_DEFAULT: f32 = 38.0;
-const TRIM_SPEED_MINIMUM: f32 = 0.0;
-const TRIM_SPEED_MAXIMUM: f32 = 80.0;
 const SAFETY_MARGIN_DEFAULT: f32 = 0.0;
 const SAFETY_MARGIN_MINIMUM: f32 = 0.0;
 const START_DISTANCE_DEFAULT: f32 = 0.0;
@@ -81,8 +785,10 @@ const START_DISTANCE_MINIMUM: f32 = 0.0;
 struct Distance(f32);
 
 impl Distance {
-    fn canonicalize(&self) -> i32 {
-        (self.0 * 1024.0 * 1024.0).round() as i32
+    /// Fixed point value with 20 fractional bits, in an `i64` so heights of
+    /// several kilometers don't saturate and collide.
+    fn canonicalize(&self) -> i64 {
+        (self.0 as f64 * 1024.0 * 1024.0).round() as i64
     }
 }
 
@@ -113,40 +819,503 @@ struct SearchQueryHashable {
     pub additional_height: Distance,
     pub safety_margin: Distance,
     pub start_distance: Distance,
+    /// Distance and margin of each point of the ramp.
+    pub safety_margin_ramp: Vec<(Distance, Distance)>,
+    pub ridge_lift: bool,
+    pub polar: Option<Vec<(Distance, Distance)>>,
+    /// Altitude, speed and direction in radians of each layer.
+    pub wind_layers: Vec<(Distance, Distance, Distance)>,
+    pub start_sector: Option<(Distance, Distance)>,
+    pub reverse: bool,
+    pub terrain_error: bool,
+    pub model: GlideModel,
+    /// Source of the edge script, checked by `query_from_request`.
+    pub script: Option<String>,
 }
 
 impl SearchQueryHashable {
+    fn from_query(query: SearchQuery) -> SearchQueryHashable {
+        SearchQueryHashable {
+            glide_ratio: Distance(query.glide_ratio),
+            trim_speed: Distance(query.trim_speed),
+            wind_direction: Distance(query.wind_direction),
+            wind_speed: Distance(query.wind_speed),
+            start_height: query.start_height.map(Distance),
+            additional_height: Distance(query.additional_height),
+            safety_margin: Distance(query.safety_margin),
+            start_distance: Distance(query.start_distance),
+            safety_margin_ramp: query
+                .safety_margin_ramp
+                .into_iter()
+                .map(|point| (Distance(point.distance), Distance(point.margin)))
+                .collect(),
+            ridge_lift: query.ridge_lift,
+            polar: query.polar.map(|polar| {
+                polar
+                    .into_iter()
+                    .map(|point| (Distance(point.speed), Distance(point.sink)))
+                    .collect()
+            }),
+            wind_layers: query
+                .wind_layers
+                .into_iter()
+                .map(|layer| {
+                    (
+                        Distance(layer.altitude),
+                        Distance(layer.speed),
+                        Distance(layer.direction),
+                    )
+                })
+                .collect(),
+            start_sector: query
+                .start_sector
+                .map(|(from, to)| (Distance(from), Distance(to))),
+            reverse: query.reverse,
+            terrain_error: query.terrain_error,
+            model: query.model,
+            script: None,
+        }
+    }
+
+    /// Stable textual representation, used as key for the persistent cone cache.
+    pub fn canonical_string(&self) -> String {
+        let polar = self
+            .polar
+            .as_ref()
+            .map(|polar| {
+                polar
+                    .iter()
+                    .map(|(speed, sink)| {
+                        format!("{}:{}", speed.canonicalize(), sink.canonicalize())
+                    })
+                    .collect::<Vec<_>>()
+                    .join(",")
+            })
+            .unwrap_or("none".to_string());
+        let wind_layers = self
+            .wind_layers
+            .iter()
+            .map(|(altitude, speed, direction)| {
+                format!(
+                    "{}:{}:{}",
+                    altitude.canonicalize(),
+                    speed.canonicalize(),
+                    direction.canonicalize()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        let safety_margin_ramp = self
+            .safety_margin_ramp
+            .iter()
+            .map(|(distance, margin)| {
+                format!("{}:{}", distance.canonicalize(), margin.canonicalize())
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        let start_sector = self
+            .start_sector
+            .as_ref()
+            .map(|(from, to)| format!("{}:{}", from.canonicalize(), to.canonicalize()))
+            .unwrap_or("none".to_string());
+        let script = self
+            .script
+            .as_ref()
+            .map(|script| hex_string(&hmac_sha256::Hash::hash(script.as_bytes())))
+            .unwrap_or("none".to_string());
+        format!(
+            "{}_{}_{}_{}_{}_{}_{}_{}_{}_{}_{}_{}_{}_{}_{}_{}_{}",
+            self.glide_ratio.canonicalize(),
+            self.trim_speed.canonicalize(),
+            self.wind_direction.canonicalize(),
+            self.wind_speed.canonicalize(),
+            self.start_height
+                .as_ref()
+                .map(|x| x.canonicalize().to_string())
+                .unwrap_or("none".to_string()),
+            self.additional_height.canonicalize(),
+            self.safety_margin.canonicalize(),
+            self.start_distance.canonicalize(),
+            safety_margin_ramp,
+            self.ridge_lift,
+            polar,
+            wind_layers,
+            start_sector,
+            self.reverse,
+            self.terrain_error,
+            self.model.name(),
+            script,
+        )
+    }
+
     pub fn search_query(self) -> SearchQuery {
-        SearchQuery {
-            glide_ratio: self.glide_ratio.0,
-            trim_speed: self.trim_speed.0,
-            wind_direction: self.wind_direction.0,
-            wind_speed: self.wind_speed.0,
-            start_height: self.start_height.map(|x| x.0),
-            additional_height: self.additional_height.0,
-            safety_margin: self.safety_margin.0,
-            start_distance: self.start_distance.0,
+        let mut builder = SearchQuery::builder()
+            .glide_ratio(self.glide_ratio.0)
+            .trim_speed_kmh(self.trim_speed.0)
+            .wind_direction_deg(self.wind_direction.0.to_degrees())
+            .wind_speed_kmh(self.wind_speed.0)
+            .wind_layers(
+                self.wind_layers
+                    .into_iter()
+                    .map(|(altitude, speed, direction)| WindLayer {
+                        altitude: altitude.0,
+                        speed: speed.0,
+                        direction: direction.0,
+                    })
+                    .collect(),
+            )
+            .additional_height_m(self.additional_height.0)
+            .safety_margin_m(self.safety_margin.0)
+            .start_distance_m(self.start_distance.0)
+            .safety_margin_ramp(
+                self.safety_margin_ramp
+                    .into_iter()
+                    .map(|(distance, margin)| MarginPoint {
+                        distance: distance.0,
+                        margin: margin.0,
+                    })
+                    .collect(),
+            )
+            .ridge_lift(self.ridge_lift)
+            .reverse(self.reverse)
+            .terrain_error(self.terrain_error)
+            .model(self.model);
+        if let Some(start_height) = self.start_height {
+            builder = builder.start_height_m(start_height.0);
+        }
+        if let Some(polar) = self.polar {
+            builder = builder.polar(
+                polar
+                    .into_iter()
+                    .map(|(speed, sink)| PolarPoint {
+                        speed: speed.0,
+                        sink: sink.0,
+                    })
+                    .collect(),
+            );
+        }
+        if let Some((from, to)) = self.start_sector {
+            builder = builder.start_sector_deg(from.0.to_degrees(), to.0.to_degrees());
         }
+        if let Some(script) = self.script {
+            builder = builder
+                .edge_hook(edge_hook(&script).expect("scripts are checked by query_from_request"));
+        }
+        builder
+            .build()
+            .expect("queries are checked when they are created")
     }
 }
 
-#[cached(size = 200)]
+/// Hook running an edge script, see `EdgeScript`.
+#[cfg(feature = "scripting")]
+fn edge_hook(script: &str) -> Result<Arc<dyn EdgeHook>, String> {
+    Ok(Arc::new(EdgeScript::compile(script)?.hook()))
+}
+
+#[cfg(not(feature = "scripting"))]
+fn edge_hook(_script: &str) -> Result<Arc<dyn EdgeHook>, String> {
+    Err("This server is built without the scripting feature".to_string())
+}
+
+type PointSearchKey = (
+    Distance,
+    Distance,
+    Distance,
+    SearchQueryHashable,
+    Option<SyntheticTerrain>,
+    bool,
+);
+
+/// Explored nodes, grid, ground height and index of the start, and the
+/// bearings under which the cone is cut off by the grid border.
+type PointSearch = (Vec<Node>, HeightGrid, f32, GridIx, Vec<u16>);
+
+/// Estimated memory of a search in the search cache.
+fn point_search_bytes(search: &PointSearch) -> usize {
+    let (explored, grid, _, _, _) = search;
+    explored.len() * std::mem::size_of::<Node>()
+        + grid.heights.len() * std::mem::size_of::<i16>()
+        + grid.data_quality.as_ref().map_or(0, |quality| {
+            quality.len() * std::mem::size_of::<DataQuality>()
+        })
+}
+
+#[cached(
+    ty = "ByteLimitedCache<PointSearchKey, PointSearch>",
+    create = r#"{
+        let config = &app_config().server;
+        ByteLimitedCache::new(
+            config.cone_cache_size,
+            config.cone_cache_bytes,
+            config.cone_cache_ttl_secs.map(Duration::from_secs),
+            point_search_bytes,
+        )
+        .with_observer(|hit| record_cache_access(CacheTier::Search, hit))
+    }"#,
+    result = true
+)]
 fn search_from_point_memoized(
     latitude: Distance,
     longitude: Distance,
     cell_size: Distance,
     query: SearchQueryHashable,
-) -> (Vec<Node>, HeightGrid, f32, GridIx) {
-    let search_result =
-        search_from_point(latitude.0, longitude.0, cell_size.0, query.search_query());
-    (
-        search_result.explored.into_it().collect(),
-        search_result.height_grid,
-        search_result.ground_height,
-        search_result.start_ix,
+    synthetic: Option<SyntheticTerrain>,
+    avoid_airspace: bool,
+) -> Result<PointSearch, SearchError> {
+    if let Some(terrain) = synthetic {
+        let search_result = search_synthetic(
+            terrain,
+            latitude.0,
+            longitude.0,
+            cell_size.0,
+            query.search_query(),
+        )?;
+        return Ok((
+            search_result.explored.into_it().collect(),
+            search_result.height_grid,
+            search_result.ground_height,
+            search_result.start_ix,
+            search_result.border_bearings,
+        ));
+    }
+
+    if avoid_airspace {
+        // Airspaces change independently of the DEM, so these searches are
+        // not stored in the persistent cache.
+        let search_result = search_from_point_avoiding_airspace(
+            latitude.0,
+            longitude.0,
+            cell_size.0,
+            query.search_query(),
+            airspaces(),
+        )?;
+        return Ok((
+            search_result.explored.into_it().collect(),
+            search_result.height_grid,
+            search_result.ground_height,
+            search_result.start_ix,
+            search_result.border_bearings,
+        ));
+    }
+
+    let config = app_config();
+    let mut canonical_query = format!(
+        "{}_{}_{}_{}",
+        latitude.canonicalize(),
+        longitude.canonicalize(),
+        cell_size.canonicalize(),
+        query.canonical_string()
+    );
+    // Parallel searches are approximate, they must not be mixed up with
+    // exact ones of other instances.
+    if config.parallel_search {
+        canonical_query += "_parallel";
+    }
+    let storage_key = format!(
+        "cones/{}.bin",
+        cone_cache_key(
+            &canonical_query,
+            &format!(
+                "{}/{}",
+                current_dem_version(),
+                dem_generation().load(Ordering::SeqCst)
+            )
+        )
+    );
+
+    if config.persistent_cone_cache {
+        let key = storage_key.clone();
+        if let Some(cone) = wait_for_storage(async move { cache_storage().get(&key).await })
+            .and_then(|bytes| decode_cone(&bytes))
+        {
+            return Ok((
+                cone.explored,
+                cone.height_grid,
+                cone.ground_height,
+                cone.start_ix,
+                cone.border_bearings,
+            ));
+        }
+    }
+
+    let search_result = if config.parallel_search {
+        search_from_point_parallel(latitude.0, longitude.0, cell_size.0, query.search_query())?
+    } else {
+        search_from_point(latitude.0, longitude.0, cell_size.0, query.search_query())?
+    };
+    let cone = CachedCone {
+        explored: search_result.explored.into_it().collect(),
+        height_grid: search_result.height_grid,
+        ground_height: search_result.ground_height,
+        start_ix: search_result.start_ix,
+        border_bearings: search_result.border_bearings,
+    };
+
+    if config.persistent_cone_cache && !config.read_only {
+        let bytes = encode_cone(&cone);
+        if let Err(e) =
+            wait_for_storage(async move { cache_storage().put(&storage_key, &bytes).await })
+        {
+            warn!(parent: &current_request_span(), "Could not store cone in cache: {e}");
+        }
+    }
+
+    Ok((
+        cone.explored,
+        cone.height_grid,
+        cone.ground_height,
+        cone.start_ix,
+        cone.border_bearings,
+    ))
+}
+
+/// Explored nodes and grid of a multi start search, with the index and
+/// height of each start.
+type MultiStartSearch = (Vec<Node>, HeightGrid, Vec<(GridIx, f32)>, Vec<u16>);
+
+/// Multi start searches are only cached in memory.
+#[cached(size = 20, result = true)]
+fn search_from_points_memoized(
+    points: Vec<(Distance, Distance, Option<Distance>)>,
+    cell_size: Distance,
+    query: SearchQueryHashable,
+) -> Result<MultiStartSearch, SearchError> {
+    let points = points
+        .into_iter()
+        .map(|(lat, lon, height)| (lat.0, lon.0, height.map(|h| h.0)))
+        .collect::<Vec<_>>();
+    let result = search_from_points(&points, cell_size.0, query.search_query())?;
+    Ok((
+        result.explored.into_it().collect(),
+        result.height_grid,
+        result.starts,
+        result.border_bearings,
+    ))
+}
+
+/// Explored nodes and grid of a search with thermals, with the ground height
+/// and index of the start and the thermals climbed in.
+type ThermalSearch = (Vec<Node>, HeightGrid, f32, GridIx, Vec<GridIx>, Vec<u16>);
+
+/// Searches with thermals are only cached in memory.
+#[cached(size = 20, result = true)]
+fn search_with_thermals_memoized(
+    latitude: Distance,
+    longitude: Distance,
+    cell_size: Distance,
+    query: SearchQueryHashable,
+    thermals: Vec<(Distance, Distance, Distance)>,
+) -> Result<ThermalSearch, SearchError> {
+    let thermals = thermals
+        .into_iter()
+        .map(|(lat, lon, top)| (lat.0, lon.0, top.0))
+        .collect::<Vec<_>>();
+    let (result, climbs) = search_from_point_with_thermals(
+        latitude.0,
+        longitude.0,
+        cell_size.0,
+        query.search_query(),
+        &thermals,
+    )?;
+    Ok((
+        result.explored.into_it().collect(),
+        result.height_grid,
+        result.ground_height,
+        result.start_ix,
+        climbs,
+        result.border_bearings,
+    ))
+}
+
+/// Relative change of the effective glide ratio above which a sector is
+/// recomputed in incremental searches.
+const INCREMENTAL_SEARCH_THRESHOLD: f32 = 0.02;
+
+/// Searches for `query` by reusing a cached search that only differed in
+/// wind direction, recomputing only the sectors affected by the change.
+/// `None` if there is no previous search, or `query` itself is cached already
+/// and the exact result should be used instead.
+fn search_incrementally(
+    latitude: Distance,
+    longitude: Distance,
+    cell_size: Distance,
+    query: SearchQueryHashable,
+    previous_wind_direction: Distance,
+) -> Option<PointSearch> {
+    let key = (
+        latitude.clone(),
+        longitude.clone(),
+        cell_size.clone(),
+        query.clone(),
+        None,
+        false,
+    );
+    if SEARCH_FROM_POINT_MEMOIZED
+        .lock()
+        .unwrap()
+        .contains_at(&key, Instant::now())
+    {
+        return None;
+    }
+    search_incrementally_memoized(
+        latitude,
+        longitude,
+        cell_size,
+        query,
+        previous_wind_direction,
     )
 }
 
+/// Incremental searches are approximations, so they are cached apart from
+/// the exact ones, keyed by the wind direction they were derived from.
+#[cached(size = 20, option = true)]
+fn search_incrementally_memoized(
+    latitude: Distance,
+    longitude: Distance,
+    cell_size: Distance,
+    query: SearchQueryHashable,
+    previous_wind_direction: Distance,
+) -> Option<PointSearch> {
+    let previous_query = SearchQueryHashable {
+        wind_direction: previous_wind_direction,
+        ..query.clone()
+    };
+    let previous_key = (
+        latitude.clone(),
+        longitude.clone(),
+        cell_size.clone(),
+        previous_query.clone(),
+        None,
+        false,
+    );
+    let (explored, height_grid, _, _, _) = SEARCH_FROM_POINT_MEMOIZED
+        .lock()
+        .unwrap()
+        .cache_get(&previous_key)?
+        .clone();
+
+    let result = search_from_point_incremental(
+        latitude.0,
+        longitude.0,
+        cell_size.0,
+        query.search_query(),
+        PreviousSearch {
+            explored: &explored,
+            height_grid: &height_grid,
+            query: &previous_query.search_query(),
+        },
+        INCREMENTAL_SEARCH_THRESHOLD,
+    )?;
+    Some((
+        result.explored.into_it().collect(),
+        result.height_grid,
+        result.ground_height,
+        result.start_ix,
+        result.border_bearings,
+    ))
+}
+
 pub struct SearchFromRequestResult {
     explored: Vec<Node>,
     height_grid: HeightGrid,
@@ -155,83 +1324,582 @@ pub struct SearchFromRequestResult {
     height_at_start: f32,
     start_ix: GridIx,
     in_safety_margin: Array2<bool>,
+    /// Only set with `AirspaceMode::Flag`.
+    in_airspace: Array2<bool>,
+    /// Reachable cells inside a no landing zone.
+    landing_prohibited: Array2<bool>,
+    /// Surface of the reachable cells, where land cover tiles are available.
+    land_cover: Array2<Option<LandCover>>,
+    /// Start after the drift of the climb, only set with a climb rate.
+    effective_start: Option<(f32, f32)>,
+    /// All starts of a multi start search, the first one at `start_ix`. With
+    /// thermals the start followed by the thermals climbed in.
+    starts: Option<Vec<GridIx>>,
+    /// Index in `starts` of the start each reachable node is reached from.
+    start_sources: HashMap<GridIx, u8>,
+    /// Bearings under which the cone reached the border of the grid, see
+    /// `GridMap::border_bearings`.
+    border_bearings: Vec<u16>,
 }
 
-#[allow(clippy::too_many_arguments)]
-pub fn search_from_request(
-    lat: f32,
-    lon: f32,
-    cell_size_opt: Option<f32>,
-    glide_number_opt: Option<f32>,
-    additional_height_opt: Option<f32>,
+/// Start of a request, given either as `lat` and `lon`, as a `coord` string
+/// in one of the formats of `parse_coordinates` or as an `olc` Plus Code.
+fn start_from_request(
+    lat: Option<f32>,
+    lon: Option<f32>,
+    coord: Option<&str>,
+    olc: Option<&str>,
+) -> Result<(f32, f32), (Status, String)> {
+    match (lat, lon, coord, olc) {
+        (Some(lat), Some(lon), None, None) => Ok((lat, lon)),
+        (None, None, Some(coord), None) => parse_coordinates(coord)
+            .map_err(|e| (Status::BadRequest, format!("Invalid coord: {e}"))),
+        (None, None, None, Some(olc)) => {
+            decode_plus_code(olc).map_err(|e| (Status::BadRequest, format!("Invalid olc: {e}")))
+        }
+        _ => Err((
+            Status::BadRequest,
+            "The start is needed as either lat and lon, coord or olc".to_string(),
+        )),
+    }
+}
+
+/// Query parameters of a cone search, shared by every route that searches a
+/// cone and by the queries of `/flight_cone_batch`. Routes take it as the
+/// trailing `<params..>` after their own parameters.
+#[derive(FromForm, Deserialize, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+struct ConeParams {
+    lat: Option<f32>,
+    lon: Option<f32>,
+    coord: Option<String>,
+    olc: Option<String>,
+    cell_size: Option<f32>,
+    glide_number: Option<f32>,
+    additional_height: Option<f32>,
     start_height: Option<f32>,
-    wind_speed_opt: Option<f32>,
-    wind_direction_opt: Option<f32>,
-    trim_speed_opt: Option<f32>,
-    safety_margin_opt: Option<f32>,
-    start_distance_opt: Option<f32>,
-) -> SearchFromRequestResult {
-    let cell_size = cell_size_opt
-        .unwrap_or(CELL_SIZE_DEFAULT)
-        .clamp(CELL_SIZE_MINIMUM, CELL_SIZE_MAXIMUM);
-    let glide_number = glide_number_opt
-        .unwrap_or(GLIDE_NUMBER_DEFAULT)
-        .clamp(GLIDE_NUMBER_MINIMUM, GLIDE_NUMBER_MAXIMUM);
-    let additional_height = additional_height_opt
-        .unwrap_or(ADDITIONAL_HEIGHT_DEFAULT)
-        .clamp(ADDITIONAL_HEIGHT_MINIMUM, ADDITIONAL_HEIGHT_MAXIMUM);
-    let wind_speed = wind_speed_opt
-        .unwrap_or(WIND_SPEED_DEFAULT)
-        .clamp(WIND_SPEED_MINIMUM, WIND_SPEED_MAXIMUM);
-    let wind_direction = wind_direction_opt.unwrap_or(WIND_DIRECTION_DEFAULT);
-    let trim_speed = trim_speed_opt
-        .unwrap_or(TRIM_SPEED_DEFAULT)
-        .clamp(TRIM_SPEED_MINIMUM, TRIM_SPEED_MAXIMUM);
-    let safety_margin = safety_margin_opt
-        .unwrap_or(SAFETY_MARGIN_DEFAULT)
-        .max(SAFETY_MARGIN_MINIMUM);
-    let start_distance = start_distance_opt
-        .unwrap_or(START_DISTANCE_DEFAULT)
-        .max(START_DISTANCE_MINIMUM);
+    wind_speed: Option<f32>,
+    wind_direction: Option<f32>,
+    trim_speed: Option<f32>,
+    sink_rate: Option<f32>,
+    safety_margin: Option<f32>,
+    start_distance: Option<f32>,
+    safety_margin_ramp: Option<String>,
+    ridge_lift: Option<bool>,
+    polar: Option<String>,
+    wind_layers: Option<String>,
+    sector_from: Option<f32>,
+    sector_to: Option<f32>,
+    terrain_error: Option<bool>,
+    model: Option<String>,
+    airspace: Option<String>,
+    climb_rate: Option<f32>,
+    reverse: Option<bool>,
+    starts: Option<String>,
+    thermals: Option<String>,
+    synthetic: Option<String>,
+    previous_wind_direction: Option<f32>,
+    script: Option<String>,
+}
 
-    let accuracy = 10000.0;
+impl ConeParams {
+    fn start(&self) -> Result<(f32, f32), (Status, String)> {
+        start_from_request(
+            self.lat,
+            self.lon,
+            self.coord.as_deref(),
+            self.olc.as_deref(),
+        )
+    }
 
-    let lat_rounded = (lat * accuracy).round() / accuracy;
-    let lon_rounded = (lon * accuracy).round() / accuracy;
+    fn synthetic(&self) -> Result<Option<SyntheticTerrain>, (Status, String)> {
+        self.synthetic
+            .as_deref()
+            .map(|s| s.parse::<SyntheticTerrain>())
+            .transpose()
+            .map_err(|e| (Status::BadRequest, e))
+    }
 
-    let (explored, grid, height_at_start, start_ix) = search_from_point_memoized(
-        Distance(lat_rounded),
-        Distance(lon_rounded),
-        Distance(cell_size),
-        SearchQueryHashable {
-            start_height: start_height.map(Distance),
-            additional_height: Distance(additional_height),
-            wind_speed: Distance(wind_speed),
-            wind_direction: Distance(wind_direction / 180.0 * PI),
-            glide_ratio: Distance(1.0 / glide_number),
-            trim_speed: Distance(trim_speed),
-            safety_margin: Distance(safety_margin),
-            start_distance: Distance(start_distance),
-        },
-    );
+    /// See `effective_glide_number`.
+    fn glide_number(&self) -> Option<f32> {
+        effective_glide_number(self.glide_number, self.trim_speed, self.sink_rate)
+    }
+}
 
-    let mut heights =
-        Array2::from_elem((grid.heights.shape()[0], grid.heights.shape()[1]), -1000.0);
-    let mut node_heights =
-        Array2::from_elem((grid.heights.shape()[0], grid.heights.shape()[1]), -1000.0);
+/// Glide number of a request, or the one of its `sink_rate` at trim speed.
+fn effective_glide_number(
+    glide_number: Option<f32>,
+    trim_speed: Option<f32>,
+    sink_rate: Option<f32>,
+) -> Option<f32> {
+    glide_number.or_else(|| {
+        sink_rate.map(|sink_rate| {
+            1.0 / glide_ratio_at(trim_speed.unwrap_or(TRIM_SPEED_DEFAULT), sink_rate)
+        })
+    })
+}
+
+/// Applies defaults and limits to the flight parameters of a request.
+fn query_from_request(params: &ConeParams) -> Result<SearchQueryHashable, (Status, String)> {
+    let additional_height = limits().additional_height.clamp(
+        params
+            .additional_height
+            .unwrap_or(ADDITIONAL_HEIGHT_DEFAULT),
+    );
+    let wind_speed = limits()
+        .wind_speed
+        .clamp(params.wind_speed.unwrap_or(WIND_SPEED_DEFAULT));
+    let wind_direction = params.wind_direction.unwrap_or(WIND_DIRECTION_DEFAULT);
+    let trim_speed = limits()
+        .trim_speed
+        .clamp(params.trim_speed.unwrap_or(TRIM_SPEED_DEFAULT));
+    if params.glide_number.is_some() && params.sink_rate.is_some() {
+        return Err((
+            Status::BadRequest,
+            "Pass either glide_number or sink_rate".to_string(),
+        ));
+    }
+    if params
+        .sink_rate
+        .is_some_and(|sink_rate| !(sink_rate.is_finite() && sink_rate > 0.0))
+    {
+        return Err((
+            Status::BadRequest,
+            "The sink rate must be positive".to_string(),
+        ));
+    }
+    let glide_number = limits().glide_number.clamp(
+        effective_glide_number(params.glide_number, Some(trim_speed), params.sink_rate)
+            .unwrap_or(GLIDE_NUMBER_DEFAULT),
+    );
+    let safety_margin = params
+        .safety_margin
+        .unwrap_or(SAFETY_MARGIN_DEFAULT)
+        .max(SAFETY_MARGIN_MINIMUM);
+    let start_distance = params
+        .start_distance
+        .unwrap_or(START_DISTANCE_DEFAULT)
+        .max(START_DISTANCE_MINIMUM);
+    let safety_margin_ramp = params
+        .safety_margin_ramp
+        .as_deref()
+        .map(parse_safety_margin_ramp)
+        .transpose()
+        .map_err(|e| {
+            (
+                Status::BadRequest,
+                format!("Invalid safety margin ramp: {e}"),
+            )
+        })?
+        .unwrap_or_default();
+    let wind_layers = params
+        .wind_layers
+        .as_deref()
+        .map(parse_wind_layers)
+        .transpose()
+        .map_err(|e| (Status::BadRequest, format!("Invalid wind layers: {e}")))?
+        .unwrap_or_default()
+        .into_iter()
+        .map(|layer| WindLayer {
+            speed: layer.speed.min(limits().wind_speed.max),
+            ..layer
+        })
+        .collect();
+    let model = params
+        .model
+        .as_deref()
+        .map(|m| m.parse::<GlideModel>())
+        .transpose()
+        .map_err(|e| (Status::BadRequest, e))?
+        .unwrap_or_default();
+
+    let mut builder = SearchQuery::builder()
+        .glide_number(glide_number)
+        .trim_speed_kmh(trim_speed)
+        .wind_speed_kmh(wind_speed)
+        .wind_direction_deg(wind_direction)
+        .wind_layers(wind_layers)
+        .additional_height_m(additional_height)
+        .safety_margin_m(safety_margin)
+        .start_distance_m(start_distance)
+        .safety_margin_ramp(safety_margin_ramp)
+        .ridge_lift(params.ridge_lift.unwrap_or(false))
+        .terrain_error(params.terrain_error.unwrap_or(false))
+        .model(model);
+    if let Some(start_height) = params.start_height {
+        builder = builder.start_height_m(start_height);
+    }
+    if let Some(polar) = params.polar.as_deref() {
+        builder = builder.polar(parse_polar(polar).map_err(|e| (Status::BadRequest, e))?);
+    }
+    builder = match (params.sector_from, params.sector_to) {
+        (Some(from), Some(to)) => builder.start_sector_deg(from, to),
+        (None, None) => builder,
+        _ => {
+            return Err((
+                Status::BadRequest,
+                "Invalid sector: sector_from and sector_to have to be given together".to_string(),
+            ))
+        }
+    };
+    let query = builder.build().map_err(|e| (Status::BadRequest, e))?;
+    let mut query = SearchQueryHashable::from_query(query);
+    if let Some(script) = &params.script {
+        if params.reverse.unwrap_or(false) {
+            return Err((
+                Status::BadRequest,
+                "Scripts can not be used in reverse searches".to_string(),
+            ));
+        }
+        edge_hook(script).map_err(|e| (Status::BadRequest, e))?;
+        query.script = Some(script.clone());
+    }
+    Ok(query)
+}
+
+/// Start of a search, moved by the drift of the climb with a climb rate and
+/// rounded so nearby requests share cached searches. The flag tells whether
+/// the start drifted.
+fn search_start(
+    lat: f32,
+    lon: f32,
+    cell_size: f32,
+    query: &SearchQueryHashable,
+    climb_rate_opt: Option<f32>,
+    synthetic: Option<SyntheticTerrain>,
+) -> ((f32, f32), bool) {
+    // Synthetic terrain is generated around the start, so it never drifts.
+    let effective_start = match (climb_rate_opt, synthetic) {
+        (Some(climb_rate), None) if !query.reverse => Some(drifted_start(
+            lat,
+            lon,
+            cell_size,
+            &query.clone().search_query(),
+            limits().climb_rate.clamp(climb_rate),
+        )),
+        _ => None,
+    };
+    let (lat, lon) = effective_start.unwrap_or((lat, lon));
+
+    let accuracy = 10000.0;
+
+    let lat_rounded = (lat * accuracy).round() / accuracy;
+    let lon_rounded = (lon * accuracy).round() / accuracy;
+    ((lat_rounded, lon_rounded), effective_start.is_some())
+}
+
+fn search_from_request(
+    lat: f32,
+    lon: f32,
+    params: &ConeParams,
+) -> Result<SearchFromRequestResult, (Status, String)> {
+    run_search_request(search_request(lat, lon, params)?)
+}
+
+/// Landability of a cell by the slope of the terrain and its surface.
+fn cell_landability(
+    grid: &HeightGrid,
+    land_cover: &Array2<Option<LandCover>>,
+    ix: GridIx,
+) -> Landability {
+    let ix = (ix.0 as usize, ix.1 as usize);
+    landability_at(grid, ix.0, ix.1).with_land_cover(land_cover[ix])
+}
+
+/// Search of a request with validated parameters and the start rounded so
+/// nearby requests share cached searches.
+struct SearchRequest {
+    lat: f32,
+    lon: f32,
+    cell_size: f32,
+    query: SearchQueryHashable,
+    airspace: Option<AirspaceMode>,
+    further_starts: Option<Vec<(f32, f32, Option<f32>)>>,
+    /// `(latitude, longitude, top)` of thermals to climb in.
+    thermals: Option<Vec<(f32, f32, f32)>>,
+    synthetic: Option<SyntheticTerrain>,
+    /// Whether the start drifted with the climb.
+    drifted: bool,
+    previous_wind_direction: Option<f32>,
+}
+
+impl SearchRequest {
+    /// Identifies the result of the search and everything derived from it,
+    /// e.g. for the raster cache. Incremental searches from a previous wind
+    /// direction are approximations, so that direction is part of it.
+    fn key(&self) -> String {
+        format!(
+            "{}_{}_{}_{}_{:?}_{:?}_{:?}_{:?}_{:?}",
+            self.lat,
+            self.lon,
+            self.cell_size,
+            self.query.canonical_string(),
+            self.airspace,
+            self.further_starts,
+            self.thermals,
+            self.synthetic,
+            self.previous_wind_direction,
+        )
+    }
+}
+
+/// Checks the parameters of a request and places its start, without
+/// searching yet.
+fn search_request(
+    lat: f32,
+    lon: f32,
+    params: &ConeParams,
+) -> Result<SearchRequest, (Status, String)> {
+    let cell_size = limits()
+        .cell_size
+        .clamp(params.cell_size.unwrap_or(CELL_SIZE_DEFAULT));
+
+    let synthetic = params.synthetic()?;
+    let mut query = query_from_request(params)?;
+    query.reverse = params.reverse.unwrap_or(false);
+    if query.reverse && query.start_height.is_none() {
+        return Err((
+            Status::BadRequest,
+            "Reverse searches need a start_height to limit the search".to_string(),
+        ));
+    }
+    let airspace = params
+        .airspace
+        .as_deref()
+        .map(|a| a.parse::<AirspaceMode>())
+        .transpose()
+        .map_err(|e| (Status::BadRequest, e))?;
+    let avoid_airspace = airspace == Some(AirspaceMode::Avoid);
+    let further_starts = params
+        .starts
+        .as_deref()
+        .map(parse_starts)
+        .transpose()
+        .map_err(|e| (Status::BadRequest, format!("Invalid starts: {e}")))?;
+    if let Some(further_starts) = &further_starts {
+        let starts_maximum = limits().starts_maximum;
+        if further_starts.len() >= starts_maximum {
+            return Err((
+                Status::BadRequest,
+                format!("At most {starts_maximum} starts are supported"),
+            ));
+        }
+        if synthetic.is_some() || avoid_airspace || query.reverse {
+            return Err((
+                Status::BadRequest,
+                "starts can not be combined with synthetic terrain, airspace=avoid or reverse"
+                    .to_string(),
+            ));
+        }
+    }
+    let thermals = params
+        .thermals
+        .as_deref()
+        .map(parse_thermals)
+        .transpose()
+        .map_err(|e| (Status::BadRequest, format!("Invalid thermals: {e}")))?;
+    if let Some(thermals) = &thermals {
+        if thermals.len() > MAX_THERMALS {
+            return Err((
+                Status::BadRequest,
+                format!("At most {MAX_THERMALS} thermals are supported"),
+            ));
+        }
+        if further_starts.is_some() || synthetic.is_some() || avoid_airspace || query.reverse {
+            return Err((
+                Status::BadRequest,
+                "thermals can not be combined with starts, synthetic terrain, airspace=avoid or \
+                 reverse"
+                    .to_string(),
+            ));
+        }
+    }
+
+    let ((lat, lon), drifted) =
+        search_start(lat, lon, cell_size, &query, params.climb_rate, synthetic);
+
+    Ok(SearchRequest {
+        lat,
+        lon,
+        cell_size,
+        query,
+        airspace,
+        further_starts,
+        thermals,
+        synthetic,
+        drifted,
+        previous_wind_direction: params.previous_wind_direction,
+    })
+}
+
+/// Counts the search of a request in the usage statistics. Only done for
+/// the cones users ask for, not for every image, tile or export derived from
+/// them, so each request is counted once.
+fn record_usage(request: &SearchRequest) {
+    if request.synthetic.is_some() {
+        return;
+    }
+    usage_stats().record(
+        current_day(),
+        request.lat,
+        request.lon,
+        SearchParameters {
+            glide_number: 1.0 / request.query.glide_ratio.0,
+            wind_speed: request.query.wind_speed.0,
+            additional_height: request.query.additional_height.0,
+        },
+    );
+}
+
+/// Runs the search of a request, or takes it from the search cache, and
+/// derives the grids of the result.
+fn run_search_request(request: SearchRequest) -> Result<SearchFromRequestResult, (Status, String)> {
+    let SearchRequest {
+        lat: lat_rounded,
+        lon: lon_rounded,
+        cell_size,
+        query,
+        airspace,
+        further_starts,
+        thermals,
+        synthetic,
+        drifted,
+        previous_wind_direction,
+    } = request;
+    let safety_margin = query.safety_margin.0;
+    let avoid_airspace = airspace == Some(AirspaceMode::Avoid);
+
+    let (explored, grid, height_at_start, start_ix, starts, border_bearings) =
+        match (further_starts, thermals) {
+            (Some(further_starts), _) => {
+                let points = [(
+                    lat_rounded,
+                    lon_rounded,
+                    query.start_height.as_ref().map(|h| h.0),
+                )]
+                .into_iter()
+                .chain(further_starts)
+                .map(|(lat, lon, height)| (Distance(lat), Distance(lon), height.map(Distance)))
+                .collect();
+                let (explored, grid, starts, border_bearings) =
+                    search_from_points_memoized(points, Distance(cell_size), query)
+                        .map_err(search_error_response)?;
+                let height_at_start = starts[0].1;
+                let starts = starts.into_iter().map(|(ix, _)| ix).collect::<Vec<_>>();
+                (
+                    explored,
+                    grid,
+                    height_at_start,
+                    starts[0],
+                    Some(starts),
+                    border_bearings,
+                )
+            }
+            // The thermals climbed in are listed as starts, so nodes are marked
+            // with the climb they are reached after.
+            (None, Some(thermals)) => {
+                let thermals = thermals
+                    .into_iter()
+                    .map(|(lat, lon, top)| (Distance(lat), Distance(lon), Distance(top)))
+                    .collect();
+                let (explored, grid, height_at_start, start_ix, climbs, border_bearings) =
+                    search_with_thermals_memoized(
+                        Distance(lat_rounded),
+                        Distance(lon_rounded),
+                        Distance(cell_size),
+                        query,
+                        thermals,
+                    )
+                    .map_err(search_error_response)?;
+                (
+                    explored,
+                    grid,
+                    height_at_start,
+                    start_ix,
+                    Some([start_ix].into_iter().chain(climbs).collect()),
+                    border_bearings,
+                )
+            }
+            (None, None) => {
+                let incremental = match (
+                    previous_wind_direction,
+                    synthetic,
+                    avoid_airspace || query.reverse || query.script.is_some(),
+                ) {
+                    (Some(previous_direction), None, false) if previous_direction.is_finite() => {
+                        search_incrementally(
+                            Distance(lat_rounded),
+                            Distance(lon_rounded),
+                            Distance(cell_size),
+                            query.clone(),
+                            Distance(previous_direction / 180.0 * PI),
+                        )
+                    }
+                    _ => None,
+                };
+                let (explored, grid, height_at_start, start_ix, border_bearings) = match incremental
+                {
+                    Some(search) => search,
+                    None => search_from_point_memoized(
+                        Distance(lat_rounded),
+                        Distance(lon_rounded),
+                        Distance(cell_size),
+                        query,
+                        synthetic,
+                        avoid_airspace,
+                    )
+                    .map_err(search_error_response)?,
+                };
+                (
+                    explored,
+                    grid,
+                    height_at_start,
+                    start_ix,
+                    None,
+                    border_bearings,
+                )
+            }
+        };
+    let start_sources = starts
+        .as_ref()
+        .map(|starts| start_sources(explored.iter(), starts))
+        .unwrap_or_default();
+
+    let mut heights =
+        Array2::from_elem((grid.heights.shape()[0], grid.heights.shape()[1]), -1000.0);
+    let mut node_heights =
+        Array2::from_elem((grid.heights.shape()[0], grid.heights.shape()[1]), -1000.0);
     let mut in_safety_margin =
         Array2::from_elem((grid.heights.shape()[0], grid.heights.shape()[1]), false);
+    let mut in_airspace =
+        Array2::from_elem((grid.heights.shape()[0], grid.heights.shape()[1]), false);
+    let airspace_grid =
+        (airspace == Some(AirspaceMode::Flag)).then(|| AirspaceGrid::new(airspaces(), &grid));
 
     for node in explored.iter() {
         if node.reachable {
             heights[(node.ix.0 as usize, node.ix.1 as usize)] =
                 node.height - grid.heights[(node.ix.0 as usize, node.ix.1 as usize)] as f32;
             node_heights[(node.ix.0 as usize, node.ix.1 as usize)] = node.height;
-            in_safety_margin[(node.ix.0 as usize, node.ix.1 as usize)] = node.in_safety_margin;
+            in_safety_margin[(node.ix.0 as usize, node.ix.1 as usize)] =
+                node.in_safety_margin(safety_margin);
+            in_airspace[(node.ix.0 as usize, node.ix.1 as usize)] = airspace_grid
+                .as_ref()
+                .is_some_and(|airspace| airspace.contains(&node.ix, node.height));
         }
     }
 
-    SearchFromRequestResult {
+    let landing_prohibited = match no_landing_zones().read().unwrap().as_ref() {
+        Some(zones) => region_mask(&explored, &grid, zones),
+        None => Array2::from_elem((grid.heights.shape()[0], grid.heights.shape()[1]), false),
+    };
+    let land_cover = if grid.data_source == DataSource::Synthetic {
+        Array2::from_elem((grid.heights.shape()[0], grid.heights.shape()[1]), None)
+    } else {
+        land_cover_grid(&explored, &grid, load_land_cover_tile)
+    };
+
+    Ok(SearchFromRequestResult {
         explored,
         height_grid: grid,
         heights,
@@ -239,16 +1907,77 @@ pub fn search_from_request(
         height_at_start,
         start_ix,
         in_safety_margin,
+        in_airspace,
+        landing_prohibited,
+        land_cover,
+        effective_start: drifted.then_some((lat_rounded, lon_rounded)),
+        starts,
+        start_sources,
+        border_bearings,
+    })
+}
+
+/// Searches that can not be run, e.g. because the grid would be too large,
+/// are answered with the reason so clients can adjust the request.
+fn search_error_response(error: SearchError) -> (Status, String) {
+    match error {
+        SearchError::NoHeightData => no_height_data(),
+        SearchError::GridTooLarge { .. } | SearchError::EdgeHook(_) => {
+            (Status::UnprocessableEntity, error.to_string())
+        }
     }
 }
 
+fn no_height_data() -> (Status, String) {
+    (
+        Status::NotFound,
+        "No height data available at this location".to_string(),
+    )
+}
+
 #[derive(Serialize)]
 struct NodeResponse {
     index: GridIx,
     height: i16,
+    /// Meters flown along the glide path.
     distance: i32,
+    /// Meters from the start in a straight line, never more than `distance`.
+    straight_distance: i32,
     reference: Option<GridIx>,
     agl: i16,
+    #[serde(skip_serializing_if = "DataQuality::is_measured")]
+    data_quality: DataQuality,
+    /// Reached inside restricted airspace, only with `airspace=flag`.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    in_airspace: bool,
+    /// Inside a no landing zone. The cell is reachable, but must not be
+    /// chosen as landing.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    landing_prohibited: bool,
+    /// Whether the terrain of the cell is flat enough and its surface
+    /// suitable to land on.
+    landability: Landability,
+    /// Surface of the cell, unset without land cover data.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    land_cover: Option<LandCover>,
+    /// Lowest height above ground along the path, so clients can apply other
+    /// safety margins without searching again. Unset if the whole path is
+    /// within the start distance.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    clearance: Option<i16>,
+    /// Lowest height above ground on the way to the node, also between the
+    /// cells of the path. A low value means the node is only reached by just
+    /// clearing some terrain. Unset if no part of the path counts, see
+    /// `Node::min_agl`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_agl: Option<i16>,
+    /// Ground speed in km/h on arrival, in the wind at the node. Useful to
+    /// judge downwind arrivals into small fields.
+    ground_speed: i16,
+    /// Index in `starts` of the start the node is reached from, only for
+    /// multi start searches and searches with thermals.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    start: Option<u8>,
 }
 
 #[derive(Serialize)]
@@ -263,6 +1992,8 @@ struct ReducedNodeResponse {
 #[derive(Serialize)]
 struct FlightConeResponse {
     nodes: Option<Vec<NodeResponse>>,
+    /// Number of reachable nodes, set for paginated requests.
+    total_nodes: Option<usize>,
     cell_size: f32,
     min_cell_size: f32,
     angular_resolution: (f32, f32),
@@ -271,6 +2002,47 @@ struct FlightConeResponse {
     start_ix: GridIx,
     grid_shape: (usize, usize),
     start_height: f32,
+    data_source: DataSource,
+    dem_version: DemVersion,
+    algorithm_version: u32,
+    ridge_lift: bool,
+    /// `(lat, lon)` of the requested start, also when given as `coord` or
+    /// `olc`.
+    start_location: (f32, f32),
+    /// `(lat, lon)` of the start after the wind drift of the climb, only set
+    /// with `climb_rate`.
+    effective_start: Option<(f32, f32)>,
+    /// Node heights are the heights needed to reach the start, see
+    /// `SearchQuery::reverse`.
+    reverse: bool,
+    /// Meters the terrain was raised by to account for the vertical error of
+    /// the DEM, only set with `terrain_error`.
+    terrain_error_margin: Option<f32>,
+    /// Grid positions of all starts of a multi start search, or of the start
+    /// and the thermals climbed in.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    starts: Option<Vec<GridIx>>,
+    /// The cone reached the border of the loaded grid, so it may be cut off
+    /// under `border_bearings`.
+    truncated_at_border: bool,
+    /// Bearings in degrees from the start, rounded to 10 degrees.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    border_bearings: Vec<u16>,
+    /// Only set with `debug=true`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    debug: Option<ConeDebugInfo>,
+}
+
+/// Where the time of a cone request went.
+#[derive(Serialize)]
+struct ConeDebugInfo {
+    #[serde(flatten)]
+    metrics: RequestMetrics,
+    /// Milliseconds from the start of the search to the response, more
+    /// than the timed stages.
+    total_ms: f64,
+    explored_nodes: usize,
+    reachable_nodes: usize,
 }
 
 #[derive(Serialize)]
@@ -282,6 +2054,7 @@ struct HeightMapResponse {
     start_ix: GridIx,
     grid_shape: (usize, usize),
     heights: Vec<i16>,
+    data_source: DataSource,
 }
 
 #[derive(Serialize)]
@@ -293,6 +2066,7 @@ struct HeightMapMetaResponse {
     start_ix: GridIx,
     grid_shape: (usize, usize),
     start_height: f32,
+    data_source: DataSource,
 }
 
 fn build_height_grid(
@@ -301,15 +2075,16 @@ fn build_height_grid(
     margin_m: Option<f32>,
     cell_size: Option<f32>,
 ) -> Result<HeightGrid, Status> {
-    if !location_supported(lat, lon) {
+    let Some(data_source) = select_data_source(lat, lon, cell_size.unwrap_or(0.0)) else {
         return Result::Err(Status::NotFound);
-    }
+    };
 
     let margin = margin_m.unwrap_or(15_000.0).clamp(1_000.0, 300_000.0);
-    let mut grid = get_height_data_around_point(lat, lon, Some(margin));
+    let mut grid = get_height_data_around_point_from(data_source, lat, lon, Some(margin))
+        .ok_or(Status::NotFound)?;
 
     if let Some(requested_cell_size) = cell_size {
-        let effective_cell_size = requested_cell_size.max(grid.cell_size);
+        let effective_cell_size = snap_cell_size(requested_cell_size, grid.cell_size);
         grid = grid.scale(grid.cell_size / effective_cell_size);
     }
 
@@ -324,9 +2099,10 @@ fn height_map_meta_from_grid(grid: &HeightGrid) -> HeightMapMetaResponse {
         min_cell_size: grid.min_cell_size,
         lat: grid.latitudes,
         lon: grid.longitudes,
-        start_ix: (center_row as u16, center_col as u16),
+        start_ix: (center_row as GridIxType, center_col as GridIxType),
         grid_shape: (grid.heights.shape()[0], grid.heights.shape()[1]),
         start_height: grid.heights[(center_row, center_col)] as f32,
+        data_source: grid.data_source,
     }
 }
 
@@ -397,58 +2173,38 @@ fn get_height_map(
         start_ix: meta.start_ix,
         grid_shape: meta.grid_shape,
         heights: grid.heights.iter().copied().collect(),
+        data_source: meta.data_source,
     };
 
     Result::Ok(Json(response))
 }
 
-#[allow(clippy::too_many_arguments)]
-#[get("/flight_cone?<lat>&<lon>&<cell_size>&<glide_number>&<additional_height>&<start_height>&<wind_speed>&<wind_direction>&<trim_speed>&<safety_margin>&<start_distance>")]
-fn get_flight_cone(
-    lat: f32,
-    lon: f32,
-    cell_size: Option<f32>,
-    glide_number: Option<f32>,
-    additional_height: Option<f32>,
-    start_height: Option<f32>,
-    wind_speed: Option<f32>,
-    wind_direction: Option<f32>,
-    trim_speed: Option<f32>,
-    safety_margin: Option<f32>,
-    start_distance: Option<f32>,
-) -> Result<Json<FlightConeResponse>, Status> {
-    if !location_supported(lat, lon) {
-        return Result::Err(Status::NotFound);
-    }
-
-    {
-        let mut lock = num_searches().lock().unwrap();
-        *lock += 1;
-    }
-
-    let search_from_request_result = search_from_request(
-        lat,
-        lon,
-        cell_size,
-        glide_number,
-        additional_height,
-        start_height,
-        wind_speed,
-        wind_direction,
-        trim_speed,
-        safety_margin,
-        start_distance,
-    );
-
+/// The `/flight_cone` response of a search, with all reachable nodes or the
+/// page of `offset` and `limit`, and the number of explored nodes.
+fn flight_cone_response(
+    search_from_request_result: SearchFromRequestResult,
+    start_location: (f32, f32),
+    ridge_lift: bool,
+    reverse: bool,
+    terrain_error: bool,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> (FlightConeResponse, usize) {
     let grid = search_from_request_result.height_grid;
     let explored = search_from_request_result.explored;
     let height_at_start = search_from_request_result.height_at_start;
     let start_ix = search_from_request_result.start_ix;
+    let in_airspace = search_from_request_result.in_airspace;
+    let landing_prohibited = search_from_request_result.landing_prohibited;
+    let land_cover = search_from_request_result.land_cover;
+    let start_sources = search_from_request_result.start_sources;
+    let starts = search_from_request_result.starts;
 
     let resolution = grid.get_angular_resolution();
 
     let mut response = FlightConeResponse {
         nodes: None,
+        total_nodes: None,
         cell_size: grid.cell_size,
         angular_resolution: resolution,
         start_ix,
@@ -457,173 +2213,823 @@ fn get_flight_cone(
         min_cell_size: grid.min_cell_size,
         grid_shape: (grid.heights.shape()[0], grid.heights.shape()[1]),
         start_height: height_at_start,
+        data_source: grid.data_source,
+        dem_version: current_dem_version(),
+        algorithm_version: ALGORITHM_VERSION,
+        ridge_lift,
+        start_location,
+        effective_start: search_from_request_result.effective_start,
+        reverse,
+        terrain_error_margin: terrain_error.then(|| grid.data_source.vertical_error()),
+        starts: starts.clone(),
+        truncated_at_border: !search_from_request_result.border_bearings.is_empty(),
+        border_bearings: search_from_request_result.border_bearings,
+        debug: None,
     };
 
-    let mut nodes = vec![];
+    // Multi start searches measure from the start each node is reached from.
+    let origin_of = |node: &Node| {
+        start_sources
+            .get(&node.ix)
+            .and_then(|start| starts.as_ref()?.get(*start as usize))
+            .copied()
+            .unwrap_or(start_ix)
+    };
+    let to_response = |node: &Node| NodeResponse {
+        index: node.ix,
+        height: node.height as i16,
+        distance: node.distance as i32,
+        straight_distance: (l2_distance(&origin_of(node), &node.ix) * grid.cell_size) as i32,
+        reference: node.reference,
+        agl: node.height as i16 - grid.heights[(node.ix.0 as usize, node.ix.1 as usize)],
+        data_quality: grid.data_quality_at((node.ix.0 as usize, node.ix.1 as usize)),
+        in_airspace: in_airspace[(node.ix.0 as usize, node.ix.1 as usize)],
+        landing_prohibited: landing_prohibited[(node.ix.0 as usize, node.ix.1 as usize)],
+        landability: cell_landability(&grid, &land_cover, node.ix),
+        land_cover: land_cover[(node.ix.0 as usize, node.ix.1 as usize)],
+        clearance: node.clearance.is_finite().then_some(node.clearance as i16),
+        min_agl: node.min_agl.is_finite().then_some(node.min_agl as i16),
+        ground_speed: node.ground_speed.round() as i16,
+        start: start_sources.get(&node.ix).copied(),
+    };
 
-    for node in explored {
-        if node.reachable {
-            nodes.push(NodeResponse {
-                index: node.ix,
-                height: node.height as i16,
-                distance: node.distance as i32,
-                reference: node.reference,
-                agl: node.height as i16 - grid.heights[(node.ix.0 as usize, node.ix.1 as usize)],
-            })
-        }
+    let explored_nodes = explored.len();
+    let _timer = time_stage(Stage::Serialization);
+    if offset.is_some() || limit.is_some() {
+        // Paginated requests get the nodes in the same order as the websocket
+        // stream, so clients can draw each page as it arrives.
+        let ordered = reachable_in_transfer_order(explored);
+        let start = offset.unwrap_or(0).min(ordered.len());
+        let end = limit.map_or(ordered.len(), |l| {
+            start.saturating_add(l).min(ordered.len())
+        });
+
+        response.total_nodes = Some(ordered.len());
+        response.nodes = Some(ordered[start..end].iter().map(to_response).collect());
+    } else {
+        response.nodes = Some(
+            explored
+                .iter()
+                .filter(|node| node.reachable)
+                .map(to_response)
+                .collect(),
+        );
     }
 
-    response.nodes = Some(nodes);
-
-    Result::Ok(Json(response))
+    (response, explored_nodes)
 }
 
-#[allow(clippy::too_many_arguments)]
-#[get("/flight_cone_ws/ws?<lat>&<lon>&<cell_size>&<glide_number>&<additional_height>&<start_height>&<wind_speed>&<wind_direction>&<trim_speed>&<safety_margin>&<start_distance>")]
-fn get_flight_cone_stream(
-    ws: WebSocket,
-    lat: f32,
-    lon: f32,
-    cell_size: Option<f32>,
-    glide_number: Option<f32>,
-    additional_height: Option<f32>,
-    start_height: Option<f32>,
-    wind_speed: Option<f32>,
-    wind_direction: Option<f32>,
-    trim_speed: Option<f32>,
-    safety_margin: Option<f32>,
-    start_distance: Option<f32>,
-) -> Stream!['static] {
+#[get("/flight_cone?<offset>&<limit>&<debug>&<params..>")]
+fn get_flight_cone(
+    params: ConeParams,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    debug: Option<bool>,
+) -> Result<Json<FlightConeResponse>, (Status, String)> {
+    let (lat, lon) = params.start()?;
+    // Synthetic terrain needs no DEM files, for frontend development and CI.
+    if params.synthetic()?.is_none()
+        && select_data_source(lat, lon, params.cell_size.unwrap_or(CELL_SIZE_DEFAULT)).is_none()
+    {
+        return Result::Err(no_height_data());
+    }
+
     {
         let mut lock = num_searches().lock().unwrap();
         *lock += 1;
     }
 
-    let search_from_request_result = search_from_request(
-        lat,
-        lon,
-        cell_size,
-        glide_number,
-        additional_height,
-        start_height,
-        wind_speed,
-        wind_direction,
-        trim_speed,
-        safety_margin,
-        start_distance,
-    );
+    let started = Instant::now();
+    let (response, metrics) = collect_request_metrics(|| {
+        let request = search_request(lat, lon, &params)?;
+        record_usage(&request);
+        let search_from_request_result = run_search_request(request)?;
+
+        Ok(flight_cone_response(
+            search_from_request_result,
+            (lat, lon),
+            params.ridge_lift.unwrap_or(false),
+            params.reverse.unwrap_or(false),
+            params.terrain_error.unwrap_or(false),
+            offset,
+            limit,
+        ))
+    });
+    let (mut response, explored_nodes) = response?;
+
+    if debug.unwrap_or(false) {
+        response.debug = Some(ConeDebugInfo {
+            metrics,
+            total_ms: started.elapsed().as_secs_f64() * 1000.0,
+            explored_nodes,
+            reachable_nodes: response
+                .total_nodes
+                .or(response.nodes.as_ref().map(Vec::len))
+                .unwrap_or(0),
+        });
+    }
+    Result::Ok(Json(response))
+}
 
-    let grid = search_from_request_result.height_grid;
-    let explored = search_from_request_result.explored;
+/// Cells of a search that weigh as much as one job in the order of the cone
+/// jobs.
+const CONE_JOB_CELLS_PER_WEIGHT: f32 = 1_000_000.0;
+
+/// Search submitted with `POST /flight_cone_job`, with what its response
+/// needs besides the search.
+struct ConeJob {
+    request: SearchRequest,
+    start_location: (f32, f32),
+    ridge_lift: bool,
+    reverse: bool,
+    terrain_error: bool,
+    /// Span of the submitting request, the search is logged in it.
+    span: Span,
+    /// Tiles shared with nearby jobs of the same batch.
+    shared_tiles: Option<Arc<SharedTiles>>,
+}
 
-    let mut nodes = vec![];
-    let mut distances = std::collections::HashMap::<GridIx, f32>::new();
+/// The `/flight_cone` JSON of a finished cone job, or the error `/flight_cone`
+/// would have answered with.
+type ConeJobResult = Result<Arc<String>, (Status, String)>;
 
-    for node in explored {
-        if node.reachable {
-            distances.insert(node.ix, node.distance);
-            nodes.push(node);
+struct ConeJobs {
+    queue: Mutex<JobQueue<ConeJob, ConeJobResult>>,
+    /// Notified for every submitted job.
+    submitted: Notify,
+}
+
+fn cone_jobs() -> &'static ConeJobs {
+    static INSTANCE: OnceLock<ConeJobs> = OnceLock::new();
+    INSTANCE.get_or_init(|| {
+        let config = &app_config().cone_jobs;
+        ConeJobs {
+            queue: Mutex::new(JobQueue::new(config.max_queued, config.max_finished)),
+            submitted: Notify::new(),
         }
-    }
+    })
+}
 
-    // Group nodes by reference, sort by distance of the reference
-    let groups = nodes.iter().fold(
-        std::collections::HashMap::<Option<GridIx>, Vec<&Node>>::new(),
-        |mut acc, node| {
-            acc.entry(node.reference).or_default().push(node);
-            acc
-        },
+/// Upper bound of the distance a search reaches, from the height at its
+/// start estimated without loading the grid.
+fn estimated_search_radius(request: &SearchRequest) -> f32 {
+    let query = request.query.clone().search_query();
+    let data_source = select_data_source(request.lat, request.lon, request.cell_size);
+    let ground_height = match (request.synthetic, data_source) {
+        (None, Some(data_source)) => get_interpolated_height_at_point_from(
+            data_source,
+            request.lat,
+            request.lon,
+            Interpolation::Bilinear,
+        ),
+        _ => 0.0,
+    };
+    let height_at_start = query
+        .start_height
+        .unwrap_or(ground_height + query.additional_height)
+        .max(ground_height);
+    max_search_distance(&query, height_at_start)
+}
+
+/// Weight of a search in the order of the cone jobs, by the cells of the
+/// largest area it may need.
+fn cone_job_weight(request: &SearchRequest) -> u64 {
+    let side = 2.0 * estimated_search_radius(request) / request.cell_size;
+    (side * side / CONE_JOB_CELLS_PER_WEIGHT) as u64
+}
+
+/// Unguessable id of a cone job, so only its submitter can poll the result.
+fn cone_job_id() -> String {
+    let mut id = [0u8; 16];
+    OsRng.fill_bytes(&mut id);
+    hex_string(&id)
+}
+
+fn run_cone_job(job: ConeJob) -> ConeJobResult {
+    let _span = job.span.enter();
+    let _pinned = job.shared_tiles.as_ref().map(|tiles| tiles.pin());
+    {
+        let mut lock = num_searches().lock().unwrap();
+        *lock += 1;
+    }
+    let result = run_search_request(job.request)?;
+    let (response, _) = flight_cone_response(
+        result,
+        job.start_location,
+        job.ridge_lift,
+        job.reverse,
+        job.terrain_error,
+        None,
+        None,
     );
-    let mut groups = groups
-        .into_iter()
-        .map(|(a, b)| (a.map(|ix| distances[&ix]).unwrap_or(-1.0), b))
-        .collect::<Vec<_>>();
-    groups.sort_by(|a, b| {
-        if a.0 < b.0 {
-            Ordering::Less
-        } else {
-            Ordering::Greater
-        }
-    });
-    let returned_nodes = groups
-        .into_iter()
-        .flat_map(|(_, mut v)| {
-            v.sort_by(|a, b| {
-                if a.distance < b.distance {
-                    Ordering::Less
-                } else {
-                    Ordering::Greater
-                }
-            });
-            v
-        })
-        .cloned()
-        .collect::<Vec<_>>();
+    serde_json::to_string(&response)
+        .map(Arc::new)
+        .map_err(|e| (Status::InternalServerError, e.to_string()))
+}
 
-    let mut last_reference = None;
+/// Runs the cone jobs once the server is up, `workers` searches at a time.
+struct ConeJobWorkers {
+    workers: usize,
+}
 
-    Stream! { ws =>
-        let chunk_size = 20000;
-        for i in (0..returned_nodes.len()).step_by(chunk_size) {
-            let n = returned_nodes[i..(i + chunk_size).min(returned_nodes.len())].iter().map(|node| {
-                let reference = if node.reference == last_reference {
-                    None
-                } else {
-                    last_reference = node.reference;
-                    node.reference
-                };
-                ReducedNodeResponse {
-                    i: node.ix,
-                    r: reference,
+#[rocket::async_trait]
+impl Fairing for ConeJobWorkers {
+    fn info(&self) -> Info {
+        Info {
+            name: "Cone job workers",
+            kind: Kind::Liftoff,
+        }
+    }
+
+    async fn on_liftoff(&self, _: &Rocket<Orbit>) {
+        for _ in 0..self.workers {
+            rocket::tokio::spawn(async {
+                let jobs = cone_jobs();
+                loop {
+                    let next = jobs.queue.lock().unwrap().take();
+                    let Some((id, job)) = next else {
+                        jobs.submitted.notified().await;
+                        continue;
+                    };
+                    let result = task::spawn_blocking(move || run_cone_job(job))
+                        .await
+                        .unwrap_or_else(|e| Err((Status::InternalServerError, e.to_string())));
+                    jobs.queue.lock().unwrap().finish(&id, result);
                 }
-            }).collect::<Vec<_>>();
-            let response_str = serde_json::to_string(&n).unwrap();
-            yield rocket_ws::Message::Text(response_str);
+            });
         }
     }
 }
 
-#[allow(clippy::too_many_arguments)]
-#[get("/flight_cone_bounds?<lat>&<lon>&<cell_size>&<glide_number>&<additional_height>&<start_height>&<wind_speed>&<wind_direction>&<trim_speed>&<safety_margin>&<start_distance>")]
-fn get_flight_cone_bounds(
-    lat: f32,
-    lon: f32,
-    cell_size: Option<f32>,
-    glide_number: Option<f32>,
-    additional_height: Option<f32>,
-    start_height: Option<f32>,
-    wind_speed: Option<f32>,
-    wind_direction: Option<f32>,
-    trim_speed: Option<f32>,
-    safety_margin: Option<f32>,
-    start_distance: Option<f32>,
-) -> Result<Json<FlightConeResponse>, Status> {
-    if !location_supported(lat, lon) {
-        return Result::Err(Status::NotFound);
-    }
+#[derive(Serialize)]
+struct ConeJobStatus {
+    id: String,
+    /// `queued` or `running`.
+    status: &'static str,
+    /// Number of jobs that run before a queued job.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    position: Option<usize>,
+    /// Where the job is polled.
+    url: String,
+}
 
-    let search_from_request_result = search_from_request(
-        lat,
-        lon,
-        cell_size,
-        glide_number,
-        additional_height,
-        start_height,
-        wind_speed,
-        wind_direction,
-        trim_speed,
-        safety_margin,
-        start_distance,
-    );
+#[derive(Responder)]
+enum ConeJobResponse {
+    #[response(status = 202)]
+    Pending(Json<ConeJobStatus>),
+    /// The `/flight_cone` JSON.
+    #[response(content_type = "json")]
+    Finished(String),
+}
 
-    let grid = search_from_request_result.height_grid;
-    let height_at_start = search_from_request_result.height_at_start;
-    let start_ix = search_from_request_result.start_ix;
+fn pending_cone_job(id: &str, status: &'static str, position: Option<usize>) -> ConeJobStatus {
+    ConeJobStatus {
+        id: id.to_string(),
+        status,
+        position,
+        url: format!("/flight_cone_job/{id}"),
+    }
+}
 
-    let resolution = grid.get_angular_resolution();
+/// Checks the parameters of a `/flight_cone` request and turns it into a
+/// job, with the weight of the job.
+fn cone_job(params: &ConeParams) -> Result<(ConeJob, u64), (Status, String)> {
+    let (lat, lon) = params.start()?;
+    if params.synthetic()?.is_none()
+        && select_data_source(lat, lon, params.cell_size.unwrap_or(CELL_SIZE_DEFAULT)).is_none()
+    {
+        return Err(no_height_data());
+    }
+
+    let request = search_request(lat, lon, params)?;
+    let weight = cone_job_weight(&request);
+    let job = ConeJob {
+        request,
+        start_location: (lat, lon),
+        ridge_lift: params.ridge_lift.unwrap_or(false),
+        reverse: params.reverse.unwrap_or(false),
+        terrain_error: params.terrain_error.unwrap_or(false),
+        span: current_request_span(),
+        shared_tiles: None,
+    };
+    Ok((job, weight))
+}
+
+fn submit_cone_job(job: ConeJob, weight: u64) -> Result<ConeJobStatus, (Status, String)> {
+    let id = cone_job_id();
+    let jobs = cone_jobs();
+    let state = {
+        let mut queue = jobs.queue.lock().unwrap();
+        queue
+            .submit(id.clone(), job, weight)
+            .map_err(|e| (Status::ServiceUnavailable, e))?;
+        queue.state(&id)
+    };
+    jobs.submitted.notify_one();
+    info!(job = %id, weight, "Queued cone job");
+    Ok(match state {
+        Some(JobState::Queued { position }) => pending_cone_job(&id, "queued", Some(position)),
+        _ => pending_cone_job(&id, "running", None),
+    })
+}
+
+/// Queues the search of a `/flight_cone` request, for searches that take
+/// longer than a connection should be held. The parameters are checked right
+/// away, the result is polled from the returned `url`.
+#[post("/flight_cone_job?<params..>")]
+fn post_flight_cone_job(params: ConeParams) -> Result<ConeJobResponse, (Status, String)> {
+    let (job, weight) = cone_job(&params)?;
+    record_usage(&job.request);
+    Ok(ConeJobResponse::Pending(Json(submit_cone_job(
+        job, weight,
+    )?)))
+}
+
+/// State of a cone job while it is pending, then the response of
+/// `/flight_cone` with all nodes, or its error.
+#[get("/flight_cone_job/<id>")]
+fn get_flight_cone_job(id: &str) -> Result<ConeJobResponse, (Status, String)> {
+    let state = cone_jobs().queue.lock().unwrap().state(id);
+    match state {
+        Some(JobState::Queued { position }) => Ok(ConeJobResponse::Pending(Json(
+            pending_cone_job(id, "queued", Some(position)),
+        ))),
+        Some(JobState::Running) => Ok(ConeJobResponse::Pending(Json(pending_cone_job(
+            id, "running", None,
+        )))),
+        Some(JobState::Finished(result)) => {
+            result.map(|json| ConeJobResponse::Finished(json.as_ref().clone()))
+        }
+        None => Err((
+            Status::NotFound,
+            "Unknown job, or its result was dropped already".to_string(),
+        )),
+    }
+}
+
+/// Queries of a batch at most.
+const MAX_BATCH_QUERIES: usize = 50;
+
+/// Source, latitude and longitude of a DEM tile.
+type DemTile = (DataSource, i32, i32);
+
+/// DEM tiles of the jobs of a batch that start in the same tile. The first of
+/// the jobs that runs reads them, so the others, also when they run at the
+/// same time on other workers, find them in memory. The tiles are pinned
+/// while a job of the group runs, so its search can't evict them from the
+/// tile cache, and stay in the cache for the next job afterwards.
+struct SharedTiles {
+    tiles: Vec<DemTile>,
+}
+
+impl SharedTiles {
+    fn pin(&self) -> Vec<PinnedTile> {
+        self.tiles
+            .iter()
+            .map(|&(source, latitude, longitude)| pin_tile(source, latitude, longitude))
+            .collect()
+    }
+}
+
+/// Existing DEM tiles a search may need, none on synthetic terrain.
+fn search_tiles(request: &SearchRequest) -> Vec<DemTile> {
+    let Some(source) = select_data_source(request.lat, request.lon, request.cell_size) else {
+        return vec![];
+    };
+    if request.synthetic.is_some() {
+        return vec![];
+    }
+    let (south, north, west, east) =
+        get_bounds_around_point(request.lat, request.lon, estimated_search_radius(request));
+    let mut tiles = vec![];
+    for latitude in south.floor() as i32..=north.floor() as i32 {
+        for longitude in west.floor() as i32..=east.floor() as i32 {
+            if tile_exists(source, latitude, longitude) {
+                tiles.push((source, latitude, longitude));
+            }
+        }
+    }
+    tiles
+}
+
+/// Job of a query of a batch, or why the query was rejected.
+#[derive(Serialize)]
+#[serde(untagged)]
+enum BatchItem {
+    Queued(ConeJobStatus),
+    Rejected { error: String, status_code: u16 },
+}
+
+/// Status 202 with the items of a batch.
+type BatchResponse = (Status, Json<Vec<BatchItem>>);
+
+/// Queues a cone job for every query of a batch, e.g. all launches of a
+/// region. Jobs starting in the same DEM tile are queued together and share
+/// their tiles, see `SharedTiles`. Answers with the job or the error of
+/// every query, in the order of the queries.
+#[post("/flight_cone_batch", format = "json", data = "<queries>")]
+fn post_flight_cone_batch(
+    queries: Json<Vec<ConeParams>>,
+) -> Result<BatchResponse, (Status, String)> {
+    if !(1..=MAX_BATCH_QUERIES).contains(&queries.len()) {
+        return Err((
+            Status::BadRequest,
+            format!("A batch needs between 1 and {MAX_BATCH_QUERIES} queries"),
+        ));
+    }
+
+    let mut items = Vec::with_capacity(queries.len());
+    let mut jobs = vec![];
+    for (i, query) in queries.iter().enumerate() {
+        match cone_job(query) {
+            Ok((job, weight)) => {
+                record_usage(&job.request);
+                let (lat, lon) = (job.request.lat, job.request.lon);
+                jobs.push(((lat.floor() as i32, lon.floor() as i32), i, job, weight));
+                items.push(None);
+            }
+            Err((status, error)) => items.push(Some(BatchItem::Rejected {
+                error,
+                status_code: status.code,
+            })),
+        }
+    }
+
+    let mut group_tiles: BTreeMap<(i32, i32), Vec<DemTile>> = BTreeMap::new();
+    for (tile, _, job, _) in jobs.iter() {
+        let tiles = group_tiles.entry(*tile).or_default();
+        for search_tile in search_tiles(&job.request) {
+            if !tiles.contains(&search_tile) {
+                tiles.push(search_tile);
+            }
+        }
+    }
+    let shared_tiles = group_tiles
+        .into_iter()
+        .map(|(tile, tiles)| (tile, Arc::new(SharedTiles { tiles })))
+        .collect::<BTreeMap<_, _>>();
+
+    jobs.sort_by_key(|(tile, i, _, _)| (*tile, *i));
+    for (tile, i, mut job, weight) in jobs {
+        job.shared_tiles = shared_tiles.get(&tile).cloned();
+        items[i] = Some(match submit_cone_job(job, weight) {
+            Ok(status) => BatchItem::Queued(status),
+            Err((status, error)) => BatchItem::Rejected {
+                error,
+                status_code: status.code,
+            },
+        });
+    }
+
+    Ok((
+        Status::Accepted,
+        Json(items.into_iter().flatten().collect()),
+    ))
+}
+
+const CONE_STREAM_CHUNK_SIZE: usize = 20000;
+
+/// Smaller than `CONE_STREAM_CHUNK_SIZE`, so the first nodes of a progressive
+/// stream arrive while the search is still running.
+const PROGRESSIVE_CHUNK_SIZE: usize = 2000;
+
+/// Node of the flight cone stream, with the reference only given where it
+/// differs from the one of the previous node.
+fn reduced_node(node: &Node, last_reference: &mut Option<GridIx>) -> ReducedNodeResponse {
+    let reference = if node.reference == *last_reference {
+        None
+    } else {
+        *last_reference = node.reference;
+        node.reference
+    };
+    ReducedNodeResponse {
+        i: node.ix,
+        r: reference,
+    }
+}
+
+/// Encoding of the nodes of the flight cone stream.
+#[derive(Clone, Copy, PartialEq)]
+enum StreamFormat {
+    Json,
+    /// Frames of `encode_node_frame`, several times smaller than JSON.
+    Binary,
+}
+
+impl FromStr for StreamFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(StreamFormat::Json),
+            "binary" => Ok(StreamFormat::Binary),
+            _ => Err(format!("Unknown stream format {s}")),
+        }
+    }
+}
+
+/// Message of the flight cone stream. Only nodes are sent as binary
+/// messages, the grid of a progressive stream is always JSON.
+enum StreamMessage {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+fn node_message(nodes: &[ReducedNodeResponse], format: StreamFormat) -> StreamMessage {
+    match format {
+        StreamFormat::Json => StreamMessage::Text(serde_json::to_string(nodes).unwrap()),
+        StreamFormat::Binary => StreamMessage::Binary(encode_node_frame(
+            &nodes
+                .iter()
+                .map(|node| StreamNode {
+                    ix: node.i,
+                    reference: node.r,
+                })
+                .collect::<Vec<_>>(),
+        )),
+    }
+}
+
+/// Messages of the flight cone stream: the reachable nodes in the order of
+/// `reachable_in_transfer_order`.
+fn cone_stream_messages(nodes: &[Node], format: StreamFormat) -> Vec<StreamMessage> {
+    let mut last_reference = None;
+    nodes
+        .chunks(CONE_STREAM_CHUNK_SIZE)
+        .map(|chunk| {
+            let n = chunk
+                .iter()
+                .map(|node| reduced_node(node, &mut last_reference))
+                .collect::<Vec<_>>();
+            node_message(&n, format)
+        })
+        .collect()
+}
+
+/// First message of a progressive stream, the grid the node indices refer to.
+fn cone_stream_frame(grid: &HeightGrid, start_ix: GridIx, start_height: f32) -> String {
+    serde_json::to_string(&HeightMapMetaResponse {
+        cell_size: grid.cell_size,
+        min_cell_size: grid.min_cell_size,
+        lat: grid.latitudes,
+        lon: grid.longitudes,
+        start_ix,
+        grid_shape: (grid.heights.shape()[0], grid.heights.shape()[1]),
+        start_height,
+        data_source: grid.data_source,
+    })
+    .unwrap()
+}
+
+/// Cancels a search when dropped. Streams hold one, so their search stops
+/// when the client disconnects and the stream is dropped.
+struct CancelOnDrop(CancellationToken);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
+
+/// Runs a prepared search on a blocking thread and sends the reachable nodes
+/// as soon as they are explored, in the grid of `setup`. Stops early when the
+/// receiver is dropped or the search is cancelled. A finished search is
+/// stored in the search cache under `key`.
+fn stream_search_progressively(
+    setup: SearchSetup,
+    key: PointSearchKey,
+    format: StreamFormat,
+    sender: UnboundedSender<StreamMessage>,
+) {
+    rocket::tokio::task::spawn_blocking(move || {
+        let mut search = search_iter(setup.start_ix, setup.start_height, &setup.config);
+        let mut chunk = vec![];
+        let mut last_reference = None;
+        while let Some(node) = search.step() {
+            if !node.reachable {
+                continue;
+            }
+            chunk.push(reduced_node(node, &mut last_reference));
+            if chunk.len() == PROGRESSIVE_CHUNK_SIZE {
+                if sender.send(node_message(&chunk, format)).is_err() {
+                    return;
+                }
+                chunk.clear();
+            }
+        }
+        if setup.config.is_cancelled() {
+            info!("Search cancelled by the client");
+            return;
+        }
+        if !chunk.is_empty() && sender.send(node_message(&chunk, format)).is_err() {
+            return;
+        }
+
+        let explored = search.into_state().explored;
+        let border_bearings = explored.border_bearings(&setup.start_ix);
+        let (explored, height_grid, start_ix) =
+            reindex(explored, &setup.config.grid, setup.start_ix);
+        SEARCH_FROM_POINT_MEMOIZED.lock().unwrap().cache_set(
+            key,
+            (
+                explored.into_it().collect(),
+                height_grid,
+                setup.ground_height,
+                start_ix,
+                border_bearings,
+            ),
+        );
+    });
+}
+
+/// Sends messages that are already complete through a channel, like the
+/// ones of a progressive stream.
+fn channel_of(messages: Vec<StreamMessage>) -> UnboundedReceiver<StreamMessage> {
+    let (sender, receiver) = unbounded_channel();
+    for message in messages {
+        sender.send(message).unwrap();
+    }
+    receiver
+}
+
+fn flight_cone_stream_messages(
+    params: &ConeParams,
+    progressive: bool,
+    format: StreamFormat,
+    cancellation: CancellationToken,
+) -> Result<UnboundedReceiver<StreamMessage>, (Status, String)> {
+    let (lat, lon) = params.start()?;
+    {
+        let mut lock = num_searches().lock().unwrap();
+        *lock += 1;
+    }
+    let request = search_request(lat, lon, params)?;
+    record_usage(&request);
+
+    if progressive {
+        let key = (
+            Distance(request.lat),
+            Distance(request.lon),
+            Distance(request.cell_size),
+            request.query.clone(),
+            None,
+            false,
+        );
+        let cached = SEARCH_FROM_POINT_MEMOIZED
+            .lock()
+            .unwrap()
+            .cache_get(&key)
+            .is_some();
+        // Cached searches are sent at once, searches avoiding airspaces,
+        // parallel searches and searches that are not from a single start in
+        // the flight direction can not be run step by step. Scripted searches
+        // only report errors of their script at the end.
+        let single_start = !request.query.reverse
+            && request.query.script.is_none()
+            && request.further_starts.is_none()
+            && request.thermals.is_none()
+            && request.synthetic.is_none()
+            && request.previous_wind_direction.is_none();
+        if !cached
+            && single_start
+            && request.airspace != Some(AirspaceMode::Avoid)
+            && !app_config().parallel_search
+        {
+            let mut setup = prepare_search(
+                request.lat,
+                request.lon,
+                request.cell_size,
+                request.query.search_query(),
+            )
+            .map_err(search_error_response)?;
+            setup.config.cancellation = Some(cancellation);
+            let (sender, receiver) = unbounded_channel();
+            sender
+                .send(StreamMessage::Text(cone_stream_frame(
+                    &setup.config.grid,
+                    setup.start_ix,
+                    setup.ground_height,
+                )))
+                .unwrap();
+            stream_search_progressively(setup, key, format, sender);
+            return Ok(receiver);
+        }
+    }
+
+    let result = run_search_request(request)?;
+    let frame = progressive.then(|| {
+        StreamMessage::Text(cone_stream_frame(
+            &result.height_grid,
+            result.start_ix,
+            result.height_at_start,
+        ))
+    });
+    Ok(channel_of(
+        frame
+            .into_iter()
+            .chain(cone_stream_messages(
+                &reachable_in_transfer_order(result.explored),
+                format,
+            ))
+            .collect(),
+    ))
+}
+
+#[get("/flight_cone_ws/ws?<progressive>&<format>&<params..>")]
+fn get_flight_cone_stream(
+    ws: WebSocket,
+    params: ConeParams,
+    progressive: Option<bool>,
+    format: Option<&str>,
+) -> Stream!['static] {
+    let cancellation = CancellationToken::new();
+    let format = format
+        .unwrap_or("json")
+        .parse::<StreamFormat>()
+        .map_err(|e| (Status::BadRequest, e));
+    let messages = format.and_then(|format| {
+        flight_cone_stream_messages(
+            &params,
+            progressive.unwrap_or(false),
+            format,
+            cancellation.clone(),
+        )
+    });
+
+    Stream! { ws =>
+        let _cancel_on_drop = CancelOnDrop(cancellation);
+        let mut messages = match messages {
+            Ok(messages) => messages,
+            Err(e) => {
+                let frame = CloseFrame {
+                    code: CloseCode::Policy,
+                    reason: e.1.into(),
+                };
+                yield rocket_ws::Message::Close(Some(frame));
+                return;
+            }
+        };
+        while let Some(message) = messages.recv().await {
+            yield match message {
+                StreamMessage::Text(text) => rocket_ws::Message::Text(text),
+                StreamMessage::Binary(data) => rocket_ws::Message::Binary(data),
+            };
+        }
+    }
+}
+
+/// Fallback of the flight cone stream for networks that block websockets,
+/// requested by adding `chunked=true` to the websocket url. The messages
+/// are sent with chunked transfer encoding, one per line.
+#[get("/flight_cone_ws/ws?<chunked>&<progressive>&<params..>", rank = 2)]
+fn get_flight_cone_chunked(
+    chunked: bool,
+    params: ConeParams,
+    progressive: Option<bool>,
+) -> Result<TextStream![String], (Status, String)> {
+    if !chunked {
+        return Err((
+            Status::BadRequest,
+            "Use a websocket or chunked=true".to_string(),
+        ));
+    }
+    let cancellation = CancellationToken::new();
+    let messages = flight_cone_stream_messages(
+        &params,
+        progressive.unwrap_or(false),
+        StreamFormat::Json,
+        cancellation.clone(),
+    )?;
+    Ok(TextStream! {
+        let _cancel_on_drop = CancelOnDrop(cancellation);
+        let mut messages = messages;
+        // Lines can only hold JSON messages.
+        while let Some(StreamMessage::Text(message)) = messages.recv().await {
+            yield message + "\n";
+        }
+    })
+}
+
+#[get("/flight_cone_bounds?<params..>")]
+fn get_flight_cone_bounds(
+    params: ConeParams,
+) -> Result<Json<FlightConeResponse>, (Status, String)> {
+    let (lat, lon) = params.start()?;
+    if select_data_source(lat, lon, params.cell_size.unwrap_or(CELL_SIZE_DEFAULT)).is_none() {
+        return Result::Err(no_height_data());
+    }
+
+    let search_from_request_result = search_from_request(lat, lon, &params)?;
+
+    let grid = search_from_request_result.height_grid;
+    let height_at_start = search_from_request_result.height_at_start;
+    let start_ix = search_from_request_result.start_ix;
+
+    let resolution = grid.get_angular_resolution();
 
     let response = FlightConeResponse {
         nodes: None,
+        total_nodes: None,
         cell_size: grid.cell_size,
         angular_resolution: resolution,
         start_ix,
@@ -632,60 +3038,364 @@ fn get_flight_cone_bounds(
         min_cell_size: grid.min_cell_size,
         grid_shape: (grid.heights.shape()[0], grid.heights.shape()[1]),
         start_height: height_at_start,
+        data_source: grid.data_source,
+        dem_version: current_dem_version(),
+        algorithm_version: ALGORITHM_VERSION,
+        ridge_lift: params.ridge_lift.unwrap_or(false),
+        start_location: (lat, lon),
+        effective_start: search_from_request_result.effective_start,
+        reverse: params.reverse.unwrap_or(false),
+        terrain_error_margin: params
+            .terrain_error
+            .unwrap_or(false)
+            .then(|| grid.data_source.vertical_error()),
+        starts: None,
+        truncated_at_border: !search_from_request_result.border_bearings.is_empty(),
+        border_bearings: search_from_request_result.border_bearings,
+        debug: None,
     };
 
     Result::Ok(Json(response))
 }
 
-const DEFAULT_LERP_COLORS: [[f32; 4]; 3] = [
-    [255.0, 0.0, 0.0, 255.0],
-    [180.0, 190.0, 0.0, 255.0],
-    [0.0, 150.0, 255.0, 255.0],
-];
-const SAFETY_MARGIN_LERP_COLORS: [[f32; 4]; 3] = [
-    [255.0 / 5.0 * 3.0, 0.0, 0.0, 255.0],
-    [180.0 / 5.0 * 3.0, 190.0 / 5.0 * 3.0, 0.0, 255.0],
-    [0.0, 150.0 / 5.0 * 3.0, 255.0 / 5.0 * 3.0, 255.0],
-];
-const DEFAULT_LERP_STEPS: [f32; 3] = [0.0, 0.5, 1.0];
+const VIEW_WIDTH_DEFAULT: u32 = 1024;
+const VIEW_HEIGHT_DEFAULT: u32 = 768;
+
+/// Cheap estimate of the map area the cone will cover, so frontends can move
+/// the map before the search finishes. Only needs the height at the start.
+#[get("/suggest_view?<width>&<height>&<params..>")]
+fn get_suggest_view(
+    params: ConeParams,
+    width: Option<u32>,
+    height: Option<u32>,
+) -> Result<Json<MapView>, Status> {
+    let (lat, lon) = params.start().map_err(|(status, _)| status)?;
+    let Some(data_source) = select_data_source(lat, lon, CELL_SIZE_DEFAULT) else {
+        return Result::Err(Status::NotFound);
+    };
 
-#[allow(clippy::too_many_arguments)]
-#[get("/agl_image?<lat>&<lon>&<cell_size>&<glide_number>&<additional_height>&<start_height>&<wind_speed>&<wind_direction>&<trim_speed>&<safety_margin>&<start_distance>")]
-fn get_agl_image(
-    lat: f32,
-    lon: f32,
-    cell_size: Option<f32>,
-    glide_number: Option<f32>,
-    additional_height: Option<f32>,
-    start_height: Option<f32>,
-    wind_speed: Option<f32>,
-    wind_direction: Option<f32>,
-    trim_speed: Option<f32>,
-    safety_margin: Option<f32>,
-    start_distance: Option<f32>,
-) -> (ContentType, Vec<u8>) {
-    let search_from_request_result = search_from_request(
+    let query = query_from_request(&params)
+        .map_err(|(status, _)| status)?
+        .search_query();
+
+    let ground_height =
+        get_interpolated_height_at_point_from(data_source, lat, lon, Interpolation::Bilinear);
+    let height_at_start = query
+        .start_height
+        .unwrap_or(ground_height + query.additional_height)
+        .max(ground_height);
+
+    Result::Ok(Json(suggest_view(
         lat,
         lon,
-        cell_size,
-        glide_number,
-        additional_height,
-        start_height,
-        wind_speed,
-        wind_direction,
-        trim_speed,
+        max_search_distance(&query, height_at_start),
+        width.unwrap_or(VIEW_WIDTH_DEFAULT).max(1),
+        height.unwrap_or(VIEW_HEIGHT_DEFAULT).max(1),
+    )))
+}
+
+/// Searches with more cells are rejected by the debug endpoints, their
+/// responses would get too large.
+const DEBUG_MAX_CELLS: usize = 40_000;
+/// The brute force reference search is quadratic in the number of cells.
+const VERIFY_MAX_CELLS: usize = 2_500;
+
+#[derive(Serialize)]
+struct ExplorationStepResponse {
+    index: GridIx,
+    distance: f32,
+    height: f32,
+    reachable: bool,
+}
+
+#[derive(Serialize)]
+struct ExplorationOrderResponse {
+    cell_size: f32,
+    lat: (f32, f32),
+    lon: (f32, f32),
+    grid_shape: (usize, usize),
+    start_ix: GridIx,
+    start_height: f32,
+    steps: Vec<ExplorationStepResponse>,
+    verification: Option<VerificationResponse>,
+}
+
+/// Cells where the search and the brute force reference disagree.
+#[derive(Serialize)]
+struct VerificationResponse {
+    only_search: Vec<GridIx>,
+    only_reference: Vec<GridIx>,
+}
+
+/// Cells in the order they are popped from the queue, for animating the
+/// search. Indices refer to the full (uncropped) search grid. With `verify`,
+/// the result is also compared against the brute force reference search.
+#[get("/debug/exploration_order?<verify>&<params..>")]
+fn get_exploration_order(
+    params: ConeParams,
+    verify: Option<bool>,
+) -> Result<Json<ExplorationOrderResponse>, (Status, String)> {
+    let (lat, lon) = params.start()?;
+    let cell_size = limits()
+        .cell_size
+        .clamp(params.cell_size.unwrap_or(CELL_SIZE_DEFAULT));
+    if select_data_source(lat, lon, cell_size).is_none() {
+        return Result::Err(no_height_data());
+    }
+
+    let query = query_from_request(&params)?;
+    let airspace = params
+        .airspace
+        .as_deref()
+        .map(|a| a.parse::<AirspaceMode>())
+        .transpose()
+        .map_err(|e| (Status::BadRequest, e))?;
+    let (lat, lon) = match params.climb_rate {
+        Some(climb_rate) => drifted_start(
+            lat,
+            lon,
+            cell_size,
+            &query.clone().search_query(),
+            limits().climb_rate.clamp(climb_rate),
+        ),
+        None => (lat, lon),
+    };
+    let mut setup =
+        prepare_search(lat, lon, cell_size, query.search_query()).map_err(search_error_response)?;
+    if airspace == Some(AirspaceMode::Avoid) {
+        setup.config.airspace = Some(AirspaceGrid::new(airspaces(), &setup.config.grid));
+    }
+    let grid = &setup.config.grid;
+    let verify = verify.unwrap_or(false);
+    let max_cells = if verify {
+        VERIFY_MAX_CELLS
+    } else {
+        DEBUG_MAX_CELLS
+    };
+    if grid.heights.len() > max_cells {
+        return Result::Err((
+            Status::BadRequest,
+            format!("Search grid has more than {max_cells} cells, increase cell_size"),
+        ));
+    }
+
+    let (_, steps) = search_traced(setup.start_ix, setup.start_height, &setup.config);
+    let verification = verify.then(|| {
+        let report = verify_search(setup.start_ix, setup.start_height, &setup.config);
+        VerificationResponse {
+            only_search: report.only_search,
+            only_reference: report.only_reference,
+        }
+    });
+
+    Result::Ok(Json(ExplorationOrderResponse {
+        cell_size: grid.cell_size,
+        lat: grid.latitudes,
+        lon: grid.longitudes,
+        grid_shape: (grid.heights.shape()[0], grid.heights.shape()[1]),
+        start_ix: setup.start_ix,
+        start_height: setup.start_height,
+        steps: steps
+            .into_iter()
+            .map(|step| ExplorationStepResponse {
+                index: step.ix,
+                distance: step.distance,
+                height: step.height,
+                reachable: step.reachable,
+            })
+            .collect(),
+        verification,
+    }))
+}
+
+/// Small standalone UI for trying out the API, see `debug_ui/`.
+static DEBUG_UI: Dir = include_dir!("$CARGO_MANIFEST_DIR/debug_ui");
+
+fn debug_ui_file(path: &str) -> Option<(ContentType, &'static [u8])> {
+    let file = DEBUG_UI.get_file(path)?;
+    let content_type = path
+        .rsplit_once('.')
+        .and_then(|(_, extension)| ContentType::from_extension(extension))
+        .unwrap_or(ContentType::Binary);
+    Some((content_type, file.contents()))
+}
+
+#[get("/debug")]
+fn get_debug_ui() -> Option<(ContentType, &'static [u8])> {
+    debug_ui_file("index.html")
+}
+
+#[get("/debug/ui/<file>")]
+fn get_debug_ui_file(file: &str) -> Option<(ContentType, &'static [u8])> {
+    debug_ui_file(file)
+}
+
+/// Cells within the safety margin get the colors of the ramp darkened by
+/// this factor.
+const SAFETY_MARGIN_DARKENING: f32 = 0.6;
+/// Share of the landability tint in the colors of cells that are marginal or
+/// not landable, and the tints themselves.
+const LANDABILITY_TINT_SHARE: f32 = 0.6;
+const MARGINAL_TINT: [u8; 3] = [255, 200, 0];
+const UNLANDABLE_TINT: [u8; 3] = [128, 128, 128];
+
+/// Color ramp of the `colormap` parameter, the default one if there is none.
+fn color_ramp_from_request(colormap: Option<&str>) -> Result<ColorRamp, (Status, String)> {
+    colormap
+        .map(parse_color_ramp)
+        .transpose()
+        .map(Option::unwrap_or_default)
+        .map_err(|e| (Status::BadRequest, e))
+}
+
+fn projection_from_request(projection: Option<&str>) -> Result<RasterProjection, (Status, String)> {
+    projection
+        .map(str::parse)
+        .transpose()
+        .map(Option::unwrap_or_default)
+        .map_err(|e| (Status::BadRequest, e))
+}
+
+/// Image derived from a search, cached in the raster cache.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum RasterLayer {
+    Agl,
+    AglTile(TileIndex),
+    Height,
+    RawHeight,
+}
+
+/// `SearchRequest::key` of the search, the layer and how it is styled, e.g.
+/// its color ramp.
+type RasterKey = (String, RasterLayer, String);
+
+fn raster_cache() -> &'static Mutex<ByteLimitedCache<RasterKey, Vec<u8>>> {
+    static INSTANCE: OnceLock<Mutex<ByteLimitedCache<RasterKey, Vec<u8>>>> = OnceLock::new();
+    INSTANCE.get_or_init(|| {
+        let config = &app_config().server;
+        Mutex::new(
+            ByteLimitedCache::new(
+                config.raster_cache_size,
+                config.raster_cache_bytes,
+                config.cone_cache_ttl_secs.map(Duration::from_secs),
+                Vec::len,
+            )
+            .with_observer(|hit| record_cache_access(CacheTier::Raster, hit)),
+        )
+    })
+}
+
+/// PNG of `key` from the raster cache, rendered and stored if it is not
+/// cached. The cache is not locked while rendering.
+fn cached_raster(
+    key: RasterKey,
+    render: impl FnOnce() -> Result<Vec<u8>, (Status, String)>,
+) -> Result<Vec<u8>, (Status, String)> {
+    if let Some(png) = raster_cache().lock().unwrap().cache_get(&key) {
+        return Ok(png.clone());
+    }
+    let png = render()?;
+    raster_cache().lock().unwrap().cache_set(key, png.clone());
+    Ok(png)
+}
+
+/// Colors of the reachable cells of a search by their height above ground,
+/// shared by the AGL image and its tiles so both look the same.
+struct AglStyle<'a> {
+    result: &'a SearchFromRequestResult,
+    ramp: &'a ColorRamp,
+    safety_margin_ramp: ColorRamp,
+    brightness: Option<Array2<f32>>,
+    /// Tint cells by their landability, see `landability_at`.
+    landability: bool,
+    /// Heights above ground at the ends of the ramp.
+    hmin: f32,
+    hmax: f32,
+}
+
+impl<'a> AglStyle<'a> {
+    /// With a `sun` the colors are shaded by the terrain, see `hillshade`.
+    /// With `landability`, marginal cells are tinted yellow and cells too
+    /// steep to land grey.
+    fn new(
+        result: &'a SearchFromRequestResult,
+        safety_margin: Option<f32>,
+        sun: Option<Sun>,
+        ramp: &'a ColorRamp,
+        landability: bool,
+    ) -> AglStyle<'a> {
+        let reachable = result.heights.iter().filter(|agl| **agl > 0.0);
+        let hmin = reachable.clone().copied().fold(f32::MAX, f32::min);
+        let hmax = reachable.copied().fold(f32::MIN, f32::max);
+        AglStyle {
+            result,
+            ramp,
+            safety_margin_ramp: ramp.darkened(SAFETY_MARGIN_DARKENING),
+            brightness: sun.map(|sun| hillshade(&result.height_grid, sun)),
+            landability,
+            hmin: hmin.max(safety_margin.unwrap_or(0.0)),
+            hmax,
+        }
+    }
+
+    /// Color of the cell at `ix`, `None` if it is not reachable. The hatching
+    /// of estimated heights is aligned to `pixel`.
+    fn color(&self, ix: (usize, usize), pixel: (u32, u32)) -> Option<[u8; 4]> {
+        let agl = self.result.heights[ix];
+        if agl <= 0.0 {
+            return None;
+        }
+        let s = ((agl - self.hmin) / (self.hmax - self.hmin)).clamp(0.0, 1.0);
+        let ramp = if self.result.in_safety_margin[ix] {
+            &self.safety_margin_ramp
+        } else {
+            self.ramp
+        };
+        let mut color = f32_color_to_u8(ramp.color_at(s));
+        if self.landability {
+            let cell = (ix.0 as GridIxType, ix.1 as GridIxType);
+            match cell_landability(&self.result.height_grid, &self.result.land_cover, cell) {
+                Landability::Landable => {}
+                Landability::Marginal => {
+                    color = tint(color, MARGINAL_TINT, LANDABILITY_TINT_SHARE);
+                }
+                Landability::Unlandable => {
+                    color = tint(color, UNLANDABLE_TINT, LANDABILITY_TINT_SHARE);
+                }
+            }
+        }
+        if let Some(brightness) = &self.brightness {
+            color = shade(color, brightness[ix]);
+        }
+        if !self.result.height_grid.data_quality_at(ix).is_measured() {
+            color = hatch(color, pixel.0, pixel.1);
+        }
+        Some(color)
+    }
+}
+
+/// Reachable area colored by the height above ground with `ramp`, cropped
+/// to the reachable cells. See `AglStyle::new` for `sun` and `landability`.
+fn agl_png(
+    search_from_request_result: &SearchFromRequestResult,
+    safety_margin: Option<f32>,
+    sun: Option<Sun>,
+    ramp: &ColorRamp,
+    landability: bool,
+    projection: RasterProjection,
+) -> Vec<u8> {
+    let style = AglStyle::new(
+        search_from_request_result,
         safety_margin,
-        start_distance,
+        sun,
+        ramp,
+        landability,
     );
-
-    let heights = search_from_request_result.heights;
-    let in_safety_margin = search_from_request_result.in_safety_margin;
+    let heights = &search_from_request_result.heights;
 
     let mut imgx = heights.shape()[0];
     let mut imgy = heights.shape()[1];
 
-    let mut hmin = f32::MAX;
-    let mut hmax = f32::MIN;
     let mut x_lower = usize::MAX;
     let mut x_upper = usize::MIN;
     let mut y_lower = usize::MAX;
@@ -694,9 +3404,6 @@ fn get_agl_image(
     for x in 0..imgx {
         for y in 0..imgy {
             if heights[(x, y)] > 0.0 {
-                hmin = hmin.min(heights[(x, y)]);
-                hmax = hmax.max(heights[(x, y)]);
-
                 x_lower = min(x_lower, x);
                 x_upper = max(x_upper, x);
                 y_lower = min(y_lower, y);
@@ -705,97 +3412,260 @@ fn get_agl_image(
         }
     }
 
-    hmin = hmin.max(safety_margin.unwrap_or(0.0));
-
     if x_lower == usize::MAX {
         imgx = 1;
         imgy = 1;
         x_lower = 0;
-        x_upper = 0;
         y_lower = 0;
-        y_upper = 0;
     } else {
         imgx = (x_upper - x_lower) + 1;
         imgy = (y_upper - y_lower) + 1;
     }
 
-    let heights_sub = heights.slice(s![x_lower..(x_upper + 1), y_lower..(y_upper + 1)]);
-    let safety_margin_sub =
-        in_safety_margin.slice(s![x_lower..(x_upper + 1), y_lower..(y_upper + 1)]);
-
     let mut img = DynamicImage::new_rgba8(imgy as u32, imgx as u32);
 
     // Iterate over the coordinates and pixels of the image
     for x in 0..imgx {
         for y in 0..imgy {
-            let ix = (x, y);
-            if heights_sub[ix] > 0.0 {
-                let agl = heights_sub[ix];
-                let s = ((agl - hmin) / (hmax - hmin)).clamp(0.0, 1.0);
-
-                if safety_margin_sub[ix] {
-                    img.put_pixel(
-                        y as u32,
-                        (imgx - x) as u32 - 1,
-                        Rgba(f32_color_to_u8(lerp(
-                            &SAFETY_MARGIN_LERP_COLORS,
-                            &DEFAULT_LERP_STEPS,
-                            s,
-                        ))),
-                    );
-                } else {
-                    img.put_pixel(
-                        y as u32,
-                        (imgx - x) as u32 - 1,
-                        Rgba(f32_color_to_u8(lerp(
-                            &DEFAULT_LERP_COLORS,
-                            &DEFAULT_LERP_STEPS,
-                            s,
-                        ))),
-                    );
-                }
-            } else {
-                img.put_pixel(y as u32, (imgx - x) as u32 - 1, Rgba([255, 255, 255, 0]));
-            }
+            let color = style
+                .color((x + x_lower, y + y_lower), (y as u32, x as u32))
+                .unwrap_or([255, 255, 255, 0]);
+            img.put_pixel(y as u32, (imgx - x) as u32 - 1, Rgba(color));
         }
     }
 
-    let mut c = Cursor::new(Vec::new());
-    img.write_to(&mut c, ImageFormat::Png).expect("");
-    (ContentType::PNG, c.into_inner())
+    encode_raster(
+        img,
+        &search_from_request_result.height_grid,
+        (x_lower, x_lower + imgx - 1),
+        projection,
+    )
 }
 
-#[allow(clippy::too_many_arguments)]
-#[get("/height_image?<lat>&<lon>&<cell_size>&<glide_number>&<additional_height>&<start_height>&<wind_speed>&<wind_direction>&<trim_speed>&<safety_margin>&<start_distance>")]
-fn get_height_image(
-    lat: f32,
-    lon: f32,
-    cell_size: Option<f32>,
-    glide_number: Option<f32>,
-    additional_height: Option<f32>,
-    start_height: Option<f32>,
-    wind_speed: Option<f32>,
-    wind_direction: Option<f32>,
-    trim_speed: Option<f32>,
+/// PNG of an image of the grid rows `rows` (from the south, inclusive),
+/// warped from the grid into `projection` first.
+fn encode_raster(
+    img: DynamicImage,
+    grid: &HeightGrid,
+    rows: (usize, usize),
+    projection: RasterProjection,
+) -> Vec<u8> {
+    let img = match projection {
+        RasterProjection::PlateCarree => img,
+        RasterProjection::WebMercator => {
+            let latitudes = (
+                grid.coordinates_at(rows.0 as f32, 0.0).0,
+                grid.coordinates_at((rows.1 + 1) as f32, 0.0).0,
+            );
+            let mut warped = DynamicImage::new_rgba8(img.width(), img.height());
+            for (y, source) in mercator_rows(latitudes, img.height() as usize)
+                .into_iter()
+                .enumerate()
+            {
+                for x in 0..img.width() {
+                    warped.put_pixel(x, y as u32, img.get_pixel(x, source as u32));
+                }
+            }
+            warped
+        }
+    };
+
+    let mut c = Cursor::new(Vec::new());
+    img.write_to(&mut c, ImageFormat::Png).expect("");
+    c.into_inner()
+}
+
+/// Reachable area of `tile` colored like `agl_png`, with the colors scaled
+/// to the whole cone so neighbouring tiles match.
+fn agl_tile_png(
+    search_from_request_result: &SearchFromRequestResult,
+    tile: TileIndex,
     safety_margin: Option<f32>,
-    start_distance: Option<f32>,
-) -> (ContentType, Vec<u8>) {
-    let search_from_request_result = search_from_request(
-        lat,
-        lon,
-        cell_size,
-        glide_number,
-        additional_height,
-        start_height,
-        wind_speed,
-        wind_direction,
-        trim_speed,
-        safety_margin,
-        start_distance,
+    sun: Option<Sun>,
+    ramp: &ColorRamp,
+) -> Vec<u8> {
+    let style = AglStyle::new(search_from_request_result, safety_margin, sun, ramp, false);
+    let cells = tile_cells(&search_from_request_result.height_grid, tile);
+    let size = TILE_PIXELS as u32;
+    let mut img = DynamicImage::new_rgba8(size, size);
+    for (i, cell) in cells.into_iter().enumerate() {
+        let (x, y) = (i as u32 % size, i as u32 / size);
+        // Pixels of the whole map, so the hatching continues across tiles.
+        let pixel = (tile.x * size + x, tile.y * size + y);
+        if let Some(color) = cell.and_then(|ix| style.color(ix, pixel)) {
+            img.put_pixel(x, y, Rgba(color));
+        }
+    }
+
+    let mut c = Cursor::new(Vec::new());
+    img.write_to(&mut c, ImageFormat::Png).expect("");
+    c.into_inner()
+}
+
+/// Reachable area colored by the height above ground, with the color ramp
+/// given by `colormap`. With `hillshade=true` the colors are shaded by the
+/// terrain, lit by a sun at `sun_azimuth` and `sun_elevation` degrees. With
+/// `landability=true` cells are tinted by how suitable they are for landing.
+#[get("/agl_image?<hillshade>&<sun_azimuth>&<sun_elevation>&<colormap>&<landability>&<projection>&<params..>")]
+fn get_agl_image(
+    params: ConeParams,
+    hillshade: Option<bool>,
+    sun_azimuth: Option<f32>,
+    sun_elevation: Option<f32>,
+    colormap: Option<&str>,
+    landability: Option<bool>,
+    projection: Option<&str>,
+) -> Result<(ContentType, Vec<u8>), (Status, String)> {
+    let ramp = color_ramp_from_request(colormap)?;
+    let projection = projection_from_request(projection)?;
+    let default_sun = Sun::default();
+    let sun = Sun {
+        azimuth: sun_azimuth.unwrap_or(default_sun.azimuth),
+        elevation: sun_elevation.unwrap_or(default_sun.elevation),
+    };
+    if !sun.azimuth.is_finite() || !(0.0..=90.0).contains(&sun.elevation) {
+        return Result::Err((
+            Status::BadRequest,
+            "sun_azimuth must be a number and sun_elevation between 0 and 90 degrees".to_string(),
+        ));
+    }
+    let (lat, lon) = params.start()?;
+    let request = search_request(lat, lon, &params)?;
+    let sun = hillshade.unwrap_or(false).then_some(sun);
+    let landability = landability.unwrap_or(false);
+    let key = (
+        request.key(),
+        RasterLayer::Agl,
+        format!(
+            "{}_{sun:?}_{landability}_{projection:?}",
+            colormap.unwrap_or("default")
+        ),
+    );
+
+    let png = cached_raster(key, || {
+        Ok(agl_png(
+            &run_search_request(request)?,
+            params.safety_margin,
+            sun,
+            &ramp,
+            landability,
+            projection,
+        ))
+    })?;
+    Result::Ok((ContentType::PNG, png))
+}
+
+/// Tile of `/agl_image` in the Web Mercator tile pyramid, as `<y>.png`.
+/// Large cones are served in small parts and stay sharp when zoomed in.
+#[allow(clippy::too_many_arguments)]
+#[get("/agl_tiles/<z>/<x>/<y>?<hillshade>&<sun_azimuth>&<sun_elevation>&<colormap>&<params..>")]
+fn get_agl_tile(
+    z: u8,
+    x: u32,
+    y: &str,
+    params: ConeParams,
+    hillshade: Option<bool>,
+    sun_azimuth: Option<f32>,
+    sun_elevation: Option<f32>,
+    colormap: Option<&str>,
+) -> Result<(ContentType, Vec<u8>), (Status, String)> {
+    let tile = y
+        .strip_suffix(".png")
+        .and_then(|y| y.parse::<u32>().ok())
+        .and_then(|y| TileIndex::new(z, x, y))
+        .ok_or((Status::NotFound, "Unknown tile".to_string()))?;
+    let ramp = color_ramp_from_request(colormap)?;
+    let default_sun = Sun::default();
+    let sun = Sun {
+        azimuth: sun_azimuth.unwrap_or(default_sun.azimuth),
+        elevation: sun_elevation.unwrap_or(default_sun.elevation),
+    };
+    if !sun.azimuth.is_finite() || !(0.0..=90.0).contains(&sun.elevation) {
+        return Result::Err((
+            Status::BadRequest,
+            "sun_azimuth must be a number and sun_elevation between 0 and 90 degrees".to_string(),
+        ));
+    }
+    let (lat, lon) = params.start()?;
+    let request = search_request(lat, lon, &params)?;
+    let sun = hillshade.unwrap_or(false).then_some(sun);
+    let key = (
+        request.key(),
+        RasterLayer::AglTile(tile),
+        format!("{}_{sun:?}", colormap.unwrap_or("default")),
+    );
+
+    let png = cached_raster(key, || {
+        Ok(agl_tile_png(
+            &run_search_request(request)?,
+            tile,
+            params.safety_margin,
+            sun,
+            &ramp,
+        ))
+    })?;
+    Result::Ok((ContentType::PNG, png))
+}
+
+/// Color scale of `colormap` from `hmin` to `hmax` meters with labeled
+/// ticks, e.g. as legend of `/agl_image`.
+#[get("/legend?<hmin>&<hmax>&<colormap>")]
+fn get_legend(
+    hmin: f32,
+    hmax: f32,
+    colormap: Option<&str>,
+) -> Result<(ContentType, Vec<u8>), (Status, String)> {
+    if !(hmin.is_finite() && hmax.is_finite() && hmin < hmax) {
+        return Err((Status::BadRequest, "hmin must be below hmax".to_string()));
+    }
+    let raster = legend(&color_ramp_from_request(colormap)?, hmin, hmax);
+
+    let mut img = DynamicImage::new_rgba8(raster.width as u32, raster.height as u32);
+    for y in 0..raster.height {
+        for x in 0..raster.width {
+            img.put_pixel(x as u32, y as u32, Rgba(raster.pixel(x, y)));
+        }
+    }
+    let mut c = Cursor::new(Vec::new());
+    img.write_to(&mut c, ImageFormat::Png).expect("");
+    Ok((ContentType::PNG, c.into_inner()))
+}
+
+/// Reachable area colored by the height of the glide above sea level, with
+/// the color ramp given by `colormap`.
+#[get("/height_image?<colormap>&<projection>&<params..>")]
+fn get_height_image(
+    params: ConeParams,
+    colormap: Option<&str>,
+    projection: Option<&str>,
+) -> Result<(ContentType, Vec<u8>), (Status, String)> {
+    let ramp = color_ramp_from_request(colormap)?;
+    let projection = projection_from_request(projection)?;
+    let (lat, lon) = params.start()?;
+    let request = search_request(lat, lon, &params)?;
+    let key = (
+        request.key(),
+        RasterLayer::Height,
+        format!("{}_{projection:?}", colormap.unwrap_or("default")),
     );
 
+    let png = cached_raster(key, || {
+        Ok(height_png(run_search_request(request)?, &ramp, projection))
+    })?;
+    Result::Ok((ContentType::PNG, png))
+}
+
+/// Reachable area colored by the height of the glide with `ramp`, cropped
+/// to the reachable cells.
+fn height_png(
+    search_from_request_result: SearchFromRequestResult,
+    ramp: &ColorRamp,
+    projection: RasterProjection,
+) -> Vec<u8> {
+    let safety_margin_ramp = ramp.darkened(SAFETY_MARGIN_DARKENING);
     let heights = search_from_request_result.node_heights;
     let safety_margin = search_from_request_result.in_safety_margin;
+    let grid = &search_from_request_result.height_grid;
 
     let mut imgx = heights.shape()[0];
     let mut imgy = heights.shape()[1];
@@ -837,358 +3707,1914 @@ fn get_height_image(
                 let height = heights_sub[ix];
                 let s = (height - hmin) / (hmax - hmin);
 
-                if safety_margin_sub[ix] {
-                    img.put_pixel(
-                        y as u32,
-                        (imgx - x) as u32 - 1,
-                        Rgba(f32_color_to_u8(lerp(
-                            &SAFETY_MARGIN_LERP_COLORS,
-                            &DEFAULT_LERP_STEPS,
-                            s,
-                        ))),
-                    );
+                let ramp = if safety_margin_sub[ix] {
+                    &safety_margin_ramp
                 } else {
-                    img.put_pixel(
-                        y as u32,
-                        (imgx - x) as u32 - 1,
-                        Rgba(f32_color_to_u8(lerp(
-                            &DEFAULT_LERP_COLORS,
-                            &DEFAULT_LERP_STEPS,
-                            s,
-                        ))),
-                    );
+                    ramp
+                };
+                let mut color = f32_color_to_u8(ramp.color_at(s));
+                if !grid
+                    .data_quality_at((x + x_lower, y + y_lower))
+                    .is_measured()
+                {
+                    color = hatch(color, y as u32, x as u32);
                 }
+                img.put_pixel(y as u32, (imgx - x) as u32 - 1, Rgba(color));
             } else {
                 img.put_pixel(y as u32, (imgx - x) as u32 - 1, Rgba([255, 255, 255, 0]));
             }
         }
     }
 
+    encode_raster(img, grid, (x_lower, x_upper), projection)
+}
+
+#[get("/raw_height_image?<params..>")]
+fn get_raw_height_image(params: ConeParams) -> Result<(ContentType, Vec<u8>), (Status, String)> {
+    let (lat, lon) = params.start()?;
+    let request = search_request(lat, lon, &params)?;
+    let key = (request.key(), RasterLayer::RawHeight, String::new());
+
+    let png = cached_raster(key, || Ok(raw_height_png(run_search_request(request)?)))?;
+    Result::Ok((ContentType::PNG, png))
+}
+
+/// Height above ground of the reachable cells in the red and green
+/// channels and whether they are within the safety margin in the blue one,
+/// cropped to the reachable cells.
+fn raw_height_png(search_from_request_result: SearchFromRequestResult) -> Vec<u8> {
+    let heights = search_from_request_result.heights;
+    let in_safety_margin = search_from_request_result.in_safety_margin;
+
+    let mut imgx = heights.shape()[0];
+    let mut imgy = heights.shape()[1];
+
+    let mut hmin = f32::MAX;
+    let mut hmax = f32::MIN;
+    let mut x_lower = usize::MAX;
+    let mut x_upper = usize::MIN;
+    let mut y_lower = usize::MAX;
+    let mut y_upper = usize::MIN;
+
+    for x in 0..imgx {
+        for y in 0..imgy {
+            if heights[(x, y)] > 0.0 {
+                hmin = hmin.min(heights[(x, y)]);
+                hmax = hmax.max(heights[(x, y)]);
+
+                x_lower = min(x_lower, x);
+                x_upper = max(x_upper, x);
+                y_lower = min(y_lower, y);
+                y_upper = max(y_upper, y);
+            }
+        }
+    }
+
+    imgx = (x_upper - x_lower) + 1;
+    imgy = (y_upper - y_lower) + 1;
+
+    let heights_sub = heights.slice(s![x_lower..(x_upper + 1), y_lower..(y_upper + 1)]);
+    let safety_margin_sub =
+        in_safety_margin.slice(s![x_lower..(x_upper + 1), y_lower..(y_upper + 1)]);
+
+    let mut img = DynamicImage::new_rgb8(imgy as u32, imgx as u32);
+
+    // Iterate over the coordinates and pixels of the image
+    for x in 0..imgx {
+        for y in 0..imgy {
+            let ix = (x, y);
+            if heights_sub[ix] >= 0.0 {
+                let height = heights_sub[ix].round() as i32;
+                let safety_margin = safety_margin_sub[ix];
+                img.put_pixel(
+                    y as u32,
+                    (imgx - x) as u32 - 1,
+                    Rgba([
+                        (height / 256) as u8,
+                        (height % 256) as u8,
+                        if safety_margin { 128 } else { 255 },
+                        255,
+                    ]),
+                );
+            } else {
+                img.put_pixel(y as u32, (imgx - x) as u32 - 1, Rgba([255, 255, 0, 255]));
+            }
+        }
+    }
+
     let mut c = Cursor::new(Vec::new());
     img.write_to(&mut c, ImageFormat::Png).expect("");
-    (ContentType::PNG, c.into_inner())
+    c.into_inner()
+}
+
+fn single_element(name: &str, content: &str, writer: &mut Writer<Cursor<Vec<u8>>>) {
+    start(name, writer);
+    writer
+        .write_event(Event::Text(BytesText::new(content)))
+        .unwrap();
+    end(name, writer);
+}
+
+fn start(name: &str, writer: &mut Writer<Cursor<Vec<u8>>>) {
+    //writer.write_indent().unwrap();
+    writer
+        .write_event(Event::Start(BytesStart::new(name)))
+        .unwrap();
+}
+
+fn end(name: &str, writer: &mut Writer<Cursor<Vec<u8>>>) {
+    //writer.write_indent().unwrap();
+    writer.write_event(Event::End(BytesEnd::new(name))).unwrap();
+}
+
+fn interpolate(node: &Node, px: GridIxType, py: GridIxType, heights: &Array2<f32>) -> f32 {
+    let x = min((node.ix.0 + px) as usize, heights.shape()[0] - 1);
+    let y = min((node.ix.1 + py) as usize, heights.shape()[1] - 1);
+    if heights[(x, y)] > -1000.0 {
+        heights[(x, y)]
+    } else {
+        node.height
+    }
+}
+
+const KML_AGL_BANDS: usize = 5;
+const KML_RING_SPACING: f32 = 5000.0;
+/// Tilt of the camera looking at the start, in degrees from straight down.
+const KML_LOOK_AT_TILT: f32 = 45.0;
+/// Smallest distance of the camera from the start, for tiny cones.
+const KML_LOOK_AT_MIN_RANGE: f32 = 2000.0;
+
+/// Outline of the reachable area, with the unreachable cells inside of it as
+/// inner boundaries.
+fn write_outline(
+    polygons: &[RegionPolygon],
+    height_grid: &HeightGrid,
+    writer: &mut Writer<Cursor<Vec<u8>>>,
+) {
+    let ring = |name: &str, corners: &[Corner], writer: &mut Writer<Cursor<Vec<u8>>>| {
+        start(name, writer);
+        start("LinearRing", writer);
+        let coordinates = corners
+            .iter()
+            .map(|corner| {
+                let (lat, lon) = corner_coordinates(*corner, height_grid);
+                format!("{},{}", lon, lat)
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        single_element("coordinates", coordinates.as_str(), writer);
+        end("LinearRing", writer);
+        end(name, writer);
+    };
+
+    start("Folder", writer);
+    single_element("name", "Outline", writer);
+    for polygon in polygons {
+        start("Placemark", writer);
+        start("Style", writer);
+        start("LineStyle", writer);
+        single_element("color", "ff000000", writer);
+        single_element("width", "2", writer);
+        end("LineStyle", writer);
+        start("PolyStyle", writer);
+        single_element("fill", "0", writer);
+        end("PolyStyle", writer);
+        end("Style", writer);
+        start("Polygon", writer);
+        single_element("tessellate", "1", writer);
+        single_element("altitudeMode", "clampToGround", writer);
+        ring("outerBoundaryIs", &polygon.outer, writer);
+        for hole in &polygon.holes {
+            ring("innerBoundaryIs", hole, writer);
+        }
+        end("Polygon", writer);
+        end("Placemark", writer);
+    }
+    end("Folder", writer);
+}
+
+/// Scheme and host the request was sent to, e.g. `https://example.com`, so
+/// documents can link back to the server.
+struct BaseUrl(String);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for BaseUrl {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> rocket::request::Outcome<Self, ()> {
+        let config = request.rocket().config();
+        let scheme =
+            request
+                .headers()
+                .get_one("X-Forwarded-Proto")
+                .unwrap_or(if config.tls_enabled() {
+                    "https"
+                } else {
+                    "http"
+                });
+        let host = request
+            .host()
+            .map(|host| host.to_string())
+            .unwrap_or_else(|| format!("{}:{}", config.address, config.port));
+        rocket::request::Outcome::Success(BaseUrl(format!("{scheme}://{host}")))
+    }
+}
+
+/// Camera looking at the start from the south, far enough away to see the
+/// whole reachable area.
+fn write_look_at(lat: f32, lon: f32, max_distance: f32, writer: &mut Writer<Cursor<Vec<u8>>>) {
+    start("LookAt", writer);
+    single_element("longitude", lon.to_string().as_str(), writer);
+    single_element("latitude", lat.to_string().as_str(), writer);
+    single_element("altitude", "0", writer);
+    single_element("heading", "0", writer);
+    single_element("tilt", KML_LOOK_AT_TILT.to_string().as_str(), writer);
+    single_element(
+        "range",
+        (3.0 * max_distance)
+            .max(KML_LOOK_AT_MIN_RANGE)
+            .round()
+            .to_string()
+            .as_str(),
+        writer,
+    );
+    single_element("altitudeMode", "relativeToGround", writer);
+    end("LookAt", writer);
+}
+
+/// One image of the whole reachable area, stretched over the bounds of the
+/// grid like the overlay of the frontend.
+fn write_ground_overlay(
+    height_grid: &HeightGrid,
+    href: &str,
+    writer: &mut Writer<Cursor<Vec<u8>>>,
+) {
+    start("GroundOverlay", writer);
+    single_element("name", "Height above ground", writer);
+    start("Icon", writer);
+    single_element("href", href, writer);
+    end("Icon", writer);
+    start("LatLonBox", writer);
+    single_element(
+        "north",
+        height_grid.latitudes.1.to_string().as_str(),
+        writer,
+    );
+    single_element(
+        "south",
+        height_grid.latitudes.0.to_string().as_str(),
+        writer,
+    );
+    single_element(
+        "east",
+        height_grid.longitudes.1.to_string().as_str(),
+        writer,
+    );
+    single_element(
+        "west",
+        height_grid.longitudes.0.to_string().as_str(),
+        writer,
+    );
+    end("LatLonBox", writer);
+    end("GroundOverlay", writer);
+}
+
+fn write_cell_placemark(
+    node: &Node,
+    height_grid: &HeightGrid,
+    heights: &Array2<f32>,
+    node_heights: &Array2<f32>,
+    (hmin, hmax): (f32, f32),
+    ramp: &ColorRamp,
+    writer: &mut Writer<Cursor<Vec<u8>>>,
+) {
+    // `longitude,latitude` of a corner of the cell.
+    let corner = |d_row: f32, d_col: f32| {
+        let (lat, lon) =
+            height_grid.coordinates_at(node.ix.0 as f32 + d_row, node.ix.1 as f32 + d_col);
+        format!("{lon},{lat}")
+    };
+
+    let agl = heights[(node.ix.0 as usize, node.ix.1 as usize)];
+    let s = ((agl - hmin) / (hmax - hmin)).clamp(0.0, 1.0);
+    let color = f32_color_to_u8(ramp.color_at(s));
+
+    let color_string = format!(
+        "{:02x}{:02x}{:02x}{:02x}",
+        150u8, color[2], color[1], color[0],
+    );
+
+    start("Placemark", writer);
+
+    start("Style", writer);
+    start("LineStyle", writer);
+
+    single_element("color", color_string.as_str(), writer); //TODO: Fix color
+    end("LineStyle", writer);
+    start("PolyStyle", writer);
+    single_element("color", color_string.as_str(), writer);
+    single_element("outline", "1", writer);
+    end("PolyStyle", writer);
+    end("Style", writer);
+
+    start("Polygon", writer);
+    single_element("altitudeMode", "absolute", writer);
+    start("outerBoundaryIs", writer);
+    start("LinearRing", writer);
+    start("coordinates", writer);
+
+    writer.write_indent().unwrap();
+    writer
+        .write_event(Event::Text(BytesText::new(
+            format!("{},{}", corner(-0.5, -0.5), node.height).as_str(),
+        )))
+        .unwrap();
+    writer.write_indent().unwrap();
+    writer
+        .write_event(Event::Text(BytesText::new(
+            format!(
+                "{},{}",
+                corner(0.5, -0.5),
+                interpolate(node, 1, 0, node_heights)
+            )
+            .as_str(),
+        )))
+        .unwrap();
+    writer.write_indent().unwrap();
+    writer
+        .write_event(Event::Text(BytesText::new(
+            format!(
+                "{},{}",
+                corner(0.5, 0.5),
+                interpolate(node, 1, 1, node_heights)
+            )
+            .as_str(),
+        )))
+        .unwrap();
+    writer.write_indent().unwrap();
+    writer
+        .write_event(Event::Text(BytesText::new(
+            format!(
+                "{},{}",
+                corner(-0.5, 0.5),
+                interpolate(node, 0, 1, node_heights)
+            )
+            .as_str(),
+        )))
+        .unwrap();
+    writer.write_indent().unwrap();
+    writer
+        .write_event(Event::Text(BytesText::new(
+            format!("{},{}", corner(-0.5, -0.5), node.height).as_str(),
+        )))
+        .unwrap();
+    writer.write_indent().unwrap();
+
+    end("coordinates", writer);
+    end("LinearRing", writer);
+    end("outerBoundaryIs", writer);
+    end("Polygon", writer);
+
+    end("Placemark", writer);
+}
+
+/// Provenance of the heights as `ExtendedData` of the document, so it stays
+/// with the file when it is passed on.
+fn write_provenance(provenance: &Provenance, writer: &mut Writer<Cursor<Vec<u8>>>) {
+    start("ExtendedData", writer);
+    for (name, value) in [
+        ("source", format!("{:?}", provenance.source)),
+        ("dataset", provenance.dataset.clone()),
+        ("release_date", provenance.release_date.clone()),
+        ("license", provenance.license.clone()),
+        ("attribution", provenance.attribution.clone()),
+    ] {
+        let mut data = BytesStart::new("Data");
+        data.push_attribute(("name", name));
+        writer.write_event(Event::Start(data)).unwrap();
+        single_element("value", value.as_str(), writer);
+        end("Data", writer);
+    }
+    end("ExtendedData", writer);
 }
 
+/// KML document with the reachable area grouped by height above ground and
+/// distance rings around the start. With an `overlay_href` the area is a
+/// single ground overlay of that image instead of one polygon per cell.
 #[allow(clippy::too_many_arguments)]
-#[get("/raw_height_image?<lat>&<lon>&<cell_size>&<glide_number>&<additional_height>&<start_height>&<wind_speed>&<wind_direction>&<trim_speed>&<safety_margin>&<start_distance>")]
-fn get_raw_height_image(
-    lat: f32,
-    lon: f32,
-    cell_size: Option<f32>,
+fn kml_document(
+    search_from_request_result: &SearchFromRequestResult,
     glide_number: Option<f32>,
-    additional_height: Option<f32>,
-    start_height: Option<f32>,
+    trim_speed: Option<f32>,
     wind_speed: Option<f32>,
     wind_direction: Option<f32>,
-    trim_speed: Option<f32>,
     safety_margin: Option<f32>,
-    start_distance: Option<f32>,
-) -> (ContentType, Vec<u8>) {
-    let search_from_request_result = search_from_request(
-        lat,
-        lon,
-        cell_size,
-        glide_number,
-        additional_height,
+    overlay_href: Option<&str>,
+) -> Vec<u8> {
+    let heights = &search_from_request_result.heights;
+    let node_heights = &search_from_request_result.node_heights;
+    let height_grid = &search_from_request_result.height_grid;
+    let nodes = &search_from_request_result.explored;
+    let stats = cone_stats(
+        nodes,
+        height_grid,
+        Some(&search_from_request_result.landing_prohibited),
+    );
+    let ramp = ColorRamp::default();
+
+    let start_ix = search_from_request_result.start_ix;
+    let (start_lat, start_lon) =
+        height_grid.cell_center((start_ix.0 as usize, start_ix.1 as usize));
+
+    let cursor = Cursor::new(Vec::new());
+    let mut writer = Writer::new_with_indent(cursor, b' ', 4);
+
+    let mut elem = BytesStart::new("kml");
+    elem.push_attribute(("xmlns", "http://www.opengis.net/kml/2.2"));
+    writer.write_event(Event::Start(elem)).unwrap();
+    start("Document", &mut writer);
+    single_element("name", "Glide area", &mut writer);
+    write_provenance(&grid_provenance(height_grid), &mut writer);
+    write_look_at(start_lat, start_lon, stats.max_distance, &mut writer);
+
+    start("Placemark", &mut writer);
+    single_element("name", "Start", &mut writer);
+    single_element(
+        "description",
+        format!(
+            "Glide number: {}\nTrim speed: {} km/h\nWind: {} km/h from {}°\nStart height: {} m\n\
+             Safety margin: {} m\nCell size: {} m\nReachable area: {:.1} km²\n\
+             Max. distance: {:.1} km\nLanding prohibited: {:.1} km²\nDEM: {}\n\
+             Algorithm version: {}",
+            glide_number.unwrap_or(GLIDE_NUMBER_DEFAULT),
+            trim_speed.unwrap_or(TRIM_SPEED_DEFAULT),
+            wind_speed.unwrap_or(WIND_SPEED_DEFAULT),
+            wind_direction.unwrap_or(WIND_DIRECTION_DEFAULT),
+            search_from_request_result.height_at_start,
+            safety_margin.unwrap_or(SAFETY_MARGIN_DEFAULT),
+            height_grid.cell_size,
+            stats.area_km2,
+            stats.max_distance / 1000.0,
+            stats.prohibited_cells as f32 * height_grid.cell_size * height_grid.cell_size / 1e6,
+            current_dem_version(),
+            ALGORITHM_VERSION,
+        )
+        .as_str(),
+        &mut writer,
+    );
+    start("Point", &mut writer);
+    single_element(
+        "coordinates",
+        format!("{},{}", start_lon, start_lat).as_str(),
+        &mut writer,
+    );
+    end("Point", &mut writer);
+    end("Placemark", &mut writer);
+
+    start("Folder", &mut writer);
+    single_element("name", "Reachable area", &mut writer);
+    if let Some(href) = overlay_href {
+        write_ground_overlay(height_grid, href, &mut writer);
+    } else {
+        // Group cells by height above ground, so they can be toggled per
+        // band in the sidebar instead of being one flat list.
+        let bands = agl_bands(stats.min_agl, stats.max_agl, KML_AGL_BANDS);
+        let mut nodes_per_band = vec![vec![]; bands.len()];
+        for node in nodes.iter().filter(|n| n.reachable) {
+            nodes_per_band[agl_band_of(&bands, agl_of(node, height_grid))].push(node);
+        }
+
+        for (band, band_nodes) in bands.iter().zip(nodes_per_band.iter()).rev() {
+            start("Folder", &mut writer);
+            single_element(
+                "name",
+                format!("{:.0} - {:.0} m AGL", band.min, band.max).as_str(),
+                &mut writer,
+            );
+            for node in band_nodes {
+                write_cell_placemark(
+                    node,
+                    height_grid,
+                    heights,
+                    node_heights,
+                    (stats.min_agl, stats.max_agl),
+                    &ramp,
+                    &mut writer,
+                );
+            }
+            end("Folder", &mut writer);
+        }
+    }
+    end("Folder", &mut writer);
+
+    let polygons = region_polygons(
+        nodes,
+        (
+            height_grid.heights.shape()[0],
+            height_grid.heights.shape()[1],
+        ),
+    );
+    write_outline(&polygons, height_grid, &mut writer);
+
+    start("Folder", &mut writer);
+    single_element("name", "Distance rings", &mut writer);
+    for radius in ring_radii(stats.max_distance, KML_RING_SPACING) {
+        start("Placemark", &mut writer);
+        single_element(
+            "name",
+            format!("{:.0} km", radius / 1000.0).as_str(),
+            &mut writer,
+        );
+        start("LineString", &mut writer);
+        single_element("tessellate", "1", &mut writer);
+        single_element("altitudeMode", "clampToGround", &mut writer);
+        let coordinates = distance_ring(start_lat, start_lon, radius, 72)
+            .iter()
+            .map(|(lon, lat)| format!("{},{}", lon, lat))
+            .collect::<Vec<_>>()
+            .join(" ");
+        single_element("coordinates", coordinates.as_str(), &mut writer);
+        end("LineString", &mut writer);
+        end("Placemark", &mut writer);
+    }
+    end("Folder", &mut writer);
+
+    end("Document", &mut writer);
+    end("kml", &mut writer);
+
+    writer.into_inner().into_inner()
+}
+
+#[get("/kml?<ground_overlay>&<params..>")]
+fn get_kml(
+    params: ConeParams,
+    ground_overlay: Option<bool>,
+    uri: &Origin<'_>,
+    base_url: BaseUrl,
+) -> Result<(ContentType, Vec<u8>), (Status, String)> {
+    let (lat, lon) = params.start()?;
+    let search_from_request_result = search_from_request(lat, lon, &params)?;
+
+    // The image takes the same parameters as the KML.
+    let overlay_href = ground_overlay.unwrap_or(false).then(|| {
+        format!(
+            "{}/agl_image?{}",
+            base_url.0,
+            uri.query().map(|query| query.as_str()).unwrap_or("")
+        )
+    });
+
+    Result::Ok((
+        ContentType::XML,
+        kml_document(
+            &search_from_request_result,
+            params.glide_number(),
+            params.trim_speed,
+            params.wind_speed,
+            params.wind_direction,
+            params.safety_margin,
+            overlay_href.as_deref(),
+        ),
+    ))
+}
+
+/// KML with the reachable area as a ground overlay, packed together with
+/// the image so it opens without the server.
+#[derive(Responder)]
+#[response(content_type = "application/vnd.google-earth.kmz")]
+struct KmzResponse {
+    body: Vec<u8>,
+    disposition: Header<'static>,
+}
+
+/// Name of a KMZ download, e.g. `glide_area_47.4211N_10.9863E_gn8_sm0.kmz`.
+fn kmz_file_name(
+    lat: f32,
+    lon: f32,
+    glide_number: Option<f32>,
+    safety_margin: Option<f32>,
+) -> String {
+    format!(
+        "glide_area_{:.4}{}_{:.4}{}_gn{}_sm{}.kmz",
+        lat.abs(),
+        if lat < 0.0 { "S" } else { "N" },
+        lon.abs(),
+        if lon < 0.0 { "W" } else { "E" },
+        glide_number.unwrap_or(GLIDE_NUMBER_DEFAULT),
+        safety_margin.unwrap_or(SAFETY_MARGIN_DEFAULT),
+    )
+}
+
+#[get("/kmz?<params..>")]
+fn get_kmz(params: ConeParams) -> Result<KmzResponse, (Status, String)> {
+    let (lat, lon) = params.start()?;
+    let search_from_request_result = search_from_request(lat, lon, &params)?;
+
+    // Google Earth opens the first KML file of the archive.
+    let files = [
+        (
+            "doc.kml".to_string(),
+            kml_document(
+                &search_from_request_result,
+                params.glide_number(),
+                params.trim_speed,
+                params.wind_speed,
+                params.wind_direction,
+                params.safety_margin,
+                Some("files/agl.png"),
+            ),
+        ),
+        (
+            "files/agl.png".to_string(),
+            agl_png(
+                &search_from_request_result,
+                params.safety_margin,
+                None,
+                &ColorRamp::default(),
+                false,
+                RasterProjection::PlateCarree,
+            ),
+        ),
+    ];
+
+    Result::Ok(KmzResponse {
+        body: zip_stored(&files),
+        disposition: Header::new(
+            "Content-Disposition",
+            format!(
+                "attachment; filename=\"{}\"",
+                kmz_file_name(lat, lon, params.glide_number, params.safety_margin)
+            ),
+        ),
+    })
+}
+
+/// GPX track along the outer boundary of the reachable area around the start.
+fn gpx_document(search_from_request_result: &SearchFromRequestResult) -> Vec<u8> {
+    let height_grid = &search_from_request_result.height_grid;
+    let shape = (
+        height_grid.heights.shape()[0],
+        height_grid.heights.shape()[1],
+    );
+    let component = reachable_component(
+        &search_from_request_result.explored,
+        shape,
+        search_from_request_result.start_ix,
+    );
+    let boundary = outer_boundary(&component, &search_from_request_result.node_heights);
+
+    let cursor = Cursor::new(Vec::new());
+    let mut writer = Writer::new_with_indent(cursor, b' ', 4);
+
+    let mut elem = BytesStart::new("gpx");
+    elem.push_attribute(("xmlns", "http://www.topografix.com/GPX/1/1"));
+    elem.push_attribute(("version", "1.1"));
+    elem.push_attribute(("creator", "hikeandfly"));
+    writer.write_event(Event::Start(elem)).unwrap();
+    let provenance = grid_provenance(height_grid);
+    start("metadata", &mut writer);
+    single_element(
+        "desc",
+        format!(
+            "Heights: {} ({}), {}",
+            provenance.dataset, provenance.license, provenance.attribution
+        )
+        .as_str(),
+        &mut writer,
+    );
+    end("metadata", &mut writer);
+    start("trk", &mut writer);
+    single_element("name", "Glide area", &mut writer);
+    start("trkseg", &mut writer);
+    for point in boundary.iter() {
+        let (point_lat, point_lon) = corner_coordinates(point.corner, height_grid);
+        let mut trkpt = BytesStart::new("trkpt");
+        trkpt.push_attribute(("lat", format!("{point_lat:.6}").as_str()));
+        trkpt.push_attribute(("lon", format!("{point_lon:.6}").as_str()));
+        writer.write_event(Event::Start(trkpt)).unwrap();
+        single_element("ele", format!("{:.0}", point.height).as_str(), &mut writer);
+        end("trkpt", &mut writer);
+    }
+    end("trkseg", &mut writer);
+    end("trk", &mut writer);
+    end("gpx", &mut writer);
+
+    writer.into_inner().into_inner()
+}
+
+/// Outer boundary of the reachable area around the start as a GPX track, with
+/// the height at which the boundary is reached as elevation, for loading the
+/// glide range onto flight instruments.
+#[get("/flight_cone_gpx?<params..>")]
+fn get_flight_cone_gpx(params: ConeParams) -> Result<(ContentType, Vec<u8>), (Status, String)> {
+    let (lat, lon) = params.start()?;
+    if select_data_source(lat, lon, params.cell_size.unwrap_or(CELL_SIZE_DEFAULT)).is_none() {
+        return Result::Err(no_height_data());
+    }
+
+    let search_from_request_result = search_from_request(lat, lon, &params)?;
+
+    Result::Ok((
+        ContentType::new("application", "gpx+xml"),
+        gpx_document(&search_from_request_result),
+    ))
+}
+
+/// Reachable area as a GeoJSON FeatureCollection with one polygon per cell,
+/// for use in web maps or GIS tools.
+#[get("/flight_cone_geojson?<params..>")]
+fn get_flight_cone_geojson(
+    params: ConeParams,
+) -> Result<Json<FeatureCollection<CellProperties>>, (Status, String)> {
+    let (lat, lon) = params.start()?;
+    if select_data_source(lat, lon, params.cell_size.unwrap_or(CELL_SIZE_DEFAULT)).is_none() {
+        return Result::Err(no_height_data());
+    }
+
+    let search_from_request_result = search_from_request(lat, lon, &params)?;
+
+    Result::Ok(Json(
+        reachable_area(
+            &search_from_request_result.explored,
+            &search_from_request_result.height_grid,
+        )
+        .with_metadata(grid_provenance(&search_from_request_result.height_grid)),
+    ))
+}
+
+/// Outline of the reachable area as a GeoJSON multi polygon, with the
+/// unreachable cells inside of it as holes.
+#[get("/flight_cone_outline?<params..>")]
+fn get_flight_cone_outline(
+    params: ConeParams,
+) -> Result<Json<FeatureCollection<OutlineProperties>>, (Status, String)> {
+    let (lat, lon) = params.start()?;
+    if select_data_source(lat, lon, params.cell_size.unwrap_or(CELL_SIZE_DEFAULT)).is_none() {
+        return Result::Err(no_height_data());
+    }
+
+    let search_from_request_result = search_from_request(lat, lon, &params)?;
+
+    let explored = &search_from_request_result.explored;
+    let grid = &search_from_request_result.height_grid;
+    let polygons = region_polygons(explored, (grid.heights.shape()[0], grid.heights.shape()[1]));
+    Result::Ok(Json(
+        reachable_outline(&polygons, grid, cone_stats(explored, grid, None).area_km2)
+            .with_metadata(grid_provenance(grid)),
+    ))
+}
+
+/// Reachable surface as a Cesium CZML document, one polygon per cell at the
+/// height it is reached at, for inspecting the cone in 3D globe viewers.
+#[get("/czml?<colormap>&<params..>")]
+fn get_czml(
+    params: ConeParams,
+    colormap: Option<&str>,
+) -> Result<Json<Vec<CzmlPacket>>, (Status, String)> {
+    let ramp = color_ramp_from_request(colormap)?;
+    let (lat, lon) = params.start()?;
+    if select_data_source(lat, lon, params.cell_size.unwrap_or(CELL_SIZE_DEFAULT)).is_none() {
+        return Result::Err(no_height_data());
+    }
+
+    let search_from_request_result = search_from_request(lat, lon, &params)?;
+
+    Result::Ok(Json(cone_czml(
+        &search_from_request_result.explored,
+        &search_from_request_result.height_grid,
+        (lat, lon, search_from_request_result.height_at_start),
+        &ramp,
+    )))
+}
+
+/// Default spacing of the lines of `get_agl_contours` in meters.
+const CONTOUR_STEP_DEFAULT: f32 = 100.0;
+/// Smallest spacing of the lines of `get_agl_contours`, smaller steps are
+/// below the precision of the search.
+const CONTOUR_STEP_MIN: f32 = 10.0;
+
+/// Lines of equal height above ground in the reachable area every `step`
+/// meters, as a GeoJSON FeatureCollection with one MultiLineString per level.
+#[get("/agl_contours?<step>&<params..>")]
+fn get_agl_contours(
+    step: Option<f32>,
+    params: ConeParams,
+) -> Result<Json<FeatureCollection<ContourProperties>>, (Status, String)> {
+    let step = step.unwrap_or(CONTOUR_STEP_DEFAULT);
+    if !step.is_finite() || step < CONTOUR_STEP_MIN {
+        return Result::Err((
+            Status::BadRequest,
+            format!("Contour step must be at least {CONTOUR_STEP_MIN} m"),
+        ));
+    }
+    let (lat, lon) = params.start()?;
+    if select_data_source(lat, lon, params.cell_size.unwrap_or(CELL_SIZE_DEFAULT)).is_none() {
+        return Result::Err(no_height_data());
+    }
+
+    let search_from_request_result = search_from_request(lat, lon, &params)?;
+
+    Result::Ok(Json(
+        agl_contours(
+            &search_from_request_result.heights,
+            &search_from_request_result.height_grid,
+            step,
+        )
+        .with_metadata(grid_provenance(&search_from_request_result.height_grid)),
+    ))
+}
+
+/// The reachable area as a Mapbox Vector Tile, see `cone_tile`. `y` is
+/// given with the `.pbf` extension, as map libraries request it.
+#[get("/flight_cone_tiles/<z>/<x>/<y>?<params..>")]
+fn get_flight_cone_tile(
+    z: u8,
+    x: u32,
+    y: &str,
+    params: ConeParams,
+) -> Result<(ContentType, Vec<u8>), (Status, String)> {
+    let tile = y
+        .strip_suffix(".pbf")
+        .and_then(|y| y.parse::<u32>().ok())
+        .and_then(|y| TileIndex::new(z, x, y))
+        .ok_or((Status::NotFound, "Unknown tile".to_string()))?;
+    let (lat, lon) = params.start()?;
+    if select_data_source(lat, lon, params.cell_size.unwrap_or(CELL_SIZE_DEFAULT)).is_none() {
+        return Result::Err(no_height_data());
+    }
+
+    let search_from_request_result = search_from_request(lat, lon, &params)?;
+
+    Result::Ok((
+        ContentType::new("application", "vnd.mapbox-vector-tile"),
+        cone_tile(
+            &search_from_request_result.explored,
+            &search_from_request_result.height_grid,
+            tile,
+        ),
+    ))
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum WaypointFormat {
+    Cup,
+    Wpt,
+}
+
+impl FromStr for WaypointFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cup" => Ok(WaypointFormat::Cup),
+            "wpt" => Ok(WaypointFormat::Wpt),
+            _ => Err(format!("Unknown waypoint format {s}")),
+        }
+    }
+}
+
+/// Landing zones of a search as waypoints, numbered from the highest arrival.
+fn landing_waypoints(result: &SearchFromRequestResult) -> Vec<Waypoint> {
+    detect_landing_zones(
+        &result.explored,
+        &result.height_grid,
+        Some(&result.landing_prohibited),
+    )
+    .iter()
+    .enumerate()
+    .map(|(number, zone)| Waypoint {
+        name: format!("LZ{:02}", number + 1),
+        latitude: zone.latitude,
+        longitude: zone.longitude,
+        elevation: zone.elevation,
+        description: format!(
+            "Arrival {:.0} m AGL, {:.1} ha",
+            zone.arrival_agl,
+            zone.area_m2 / 10_000.0
+        ),
+    })
+    .collect()
+}
+
+/// Flat, reachable landing zones as SeeYou `.cup` (default) or OziExplorer
+/// `.wpt` waypoints, for loading the reachable landings onto flight
+/// instruments.
+#[get("/landing_zones?<format>&<params..>")]
+fn get_landing_zones(
+    format: Option<&str>,
+    params: ConeParams,
+) -> Result<(ContentType, Vec<u8>), (Status, String)> {
+    let format = format
+        .unwrap_or("cup")
+        .parse::<WaypointFormat>()
+        .map_err(|e| (Status::BadRequest, e))?;
+    let (lat, lon) = params.start()?;
+    if select_data_source(lat, lon, params.cell_size.unwrap_or(CELL_SIZE_DEFAULT)).is_none() {
+        return Result::Err(no_height_data());
+    }
+
+    let search_from_request_result = search_from_request(lat, lon, &params)?;
+
+    let waypoints = landing_waypoints(&search_from_request_result);
+    Result::Ok(match format {
+        WaypointFormat::Cup => (
+            ContentType::new("application", "x-seeyou-cup"),
+            format_cup(&waypoints).into_bytes(),
+        ),
+        WaypointFormat::Wpt => (
+            ContentType::new("application", "x-ozi-wpt"),
+            format_wpt(&waypoints).into_bytes(),
+        ),
+    })
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ExportFormat {
+    Kml,
+    GeoJson,
+    Gpx,
+    Png,
+    Stats,
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "kml" => Ok(ExportFormat::Kml),
+            "geojson" => Ok(ExportFormat::GeoJson),
+            "gpx" => Ok(ExportFormat::Gpx),
+            "png" => Ok(ExportFormat::Png),
+            "stats" => Ok(ExportFormat::Stats),
+            _ => Err(format!("Unknown export format {s}")),
+        }
+    }
+}
+
+/// Metadata of an export, so archived cones can be told apart later.
+#[derive(Serialize)]
+struct ExportStats {
+    start_location: (f32, f32),
+    start_height: f32,
+    cell_size: f32,
+    dem_version: DemVersion,
+    provenance: Provenance,
+    algorithm_version: u32,
+    #[serde(flatten)]
+    stats: ConeStats,
+}
+
+/// The requested `formats` (comma separated, all by default) of one search
+/// in a zip archive, so a planning session is archived without searching once
+/// per format.
+#[get("/export.zip?<formats>&<params..>")]
+fn get_export_zip(
+    formats: Option<&str>,
+    params: ConeParams,
+) -> Result<(ContentType, Vec<u8>), (Status, String)> {
+    let mut export_formats = vec![];
+    for format in formats
+        .unwrap_or("kml,geojson,gpx,png,stats")
+        .split(',')
+        .map(str::trim)
+        .filter(|f| !f.is_empty())
+    {
+        let format = format
+            .parse::<ExportFormat>()
+            .map_err(|e| (Status::BadRequest, e))?;
+        if !export_formats.contains(&format) {
+            export_formats.push(format);
+        }
+    }
+    if export_formats.is_empty() {
+        return Result::Err((Status::BadRequest, "No export formats given".to_string()));
+    }
+
+    let (lat, lon) = params.start()?;
+    if select_data_source(lat, lon, params.cell_size.unwrap_or(CELL_SIZE_DEFAULT)).is_none() {
+        return Result::Err(no_height_data());
+    }
+
+    let search_from_request_result = search_from_request(lat, lon, &params)?;
+
+    let grid = &search_from_request_result.height_grid;
+    let files = export_formats
+        .into_iter()
+        .map(|format| match format {
+            ExportFormat::Kml => (
+                "glide_area.kml".to_string(),
+                kml_document(
+                    &search_from_request_result,
+                    params.glide_number(),
+                    params.trim_speed,
+                    params.wind_speed,
+                    params.wind_direction,
+                    params.safety_margin,
+                    None,
+                ),
+            ),
+            ExportFormat::GeoJson => (
+                "glide_area.geojson".to_string(),
+                serde_json::to_vec(
+                    &reachable_area(&search_from_request_result.explored, grid)
+                        .with_metadata(grid_provenance(grid)),
+                )
+                .unwrap(),
+            ),
+            ExportFormat::Gpx => (
+                "glide_area.gpx".to_string(),
+                gpx_document(&search_from_request_result),
+            ),
+            ExportFormat::Png => (
+                "glide_area.png".to_string(),
+                agl_png(
+                    &search_from_request_result,
+                    params.safety_margin,
+                    None,
+                    &ColorRamp::default(),
+                    false,
+                    RasterProjection::PlateCarree,
+                ),
+            ),
+            ExportFormat::Stats => (
+                "stats.json".to_string(),
+                serde_json::to_vec_pretty(&ExportStats {
+                    start_location: (lat, lon),
+                    start_height: search_from_request_result.height_at_start,
+                    cell_size: grid.cell_size,
+                    dem_version: current_dem_version(),
+                    provenance: grid_provenance(grid),
+                    algorithm_version: ALGORITHM_VERSION,
+                    stats: cone_stats(
+                        &search_from_request_result.explored,
+                        grid,
+                        Some(&search_from_request_result.landing_prohibited),
+                    ),
+                })
+                .unwrap(),
+            ),
+        })
+        .collect::<Vec<_>>();
+
+    Result::Ok((ContentType::ZIP, zip_stored(&files)))
+}
+
+#[derive(Serialize)]
+struct RegionSplitResponse {
+    /// Reachable cells with their center inside the region.
+    inside: FeatureCollection<CellProperties>,
+    /// Reachable cells with their center outside of the region.
+    outside: FeatureCollection<CellProperties>,
+    /// `[longitude, latitude]` points where the outer boundary of the cone
+    /// crosses the boundary of the region.
+    crossings: Vec<[f32; 2]>,
+}
+
+/// Splits the reachable area at the boundary of a region given as GeoJSON in
+/// the request body, e.g. a national park in which landing is prohibited.
+#[post("/flight_cone_region?<params..>", format = "json", data = "<region>")]
+fn post_flight_cone_region(
+    params: ConeParams,
+    region: Json<RegionGeoJson>,
+) -> Result<Json<RegionSplitResponse>, (Status, String)> {
+    let (lat, lon) = params.start()?;
+    let region =
+        Region::from_geojson(region.into_inner()).map_err(|e| (Status::UnprocessableEntity, e))?;
+    if select_data_source(lat, lon, params.cell_size.unwrap_or(CELL_SIZE_DEFAULT)).is_none() {
+        return Result::Err(no_height_data());
+    }
+
+    let search_from_request_result = search_from_request(lat, lon, &params)?;
+
+    let grid = &search_from_request_result.height_grid;
+    let component = reachable_component(
+        &search_from_request_result.explored,
+        (grid.heights.shape()[0], grid.heights.shape()[1]),
+        search_from_request_result.start_ix,
+    );
+    let cone_boundary = outer_boundary(&component, &search_from_request_result.node_heights)
+        .iter()
+        .map(|point| {
+            let (point_lat, point_lon) = corner_coordinates(point.corner, grid);
+            [point_lon, point_lat]
+        })
+        .collect::<Vec<_>>();
+    let (inside, outside) = split_by_region(&search_from_request_result.explored, grid, &region);
+
+    Result::Ok(Json(RegionSplitResponse {
+        inside: reachable_area(&inside, grid),
+        outside: reachable_area(&outside, grid),
+        crossings: boundary_crossings(&cone_boundary, &region),
+    }))
+}
+
+/// Glide path from the start to the cell at `target_lat`/`target_lon`, with
+/// the height and AGL at every vertex.
+#[get("/flight_path?<target_lat>&<target_lon>&<params..>")]
+fn get_flight_path(
+    params: ConeParams,
+    target_lat: f32,
+    target_lon: f32,
+) -> Result<Json<Vec<PathVertex>>, (Status, String)> {
+    let (lat, lon) = params.start()?;
+    if select_data_source(lat, lon, params.cell_size.unwrap_or(CELL_SIZE_DEFAULT)).is_none() {
+        return Result::Err(no_height_data());
+    }
+
+    let search_from_request_result = search_from_request(lat, lon, &params)?;
+
+    let grid = &search_from_request_result.height_grid;
+    grid.index_of(target_lat, target_lon)
+        .and_then(|(row, col)| {
+            glide_path(
+                &search_from_request_result.explored,
+                grid,
+                (row as GridIxType, col as GridIxType),
+            )
+        })
+        .map(Json)
+        .ok_or((
+            Status::NotFound,
+            "The target can not be reached from the start".to_string(),
+        ))
+}
+
+/// Hike and fly route between two points, as flights and hikes in turn. The
+/// flights use the flight parameters of `/kml`, `max_hike` limits every hike
+/// from a landing to the next launch in meters.
+#[get("/plan_route?<from>&<to>&<max_hike>&<params..>")]
+fn get_plan_route(
+    from: &str,
+    to: &str,
+    max_hike: Option<f32>,
+    params: ConeParams,
+) -> Result<Json<PlannedRoute>, (Status, String)> {
+    let from =
+        parse_coordinates(from).map_err(|e| (Status::BadRequest, format!("Invalid from: {e}")))?;
+    let to = parse_coordinates(to).map_err(|e| (Status::BadRequest, format!("Invalid to: {e}")))?;
+    let max_hike = max_hike.unwrap_or(MAX_HIKE_DEFAULT);
+    if !max_hike.is_finite() || max_hike < 0.0 {
+        return Result::Err((
+            Status::BadRequest,
+            "max_hike must be a distance in meters".to_string(),
+        ));
+    }
+
+    let route = plan_route(from, to, max_hike, |lat, lon| {
+        if select_data_source(lat, lon, params.cell_size.unwrap_or(CELL_SIZE_DEFAULT)).is_none() {
+            return Result::Err(no_height_data());
+        }
+        let result = search_from_request(lat, lon, &params)?;
+        let start_height = result
+            .explored
+            .iter()
+            .find(|node| node.ix == result.start_ix)
+            .map_or(result.height_at_start, |node| node.height);
+        Ok(RouteCone {
+            explored: result.explored,
+            grid: result.height_grid,
+            start_height,
+            landing_prohibited: Some(result.landing_prohibited),
+        })
+    })?;
+    Result::Ok(Json(route))
+}
+
+/// Default hiking time of `/hike_isochrone` in hours.
+const HIKE_HOURS_DEFAULT: f32 = 4.0;
+/// Default and smallest hours between two isochrones.
+const HIKE_STEP_DEFAULT: f32 = 1.0;
+const HIKE_STEP_MIN: f32 = 0.25;
+
+/// Lines around the area reachable on foot from the start within every
+/// `step` hours up to `hours`, the uphill counterpart of the glide cone.
+#[get("/hike_isochrone?<lat>&<lon>&<coord>&<olc>&<cell_size>&<hours>&<step>")]
+fn get_hike_isochrone(
+    lat: Option<f32>,
+    lon: Option<f32>,
+    coord: Option<&str>,
+    olc: Option<&str>,
+    cell_size: Option<f32>,
+    hours: Option<f32>,
+    step: Option<f32>,
+) -> Result<Json<FeatureCollection<IsochroneProperties>>, (Status, String)> {
+    let (lat, lon) = start_from_request(lat, lon, coord, olc)?;
+    let cell_size = cell_size.unwrap_or(CELL_SIZE_DEFAULT);
+    let hours = hours.unwrap_or(HIKE_HOURS_DEFAULT);
+    if !(hours > 0.0 && hours <= MAX_HIKE_HOURS) {
+        return Result::Err((
+            Status::BadRequest,
+            format!("hours must be more than 0 and at most {MAX_HIKE_HOURS}"),
+        ));
+    }
+    let step = step.unwrap_or(HIKE_STEP_DEFAULT);
+    if !step.is_finite() || step < HIKE_STEP_MIN {
+        return Result::Err((
+            Status::BadRequest,
+            format!("step must be at least {HIKE_STEP_MIN} hours"),
+        ));
+    }
+    if select_data_source(lat, lon, cell_size).is_none() {
+        return Result::Err(no_height_data());
+    }
+
+    let distance = hours * MAX_HIKE_SPEED_KMH * 1000.0;
+    let grid = load_search_grid(lat, lon, cell_size, SearchExtent::around(distance))
+        .map_err(search_error_response)?;
+    let start = grid.index_of(lat, lon).ok_or_else(no_height_data)?;
+    let times = hiking_times(&grid, start, hours);
+    Result::Ok(Json(
+        hike_isochrones(&times, &grid, hours, step).with_metadata(grid_provenance(&grid)),
+    ))
+}
+
+/// Terrain along the line through `points`, a comma separated list of
+/// `latitude:longitude`, for cross sections. With a start given like for
+/// `/kml`, the samples the cone reaches also get the height of the glide.
+#[get("/elevation_profile?<points>&<params..>")]
+fn get_elevation_profile(
+    points: &str,
+    params: ConeParams,
+) -> Result<Json<Vec<ProfileSample>>, (Status, String)> {
+    let points = parse_polyline(points).map_err(|e| (Status::BadRequest, e))?;
+    let (first_lat, first_lon) = points[0];
+    if select_data_source(
+        first_lat,
+        first_lon,
+        params.cell_size.unwrap_or(CELL_SIZE_DEFAULT),
+    )
+    .is_none()
+    {
+        return Result::Err(no_height_data());
+    }
+    let grid = load_search_grid(
+        first_lat,
+        first_lon,
+        params.cell_size.unwrap_or(CELL_SIZE_DEFAULT),
+        polyline_extent(&points),
+    )
+    .map_err(search_error_response)?;
+    let mut samples = elevation_profile(&grid, &points);
+
+    if params.lat.is_some()
+        || params.lon.is_some()
+        || params.coord.is_some()
+        || params.olc.is_some()
+    {
+        let (lat, lon) = params.start()?;
+        if select_data_source(lat, lon, params.cell_size.unwrap_or(CELL_SIZE_DEFAULT)).is_none() {
+            return Result::Err(no_height_data());
+        }
+        let result = search_from_request(lat, lon, &params)?;
+        add_glide_heights(&mut samples, &result.explored, &result.height_grid);
+    }
+    Result::Ok(Json(samples))
+}
+
+/// Largest IGC file `/analyze_igc` accepts, larger than the default string
+/// limit of Rocket as tracks of long flights are several megabytes.
+const IGC_LIMIT: ByteUnit = ByteUnit::Mebibyte(5);
+
+/// Compares the IGC track in the body to the cone searched from its first
+/// fix, with the flight parameters of `/kml`. The cone starts at the
+/// altitude of the first fix unless `start_height` is given.
+#[post("/analyze_igc?<params..>", data = "<track>")]
+async fn post_analyze_igc(
+    mut params: ConeParams,
+    track: Data<'_>,
+) -> Result<Json<TrackComparison>, (Status, String)> {
+    let track = track
+        .open(IGC_LIMIT)
+        .into_string()
+        .await
+        .map_err(|e| (Status::BadRequest, format!("Could not read the track: {e}")))?;
+    if !track.is_complete() {
+        return Result::Err((
+            Status::PayloadTooLarge,
+            format!("Tracks may be at most {IGC_LIMIT}"),
+        ));
+    }
+    let fixes = parse_igc(&track).map_err(|e| (Status::BadRequest, e))?;
+
+    let (lat, lon) = (fixes[0].lat, fixes[0].lon);
+    if select_data_source(lat, lon, params.cell_size.unwrap_or(CELL_SIZE_DEFAULT)).is_none() {
+        return Result::Err(no_height_data());
+    }
+    let start_height = *params.start_height.get_or_insert(fixes[0].altitude);
+    let result = search_from_request(lat, lon, &params)?;
+    Result::Ok(Json(compare_track(
+        &fixes,
+        &result.explored,
+        &result.height_grid,
         start_height,
-        wind_speed,
-        wind_direction,
-        trim_speed,
-        safety_margin,
-        start_distance,
-    );
+    )))
+}
 
-    let heights = search_from_request_result.heights;
-    let in_safety_margin = search_from_request_result.in_safety_margin;
+/// Launch score maps kept in memory, each is as large as its hiking grid.
+const LAUNCH_SCORE_CACHE_SIZE: usize = 20;
+const LAUNCH_SCORE_CACHE_BYTES: usize = 64 << 20;
 
-    let mut imgx = heights.shape()[0];
-    let mut imgy = heights.shape()[1];
+/// Hiking grid and launch scores of a start.
+type LaunchScoreMap = (HeightGrid, LaunchScores);
 
-    let mut hmin = f32::MAX;
-    let mut hmax = f32::MIN;
-    let mut x_lower = usize::MAX;
-    let mut x_upper = usize::MIN;
-    let mut y_lower = usize::MAX;
-    let mut y_upper = usize::MIN;
+/// Launch score maps by the `SearchRequest::key` of the start and the
+/// hiking time, they take a search per scored launch.
+fn launch_score_cache() -> &'static Mutex<ByteLimitedCache<String, Arc<LaunchScoreMap>>> {
+    static INSTANCE: OnceLock<Mutex<ByteLimitedCache<String, Arc<LaunchScoreMap>>>> =
+        OnceLock::new();
+    INSTANCE.get_or_init(|| {
+        Mutex::new(ByteLimitedCache::new(
+            LAUNCH_SCORE_CACHE_SIZE,
+            LAUNCH_SCORE_CACHE_BYTES,
+            app_config()
+                .server
+                .cone_cache_ttl_secs
+                .map(Duration::from_secs),
+            |map| map.0.heights.len() * std::mem::size_of::<i16>() + map.1.bytes(),
+        ))
+    })
+}
 
-    for x in 0..imgx {
-        for y in 0..imgy {
-            if heights[(x, y)] > 0.0 {
-                hmin = hmin.min(heights[(x, y)]);
-                hmax = hmax.max(heights[(x, y)]);
+/// Scores of the launches reachable on foot from the start within `hours`,
+/// see `launch_scores`. Every launch is searched with the flight
+/// parameters, starting `additional_height` above the terrain. The cache is
+/// not locked while searching.
+fn launch_score_map(
+    lat: f32,
+    lon: f32,
+    hours: Option<f32>,
+    params: &ConeParams,
+) -> Result<Arc<LaunchScoreMap>, (Status, String)> {
+    let hours = hours.unwrap_or(HIKE_HOURS_DEFAULT);
+    if !(hours > 0.0 && hours <= MAX_HIKE_HOURS) {
+        return Result::Err((
+            Status::BadRequest,
+            format!("hours must be more than 0 and at most {MAX_HIKE_HOURS}"),
+        ));
+    }
+    if select_data_source(lat, lon, params.cell_size.unwrap_or(CELL_SIZE_DEFAULT)).is_none() {
+        return Result::Err(no_height_data());
+    }
+    let params = ConeParams {
+        start_height: None,
+        ..params.clone()
+    };
+    let search = |lat: f32, lon: f32| search_request(lat, lon, &params);
+    let key = format!("{}_{hours}", search(lat, lon)?.key());
+    if let Some(map) = launch_score_cache().lock().unwrap().cache_get(&key) {
+        return Ok(map.clone());
+    }
 
-                x_lower = min(x_lower, x);
-                x_upper = max(x_upper, x);
-                y_lower = min(y_lower, y);
-                y_upper = max(y_upper, y);
-            }
+    let distance = hours * MAX_HIKE_SPEED_KMH * 1000.0;
+    let grid = load_search_grid(
+        lat,
+        lon,
+        params.cell_size.unwrap_or(CELL_SIZE_DEFAULT),
+        SearchExtent::around(distance),
+    )
+    .map_err(search_error_response)?;
+    let start = grid.index_of(lat, lon).ok_or_else(no_height_data)?;
+    let times = hiking_times(&grid, start, hours);
+    let scores = launch_scores(&grid, &times, start, MAX_SCORED_LAUNCHES, |lat, lon| {
+        if select_data_source(lat, lon, params.cell_size.unwrap_or(CELL_SIZE_DEFAULT)).is_none() {
+            return Result::Err(no_height_data());
         }
-    }
+        let result = run_search_request(search(lat, lon)?)?;
+        Ok(cone_stats(&result.explored, &result.height_grid, None).area_km2)
+    })?;
 
-    imgx = (x_upper - x_lower) + 1;
-    imgy = (y_upper - y_lower) + 1;
+    let map = Arc::new((grid, scores));
+    launch_score_cache()
+        .lock()
+        .unwrap()
+        .cache_set(key, map.clone());
+    Ok(map)
+}
 
-    let heights_sub = heights.slice(s![x_lower..(x_upper + 1), y_lower..(y_upper + 1)]);
-    let safety_margin_sub =
-        in_safety_margin.slice(s![x_lower..(x_upper + 1), y_lower..(y_upper + 1)]);
+#[derive(Serialize)]
+struct LaunchScoreResponse {
+    /// Bounds of `/launch_score_image`.
+    lat: (f32, f32),
+    lon: (f32, f32),
+    launches: Vec<ScoredLaunch>,
+}
 
-    let mut img = DynamicImage::new_rgb8(imgy as u32, imgx as u32);
+/// Launches reachable on foot from the start within `hours`, scored by the
+/// area reachable by air from them per hiking hour, best first. Takes the
+/// flight parameters of `/kml` except `start_height`.
+#[get("/launch_scores?<hours>&<params..>")]
+fn get_launch_scores(
+    params: ConeParams,
+    hours: Option<f32>,
+) -> Result<Json<LaunchScoreResponse>, (Status, String)> {
+    let (lat, lon) = params.start()?;
+    let map = launch_score_map(lat, lon, hours, &params)?;
+    let (grid, scores) = map.as_ref();
+    Result::Ok(Json(LaunchScoreResponse {
+        lat: grid.latitudes,
+        lon: grid.longitudes,
+        launches: scores.launches.clone(),
+    }))
+}
 
-    // Iterate over the coordinates and pixels of the image
-    for x in 0..imgx {
-        for y in 0..imgy {
-            let ix = (x, y);
-            if heights_sub[ix] >= 0.0 {
-                let height = heights_sub[ix].round() as i32;
-                let safety_margin = safety_margin_sub[ix];
-                img.put_pixel(
-                    y as u32,
-                    (imgx - x) as u32 - 1,
-                    Rgba([
-                        (height / 256) as u8,
-                        (height % 256) as u8,
-                        if safety_margin { 128 } else { 255 },
-                        255,
-                    ]),
-                );
+/// Heat map of the best launch spots of `/launch_scores`, every cell reached
+/// on foot colored by its score with the color ramp given by `colormap`.
+#[get("/launch_score_image?<hours>&<colormap>&<projection>&<params..>")]
+fn get_launch_score_image(
+    params: ConeParams,
+    hours: Option<f32>,
+    colormap: Option<&str>,
+    projection: Option<&str>,
+) -> Result<(ContentType, Vec<u8>), (Status, String)> {
+    let ramp = color_ramp_from_request(colormap)?;
+    let projection = projection_from_request(projection)?;
+    let (lat, lon) = params.start()?;
+    let map = launch_score_map(lat, lon, hours, &params)?;
+    let (grid, scores) = map.as_ref();
+
+    let max_score = scores.scores.iter().flatten().copied().fold(0.0, f32::max);
+    let (rows, cols) = (grid.heights.shape()[0], grid.heights.shape()[1]);
+    let mut img = DynamicImage::new_rgba8(cols as u32, rows as u32);
+    for ((row, col), score) in scores.scores.indexed_iter() {
+        if let Some(score) = score {
+            let s = if max_score > 0.0 {
+                score / max_score
             } else {
-                img.put_pixel(y as u32, (imgx - x) as u32 - 1, Rgba([255, 255, 0, 255]));
-            }
+                0.0
+            };
+            img.put_pixel(
+                col as u32,
+                (rows - row - 1) as u32,
+                Rgba(f32_color_to_u8(ramp.color_at(s))),
+            );
         }
     }
-
-    let mut c = Cursor::new(Vec::new());
-    img.write_to(&mut c, ImageFormat::Png).expect("");
-    (ContentType::PNG, c.into_inner())
+    Result::Ok((
+        ContentType::PNG,
+        encode_raster(img, grid, (0, rows - 1), projection),
+    ))
 }
 
-fn single_element(name: &str, content: &str, writer: &mut Writer<Cursor<Vec<u8>>>) {
-    start(name, writer);
-    writer
-        .write_event(Event::Text(BytesText::new(content)))
-        .unwrap();
-    end(name, writer);
+/// Boundary distances kept in memory, each is as large as the grid of its
+/// search.
+const BOUNDARY_DISTANCE_CACHE_SIZE: usize = 50;
+const BOUNDARY_DISTANCE_CACHE_BYTES: usize = 256 << 20;
+
+/// Boundary distances by `SearchRequest::key`, so repeated margin queries
+/// for the same cone don't search again.
+fn boundary_distance_cache() -> &'static Mutex<ByteLimitedCache<String, Arc<BoundaryDistance>>> {
+    static INSTANCE: OnceLock<Mutex<ByteLimitedCache<String, Arc<BoundaryDistance>>>> =
+        OnceLock::new();
+    INSTANCE.get_or_init(|| {
+        Mutex::new(ByteLimitedCache::new(
+            BOUNDARY_DISTANCE_CACHE_SIZE,
+            BOUNDARY_DISTANCE_CACHE_BYTES,
+            app_config()
+                .server
+                .cone_cache_ttl_secs
+                .map(Duration::from_secs),
+            |distance| distance.bytes(),
+        ))
+    })
 }
 
-fn start(name: &str, writer: &mut Writer<Cursor<Vec<u8>>>) {
-    //writer.write_indent().unwrap();
-    writer
-        .write_event(Event::Start(BytesStart::new(name)))
-        .unwrap();
+/// Boundary distance of the cone of a request from the cache, computed and
+/// stored if it is not cached. The cache is not locked while searching.
+fn cached_boundary_distance(
+    request: SearchRequest,
+) -> Result<Arc<BoundaryDistance>, (Status, String)> {
+    let key = request.key();
+    if let Some(distance) = boundary_distance_cache().lock().unwrap().cache_get(&key) {
+        return Ok(distance.clone());
+    }
+    let result = run_search_request(request)?;
+    let distance = Arc::new(BoundaryDistance::new(&result.explored, &result.height_grid));
+    boundary_distance_cache()
+        .lock()
+        .unwrap()
+        .cache_set(key, distance.clone());
+    Ok(distance)
 }
 
-fn end(name: &str, writer: &mut Writer<Cursor<Vec<u8>>>) {
-    //writer.write_indent().unwrap();
-    writer.write_event(Event::End(BytesEnd::new(name))).unwrap();
+#[derive(Serialize)]
+struct MarginAtResponse {
+    /// Distance in meters to the boundary of the cone, negative outside of
+    /// it.
+    margin: f32,
+    inside: bool,
 }
 
-fn interpolate(node: &Node, px: u16, py: u16, heights: &Array2<f32>) -> f32 {
-    let x = min((node.ix.0 + px) as usize, heights.shape()[0] - 1);
-    let y = min((node.ix.1 + py) as usize, heights.shape()[1] - 1);
-    if heights[(x, y)] > -1000.0 {
-        heights[(x, y)]
-    } else {
-        node.height
+/// How far a point is inside the area reachable from the start, for
+/// checking the position in flight against a cone without searching again.
+#[get("/margin_at?<target_lat>&<target_lon>&<params..>")]
+fn get_margin_at(
+    params: ConeParams,
+    target_lat: f32,
+    target_lon: f32,
+) -> Result<Json<MarginAtResponse>, (Status, String)> {
+    let (lat, lon) = params.start()?;
+    if select_data_source(lat, lon, params.cell_size.unwrap_or(CELL_SIZE_DEFAULT)).is_none() {
+        return Result::Err(no_height_data());
     }
+
+    let request = search_request(lat, lon, &params)?;
+
+    let margin = cached_boundary_distance(request)?
+        .at(target_lat, target_lon)
+        .ok_or((
+            Status::NotFound,
+            "The target is outside of the searched area".to_string(),
+        ))?;
+    Result::Ok(Json(MarginAtResponse {
+        margin,
+        inside: margin > 0.0,
+    }))
 }
 
-#[allow(clippy::too_many_arguments)]
-#[get("/kml?<lat>&<lon>&<cell_size>&<glide_number>&<additional_height>&<start_height>&<wind_speed>&<wind_direction>&<trim_speed>&<safety_margin>&<start_distance>")]
-fn get_kml(
-    lat: f32,
-    lon: f32,
-    cell_size: Option<f32>,
-    glide_number: Option<f32>,
-    additional_height: Option<f32>,
-    start_height: Option<f32>,
-    wind_speed: Option<f32>,
-    wind_direction: Option<f32>,
-    trim_speed: Option<f32>,
-    safety_margin: Option<f32>,
-    start_distance: Option<f32>,
-) -> (ContentType, Vec<u8>) {
-    let search_from_request_result = search_from_request(
-        lat,
-        lon,
-        cell_size,
-        glide_number,
-        additional_height,
-        start_height,
-        wind_speed,
-        wind_direction,
-        trim_speed,
-        safety_margin,
-        start_distance,
-    );
+fn search_index_slot() -> &'static RwLock<Arc<SearchLocation>> {
+    static INSTANCE: OnceCell<RwLock<Arc<SearchLocation>>> = OnceCell::new();
+    INSTANCE.get_or_init(|| {
+        info!("Building search index");
 
-    let heights = search_from_request_result.heights;
-    let node_heights = search_from_request_result.node_heights;
-    let height_grid = search_from_request_result.height_grid;
-    let nodes = search_from_request_result.explored;
+        let data = fs::read(data_dir().join("search_index.fb"))
+            .expect("Should be able to read search_index.fb");
+        let r = flexbuffers::Reader::get_root(&data[..]).unwrap();
 
-    let imgx = heights.shape()[0];
-    let imgy = heights.shape()[1];
-    let mut hmin = f32::MAX;
-    let mut hmax = f32::MIN;
-    for x in 0..imgx {
-        for y in 0..imgy {
-            if heights[(x, y)] > -1000.0 {
-                hmin = hmin.min(heights[(x, y)]);
-                hmax = hmax.max(heights[(x, y)]);
-            }
-        }
-    }
+        let index = SearchLocation::deserialize(r).unwrap();
+        RwLock::new(Arc::new(index))
+    })
+}
 
-    let lat_resolution =
-        (height_grid.latitudes.1 - height_grid.latitudes.0) / height_grid.heights.shape()[0] as f32;
-    let lon_resolution = (height_grid.longitudes.1 - height_grid.longitudes.0)
-        / height_grid.heights.shape()[1] as f32;
+/// The current location search index, replaced by the site data refresh.
+fn search_index() -> Arc<SearchLocation> {
+    search_index_slot().read().unwrap().clone()
+}
 
-    let lat_r_2 = lat_resolution / 2.0;
-    let lon_r_2 = lon_resolution / 2.0;
+/// Locations of a JSONL file, one JSON object per line.
+fn parse_location_lines(content: &str) -> Result<Vec<Location>, String> {
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .enumerate()
+        .map(|(i, line)| {
+            serde_json::from_str(line)
+                .map_err(|e| format!("Invalid location in line {}: {e}", i + 1))
+        })
+        .collect()
+}
 
-    let cursor = Cursor::new(Vec::new());
-    let mut writer = Writer::new_with_indent(cursor, b' ', 4);
+fn load_flying_sites() -> Result<BTree<Location>, String> {
+    let path = data_dir().join("search_data_flying_sites.jsonl");
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Could not read {}: {e}", path.display()))?;
+    let items = parse_location_lines(&content)?
+        .into_iter()
+        .map(|location| (location.center.clone(), location))
+        .collect();
+    Ok(BTree::new(items, None, None))
+}
 
-    let mut elem = BytesStart::new("kml");
-    elem.push_attribute(("xmlns", "http://www.opengis.net/kml/2.2"));
-    writer.write_event(Event::Start(elem)).unwrap();
-    start("Document", &mut writer);
+fn flying_site_search_index_slot() -> &'static RwLock<Arc<BTree<Location>>> {
+    static INSTANCE: OnceCell<RwLock<Arc<BTree<Location>>>> = OnceCell::new();
+    INSTANCE.get_or_init(|| {
+        info!("Building flying site search index");
+        RwLock::new(Arc::new(load_flying_sites().unwrap()))
+    })
+}
 
-    for node in nodes {
-        if node.reachable {
-            let x = node.ix.0 as f32 * lat_resolution + height_grid.latitudes.0;
-            let y = node.ix.1 as f32 * lon_resolution + height_grid.longitudes.0;
+/// The current flying site index, replaced by the site data refresh.
+fn flying_site_search_index() -> Arc<BTree<Location>> {
+    flying_site_search_index_slot().read().unwrap().clone()
+}
+
+/// Known landing zones, in the same format as the flying sites. The file is
+/// optional, without it no landing zones are known.
+fn load_landing_zones() -> Result<BTree<Location>, String> {
+    let path = data_dir().join("search_data_landing_zones.jsonl");
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(format!("Could not read {}: {e}", path.display())),
+    };
+    let items = parse_location_lines(&content)?
+        .into_iter()
+        .map(|location| (location.center.clone(), location))
+        .collect();
+    Ok(BTree::new(items, None, None))
+}
+
+fn landing_zone_index_slot() -> &'static RwLock<Arc<BTree<Location>>> {
+    static INSTANCE: OnceCell<RwLock<Arc<BTree<Location>>>> = OnceCell::new();
+    INSTANCE.get_or_init(|| {
+        info!("Building landing zone index");
+        RwLock::new(Arc::new(load_landing_zones().unwrap()))
+    })
+}
+
+/// The current landing zone index, replaced by the site data refresh.
+fn landing_zone_index() -> Arc<BTree<Location>> {
+    landing_zone_index_slot().read().unwrap().clone()
+}
 
-            let agl = heights[(node.ix.0 as usize, node.ix.1 as usize)];
-            let s = ((agl - hmin) / (hmax - hmin)).clamp(0.0, 1.0);
-            let color = f32_color_to_u8(lerp(&DEFAULT_LERP_COLORS, &DEFAULT_LERP_STEPS, s));
+/// Writes the file through a temporary file, so readers never see a partially
+/// written file.
+fn write_replacing(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    let mut part = path.as_os_str().to_owned();
+    part.push(".part");
+    fs::write(&part, bytes).map_err(|e| format!("Could not write {}: {e}", path.display()))?;
+    fs::rename(&part, path).map_err(|e| format!("Could not move {}: {e}", path.display()))
+}
 
-            let color_string = format!(
-                "{:02x}{:02x}{:02x}{:02x}",
-                150u8, color[2], color[1], color[0],
+/// Rebuilds the location search index from all JSONL files in the data
+/// directory and the flying site and landing zone indexes, stores the
+/// location search index and swaps all of them in. Searches running
+/// meanwhile keep the old indexes.
+fn rebuild_search_indexes() -> Result<String, String> {
+    let mut locations = vec![];
+    let entries = fs::read_dir(data_dir()).map_err(|e| e.to_string())?;
+    for entry in entries {
+        let path = entry.map_err(|e| e.to_string())?.path();
+        if path.extension().and_then(|s| s.to_str()) == Some("jsonl") {
+            let content = fs::read_to_string(&path)
+                .map_err(|e| format!("Could not read {}: {e}", path.display()))?;
+            locations.extend(
+                parse_location_lines(&content).map_err(|e| format!("{}: {e}", path.display()))?,
             );
+        }
+    }
+    let n_locations = locations.len();
+    let index = SearchLocation::from_locations(locations);
+    let mut serializer = flexbuffers::FlexbufferSerializer::new();
+    index
+        .serialize(&mut serializer)
+        .map_err(|e| e.to_string())?;
+    write_replacing(&data_dir().join("search_index.fb"), serializer.view())?;
+    let flying_sites = load_flying_sites()?;
+    let landing_zones = load_landing_zones()?;
+
+    *search_index_slot().write().unwrap() = Arc::new(index);
+    *flying_site_search_index_slot().write().unwrap() = Arc::new(flying_sites);
+    *landing_zone_index_slot().write().unwrap() = Arc::new(landing_zones);
+    Ok(format!("search index rebuilt with {n_locations} locations"))
+}
 
-            start("Placemark", &mut writer);
-
-            start("Style", &mut writer);
-            start("LineStyle", &mut writer);
-
-            single_element("color", color_string.as_str(), &mut writer); //TODO: Fix color
-            end("LineStyle", &mut writer);
-            start("PolyStyle", &mut writer);
-            single_element("color", color_string.as_str(), &mut writer);
-            single_element("outline", "1", &mut writer);
-            end("PolyStyle", &mut writer);
-            end("Style", &mut writer);
-
-            start("Polygon", &mut writer);
-            single_element("altitudeMode", "absolute", &mut writer);
-            start("outerBoundaryIs", &mut writer);
-            start("LinearRing", &mut writer);
-            start("coordinates", &mut writer);
-
-            writer.write_indent().unwrap();
-            writer
-                .write_event(Event::Text(BytesText::new(
-                    format!("{},{},{}", y - lon_r_2, x - lat_r_2, node.height).as_str(),
-                )))
-                .unwrap();
-            writer.write_indent().unwrap();
-            writer
-                .write_event(Event::Text(BytesText::new(
-                    format!(
-                        "{},{},{}",
-                        y - lon_r_2,
-                        x + lat_r_2,
-                        interpolate(&node, 1, 0, &node_heights)
-                    )
-                    .as_str(),
-                )))
-                .unwrap();
-            writer.write_indent().unwrap();
-            writer
-                .write_event(Event::Text(BytesText::new(
-                    format!(
-                        "{},{},{}",
-                        y + lon_r_2,
-                        x + lat_r_2,
-                        interpolate(&node, 1, 1, &node_heights)
-                    )
-                    .as_str(),
-                )))
-                .unwrap();
-            writer.write_indent().unwrap();
-            writer
-                .write_event(Event::Text(BytesText::new(
-                    format!(
-                        "{},{},{}",
-                        y + lon_r_2,
-                        x - lat_r_2,
-                        interpolate(&node, 0, 1, &node_heights)
-                    )
-                    .as_str(),
-                )))
-                .unwrap();
-            writer.write_indent().unwrap();
-            writer
-                .write_event(Event::Text(BytesText::new(
-                    format!("{},{},{}", y - lon_r_2, x - lat_r_2, node.height).as_str(),
-                )))
-                .unwrap();
-            writer.write_indent().unwrap();
+/// Downloads a site data file. Files that are not valid JSONL of locations
+/// do not replace the current file.
+async fn download_site_data(source: &SiteDataSource) -> Result<usize, String> {
+    let response = reqwest_client()
+        .get(&source.url)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!(
+            "{} failed with status {}",
+            source.url,
+            response.status()
+        ));
+    }
+    let content = response.text().await.map_err(|e| e.to_string())?;
+    let n_locations = parse_location_lines(&content)
+        .map_err(|e| format!("{}: {e}", source.url))?
+        .len();
+    write_replacing(&data_dir().join(&source.file_name), content.as_bytes())?;
+    Ok(n_locations)
+}
 
-            end("coordinates", &mut writer);
-            end("LinearRing", &mut writer);
-            end("outerBoundaryIs", &mut writer);
-            end("Polygon", &mut writer);
+/// Refreshes the site data files and rebuilds the search indexes. A failed
+/// download keeps the previous file of that source.
+async fn refresh_site_data() -> Result<String, String> {
+    for source in app_config().refresh.site_data.iter() {
+        match download_site_data(source).await {
+            Ok(n_locations) => {
+                info!("Downloaded {n_locations} locations to {}", source.file_name)
+            }
+            Err(e) => warn!("Could not refresh {}: {e}", source.file_name),
+        }
+    }
+    rocket::tokio::task::spawn_blocking(rebuild_search_indexes)
+        .await
+        .map_err(|e| e.to_string())?
+}
 
-            end("Placemark", &mut writer);
+/// Downloads the SRTM tiles that are missing or fail their checksum in the
+/// hgt manifest again.
+async fn refresh_dem() -> Result<String, String> {
+    let url_template = app_config()
+        .dem_url_template
+        .clone()
+        .ok_or("The DEM refresh needs a dem_url_template")?;
+    let report = rocket::tokio::task::spawn_blocking(|| verify_data_dir(true))
+        .await
+        .map_err(|e| e.to_string())?;
+    let downloader = DemDownloader::new(url_template);
+    let mut replaced = 0;
+    for problem in report.problems.iter() {
+        let Some((latitude, longitude)) = parse_tile_name(problem.file()) else {
+            continue;
+        };
+        match downloader
+            .download(latitude, longitude, &Span::none())
+            .await
+        {
+            Ok(()) => {
+                forget_tile(latitude, longitude);
+                replaced += 1;
+            }
+            Err(e) => warn!("Could not download DEM tile: {e}"),
         }
     }
+    if replaced > 0 {
+        rocket::tokio::task::spawn_blocking(dem_tiles_stored)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(format!(
+        "replaced {replaced} of {} bad tiles",
+        report.problems.len()
+    ))
+}
 
-    end("Document", &mut writer);
-    end("kml", &mut writer);
+type JobFuture = Pin<Box<dyn Future<Output = Result<String, String>> + Send>>;
 
-    (ContentType::XML, writer.into_inner().into_inner())
+/// Background job, `run` returns a summary of what it did.
+struct ScheduledJob {
+    name: &'static str,
+    run: fn() -> JobFuture,
 }
 
-fn search_index() -> &'static SearchLocation {
-    static INSTANCE: OnceCell<SearchLocation> = OnceCell::new();
-    INSTANCE.get_or_init(|| {
-        println!("Building search index...");
+/// Runs the jobs every `refresh.interval_hours` once the server is up, the
+/// first time at `refresh.start_hour`. A job starts again only once its
+/// previous run finished.
+struct Scheduler {
+    jobs: Vec<ScheduledJob>,
+    config: RefreshConfig,
+}
 
-        let data = fs::read("data/search_index.fb").expect("Should be able to read hosts file");
-        let r = flexbuffers::Reader::get_root(&data[..]).unwrap();
+#[rocket::async_trait]
+impl Fairing for Scheduler {
+    fn info(&self) -> Info {
+        Info {
+            name: "Scheduled jobs",
+            kind: Kind::Liftoff,
+        }
+    }
 
-        let index = SearchLocation::deserialize(r).unwrap();
-        index
-    })
+    async fn on_liftoff(&self, _: &Rocket<Orbit>) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let start = rocket::tokio::time::Instant::now() + self.config.first_delay(now);
+        let interval = self.config.interval();
+        for job in self.jobs.iter() {
+            let (name, run) = (job.name, job.run);
+            rocket::tokio::spawn(async move {
+                let mut ticks = rocket::tokio::time::interval_at(start, interval);
+                ticks.set_missed_tick_behavior(MissedTickBehavior::Skip);
+                loop {
+                    ticks.tick().await;
+                    match run().await {
+                        Ok(summary) => info!(job = name, "Job finished: {summary}"),
+                        Err(e) => error!(job = name, "Job failed: {e}"),
+                    }
+                }
+            });
+        }
+    }
 }
 
-fn flying_site_search_index() -> &'static BTree<Location> {
-    static INSTANCE: OnceCell<BTree<Location>> = OnceCell::new();
-    INSTANCE.get_or_init(|| {
-        println!("Building flying site search index...");
-        let mut items = vec![];
+/// Reads the DEM tiles of `region` into the tile caches, at most as many as
+/// they hold so tiles loaded early are not evicted again. Returns the number
+/// of tiles loaded.
+fn preload_dem(region: &DatasetRegion) -> usize {
+    let tiles = (region.latitudes.0.floor() as i32..=region.latitudes.1.floor() as i32)
+        .flat_map(|lat| {
+            (region.longitudes.0.floor() as i32..=region.longitudes.1.floor() as i32)
+                .map(move |lon| (lat, lon))
+        })
+        .collect::<Vec<_>>();
+    let mut loaded = 0;
+    for (source, capacity) in [
+        (DataSource::Srtm, HGT_CACHE_TILES),
+        (DataSource::Coarse, COARSE_HGT_CACHE_TILES),
+    ] {
+        for (lat, lon) in tiles
+            .iter()
+            .filter(|(lat, lon)| tile_exists(source, *lat, *lon))
+            .take(capacity)
+        {
+            load_tile(source, *lat, *lon);
+            loaded += 1;
+        }
+    }
+    loaded
+}
+
+/// Fetches the OpenTopoMap tiles of `region` at the configured zoom levels
+/// into the cache storage and memory. Returns the number of tiles available
+/// afterwards and the number that failed.
+async fn preload_map_tiles(region: &DatasetRegion, config: &PreloadConfig) -> (usize, usize) {
+    let tiles = config
+        .map_zoom_levels
+        .iter()
+        .flat_map(|z| {
+            tiles_covering(region.latitudes, region.longitudes, *z)
+                .into_iter()
+                .map(move |(x, y)| (*z, x, y))
+        })
+        .take(config.max_map_tiles)
+        .collect::<Vec<_>>();
+    let (mut loaded, mut failed) = (0, 0);
+    for (z, x, y) in tiles {
+        // Spread over the subdomains like the frontend does.
+        let subdomain = ["a", "b", "c"][((x + y) % 3) as usize];
+        match get_tile(subdomain.to_string(), z, x, y).await {
+            Ok(_) => loaded += 1,
+            Err(_) => failed += 1,
+        }
+    }
+    (loaded, failed)
+}
 
-        let r = File::open("data/search_data_flying_sites.jsonl").unwrap();
-        let reader = BufReader::new(r);
-        for line in reader.lines() {
-            let location: Location = serde_json::from_str(&line.unwrap()).unwrap();
+/// Loads the DEM and map tiles of the home region once the server is up, so
+/// the first requests after a deploy are not slowed down by reading them.
+struct Preloader {
+    region: String,
+    config: PreloadConfig,
+}
 
-            items.push((location.center.clone(), location));
+#[rocket::async_trait]
+impl Fairing for Preloader {
+    fn info(&self) -> Info {
+        Info {
+            name: "Home region preloading",
+            kind: Kind::Liftoff,
         }
+    }
 
-        BTree::new(items, None, None)
-    })
+    async fn on_liftoff(&self, _: &Rocket<Orbit>) {
+        let region = match find_region(&self.region) {
+            Ok(region) => Arc::new(region),
+            Err(e) => {
+                warn!("Not preloading the home region: {e}");
+                return;
+            }
+        };
+        let config = self.config.clone();
+        rocket::tokio::spawn(async move {
+            let start = Instant::now();
+            let dem_tiles = if config.dem {
+                let region = region.clone();
+                task::spawn_blocking(move || preload_dem(&region))
+                    .await
+                    .unwrap_or(0)
+            } else {
+                0
+            };
+            let (map_tiles, failed) = preload_map_tiles(&region, &config).await;
+            info!(
+                "Preloaded {} in {:?}: {dem_tiles} DEM tiles, {map_tiles} map tiles ({failed} failed)",
+                region.name,
+                start.elapsed()
+            );
+        });
+    }
+}
+
+/// Messages answering a query of the location search: up to ten matches,
+/// followed by a message with index 1000 marking the end.
+fn search_messages(ix: &SearchLocation, q: &str) -> Vec<String> {
+    ix.index
+        .find_with_max_edit_distance(q, (q.len() / 4).clamp(2, 255) as u8, true)
+        .flatten()
+        .take(10)
+        .enumerate()
+        .map(|(i, x)| LocationWithQuery {
+            query: q.to_string(),
+            index: i,
+            location: Location {
+                name: x.0.to_string(),
+                center: x.1.center.clone(),
+                additional_info: ix.additional_info.get(x.1.additional_info_ix).cloned(),
+            },
+        })
+        .chain(std::iter::once(LocationWithQuery {
+            query: q.to_string(),
+            index: 1000,
+            location: Location {
+                name: "".to_string(),
+                center: vec![0.0, 0.0],
+                additional_info: None,
+            },
+        }))
+        .map(|x| serde_json::to_string(&x).unwrap())
+        .collect()
 }
 
-#[allow(clippy::too_many_arguments)]
 #[get("/search_ws/ws")]
 fn search(ws: WebSocket) -> Stream!['static] {
-    let ix = search_index();
-
     Stream! { ws =>
         for await message in ws {
             if message.is_err() {
@@ -1196,38 +5622,148 @@ fn search(ws: WebSocket) -> Stream!['static] {
             }
             let m = message.unwrap();
             if let rocket_ws::Message::Text(t) = m {
-                let q = t.as_str();
-                let result = ix
-                    .index
-                    .find_with_max_edit_distance(q, (q.len() / 4).clamp(2, 255) as u8, true)
-                    .flatten()
-                    .take(10)
-                    .enumerate()
-                    .map(|(i, x)| LocationWithQuery {
-                        query: q.to_string(),
-                        index: i,
-                        location: Location {
-                            name: x.0.to_string(),
-                            center: x.1.center.clone(),
-                            additional_info: ix.additional_info.get(x.1.additional_info_ix).cloned(),
-                        }
-                    });
-
-                for x in result {
-                    yield rocket_ws::Message::Text(serde_json::to_string(&x).unwrap());
+                for message in search_messages(&search_index(), t.as_str()) {
+                    yield rocket_ws::Message::Text(message);
                 }
-                yield rocket_ws::Message::Text(serde_json::to_string(&LocationWithQuery {
-                        query: q.to_string(),
-                        index: 1000,
-                        location: Location {
-                            name: "".to_string(),
-                            center: vec![0.0, 0.0],
-                            additional_info: None,
-                        }
-                    }).unwrap() );
             }
         }
-    }
+    }
+}
+
+/// Fallback of the location search for networks that block websockets: each
+/// request answers one query `q`, with the messages of the websocket sent
+/// with chunked transfer encoding, one per line.
+#[get("/search_ws/ws?<q>&<chunked>", rank = 2)]
+fn search_chunked(q: &str, chunked: bool) -> Result<TextStream![String], (Status, String)> {
+    if !chunked {
+        return Err((
+            Status::BadRequest,
+            "Use a websocket or chunked=true".to_string(),
+        ));
+    }
+    let messages = search_messages(&search_index(), q);
+    Ok(TextStream! {
+        for message in messages {
+            yield message + "\n";
+        }
+    })
+}
+
+/// Elevation lookup compatible with the Open-Elevation API, e.g.
+/// `/api/v1/lookup?locations=47.2692,11.4041|46.5,8.0`.
+#[get("/api/v1/lookup?<locations>")]
+fn get_elevation_lookup(locations: &str) -> Result<Json<LookupResponse>, (Status, String)> {
+    let locations = parse_locations(locations).map_err(|e| (Status::BadRequest, e))?;
+    lookup(&locations)
+        .map(Json)
+        .map_err(|e| (Status::BadRequest, e))
+}
+
+#[post("/api/v1/lookup", format = "json", data = "<request>")]
+fn post_elevation_lookup(
+    request: Json<LookupRequest>,
+) -> Result<Json<LookupResponse>, (Status, String)> {
+    lookup(&request.locations)
+        .map(Json)
+        .map_err(|e| (Status::BadRequest, e))
+}
+
+/// Airspaces of the data directory, empty if there is no airspace file.
+fn airspaces() -> &'static Vec<Airspace> {
+    static INSTANCE: OnceCell<Vec<Airspace>> = OnceCell::new();
+    INSTANCE.get_or_init(|| {
+        let path = data_dir().join(AIRSPACE_FILE_NAME);
+        let Ok(content) = fs::read_to_string(&path) else {
+            warn!("No airspaces found at {}", path.display());
+            return vec![];
+        };
+        let airspaces = parse_openair(&content).expect("Could not parse airspaces");
+        info!("Loaded {} airspaces", airspaces.len());
+        airspaces
+    })
+}
+
+fn parse_no_landing_zones(content: &str) -> Result<Region, String> {
+    let geojson = serde_json::from_str::<RegionGeoJson>(content)
+        .map_err(|e| format!("Invalid GeoJSON: {e}"))?;
+    Region::from_geojson(geojson)
+}
+
+/// Areas of the data directory where landing is prohibited, `None` if there
+/// is no no landing zone file. Can be replaced at runtime by an upload.
+fn no_landing_zones() -> &'static RwLock<Option<Region>> {
+    static INSTANCE: OnceCell<RwLock<Option<Region>>> = OnceCell::new();
+    INSTANCE.get_or_init(|| {
+        let path = data_dir().join(NO_LANDING_FILE_NAME);
+        let Ok(content) = fs::read_to_string(&path) else {
+            info!("No no landing zones found at {}", path.display());
+            return RwLock::new(None);
+        };
+        let zones = parse_no_landing_zones(&content).expect("Could not parse no landing zones");
+        info!("Loaded {} no landing zones", zones.polygons.len());
+        RwLock::new(Some(zones))
+    })
+}
+
+/// Land cover tiles kept in memory. Tiles at the full 10 m resolution of
+/// WorldCover take more than a GB each.
+const LAND_COVER_CACHE_TILES: usize = 4;
+
+/// Land cover tile with its south west corner at `south` and `west` from
+/// `land_cover/` in the data directory, `None` if there is none or it can't
+/// be read.
+#[cached(
+    ty = "SizedCache<(i32, i32), Option<Arc<LandCoverTile>>>",
+    create = "{ SizedCache::with_size(LAND_COVER_CACHE_TILES) }"
+)]
+fn load_land_cover_tile(south: i32, west: i32) -> Option<Arc<LandCoverTile>> {
+    let path = land_cover_file(&data_dir().join(LAND_COVER_DIR), south, west)?;
+    let image = ImageReader::open(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|mut reader| {
+            reader.no_limits();
+            reader.decode().map_err(|e| e.to_string())
+        });
+    let image = match image {
+        Ok(image) => image.to_luma8(),
+        Err(e) => {
+            warn!("Could not read land cover tile {}: {e}", path.display());
+            return None;
+        }
+    };
+    let shape = (image.height() as usize, image.width() as usize);
+    let codes = Array2::from_shape_vec(shape, image.into_raw()).ok()?;
+    Some(Arc::new(LandCoverTile { south, west, codes }))
+}
+
+/// Replaces the no landing zones with the uploaded GeoJSON and stores it in
+/// the data directory, so it is used after a restart as well.
+#[put("/admin/no_landing_zones", format = "json", data = "<zones>")]
+fn put_no_landing_zones(_admin: Admin, zones: String) -> Result<String, (Status, String)> {
+    let region = parse_no_landing_zones(&zones).map_err(|e| (Status::UnprocessableEntity, e))?;
+    fs::write(data_dir().join(NO_LANDING_FILE_NAME), &zones).map_err(|e| {
+        (
+            Status::InternalServerError,
+            format!("Could not store the no landing zones: {e}"),
+        )
+    })?;
+    let message = format!("Stored {} no landing zones", region.polygons.len());
+    *no_landing_zones().write().unwrap() = Some(region);
+    Ok(message)
+}
+
+#[get("/airspaces?<min_lat>&<max_lat>&<min_lon>&<max_lon>")]
+fn get_airspaces(
+    min_lat: f32,
+    max_lat: f32,
+    min_lon: f32,
+    max_lon: f32,
+) -> Json<FeatureCollection<AirspaceProperties>> {
+    Json(airspace_features(
+        airspaces(),
+        (min_lat, max_lat),
+        (min_lon, max_lon),
+    ))
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -1252,40 +5788,395 @@ fn search_flying_site(
     Result::Ok(Json(sites))
 }
 
-#[cached(size = 500, option = true)]
-fn load_png_from_disk(path: String) -> Option<Vec<u8>> {
-    if Path::new(&path).exists() {
-        let bytes = fs::read(&path).ok()?;
-        if bytes.len() < 1000 {
-            let _ = fs::remove_file(&path);
-            println!("Found broken file on disk, ignoring");
-            return None;
+/// Known landing zone inside a cone.
+#[derive(Serialize)]
+struct ReachableLanding {
+    name: String,
+    /// Longitude and latitude like in the landing zone data.
+    center: Vec<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    additional_info: Option<String>,
+    /// Arrival altitude above sea level in meters.
+    height: f32,
+    /// Arrival height above ground in meters.
+    agl: f32,
+    /// Distance flown to the landing zone in meters.
+    distance: f32,
+    landability: Landability,
+    landing_prohibited: bool,
+}
+
+/// Known landing zones within a cone, highest arrival above ground first.
+fn reachable_landings(result: &SearchFromRequestResult) -> Vec<ReachableLanding> {
+    let grid = &result.height_grid;
+    let index = landing_zone_index();
+    let lower = [grid.longitudes.0, grid.latitudes.0];
+    let upper = [grid.longitudes.1, grid.latitudes.1];
+    let zones = index
+        .in_interval(&lower, &upper, None)
+        .map(|(_, zone, _)| zone)
+        .collect::<Vec<_>>();
+    let points = zones
+        .iter()
+        .map(|zone| (zone.center[1], zone.center[0]))
+        .collect::<Vec<_>>();
+
+    let mut landings = zones
+        .iter()
+        .zip(reachable_nodes_at(&result.explored, grid, &points))
+        .filter_map(|(zone, node)| {
+            let node = node?;
+            let ix = (node.ix.0 as usize, node.ix.1 as usize);
+            Some(ReachableLanding {
+                name: zone.name.clone(),
+                center: zone.center.clone(),
+                additional_info: zone.additional_info.clone(),
+                height: node.height,
+                agl: agl_of(node, grid),
+                distance: node.distance,
+                landability: landability_at(grid, ix.0, ix.1)
+                    .with_land_cover(result.land_cover[ix]),
+                landing_prohibited: result.landing_prohibited[ix],
+            })
+        })
+        .collect::<Vec<_>>();
+    landings.sort_by(|a, b| b.agl.total_cmp(&a.agl));
+    landings
+}
+
+/// Which of the known landing zones can be reached from the start, with the
+/// arrival altitude at each.
+#[get("/reachable_landings?<params..>")]
+fn get_reachable_landings(
+    params: ConeParams,
+) -> Result<Json<Vec<ReachableLanding>>, (Status, String)> {
+    let (lat, lon) = params.start()?;
+    if select_data_source(lat, lon, params.cell_size.unwrap_or(CELL_SIZE_DEFAULT)).is_none() {
+        return Result::Err(no_height_data());
+    }
+
+    let search_from_request_result = search_from_request(lat, lon, &params)?;
+
+    Result::Ok(Json(reachable_landings(&search_from_request_result)))
+}
+
+/// Flying site whose name has the slug `id`, see `datasets::slug`.
+fn flying_site(id: &str) -> Option<Location> {
+    flying_site_search_index()
+        .in_interval(&[-180.0, -90.0], &[180.0, 90.0], None)
+        .map(|(_, site, _)| site)
+        .find(|site| slug(&site.name) == id)
+        .cloned()
+}
+
+/// Cone of a site with the default parameters, for a first impression of
+/// where it goes.
+#[derive(Clone, Serialize)]
+struct QuickLookCone {
+    /// Bounds of the reachable cells.
+    lat: (f32, f32),
+    lon: (f32, f32),
+    area_km2: f32,
+    max_distance: f32,
+}
+
+#[derive(Serialize)]
+struct FlyingSiteDetail {
+    id: String,
+    #[serde(flatten)]
+    site: Location,
+    /// Terrain height at the site in meters, unset without height data.
+    elevation: Option<i16>,
+    /// Bearing in degrees the terrain at the site faces, unset on flat
+    /// terrain.
+    aspect: Option<f32>,
+    /// Directions the wind can come from to launch, see
+    /// `launch_wind_directions`.
+    wind_directions: Vec<&'static str>,
+    cone: Option<QuickLookCone>,
+}
+
+/// Aspect of the terrain and quick look cone at a site. Sites are looked at
+/// over and over again, so these are cached.
+#[cached(
+    size = 200,
+    key = "String",
+    convert = r#"{ format!("{lat}_{lon}") }"#,
+    result = true
+)]
+fn site_quick_look(lat: f32, lon: f32) -> Result<(Option<f32>, QuickLookCone), (Status, String)> {
+    let result = search_from_request(lat, lon, &ConeParams::default())?;
+    let grid = &result.height_grid;
+    let aspect = aspect_at(grid, result.start_ix.0 as usize, result.start_ix.1 as usize);
+
+    let stats = cone_stats(&result.explored, grid, None);
+    let (mut rows, mut cols) = ((GridIxType::MAX, 0), (GridIxType::MAX, 0));
+    for node in result.explored.iter().filter(|node| node.reachable) {
+        rows = (rows.0.min(node.ix.0), rows.1.max(node.ix.0));
+        cols = (cols.0.min(node.ix.1), cols.1.max(node.ix.1));
+    }
+    let south_west = grid.cell_center((rows.0 as usize, cols.0 as usize));
+    let north_east = grid.cell_center((rows.1 as usize, cols.1 as usize));
+    let cone = QuickLookCone {
+        lat: (south_west.0, north_east.0),
+        lon: (south_west.1, north_east.1),
+        area_km2: stats.area_km2,
+        max_distance: stats.max_distance,
+    };
+    Ok((aspect, cone))
+}
+
+/// Everything known about a flying site: its data, the terrain there and
+/// where the default cone from it reaches.
+#[get("/flying_site/<id>")]
+fn get_flying_site(id: &str) -> Result<Json<FlyingSiteDetail>, (Status, String)> {
+    let site =
+        flying_site(id).ok_or_else(|| (Status::NotFound, format!("Unknown flying site {id}")))?;
+    let (lat, lon) = (site.center[1], site.center[0]);
+
+    let (elevation, aspect, cone) = match select_data_source(lat, lon, CELL_SIZE_DEFAULT) {
+        Some(source) => {
+            let (aspect, cone) = site_quick_look(lat, lon)?;
+            (
+                Some(get_height_at_point_from(source, lat, lon)),
+                aspect,
+                Some(cone),
+            )
         }
-        Some(bytes)
-    } else {
-        None
+        None => (None, None, None),
+    };
+    Result::Ok(Json(FlyingSiteDetail {
+        id: id.to_string(),
+        site,
+        elevation,
+        aspect,
+        wind_directions: aspect.map(launch_wind_directions).unwrap_or_default(),
+        cone,
+    }))
+}
+
+/// Id, site and cone of a compared launch.
+type LaunchSearch = (String, Location, SearchFromRequestResult);
+
+/// Searches from the launches with the comma separated `ids`, in the order
+/// of the ids.
+fn launch_searches(
+    ids: &str,
+    search: impl Fn(f32, f32) -> Result<SearchFromRequestResult, (Status, String)>,
+) -> Result<Vec<LaunchSearch>, (Status, String)> {
+    parse_launch_ids(ids)
+        .map_err(|e| (Status::BadRequest, e))?
+        .into_iter()
+        .map(|id| {
+            let site = flying_site(id)
+                .ok_or_else(|| (Status::NotFound, format!("Unknown launch {id}")))?;
+            let result = search(site.center[1], site.center[0])?;
+            Ok((id.to_string(), site, result))
+        })
+        .collect()
+}
+
+#[derive(Serialize)]
+struct DirectionRange {
+    direction: &'static str,
+    /// Largest straight-line distance in meters to a reachable cell.
+    distance: f32,
+}
+
+#[derive(Serialize)]
+struct TargetReach {
+    reachable: bool,
+    /// Height above ground in meters on arrival, only set if reachable.
+    agl: Option<f32>,
+    /// Length of the glide path in meters, only set if reachable.
+    distance: Option<f32>,
+}
+
+#[derive(Serialize)]
+struct LaunchComparison {
+    id: String,
+    name: String,
+    /// `(lat, lon)` of the launch.
+    location: (f32, f32),
+    start_height: f32,
+    area_km2: f32,
+    max_distance: f32,
+    ranges: Vec<DirectionRange>,
+    /// Only set with `target_lat` and `target_lon`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    target: Option<TargetReach>,
+}
+
+#[derive(Serialize)]
+struct LaunchComparisonResponse {
+    launches: Vec<LaunchComparison>,
+    /// Bounds of `/compare_launches_overlay` for the same parameters.
+    overlay_lat: (f32, f32),
+    overlay_lon: (f32, f32),
+}
+
+fn compare_launch(
+    id: String,
+    site: Location,
+    result: &SearchFromRequestResult,
+    target: Option<(f32, f32)>,
+) -> LaunchComparison {
+    let grid = &result.height_grid;
+    let stats = cone_stats(&result.explored, grid, None);
+    let ranges = range_per_direction(&result.explored, result.start_ix, grid.cell_size);
+    let target = target.map(|(lat, lon)| {
+        let node = grid.index_of(lat, lon).and_then(|(row, col)| {
+            result
+                .explored
+                .iter()
+                .find(|node| node.reachable && node.ix == (row as GridIxType, col as GridIxType))
+        });
+        TargetReach {
+            reachable: node.is_some(),
+            agl: node.map(|node| agl_of(node, grid)),
+            distance: node.map(|node| node.distance),
+        }
+    });
+    LaunchComparison {
+        id,
+        location: (site.center[1], site.center[0]),
+        name: site.name,
+        start_height: result.height_at_start,
+        area_km2: stats.area_km2,
+        max_distance: stats.max_distance,
+        ranges: DIRECTIONS
+            .iter()
+            .zip(ranges)
+            .map(|(direction, distance)| DirectionRange {
+                direction,
+                distance,
+            })
+            .collect(),
+        target,
     }
 }
 
-#[cached(size = 4000, option = true)]
-fn load_webp_from_disk(path: String) -> Option<Vec<u8>> {
-    if Path::new(&path).exists() {
-        let bytes = fs::read(&path).ok()?;
-        if bytes.len() < 1000 {
-            let _ = fs::remove_file(&path);
-            println!("Found broken file on disk, ignoring");
-            return None;
+/// Cones of several flying sites with the same parameters side by side, so
+/// pilots can pick the launch for the day.
+#[get("/compare_launches?<ids>&<target_lat>&<target_lon>&<params..>")]
+fn get_compare_launches(
+    ids: &str,
+    params: ConeParams,
+    target_lat: Option<f32>,
+    target_lon: Option<f32>,
+) -> Result<Json<LaunchComparisonResponse>, (Status, String)> {
+    let target = match (target_lat, target_lon) {
+        (Some(lat), Some(lon)) => Some((lat, lon)),
+        (None, None) => None,
+        _ => {
+            return Err((
+                Status::BadRequest,
+                "target_lat and target_lon must be given together".to_string(),
+            ))
         }
-        Some(bytes)
-    } else {
-        None
+    };
+    let search = |lat, lon| search_from_request(lat, lon, &params);
+    let searches = launch_searches(ids, search)?;
+
+    let grids = searches
+        .iter()
+        .map(|(_, _, result)| &result.height_grid)
+        .collect::<Vec<_>>();
+    let extent = coverage_extent(&grids);
+    Ok(Json(LaunchComparisonResponse {
+        launches: searches
+            .iter()
+            .map(|(id, site, result)| compare_launch(id.clone(), site.clone(), result, target))
+            .collect(),
+        overlay_lat: extent.latitudes,
+        overlay_lon: extent.longitudes,
+    }))
+}
+
+/// Image of how many of the compared launches reach each cell, colored with
+/// `colormap` from one launch to all of them.
+#[get("/compare_launches_overlay?<ids>&<colormap>&<params..>")]
+fn get_compare_launches_overlay(
+    ids: &str,
+    params: ConeParams,
+    colormap: Option<&str>,
+) -> Result<(ContentType, Vec<u8>), (Status, String)> {
+    let ramp = color_ramp_from_request(colormap)?;
+    let search = |lat, lon| search_from_request(lat, lon, &params);
+    let searches = launch_searches(ids, search)?;
+
+    let cones = searches
+        .iter()
+        .map(|(_, _, result)| (&result.height_grid, result.explored.as_slice()))
+        .collect::<Vec<_>>();
+    let coverage = combined_coverage(&cones);
+    let (rows, cols) = coverage.counts.dim();
+    let max_count = searches.len().max(2) as f32;
+
+    let mut img = DynamicImage::new_rgba8(cols.max(1) as u32, rows.max(1) as u32);
+    for ((row, col), count) in coverage.counts.indexed_iter() {
+        if *count == 0 {
+            continue;
+        }
+        let s = (*count as f32 - 1.0) / (max_count - 1.0);
+        img.put_pixel(
+            col as u32,
+            (rows - row - 1) as u32,
+            Rgba(f32_color_to_u8(ramp.color_at(s))),
+        );
+    }
+    let mut c = Cursor::new(Vec::new());
+    img.write_to(&mut c, ImageFormat::Png).expect("");
+    Ok((ContentType::PNG, c.into_inner()))
+}
+
+async fn load_from_storage(key: &str) -> Option<Vec<u8>> {
+    let bytes = cache_storage().get(key).await?;
+    if bytes.len() < 1000 {
+        if !app_config().read_only {
+            let _ = cache_storage().remove(key).await;
+        }
+        warn!(parent: &current_request_span(), key, "Ignoring a broken file in the storage");
+        return None;
+    }
+    Some(bytes)
+}
+
+#[cached(
+    ty = "SizedCache<String, Return<Vec<u8>>>",
+    create = "{ SizedCache::with_size(app_config().server.png_cache_size) }",
+    option = true,
+    with_cached_flag = true
+)]
+async fn load_png_from_storage(storage_key: String) -> Option<Return<Vec<u8>>> {
+    load_from_storage(&storage_key).await.map(Return::new)
+}
+
+#[cached(
+    ty = "SizedCache<String, Return<Vec<u8>>>",
+    create = "{ SizedCache::with_size(app_config().server.webp_cache_size) }",
+    option = true,
+    with_cached_flag = true
+)]
+async fn load_webp_from_storage(storage_key: String) -> Option<Return<Vec<u8>>> {
+    load_from_storage(&storage_key).await.map(Return::new)
+}
+
+/// Counts a map tile lookup, `tile` is the tile if it was found in memory
+/// or in the cache storage. Tiles not in memory are looked up in the
+/// storage, tiles not there are fetched upstream.
+fn record_map_tile_lookup(tile: Option<&Return<Vec<u8>>>) {
+    let in_memory = tile.is_some_and(|tile| tile.was_cached);
+    record_cache_access(CacheTier::Png, in_memory);
+    if !in_memory {
+        record_cache_access(CacheTier::Tiles, tile.is_some());
     }
 }
 
 fn reqwest_client() -> &'static Client {
     static INSTANCE: OnceCell<Client> = OnceCell::new();
     INSTANCE.get_or_init(|| {
-        println!("Building reqwest client...");
+        info!("Building reqwest client");
         ClientBuilder::new()
             .danger_accept_invalid_certs(true)
             .build()
@@ -1294,29 +6185,38 @@ fn reqwest_client() -> &'static Client {
 }
 
 async fn get_tile(s: String, z: u8, x: u32, y: u32) -> Result<(ContentType, Vec<u8>), Status> {
-    // Load from data/tiles/ if exists, otherwise fetch from server
-    let path_webp = format!("data/tiles_webp/{s}/{z}/{x}/{y}.webp");
-    let path_png = format!("data/tiles/{s}/{z}/{x}/{y}.png");
-    if let Some(bytes) = load_webp_from_disk(path_webp.clone()) {
-        Result::Ok((ContentType::WEBP, bytes))
-    } else if let Some(bytes) = load_png_from_disk(path_png.clone()) {
-        Result::Ok((ContentType::PNG, bytes))
+    // Load from tiles/ in the cache storage if it exists, otherwise fetch from server
+    let key_webp = format!("tiles_webp/{s}/{z}/{x}/{y}.webp");
+    let key_png = format!("tiles/{s}/{z}/{x}/{y}.png");
+    let webp = load_webp_from_storage(key_webp).await;
+    let png = match webp {
+        Some(_) => None,
+        None => load_png_from_storage(key_png.clone()).await,
+    };
+    record_map_tile_lookup(webp.as_ref().or(png.as_ref()));
+    if let Some(bytes) = webp {
+        Result::Ok((ContentType::WEBP, bytes.value))
+    } else if let Some(bytes) = png {
+        Result::Ok((ContentType::PNG, bytes.value))
+    } else if app_config().read_only {
+        Result::Err(Status::NotFound)
     } else {
-        println!("Fetching tile {s}/{z}/{x}/{y}");
         let url = format!("https://{s}.tile.opentopomap.org/{z}/{x}/{y}.png");
+        info!(parent: &current_request_span(), %url, "Fetching tile");
         let response = reqwest_client().get(&url).send().await.map_err(|e| {
-            println!("{e}");
+            warn!(parent: &current_request_span(), %url, "Could not fetch tile: {e}");
             Status::InternalServerError
         })?;
         let bytes = response.bytes().await.map_err(|e| {
-            println!("{e}");
+            warn!(parent: &current_request_span(), %url, "Could not fetch tile: {e}");
             Status::InternalServerError
         })?;
 
-        // Save to data/tiles/ for future use
-        fs::create_dir_all(format!("data/tiles/{s}/{z}/{x}"))
+        // Save to tiles/ for future use
+        cache_storage()
+            .put(&key_png, &bytes)
+            .await
             .map_err(|_| Status::InternalServerError)?;
-        fs::write(&path_png, &bytes).map_err(|_| Status::InternalServerError)?;
 
         Result::Ok((ContentType::PNG, bytes.to_vec()))
     }
@@ -1331,9 +6231,14 @@ async fn get_cached_proxy_tile(
     upstream_url: String,
     content_type: ContentType,
 ) -> Result<(ContentType, Vec<u8>), Status> {
-    let path = format!("data/tiles_{provider}/{z}/{x}/{y}.{extension}");
-    if let Some(bytes) = load_png_from_disk(path.clone()) {
-        return Result::Ok((content_type, bytes));
+    let key = format!("tiles_{provider}/{z}/{x}/{y}.{extension}");
+    let tile = load_png_from_storage(key.clone()).await;
+    record_map_tile_lookup(tile.as_ref());
+    if let Some(bytes) = tile {
+        return Result::Ok((content_type, bytes.value));
+    }
+    if app_config().read_only {
+        return Result::Err(Status::NotFound);
     }
 
     let response = reqwest_client()
@@ -1341,21 +6246,36 @@ async fn get_cached_proxy_tile(
         .send()
         .await
         .map_err(|e| {
-            println!("{e}");
+            warn!(parent: &current_request_span(), url = %upstream_url, "Could not fetch tile: {e}");
             Status::InternalServerError
         })?;
     let bytes = response.bytes().await.map_err(|e| {
-        println!("{e}");
+        warn!(parent: &current_request_span(), url = %upstream_url, "Could not fetch tile: {e}");
         Status::InternalServerError
     })?;
 
-    fs::create_dir_all(format!("data/tiles_{provider}/{z}/{x}"))
+    cache_storage()
+        .put(&key, &bytes)
+        .await
         .map_err(|_| Status::InternalServerError)?;
-    fs::write(&path, &bytes).map_err(|_| Status::InternalServerError)?;
 
     Result::Ok((content_type, bytes.to_vec()))
 }
 
+/// Archives of precomputed cones, built by the `precompute_cones` binary.
+#[get("/datasets/cones/<file>")]
+async fn get_cone_dataset(file: &str) -> Result<(ContentType, Vec<u8>), Status> {
+    let region = file
+        .strip_suffix(".tar.zst")
+        .filter(|region| is_valid_region_name(region))
+        .ok_or(Status::NotFound)?;
+    let bytes = cache_storage()
+        .get(&dataset_key(region))
+        .await
+        .ok_or(Status::NotFound)?;
+    Result::Ok((ContentType::new("application", "zstd"), bytes))
+}
+
 #[get("/opentopomap/<s>/<z>/<x>/<y_p>")]
 async fn get_opentopomap_tile(
     s: String,
@@ -1404,11 +6324,11 @@ struct Stats {
 #[get("/stats")]
 fn get_stats() -> Result<rocket::serde::json::Json<Stats>, Status> {
     let mut webp_cache_size = 0;
-    if let Ok(guard) = LOAD_WEBP_FROM_DISK.try_lock() {
+    if let Ok(guard) = LOAD_WEBP_FROM_STORAGE.try_lock() {
         webp_cache_size = guard.cache_size();
     }
     let mut png_cache_size = 0;
-    if let Ok(guard) = LOAD_PNG_FROM_DISK.try_lock() {
+    if let Ok(guard) = LOAD_PNG_FROM_STORAGE.try_lock() {
         png_cache_size = guard.cache_size();
     }
     let mut cone_cache_size = 0;
@@ -1417,8 +6337,9 @@ fn get_stats() -> Result<rocket::serde::json::Json<Stats>, Status> {
     }
     let hgt_read_cache_size = cache_sizes();
 
-    let folder_size_png = get_size("data/tiles/").unwrap_or(0);
-    let folder_size_webp = get_size("data/tiles_webp/").unwrap_or(0);
+    let tile_cache_dir = app_config().server.tile_cache_dir();
+    let folder_size_png = get_size(tile_cache_dir.join("tiles")).unwrap_or(0);
+    let folder_size_webp = get_size(tile_cache_dir.join("tiles_webp")).unwrap_or(0);
 
     let num_searches = *num_searches().lock().unwrap();
     let num_index_accesses = *num_index_accesses().lock().unwrap();
@@ -1435,28 +6356,677 @@ fn get_stats() -> Result<rocket::serde::json::Json<Stats>, Status> {
     }))
 }
 
+#[derive(Serialize)]
+struct TileVerificationReport {
+    manifest_found: bool,
+    checked_checksums: bool,
+    problems: Vec<TileProblem>,
+}
+
+fn verify_data_dir(check_checksums: bool) -> TileVerificationReport {
+    let data_dir = data_dir();
+    let mut problems = verify_tile_shapes(data_dir);
+
+    let manifest = load_manifest(data_dir);
+    if let Some(Ok(entries)) = &manifest {
+        problems.extend(verify_tiles(data_dir, entries, check_checksums));
+    } else if let Some(Err(e)) = &manifest {
+        error!("Could not parse hgt manifest: {e}");
+    }
+
+    TileVerificationReport {
+        manifest_found: manifest.is_some(),
+        checked_checksums: check_checksums && manifest.is_some(),
+        problems,
+    }
+}
+
+/// Searches per day, the busiest regions and the average flight parameters
+/// of the last 30 days, with noise added so single users can't be told
+/// apart, see `UsageStats::public`.
+#[get("/stats/public")]
+fn get_public_stats() -> Json<PublicUsage> {
+    Json(usage_stats().public(current_day()))
+}
+
+/// Hits and misses of the caches per tier and endpoint and the latencies
+/// per endpoint, in the Prometheus text format.
+#[get("/metrics")]
+fn get_metrics() -> (ContentType, String) {
+    (
+        ContentType::new("text", "plain").with_params(("version", "0.0.4")),
+        cache_metrics().to_prometheus() + latency_metrics().to_prometheus().as_str(),
+    )
+}
+
+/// Secret of the `/admin` routes, managed only when one is configured.
+struct AdminToken(String);
+
+/// Guard of the `/admin` routes, passes requests with the `admin_token` as
+/// bearer token.
+struct Admin;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Admin {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> rocket::request::Outcome<Self, ()> {
+        let token = request.rocket().state::<AdminToken>();
+        let sent = request
+            .headers()
+            .get_one("Authorization")
+            .and_then(|value| value.strip_prefix("Bearer "));
+        match (token, sent) {
+            (Some(AdminToken(token)), Some(sent)) if same_secret(token, sent) => {
+                rocket::request::Outcome::Success(Admin)
+            }
+            _ => rocket::request::Outcome::Error((Status::Unauthorized, ())),
+        }
+    }
+}
+
+/// Compares all bytes, so the time taken does not tell how much of a secret
+/// was guessed.
+fn same_secret(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Statistics of the in-memory search cache.
+#[get("/admin/cache_stats")]
+fn get_cache_stats(_admin: Admin) -> Json<CacheStats> {
+    Json(SEARCH_FROM_POINT_MEMOIZED.lock().unwrap().stats())
+}
+
+/// Drops all searches and DEM tiles kept in memory, e.g. after the DEM was
+/// updated. The persistent cone cache is keyed by the DEM version and not
+/// affected.
+#[post("/admin/cache_clear")]
+fn post_cache_clear(_admin: Admin) -> String {
+    let cleared = clear_search_caches();
+    clear_tile_cache();
+    format!("Cleared {cleared} cached searches")
+}
+
+/// Drops the searches and the images rendered from them kept in memory,
+/// returns how many searches were cached.
+fn clear_search_caches() -> usize {
+    let mut cleared = 0;
+    {
+        let mut cache = SEARCH_FROM_POINT_MEMOIZED.lock().unwrap();
+        cleared += cache.cache_size();
+        cache.cache_clear();
+    }
+    {
+        let mut cache = SEARCH_INCREMENTALLY_MEMOIZED.lock().unwrap();
+        cleared += cache.cache_size();
+        cache.cache_clear();
+    }
+    SEARCH_FROM_POINTS_MEMOIZED.lock().unwrap().cache_clear();
+    raster_cache().lock().unwrap().cache_clear();
+    cleared
+}
+
+/// Whether tiles are verified with checksums, which reads all of them, so
+/// only one verification with checksums runs at a time.
+static VERIFYING_CHECKSUMS: AtomicBool = AtomicBool::new(false);
+
+#[get("/admin/verify_tiles?<checksums>")]
+async fn get_verify_tiles(
+    _admin: Admin,
+    checksums: Option<bool>,
+) -> Result<Json<TileVerificationReport>, (Status, String)> {
+    if !checksums.unwrap_or(false) {
+        return Ok(Json(verify_data_dir(false)));
+    }
+    if VERIFYING_CHECKSUMS.swap(true, Ordering::AcqRel) {
+        return Err((
+            Status::TooManyRequests,
+            "Tiles are already verified with checksums".to_string(),
+        ));
+    }
+    // Reset in the task, which finishes even if the request is dropped.
+    task::spawn_blocking(|| {
+        let report = verify_data_dir(true);
+        VERIFYING_CHECKSUMS.store(false, Ordering::Release);
+        report
+    })
+    .await
+    .map(Json)
+    .map_err(|e| (Status::InternalServerError, e.to_string()))
+}
+
+/// Result of one step of `--check`, `Err` holds the reason of the failure.
+type CheckResult = Result<String, String>;
+
+/// Name of a `--check` step and the function running it.
+type Check = (&'static str, fn() -> CheckResult);
+
+fn check_config() -> CheckResult {
+    let config = load_app_config()?;
+    if config.storage.backend == StorageBackend::S3
+        && (config.storage.bucket.is_empty() || config.storage.endpoint.is_empty())
+    {
+        return Err("S3 storage needs a bucket and an endpoint".to_string());
+    }
+    if !config.server.data_dir.is_dir() {
+        return Err(format!(
+            "Data directory {} does not exist",
+            config.server.data_dir.display()
+        ));
+    }
+    config.server.parameter_limits.validate()?;
+    log_filter(&config.logging)?;
+    if let Some(template) = &config.dem_url_template {
+        let has_tile = template.contains("{tile}");
+        if !(has_tile || template.contains("{lat}") && template.contains("{lon}")) {
+            return Err("dem_url_template needs {tile} or {lat} and {lon}".to_string());
+        }
+    }
+    config.refresh.validate()?;
+    config.preload.validate()?;
+    config.cone_jobs.validate()?;
+    if config.refresh.dem && config.dem_url_template.is_none() {
+        return Err("The DEM refresh needs a dem_url_template".to_string());
+    }
+    Ok("configuration is valid".to_string())
+}
+
+/// Region called `name` in `regions.txt` of the data directory.
+fn find_region(name: &str) -> Result<DatasetRegion, String> {
+    let content = fs::read_to_string(data_dir().join(REGIONS_FILE_NAME))
+        .map_err(|e| format!("Could not read {REGIONS_FILE_NAME}: {e}"))?;
+    parse_regions(&content)?
+        .into_iter()
+        .find(|r| r.name == name)
+        .ok_or_else(|| format!("Unknown home region {name}"))
+}
+
+fn check_home_region() -> CheckResult {
+    let Some(name) = load_app_config()?.home_region else {
+        return Ok("no home region configured, skipped".to_string());
+    };
+    let region = find_region(&name)?;
+
+    let missing = missing_tiles(region.latitudes, region.longitudes);
+    if !missing.is_empty() {
+        return Err(format!(
+            "{} DEM tiles missing for {name}, e.g. {:?}",
+            missing.len(),
+            missing[0]
+        ));
+    }
+    Ok(format!("DEM covers {name}"))
+}
+
+fn check_dem_tiles() -> CheckResult {
+    let report = verify_data_dir(false);
+    let problems = report.problems;
+    if !problems.is_empty() {
+        return Err(format!(
+            "{} bad DEM tiles, e.g. {:?}",
+            problems.len(),
+            problems[0]
+        ));
+    }
+    Ok(if report.manifest_found {
+        "DEM tiles match the manifest".to_string()
+    } else {
+        "DEM tile sizes are valid, no manifest found".to_string()
+    })
+}
+
+fn check_search_indexes() -> CheckResult {
+    if !load_app_config()?.endpoints.location_search {
+        return Ok("location search disabled, skipped".to_string());
+    }
+    // The loaders panic on bad data, the panic message is printed by the hook.
+    let flying_sites = std::panic::catch_unwind(|| {
+        search_index();
+        flying_site_search_index()
+            .in_interval(&[-180.0, -90.0], &[180.0, 90.0], None)
+            .count()
+    })
+    .map_err(|_| "Could not load the search indexes".to_string())?;
+    if flying_sites == 0 {
+        return Err("Flying site index is empty".to_string());
+    }
+    Ok(format!(
+        "search indexes loaded, {flying_sites} flying sites"
+    ))
+}
+
+fn check_synthetic_search() -> CheckResult {
+    let query = query_from_request(&ConeParams::default()).map_err(|(_, message)| message)?;
+    let result = search_synthetic(
+        SyntheticTerrain::Cone,
+        47.0,
+        11.0,
+        load_app_config()?.server.parameter_limits.cell_size.max,
+        query.search_query(),
+    )
+    .map_err(|e| e.to_string())?;
+    let reachable = result.explored.iter().filter(|n| n.reachable).count();
+    if reachable <= 1 {
+        return Err("Synthetic search found no reachable cells".to_string());
+    }
+    Ok(format!("synthetic search reached {reachable} cells"))
+}
+
+/// Validates the deployment without starting the server, for use in
+/// deployment pipelines. Returns false if any check failed.
+fn self_check() -> bool {
+    let checks: [Check; 5] = [
+        ("config", check_config),
+        ("dem_tiles", check_dem_tiles),
+        ("home_region", check_home_region),
+        ("search_index", check_search_indexes),
+        ("search", check_synthetic_search),
+    ];
+    let mut ok = true;
+    for (name, check) in checks {
+        match check() {
+            Ok(message) => println!("[ok] {name}: {message}"),
+            Err(message) => {
+                println!("[failed] {name}: {message}");
+                ok = false;
+            }
+        }
+    }
+    ok
+}
+
+/// Requests of `--load-test` if no number is given.
+const LOAD_TEST_DEFAULT_REQUESTS: usize = 500;
+/// Requests of a load test that did not finish by then are reported as stalled.
+const LOAD_TEST_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Settings of the server of a load test: the configured ones without the
+/// location search and the frontend, as their files are not needed for
+/// synthetic terrain.
+fn load_test_config() -> Result<AppConfig, String> {
+    let mut config = load_app_config()?;
+    config.endpoints.location_search = false;
+    config.server.static_dir = None;
+    Ok(config)
+}
+
+/// Message of a panic caught by `catch_unwind` or `JoinHandle::join`.
+fn panic_message(panic: &(dyn std::any::Any + Send)) -> &str {
+    panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic")
+}
+
+/// Sends `requests` mixed requests on synthetic terrain all at once to an
+/// in-process server, see `load_test_config`, to find deadlocks and
+/// contention in the global caches. Returns false if the server failed, a
+/// request failed with a server error or did not finish within `timeout`.
+fn load_test(requests: usize, timeout: Duration) -> bool {
+    let config = match load_test_config() {
+        Ok(config) => config,
+        Err(e) => {
+            println!("{e}");
+            return false;
+        }
+    };
+    let uris = load_test_uris(requests);
+    let (sender, receiver) = mpsc::channel();
+    let task_uris = uris.clone();
+    // The server gets its own thread, so stalled requests can't keep the
+    // timeout below from being reported.
+    let server_thread = std::thread::spawn(move || {
+        let runtime = rocket::tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .expect("Could not start the runtime");
+        runtime.block_on(async move {
+            let client = Arc::new(
+                LocalClient::untracked(server(&config))
+                    .await
+                    .expect("Could not start the server"),
+            );
+            // Released together, so the first requests hit empty caches at once.
+            let barrier = Arc::new(Barrier::new(task_uris.len()));
+            let tasks = task_uris
+                .into_iter()
+                .enumerate()
+                .map(|(i, uri)| {
+                    let (client, barrier, sender) =
+                        (client.clone(), barrier.clone(), sender.clone());
+                    rocket::tokio::spawn(async move {
+                        barrier.wait().await;
+                        let start = Instant::now();
+                        let response = client.get(uri).dispatch().await;
+                        let status = response.status().code;
+                        response.into_bytes().await;
+                        let _ = sender.send((i, status, start.elapsed()));
+                    })
+                })
+                .collect::<Vec<_>>();
+            for task in tasks {
+                let _ = task.await;
+            }
+        });
+    });
+
+    println!("Sending {} concurrent requests...", uris.len());
+    let deadline = Instant::now() + timeout;
+    let mut pending = (0..uris.len()).collect::<HashSet<_>>();
+    // Durations in microseconds and statuses per path.
+    let mut results: BTreeMap<&str, (Vec<u64>, BTreeMap<u16, usize>)> = BTreeMap::new();
+    let mut server_errors = 0;
+    while !pending.is_empty() {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        let (i, status, duration) = match receiver.recv_timeout(timeout) {
+            Ok(result) => result,
+            // Every request holds a sender, so they are all gone once the
+            // server thread ended.
+            Err(RecvTimeoutError::Disconnected) => {
+                if let Err(panic) = server_thread.join() {
+                    println!("The server failed: {}", panic_message(&*panic));
+                    return false;
+                }
+                break;
+            }
+            Err(RecvTimeoutError::Timeout) => break,
+        };
+        pending.remove(&i);
+        if status >= 500 {
+            server_errors += 1;
+            println!("Status {status} for {}", uris[i]);
+        }
+        let (durations, statuses) = results.entry(uri_path(&uris[i])).or_default();
+        durations.push(duration.as_micros() as u64);
+        *statuses.entry(status).or_default() += 1;
+    }
+
+    println!(
+        "{:<20} {:>6} {:>10} {:>10} {:>10}  statuses",
+        "path", "count", "p50 ms", "p95 ms", "max ms"
+    );
+    for (path, (durations, statuses)) in results.iter() {
+        let Some(summary) = latency_summary(durations) else {
+            continue;
+        };
+        let statuses = statuses
+            .iter()
+            .map(|(status, count)| format!("{status}: {count}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        println!(
+            "{:<20} {:>6} {:>10.1} {:>10.1} {:>10.1}  {statuses}",
+            path,
+            summary.count,
+            summary.p50 as f64 / 1000.0,
+            summary.p95 as f64 / 1000.0,
+            summary.max as f64 / 1000.0,
+        );
+    }
+
+    if !pending.is_empty() {
+        let mut stalled = pending.into_iter().collect::<Vec<_>>();
+        stalled.sort();
+        println!(
+            "{} requests stalled or failed without a response, e.g. {}",
+            stalled.len(),
+            uris[stalled[0]]
+        );
+        return false;
+    }
+    println!("{server_errors} requests failed with a server error");
+    server_errors == 0
+}
+
+/// Answers the requests of a disabled route.
+#[derive(Clone)]
+struct Gone;
+
+#[rocket::async_trait]
+impl Handler for Gone {
+    async fn handle<'r>(
+        &self,
+        request: &'r rocket::Request<'_>,
+        _: rocket::Data<'r>,
+    ) -> Outcome<'r> {
+        Outcome::from(
+            request,
+            (Status::Gone, "This endpoint is disabled on this server"),
+        )
+    }
+}
+
+/// Mounts a group of routes, or if the group is disabled, routes answering
+/// the same requests with 410 Gone.
+fn mount_group(server: Rocket<Build>, enabled: bool, routes: Vec<Route>) -> Rocket<Build> {
+    if enabled {
+        return server.mount("/", routes);
+    }
+    let gone = routes
+        .into_iter()
+        .map(|mut route| {
+            route.handler = Box::new(Gone);
+            route
+        })
+        .collect::<Vec<_>>();
+    server.mount("/", gone)
+}
+
 #[launch]
 fn rocket() -> _ {
-    search_index();
-    flying_site_search_index();
+    // Before anything is read from it. An invalid configuration is reported
+    // by `--check` or when the settings are first used.
+    match load_app_config() {
+        Ok(config) => {
+            init_logging(&config.logging);
+            set_data_dir(config.server.data_dir).expect("Data directory is only set once");
+        }
+        Err(_) => init_logging(&LogConfig::default()),
+    }
+
+    if std::env::args().any(|arg| arg == "--check") {
+        std::process::exit(if self_check() { 0 } else { 1 });
+    }
+    let load_test_requests = std::env::args().find_map(|arg| match arg.as_str() {
+        "--load-test" => Some(LOAD_TEST_DEFAULT_REQUESTS),
+        _ => arg.strip_prefix("--load-test=").map(|requests| {
+            requests
+                .parse()
+                .expect("--load-test needs the number of requests")
+        }),
+    });
+    if let Some(requests) = load_test_requests {
+        std::process::exit(if load_test(requests, LOAD_TEST_TIMEOUT) {
+            0
+        } else {
+            1
+        });
+    }
+
+    server(app_config())
+}
+
+/// The server with the routes and fairings enabled by `config`.
+fn server(config: &AppConfig) -> Rocket<Build> {
+    set_current_endpoint(current_endpoint);
 
-    rocket::build()
+    if config.endpoints.location_search {
+        search_index();
+        flying_site_search_index();
+        landing_zone_index();
+    }
+
+    // Only sizes are checked on startup, checksums take minutes for all tiles.
+    let report = verify_data_dir(false);
+    for problem in report.problems.iter() {
+        warn!("Bad DEM tile: {:?}", problem);
+    }
+
+    if config.read_only {
+        info!("Running in read-only mode");
+    }
+    let disabled = config.endpoints.disabled();
+    if !disabled.is_empty() {
+        info!("Disabled endpoints: {}", disabled.join(", "));
+    }
+
+    let mut server = rocket::build()
         .mount("/", routes![index])
         .mount("/", routes![get_flight_cone])
-        .mount("/", routes![get_flight_cone_stream])
+        .mount("/", routes![post_flight_cone_job, get_flight_cone_job])
+        .mount("/", routes![post_flight_cone_batch])
+        .mount(
+            "/",
+            routes![get_flight_cone_stream, get_flight_cone_chunked],
+        )
         .mount("/", routes![get_raw_height_image])
         .mount("/", routes![get_flight_cone_bounds])
-        .mount("/", routes![search])
-        .mount("/", routes![search_flying_site])
-        .mount("/", routes![get_agl_image])
+        .mount("/", routes![get_suggest_view])
+        .mount("/", routes![get_agl_image, get_agl_tile])
+        .mount("/", routes![get_flight_cone_tile])
         .mount("/", routes![get_height_image])
-        .mount("/", routes![get_kml])
-        .mount("/", routes![get_opentopomap_tile])
-        .mount("/", routes![get_openstreetmap_tile])
-        .mount("/", routes![get_satellite_tile])
-        .mount("/", routes![get_stats])
+        .mount("/", routes![get_legend])
+        .mount("/", routes![get_metrics])
+        .mount("/", routes![get_stats, get_public_stats])
         .mount("/", routes![get_height_map])
         .mount("/", routes![get_height_map_meta])
         .mount("/", routes![get_height_map_image])
-        .mount("/static", FileServer::from("./static"))
+        .attach(EndpointTracker::default())
+        .attach(ConeJobWorkers {
+            workers: config.cone_jobs.workers,
+        });
+
+    match &config.server.static_dir {
+        Some(dir) if dir.is_dir() => server = server.mount("/static", FileServer::from(dir)),
+        Some(dir) => warn!("Not serving /static, {} does not exist", dir.display()),
+        None => {}
+    }
+
+    let endpoints = &config.endpoints;
+    server = mount_group(
+        server,
+        endpoints.location_search,
+        [
+            routes![search, search_chunked],
+            routes![
+                search_flying_site,
+                get_flying_site,
+                get_reachable_landings,
+                get_compare_launches,
+                get_compare_launches_overlay
+            ],
+        ]
+        .concat(),
+    );
+    server = mount_group(
+        server,
+        endpoints.elevation_api,
+        routes![get_elevation_lookup, post_elevation_lookup],
+    );
+    server = mount_group(server, endpoints.airspaces, routes![get_airspaces]);
+    server = mount_group(
+        server,
+        endpoints.exports,
+        routes![
+            get_kml,
+            get_kmz,
+            get_flight_cone_geojson,
+            get_flight_cone_outline,
+            get_czml,
+            get_agl_contours,
+            get_flight_cone_gpx,
+            get_landing_zones,
+            get_export_zip,
+            get_flight_path,
+            get_margin_at,
+            get_plan_route,
+            get_hike_isochrone,
+            get_launch_scores,
+            get_launch_score_image,
+            get_elevation_profile,
+            post_analyze_igc,
+            post_flight_cone_region
+        ],
+    );
+    server = mount_group(server, endpoints.datasets, routes![get_cone_dataset]);
+    server = mount_group(
+        server,
+        endpoints.tile_proxy,
+        routes![
+            get_opentopomap_tile,
+            get_openstreetmap_tile,
+            get_satellite_tile
+        ],
+    );
+
+    match config
+        .admin_token
+        .as_deref()
+        .filter(|token| !token.is_empty())
+    {
+        Some(_) if config.read_only => warn!("Ignoring admin_token in read-only mode"),
+        Some(token) => {
+            server = server.manage(AdminToken(token.to_string()));
+            server = server.mount("/", routes![get_verify_tiles]);
+            server = server.mount("/", routes![get_cache_stats, post_cache_clear]);
+            server = server.mount("/", routes![put_no_landing_zones]);
+        }
+        None => info!("No admin_token configured, the admin routes are not mounted"),
+    }
+    if config.replay_log {
+        if config.read_only {
+            warn!("Ignoring replay_log in read-only mode");
+        } else {
+            server = server.attach(replay_logger());
+        }
+    }
+    if let Some(url_template) = &config.dem_url_template {
+        if config.read_only {
+            warn!("Ignoring dem_url_template in read-only mode");
+        } else {
+            server = server.attach(DemDownloader::new(url_template.clone()));
+        }
+    }
+    if config.refresh.interval_hours > 0 {
+        if config.read_only {
+            warn!("Ignoring refresh in read-only mode");
+        } else {
+            let mut jobs = vec![ScheduledJob {
+                name: "site data",
+                run: || Box::pin(refresh_site_data()),
+            }];
+            if config.refresh.dem {
+                jobs.push(ScheduledJob {
+                    name: "DEM",
+                    run: || Box::pin(refresh_dem()),
+                });
+            }
+            server = server.attach(Scheduler {
+                jobs,
+                config: config.refresh.clone(),
+            });
+        }
+    }
+    if let Some(region) = &config.home_region {
+        server = server.attach(Preloader {
+            region: region.clone(),
+            config: config.preload.clone(),
+        });
+    }
+    if config.debug_endpoints {
+        server = server.mount("/", routes![get_exploration_order]);
+        server = server.mount("/", routes![get_debug_ui]);
+        server = server.mount("/", routes![get_debug_ui_file]);
+    }
+
+    server
 }
+
+#[cfg(test)]
+#[path = "./main_test.rs"]
+mod main_test;
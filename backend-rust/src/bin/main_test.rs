@@ -0,0 +1,145 @@
+use std::time::{Duration, Instant};
+
+use backend_rust::{search::SearchQuery, storage::uri_encode_path};
+use rocket::{
+    http::{ContentType, Header, Status},
+    local::blocking::Client,
+};
+
+use super::{
+    load_test, server, AppConfig, SearchQueryHashable, TileDownloads, DEM_RETRY_DELAY,
+    DEM_RETRY_DELAY_MAX,
+};
+
+/// Settings of a server that needs no files, see `load_test_config`.
+fn test_config() -> AppConfig {
+    let mut config = AppConfig::default();
+    config.endpoints.location_search = false;
+    config.server.static_dir = None;
+    config
+}
+
+fn client(config: AppConfig) -> Client {
+    Client::untracked(server(&config)).unwrap()
+}
+
+#[test]
+fn test_admin_routes() {
+    let put_zones = |client: &Client, token: Option<&str>| {
+        let mut request = client
+            .put("/admin/no_landing_zones")
+            .header(ContentType::JSON)
+            .body("{}");
+        if let Some(token) = token {
+            request = request.header(Header::new("Authorization", format!("Bearer {token}")));
+        }
+        request.dispatch().status()
+    };
+
+    let client_without_token = client(test_config());
+    assert_eq!(put_zones(&client_without_token, None), Status::NotFound);
+
+    let mut config = test_config();
+    config.admin_token = Some("secret".to_string());
+    let client_with_token = client(config);
+    assert_eq!(put_zones(&client_with_token, None), Status::Unauthorized);
+    assert_eq!(
+        put_zones(&client_with_token, Some("guess")),
+        Status::Unauthorized
+    );
+    assert_eq!(
+        client_with_token
+            .get("/admin/verify_tiles?checksums=true")
+            .dispatch()
+            .status(),
+        Status::Unauthorized
+    );
+    assert_eq!(
+        client_with_token
+            .post("/admin/cache_clear")
+            .dispatch()
+            .status(),
+        Status::Unauthorized
+    );
+    assert_eq!(
+        client_with_token
+            .get("/admin/cache_stats")
+            .header(Header::new("Authorization", "Bearer secret"))
+            .dispatch()
+            .status(),
+        Status::Ok
+    );
+
+    let mut config = test_config();
+    config.admin_token = Some("secret".to_string());
+    config.read_only = true;
+    let read_only_client = client(config);
+    assert_eq!(
+        put_zones(&read_only_client, Some("secret")),
+        Status::NotFound
+    );
+}
+
+#[test]
+fn test_load_test() {
+    assert!(load_test(48, Duration::from_secs(120)));
+}
+
+#[test]
+fn test_scripted_cone() {
+    let client = client(test_config());
+    let cone = |script: &str, reverse: bool| {
+        client
+            .get(format!(
+                "/flight_cone?lat=47.4&lon=11.1&synthetic=cone&reverse={reverse}&script={}",
+                uri_encode_path(script)
+            ))
+            .dispatch()
+            .status()
+    };
+
+    if cfg!(feature = "scripting") {
+        assert_eq!(
+            cone("if agl < 100.0 { false } else { 1.5 }", false),
+            Status::Ok
+        );
+        assert_eq!(cone("if agl <", false), Status::BadRequest);
+        assert_eq!(cone("loop {}", false), Status::UnprocessableEntity);
+    } else {
+        assert_eq!(cone("1.5", false), Status::BadRequest);
+    }
+    assert_eq!(cone("1.5", true), Status::BadRequest);
+}
+
+#[test]
+fn test_canonical_string_of_high_starts() {
+    let key = |start_height| {
+        let query = SearchQuery::builder()
+            .start_height_m(start_height)
+            .build()
+            .unwrap();
+        SearchQueryHashable::from_query(query).canonical_string()
+    };
+    assert_ne!(key(2500.0), key(3000.0));
+    assert_eq!(key(3000.0), key(3000.0));
+}
+
+#[test]
+fn test_tile_download_backoff() {
+    let now = Instant::now();
+    let mut downloads = TileDownloads::default();
+    assert!(downloads.may_download(now));
+
+    downloads.failed(now);
+    assert!(!downloads.may_download(now));
+    assert!(downloads.may_download(now + DEM_RETRY_DELAY));
+
+    downloads.failed(now);
+    assert!(!downloads.may_download(now + DEM_RETRY_DELAY));
+    assert!(downloads.may_download(now + DEM_RETRY_DELAY * 2));
+
+    for _ in 0..40 {
+        downloads.failed(now);
+    }
+    assert!(downloads.may_download(now + DEM_RETRY_DELAY_MAX));
+}
@@ -0,0 +1,169 @@
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    io::{BufRead, BufReader},
+    path::Path,
+};
+
+use backend_rust::{
+    datasets::{agl_raster, dataset_key, parse_regions, slug, REGIONS_FILE_NAME},
+    height_data::select_data_source,
+    hgt_manifest::dem_version,
+    search::{search_from_point, SearchQuery, ALGORITHM_VERSION},
+    types::Location,
+};
+use serde::Serialize;
+
+const CELL_SIZE: f32 = 200.0;
+const GLIDE_NUMBER: f32 = 8.0;
+const ADDITIONAL_HEIGHT: f32 = 10.0;
+const TRIM_SPEED: f32 = 38.0;
+
+#[derive(Serialize)]
+struct ConeMetadata {
+    name: String,
+    file: String,
+    lat: f32,
+    lon: f32,
+    ground_height: f32,
+    cell_size: f32,
+    /// Bounds of the raster, `[south, north]` and `[west, east]`.
+    bounds_lat: (f32, f32),
+    bounds_lon: (f32, f32),
+    grid_shape: (usize, usize),
+}
+
+#[derive(Serialize)]
+struct DatasetIndex {
+    region: String,
+    dem_version: String,
+    algorithm_version: u32,
+    glide_number: f32,
+    additional_height: f32,
+    trim_speed: f32,
+    cones: Vec<ConeMetadata>,
+}
+
+const ARCHIVE_README: &str = "Precomputed glide areas (hikeandfly)
+
+index.json lists all cones with their bounds and the parameters used.
+Each <file>.agl is a raster of little endian i16 values in row major
+order, rows from south to north, holding the height above ground in
+meters at which the cell is reached, or -32768 if it is not reachable.
+";
+
+fn append_file<W: std::io::Write>(builder: &mut tar::Builder<W>, name: &str, content: &[u8]) {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(content.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder.append_data(&mut header, name, content).unwrap();
+}
+
+fn main() {
+    let data_dir = Path::new("./data");
+    let region_name = std::env::args()
+        .find_map(|a| a.strip_prefix("--region=").map(str::to_string))
+        .expect("Usage: precompute_cones --region=<name>");
+
+    let regions = parse_regions(
+        &fs::read_to_string(data_dir.join(REGIONS_FILE_NAME)).expect("Could not read regions"),
+    )
+    .unwrap();
+    let region = regions
+        .into_iter()
+        .find(|r| r.name == region_name)
+        .expect("Unknown region");
+
+    let reader =
+        BufReader::new(File::open(data_dir.join("search_data_flying_sites.jsonl")).unwrap());
+    let sites = reader
+        .lines()
+        .map(|line| serde_json::from_str::<Location>(&line.unwrap()).unwrap())
+        .filter(|site| region.contains(site.center[1], site.center[0]))
+        .collect::<Vec<_>>();
+    println!("Precomputing {} cones for {}...", sites.len(), region.name);
+
+    let archive_path = data_dir.join(dataset_key(&region.name));
+    fs::create_dir_all(archive_path.parent().unwrap()).unwrap();
+    let encoder = zstd::Encoder::new(File::create(&archive_path).unwrap(), 19).unwrap();
+    let mut builder = tar::Builder::new(encoder);
+
+    let mut cones = vec![];
+    let mut used_files = HashSet::new();
+    for site in sites {
+        let (lat, lon) = (site.center[1], site.center[0]);
+        if select_data_source(lat, lon, CELL_SIZE).is_none() {
+            println!("Skipping {}, no DEM data", site.name);
+            continue;
+        }
+
+        let result = match search_from_point(
+            lat,
+            lon,
+            CELL_SIZE,
+            SearchQuery::builder()
+                .glide_number(GLIDE_NUMBER)
+                .trim_speed_kmh(TRIM_SPEED)
+                .additional_height_m(ADDITIONAL_HEIGHT)
+                .build()
+                .expect("The cone parameters are valid"),
+        ) {
+            Ok(result) => result,
+            Err(e) => {
+                println!("Skipping {}, {e}", site.name);
+                continue;
+            }
+        };
+        let explored = result.explored.into_it().collect::<Vec<_>>();
+        let grid = result.height_grid;
+
+        let mut file = slug(&site.name);
+        if file.is_empty() {
+            file = "site".to_string();
+        }
+        while !used_files.insert(file.clone()) {
+            file.push('_');
+        }
+        append_file(
+            &mut builder,
+            &format!("{file}.agl"),
+            &agl_raster(&explored, &grid),
+        );
+
+        cones.push(ConeMetadata {
+            name: site.name,
+            file,
+            lat,
+            lon,
+            ground_height: result.ground_height,
+            cell_size: grid.cell_size,
+            bounds_lat: grid.latitudes,
+            bounds_lon: grid.longitudes,
+            grid_shape: (grid.heights.shape()[0], grid.heights.shape()[1]),
+        });
+    }
+
+    let index = DatasetIndex {
+        region: region.name,
+        dem_version: dem_version(data_dir).to_string(),
+        algorithm_version: ALGORITHM_VERSION,
+        glide_number: GLIDE_NUMBER,
+        additional_height: ADDITIONAL_HEIGHT,
+        trim_speed: TRIM_SPEED,
+        cones,
+    };
+    append_file(
+        &mut builder,
+        "index.json",
+        serde_json::to_string_pretty(&index).unwrap().as_bytes(),
+    );
+    append_file(&mut builder, "README.txt", ARCHIVE_README.as_bytes());
+
+    builder.into_inner().unwrap().finish().unwrap();
+    println!(
+        "Wrote {} cones to {}",
+        index.cones.len(),
+        archive_path.display()
+    );
+}
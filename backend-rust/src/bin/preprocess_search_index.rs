@@ -3,21 +3,14 @@ use std::{
     io::{BufRead, BufReader},
 };
 
-use backend_rust::{
-    textsearch::SearchIndex,
-    types::{Location, LocationInfo, SearchLocation},
-};
+use backend_rust::types::{Location, SearchLocation};
 use serde::Serialize;
 
 fn main() {
     println!("Building search index...");
-    let mut ix = SearchIndex::new();
-
     let paths = fs::read_dir("./data").unwrap();
 
-    let mut additional_info_map = std::collections::HashMap::<String, usize>::new();
-    let mut additional_info_vec = vec![];
-
+    let mut locations = vec![];
     for path in paths {
         let path = path.unwrap().path();
         if path.extension().and_then(|s| s.to_str()) == Some("jsonl") {
@@ -26,31 +19,11 @@ fn main() {
             let reader = BufReader::new(r);
             for line in reader.lines() {
                 let location: Location = serde_json::from_str(&line.unwrap()).unwrap();
-
-                let ad = location.additional_info.unwrap_or_default();
-                let additional_info_ix = if let Some(&ix) = additional_info_map.get(&ad) {
-                    ix
-                } else {
-                    let ix = additional_info_vec.len();
-                    additional_info_map.insert(ad.clone(), ix);
-                    additional_info_vec.push(ad);
-                    ix
-                };
-
-                ix.insert(
-                    location.name.as_str(),
-                    LocationInfo {
-                        center: location.center,
-                        additional_info_ix,
-                    },
-                );
+                locations.push(location);
             }
         }
     }
-    let search_index = SearchLocation {
-        index: ix.finalize(),
-        additional_info: additional_info_vec,
-    };
+    let search_index = SearchLocation::from_locations(locations);
 
     let mut s = flexbuffers::FlexbufferSerializer::new();
     search_index.serialize(&mut s).unwrap();
@@ -0,0 +1,103 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{Read, Write},
+    net::TcpStream,
+    path::Path,
+    time::Instant,
+};
+
+use backend_rust::replay::{latency_summary, parse_replay_log, REPLAY_LOG_FILE_NAME};
+
+/// Sends a plain HTTP/1.1 GET and returns the status code once the whole
+/// response has been received.
+fn http_get(server: &str, uri: &str) -> Result<u16, String> {
+    let mut stream = TcpStream::connect(server).map_err(|e| e.to_string())?;
+    write!(
+        stream,
+        "GET {uri} HTTP/1.1\r\nHost: {server}\r\nConnection: close\r\n\r\n"
+    )
+    .map_err(|e| e.to_string())?;
+
+    let mut response = vec![];
+    stream
+        .read_to_end(&mut response)
+        .map_err(|e| e.to_string())?;
+    let status_line = response.split(|b| *b == b'\n').next().unwrap_or_default();
+    String::from_utf8_lossy(status_line)
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse().ok())
+        .ok_or("Malformed response".to_string())
+}
+
+fn main() {
+    let arg_value = |name: &str| {
+        std::env::args().find_map(|a| a.strip_prefix(&format!("--{name}=")).map(str::to_string))
+    };
+    let log_path = arg_value("log").unwrap_or(
+        Path::new("./data")
+            .join(REPLAY_LOG_FILE_NAME)
+            .display()
+            .to_string(),
+    );
+    let server = arg_value("server").unwrap_or("127.0.0.1:8000".to_string());
+    let limit = arg_value("limit").map(|l| l.parse::<usize>().expect("Invalid limit"));
+
+    let mut entries = parse_replay_log(&fs::read_to_string(&log_path).expect("Could not read log"))
+        .expect("Could not parse log");
+    if let Some(limit) = limit {
+        entries.truncate(limit);
+    }
+    println!("Replaying {} requests against {server}...", entries.len());
+
+    // Recorded and replayed durations per path, in microseconds.
+    let mut durations: BTreeMap<String, (Vec<u64>, Vec<u64>)> = BTreeMap::new();
+    let mut status_mismatches = 0;
+    for entry in entries.iter() {
+        let start = Instant::now();
+        let status = match http_get(&server, &entry.uri()) {
+            Ok(status) => status,
+            Err(e) => {
+                println!("Request to {} failed: {e}", entry.uri());
+                std::process::exit(1);
+            }
+        };
+        let duration = start.elapsed().as_micros() as u64;
+
+        if status != entry.status {
+            status_mismatches += 1;
+            println!(
+                "Status {status} instead of {} for {}",
+                entry.status,
+                entry.uri()
+            );
+        }
+        let (recorded, replayed) = durations.entry(entry.path.clone()).or_default();
+        recorded.push(entry.duration_us);
+        replayed.push(duration);
+    }
+
+    println!(
+        "{:<28} {:>6} {:>12} {:>12} {:>12} {:>12} {:>7}",
+        "path", "count", "p50 rec ms", "p50 now ms", "p95 rec ms", "p95 now ms", "ratio"
+    );
+    for (path, (recorded, replayed)) in durations.iter() {
+        let (Some(recorded), Some(replayed)) =
+            (latency_summary(recorded), latency_summary(replayed))
+        else {
+            continue;
+        };
+        println!(
+            "{:<28} {:>6} {:>12.1} {:>12.1} {:>12.1} {:>12.1} {:>7.2}",
+            path,
+            recorded.count,
+            recorded.p50 as f64 / 1000.0,
+            replayed.p50 as f64 / 1000.0,
+            recorded.p95 as f64 / 1000.0,
+            replayed.p95 as f64 / 1000.0,
+            replayed.p50 as f64 / recorded.p50.max(1) as f64
+        );
+    }
+    println!("{status_mismatches} responses with a different status");
+}
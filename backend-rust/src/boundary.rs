@@ -0,0 +1,221 @@
+use ndarray::{s, Array2};
+
+use crate::{
+    height_data::HeightGrid,
+    search::{GridIx, Node},
+};
+
+/// Corner of the cell grid, corner `(row, col)` is the south west corner of
+/// cell `(row, col)`.
+pub type Corner = (usize, usize);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoundaryPoint {
+    pub corner: Corner,
+    /// Height at which the reachable cell next to the boundary is reached.
+    pub height: f32,
+}
+
+/// Reachable area of one 4-connected group of cells as closed rings of
+/// corners: the outer boundary counterclockwise and the holes clockwise,
+/// like the rings of a GeoJSON polygon.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RegionPolygon {
+    pub outer: Vec<Corner>,
+    pub holes: Vec<Vec<Corner>>,
+}
+
+fn reachable_cells(explored: &[Node], shape: (usize, usize)) -> Array2<bool> {
+    let mut reachable = Array2::from_elem(shape, false);
+    for node in explored.iter().filter(|n| n.reachable) {
+        reachable[(node.ix.0 as usize, node.ix.1 as usize)] = true;
+    }
+    reachable
+}
+
+/// Cells of `mask` that are 4-connected to `start` through cells of `mask`.
+fn flood_fill(mask: &Array2<bool>, start: (usize, usize)) -> Array2<bool> {
+    let mut component = Array2::from_elem(mask.raw_dim(), false);
+    if !mask[start] {
+        return component;
+    }
+    component[start] = true;
+    let mut stack = vec![start];
+    while let Some((row, col)) = stack.pop() {
+        let neighbors = [
+            (row.wrapping_sub(1), col),
+            (row + 1, col),
+            (row, col.wrapping_sub(1)),
+            (row, col + 1),
+        ];
+        for neighbor in neighbors {
+            if mask.get(neighbor) == Some(&true) && !component[neighbor] {
+                component[neighbor] = true;
+                stack.push(neighbor);
+            }
+        }
+    }
+    component
+}
+
+/// Cells that are reachable and 4-connected to `start` through reachable cells.
+pub fn reachable_component(
+    explored: &[Node],
+    shape: (usize, usize),
+    start: GridIx,
+) -> Array2<bool> {
+    flood_fill(
+        &reachable_cells(explored, shape),
+        (start.0 as usize, start.1 as usize),
+    )
+}
+
+/// Polygons of all reachable cells, one per 4-connected group. Unreachable
+/// cells enclosed by a group, e.g. a peak above the glide, are holes of it.
+/// Groups inside such a hole are polygons of their own.
+pub fn region_polygons(explored: &[Node], shape: (usize, usize)) -> Vec<RegionPolygon> {
+    let reachable = reachable_cells(explored, shape);
+    let mut done = Array2::from_elem(shape, false);
+    let mut result = vec![];
+    for (ix, is_reachable) in reachable.indexed_iter() {
+        if !*is_reachable || done[ix] {
+            continue;
+        }
+        let component = flood_fill(&reachable, ix);
+        done.zip_mut_with(&component, |done, inside| *done |= *inside);
+        result.push(component_polygon(&component));
+    }
+    result
+}
+
+/// Outer boundary and holes of a single 4-connected group of cells.
+fn component_polygon(component: &Array2<bool>) -> RegionPolygon {
+    let outer = boundary_corners(component)
+        .into_iter()
+        .map(|(corner, _)| corner)
+        .collect();
+
+    // Cells outside of the bounding box of the group can't be in a hole, so
+    // only the box is searched for them.
+    let cells = component.indexed_iter().filter(|(_, inside)| **inside);
+    let (mut lower, mut upper) = ((usize::MAX, usize::MAX), (0, 0));
+    for ((row, col), _) in cells {
+        lower = (lower.0.min(row), lower.1.min(col));
+        upper = (upper.0.max(row), upper.1.max(col));
+    }
+    let outside = component
+        .slice(s![lower.0..=upper.0, lower.1..=upper.1])
+        .map(|inside| !inside);
+    let (rows, cols) = outside.dim();
+
+    let mut done = Array2::from_elem(outside.raw_dim(), false);
+    let mut holes = vec![];
+    for (ix, is_outside) in outside.indexed_iter() {
+        if !*is_outside || done[ix] {
+            continue;
+        }
+        let region = flood_fill(&outside, ix);
+        done.zip_mut_with(&region, |done, inside| *done |= *inside);
+        // Regions touching the box are connected to the cells around it.
+        let touches_box = region.indexed_iter().any(|((row, col), inside)| {
+            *inside && (row == 0 || col == 0 || row == rows - 1 || col == cols - 1)
+        });
+        if !touches_box {
+            let mut ring = boundary_corners(&region)
+                .into_iter()
+                .map(|(corner, _)| (corner.0 + lower.0, corner.1 + lower.1))
+                .collect::<Vec<_>>();
+            ring.reverse();
+            holes.push(ring);
+        }
+    }
+    RegionPolygon { outer, holes }
+}
+
+/// Cell touching `corner` on the side `(d_row, d_col)`, if it is inside the grid.
+fn cell_at(corner: Corner, d_row: isize, d_col: isize) -> Option<(usize, usize)> {
+    let row = if d_row > 0 {
+        Some(corner.0)
+    } else {
+        corner.0.checked_sub(1)
+    }?;
+    let col = if d_col > 0 {
+        Some(corner.1)
+    } else {
+        corner.1.checked_sub(1)
+    }?;
+    Some((row, col))
+}
+
+/// Outer boundary of the cells in `inside` as a closed counterclockwise ring
+/// of corners, holes are ignored. Only corners where the boundary changes
+/// direction are returned, the first corner is repeated at the end.
+pub fn outer_boundary(inside: &Array2<bool>, heights: &Array2<f32>) -> Vec<BoundaryPoint> {
+    boundary_corners(inside)
+        .into_iter()
+        .map(|(corner, cell)| BoundaryPoint {
+            corner,
+            height: heights[cell],
+        })
+        .collect()
+}
+
+/// Corners of `outer_boundary`, each with the inside cell next to the edge
+/// that ends at it.
+fn boundary_corners(inside: &Array2<bool>) -> Vec<(Corner, (usize, usize))> {
+    let is_inside =
+        |cell: Option<(usize, usize)>| cell.is_some_and(|c| inside.get(c) == Some(&true));
+
+    // The southernmost cell always has its southern edge on the outer boundary.
+    let Some(first) = inside.indexed_iter().find(|(_, v)| **v).map(|(ix, _)| ix) else {
+        return vec![];
+    };
+
+    let start = (first.0, first.1);
+    // Directions as (d_row, d_col), walking with the inside on the left.
+    let start_direction = (0isize, 1isize);
+    let mut corner = start;
+    let mut direction = start_direction;
+    let mut result = vec![(corner, first)];
+
+    loop {
+        let next = (
+            corner.0.wrapping_add_signed(direction.0),
+            corner.1.wrapping_add_signed(direction.1),
+        );
+        let left = (direction.1, -direction.0);
+        let right = (-direction.1, direction.0);
+        let ahead_left = cell_at(next, direction.0 + left.0, direction.1 + left.1);
+        let ahead_right = cell_at(next, direction.0 + right.0, direction.1 + right.1);
+
+        let new_direction = if is_inside(ahead_right) {
+            right
+        } else if is_inside(ahead_left) {
+            direction
+        } else {
+            left
+        };
+        corner = next;
+
+        if new_direction != direction || (corner == start && new_direction == start_direction) {
+            // The cell left of the edge just walked is inside.
+            let cell = cell_at(corner, left.0 - direction.0, left.1 - direction.1).unwrap();
+            result.push((corner, cell));
+        }
+        direction = new_direction;
+
+        if corner == start && direction == start_direction {
+            break;
+        }
+    }
+    result
+}
+
+/// `(latitude, longitude)` of a corner of the grid.
+pub fn corner_coordinates(corner: Corner, grid: &HeightGrid) -> (f32, f32) {
+    grid.coordinates_at(corner.0 as f32 - 0.5, corner.1 as f32 - 0.5)
+}
+
+#[cfg(test)]
+#[path = "./boundary_test.rs"]
+mod boundary_test;
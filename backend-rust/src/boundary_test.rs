@@ -0,0 +1,178 @@
+use ndarray::{array, Array2};
+
+use crate::{
+    search::{search_from_height_grid, GridIxType, Node, SearchQuery},
+    synthetic::{synthetic_height_grid, SyntheticTerrain},
+};
+
+use super::{outer_boundary, reachable_component, region_polygons, RegionPolygon};
+
+#[test]
+fn test_reachable_component() {
+    let explored = [
+        Node::for_test((1, 1), 1000.0, true),
+        Node::for_test((1, 2), 1000.0, true),
+        Node::for_test((2, 2), 1000.0, false),
+        // Only connected diagonally.
+        Node::for_test((0, 3), 1000.0, true),
+    ];
+
+    let component = reachable_component(&explored, (3, 4), (1, 1));
+
+    assert_eq!(
+        component,
+        array![
+            [false, false, false, false],
+            [false, true, true, false],
+            [false, false, false, false]
+        ]
+    );
+}
+
+#[test]
+fn test_outer_boundary() {
+    // L shaped area with a hole that is not part of the outer boundary.
+    let inside = array![
+        [true, true, true, true],
+        [true, false, true, true],
+        [true, true, true, false],
+    ];
+    let heights = Array2::from_shape_fn((3, 4), |(row, col)| (row * 10 + col) as f32);
+
+    let boundary = outer_boundary(&inside, &heights);
+    let corners = boundary.iter().map(|p| p.corner).collect::<Vec<_>>();
+
+    assert_eq!(
+        corners,
+        vec![(0, 0), (0, 4), (2, 4), (2, 3), (3, 3), (3, 0), (0, 0)]
+    );
+    assert_eq!(boundary[1].height, 3.0);
+    assert_eq!(boundary[2].height, 13.0);
+}
+
+#[test]
+fn test_outer_boundary_empty() {
+    let inside = Array2::from_elem((2, 2), false);
+    assert!(outer_boundary(&inside, &Array2::zeros((2, 2))).is_empty());
+}
+
+fn explored(reachable: &Array2<bool>) -> Vec<Node> {
+    reachable
+        .indexed_iter()
+        .map(|((row, col), reachable)| {
+            Node::for_test((row as GridIxType, col as GridIxType), 1000.0, *reachable)
+        })
+        .collect()
+}
+
+#[test]
+fn test_region_polygons() {
+    // A ring around unreachable cells with a notch into them, and a cell
+    // only touching the ring diagonally.
+    let reachable = array![
+        [true, true, true, true, false, false],
+        [true, false, false, true, false, false],
+        [true, false, true, true, false, false],
+        [true, false, false, true, false, false],
+        [true, true, true, true, false, false],
+        [false, false, false, false, true, false],
+    ];
+    let polygons = region_polygons(&explored(&reachable), (6, 6));
+    assert_eq!(polygons.len(), 2);
+    assert_eq!(
+        polygons[0],
+        RegionPolygon {
+            outer: vec![(0, 0), (0, 4), (5, 4), (5, 0), (0, 0)],
+            holes: vec![vec![
+                (1, 1),
+                (4, 1),
+                (4, 3),
+                (3, 3),
+                (3, 2),
+                (2, 2),
+                (2, 3),
+                (1, 3),
+                (1, 1)
+            ]],
+        }
+    );
+    assert_eq!(
+        polygons[1],
+        RegionPolygon {
+            outer: vec![(5, 4), (5, 5), (6, 5), (6, 4), (5, 4)],
+            holes: vec![],
+        }
+    );
+}
+
+#[test]
+fn test_region_polygons_island() {
+    let mut reachable = Array2::from_elem((5, 5), true);
+    for ix in [
+        (1, 1),
+        (1, 2),
+        (1, 3),
+        (2, 1),
+        (2, 3),
+        (3, 1),
+        (3, 2),
+        (3, 3),
+    ] {
+        reachable[ix] = false;
+    }
+
+    let polygons = region_polygons(&explored(&reachable), (5, 5));
+    assert_eq!(polygons.len(), 2);
+    assert_eq!(
+        polygons[0].holes,
+        vec![vec![(1, 1), (4, 1), (4, 4), (1, 4), (1, 1)]]
+    );
+    assert_eq!(
+        polygons[1],
+        RegionPolygon {
+            outer: vec![(2, 2), (2, 3), (3, 3), (3, 2), (2, 2)],
+            holes: vec![],
+        }
+    );
+}
+
+#[test]
+fn test_region_polygons_blocking_peak() {
+    // A peak north of the start on flat terrain that the glide can't cross,
+    // but can fly around.
+    let mut grid = synthetic_height_grid(SyntheticTerrain::Flat, 47.0, 11.0, 200.0, 4000.0);
+    let center = grid.heights.shape()[0] / 2;
+    for row in center + 4..center + 7 {
+        for col in center - 1..center + 2 {
+            grid.heights[(row, col)] = 3000;
+        }
+    }
+    let query = SearchQuery::builder()
+        .additional_height_m(300.0)
+        .build()
+        .unwrap();
+    let start_ix = (center as GridIxType, center as GridIxType);
+    let result = search_from_height_grid(grid, start_ix, query).unwrap();
+    let explored = result.explored.into_it().collect::<Vec<_>>();
+    let shape = (
+        result.height_grid.heights.shape()[0],
+        result.height_grid.heights.shape()[1],
+    );
+
+    let polygons = region_polygons(&explored, shape);
+    assert_eq!(polygons.len(), 1);
+    assert_eq!(polygons[0].holes.len(), 1);
+    // The hole is the peak, in clockwise order.
+    let hole = &polygons[0].holes[0];
+    let (row, col) = hole[0];
+    assert_eq!(
+        hole,
+        &vec![
+            (row, col),
+            (row + 3, col),
+            (row + 3, col + 3),
+            (row, col + 3),
+            (row, col)
+        ]
+    );
+}
@@ -0,0 +1,151 @@
+use ndarray::Array2;
+
+use crate::{
+    pqueue::PriorityQueue,
+    search::{
+        get_effective_glide_ratio_in_wind, l2_diff, l2_distance, lift_adjusted_glide_ratio, search,
+        Edge, GridIx, GridIxType, SearchConfig,
+    },
+};
+
+/// Samples per cell when checking the line of sight of a glide.
+const SAMPLES_PER_CELL: f32 = 4.0;
+
+/// Slow reference for `search`: best arrival height of every cell when
+/// gliding in straight lines between reachable cells, checking the line of
+/// sight of every glide against the terrain. Does not use reference node
+/// shortcuts, so it can be used as an oracle for them. Unreachable cells are
+/// `f32::NEG_INFINITY`. Cells outside of the start sector or whose arrival
+/// height is in restricted airspace are never reached, and the `EdgeHook` of
+/// the query applies to every glide.
+/// The safety margin is ignored, since it only changes
+/// which paths are preferred, not which cells are reachable.
+/// Glides are always geometric, see `GlideModel`.
+///
+/// Quadratic in the number of cells, only use it on small grids.
+pub fn reference_heights(start: GridIx, height: f32, config: &SearchConfig) -> Array2<f32> {
+    let shape = config.grid.heights.shape();
+    let mut heights = Array2::from_elem((shape[0], shape[1]), f32::NEG_INFINITY);
+    let mut done = Array2::from_elem((shape[0], shape[1]), false);
+    let mut queue = PriorityQueue::<f32, GridIx>::new();
+
+    heights[(start.0 as usize, start.1 as usize)] = height;
+    queue.push(start, -height);
+
+    // Heights only decrease along a path, so popping the highest cell first
+    // settles it, like Dijkstra.
+    while let Some(first) = queue.pop() {
+        let from = first.key;
+        let from_height = heights[(from.0 as usize, from.1 as usize)];
+        done[(from.0 as usize, from.1 as usize)] = true;
+
+        for ((row, col), height) in heights.indexed_iter_mut() {
+            if done[(row, col)] {
+                continue;
+            }
+            let to = (row as GridIxType, col as GridIxType);
+            if !config.in_start_sector(&start, &to) {
+                continue;
+            }
+            let Some(arrival) = glide_height(&from, from_height, &to, config) else {
+                continue;
+            };
+            if arrival <= *height {
+                continue;
+            }
+            *height = arrival;
+            if queue.contains_key(&to) {
+                queue.update_priority_if_less(to, -arrival);
+            } else {
+                queue.push(to, -arrival);
+            }
+        }
+    }
+    heights
+}
+
+/// Height when arriving at `to` gliding straight from `from`, or `None` if
+/// the terrain is in the way.
+fn glide_height(
+    from: &GridIx,
+    from_height: f32,
+    to: &GridIx,
+    config: &SearchConfig,
+) -> Option<f32> {
+    let diff = l2_diff(from, to);
+    let angle = (diff.0 as f32).atan2(diff.1 as f32);
+    let (wind_speed, wind_direction) = config.wind_at(to, from_height);
+    let glide_ratio =
+        get_effective_glide_ratio_in_wind(&config.query, angle, wind_speed, wind_direction)
+            .glide_ratio;
+    if glide_ratio.is_infinite() {
+        return None;
+    }
+    let glide_ratio = lift_adjusted_glide_ratio(config, to, glide_ratio, from_height);
+
+    let length = l2_distance(from, to);
+    let distance = length * config.grid.cell_size;
+    let (arrival, allowed) = config.hooked_height(&Edge {
+        from: *from,
+        to: *to,
+        from_height,
+        to_height: from_height - distance * glide_ratio,
+        ground_height: config.grid.heights[(to.0 as usize, to.1 as usize)] as f32,
+        distance,
+    });
+    if !allowed || config.in_airspace(to, arrival) {
+        return None;
+    }
+
+    let samples = ((length * SAMPLES_PER_CELL).ceil() as usize).max(1);
+    for i in 0..=samples {
+        let t = i as f32 / samples as f32;
+        let row = (from.0 as f32 + (to.0 as f32 - from.0 as f32) * t).round() as usize;
+        let col = (from.1 as f32 + (to.1 as f32 - from.1 as f32) * t).round() as usize;
+        let height = from_height + (arrival - from_height) * t;
+        if height < config.grid.heights[(row, col)] as f32 {
+            return None;
+        }
+    }
+    Some(arrival)
+}
+
+/// Cells where `search` and `reference_heights` disagree on reachability.
+#[derive(Debug, Default, PartialEq)]
+pub struct VerificationReport {
+    /// Reachable according to the search, but not the reference.
+    pub only_search: Vec<GridIx>,
+    /// Reachable according to the reference, but not the search.
+    pub only_reference: Vec<GridIx>,
+}
+
+impl VerificationReport {
+    pub fn is_consistent(&self) -> bool {
+        self.only_search.is_empty() && self.only_reference.is_empty()
+    }
+}
+
+pub fn verify_search(start: GridIx, height: f32, config: &SearchConfig) -> VerificationReport {
+    let state = search(start, height, config);
+    let reference = reference_heights(start, height, config);
+
+    let mut reachable = Array2::from_elem(reference.raw_dim(), false);
+    for node in state.explored.iter().filter(|n| n.reachable) {
+        reachable[(node.ix.0 as usize, node.ix.1 as usize)] = true;
+    }
+
+    let mut report = VerificationReport::default();
+    for ((row, col), reference_height) in reference.indexed_iter() {
+        let ix = (row as GridIxType, col as GridIxType);
+        match (reachable[(row, col)], reference_height.is_finite()) {
+            (true, false) => report.only_search.push(ix),
+            (false, true) => report.only_reference.push(ix),
+            _ => {}
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+#[path = "./brute_force_test.rs"]
+mod brute_force_test;
@@ -0,0 +1,67 @@
+use ndarray::Array2;
+
+use crate::{
+    height_data::HeightGrid,
+    search::{SearchConfig, SearchQuery},
+};
+
+use super::{reference_heights, verify_search};
+
+fn test_config(heights: Array2<i16>, wind_speed: f32) -> SearchConfig {
+    SearchConfig {
+        grid: HeightGrid::for_test(heights, (47.0, 47.1), (11.0, 11.1)),
+        query: SearchQuery {
+            glide_ratio: 0.1,
+            wind_speed,
+            ..Default::default()
+        },
+        wind_field: None,
+        airspace: None,
+        cancellation: None,
+    }
+}
+
+#[test]
+fn test_reference_heights_flat() {
+    let config = test_config(Array2::zeros((11, 11)), 0.0);
+    let heights = reference_heights((5, 5), 30.0, &config);
+
+    assert_eq!(heights[(5, 5)], 30.0);
+    assert_eq!(heights[(5, 8)], 0.0);
+    assert!(heights[(5, 9)].is_infinite());
+    assert!(heights[(7, 7)].is_finite());
+}
+
+#[test]
+fn test_reference_heights_around_wall() {
+    let mut heights = Array2::zeros((11, 11));
+    for col in 0..10 {
+        heights[(6, col)] = 1000;
+    }
+    let config = test_config(heights, 0.0);
+    let heights = reference_heights((4, 5), 100.0, &config);
+
+    // Directly behind the wall is out of reach, but the glide around it
+    // turns at the end of the wall.
+    assert!(heights[(8, 5)].is_infinite());
+    assert!(heights[(7, 10)].is_finite());
+    assert!(heights[(7, 10)] < heights[(5, 10)]);
+}
+
+#[test]
+fn test_verify_search() {
+    let config = test_config(Array2::zeros((15, 15)), 10.0);
+    assert!(verify_search((7, 7), 80.0, &config).is_consistent());
+
+    // On rough terrain the search may miss cells, but must never claim
+    // cells to be reachable that are not.
+    let mut heights = Array2::from_shape_fn((15, 15), |(row, col)| {
+        ((row as i16 * 37 + col as i16 * 23) % 11) * 10
+    });
+    heights[(7, 7)] = 0;
+    let config = test_config(heights, 10.0);
+    for height in [80.0, 120.0] {
+        let report = verify_search((7, 7), height, &config);
+        assert!(report.only_search.is_empty(), "{:?}", report);
+    }
+}
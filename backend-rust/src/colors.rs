@@ -11,8 +11,8 @@ pub fn lerp_color(a: &[f32; 4], b: &[f32; 4], s: f32) -> [f32; 4] {
     ]
 }
 
-pub fn lerp<const S: usize>(lerp_colors: &[[f32; 4]; S], steps: &[f32; S], s: f32) -> [f32; 4] {
-    for i in 0..(S - 1) {
+pub fn lerp(lerp_colors: &[[f32; 4]], steps: &[f32], s: f32) -> [f32; 4] {
+    for i in 0..(steps.len() - 1) {
         if s >= steps[i] && s < steps[i + 1] {
             return lerp_color(
                 &lerp_colors[i],
@@ -21,7 +21,177 @@ pub fn lerp<const S: usize>(lerp_colors: &[[f32; 4]; S], steps: &[f32; S], s: f3
             );
         }
     }
-    lerp_colors[S - 1]
+    if s < steps[0] {
+        return lerp_colors[0];
+    }
+    lerp_colors[lerp_colors.len() - 1]
+}
+
+/// Colors at increasing steps between 0 and 1, values in between are
+/// interpolated linearly.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColorRamp {
+    pub colors: Vec<[f32; 4]>,
+    pub steps: Vec<f32>,
+}
+
+/// Step and RGB color of a stop of a preset.
+type PresetStop = (f32, [u8; 3]);
+
+/// Named color ramps. The default goes from red at the lowest to blue at
+/// the highest value. `deuteranopia` and `protanopia` follow it with colors
+/// of the Okabe-Ito palette that stay apart for red-green color blindness,
+/// the latter avoiding the reds that look dark without red cones.
+const PRESETS: [(&str, &[PresetStop]); 6] = [
+    (
+        "default",
+        &[
+            (0.0, [255, 0, 0]),
+            (0.5, [180, 190, 0]),
+            (1.0, [0, 150, 255]),
+        ],
+    ),
+    (
+        "viridis",
+        &[
+            (0.0, [68, 1, 84]),
+            (0.25, [59, 82, 139]),
+            (0.5, [33, 145, 140]),
+            (0.75, [94, 201, 98]),
+            (1.0, [253, 231, 37]),
+        ],
+    ),
+    (
+        "turbo",
+        &[
+            (0.0, [35, 23, 27]),
+            (0.125, [68, 106, 238]),
+            (0.25, [38, 189, 225]),
+            (0.375, [64, 243, 146]),
+            (0.5, [150, 250, 80]),
+            (0.625, [238, 208, 45]),
+            (0.75, [255, 128, 29]),
+            (0.875, [201, 45, 12]),
+            (1.0, [144, 13, 0]),
+        ],
+    ),
+    ("grayscale", &[(0.0, [0, 0, 0]), (1.0, [255, 255, 255])]),
+    (
+        "deuteranopia",
+        &[
+            (0.0, [213, 94, 0]),
+            (0.5, [240, 228, 66]),
+            (1.0, [0, 114, 178]),
+        ],
+    ),
+    (
+        "protanopia",
+        &[
+            (0.0, [230, 159, 0]),
+            (0.5, [86, 180, 233]),
+            (1.0, [0, 60, 150]),
+        ],
+    ),
+];
+
+/// Names of the preset color ramps.
+pub fn color_ramp_names() -> impl Iterator<Item = &'static str> {
+    PRESETS.iter().map(|(name, _)| *name)
+}
+
+impl ColorRamp {
+    fn preset(name: &str) -> Option<ColorRamp> {
+        let (_, stops) = PRESETS.iter().find(|(preset, _)| *preset == name)?;
+        Some(ColorRamp {
+            colors: stops
+                .iter()
+                .map(|(_, [r, g, b])| [*r as f32, *g as f32, *b as f32, 255.0])
+                .collect(),
+            steps: stops.iter().map(|(step, _)| *step).collect(),
+        })
+    }
+
+    /// Color at `s` between 0 and 1.
+    pub fn color_at(&self, s: f32) -> [f32; 4] {
+        lerp(&self.colors, &self.steps, s)
+    }
+
+    /// The ramp with its color channels multiplied by `factor`, keeping the
+    /// alpha.
+    pub fn darkened(&self, factor: f32) -> ColorRamp {
+        ColorRamp {
+            colors: self
+                .colors
+                .iter()
+                .map(|[r, g, b, a]| [r * factor, g * factor, b * factor, *a])
+                .collect(),
+            steps: self.steps.clone(),
+        }
+    }
+}
+
+impl Default for ColorRamp {
+    fn default() -> Self {
+        ColorRamp::preset("default").unwrap()
+    }
+}
+
+/// Parses a color of 6 or 8 hex digits, `rrggbb` or `rrggbbaa`.
+fn parse_hex_color(value: &str) -> Result<[f32; 4], String> {
+    let value = value.trim().trim_start_matches('#');
+    if !(value.len() == 6 || value.len() == 8) || !value.is_ascii() {
+        return Err(format!(
+            "Color {value} is not of the form rrggbb or rrggbbaa"
+        ));
+    }
+    let mut color = [255.0; 4];
+    for (i, channel) in color.iter_mut().enumerate().take(value.len() / 2) {
+        *channel = u8::from_str_radix(&value[2 * i..2 * i + 2], 16)
+            .map_err(|e| format!("Bad color {value}: {e}"))? as f32;
+    }
+    Ok(color)
+}
+
+/// Parses a color ramp, either the name of a preset, see
+/// `color_ramp_names`, or comma separated `step:color` pairs with hex colors,
+/// e.g. `0:ff0000,0.5:b4be00,1:0096ff`. Steps must be increasing from 0
+/// to 1.
+pub fn parse_color_ramp(value: &str) -> Result<ColorRamp, String> {
+    if let Some(ramp) = ColorRamp::preset(value.trim()) {
+        return Ok(ramp);
+    }
+    if !value.contains(':') {
+        return Err(format!(
+            "Unknown color map {value}, expected one of {} or step:color pairs",
+            color_ramp_names().collect::<Vec<_>>().join(", ")
+        ));
+    }
+
+    let mut ramp = ColorRamp {
+        colors: vec![],
+        steps: vec![],
+    };
+    for pair in value.split(',') {
+        let (step, color) = pair
+            .split_once(':')
+            .ok_or_else(|| format!("Color stop {pair} is not of the form step:color"))?;
+        let step: f32 = step
+            .trim()
+            .parse()
+            .map_err(|e| format!("Bad color step {step}: {e}"))?;
+        if !(0.0..=1.0).contains(&step) {
+            return Err(format!("Color step {step} must be between 0 and 1"));
+        }
+        if ramp.steps.last().is_some_and(|last| *last >= step) {
+            return Err("Color steps must be increasing".to_string());
+        }
+        ramp.steps.push(step);
+        ramp.colors.push(parse_hex_color(color)?);
+    }
+    if ramp.steps.len() < 2 {
+        return Err("A color map needs at least two stops".to_string());
+    }
+    Ok(ramp)
 }
 
 pub fn f32_color_to_u8(color: [f32; 4]) -> [u8; 4] {
@@ -33,6 +203,37 @@ pub fn f32_color_to_u8(color: [f32; 4]) -> [u8; 4] {
     ]
 }
 
+/// Spacing in pixels of the diagonal lines drawn over guessed terrain.
+const HATCH_SPACING: u32 = 4;
+
+/// Darkens every pixel on a diagonal line, which hatches an area of cells
+/// that are filled with the same `color`.
+pub fn hatch(color: [u8; 4], x: u32, y: u32) -> [u8; 4] {
+    if !(x + y).is_multiple_of(HATCH_SPACING) {
+        return color;
+    }
+    [color[0] / 2, color[1] / 2, color[2] / 2, color[3].max(160)]
+}
+
+/// Multiplies the color channels of `color` with `brightness` between 0 and
+/// 1, keeping its alpha.
+pub fn shade(color: [u8; 4], brightness: f32) -> [u8; 4] {
+    let channel = |value: u8| (value as f32 * brightness.clamp(0.0, 1.0)).round() as u8;
+    [
+        channel(color[0]),
+        channel(color[1]),
+        channel(color[2]),
+        color[3],
+    ]
+}
+
+/// Mixes `color` with `tint` by `share` between 0 and 1, keeping its alpha.
+pub fn tint(color: [u8; 4], tint: [u8; 3], share: f32) -> [u8; 4] {
+    let share = share.clamp(0.0, 1.0);
+    let channel = |i: usize| lerp_f32(color[i] as f32, tint[i] as f32, share).round() as u8;
+    [channel(0), channel(1), channel(2), color[3]]
+}
+
 #[cfg(test)]
 #[path = "./colors_test.rs"]
 mod colors_test;
@@ -1,4 +1,6 @@
-use crate::colors::lerp_f32;
+use crate::colors::{
+    color_ramp_names, f32_color_to_u8, hatch, lerp_f32, parse_color_ramp, shade, tint, ColorRamp,
+};
 
 #[test]
 fn test_lerp_f32() {
@@ -14,3 +16,65 @@ fn test_lerp_f32() {
     assert_eq!(lerp_f32(3.5, 7.75, 0.25), 4.5625);
     assert_eq!(lerp_f32(3.5, 8.5, 0.2), 4.5);
 }
+
+#[test]
+fn test_hatch() {
+    let color = [200, 100, 50, 128];
+    assert_eq!(hatch(color, 1, 2), color);
+    assert_eq!(hatch(color, 1, 3), [100, 50, 25, 160]);
+    assert_eq!(hatch(color, 0, 0), hatch(color, 3, 5));
+}
+
+#[test]
+fn test_shade() {
+    let color = [200, 100, 51, 128];
+    assert_eq!(shade(color, 1.0), color);
+    assert_eq!(shade(color, 0.5), [100, 50, 26, 128]);
+    assert_eq!(shade(color, -1.0), [0, 0, 0, 128]);
+}
+
+#[test]
+fn test_tint() {
+    let color = [200, 100, 0, 128];
+    assert_eq!(tint(color, [0, 0, 255], 0.0), color);
+    assert_eq!(tint(color, [0, 0, 255], 0.5), [100, 50, 128, 128]);
+    assert_eq!(tint(color, [0, 0, 255], 2.0), [0, 0, 255, 128]);
+}
+
+#[test]
+fn test_color_ramp() {
+    let ramp = ColorRamp::default();
+    assert_eq!(ramp.color_at(0.0), [255.0, 0.0, 0.0, 255.0]);
+    assert_eq!(ramp.color_at(0.25), [217.5, 95.0, 0.0, 255.0]);
+    assert_eq!(ramp.color_at(1.0), [0.0, 150.0, 255.0, 255.0]);
+    assert_eq!(ramp.darkened(0.6).color_at(1.0), [0.0, 90.0, 153.0, 255.0]);
+
+    for name in color_ramp_names() {
+        let preset = parse_color_ramp(name).unwrap();
+        assert_eq!(preset.steps.first(), Some(&0.0));
+        assert_eq!(preset.steps.last(), Some(&1.0));
+        assert_eq!(preset.colors.len(), preset.steps.len());
+    }
+    assert_eq!(parse_color_ramp("default"), Ok(ramp));
+}
+
+#[test]
+fn test_parse_color_ramp() {
+    let ramp = parse_color_ramp("0.2:000000, 1:#ff800080").unwrap();
+    assert_eq!(ramp.steps, vec![0.2, 1.0]);
+    assert_eq!(
+        ramp.colors,
+        vec![[0.0, 0.0, 0.0, 255.0], [255.0, 128.0, 0.0, 128.0]]
+    );
+    // Values below the first step get its color.
+    assert_eq!(ramp.color_at(0.0), [0.0, 0.0, 0.0, 255.0]);
+    assert_eq!(f32_color_to_u8(ramp.color_at(0.6)), [127, 64, 0, 191]);
+
+    assert!(parse_color_ramp("magma").is_err());
+    assert!(parse_color_ramp("0:000000").is_err());
+    assert!(parse_color_ramp("0:000000,0:ffffff").is_err());
+    assert!(parse_color_ramp("0:000000,1.5:ffffff").is_err());
+    assert!(parse_color_ramp("0:000000,1:fffff").is_err());
+    assert!(parse_color_ramp("0:000000,1:gggggg").is_err());
+    assert!(parse_color_ramp("0:000000,1").is_err());
+}
@@ -0,0 +1,145 @@
+use std::f32::consts::PI;
+
+use ndarray::Array2;
+
+use crate::{
+    height_data::HeightGrid,
+    search::{l2_distance, GridIx, Node},
+};
+
+/// Most launches compared at once, every launch is a search of its own.
+pub const MAX_LAUNCHES: usize = 10;
+
+/// Compass directions of `range_per_direction`, clockwise from north.
+pub const DIRECTIONS: [&str; 8] = ["N", "NE", "E", "SE", "S", "SW", "W", "NW"];
+
+/// Comma separated ids of the launches to compare, at most `MAX_LAUNCHES`.
+pub fn parse_launch_ids(ids: &str) -> Result<Vec<&str>, String> {
+    let ids = ids
+        .split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .collect::<Vec<_>>();
+    if ids.is_empty() {
+        return Err("No launch ids given".to_string());
+    }
+    if ids.len() > MAX_LAUNCHES {
+        return Err(format!("At most {MAX_LAUNCHES} launches can be compared"));
+    }
+    Ok(ids)
+}
+
+/// Index in `DIRECTIONS` of the 45 degree sector around the direction of an
+/// offset to the north and east.
+pub fn direction_of(north: f32, east: f32) -> usize {
+    let bearing = east.atan2(north).rem_euclid(2.0 * PI);
+    (bearing / (PI / 4.0)).round() as usize % DIRECTIONS.len()
+}
+
+/// Directions of `DIRECTIONS` the wind can come from to launch on a slope
+/// facing `aspect`, in degrees: the direction the slope faces and its two
+/// neighbours.
+pub fn launch_wind_directions(aspect: f32) -> Vec<&'static str> {
+    let aspect = aspect.to_radians();
+    let facing = direction_of(aspect.cos(), aspect.sin());
+    [DIRECTIONS.len() - 1, 0, 1]
+        .iter()
+        .map(|offset| DIRECTIONS[(facing + offset) % DIRECTIONS.len()])
+        .collect()
+}
+
+/// Largest straight-line distance in meters from the start to a reachable
+/// cell, per direction of `DIRECTIONS`.
+pub fn range_per_direction(explored: &[Node], start_ix: GridIx, cell_size: f32) -> [f32; 8] {
+    let mut result = [0.0f32; 8];
+    for node in explored.iter().filter(|node| node.reachable) {
+        let north = node.ix.0 as f32 - start_ix.0 as f32;
+        let east = node.ix.1 as f32 - start_ix.1 as f32;
+        if north == 0.0 && east == 0.0 {
+            continue;
+        }
+        let direction = direction_of(north, east);
+        let distance = l2_distance(&node.ix, &start_ix) * cell_size;
+        result[direction] = result[direction].max(distance);
+    }
+    result
+}
+
+/// Number of cones that reach each cell of a grid covering all of them, at
+/// the finest resolution of the cones. Row 0 is the south like in
+/// `HeightGrid`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Coverage {
+    pub counts: Array2<u8>,
+    pub latitudes: (f32, f32),
+    pub longitudes: (f32, f32),
+}
+
+/// Coverage grid of cones on the given grids, with all counts 0.
+pub fn coverage_extent(grids: &[&HeightGrid]) -> Coverage {
+    let Some(first) = grids.first() else {
+        return Coverage {
+            counts: Array2::zeros((0, 0)),
+            latitudes: (0.0, 0.0),
+            longitudes: (0.0, 0.0),
+        };
+    };
+    let (mut latitudes, mut longitudes) = (first.latitudes, first.longitudes);
+    let mut resolution = first.get_angular_resolution();
+    for grid in grids {
+        latitudes = (
+            latitudes.0.min(grid.latitudes.0),
+            latitudes.1.max(grid.latitudes.1),
+        );
+        longitudes = (
+            longitudes.0.min(grid.longitudes.0),
+            longitudes.1.max(grid.longitudes.1),
+        );
+        let (lat_resolution, lon_resolution) = grid.get_angular_resolution();
+        resolution = (
+            resolution.0.min(lat_resolution),
+            resolution.1.min(lon_resolution),
+        );
+    }
+    // Rounding errors of the bounds must not add a row or column.
+    let cells = |extent: f32, resolution: f32| (extent / resolution - 1e-3).ceil() as usize;
+    let shape = (
+        cells(latitudes.1 - latitudes.0, resolution.0),
+        cells(longitudes.1 - longitudes.0, resolution.1),
+    );
+    Coverage {
+        counts: Array2::zeros(shape),
+        latitudes: (latitudes.0, latitudes.0 + shape.0 as f32 * resolution.0),
+        longitudes: (longitudes.0, longitudes.0 + shape.1 as f32 * resolution.1),
+    }
+}
+
+pub fn combined_coverage(cones: &[(&HeightGrid, &[Node])]) -> Coverage {
+    let mut coverage = coverage_extent(&cones.iter().map(|(grid, _)| *grid).collect::<Vec<_>>());
+    let shape = coverage.counts.dim();
+    let resolution = (
+        (coverage.latitudes.1 - coverage.latitudes.0) / shape.0 as f32,
+        (coverage.longitudes.1 - coverage.longitudes.0) / shape.1 as f32,
+    );
+    for (grid, explored) in cones {
+        let mut reachable = Array2::from_elem(grid.heights.raw_dim(), false);
+        for node in explored.iter().filter(|node| node.reachable) {
+            reachable[(node.ix.0 as usize, node.ix.1 as usize)] = true;
+        }
+        for ((row, col), count) in coverage.counts.indexed_iter_mut() {
+            let lat = coverage.latitudes.0 + row as f32 * resolution.0;
+            let lon = coverage.longitudes.0 + col as f32 * resolution.1;
+            if grid
+                .index_of(lat, lon)
+                .is_some_and(|ix| reachable.get(ix) == Some(&true))
+            {
+                *count += 1;
+            }
+        }
+    }
+    coverage
+}
+
+#[cfg(test)]
+#[path = "./compare_test.rs"]
+mod compare_test;
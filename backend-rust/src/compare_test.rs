@@ -0,0 +1,82 @@
+use ndarray::Array2;
+
+use crate::{height_data::HeightGrid, search::Node};
+
+use super::{
+    combined_coverage, direction_of, launch_wind_directions, parse_launch_ids, range_per_direction,
+};
+
+fn grid(latitudes: (f32, f32), longitudes: (f32, f32)) -> HeightGrid {
+    HeightGrid::for_test(Array2::zeros((2, 2)), latitudes, longitudes)
+}
+
+#[test]
+fn test_parse_launch_ids() {
+    assert_eq!(
+        parse_launch_ids("hochries, brauneck,,"),
+        Ok(vec!["hochries", "brauneck"])
+    );
+    assert!(parse_launch_ids(" ,").is_err());
+    assert!(parse_launch_ids(&["a"; 11].join(",")).is_err());
+}
+
+#[test]
+fn test_direction_of() {
+    assert_eq!(direction_of(1.0, 0.0), 0);
+    assert_eq!(direction_of(1.0, 1.0), 1);
+    assert_eq!(direction_of(0.0, 1.0), 2);
+    assert_eq!(direction_of(-1.0, 0.0), 4);
+    assert_eq!(direction_of(0.0, -1.0), 6);
+    // Just west of north is still north.
+    assert_eq!(direction_of(10.0, -1.0), 0);
+}
+
+#[test]
+fn test_launch_wind_directions() {
+    assert_eq!(launch_wind_directions(0.0), vec!["NW", "N", "NE"]);
+    assert_eq!(launch_wind_directions(200.0), vec!["SE", "S", "SW"]);
+    assert_eq!(launch_wind_directions(350.0), vec!["NW", "N", "NE"]);
+}
+
+#[test]
+fn test_range_per_direction() {
+    let explored = [
+        Node::for_test((5, 5), 1000.0, true),
+        Node::for_test((8, 5), 1000.0, true),
+        Node::for_test((6, 5), 1000.0, true),
+        Node::for_test((5, 1), 1000.0, true),
+        Node::for_test((2, 8), 1000.0, true),
+        Node::for_test((5, 9), 1000.0, false),
+    ];
+
+    let ranges = range_per_direction(&explored, (5, 5), 100.0);
+
+    assert_eq!(ranges[0], 300.0);
+    assert_eq!(ranges[2], 0.0);
+    assert_eq!(ranges[6], 400.0);
+    assert!((ranges[3] - 424.26).abs() < 0.01);
+}
+
+#[test]
+fn test_combined_coverage() {
+    // Two cones of 2x2 cells, side by side with one column of overlap.
+    let west = grid((47.0, 47.2), (11.0, 11.2));
+    let east = grid((47.0, 47.2), (11.1, 11.3));
+    let west_nodes = [
+        Node::for_test((0, 0), 1000.0, true),
+        Node::for_test((0, 1), 1000.0, true),
+        Node::for_test((1, 1), 1000.0, false),
+    ];
+    let east_nodes = [
+        Node::for_test((0, 0), 1000.0, true),
+        Node::for_test((1, 1), 1000.0, true),
+    ];
+
+    let coverage = combined_coverage(&[(&west, &west_nodes), (&east, &east_nodes)]);
+
+    assert_eq!(coverage.counts.shape(), &[2, 3]);
+    assert_eq!(coverage.latitudes, (47.0, 47.2));
+    assert!((coverage.longitudes.1 - 11.3).abs() < 1e-5);
+    assert_eq!(coverage.counts.row(0).to_vec(), vec![1, 2, 0]);
+    assert_eq!(coverage.counts.row(1).to_vec(), vec![0, 0, 1]);
+}
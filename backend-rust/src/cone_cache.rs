@@ -0,0 +1,249 @@
+use std::io::{Cursor, Read};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use ndarray::Array2;
+
+use crate::{
+    height_data::{DataQuality, DataSource, HeightGrid},
+    search::{GridIx, GridIxType, Node, ALGORITHM_VERSION},
+};
+
+const MAGIC: &[u8; 4] = b"CONE";
+const FORMAT_VERSION: u8 = 8;
+
+const FLAG_REACHABLE: u8 = 1;
+const FLAG_EXPLORED: u8 = 4;
+const FLAG_HAS_REFERENCE: u8 = 8;
+
+/// A finished search as stored in the persistent cone cache.
+pub struct CachedCone {
+    pub explored: Vec<Node>,
+    pub height_grid: HeightGrid,
+    pub ground_height: f32,
+    pub start_ix: GridIx,
+    pub border_bearings: Vec<u16>,
+}
+
+fn data_source_to_u8(source: DataSource) -> u8 {
+    match source {
+        DataSource::Srtm => 0,
+        DataSource::Coarse => 1,
+        DataSource::Synthetic => 2,
+    }
+}
+
+fn data_source_from_u8(value: u8) -> Option<DataSource> {
+    match value {
+        0 => Some(DataSource::Srtm),
+        1 => Some(DataSource::Coarse),
+        2 => Some(DataSource::Synthetic),
+        _ => None,
+    }
+}
+
+fn data_quality_to_u8(quality: DataQuality) -> u8 {
+    match quality {
+        DataQuality::Measured => 0,
+        DataQuality::VoidFilled => 1,
+        DataQuality::Synthesized => 2,
+    }
+}
+
+fn data_quality_from_u8(value: u8) -> Option<DataQuality> {
+    match value {
+        0 => Some(DataQuality::Measured),
+        1 => Some(DataQuality::VoidFilled),
+        2 => Some(DataQuality::Synthesized),
+        _ => None,
+    }
+}
+
+/// Grid indices are always stored as u32, so cones can be shared between
+/// builds with and without the `wide_grid_index` feature.
+#[allow(clippy::useless_conversion)]
+fn write_ix(result: &mut Vec<u8>, ix: GridIx) {
+    result.write_u32::<LittleEndian>(u32::from(ix.0)).unwrap();
+    result.write_u32::<LittleEndian>(u32::from(ix.1)).unwrap();
+}
+
+fn read_ix(reader: &mut Cursor<&[u8]>) -> Option<GridIx> {
+    Some((
+        GridIxType::try_from(reader.read_u32::<LittleEndian>().ok()?).ok()?,
+        GridIxType::try_from(reader.read_u32::<LittleEndian>().ok()?).ok()?,
+    ))
+}
+
+/// Content-addressed key for a cone. Includes the DEM and algorithm versions
+/// so instances serving different data or code never share cones.
+pub fn cone_cache_key(canonical_query: &str, dem_version: &str) -> String {
+    let digest = hmac_sha256::Hash::hash(
+        format!("{FORMAT_VERSION}\n{ALGORITHM_VERSION}\n{dem_version}\n{canonical_query}")
+            .as_bytes(),
+    );
+    digest.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+pub fn encode_cone(cone: &CachedCone) -> Vec<u8> {
+    let grid = &cone.height_grid;
+    let shape = grid.heights.shape();
+
+    let mut result = Vec::with_capacity(64 + shape[0] * shape[1] * 2 + cone.explored.len() * 33);
+    result.extend_from_slice(MAGIC);
+    result.push(FORMAT_VERSION);
+
+    // Writing to a vec can not fail.
+    result.write_u32::<LittleEndian>(shape[0] as u32).unwrap();
+    result.write_u32::<LittleEndian>(shape[1] as u32).unwrap();
+    for value in [
+        grid.cell_size,
+        grid.min_cell_size,
+        grid.latitudes.0,
+        grid.latitudes.1,
+        grid.longitudes.0,
+        grid.longitudes.1,
+        cone.ground_height,
+    ] {
+        result.write_f32::<LittleEndian>(value).unwrap();
+    }
+    result.push(data_source_to_u8(grid.data_source));
+    write_ix(&mut result, cone.start_ix);
+    result.push(cone.border_bearings.len() as u8);
+    for bearing in cone.border_bearings.iter() {
+        result.write_u16::<LittleEndian>(*bearing).unwrap();
+    }
+
+    for height in grid.heights.iter() {
+        result.write_i16::<LittleEndian>(*height).unwrap();
+    }
+
+    // Only the few cells that were not measured are stored.
+    let guessed = grid
+        .data_quality
+        .iter()
+        .flat_map(|quality| quality.iter().enumerate())
+        .filter(|(_, quality)| !quality.is_measured())
+        .collect::<Vec<_>>();
+    result
+        .write_u32::<LittleEndian>(guessed.len() as u32)
+        .unwrap();
+    for (ix, quality) in guessed {
+        result.write_u32::<LittleEndian>(ix as u32).unwrap();
+        result.push(data_quality_to_u8(*quality));
+    }
+
+    result
+        .write_u32::<LittleEndian>(cone.explored.len() as u32)
+        .unwrap();
+    for node in cone.explored.iter() {
+        let mut flags = 0;
+        if node.reachable {
+            flags |= FLAG_REACHABLE;
+        }
+        if node.explored {
+            flags |= FLAG_EXPLORED;
+        }
+        if node.reference.is_some() {
+            flags |= FLAG_HAS_REFERENCE;
+        }
+        result.push(flags);
+        write_ix(&mut result, node.ix);
+        write_ix(&mut result, node.reference.unwrap_or((0, 0)));
+        result.write_f32::<LittleEndian>(node.height).unwrap();
+        result.write_f32::<LittleEndian>(node.distance).unwrap();
+        result.write_f32::<LittleEndian>(node.clearance).unwrap();
+        result.write_f32::<LittleEndian>(node.min_agl).unwrap();
+        result.write_f32::<LittleEndian>(node.ground_speed).unwrap();
+    }
+
+    result
+}
+
+/// Decodes a cone written by `encode_cone`. Returns `None` for data
+/// written in an unknown format version or truncated data.
+pub fn decode_cone(bytes: &[u8]) -> Option<CachedCone> {
+    let mut reader = Cursor::new(bytes);
+
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic).ok()?;
+    if &magic != MAGIC || reader.read_u8().ok()? != FORMAT_VERSION {
+        return None;
+    }
+
+    let rows = reader.read_u32::<LittleEndian>().ok()? as usize;
+    let cols = reader.read_u32::<LittleEndian>().ok()? as usize;
+    let mut floats = [0.0f32; 7];
+    for value in floats.iter_mut() {
+        *value = reader.read_f32::<LittleEndian>().ok()?;
+    }
+    let data_source = data_source_from_u8(reader.read_u8().ok()?)?;
+    let start_ix = read_ix(&mut reader)?;
+    let n_bearings = reader.read_u8().ok()?;
+    let mut border_bearings = Vec::with_capacity(n_bearings as usize);
+    for _ in 0..n_bearings {
+        border_bearings.push(reader.read_u16::<LittleEndian>().ok()?);
+    }
+
+    let mut heights = Vec::with_capacity(rows * cols);
+    for _ in 0..rows * cols {
+        heights.push(reader.read_i16::<LittleEndian>().ok()?);
+    }
+
+    let n_guessed = reader.read_u32::<LittleEndian>().ok()? as usize;
+    let mut data_quality = None;
+    for _ in 0..n_guessed {
+        let ix = reader.read_u32::<LittleEndian>().ok()? as usize;
+        let quality = data_quality_from_u8(reader.read_u8().ok()?)?;
+        *data_quality
+            .get_or_insert_with(|| Array2::from_elem((rows, cols), DataQuality::Measured))
+            .as_slice_mut()?
+            .get_mut(ix)? = quality;
+    }
+
+    let n_nodes = reader.read_u32::<LittleEndian>().ok()? as usize;
+    let mut explored = Vec::with_capacity(n_nodes);
+    for _ in 0..n_nodes {
+        let flags = reader.read_u8().ok()?;
+        let ix = read_ix(&mut reader)?;
+        let reference = read_ix(&mut reader)?;
+        let height = reader.read_f32::<LittleEndian>().ok()?;
+        let distance = reader.read_f32::<LittleEndian>().ok()?;
+        let clearance = reader.read_f32::<LittleEndian>().ok()?;
+        let min_agl = reader.read_f32::<LittleEndian>().ok()?;
+        let ground_speed = reader.read_f32::<LittleEndian>().ok()?;
+        explored.push(Node {
+            height,
+            ix,
+            reference: if flags & FLAG_HAS_REFERENCE != 0 {
+                Some(reference)
+            } else {
+                None
+            },
+            distance,
+            reachable: flags & FLAG_REACHABLE != 0,
+            clearance,
+            min_agl,
+            ground_speed,
+            explored: flags & FLAG_EXPLORED != 0,
+        });
+    }
+
+    Some(CachedCone {
+        explored,
+        height_grid: HeightGrid {
+            heights: Array2::from_shape_vec((rows, cols), heights).ok()?,
+            cell_size: floats[0],
+            min_cell_size: floats[1],
+            latitudes: (floats[2], floats[3]),
+            longitudes: (floats[4], floats[5]),
+            data_source,
+            data_quality,
+        },
+        ground_height: floats[6],
+        start_ix,
+        border_bearings,
+    })
+}
+
+#[cfg(test)]
+#[path = "./cone_cache_test.rs"]
+mod cone_cache_test;
@@ -0,0 +1,89 @@
+use ndarray::Array2;
+
+use crate::{
+    height_data::{DataQuality, DataSource, HeightGrid},
+    search::{search_from_height_grid, SearchQuery},
+};
+
+use super::{cone_cache_key, decode_cone, encode_cone, CachedCone};
+
+#[test]
+fn test_encode_decode_cone() {
+    let mut heights = Array2::from_elem((12, 10), 1000);
+    heights[[3, 4]] = 1100;
+    let mut data_quality = Array2::from_elem((12, 10), DataQuality::Measured);
+    data_quality[[3, 4]] = DataQuality::VoidFilled;
+    data_quality[[7, 2]] = DataQuality::Synthesized;
+    let query = SearchQuery {
+        additional_height: 200.0,
+        safety_margin: 50.0,
+        ..Default::default()
+    };
+    let result = search_from_height_grid(
+        HeightGrid {
+            heights,
+            cell_size: 50.0,
+            min_cell_size: 50.0,
+            latitudes: (47.0, 47.1),
+            longitudes: (11.0, 11.1),
+            data_source: DataSource::Coarse,
+            data_quality: Some(data_quality),
+        },
+        (6, 6),
+        query,
+    )
+    .unwrap();
+
+    let cone = CachedCone {
+        explored: result.explored.into_it().collect(),
+        height_grid: result.height_grid,
+        ground_height: result.ground_height,
+        start_ix: result.start_ix,
+        border_bearings: result.border_bearings,
+    };
+
+    let decoded = decode_cone(&encode_cone(&cone)).unwrap();
+
+    assert_eq!(decoded.height_grid.heights, cone.height_grid.heights);
+    assert_eq!(decoded.height_grid.latitudes, cone.height_grid.latitudes);
+    assert_eq!(decoded.height_grid.longitudes, cone.height_grid.longitudes);
+    assert_eq!(decoded.height_grid.cell_size, cone.height_grid.cell_size);
+    assert_eq!(decoded.height_grid.data_source, DataSource::Coarse);
+    for ix in cone.height_grid.heights.indexed_iter().map(|(ix, _)| ix) {
+        assert_eq!(
+            decoded.height_grid.data_quality_at(ix),
+            cone.height_grid.data_quality_at(ix)
+        );
+    }
+    assert_eq!(decoded.ground_height, cone.ground_height);
+    assert_eq!(decoded.start_ix, cone.start_ix);
+    // The grid is smaller than the cone.
+    assert!(!cone.border_bearings.is_empty());
+    assert_eq!(decoded.border_bearings, cone.border_bearings);
+    assert_eq!(decoded.explored.len(), cone.explored.len());
+    for (a, b) in decoded.explored.iter().zip(cone.explored.iter()) {
+        assert_eq!(a.ix, b.ix);
+        assert_eq!(a.reference, b.reference);
+        assert_eq!(a.height, b.height);
+        assert_eq!(a.distance, b.distance);
+        assert_eq!(a.reachable, b.reachable);
+        assert_eq!(a.clearance, b.clearance);
+        assert_eq!(a.ground_speed, b.ground_speed);
+    }
+}
+
+#[test]
+fn test_decode_invalid() {
+    assert!(decode_cone(b"").is_none());
+    assert!(decode_cone(b"CONE\x63").is_none());
+    assert!(decode_cone(b"CONE\x01\x02").is_none());
+}
+
+#[test]
+fn test_cone_cache_key() {
+    let key = cone_cache_key("47_11_50", "0000abcd");
+    assert_eq!(key.len(), 64);
+    assert_eq!(key, cone_cache_key("47_11_50", "0000abcd"));
+    assert_ne!(key, cone_cache_key("47_11_50", "0000abce"));
+    assert_ne!(key, cone_cache_key("47_11_51", "0000abcd"));
+}
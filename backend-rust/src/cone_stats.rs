@@ -0,0 +1,121 @@
+use std::f32::consts::PI;
+
+use ndarray::Array2;
+use serde::Serialize;
+
+use crate::{
+    height_data::{meter_in_arcseconds, HeightGrid},
+    search::Node,
+};
+
+const METERS_PER_DEGREE_LATITUDE: f32 = 1852.0 * 60.0;
+
+/// Summary of a finished search, used for exports and metadata.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ConeStats {
+    pub reachable_cells: usize,
+    /// Area of all reachable cells in square kilometers.
+    pub area_km2: f32,
+    /// Largest glide path length to a reachable cell in meters.
+    pub max_distance: f32,
+    pub min_agl: f32,
+    pub max_agl: f32,
+    /// Reachable cells where landing is prohibited, these are not part of the
+    /// other stats.
+    pub prohibited_cells: usize,
+}
+
+pub fn agl_of(node: &Node, grid: &HeightGrid) -> f32 {
+    node.height - grid.heights[(node.ix.0 as usize, node.ix.1 as usize)] as f32
+}
+
+pub fn cone_stats(
+    explored: &[Node],
+    grid: &HeightGrid,
+    landing_prohibited: Option<&Array2<bool>>,
+) -> ConeStats {
+    let mut stats = ConeStats {
+        reachable_cells: 0,
+        area_km2: 0.0,
+        max_distance: 0.0,
+        min_agl: f32::MAX,
+        max_agl: f32::MIN,
+        prohibited_cells: 0,
+    };
+    for node in explored.iter().filter(|n| n.reachable) {
+        if landing_prohibited.is_some_and(|p| p[(node.ix.0 as usize, node.ix.1 as usize)]) {
+            stats.prohibited_cells += 1;
+            continue;
+        }
+        let agl = agl_of(node, grid);
+        stats.reachable_cells += 1;
+        stats.max_distance = stats.max_distance.max(node.distance);
+        stats.min_agl = stats.min_agl.min(agl);
+        stats.max_agl = stats.max_agl.max(agl);
+    }
+    if stats.reachable_cells == 0 {
+        stats.min_agl = 0.0;
+        stats.max_agl = 0.0;
+    }
+    stats.area_km2 = stats.reachable_cells as f32 * grid.cell_size * grid.cell_size / 1e6;
+    stats
+}
+
+/// Height above ground range covered by one band of an export.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AglBand {
+    pub min: f32,
+    pub max: f32,
+}
+
+/// Splits `[min_agl, max_agl]` into `count` equally sized bands.
+pub fn agl_bands(min_agl: f32, max_agl: f32, count: usize) -> Vec<AglBand> {
+    let width = (max_agl - min_agl) / count as f32;
+    (0..count)
+        .map(|i| AglBand {
+            min: min_agl + width * i as f32,
+            max: min_agl + width * (i + 1) as f32,
+        })
+        .collect()
+}
+
+/// Index of the band containing `agl`, values outside the bands are clamped
+/// to the first/last band.
+pub fn agl_band_of(bands: &[AglBand], agl: f32) -> usize {
+    bands
+        .iter()
+        .position(|band| agl < band.max)
+        .unwrap_or(bands.len() - 1)
+}
+
+/// Closed ring of `(longitude, latitude)` points at `radius_m` around the point.
+pub fn distance_ring(
+    latitude: f32,
+    longitude: f32,
+    radius_m: f32,
+    segments: usize,
+) -> Vec<(f32, f32)> {
+    let lat_radius = radius_m / METERS_PER_DEGREE_LATITUDE;
+    let lon_radius = radius_m * meter_in_arcseconds(latitude) / 3600.0;
+    (0..=segments)
+        .map(|i| {
+            let angle = 2.0 * PI * (i % segments) as f32 / segments as f32;
+            (
+                longitude + lon_radius * angle.sin(),
+                latitude + lat_radius * angle.cos(),
+            )
+        })
+        .collect()
+}
+
+/// Radii of distance rings every `spacing_m` up to `max_distance`.
+pub fn ring_radii(max_distance: f32, spacing_m: f32) -> Vec<f32> {
+    (1..)
+        .map(|i| i as f32 * spacing_m)
+        .take_while(|r| *r <= max_distance)
+        .collect()
+}
+
+#[cfg(test)]
+#[path = "./cone_stats_test.rs"]
+mod cone_stats_test;
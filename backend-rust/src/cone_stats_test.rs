@@ -0,0 +1,77 @@
+use approx::assert_relative_eq;
+use ndarray::Array2;
+
+use crate::{height_data::HeightGrid, search::Node};
+
+use super::{agl_band_of, agl_bands, cone_stats, distance_ring, ring_radii, AglBand};
+
+#[test]
+fn test_cone_stats() {
+    let grid = HeightGrid::for_test(
+        Array2::from_shape_vec((2, 2), vec![100, 200, 300, 400]).unwrap(),
+        (47.0, 47.1),
+        (11.0, 11.1),
+    );
+    let node = |ix, height, distance, reachable| Node {
+        height,
+        ix,
+        reference: None,
+        distance,
+        reachable,
+        clearance: f32::INFINITY,
+        min_agl: f32::INFINITY,
+        ground_speed: 0.0,
+        explored: true,
+    };
+
+    let nodes = [
+        node((0, 0), 150.0, 0.0, true),
+        node((0, 1), 350.0, 100.0, true),
+        node((1, 1), 350.0, 150.0, false),
+    ];
+    let stats = cone_stats(&nodes, &grid, None);
+
+    assert_eq!(stats.reachable_cells, 2);
+    assert_relative_eq!(stats.area_km2, 0.02);
+    assert_eq!(stats.max_distance, 100.0);
+    assert_eq!(stats.min_agl, 50.0);
+    assert_eq!(stats.max_agl, 150.0);
+    assert_eq!(stats.prohibited_cells, 0);
+
+    let mut landing_prohibited = Array2::from_elem((2, 2), false);
+    landing_prohibited[(0, 1)] = true;
+    let stats = cone_stats(&nodes, &grid, Some(&landing_prohibited));
+
+    assert_eq!(stats.reachable_cells, 1);
+    assert_relative_eq!(stats.area_km2, 0.01);
+    assert_eq!(stats.max_distance, 0.0);
+    assert_eq!(stats.max_agl, 50.0);
+    assert_eq!(stats.prohibited_cells, 1);
+}
+
+#[test]
+fn test_agl_bands() {
+    let bands = agl_bands(0.0, 100.0, 4);
+    assert_eq!(
+        bands[1],
+        AglBand {
+            min: 25.0,
+            max: 50.0
+        }
+    );
+    assert_eq!(agl_band_of(&bands, -5.0), 0);
+    assert_eq!(agl_band_of(&bands, 30.0), 1);
+    assert_eq!(agl_band_of(&bands, 100.0), 3);
+}
+
+#[test]
+fn test_distance_rings() {
+    assert_eq!(ring_radii(12000.0, 5000.0), vec![5000.0, 10000.0]);
+    assert!(ring_radii(4000.0, 5000.0).is_empty());
+
+    let ring = distance_ring(0.0, 0.0, 1852.0 * 60.0, 4);
+    assert_eq!(ring.len(), 5);
+    assert_eq!(ring[0], ring[4]);
+    assert_relative_eq!(ring[0].1, 1.0);
+    assert_relative_eq!(ring[1].0, 1.0, epsilon = 1e-3);
+}
@@ -0,0 +1,408 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::OnceLock,
+    time::Duration,
+};
+
+use serde::Deserialize;
+
+use crate::{
+    search::{MAX_STARTS, MAX_THERMALS},
+    view::MAX_ZOOM,
+};
+
+static DATA_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Directory with the DEM tiles, search indexes and other data files,
+/// `./data` unless set with `set_data_dir`.
+pub fn data_dir() -> &'static Path {
+    DATA_DIR.get_or_init(|| PathBuf::from("./data"))
+}
+
+/// Sets the data directory. Fails if it was set or used already, so it has
+/// to happen before any tile is loaded.
+pub fn set_data_dir(path: PathBuf) -> Result<(), String> {
+    DATA_DIR
+        .set(path)
+        .map_err(|_| "The data directory is already in use".to_string())
+}
+
+/// Range a parameter of a request is clamped to.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+pub struct Limit {
+    pub min: f32,
+    pub max: f32,
+}
+
+impl Limit {
+    pub fn clamp(&self, value: f32) -> f32 {
+        value.clamp(self.min, self.max)
+    }
+}
+
+/// Limits of the flight parameters of requests, so public instances can
+/// restrict expensive searches.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ParameterLimits {
+    pub cell_size: Limit,
+    pub glide_number: Limit,
+    pub additional_height: Limit,
+    pub wind_speed: Limit,
+    pub trim_speed: Limit,
+    pub climb_rate: Limit,
+    /// Maximum number of starts of a multi start search, including `lat`/`lon`.
+    /// Together with the thermals it must not exceed `MAX_STARTS`.
+    pub starts_maximum: usize,
+}
+
+impl Default for ParameterLimits {
+    fn default() -> Self {
+        ParameterLimits {
+            cell_size: Limit {
+                min: 30.0,
+                max: 500.0,
+            },
+            glide_number: Limit {
+                min: 1.0,
+                max: 15.0,
+            },
+            additional_height: Limit {
+                min: 0.0,
+                max: 1000.0,
+            },
+            wind_speed: Limit {
+                min: 0.0,
+                max: 50.0,
+            },
+            trim_speed: Limit {
+                min: 0.0,
+                max: 80.0,
+            },
+            climb_rate: Limit {
+                min: 0.5,
+                max: 20.0,
+            },
+            starts_maximum: 10,
+        }
+    }
+}
+
+impl ParameterLimits {
+    pub fn validate(&self) -> Result<(), String> {
+        let limits = [
+            ("cell_size", self.cell_size),
+            ("glide_number", self.glide_number),
+            ("additional_height", self.additional_height),
+            ("wind_speed", self.wind_speed),
+            ("trim_speed", self.trim_speed),
+            ("climb_rate", self.climb_rate),
+        ];
+        for (name, limit) in limits {
+            if limit.min.is_nan() || limit.max.is_nan() || limit.min > limit.max {
+                return Err(format!("The minimum of {name} is above its maximum"));
+            }
+        }
+        if self.cell_size.min <= 0.0 || self.glide_number.min <= 0.0 || self.climb_rate.min <= 0.0 {
+            return Err("cell_size, glide_number and climb_rate must be positive".to_string());
+        }
+        if self.starts_maximum == 0 {
+            return Err("starts_maximum must be at least 1".to_string());
+        }
+        // The climbed thermals are further starts of the same search.
+        if self.starts_maximum + MAX_THERMALS > MAX_STARTS {
+            return Err(format!(
+                "starts_maximum must be at most {}",
+                MAX_STARTS - MAX_THERMALS
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Paths, cache sizes and parameter limits of the server.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ServerConfig {
+    pub data_dir: PathBuf,
+    /// Root of the filesystem cache storage with the map tiles and the
+    /// persistent cone cache, the data directory if unset.
+    pub tile_cache_dir: Option<PathBuf>,
+    /// Built frontend served at `/static`, not served if unset.
+    pub static_dir: Option<PathBuf>,
+    /// Number of PNG map tiles kept in memory.
+    pub png_cache_size: usize,
+    /// Number of WebP map tiles kept in memory.
+    pub webp_cache_size: usize,
+    /// Number of searches kept in memory.
+    pub cone_cache_size: usize,
+    /// Estimated bytes of all searches kept in memory.
+    pub cone_cache_bytes: usize,
+    /// Seconds after which a search is dropped from memory, unlimited if
+    /// unset. Images rendered from searches expire after the same time.
+    pub cone_cache_ttl_secs: Option<u64>,
+    /// Number of images rendered from searches, e.g. by `/agl_image`, kept
+    /// in memory.
+    pub raster_cache_size: usize,
+    /// Bytes of all images rendered from searches kept in memory.
+    pub raster_cache_bytes: usize,
+    pub parameter_limits: ParameterLimits,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            data_dir: PathBuf::from("./data"),
+            tile_cache_dir: None,
+            static_dir: Some(PathBuf::from("./static")),
+            png_cache_size: 500,
+            webp_cache_size: 4000,
+            cone_cache_size: 200,
+            cone_cache_bytes: 2 << 30,
+            cone_cache_ttl_secs: None,
+            raster_cache_size: 500,
+            raster_cache_bytes: 256 << 20,
+            parameter_limits: ParameterLimits::default(),
+        }
+    }
+}
+
+impl ServerConfig {
+    pub fn tile_cache_dir(&self) -> &Path {
+        self.tile_cache_dir.as_deref().unwrap_or(&self.data_dir)
+    }
+}
+
+/// Format of the log lines.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human readable lines.
+    #[default]
+    Text,
+    /// One JSON object per line, for log collectors.
+    Json,
+}
+
+/// Logging of the server.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct LogConfig {
+    /// Filter of the logged events in the syntax of `RUST_LOG`, e.g. `debug`
+    /// or `info,backend_rust=debug`. `RUST_LOG` takes precedence if set.
+    pub level: String,
+    pub format: LogFormat,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        LogConfig {
+            // Rocket's own lines for every request are replaced by the
+            // request log of the server.
+            level: "info,rocket=warn".to_string(),
+            format: LogFormat::Text,
+        }
+    }
+}
+
+/// Route groups that can be switched off, e.g. for small deployments that
+/// only serve cones. Disabled routes answer with 410 Gone.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct EndpointGroups {
+    /// `/opentopomap`, `/openstreetmap` and `/satellite` map tiles.
+    pub tile_proxy: bool,
+    /// KML, KMZ, GeoJSON, CZML, GPX, waypoint and zip exports, the outline,
+    /// `/flight_path` and `/flight_cone_region`.
+    pub exports: bool,
+    /// `/search_ws`, `/flying_sites` and the launch comparison. The search
+    /// indexes are not loaded when disabled.
+    pub location_search: bool,
+    /// `/api/v1/lookup`.
+    pub elevation_api: bool,
+    /// `/airspaces`.
+    pub airspaces: bool,
+    /// `/datasets`.
+    pub datasets: bool,
+}
+
+impl Default for EndpointGroups {
+    fn default() -> Self {
+        EndpointGroups {
+            tile_proxy: true,
+            exports: true,
+            location_search: true,
+            elevation_api: true,
+            airspaces: true,
+            datasets: true,
+        }
+    }
+}
+
+impl EndpointGroups {
+    pub fn disabled(&self) -> Vec<&'static str> {
+        [
+            ("tile_proxy", self.tile_proxy),
+            ("exports", self.exports),
+            ("location_search", self.location_search),
+            ("elevation_api", self.elevation_api),
+            ("airspaces", self.airspaces),
+            ("datasets", self.datasets),
+        ]
+        .into_iter()
+        .filter(|(_, enabled)| !enabled)
+        .map(|(name, _)| name)
+        .collect()
+    }
+}
+
+/// JSONL file of locations in the data directory that is refreshed from a url.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct SiteDataSource {
+    pub url: String,
+    /// E.g. `search_data_flying_sites.jsonl`.
+    pub file_name: String,
+}
+
+/// Background jobs keeping the data directory up to date.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct RefreshConfig {
+    /// Hours between two runs of the jobs, `0` disables them.
+    pub interval_hours: u64,
+    /// UTC hour of the first run, e.g. `3` for a nightly refresh at night in
+    /// Europe. The first run is one interval after the start if unset.
+    pub start_hour: Option<u64>,
+    pub site_data: Vec<SiteDataSource>,
+    /// Download tiles that are missing or fail the checksums of the hgt
+    /// manifest again from `dem_url_template`.
+    pub dem: bool,
+}
+
+impl RefreshConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.start_hour.is_some_and(|hour| hour >= 24) {
+            return Err("start_hour must be below 24".to_string());
+        }
+        for source in self.site_data.iter() {
+            let file_name = &source.file_name;
+            if !file_name.ends_with(".jsonl")
+                || file_name.contains(['/', '\\'])
+                || file_name.starts_with('.')
+            {
+                return Err(format!(
+                    "{file_name} is not a .jsonl file name in the data directory"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_hours * 3600)
+    }
+
+    /// Time from `now` (seconds since the unix epoch) to the first run.
+    pub fn first_delay(&self, now: u64) -> Duration {
+        match self.start_hour {
+            Some(hour) => {
+                const DAY: u64 = 24 * 3600;
+                let until_start = (hour * 3600 + DAY - now % DAY) % DAY;
+                Duration::from_secs(until_start)
+            }
+            None => self.interval(),
+        }
+    }
+}
+
+/// What is loaded for the home region in the background after startup, so
+/// the first requests after a deploy find it in memory.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct PreloadConfig {
+    /// Read the DEM tiles of the home region into memory, as many as the
+    /// tile cache holds.
+    pub dem: bool,
+    /// Zoom levels of the OpenTopoMap tiles of the home region that are
+    /// fetched into the cache storage and memory, e.g. `[10, 11, 12]`.
+    pub map_zoom_levels: Vec<u8>,
+    /// Most map tiles preloaded, bounds the load on the tile server.
+    pub max_map_tiles: usize,
+}
+
+impl Default for PreloadConfig {
+    fn default() -> Self {
+        PreloadConfig {
+            dem: true,
+            map_zoom_levels: vec![],
+            max_map_tiles: 2000,
+        }
+    }
+}
+
+impl PreloadConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(zoom) = self.map_zoom_levels.iter().find(|zoom| **zoom > MAX_ZOOM) {
+            return Err(format!("Map zoom level {zoom} is above {MAX_ZOOM}"));
+        }
+        Ok(())
+    }
+}
+
+/// Where the tile cache and the persistent cone cache are stored.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    /// Files in the tile cache directory.
+    #[default]
+    Filesystem,
+    S3,
+}
+
+/// Bucket and credentials of the S3 cache storage, see `S3Storage`.
+#[derive(Clone, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct StorageConfig {
+    pub backend: StorageBackend,
+    pub bucket: String,
+    pub region: String,
+    /// E.g. `https://s3.eu-central-1.amazonaws.com` or the url of a MinIO instance.
+    pub endpoint: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+}
+
+/// Workers and bounds of the searches submitted to `/flight_cone_job`.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ConeJobConfig {
+    /// Searches running at the same time.
+    pub workers: usize,
+    /// Searches waiting for a worker at most, further jobs are rejected.
+    pub max_queued: usize,
+    /// Results kept for polling, the oldest are dropped first.
+    pub max_finished: usize,
+}
+
+impl Default for ConeJobConfig {
+    fn default() -> Self {
+        ConeJobConfig {
+            workers: 2,
+            max_queued: 100,
+            max_finished: 50,
+        }
+    }
+}
+
+impl ConeJobConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.workers == 0 || self.max_queued == 0 || self.max_finished == 0 {
+            return Err("workers, max_queued and max_finished must be at least 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[path = "./config_test.rs"]
+mod config_test;
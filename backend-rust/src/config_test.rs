@@ -0,0 +1,158 @@
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::search::{MAX_STARTS, MAX_THERMALS};
+
+use super::{
+    data_dir, ConeJobConfig, EndpointGroups, Limit, ParameterLimits, PreloadConfig, RefreshConfig,
+    ServerConfig, SiteDataSource,
+};
+
+#[test]
+fn test_default_config() {
+    let config = ServerConfig::default();
+    assert_eq!(config.tile_cache_dir(), Path::new("./data"));
+    assert_eq!(config.parameter_limits.validate(), Ok(()));
+    assert_eq!(data_dir(), Path::new("./data"));
+
+    let config = ServerConfig {
+        tile_cache_dir: Some(PathBuf::from("/var/cache/hikeandfly")),
+        ..ServerConfig::default()
+    };
+    assert_eq!(config.tile_cache_dir(), Path::new("/var/cache/hikeandfly"));
+}
+
+#[test]
+fn test_parameter_limits() {
+    let limit = Limit {
+        min: 30.0,
+        max: 500.0,
+    };
+    assert_eq!(limit.clamp(10.0), 30.0);
+    assert_eq!(limit.clamp(200.0), 200.0);
+    assert_eq!(limit.clamp(1000.0), 500.0);
+
+    let inverted = ParameterLimits {
+        wind_speed: Limit {
+            min: 50.0,
+            max: 0.0,
+        },
+        ..ParameterLimits::default()
+    };
+    assert!(inverted.validate().unwrap_err().contains("wind_speed"));
+
+    let no_glide = ParameterLimits {
+        glide_number: Limit {
+            min: 0.0,
+            max: 15.0,
+        },
+        ..ParameterLimits::default()
+    };
+    assert!(no_glide.validate().is_err());
+
+    let no_starts = ParameterLimits {
+        starts_maximum: 0,
+        ..ParameterLimits::default()
+    };
+    assert!(no_starts.validate().is_err());
+
+    let too_many_starts = ParameterLimits {
+        starts_maximum: MAX_STARTS,
+        ..ParameterLimits::default()
+    };
+    assert!(too_many_starts
+        .validate()
+        .unwrap_err()
+        .contains("starts_maximum"));
+    let most_starts = ParameterLimits {
+        starts_maximum: MAX_STARTS - MAX_THERMALS,
+        ..ParameterLimits::default()
+    };
+    assert!(most_starts.validate().is_ok());
+}
+
+#[test]
+fn test_refresh_config() {
+    let source = |file_name: &str| SiteDataSource {
+        url: "https://example.com/sites.jsonl".to_string(),
+        file_name: file_name.to_string(),
+    };
+    let config = RefreshConfig {
+        interval_hours: 24,
+        start_hour: Some(3),
+        site_data: vec![source("search_data_flying_sites.jsonl")],
+        dem: true,
+    };
+    assert_eq!(config.validate(), Ok(()));
+    assert_eq!(config.interval(), Duration::from_secs(24 * 3600));
+
+    // 2024-01-01 01:00 and 05:00 UTC.
+    assert_eq!(
+        config.first_delay(1_704_070_800),
+        Duration::from_secs(2 * 3600)
+    );
+    assert_eq!(
+        config.first_delay(1_704_085_200),
+        Duration::from_secs(22 * 3600)
+    );
+    let unscheduled = RefreshConfig {
+        start_hour: None,
+        ..config.clone()
+    };
+    assert_eq!(
+        unscheduled.first_delay(1_704_070_800),
+        unscheduled.interval()
+    );
+
+    for file_name in ["../etc/passwd.jsonl", "sites.json", ".jsonl"] {
+        let invalid = RefreshConfig {
+            site_data: vec![source(file_name)],
+            ..config.clone()
+        };
+        assert!(invalid.validate().unwrap_err().contains(file_name));
+    }
+    let late = RefreshConfig {
+        start_hour: Some(24),
+        ..config
+    };
+    assert!(late.validate().is_err());
+}
+
+#[test]
+fn test_endpoint_groups() {
+    assert!(EndpointGroups::default().disabled().is_empty());
+
+    let lean = EndpointGroups {
+        tile_proxy: false,
+        exports: false,
+        ..EndpointGroups::default()
+    };
+    assert_eq!(lean.disabled(), vec!["tile_proxy", "exports"]);
+}
+
+#[test]
+fn test_preload_config() {
+    let config = PreloadConfig::default();
+    assert_eq!(config.validate(), Ok(()));
+    assert!(config.map_zoom_levels.is_empty());
+
+    let too_deep = PreloadConfig {
+        map_zoom_levels: vec![12, 19],
+        ..config
+    };
+    assert!(too_deep.validate().unwrap_err().contains("19"));
+}
+
+#[test]
+fn test_cone_job_config() {
+    let config = ConeJobConfig::default();
+    assert_eq!(config.validate(), Ok(()));
+
+    let no_workers = ConeJobConfig {
+        workers: 0,
+        ..config
+    };
+    assert!(no_workers.validate().is_err());
+}
@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+
+use ndarray::Array2;
+use serde::Serialize;
+
+use crate::{
+    geojson::{Feature, FeatureCollection, Geometry},
+    height_data::HeightGrid,
+};
+
+/// Most levels traced for one grid, bounds the work of small steps.
+pub const MAX_CONTOUR_LEVELS: usize = 200;
+
+/// Edge between two neighbouring grid points a contour crosses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Edge {
+    /// Between `(row, col)` and `(row, col + 1)`.
+    Horizontal(usize, usize),
+    /// Between `(row, col)` and `(row + 1, col)`.
+    Vertical(usize, usize),
+}
+
+/// Position where the contour at `level` crosses `edge`, as fractional
+/// `(row, col)`, interpolated linearly between the grid points.
+fn crossing(values: &Array2<f32>, edge: Edge, level: f32) -> (f32, f32) {
+    let ((row, col), end) = match edge {
+        Edge::Horizontal(row, col) => ((row, col), (row, col + 1)),
+        Edge::Vertical(row, col) => ((row, col), (row + 1, col)),
+    };
+    let (a, b) = (values[(row, col)], values[end]);
+    let t = ((level - a) / (b - a)).clamp(0.0, 1.0);
+    (
+        row as f32 + (end.0 - row) as f32 * t,
+        col as f32 + (end.1 - col) as f32 * t,
+    )
+}
+
+/// Segments of the contour at `level` within the square with its lower
+/// corner at `(row, col)`, as pairs of the edges they connect. Points at
+/// exactly `level` count as above it.
+fn square_segments(values: &Array2<f32>, row: usize, col: usize, level: f32) -> Vec<(Edge, Edge)> {
+    let above = |r: usize, c: usize| values[(r, c)] >= level;
+    // Corners counterclockwise from `(row, col)` and the edges after them.
+    let corners = [
+        above(row, col),
+        above(row, col + 1),
+        above(row + 1, col + 1),
+        above(row + 1, col),
+    ];
+    let edges = [
+        Edge::Horizontal(row, col),
+        Edge::Vertical(row, col + 1),
+        Edge::Horizontal(row + 1, col),
+        Edge::Vertical(row, col),
+    ];
+    let crossed = (0..4)
+        .filter(|i| corners[*i] != corners[(i + 1) % 4])
+        .map(|i| edges[i])
+        .collect::<Vec<_>>();
+
+    match crossed.len() {
+        2 => vec![(crossed[0], crossed[1])],
+        4 => {
+            // Saddle, the average of the corners decides whether the two
+            // corners above the level are connected through the center.
+            let center = (values[(row, col)]
+                + values[(row, col + 1)]
+                + values[(row + 1, col + 1)]
+                + values[(row + 1, col)])
+                / 4.0;
+            if (center >= level) == corners[0] {
+                // Cut off the corners at (row, col + 1) and (row + 1, col).
+                vec![(edges[0], edges[1]), (edges[2], edges[3])]
+            } else {
+                vec![(edges[3], edges[0]), (edges[1], edges[2])]
+            }
+        }
+        _ => vec![],
+    }
+}
+
+/// Iso lines of `values` at `level` with marching squares, as polylines of
+/// fractional `(row, col)` grid positions. Lines around an area are closed,
+/// i.e. end with their first point. Lines only end at the border of the
+/// grid.
+pub fn contour_lines(values: &Array2<f32>, level: f32) -> Vec<Vec<(f32, f32)>> {
+    let shape = values.shape();
+    let mut segments = vec![];
+    for row in 0..shape[0].saturating_sub(1) {
+        for col in 0..shape[1].saturating_sub(1) {
+            segments.extend(square_segments(values, row, col, level));
+        }
+    }
+
+    // Every edge is shared by at most two squares, so it is the end of at
+    // most two segments.
+    let mut by_edge = HashMap::<Edge, Vec<usize>>::new();
+    for (i, (a, b)) in segments.iter().enumerate() {
+        by_edge.entry(*a).or_default().push(i);
+        by_edge.entry(*b).or_default().push(i);
+    }
+
+    let mut used = vec![false; segments.len()];
+    // Follows unused segments from `edge`, returns the edges passed.
+    let follow = |mut edge: Edge, used: &mut Vec<bool>| {
+        let mut edges = vec![];
+        while let Some(&next) = by_edge[&edge].iter().find(|i| !used[**i]) {
+            used[next] = true;
+            let (a, b) = segments[next];
+            edge = if a == edge { b } else { a };
+            edges.push(edge);
+        }
+        edges
+    };
+
+    let mut lines = vec![];
+    for i in 0..segments.len() {
+        if used[i] {
+            continue;
+        }
+        used[i] = true;
+        let (start, end) = segments[i];
+        let forward = follow(end, &mut used);
+        let backward = follow(start, &mut used);
+
+        let edges = backward
+            .into_iter()
+            .rev()
+            .chain([start, end])
+            .chain(forward)
+            .collect::<Vec<_>>();
+        lines.push(
+            edges
+                .into_iter()
+                .map(|edge| crossing(values, edge, level))
+                .collect(),
+        );
+    }
+    lines
+}
+
+/// Levels from `step` up to the highest value, at most
+/// `MAX_CONTOUR_LEVELS` of them.
+pub fn contour_levels(values: &Array2<f32>, step: f32) -> Vec<f32> {
+    let max = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    if step <= 0.0 || max < step {
+        return vec![];
+    }
+    let count = ((max / step).floor() as usize).min(MAX_CONTOUR_LEVELS);
+    (1..=count).map(|i| i as f32 * step).collect()
+}
+
+/// Properties of a contour line.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ContourProperties {
+    /// Height above ground in meters along the line.
+    pub agl: f32,
+}
+
+/// Lines of equal height above ground every `step` meters, one feature per
+/// level. `agl` holds the height above ground per cell of `grid`, cells that
+/// are not reachable must be below 0 so the lines close around the cone.
+pub fn agl_contours(
+    agl: &Array2<f32>,
+    grid: &HeightGrid,
+    step: f32,
+) -> FeatureCollection<ContourProperties> {
+    let position = |(row, col): (f32, f32)| {
+        let (lat, lon) = grid.coordinates_at(row, col);
+        [lon, lat]
+    };
+
+    FeatureCollection {
+        features: contour_levels(agl, step)
+            .into_iter()
+            .map(|level| Feature {
+                geometry: Geometry::MultiLineString {
+                    coordinates: contour_lines(agl, level)
+                        .into_iter()
+                        .map(|line| line.into_iter().map(position).collect())
+                        .collect(),
+                },
+                properties: ContourProperties { agl: level },
+            })
+            .collect(),
+        metadata: None,
+    }
+}
+
+#[cfg(test)]
+#[path = "./contours_test.rs"]
+mod contours_test;
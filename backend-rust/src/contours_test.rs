@@ -0,0 +1,114 @@
+use approx::assert_relative_eq;
+use ndarray::{arr2, Array2};
+
+use crate::{geojson::Geometry, height_data::HeightGrid};
+
+use super::{agl_contours, contour_levels, contour_lines, MAX_CONTOUR_LEVELS};
+
+fn sorted(mut points: Vec<(f32, f32)>) -> Vec<(f32, f32)> {
+    points.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    points
+}
+
+#[test]
+fn test_contour_around_peak() {
+    let mut values = Array2::zeros((5, 5));
+    values[(2, 2)] = 300.0;
+
+    let lines = contour_lines(&values, 100.0);
+    assert_eq!(lines.len(), 1);
+    let line = &lines[0];
+    assert_eq!(line.len(), 5);
+    assert_eq!(line.first(), line.last());
+
+    let expected = vec![
+        (1.0 + 1.0 / 3.0, 2.0),
+        (2.0, 1.0 + 1.0 / 3.0),
+        (2.0, 2.0 + 2.0 / 3.0),
+        (2.0 + 2.0 / 3.0, 2.0),
+    ];
+    for (point, expected) in sorted(line[1..].to_vec()).iter().zip(expected) {
+        assert_relative_eq!(point.0, expected.0, epsilon = 1e-5);
+        assert_relative_eq!(point.1, expected.1, epsilon = 1e-5);
+    }
+
+    assert!(contour_lines(&values, 400.0).is_empty());
+}
+
+#[test]
+fn test_contour_ends_at_border() {
+    let values = Array2::from_shape_fn((4, 3), |(_, col)| col as f32 * 100.0);
+
+    let lines = contour_lines(&values, 150.0);
+    assert_eq!(lines.len(), 1);
+    assert_eq!(
+        sorted(lines[0].clone()),
+        vec![(0.0, 1.5), (1.0, 1.5), (2.0, 1.5), (3.0, 1.5)]
+    );
+}
+
+#[test]
+fn test_contour_saddle() {
+    // The center is at the level, so the high corners are connected.
+    let values = arr2(&[[1.0, 0.0], [0.0, 1.0]]);
+    let lines = contour_lines(&values, 0.5);
+    assert_eq!(lines.len(), 2);
+    let mut ends = lines
+        .iter()
+        .map(|line| sorted(line.clone()))
+        .collect::<Vec<_>>();
+    ends.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    assert_eq!(
+        ends,
+        vec![vec![(0.0, 0.5), (0.5, 1.0)], vec![(0.5, 0.0), (1.0, 0.5)]]
+    );
+
+    let lines = contour_lines(&values, 0.6);
+    let mut ends = lines
+        .iter()
+        .map(|line| sorted(line.clone()))
+        .collect::<Vec<_>>();
+    ends.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    assert_eq!(ends.len(), 2);
+    // The center is below, the high corners are cut off.
+    assert_relative_eq!(ends[0][0].0, 0.0);
+    assert_relative_eq!(ends[0][1].0, 0.4, epsilon = 1e-5);
+}
+
+#[test]
+fn test_contour_levels() {
+    let values = arr2(&[[-1000.0, 250.0], [0.0, 99.0]]);
+    assert_eq!(contour_levels(&values, 100.0), vec![100.0, 200.0]);
+    assert!(contour_levels(&values, 300.0).is_empty());
+    assert!(contour_levels(&values, 0.0).is_empty());
+    assert_eq!(contour_levels(&values, 0.01).len(), MAX_CONTOUR_LEVELS);
+}
+
+#[test]
+fn test_agl_contours() {
+    let grid = HeightGrid::for_test(Array2::zeros((5, 5)), (47.0, 47.05), (11.0, 11.05));
+    // Unreachable cells around a reachable block.
+    let mut agl = Array2::from_elem((5, 5), -1000.0);
+    for row in 1..4 {
+        for col in 1..4 {
+            agl[(row, col)] = 150.0;
+        }
+    }
+    agl[(2, 2)] = 250.0;
+
+    let contours = agl_contours(&agl, &grid, 100.0);
+    assert_eq!(contours.features.len(), 2);
+    assert_eq!(contours.features[0].properties.agl, 100.0);
+    assert_eq!(contours.features[1].properties.agl, 200.0);
+
+    let Geometry::MultiLineString { coordinates } = &contours.features[0].geometry else {
+        panic!("Contours are lines");
+    };
+    assert_eq!(coordinates.len(), 1);
+    let ring = &coordinates[0];
+    assert_eq!(ring.first(), ring.last());
+    for [lon, lat] in ring.iter() {
+        assert!((11.0..11.04).contains(lon));
+        assert!((47.0..47.04).contains(lat));
+    }
+}
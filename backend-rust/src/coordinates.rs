@@ -0,0 +1,423 @@
+/// Latitude bands of UTM and MGRS from south to north, 8° each except X.
+const LATITUDE_BANDS: &str = "CDEFGHJKLMNPQRSTUVWX";
+/// Column letters of the MGRS 100 km squares, indexed by the zone modulo 3.
+const MGRS_COLUMNS: [&str; 3] = ["STUVWXYZ", "ABCDEFGH", "JKLMNPQR"];
+/// Row letters of the MGRS 100 km squares, repeating every 2000 km.
+const MGRS_ROWS: &str = "ABCDEFGHJKLMNPQRSTUV";
+
+/// Digits of Open Location Codes.
+const PLUS_CODE_ALPHABET: &str = "23456789CFGHJMPQRVWX";
+/// Position of the `+` in full Open Location Codes.
+const PLUS_CODE_SEPARATOR_POSITION: usize = 8;
+/// Number of digits encoded as latitude and longitude pairs, the following
+/// digits refine a 5 by 4 grid.
+const PLUS_CODE_PAIR_DIGITS: usize = 10;
+
+const WGS84_SEMI_MAJOR_AXIS: f64 = 6_378_137.0;
+const WGS84_FLATTENING: f64 = 1.0 / 298.257_223_563;
+const UTM_SCALE: f64 = 0.9996;
+const UTM_FALSE_EASTING: f64 = 500_000.0;
+const UTM_FALSE_NORTHING_SOUTH: f64 = 10_000_000.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Axis {
+    Latitude,
+    Longitude,
+}
+
+/// Parses `(latitude, longitude)` in degrees from the formats found in
+/// guidebooks and GPS devices:
+/// - decimal degrees, e.g. `47.2692, 11.4041` or `47.2692N 11.4041E`,
+/// - degrees, minutes and seconds, e.g. `47°16'9"N 11°24'15"E`, or with
+///   decimal minutes, e.g. `N 47°16.15' E 11°24.25'`,
+/// - UTM with zone and latitude band, e.g. `32T 681849 5237883`,
+/// - MGRS, e.g. `32TPT8184837883` or `32T PT 81848 37883`.
+///
+/// Without hemispheres the latitude comes first.
+pub fn parse_coordinates(input: &str) -> Result<(f32, f32), String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err("No coordinates given".to_string());
+    }
+
+    let (latitude, longitude) = match parse_utm(input).or_else(|| parse_mgrs(input)) {
+        Some(result) => result?,
+        None => parse_angles(input)?,
+    };
+    if !(-90.0..=90.0).contains(&latitude) {
+        return Err(format!("Latitude {latitude} is not between -90 and 90"));
+    }
+    if !(-180.0..=180.0).contains(&longitude) {
+        return Err(format!("Longitude {longitude} is not between -180 and 180"));
+    }
+    Ok((latitude as f32, longitude as f32))
+}
+
+/// Splits latitude and longitude at a comma, between two hemispheres or in
+/// the middle of the numbers.
+fn split_angles(input: &str) -> Result<(String, String), String> {
+    if let Some((first, second)) = input.split_once([',', ';']) {
+        return Ok((first.to_string(), second.to_string()));
+    }
+
+    let hemispheres = input
+        .char_indices()
+        .filter(|(_, c)| "NSEWnsew".contains(*c))
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    if let [first, second] = hemispheres[..] {
+        // Either both hemispheres follow or both precede their numbers.
+        let (first, second) = if first == 0 {
+            input.split_at(second)
+        } else {
+            input.split_at(first + 1)
+        };
+        return Ok((first.to_string(), second.to_string()));
+    }
+
+    let parts = input.split_whitespace().collect::<Vec<_>>();
+    if parts.is_empty() || !parts.len().is_multiple_of(2) {
+        return Err(format!(
+            "Could not tell latitude and longitude apart in {input}, separate them with a comma"
+        ));
+    }
+    let (first, second) = parts.split_at(parts.len() / 2);
+    Ok((first.join(" "), second.join(" ")))
+}
+
+fn parse_angles(input: &str) -> Result<(f64, f64), String> {
+    let (first, second) = split_angles(input)?;
+    let (first, first_axis) = parse_angle(&first)?;
+    let (second, second_axis) = parse_angle(&second)?;
+    match (first_axis, second_axis) {
+        (Some(a), Some(b)) if a == b => Err(format!(
+            "{input} has two {}s",
+            if a == Axis::Latitude {
+                "latitude"
+            } else {
+                "longitude"
+            }
+        )),
+        (Some(Axis::Longitude), _) | (_, Some(Axis::Latitude)) => Ok((second, first)),
+        _ => Ok((first, second)),
+    }
+}
+
+/// Parses an angle in decimal degrees or degrees, minutes and seconds with
+/// an optional sign or hemisphere, and returns it with the axis given by the
+/// hemisphere.
+fn parse_angle(value: &str) -> Result<(f64, Option<Axis>), String> {
+    let value = value.trim();
+    let (hemisphere, numbers) = match (value.chars().next(), value.chars().last()) {
+        (Some(c), _) if c.is_ascii_alphabetic() => (Some(c), &value[1..]),
+        (_, Some(c)) if c.is_ascii_alphabetic() => (Some(c), &value[..value.len() - 1]),
+        _ => (None, value),
+    };
+    let (sign, axis) = match hemisphere.map(|c| c.to_ascii_uppercase()) {
+        Some('N') => (1.0, Some(Axis::Latitude)),
+        Some('S') => (-1.0, Some(Axis::Latitude)),
+        Some('E') => (1.0, Some(Axis::Longitude)),
+        Some('W') => (-1.0, Some(Axis::Longitude)),
+        Some(c) => return Err(format!("Unknown hemisphere {c} in {value}")),
+        None => (1.0, None),
+    };
+
+    let numbers = numbers.trim();
+    let (sign, numbers) = match numbers.strip_prefix('-') {
+        Some(_) if axis.is_some() => {
+            return Err(format!("{value} has both a sign and a hemisphere"));
+        }
+        Some(numbers) => (-sign, numbers),
+        None => (sign, numbers),
+    };
+    // º is often typed instead of °.
+    if let Some(c) = numbers.chars().find(|c| c.is_alphabetic() && *c != 'º') {
+        return Err(format!("Unexpected {c} in {value}"));
+    }
+
+    let parts = numbers
+        .split(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>();
+    if parts.is_empty() || parts.len() > 3 {
+        return Err(format!(
+            "Expected degrees, minutes and seconds in {value}, e.g. 47°16'9\"N"
+        ));
+    }
+    if parts[..parts.len() - 1]
+        .iter()
+        .any(|part| part.contains('.'))
+    {
+        return Err(format!("Only the last number in {value} can have decimals"));
+    }
+
+    let mut degrees = 0.0;
+    for (i, part) in parts.iter().enumerate() {
+        let number = part
+            .parse::<f64>()
+            .map_err(|e| format!("Bad number {part} in {value}: {e}"))?;
+        if i > 0 && number >= 60.0 {
+            let unit = if i == 1 { "Minutes" } else { "Seconds" };
+            return Err(format!("{unit} in {value} must be below 60"));
+        }
+        degrees += number / 60f64.powi(i as i32);
+    }
+    Ok((sign * degrees, axis))
+}
+
+/// Splits a UTM zone and latitude band like `32T` off the start of `value`.
+fn split_zone(value: &str) -> Option<(&str, char, &str)> {
+    let digits = value.chars().take_while(|c| c.is_ascii_digit()).count();
+    if !(1..=2).contains(&digits) {
+        return None;
+    }
+    let band = value[digits..].chars().next()?;
+    band.is_ascii_alphabetic().then(|| {
+        (
+            &value[..digits],
+            band.to_ascii_uppercase(),
+            &value[digits + 1..],
+        )
+    })
+}
+
+/// Zone number and southern and northern latitude of the band.
+fn zone_and_band(zone: &str, band: char) -> Result<(u8, (f64, f64)), String> {
+    let zone = zone
+        .parse::<u8>()
+        .ok()
+        .filter(|zone| (1..=60).contains(zone))
+        .ok_or_else(|| format!("UTM zone {zone} is not between 1 and 60"))?;
+    let index = LATITUDE_BANDS
+        .find(band)
+        .ok_or_else(|| format!("{band} is not a UTM latitude band (C to X without I and O)"))?;
+    let south = -80.0 + 8.0 * index as f64;
+    let north = if band == 'X' { 84.0 } else { south + 8.0 };
+    Ok((zone, (south, north)))
+}
+
+/// Parses UTM coordinates like `32T 681849 5237883`. Returns `None` if the
+/// input does not look like UTM at all.
+fn parse_utm(input: &str) -> Option<Result<(f64, f64), String>> {
+    let [zone, easting, northing] = input.split_whitespace().collect::<Vec<_>>()[..] else {
+        return None;
+    };
+    let (zone, band, "") = split_zone(zone)? else {
+        return None;
+    };
+    let easting = easting.trim_end_matches(['E', 'm']).parse::<f64>().ok()?;
+    let northing = northing.trim_end_matches(['N', 'm']).parse::<f64>().ok()?;
+
+    Some(zone_and_band(zone, band).and_then(|(zone, _)| {
+        if !(100_000.0..=900_000.0).contains(&easting) {
+            return Err(format!(
+                "UTM easting {easting} is not between 100000 and 900000"
+            ));
+        }
+        if !(0.0..=UTM_FALSE_NORTHING_SOUTH).contains(&northing) {
+            return Err(format!(
+                "UTM northing {northing} is not between 0 and 10000000"
+            ));
+        }
+        Ok(utm_to_lat_lon(zone, band >= 'N', easting, northing))
+    }))
+}
+
+/// Parses MGRS coordinates like `32TPT8184837883`, spaces are ignored.
+/// Returns `None` if the input does not look like MGRS at all.
+fn parse_mgrs(input: &str) -> Option<Result<(f64, f64), String>> {
+    let compact = input.split_whitespace().collect::<String>();
+    let (zone, band, rest) = split_zone(&compact)?;
+    let mut square = rest.chars();
+    let (column, row) = (square.next()?, square.next()?);
+    let digits = square.as_str();
+    if !column.is_ascii_alphabetic()
+        || !row.is_ascii_alphabetic()
+        || !digits.chars().all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+
+    Some(
+        zone_and_band(zone, band).and_then(|(zone, band_latitudes)| {
+            mgrs_to_lat_lon(
+                zone,
+                band,
+                band_latitudes,
+                column.to_ascii_uppercase(),
+                row.to_ascii_uppercase(),
+                digits,
+            )
+        }),
+    )
+}
+
+fn mgrs_to_lat_lon(
+    zone: u8,
+    band: char,
+    (south, north): (f64, f64),
+    column: char,
+    row: char,
+    digits: &str,
+) -> Result<(f64, f64), String> {
+    if !digits.len().is_multiple_of(2) || digits.len() > 10 {
+        return Err(format!(
+            "MGRS coordinates need as many digits for the easting as for the northing, at most 5 each, not {digits}"
+        ));
+    }
+    let column_index = MGRS_COLUMNS[zone as usize % 3]
+        .find(column)
+        .ok_or_else(|| format!("{column} is not a 100 km square column in zone {zone}"))?;
+    let row_index = MGRS_ROWS
+        .find(row)
+        .ok_or_else(|| format!("{row} is not a 100 km square row"))?;
+    // Rows of even zones start at F.
+    let row_index = if zone.is_multiple_of(2) {
+        (row_index + 15) % 20
+    } else {
+        row_index
+    };
+
+    let (easting_digits, northing_digits) = digits.split_at(digits.len() / 2);
+    let scale = 10f64.powi(5 - easting_digits.len() as i32);
+    let meters = |digits: &str| digits.parse::<f64>().unwrap_or(0.0) * scale;
+    let easting = (column_index + 1) as f64 * 100_000.0 + meters(easting_digits);
+    let northing = row_index as f64 * 100_000.0 + meters(northing_digits);
+
+    // Rows repeat every 2000 km, the latitude band tells which repetition is
+    // meant. A 100 km square can reach a little beyond its band.
+    (0..5)
+        .map(|i| {
+            utm_to_lat_lon(
+                zone,
+                band >= 'N',
+                easting,
+                northing + i as f64 * 2_000_000.0,
+            )
+        })
+        .find(|(latitude, _)| *latitude > south - 1.0 && *latitude < north + 1.0)
+        .ok_or_else(|| format!("MGRS square {column}{row} is not in latitude band {band}"))
+}
+
+/// Converts UTM coordinates on the WGS84 ellipsoid to `(latitude, longitude)`
+/// in degrees, using the series expansion of Snyder's "Map Projections".
+pub fn utm_to_lat_lon(zone: u8, northern: bool, easting: f64, northing: f64) -> (f64, f64) {
+    let a = WGS84_SEMI_MAJOR_AXIS;
+    let e2 = WGS84_FLATTENING * (2.0 - WGS84_FLATTENING);
+    let ep2 = e2 / (1.0 - e2);
+    let x = easting - UTM_FALSE_EASTING;
+    let y = if northern {
+        northing
+    } else {
+        northing - UTM_FALSE_NORTHING_SOUTH
+    };
+
+    let m = y / UTM_SCALE;
+    let mu = m / (a * (1.0 - e2 / 4.0 - 3.0 * e2.powi(2) / 64.0 - 5.0 * e2.powi(3) / 256.0));
+    let e1 = (1.0 - (1.0 - e2).sqrt()) / (1.0 + (1.0 - e2).sqrt());
+    let phi1 = mu
+        + (3.0 * e1 / 2.0 - 27.0 * e1.powi(3) / 32.0) * (2.0 * mu).sin()
+        + (21.0 * e1.powi(2) / 16.0 - 55.0 * e1.powi(4) / 32.0) * (4.0 * mu).sin()
+        + (151.0 * e1.powi(3) / 96.0) * (6.0 * mu).sin()
+        + (1097.0 * e1.powi(4) / 512.0) * (8.0 * mu).sin();
+
+    let sin_phi1 = phi1.sin();
+    let n1 = a / (1.0 - e2 * sin_phi1.powi(2)).sqrt();
+    let t1 = phi1.tan().powi(2);
+    let c1 = ep2 * phi1.cos().powi(2);
+    let r1 = a * (1.0 - e2) / (1.0 - e2 * sin_phi1.powi(2)).powf(1.5);
+    let d = x / (n1 * UTM_SCALE);
+
+    let latitude = phi1
+        - (n1 * phi1.tan() / r1)
+            * (d.powi(2) / 2.0
+                - (5.0 + 3.0 * t1 + 10.0 * c1 - 4.0 * c1.powi(2) - 9.0 * ep2) * d.powi(4) / 24.0
+                + (61.0 + 90.0 * t1 + 298.0 * c1 + 45.0 * t1.powi(2)
+                    - 252.0 * ep2
+                    - 3.0 * c1.powi(2))
+                    * d.powi(6)
+                    / 720.0);
+    let central_meridian = (zone as f64 * 6.0 - 183.0).to_radians();
+    let longitude = central_meridian
+        + (d - (1.0 + 2.0 * t1 + c1) * d.powi(3) / 6.0
+            + (5.0 - 2.0 * c1 + 28.0 * t1 - 3.0 * c1.powi(2) + 8.0 * ep2 + 24.0 * t1.powi(2))
+                * d.powi(5)
+                / 120.0)
+            / phi1.cos();
+
+    (latitude.to_degrees(), longitude.to_degrees())
+}
+
+/// Decodes a full Open Location Code (Plus Code) like `8FVC9G8F+6X` to the
+/// `(latitude, longitude)` of the center of its area. Short codes like
+/// `9G8F+6X` need a reference location and are rejected.
+pub fn decode_plus_code(code: &str) -> Result<(f32, f32), String> {
+    let code = code.trim().to_ascii_uppercase();
+    let separator = code
+        .find('+')
+        .ok_or_else(|| format!("Plus code {code} has no +"))?;
+    if code.matches('+').count() > 1 {
+        return Err(format!("Plus code {code} has more than one +"));
+    }
+    if separator < PLUS_CODE_SEPARATOR_POSITION {
+        return Err(format!(
+            "{code} is a short plus code, use the full code with {PLUS_CODE_SEPARATOR_POSITION} digits before the +"
+        ));
+    }
+    if separator > PLUS_CODE_SEPARATOR_POSITION {
+        return Err(format!(
+            "Plus code {code} has more than {PLUS_CODE_SEPARATOR_POSITION} digits before the +"
+        ));
+    }
+
+    let (area, refinement) = (&code[..separator], &code[separator + 1..]);
+    if refinement.len() == 1 {
+        return Err(format!("Plus code {code} has a single digit after the +"));
+    }
+    // Padding shortens the code to a larger area, in pairs of digits.
+    let digits = area.trim_end_matches('0');
+    if digits.contains('0')
+        || !digits.len().is_multiple_of(2)
+        || digits.is_empty()
+        || (digits.len() < area.len() && !refinement.is_empty())
+    {
+        return Err(format!("Plus code {code} is padded incorrectly"));
+    }
+
+    let values = digits
+        .chars()
+        .chain(refinement.chars())
+        .map(|c| {
+            PLUS_CODE_ALPHABET
+                .find(c)
+                .ok_or_else(|| format!("{c} is not a plus code digit"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if values[0] * 20 >= 180 || values[1] * 20 >= 360 {
+        return Err(format!("Plus code {code} is outside of the world"));
+    }
+
+    let (mut latitude, mut longitude) = (-90.0, -180.0);
+    let (mut lat_resolution, mut lon_resolution) = (400.0, 400.0);
+    for pair in values[..values.len().min(PLUS_CODE_PAIR_DIGITS)].chunks(2) {
+        lat_resolution /= 20.0;
+        lon_resolution /= 20.0;
+        latitude += pair[0] as f64 * lat_resolution;
+        longitude += pair[1] as f64 * lon_resolution;
+    }
+    for value in values.iter().skip(PLUS_CODE_PAIR_DIGITS) {
+        lat_resolution /= 5.0;
+        lon_resolution /= 4.0;
+        latitude += (value / 4) as f64 * lat_resolution;
+        longitude += (value % 4) as f64 * lon_resolution;
+    }
+
+    Ok((
+        (latitude + lat_resolution / 2.0).min(90.0) as f32,
+        (longitude + lon_resolution / 2.0) as f32,
+    ))
+}
+
+#[cfg(test)]
+#[path = "./coordinates_test.rs"]
+mod coordinates_test;
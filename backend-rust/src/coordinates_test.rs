@@ -0,0 +1,92 @@
+use approx::assert_relative_eq;
+
+use super::{decode_plus_code, parse_coordinates, utm_to_lat_lon};
+
+fn assert_coordinates(input: &str, latitude: f32, longitude: f32) {
+    let (lat, lon) = parse_coordinates(input).unwrap_or_else(|e| panic!("{input}: {e}"));
+    assert_relative_eq!(lat, latitude, epsilon = 1e-4);
+    assert_relative_eq!(lon, longitude, epsilon = 1e-4);
+}
+
+#[test]
+fn test_parse_decimal_degrees() {
+    assert_coordinates("47.2692, 11.4041", 47.2692, 11.4041);
+    assert_coordinates("47.2692 11.4041", 47.2692, 11.4041);
+    assert_coordinates("-33.8568 151.2153", -33.8568, 151.2153);
+    assert_coordinates("47.2692N 11.4041E", 47.2692, 11.4041);
+    assert_coordinates("33.8568S, 151.2153E", -33.8568, 151.2153);
+    // Hemispheres decide the order.
+    assert_coordinates("11.4041E 47.2692N", 47.2692, 11.4041);
+}
+
+#[test]
+fn test_parse_degrees_minutes_seconds() {
+    assert_coordinates("47°16'9\"N 11°24'15\"E", 47.269_17, 11.404_167);
+    assert_coordinates("47° 16′ 9″ N, 11° 24′ 15″ E", 47.269_17, 11.404_167);
+    assert_coordinates("N 47°16.15' E 11°24.25'", 47.269_17, 11.404_167);
+    assert_coordinates("47 16 9 N 11 24 15 W", 47.269_17, -11.404_167);
+    assert_coordinates("47º16'9\" 11º24'15\"", 47.269_17, 11.404_167);
+}
+
+#[test]
+fn test_parse_utm_and_mgrs() {
+    // Reference values from the forward projection.
+    assert_coordinates("32T 681848.88 5237883.24", 47.2692, 11.4041);
+    assert_coordinates("32t 681849mE 5237883mN", 47.2692, 11.4041);
+    assert_coordinates("56H 334900.57 6252288.75", -33.8568, 151.2153);
+    assert_coordinates("32TPT8184837883", 47.2692, 11.4041);
+    assert_coordinates("32T PT 81848 37883", 47.2692, 11.4041);
+    assert_coordinates("31U DQ 48251 11939", 48.85826, 2.2945);
+
+    let (lat, lon) = parse_coordinates("32TPT8137").unwrap();
+    assert_relative_eq!(lat, 47.2692, epsilon = 0.02);
+    assert_relative_eq!(lon, 11.4041, epsilon = 0.02);
+
+    let (lat, lon) = utm_to_lat_lon(31, true, 500_000.0, 0.0);
+    assert_relative_eq!(lat, 0.0, epsilon = 1e-9);
+    assert_relative_eq!(lon, 3.0, epsilon = 1e-9);
+}
+
+#[test]
+fn test_parse_coordinates_errors() {
+    let error = |input| parse_coordinates(input).unwrap_err();
+
+    assert!(error("").contains("No coordinates"));
+    assert!(error("47.2692").contains("comma"));
+    assert!(error("47°75'N 11°E").contains("Minutes"));
+    assert!(error("47N 11N").contains("two latitudes"));
+    assert!(error("-47N 11E").contains("sign and a hemisphere"));
+    assert!(error("47.5°30' 11").contains("last number"));
+    assert!(error("95, 11").contains("Latitude"));
+    assert!(error("32I 681849 5237883").contains("latitude band"));
+    assert!(error("61T 681849 5237883").contains("zone"));
+    assert!(error("32TPT818483788").contains("digits"));
+    assert!(error("32TAT8184837883").contains("column"));
+    assert!(error("32TPA8184837883").contains("latitude band"));
+}
+
+#[test]
+fn test_decode_plus_code() {
+    let (lat, lon) = decode_plus_code("8FVC9G8F+6X").unwrap();
+    assert_relative_eq!(lat, 47.365_562, epsilon = 1e-5);
+    assert_relative_eq!(lon, 8.524_937, epsilon = 1e-5);
+
+    // Grid refinement after ten digits.
+    let (lat, lon) = decode_plus_code("8fvc9g8f+6xq").unwrap();
+    assert_relative_eq!(lat, 47.365_59, epsilon = 1e-5);
+    assert_relative_eq!(lon, 8.524_984, epsilon = 1e-5);
+
+    // Padded codes stand for larger areas.
+    let (lat, lon) = decode_plus_code("8FVC0000+").unwrap();
+    assert_relative_eq!(lat, 47.5, epsilon = 1e-5);
+    assert_relative_eq!(lon, 8.5, epsilon = 1e-5);
+
+    let error = |code| decode_plus_code(code).unwrap_err();
+    assert!(error("9G8F+6X").contains("short plus code"));
+    assert!(error("8FVC9G8F6X").contains("no +"));
+    assert!(error("8FVC9G8F+6").contains("single digit"));
+    assert!(error("8FVC0G8F+").contains("padded"));
+    assert!(error("8FVC0000+6X").contains("padded"));
+    assert!(error("8FVC9G8A+6X").contains("not a plus code digit"));
+    assert!(error("XFVC9G8F+6X").contains("outside"));
+}
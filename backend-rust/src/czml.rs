@@ -0,0 +1,149 @@
+use serde::Serialize;
+
+use crate::{
+    colors::{f32_color_to_u8, ColorRamp},
+    cone_stats::{agl_of, cone_stats},
+    geojson::cell_ring,
+    height_data::HeightGrid,
+    search::Node,
+};
+
+/// Opacity of the cells, so the terrain below stays visible.
+const CELL_ALPHA: u8 = 150;
+const START_PIXEL_SIZE: f32 = 10.0;
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Rgba {
+    pub rgba: [u8; 4],
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SolidColor {
+    pub color: Rgba,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Material {
+    pub solid_color: SolidColor,
+}
+
+/// Flat list of `longitude, latitude, height` triples, heights in meters
+/// above the ellipsoid.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Positions {
+    pub cartographic_degrees: Vec<f32>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Polygon {
+    pub positions: Positions,
+    /// Place every corner at its own height instead of on the ground.
+    pub per_position_height: bool,
+    pub material: Material,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Point {
+    pub color: Rgba,
+    pub pixel_size: f32,
+}
+
+/// Object of a CZML document, the first one describes the document itself.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Packet {
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<Positions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub point: Option<Point>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub polygon: Option<Polygon>,
+}
+
+impl Packet {
+    fn new(id: String) -> Packet {
+        Packet {
+            id,
+            name: None,
+            version: None,
+            position: None,
+            point: None,
+            polygon: None,
+        }
+    }
+}
+
+/// CZML document of the surface a search reaches: one polygon per reachable
+/// cell at the height it is reached at, colored by the height above ground
+/// with `ramp`, and a point at the start at `(latitude, longitude, height)`.
+pub fn cone_czml(
+    explored: &[Node],
+    grid: &HeightGrid,
+    start: (f32, f32, f32),
+    ramp: &ColorRamp,
+) -> Vec<Packet> {
+    let stats = cone_stats(explored, grid, None);
+
+    let mut packets = vec![
+        Packet {
+            name: Some("Glide area".to_string()),
+            version: Some("1.0"),
+            ..Packet::new("document".to_string())
+        },
+        Packet {
+            name: Some("Start".to_string()),
+            position: Some(Positions {
+                cartographic_degrees: vec![start.1, start.0, start.2],
+            }),
+            point: Some(Point {
+                color: Rgba {
+                    rgba: [0, 0, 0, 255],
+                },
+                pixel_size: START_PIXEL_SIZE,
+            }),
+            ..Packet::new("start".to_string())
+        },
+    ];
+
+    for node in explored.iter().filter(|node| node.reachable) {
+        let s = ((agl_of(node, grid) - stats.min_agl) / (stats.max_agl - stats.min_agl))
+            .clamp(0.0, 1.0);
+        let [r, g, b, _] = f32_color_to_u8(ramp.color_at(if s.is_nan() { 0.0 } else { s }));
+
+        // The ring is closed, Cesium closes polygons itself.
+        let ring = cell_ring(node.ix, grid);
+        packets.push(Packet {
+            polygon: Some(Polygon {
+                positions: Positions {
+                    cartographic_degrees: ring[..ring.len() - 1]
+                        .iter()
+                        .flat_map(|[lon, lat]| [*lon, *lat, node.height])
+                        .collect(),
+                },
+                per_position_height: true,
+                material: Material {
+                    solid_color: SolidColor {
+                        color: Rgba {
+                            rgba: [r, g, b, CELL_ALPHA],
+                        },
+                    },
+                },
+            }),
+            ..Packet::new(format!("cell_{}_{}", node.ix.0, node.ix.1))
+        });
+    }
+    packets
+}
+
+#[cfg(test)]
+#[path = "./czml_test.rs"]
+mod czml_test;
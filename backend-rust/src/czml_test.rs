@@ -0,0 +1,63 @@
+use ndarray::Array2;
+
+use crate::{
+    colors::{f32_color_to_u8, ColorRamp},
+    height_data::HeightGrid,
+    search::Node,
+};
+
+use super::cone_czml;
+
+#[test]
+fn test_cone_czml() {
+    let grid = HeightGrid::for_test(
+        Array2::from_shape_vec((2, 2), vec![100, 200, 300, 400]).unwrap(),
+        (47.0, 47.2),
+        (11.0, 11.2),
+    );
+
+    let packets = cone_czml(
+        &[
+            Node::for_test((0, 0), 500.0, true),
+            Node::for_test((0, 1), 250.0, true),
+            Node::for_test((1, 1), 350.0, false),
+        ],
+        &grid,
+        (47.0, 11.0, 500.0),
+        &ColorRamp::default(),
+    );
+
+    assert_eq!(packets.len(), 4);
+    assert_eq!(packets[0].id, "document");
+    assert_eq!(packets[0].version, Some("1.0"));
+    assert_eq!(
+        packets[1].position.as_ref().unwrap().cartographic_degrees,
+        vec![11.0, 47.0, 500.0]
+    );
+
+    let polygon = packets[3].polygon.as_ref().unwrap();
+    assert_eq!(packets[3].id, "cell_0_1");
+    assert!(polygon.per_position_height);
+    // Four corners, all at the height the cell is reached at.
+    let positions = &polygon.positions.cartographic_degrees;
+    assert_eq!(positions.len(), 12);
+    assert!(positions.chunks(3).all(|corner| corner[2] == 250.0));
+    // Lowest and highest height above ground get the ends of the ramp.
+    let ramp = ColorRamp::default();
+    let color = |s| f32_color_to_u8(ramp.color_at(s));
+    assert_eq!(
+        packets[2]
+            .polygon
+            .as_ref()
+            .unwrap()
+            .material
+            .solid_color
+            .color
+            .rgba[..3],
+        color(1.0)[..3]
+    );
+    assert_eq!(
+        polygon.material.solid_color.color.rgba[..3],
+        color(0.0)[..3]
+    );
+}
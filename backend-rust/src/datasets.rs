@@ -0,0 +1,96 @@
+use crate::{height_data::HeightGrid, search::Node};
+
+/// Region definitions in the data directory, one `<name> <min_lat> <max_lat>
+/// <min_lon> <max_lon>` line per region.
+pub const REGIONS_FILE_NAME: &str = "regions.txt";
+
+/// Value of cells in the AGL raster that can not be reached.
+pub const NOT_REACHABLE: i16 = i16::MIN;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Region {
+    pub name: String,
+    pub latitudes: (f32, f32),
+    pub longitudes: (f32, f32),
+}
+
+impl Region {
+    pub fn contains(&self, latitude: f32, longitude: f32) -> bool {
+        latitude >= self.latitudes.0
+            && latitude <= self.latitudes.1
+            && longitude >= self.longitudes.0
+            && longitude <= self.longitudes.1
+    }
+}
+
+/// Region names end up in file names and urls, so only allow a safe subset.
+pub fn is_valid_region_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+}
+
+pub fn parse_regions(content: &str) -> Result<Vec<Region>, String> {
+    let mut result = vec![];
+    for (line_number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let parts = line.split_whitespace().collect::<Vec<_>>();
+        if parts.len() != 5 {
+            return Err(format!("Line {}: expected 5 fields", line_number + 1));
+        }
+        if !is_valid_region_name(parts[0]) {
+            return Err(format!("Line {}: invalid region name", line_number + 1));
+        }
+        let mut values = [0.0f32; 4];
+        for (value, part) in values.iter_mut().zip(parts[1..].iter()) {
+            *value = part
+                .parse()
+                .map_err(|e| format!("Line {}: bad coordinate: {e}", line_number + 1))?;
+        }
+        result.push(Region {
+            name: parts[0].to_string(),
+            latitudes: (values[0], values[1]),
+            longitudes: (values[2], values[3]),
+        });
+    }
+    Ok(result)
+}
+
+/// Storage key (relative to the data directory) of the cone archive of a region.
+pub fn dataset_key(region: &str) -> String {
+    format!("datasets/cones/{region}.tar.zst")
+}
+
+/// File name friendly version of a flying site name.
+pub fn slug(name: &str) -> String {
+    let mut result = String::with_capacity(name.len());
+    for c in name.chars().flat_map(|c| c.to_lowercase()) {
+        if c.is_ascii_alphanumeric() {
+            result.push(c);
+        } else if !result.is_empty() && !result.ends_with('_') {
+            result.push('_');
+        }
+    }
+    result.trim_end_matches('_').to_string()
+}
+
+/// Height above ground of every cell of the grid as little endian i16 in row
+/// major order, rows from south to north. Unreachable cells are set to
+/// `NOT_REACHABLE`.
+pub fn agl_raster(explored: &[Node], grid: &HeightGrid) -> Vec<u8> {
+    let shape = grid.heights.shape();
+    let mut values = vec![NOT_REACHABLE; shape[0] * shape[1]];
+    for node in explored.iter().filter(|n| n.reachable) {
+        let (row, col) = (node.ix.0 as usize, node.ix.1 as usize);
+        values[row * shape[1] + col] = (node.height - grid.heights[(row, col)] as f32) as i16;
+    }
+    values.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+#[cfg(test)]
+#[path = "./datasets_test.rs"]
+mod datasets_test;
@@ -0,0 +1,85 @@
+use ndarray::Array2;
+
+use crate::{
+    height_data::{DataSource, HeightGrid},
+    search::Node,
+};
+
+use super::{agl_raster, is_valid_region_name, parse_regions, slug, Region, NOT_REACHABLE};
+
+#[test]
+fn test_parse_regions() {
+    let regions =
+        parse_regions("# name lat lon\nalps 45.5 48.0 5.5 16.5\n\ntatra 49.0 49.5 19.5 20.5\n")
+            .unwrap();
+    assert_eq!(
+        regions,
+        vec![
+            Region {
+                name: "alps".to_string(),
+                latitudes: (45.5, 48.0),
+                longitudes: (5.5, 16.5),
+            },
+            Region {
+                name: "tatra".to_string(),
+                latitudes: (49.0, 49.5),
+                longitudes: (19.5, 20.5),
+            },
+        ]
+    );
+    assert!(regions[0].contains(47.0, 11.0));
+    assert!(!regions[0].contains(47.0, 17.0));
+
+    assert!(parse_regions("alps 45.5 48.0 5.5").is_err());
+    assert!(parse_regions("../alps 45.5 48.0 5.5 16.5").is_err());
+    assert!(parse_regions("alps 45.5 north 5.5 16.5").is_err());
+}
+
+#[test]
+fn test_region_names_and_slugs() {
+    assert!(is_valid_region_name("alps-east_2"));
+    assert!(!is_valid_region_name("Alps"));
+    assert!(!is_valid_region_name("a/b"));
+    assert!(!is_valid_region_name(""));
+
+    assert_eq!(slug("Hohe Salve - Hopfgarten"), "hohe_salve_hopfgarten");
+    assert_eq!(slug("  Übungshang!"), "bungshang");
+}
+
+#[test]
+fn test_agl_raster() {
+    let grid = HeightGrid {
+        heights: Array2::from_shape_vec((2, 2), vec![100, 200, 300, 400]).unwrap(),
+        cell_size: 50.0,
+        min_cell_size: 50.0,
+        latitudes: (47.0, 47.1),
+        longitudes: (11.0, 11.1),
+        data_source: DataSource::Srtm,
+        data_quality: None,
+    };
+    let node = |ix, height, reachable| Node {
+        height,
+        ix,
+        reference: None,
+        distance: 0.0,
+        reachable,
+        clearance: f32::INFINITY,
+        min_agl: f32::INFINITY,
+        ground_speed: 0.0,
+        explored: true,
+    };
+
+    let raster = agl_raster(
+        &[node((0, 1), 250.0, true), node((1, 0), 250.0, false)],
+        &grid,
+    );
+    let values = raster
+        .chunks(2)
+        .map(|c| i16::from_le_bytes([c[0], c[1]]))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        values,
+        vec![NOT_REACHABLE, 50, NOT_REACHABLE, NOT_REACHABLE]
+    );
+}
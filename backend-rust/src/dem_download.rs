@@ -0,0 +1,97 @@
+use std::{
+    fs,
+    path::Path,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{
+    height_data::{get_bounds_around_point, tile_exists, tile_name, DataSource},
+    hgt_manifest::is_valid_hgt_size,
+};
+
+/// Tiles within this distance of a start are downloaded, which covers the
+/// cones of most starts.
+pub const DOWNLOAD_RADIUS_M: f32 = 50000.0;
+
+/// Url of a tile from a template like `https://example.com/{tile}.hgt`.
+/// `{tile}` is replaced by the tile name (e.g. `N47E011`), `{lat}` and `{lon}`
+/// by the coordinates of its south west corner.
+pub fn tile_url(template: &str, latitude: i32, longitude: i32) -> String {
+    template
+        .replace("{tile}", &tile_name(latitude, longitude))
+        .replace("{lat}", &latitude.to_string())
+        .replace("{lon}", &longitude.to_string())
+}
+
+/// SRTM tiles within `distance_m` around the point that are not in the data
+/// directory yet, the tile of the point first.
+pub fn tiles_to_download(latitude: f32, longitude: f32, distance_m: f32) -> Vec<(i32, i32)> {
+    let (lower_latitude, upper_latitude, lower_longitude, upper_longitude) =
+        get_bounds_around_point(latitude, longitude, distance_m);
+    let start = (latitude.floor() as i32, longitude.floor() as i32);
+
+    let mut tiles = ((lower_latitude.floor() as i32)..=(upper_latitude.floor() as i32))
+        .flat_map(|lat_i| {
+            ((lower_longitude.floor() as i32)..=(upper_longitude.floor() as i32))
+                .map(move |lon_i| (lat_i, lon_i))
+        })
+        .filter(|(lat_i, lon_i)| !tile_exists(DataSource::Srtm, *lat_i, *lon_i))
+        .collect::<Vec<_>>();
+    tiles.sort_by_key(|tile| *tile != start);
+    tiles
+}
+
+/// Coordinates of the south west corner of an SRTM tile from its file name,
+/// e.g. `(47, 11)` for `N47E011.hgt`.
+pub fn parse_tile_name(file_name: &str) -> Option<(i32, i32)> {
+    let name = file_name.strip_suffix(".hgt")?;
+    if name.len() != 7 || !name.is_ascii() {
+        return None;
+    }
+    let coordinate = |value: &str, positive: char, negative: char| {
+        let (hemisphere, digits) = value.split_at(1);
+        if !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let degrees = digits.parse::<i32>().ok()?;
+        match hemisphere.chars().next()? {
+            h if h == positive => Some(degrees),
+            h if h == negative => Some(-degrees),
+            _ => None,
+        }
+    };
+    Some((
+        coordinate(&name[..3], 'N', 'S')?,
+        coordinate(&name[3..], 'E', 'W')?,
+    ))
+}
+
+/// Stores a downloaded tile in the data directory. The tile is written to a
+/// temporary file first, so searches never see a partially written tile.
+pub fn store_tile(
+    data_dir: &Path,
+    latitude: i32,
+    longitude: i32,
+    bytes: &[u8],
+) -> Result<(), String> {
+    if !is_valid_hgt_size(bytes.len() as u64) {
+        return Err(format!(
+            "Downloaded tile {} is no HGT file ({} bytes)",
+            tile_name(latitude, longitude),
+            bytes.len()
+        ));
+    }
+    static PART_COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let path = data_dir.join(format!("{}.hgt", tile_name(latitude, longitude)));
+    let part = data_dir.join(format!(
+        "{}.hgt.{}.part",
+        tile_name(latitude, longitude),
+        PART_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    fs::write(&part, bytes).map_err(|e| format!("Could not write {}: {e}", part.display()))?;
+    fs::rename(&part, &path).map_err(|e| format!("Could not move {}: {e}", part.display()))
+}
+
+#[cfg(test)]
+#[path = "./dem_download_test.rs"]
+mod dem_download_test;
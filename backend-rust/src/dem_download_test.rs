@@ -0,0 +1,56 @@
+use std::fs;
+
+use super::{parse_tile_name, store_tile, tile_url, tiles_to_download};
+
+#[test]
+fn test_tile_url() {
+    assert_eq!(
+        tile_url("https://example.com/srtm/{tile}.hgt", 47, 11),
+        "https://example.com/srtm/N47E011.hgt"
+    );
+    assert_eq!(
+        tile_url("https://example.com/{lat}/{lon}/{tile}.hgt", -34, -71),
+        "https://example.com/-34/-71/S34W071.hgt"
+    );
+}
+
+#[test]
+fn test_tiles_to_download() {
+    // There is no height data at the south pole, where the tiles are narrow.
+    let tiles = tiles_to_download(-88.5, 10.5, 30000.0);
+    assert_eq!(tiles[0], (-89, 10));
+    assert_eq!(tiles.len(), 21);
+    assert!(tiles.contains(&(-89, 0)) && tiles.contains(&(-89, 20)));
+
+    assert_eq!(tiles_to_download(-88.5, 10.5, 1000.0), vec![(-89, 10)]);
+}
+
+#[test]
+fn test_store_tile() {
+    let dir = std::env::temp_dir().join(format!("dem_download_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    store_tile(&dir, 47, 11, &[0, 1, 0, 2, 0, 3, 0, 4]).unwrap();
+    assert_eq!(
+        fs::read(dir.join("N47E011.hgt")).unwrap(),
+        vec![0, 1, 0, 2, 0, 3, 0, 4]
+    );
+    // No temporary files are left behind.
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+    let error = store_tile(&dir, 47, 12, b"<html>Not found</html>").unwrap_err();
+    assert!(error.contains("no HGT file"));
+    assert!(!dir.join("N47E012.hgt").exists());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_parse_tile_name() {
+    assert_eq!(parse_tile_name("N47E011.hgt"), Some((47, 11)));
+    assert_eq!(parse_tile_name("S34W071.hgt"), Some((-34, -71)));
+    assert_eq!(parse_tile_name("N47E011"), None);
+    assert_eq!(parse_tile_name("coarse/N47E011.hgt"), None);
+    assert_eq!(parse_tile_name("X47E011.hgt"), None);
+    assert_eq!(parse_tile_name("N4+E011.hgt"), None);
+}
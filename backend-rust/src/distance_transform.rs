@@ -0,0 +1,126 @@
+use ndarray::{s, Array2, Axis};
+
+use crate::{height_data::HeightGrid, search::Node};
+
+/// Squared euclidean distance in cells from every cell to the nearest cell
+/// set in `targets`, infinite if there is none. Exact, in linear time by
+/// the lower envelope of parabolas of Felzenszwalb and Huttenlocher, first
+/// along the columns and then along the rows.
+pub fn squared_distances(targets: &Array2<bool>) -> Array2<f64> {
+    let mut distances = targets.mapv(|target| if target { 0.0 } else { f64::INFINITY });
+    let mut buffer = vec![];
+    for axis in [0, 1] {
+        for mut lane in distances.lanes_mut(Axis(axis)) {
+            buffer.clear();
+            buffer.extend(lane.iter().copied());
+            for (out, distance) in lane.iter_mut().zip(lower_envelope(&buffer)) {
+                *out = distance;
+            }
+        }
+    }
+    distances
+}
+
+/// `min_p (q - p)² + f(p)` for every `q`, ignoring infinite values of `f`.
+fn lower_envelope(f: &[f64]) -> Vec<f64> {
+    // Parabolas of the envelope and where each of them starts.
+    let mut vertices: Vec<usize> = vec![];
+    let mut starts: Vec<f64> = vec![];
+    for q in (0..f.len()).filter(|&q| f[q].is_finite()) {
+        let mut start = f64::NEG_INFINITY;
+        while let Some(&p) = vertices.last() {
+            let (p_f, q_f) = (p as f64, q as f64);
+            start = ((f[q] + q_f * q_f) - (f[p] + p_f * p_f)) / (2.0 * (q_f - p_f));
+            if start <= *starts.last().unwrap() {
+                vertices.pop();
+                starts.pop();
+                start = f64::NEG_INFINITY;
+            } else {
+                break;
+            }
+        }
+        vertices.push(q);
+        starts.push(start);
+    }
+
+    if vertices.is_empty() {
+        return vec![f64::INFINITY; f.len()];
+    }
+    let mut k = 0;
+    (0..f.len())
+        .map(|q| {
+            while k + 1 < vertices.len() && starts[k + 1] < q as f64 {
+                k += 1;
+            }
+            let offset = q as f64 - vertices[k] as f64;
+            offset * offset + f[vertices[k]]
+        })
+        .collect()
+}
+
+/// Distance in meters from every cell of a search grid to the boundary of
+/// the cone, positive for reachable and negative for unreachable cells. The
+/// boundary runs between the cells, so cells next to it are half a cell
+/// away. The border of the grid counts as the boundary.
+pub struct BoundaryDistance {
+    /// Row 0 is the south like in `HeightGrid`.
+    pub distances: Array2<f32>,
+    pub latitudes: (f32, f32),
+    pub longitudes: (f32, f32),
+}
+
+impl BoundaryDistance {
+    pub fn new(explored: &[Node], grid: &HeightGrid) -> BoundaryDistance {
+        let (rows, cols) = (grid.heights.shape()[0], grid.heights.shape()[1]);
+        let mut inside = Array2::from_elem((rows, cols), false);
+        for node in explored.iter().filter(|node| node.reachable) {
+            inside[(node.ix.0 as usize, node.ix.1 as usize)] = true;
+        }
+
+        // Outside of the grid is outside of the cone.
+        let mut outside = Array2::from_elem((rows + 2, cols + 2), true);
+        outside
+            .slice_mut(s![1..rows + 1, 1..cols + 1])
+            .assign(&inside.mapv(|inside| !inside));
+        let to_outside = squared_distances(&outside);
+        let to_inside = squared_distances(&inside);
+
+        let distances = Array2::from_shape_fn((rows, cols), |(row, col)| {
+            let cells = if inside[(row, col)] {
+                to_outside[(row + 1, col + 1)].sqrt() - 0.5
+            } else {
+                0.5 - to_inside[(row, col)].sqrt()
+            };
+            cells as f32 * grid.cell_size
+        });
+        BoundaryDistance {
+            distances,
+            latitudes: grid.latitudes,
+            longitudes: grid.longitudes,
+        }
+    }
+
+    /// Distance at the cell containing a point, `None` outside of the grid.
+    pub fn at(&self, latitude: f32, longitude: f32) -> Option<f32> {
+        let (rows, cols) = (self.distances.shape()[0], self.distances.shape()[1]);
+        let row = ((latitude - self.latitudes.0) / (self.latitudes.1 - self.latitudes.0)
+            * rows as f32)
+            .round();
+        let col = ((longitude - self.longitudes.0) / (self.longitudes.1 - self.longitudes.0)
+            * cols as f32)
+            .round();
+        if row < 0.0 || col < 0.0 {
+            return None;
+        }
+        self.distances.get((row as usize, col as usize)).copied()
+    }
+
+    /// Estimated memory of the distances.
+    pub fn bytes(&self) -> usize {
+        self.distances.len() * std::mem::size_of::<f32>()
+    }
+}
+
+#[cfg(test)]
+#[path = "./distance_transform_test.rs"]
+mod distance_transform_test;
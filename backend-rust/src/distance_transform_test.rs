@@ -0,0 +1,54 @@
+use approx::assert_relative_eq;
+use ndarray::Array2;
+
+use crate::{height_data::HeightGrid, search::Node};
+
+use super::{squared_distances, BoundaryDistance};
+
+#[test]
+fn test_squared_distances() {
+    let mut targets = Array2::from_elem((3, 4), false);
+    targets[(0, 0)] = true;
+    targets[(2, 3)] = true;
+
+    let distances = squared_distances(&targets);
+
+    assert_eq!(distances[(0, 0)], 0.0);
+    assert_eq!(distances[(1, 1)], 2.0);
+    assert_eq!(distances[(0, 3)], 4.0);
+    assert_eq!(distances[(2, 1)], 4.0);
+    assert_eq!(distances[(0, 2)], 4.0);
+
+    let none = squared_distances(&Array2::from_elem((2, 2), false));
+    assert!(none.iter().all(|distance| distance.is_infinite()));
+}
+
+#[test]
+fn test_boundary_distance() {
+    // A reachable 5x5 block in the middle of a 7x7 grid.
+    let grid = HeightGrid::for_test(Array2::zeros((7, 7)), (47.0, 47.007), (11.0, 11.007));
+    let mut explored = vec![Node::for_test((0, 0), 1000.0, false)];
+    for row in 1..6 {
+        for col in 1..6 {
+            explored.push(Node::for_test((row, col), 1000.0, true));
+        }
+    }
+
+    let distance = BoundaryDistance::new(&explored, &grid);
+
+    assert_relative_eq!(distance.distances[(3, 3)], 250.0);
+    assert_relative_eq!(distance.distances[(1, 1)], 50.0);
+    assert_relative_eq!(distance.distances[(3, 0)], -50.0);
+    assert_relative_eq!(distance.distances[(0, 0)], -(2f32.sqrt() - 0.5) * 100.0);
+    assert_eq!(distance.at(47.003, 11.003), Some(250.0));
+    assert_eq!(distance.at(46.9, 11.003), None);
+    assert_eq!(distance.at(47.003, 11.1), None);
+
+    // The border of the grid is the boundary.
+    let all = (0..7)
+        .flat_map(|row| (0..7).map(move |col| Node::for_test((row, col), 1000.0, true)))
+        .collect::<Vec<_>>();
+    let distance = BoundaryDistance::new(&all, &grid);
+    assert_relative_eq!(distance.distances[(0, 3)], 50.0);
+    assert_relative_eq!(distance.distances[(3, 3)], 350.0);
+}
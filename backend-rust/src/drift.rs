@@ -0,0 +1,69 @@
+use std::f32::consts::PI;
+
+use crate::{
+    height_data::{
+        get_interpolated_height_at_point_from, offset_point, select_data_source, DataSource,
+        Interpolation,
+    },
+    search::SearchQuery,
+};
+
+/// Altitude step in meters when integrating the drift of a climb.
+const DRIFT_STEP: f32 = 50.0;
+
+/// Horizontal displacement `(north, east)` in meters when climbing from
+/// `from_height` to `to_height` at `climb_rate` m/s, drifting with the wind
+/// of the query at each altitude.
+pub fn climb_drift(
+    query: &SearchQuery,
+    from_height: f32,
+    to_height: f32,
+    climb_rate: f32,
+) -> (f32, f32) {
+    let (mut north, mut east) = (0.0, 0.0);
+    if climb_rate <= 0.0 || to_height <= from_height {
+        return (north, east);
+    }
+
+    let steps = ((to_height - from_height) / DRIFT_STEP).ceil() as usize;
+    let step = (to_height - from_height) / steps as f32;
+    for i in 0..steps {
+        let altitude = from_height + step * (i as f32 + 0.5);
+        let (speed, direction) = query.wind_at_altitude(altitude);
+        let distance = speed / 3.6 * step / climb_rate;
+        // The wind blows towards the opposite of the direction it comes from.
+        north += distance * (direction + PI).cos();
+        east += distance * (direction + PI).sin();
+    }
+    (north, east)
+}
+
+/// Point at which the start height of the query is reached when climbing
+/// from the ground at the given point, e.g. in a winch tow. Without
+/// `start_height`, the climb ends `additional_height` above the ground.
+pub fn drifted_start(
+    latitude: f32,
+    longitude: f32,
+    cell_size: f32,
+    query: &SearchQuery,
+    climb_rate: f32,
+) -> (f32, f32) {
+    let data_source =
+        select_data_source(latitude, longitude, cell_size).unwrap_or(DataSource::Srtm);
+    let ground_height = get_interpolated_height_at_point_from(
+        data_source,
+        latitude,
+        longitude,
+        Interpolation::Bilinear,
+    );
+    let start_height = query
+        .start_height
+        .unwrap_or(ground_height + query.additional_height);
+
+    let (north, east) = climb_drift(query, ground_height, start_height, climb_rate);
+    offset_point(latitude, longitude, north, east)
+}
+
+#[cfg(test)]
+#[path = "./drift_test.rs"]
+mod drift_test;
@@ -0,0 +1,42 @@
+use approx::assert_relative_eq;
+
+use crate::{search::SearchQuery, wind::parse_wind_layers};
+
+use super::climb_drift;
+
+fn query(wind_speed: f32, wind_direction: f32, wind_layers: &str) -> SearchQuery {
+    let mut builder = SearchQuery::builder()
+        .wind_speed_kmh(wind_speed)
+        .wind_direction_deg(wind_direction);
+    if !wind_layers.is_empty() {
+        builder = builder.wind_layers(parse_wind_layers(wind_layers).unwrap());
+    }
+    builder.build().unwrap()
+}
+
+#[test]
+fn test_climb_drift() {
+    // 300 m at 1 m/s in 36 km/h from the west drifts 3 km east.
+    let (north, east) = climb_drift(&query(36.0, 270.0, ""), 1000.0, 1300.0, 1.0);
+    assert_relative_eq!(north, 0.0, epsilon = 1.0);
+    assert_relative_eq!(east, 3000.0, epsilon = 1.0);
+
+    // Only the upper half of the climb is in wind from the north.
+    let (north, east) = climb_drift(
+        &query(0.0, 0.0, "1000:0:0,1100:36:0,1200:36:0"),
+        1000.0,
+        1200.0,
+        2.0,
+    );
+    assert!(north < -600.0 && north > -1000.0);
+    assert_relative_eq!(east, 0.0, epsilon = 1.0);
+
+    assert_eq!(
+        climb_drift(&query(36.0, 0.0, ""), 1000.0, 900.0, 1.0),
+        (0.0, 0.0)
+    );
+    assert_eq!(
+        climb_drift(&query(36.0, 0.0, ""), 1000.0, 1300.0, 0.0),
+        (0.0, 0.0)
+    );
+}
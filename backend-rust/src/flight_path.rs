@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::{
+    cone_stats::agl_of,
+    height_data::HeightGrid,
+    search::{GridIx, Node},
+};
+
+/// Vertex of the glide path to a cell, at the center of a grid cell.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct PathVertex {
+    pub lat: f32,
+    pub lon: f32,
+    /// Height above sea level in meters.
+    pub height: f32,
+    /// Height above ground in meters.
+    pub agl: f32,
+    /// Length of the glide path from the start in meters.
+    pub distance: f32,
+}
+
+/// Glide path from the start of a search to `target`, found by following the
+/// references of the nodes back to the start. Between two vertices the
+/// glide is a straight line. `None` if `target` is not reachable.
+pub fn glide_path(explored: &[Node], grid: &HeightGrid, target: GridIx) -> Option<Vec<PathVertex>> {
+    let nodes = explored
+        .iter()
+        .map(|node| (node.ix, node))
+        .collect::<HashMap<_, _>>();
+    let mut path = vec![];
+    let mut current = nodes.get(&target).filter(|node| node.reachable).copied();
+    while let Some(node) = current {
+        // References never form cycles, but a broken search must not hang
+        // the server.
+        if path.len() > nodes.len() {
+            return None;
+        }
+        let (lat, lon) = grid.cell_center((node.ix.0 as usize, node.ix.1 as usize));
+        path.push(PathVertex {
+            lat,
+            lon,
+            height: node.height,
+            agl: agl_of(node, grid),
+            distance: node.distance,
+        });
+        current = match node.reference {
+            Some(reference) => Some(*nodes.get(&reference)?),
+            None => None,
+        };
+    }
+    path.reverse();
+    (!path.is_empty()).then_some(path)
+}
+
+#[cfg(test)]
+#[path = "./flight_path_test.rs"]
+mod flight_path_test;
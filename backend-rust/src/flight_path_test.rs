@@ -0,0 +1,44 @@
+use approx::assert_relative_eq;
+use ndarray::Array2;
+
+use crate::{height_data::HeightGrid, search::Node};
+
+use super::glide_path;
+
+#[test]
+fn test_glide_path() {
+    let grid = HeightGrid::for_test(Array2::from_elem((2, 4), 100), (47.0, 47.2), (11.0, 11.4));
+    let explored = [
+        Node::for_test((0, 0), 500.0, true),
+        Node {
+            reference: Some((0, 0)),
+            distance: 100.0,
+            ..Node::for_test((0, 1), 450.0, true)
+        },
+        Node {
+            reference: Some((0, 1)),
+            distance: 300.0,
+            ..Node::for_test((1, 3), 300.0, true)
+        },
+        Node {
+            reference: Some((0, 1)),
+            distance: 200.0,
+            ..Node::for_test((1, 2), 90.0, false)
+        },
+    ];
+
+    let path = glide_path(&explored, &grid, (1, 3)).unwrap();
+    assert_eq!(path.len(), 3);
+    assert_relative_eq!(path[0].lat, 47.0);
+    assert_relative_eq!(path[0].lon, 11.0);
+    assert_relative_eq!(path[0].agl, 400.0);
+    assert_relative_eq!(path[2].lat, 47.1);
+    assert_relative_eq!(path[2].lon, 11.3);
+    assert_relative_eq!(path[2].height, 300.0);
+    assert_relative_eq!(path[2].agl, 200.0);
+    assert_relative_eq!(path[2].distance, 300.0);
+
+    assert_eq!(glide_path(&explored, &grid, (0, 0)).unwrap().len(), 1);
+    assert!(glide_path(&explored, &grid, (1, 2)).is_none());
+    assert!(glide_path(&explored, &grid, (1, 0)).is_none());
+}
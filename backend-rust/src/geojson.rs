@@ -0,0 +1,134 @@
+use serde::Serialize;
+
+use crate::{
+    boundary::{corner_coordinates, Corner, RegionPolygon},
+    cone_stats::agl_of,
+    height_data::HeightGrid,
+    hgt_manifest::Provenance,
+    search::{GridIx, Node},
+};
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "type")]
+pub enum Geometry {
+    /// Rings of `[longitude, latitude]` positions, the first one is the
+    /// outer boundary.
+    Polygon { coordinates: Vec<Vec<[f32; 2]>> },
+    /// Lines of `[longitude, latitude]` positions.
+    MultiLineString { coordinates: Vec<Vec<[f32; 2]>> },
+    /// Rings of several polygons, like those of `Polygon`.
+    MultiPolygon {
+        coordinates: Vec<Vec<Vec<[f32; 2]>>>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "type", rename = "Feature")]
+pub struct Feature<P> {
+    pub geometry: Geometry,
+    pub properties: P,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "type", rename = "FeatureCollection")]
+pub struct FeatureCollection<P> {
+    pub features: Vec<Feature<P>>,
+    /// Origin of the heights the features were derived from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Provenance>,
+}
+
+impl<P> FeatureCollection<P> {
+    pub fn with_metadata(self, metadata: Provenance) -> Self {
+        FeatureCollection {
+            metadata: Some(metadata),
+            ..self
+        }
+    }
+}
+
+/// Properties of a reachable cell.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct CellProperties {
+    /// Height above ground in meters at which the cell is reached.
+    pub agl: f32,
+    /// Height above sea level in meters at which the cell is reached.
+    pub height: f32,
+    /// Length of the glide path to the cell in meters.
+    pub distance: f32,
+    /// Ground speed in km/h on arrival at the cell.
+    pub ground_speed: f32,
+}
+
+/// Closed ring around the cell at `ix`, see `HeightGrid::cell_polygon`.
+pub fn cell_ring(ix: GridIx, grid: &HeightGrid) -> Vec<[f32; 2]> {
+    grid.cell_polygon((ix.0 as usize, ix.1 as usize)).to_vec()
+}
+
+/// Properties of the outline of the reachable area.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct OutlineProperties {
+    /// Area of the reachable cells in square kilometers.
+    pub area_km2: f32,
+}
+
+fn corner_ring(ring: &[Corner], grid: &HeightGrid) -> Vec<[f32; 2]> {
+    ring.iter()
+        .map(|corner| {
+            let (lat, lon) = corner_coordinates(*corner, grid);
+            [lon, lat]
+        })
+        .collect()
+}
+
+/// Reachable area as one multi polygon, see `region_polygons`. Unreachable
+/// cells inside of it are interior rings.
+pub fn reachable_outline(
+    polygons: &[RegionPolygon],
+    grid: &HeightGrid,
+    area_km2: f32,
+) -> FeatureCollection<OutlineProperties> {
+    FeatureCollection {
+        features: vec![Feature {
+            geometry: Geometry::MultiPolygon {
+                coordinates: polygons
+                    .iter()
+                    .map(|polygon| {
+                        std::iter::once(&polygon.outer)
+                            .chain(polygon.holes.iter())
+                            .map(|ring| corner_ring(ring, grid))
+                            .collect()
+                    })
+                    .collect(),
+            },
+            properties: OutlineProperties { area_km2 },
+        }],
+        metadata: None,
+    }
+}
+
+/// One polygon per reachable cell of a finished search.
+pub fn reachable_area(explored: &[Node], grid: &HeightGrid) -> FeatureCollection<CellProperties> {
+    FeatureCollection {
+        features: explored
+            .iter()
+            .filter(|node| node.reachable)
+            .map(|node| Feature {
+                geometry: Geometry::Polygon {
+                    coordinates: vec![cell_ring(node.ix, grid)],
+                },
+                properties: CellProperties {
+                    agl: agl_of(node, grid),
+                    height: node.height,
+                    distance: node.distance,
+                    ground_speed: node.ground_speed,
+                },
+            })
+            .collect(),
+        metadata: None,
+    }
+}
+
+#[cfg(test)]
+#[path = "./geojson_test.rs"]
+mod geojson_test;
@@ -0,0 +1,85 @@
+use approx::assert_relative_eq;
+use ndarray::Array2;
+
+use crate::{boundary::RegionPolygon, height_data::HeightGrid, search::Node};
+
+use super::{cell_ring, reachable_area, reachable_outline, CellProperties, Geometry};
+
+fn grid() -> HeightGrid {
+    HeightGrid::for_test(
+        Array2::from_shape_vec((2, 2), vec![100, 200, 300, 400]).unwrap(),
+        (47.0, 47.2),
+        (11.0, 11.2),
+    )
+}
+
+#[test]
+fn test_cell_ring() {
+    let ring = cell_ring((1, 0), &grid());
+
+    assert_eq!(ring.len(), 5);
+    assert_eq!(ring[0], ring[4]);
+    assert_relative_eq!(ring[0][0], 10.95);
+    assert_relative_eq!(ring[0][1], 47.05);
+    assert_relative_eq!(ring[2][0], 11.05);
+    assert_relative_eq!(ring[2][1], 47.15);
+}
+
+#[test]
+fn test_reachable_area() {
+    let node = |ix, height, reachable| Node {
+        height,
+        ix,
+        reference: None,
+        distance: 50.0,
+        reachable,
+        clearance: f32::INFINITY,
+        min_agl: f32::INFINITY,
+        ground_speed: 0.0,
+        explored: true,
+    };
+
+    let collection = reachable_area(
+        &[node((0, 1), 250.0, true), node((1, 1), 350.0, false)],
+        &grid(),
+    );
+
+    assert_eq!(collection.features.len(), 1);
+    let feature = &collection.features[0];
+    assert_eq!(
+        feature.properties,
+        CellProperties {
+            agl: 50.0,
+            height: 250.0,
+            distance: 50.0,
+            ground_speed: 0.0,
+        }
+    );
+    let Geometry::Polygon { coordinates } = &feature.geometry else {
+        panic!("Cells are polygons");
+    };
+    assert_eq!(coordinates[0], cell_ring((0, 1), &grid()));
+}
+
+#[test]
+fn test_reachable_outline() {
+    let polygon = RegionPolygon {
+        outer: vec![(0, 0), (0, 2), (2, 2), (2, 0), (0, 0)],
+        holes: vec![vec![(1, 1), (2, 1), (2, 2), (1, 2), (1, 1)]],
+    };
+
+    let collection = reachable_outline(&[polygon], &grid(), 0.03);
+
+    assert_eq!(collection.features.len(), 1);
+    assert_eq!(collection.features[0].properties.area_km2, 0.03);
+    let Geometry::MultiPolygon { coordinates } = &collection.features[0].geometry else {
+        panic!("The outline is a multi polygon");
+    };
+    assert_eq!(coordinates.len(), 1);
+    assert_eq!(coordinates[0].len(), 2);
+    // Corners are between the cells.
+    assert_relative_eq!(coordinates[0][0][0][0], 10.95);
+    assert_relative_eq!(coordinates[0][0][0][1], 46.95);
+    assert_relative_eq!(coordinates[0][1][1][0], 11.05);
+    assert_relative_eq!(coordinates[0][1][1][1], 47.15);
+}
@@ -1,6 +1,6 @@
 use byteorder::{BigEndian, ByteOrder};
 use cached::proc_macro::cached;
-use cached::Cached;
+use cached::{Cached, Return, SizedCache};
 use ndarray::linspace;
 use ndarray::s;
 use ndarray::Array;
@@ -8,11 +8,19 @@ use ndarray::Array1;
 use ndarray::Array2;
 use ndarray::ArrayView;
 use ndarray::Ix2;
+use serde::Serialize;
+use std::collections::HashMap;
 use std::f32::consts::PI;
 use std::fs::File;
 use std::io::BufReader;
 use std::io::Read;
+use std::sync::{Arc, Mutex, OnceLock};
 
+use crate::config::data_dir;
+use crate::metrics::{record_cache_access, CacheTier};
+
+/// Points per side of SRTM1 tiles, the finest supported resolution. SRTM3
+/// tiles have 1201 points per side, both can be mixed in a data directory.
 const HGT_SIZE: usize = 3601;
 const HGT_SIZE_SQUARED: usize = HGT_SIZE * HGT_SIZE;
 const HGT_N_BYTES: usize = HGT_SIZE_SQUARED << 1;
@@ -21,7 +29,140 @@ const ANGLE_TO_RADIANS: f32 = PI / 180.0;
 const ARC_SECOND_IN_M_EQUATOR: f32 = 1852.0 / 60.0;
 const ARC_SECOND_IN_DEGREE: f32 = 1.0 / (60.0 * 60.0); //TODO is this safe
 
-pub fn get_file_name(latitude: i32, longitude: i32) -> String {
+/// Cell sizes at or above this value are served from the coarse global DEM,
+/// since the full resolution SRTM data would be downsampled away anyway.
+pub const COARSE_CELL_SIZE_THRESHOLD: f32 = 500.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DataSource {
+    /// Full resolution SRTM/viewfinderpanoramas tiles in the data directory.
+    Srtm,
+    /// Low resolution global tiles (e.g. resampled GMTED/ETOPO) in `coarse/`
+    /// of the data directory.
+    Coarse,
+    /// Generated terrain, see `synthetic`. Has no tiles on disk.
+    Synthetic,
+}
+
+/// Vertical error in meters of cells copied from a neighbour to fill a void.
+/// Voids are mostly in steep terrain, so this is well above the error of the
+/// measured cells.
+pub const VOID_FILLED_VERTICAL_ERROR: f32 = 50.0;
+
+impl DataSource {
+    /// Typical vertical error of the dataset in meters: the published 90%
+    /// absolute error of SRTM and the RMSE of GMTED2010.
+    pub fn vertical_error(&self) -> f32 {
+        match self {
+            DataSource::Srtm => 16.0,
+            DataSource::Coarse => 30.0,
+            DataSource::Synthetic => 0.0,
+        }
+    }
+
+    /// Name of the dataset usually served from this source.
+    pub fn dataset(&self) -> &'static str {
+        match self {
+            DataSource::Srtm => "SRTM",
+            DataSource::Coarse => "GMTED2010",
+            DataSource::Synthetic => "Synthetic terrain",
+        }
+    }
+
+    /// License of the usual dataset, which derived products inherit.
+    pub fn license(&self) -> &'static str {
+        match self {
+            DataSource::Srtm | DataSource::Coarse => "Public domain",
+            DataSource::Synthetic => "None",
+        }
+    }
+
+    /// Credit to give when publishing products derived from the dataset.
+    pub fn attribution(&self) -> &'static str {
+        match self {
+            DataSource::Srtm => "Shuttle Radar Topography Mission (SRTM), NASA/USGS",
+            DataSource::Coarse => "Global Multi-resolution Terrain Elevation Data 2010, USGS/NGA",
+            DataSource::Synthetic => "Generated by hikeandfly",
+        }
+    }
+}
+
+/// How trustworthy the height of a cell is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DataQuality {
+    Measured,
+    /// Void or outlier in the DEM, copied from a neighbouring cell.
+    VoidFilled,
+    /// No DEM tile covers the cell, it is assumed to be at sea level.
+    Synthesized,
+}
+
+impl DataQuality {
+    pub fn is_measured(&self) -> bool {
+        *self == DataQuality::Measured
+    }
+}
+
+/// Heights of one DEM tile.
+#[derive(Clone)]
+pub struct Tile {
+    pub heights: Array2<i16>,
+    /// Row major indices of the cells that were void filled, ascending.
+    pub void_filled: Vec<u32>,
+}
+
+impl Tile {
+    /// Resamples the tile to `shape` points per side, so tiles of different
+    /// resolutions can be combined. Heights are interpolated bilinearly, a
+    /// point counts as void filled if the closest original point was.
+    pub fn resample(&self, shape: usize) -> Tile {
+        let original = self.heights.shape()[0];
+        if original == shape {
+            return self.clone();
+        }
+
+        // The points of a tile lie on its edges, so the corners stay in place.
+        let scale = usize_f32(original - 1) / usize_f32(shape - 1);
+        let heights = Array2::from_shape_fn((shape, shape), |(row, col)| {
+            interpolate_2d_array(
+                &self.heights.view(),
+                usize_f32(row) * scale,
+                usize_f32(col) * scale,
+                Interpolation::Bilinear,
+            )
+            .round() as i16
+        });
+
+        let mut void_filled = vec![];
+        if !self.void_filled.is_empty() {
+            let mut is_void = Array2::from_elem((original, original), false);
+            for ix in self.void_filled.iter() {
+                is_void[(*ix as usize / original, *ix as usize % original)] = true;
+            }
+            for (ix, (row, col)) in (0..shape)
+                .flat_map(|row| (0..shape).map(move |col| (row, col)))
+                .enumerate()
+            {
+                let nearest = (
+                    (usize_f32(row) * scale).round() as usize,
+                    (usize_f32(col) * scale).round() as usize,
+                );
+                if is_void[nearest] {
+                    void_filled.push(ix as u32);
+                }
+            }
+        }
+
+        Tile {
+            heights,
+            void_filled,
+        }
+    }
+}
+
+pub fn tile_name(latitude: i32, longitude: i32) -> String {
     let lat_string = if latitude >= 0 {
         format!("N{:02}", latitude)
     } else {
@@ -33,21 +174,192 @@ pub fn get_file_name(latitude: i32, longitude: i32) -> String {
         format!("W{:03}", -longitude)
     };
 
-    format!("./data/{}{}.hgt", lat_string, lon_string)
+    format!("{}{}", lat_string, lon_string)
+}
+
+pub fn get_file_name(latitude: i32, longitude: i32) -> String {
+    data_dir()
+        .join(format!("{}.hgt", tile_name(latitude, longitude)))
+        .to_string_lossy()
+        .into_owned()
+}
+
+pub fn get_coarse_file_name(latitude: i32, longitude: i32) -> String {
+    data_dir()
+        .join("coarse")
+        .join(format!("{}.hgt", tile_name(latitude, longitude)))
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn tile_file_name(source: DataSource, latitude: i32, longitude: i32) -> Option<String> {
+    match source {
+        DataSource::Srtm => Some(get_file_name(latitude, longitude)),
+        DataSource::Coarse => Some(get_coarse_file_name(latitude, longitude)),
+        DataSource::Synthetic => None,
+    }
+}
+
+pub fn tile_exists(source: DataSource, latitude: i32, longitude: i32) -> bool {
+    tile_file_name(source, latitude, longitude).is_some_and(|name| File::open(name).is_ok())
+}
+
+/// Points per side of a tile, from its file size. `None` if the tile does not
+/// exist.
+fn tile_side(source: DataSource, latitude: i32, longitude: i32) -> Option<usize> {
+    let name = tile_file_name(source, latitude, longitude)?;
+    let n_entries = std::fs::metadata(name).ok()?.len() / 2;
+    Some((n_entries as f64).sqrt() as usize)
 }
 
 pub fn location_supported(latitude: f32, longitude: f32) -> bool {
+    tile_exists(
+        DataSource::Srtm,
+        latitude.floor() as i32,
+        longitude.floor() as i32,
+    )
+}
+
+/// Returns true if every tile of `source` needed for the area within
+/// `distance_m` around the point is present.
+pub fn area_supported(source: DataSource, latitude: f32, longitude: f32, distance_m: f32) -> bool {
+    let (lower_latitude, upper_latitude, lower_longitude, upper_longitude) =
+        get_bounds_around_point(latitude, longitude, distance_m);
+
+    for lat_i in (lower_latitude.floor() as i32)..=(upper_latitude.floor() as i32) {
+        for lon_i in (lower_longitude.floor() as i32)..=(upper_longitude.floor() as i32) {
+            if !tile_exists(source, lat_i, lon_i) {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// Tiles of the given area that are neither covered by SRTM nor by the coarse
+/// DEM, as `(latitude, longitude)` of their south west corner.
+pub fn missing_tiles(latitudes: (f32, f32), longitudes: (f32, f32)) -> Vec<(i32, i32)> {
+    let mut result = vec![];
+    for lat_i in (latitudes.0.floor() as i32)..=(latitudes.1.floor() as i32) {
+        for lon_i in (longitudes.0.floor() as i32)..=(longitudes.1.floor() as i32) {
+            if !tile_exists(DataSource::Srtm, lat_i, lon_i)
+                && !tile_exists(DataSource::Coarse, lat_i, lon_i)
+            {
+                result.push((lat_i, lon_i));
+            }
+        }
+    }
+    result
+}
+
+/// Picks the DEM used for a search around the given point. SRTM is preferred,
+/// the coarse global DEM is used for large cell sizes or when SRTM tiles are
+/// missing. Returns `None` if neither covers the point.
+pub fn select_data_source(latitude: f32, longitude: f32, cell_size: f32) -> Option<DataSource> {
     let lat_i = latitude.floor() as i32;
     let lon_i = longitude.floor() as i32;
 
-    let file_name = get_file_name(lat_i, lon_i);
+    let srtm = tile_exists(DataSource::Srtm, lat_i, lon_i);
+    let coarse = tile_exists(DataSource::Coarse, lat_i, lon_i);
 
-    File::open(file_name).is_ok()
+    if coarse && (cell_size >= COARSE_CELL_SIZE_THRESHOLD || !srtm) {
+        Some(DataSource::Coarse)
+    } else if srtm {
+        Some(DataSource::Srtm)
+    } else {
+        None
+    }
+}
+
+/// SRTM tiles kept in memory.
+pub const HGT_CACHE_TILES: usize = 80;
+/// Tiles of the coarse global DEM kept in memory.
+pub const COARSE_HGT_CACHE_TILES: usize = 40;
+
+#[cached(
+    ty = "SizedCache<(i32, i32), Return<Tile>>",
+    create = "{ SizedCache::with_size(HGT_CACHE_TILES) }",
+    with_cached_flag = true
+)]
+pub fn load_hgt(latitude: i32, longitude: i32) -> Return<Tile> {
+    Return::new(read_hgt_file(&get_file_name(latitude, longitude)))
+}
+
+#[cached(
+    ty = "SizedCache<(i32, i32), Return<Tile>>",
+    create = "{ SizedCache::with_size(COARSE_HGT_CACHE_TILES) }",
+    with_cached_flag = true
+)]
+pub fn load_coarse_hgt(latitude: i32, longitude: i32) -> Return<Tile> {
+    Return::new(read_hgt_file(&get_coarse_file_name(latitude, longitude)))
 }
 
-#[cached(size = 80)]
-pub fn load_hgt(latitude: i32, longitude: i32) -> Array2<i16> {
-    let file_name = get_file_name(latitude, longitude);
+pub fn load_tile(source: DataSource, latitude: i32, longitude: i32) -> Tile {
+    let pinned = pinned_tiles()
+        .lock()
+        .unwrap()
+        .get(&(source, latitude, longitude))
+        .map(|(tile, _)| tile.clone());
+    if let Some(tile) = pinned {
+        record_cache_access(CacheTier::Hgt, true);
+        return (*tile).clone();
+    }
+    let tile = match source {
+        DataSource::Srtm => load_hgt(latitude, longitude),
+        DataSource::Coarse => load_coarse_hgt(latitude, longitude),
+        DataSource::Synthetic => panic!("Synthetic terrain has no tiles"),
+    };
+    record_cache_access(CacheTier::Hgt, tile.was_cached);
+    tile.value
+}
+
+type TileKey = (DataSource, i32, i32);
+
+/// Tiles kept in memory apart from the size-bounded caches, with the number
+/// of `PinnedTile`s holding each.
+type PinnedTiles = Mutex<HashMap<TileKey, (Arc<Tile>, usize)>>;
+
+fn pinned_tiles() -> &'static PinnedTiles {
+    static PINNED: OnceLock<PinnedTiles> = OnceLock::new();
+    PINNED.get_or_init(Default::default)
+}
+
+/// Keeps a tile in memory until it is dropped, so reading many other tiles
+/// in the meantime can't evict it from the cache.
+pub struct PinnedTile {
+    key: TileKey,
+    /// Tells the pins of a tile apart from those made after it was forgotten.
+    tile: Arc<Tile>,
+}
+
+pub fn pin_tile(source: DataSource, latitude: i32, longitude: i32) -> PinnedTile {
+    let key = (source, latitude, longitude);
+    let tile = load_tile(source, latitude, longitude);
+    let mut pinned = pinned_tiles().lock().unwrap();
+    let (tile, pins) = pinned.entry(key).or_insert_with(|| (Arc::new(tile), 0));
+    *pins += 1;
+    PinnedTile {
+        key,
+        tile: tile.clone(),
+    }
+}
+
+impl Drop for PinnedTile {
+    fn drop(&mut self) {
+        let mut pinned = pinned_tiles().lock().unwrap();
+        // The tile may have been forgotten and pinned again in the meantime.
+        if let Some((tile, pins)) = pinned.get_mut(&self.key) {
+            if Arc::ptr_eq(tile, &self.tile) {
+                *pins -= 1;
+                if *pins == 0 {
+                    pinned.remove(&self.key);
+                }
+            }
+        }
+    }
+}
+
+fn read_hgt_file(file_name: &str) -> Tile {
     let file = File::open(file_name).expect("Could not open hgt file");
     let mut reader = BufReader::new(file);
     let mut content = Vec::<u8>::with_capacity(HGT_N_BYTES);
@@ -60,8 +372,12 @@ pub fn load_hgt(latitude: i32, longitude: i32) -> Array2<i16> {
     assert!(shape * shape * 2 == total_read, "Bad HGT file size");
 
     let mut result_vec: Vec<i16> = Vec::<i16>::with_capacity(content.len());
+    let mut void_filled = vec![];
     for i in (0..content.len()).step_by(2) {
         let mut r = BigEndian::read_i16(&content[i..i + 2]);
+        if r < -1000 {
+            void_filled.push(result_vec.len() as u32);
+        }
         if r < -1000 && !result_vec.is_empty() {
             r = result_vec[result_vec.len() - 1];
         }
@@ -80,7 +396,30 @@ pub fn load_hgt(latitude: i32, longitude: i32) -> Array2<i16> {
         result_vec.push(r);
     }
 
-    Array::from_shape_vec((shape, shape), result_vec).unwrap()
+    Tile {
+        heights: Array::from_shape_vec((shape, shape), result_vec).unwrap(),
+        void_filled,
+    }
+}
+
+/// Drops a tile from the cache and the pinned tiles, so a replaced file is
+/// read again.
+pub fn forget_tile(latitude: i32, longitude: i32) {
+    LOAD_HGT
+        .lock()
+        .unwrap()
+        .cache_remove(&(latitude, longitude));
+    pinned_tiles()
+        .lock()
+        .unwrap()
+        .remove(&(DataSource::Srtm, latitude, longitude));
+}
+
+/// Drops all tiles from the cache, e.g. after the DEM was updated.
+pub fn clear_tile_cache() {
+    LOAD_HGT.lock().unwrap().cache_clear();
+    LOAD_COARSE_HGT.lock().unwrap().cache_clear();
+    pinned_tiles().lock().unwrap().clear();
 }
 
 pub fn cache_sizes() -> usize {
@@ -91,7 +430,14 @@ pub fn cache_sizes() -> usize {
             0
         }
     };
-    load_hgt_cache_size
+    let load_coarse_hgt_cache_size = {
+        if let Ok(guard) = LOAD_COARSE_HGT.try_lock() {
+            guard.cache_size()
+        } else {
+            0
+        }
+    };
+    load_hgt_cache_size + load_coarse_hgt_cache_size
 }
 
 pub fn arcsecond_in_meters(latitude: f32) -> f32 {
@@ -109,49 +455,241 @@ pub struct HeightGrid {
     pub min_cell_size: f32,
     pub latitudes: (f32, f32),
     pub longitudes: (f32, f32),
+    pub data_source: DataSource,
+    /// Quality of every cell, `None` if all heights were measured.
+    pub data_quality: Option<Array2<DataQuality>>,
+}
+
+#[cfg(test)]
+impl HeightGrid {
+    /// Measured SRTM heights in 100 m cells covering the given area.
+    pub fn for_test(
+        heights: Array2<i16>,
+        latitudes: (f32, f32),
+        longitudes: (f32, f32),
+    ) -> HeightGrid {
+        HeightGrid {
+            heights,
+            cell_size: 100.0,
+            min_cell_size: 100.0,
+            latitudes,
+            longitudes,
+            data_source: DataSource::Srtm,
+            data_quality: None,
+        }
+    }
 }
 
 pub fn usize_f32(x: usize) -> f32 {
-    f32::from(x as u16)
+    x as f32
 }
 
 pub fn f32_usize(x: f32) -> usize {
-    usize::from(x as u16)
+    x as usize
 }
 
 pub fn i32_f32(x: i32) -> f32 {
     f32::from(x as i16)
 }
 
-pub fn scale_2d_array(values: &ArrayView<'_, i16, Ix2>, scales: (f32, f32)) -> Array2<i16> {
+/// How heights between the points of the DEM are sampled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interpolation {
+    /// Height of the closest point.
+    Nearest,
+    /// Weighted mean of the four surrounding points, avoids blocky terrain
+    /// and steps at coarse cell sizes.
+    Bilinear,
+}
+
+/// Height at the fractional position `(row, col)` of `values`. Positions
+/// outside of the array are clamped to its border.
+pub fn interpolate_2d_array(
+    values: &ArrayView<'_, i16, Ix2>,
+    row: f32,
+    col: f32,
+    interpolation: Interpolation,
+) -> f32 {
+    let max_row = usize_f32(values.shape()[0] - 1);
+    let max_col = usize_f32(values.shape()[1] - 1);
+    let (row, col) = (row.clamp(0.0, max_row), col.clamp(0.0, max_col));
+    match interpolation {
+        Interpolation::Nearest => values[[row.round() as usize, col.round() as usize]] as f32,
+        Interpolation::Bilinear => {
+            let (row_0, col_0) = (row.floor(), col.floor());
+            let (row_1, col_1) = ((row_0 + 1.0).min(max_row), (col_0 + 1.0).min(max_col));
+            let (t_row, t_col) = (row - row_0, col - col_0);
+            let at = |r: f32, c: f32| values[[r as usize, c as usize]] as f32;
+            let top = at(row_0, col_0) * (1.0 - t_col) + at(row_0, col_1) * t_col;
+            let bottom = at(row_1, col_0) * (1.0 - t_col) + at(row_1, col_1) * t_col;
+            top * (1.0 - t_row) + bottom * t_row
+        }
+    }
+}
+
+/// Like `scale_2d_array`, but samples the heights with the given
+/// interpolation.
+pub fn scale_heights(
+    values: &ArrayView<'_, i16, Ix2>,
+    scales: (f32, f32),
+    interpolation: Interpolation,
+) -> Array2<i16> {
+    let size_x = usize_f32(values.shape()[0]);
+    let size_y = usize_f32(values.shape()[1]);
+
+    let n_elems_x = (size_x * scales.0).ceil() as usize;
+    let n_elems_y = (size_y * scales.1).ceil() as usize;
+
+    let x_positions = Array1::from_iter(linspace(0.0, size_x - 1.0, n_elems_x));
+    let y_positions = Array1::from_iter(linspace(0.0, size_y - 1.0, n_elems_y));
+
+    Array2::from_shape_fn((n_elems_x, n_elems_y), |(new_x, new_y)| {
+        interpolate_2d_array(
+            values,
+            x_positions[new_x],
+            y_positions[new_y],
+            interpolation,
+        )
+        .round() as i16
+    })
+}
+
+pub fn scale_2d_array<T: Copy>(values: &ArrayView<'_, T, Ix2>, scales: (f32, f32)) -> Array2<T> {
     //TODO: Specialize for square shapes?
-    let size_x = values.shape()[0] as u16;
-    let size_y = values.shape()[1] as u16;
+    let size_x = usize_f32(values.shape()[0]);
+    let size_y = usize_f32(values.shape()[1]);
 
-    let n_elems_x = (f32::from(size_x) * scales.0).ceil() as usize;
-    let n_elems_y = (f32::from(size_y) * scales.1).ceil() as usize;
+    let n_elems_x = (size_x * scales.0).ceil() as usize;
+    let n_elems_y = (size_y * scales.1).ceil() as usize;
 
-    let x_indices = Array1::from_iter(linspace(0.0, f32::from(size_x - 1), n_elems_x)).round();
-    let y_indices = Array1::from_iter(linspace(0.0, f32::from(size_y - 1), n_elems_y)).round();
+    let x_indices = Array1::from_iter(linspace(0.0, size_x - 1.0, n_elems_x)).round();
+    let y_indices = Array1::from_iter(linspace(0.0, size_y - 1.0, n_elems_y)).round();
 
-    let mut result = Array2::zeros((n_elems_x, n_elems_y));
-    for (new_x, old_x) in x_indices.iter().enumerate() {
-        for (new_y, old_y) in y_indices.iter().enumerate() {
-            result[[new_x, new_y]] = values[[*old_x as usize, *old_y as usize]]
-        }
-    }
-    result
+    Array2::from_shape_fn((n_elems_x, n_elems_y), |(new_x, new_y)| {
+        values[[x_indices[new_x] as usize, y_indices[new_y] as usize]]
+    })
+}
+
+/// Cell size closest to `requested` that is a whole multiple of the
+/// `native` cell size of the DEM, so every cell of the scaled grid covers
+/// the same number of DEM cells.
+pub fn snap_cell_size(requested: f32, native: f32) -> f32 {
+    native * (requested / native).round().max(1.0)
+}
+
+/// Whole number `factor` that `scale` shrinks a grid by, if there is one.
+fn decimation_factor(scale: f32) -> Option<usize> {
+    let factor = 1.0 / scale;
+    (factor.round() >= 2.0 && (factor - factor.round()).abs() < 1e-3)
+        .then_some(factor.round() as usize)
+}
+
+/// Every `factor`-th cell of `values` in both directions, starting with the
+/// first one.
+pub fn decimate_2d_array<T: Copy>(values: &ArrayView<'_, T, Ix2>, factor: usize) -> Array2<T> {
+    let shape = (
+        values.shape()[0].div_ceil(factor),
+        values.shape()[1].div_ceil(factor),
+    );
+    Array2::from_shape_fn(shape, |(row, col)| values[[row * factor, col * factor]])
+}
+
+/// Like `decimate_2d_array`, but every height is the mean of the `factor` by
+/// `factor` cells around it, so narrow features are not dropped or doubled
+/// depending on where the samples fall.
+pub fn decimate_heights(values: &ArrayView<'_, i16, Ix2>, factor: usize) -> Array2<i16> {
+    let shape = (
+        values.shape()[0].div_ceil(factor),
+        values.shape()[1].div_ceil(factor),
+    );
+    let window = |center: usize, size: usize| {
+        center.saturating_sub((factor - 1) / 2)..(center + factor / 2 + 1).min(size)
+    };
+    Array2::from_shape_fn(shape, |(row, col)| {
+        let block = values.slice(s![
+            window(row * factor, values.shape()[0]),
+            window(col * factor, values.shape()[1])
+        ]);
+        let sum = block.iter().map(|height| *height as i64).sum::<i64>();
+        (sum as f64 / block.len() as f64).round() as i16
+    })
 }
 
 impl HeightGrid {
     pub fn scale(&self, factor: f32) -> HeightGrid {
+        self.scale_with(factor, Interpolation::Bilinear)
+    }
+
+    /// Downsamples the grid by `factor`, sampling the heights with the given
+    /// interpolation. The data quality is always taken from the nearest cell.
+    /// Whole number factors decimate the grid instead, see `decimate`.
+    pub fn scale_with(&self, factor: f32, interpolation: Interpolation) -> HeightGrid {
         let scale_f = factor.min(1.0);
+        if let Some(factor) = decimation_factor(scale_f) {
+            return self.decimate(factor);
+        }
         HeightGrid {
-            heights: scale_2d_array(&self.heights.view(), (scale_f, scale_f)),
+            heights: scale_heights(&self.heights.view(), (scale_f, scale_f), interpolation),
             cell_size: self.cell_size / scale_f,
             min_cell_size: self.min_cell_size,
             latitudes: self.latitudes,
             longitudes: self.longitudes,
+            data_source: self.data_source,
+            data_quality: self
+                .data_quality
+                .as_ref()
+                .map(|quality| scale_2d_array(&quality.view(), (scale_f, scale_f))),
+        }
+    }
+
+    /// Keeps every `factor`-th row and column, with the mean height of the
+    /// cells around it. The bounds grow to a whole number of the new cells,
+    /// so the cells keep their positions and the cell size is exact.
+    pub fn decimate(&self, factor: usize) -> HeightGrid {
+        let heights = decimate_heights(&self.heights.view(), factor);
+        let (lat_resolution, lon_resolution) = self.get_angular_resolution();
+        HeightGrid {
+            cell_size: self.cell_size * factor as f32,
+            min_cell_size: self.min_cell_size,
+            latitudes: (
+                self.latitudes.0,
+                self.latitudes.0 + (heights.shape()[0] * factor) as f32 * lat_resolution,
+            ),
+            longitudes: (
+                self.longitudes.0,
+                self.longitudes.0 + (heights.shape()[1] * factor) as f32 * lon_resolution,
+            ),
+            data_source: self.data_source,
+            data_quality: self
+                .data_quality
+                .as_ref()
+                .map(|quality| decimate_2d_array(&quality.view(), factor)),
+            heights,
+        }
+    }
+
+    pub fn data_quality_at(&self, ix: (usize, usize)) -> DataQuality {
+        self.data_quality
+            .as_ref()
+            .map_or(DataQuality::Measured, |quality| quality[ix])
+    }
+
+    /// Vertical error of the height of a cell in meters.
+    pub fn vertical_error_at(&self, ix: (usize, usize)) -> f32 {
+        match self.data_quality_at(ix) {
+            DataQuality::VoidFilled => VOID_FILLED_VERTICAL_ERROR,
+            DataQuality::Measured | DataQuality::Synthesized => self.data_source.vertical_error(),
+        }
+    }
+
+    /// Raises every cell by its vertical error, so searches on the grid
+    /// account for the uncertainty of the DEM.
+    pub fn raise_by_vertical_error(&mut self) {
+        let errors = Array2::from_shape_fn(self.heights.raw_dim(), |ix| {
+            self.vertical_error_at(ix).round() as i16
+        });
+        for (height, error) in self.heights.iter_mut().zip(errors.iter()) {
+            *height = height.saturating_add(*error);
         }
     }
 
@@ -162,6 +700,155 @@ impl HeightGrid {
         )
     }
 
+    /// `(latitude, longitude)` of a fractional grid position. Cells are
+    /// centered on whole rows and columns, so their edges are half a row or
+    /// column away.
+    ///
+    /// ```
+    /// # use backend_rust::height_data::{DataSource, HeightGrid};
+    /// # use ndarray::Array2;
+    /// let grid = HeightGrid {
+    ///     heights: Array2::zeros((10, 20)),
+    ///     cell_size: 100.0,
+    ///     min_cell_size: 100.0,
+    ///     latitudes: (47.0, 48.0),
+    ///     longitudes: (11.0, 13.0),
+    ///     data_source: DataSource::Srtm,
+    ///     data_quality: None,
+    /// };
+    /// assert_eq!(grid.coordinates_at(2.0, 5.0), (47.2, 11.5));
+    /// // South west corner of the first cell.
+    /// assert_eq!(grid.coordinates_at(-0.5, -0.5), (46.95, 10.95));
+    /// ```
+    pub fn coordinates_at(&self, row: f32, col: f32) -> (f32, f32) {
+        let (lat_resolution, lon_resolution) = self.get_angular_resolution();
+        (
+            self.latitudes.0 + row * lat_resolution,
+            self.longitudes.0 + col * lon_resolution,
+        )
+    }
+
+    /// `(latitude, longitude)` of the center of a cell, the inverse of
+    /// `index_of`.
+    ///
+    /// ```
+    /// # use backend_rust::height_data::{DataSource, HeightGrid};
+    /// # use ndarray::Array2;
+    /// # let grid = HeightGrid {
+    /// #     heights: Array2::zeros((10, 20)),
+    /// #     cell_size: 100.0,
+    /// #     min_cell_size: 100.0,
+    /// #     latitudes: (47.0, 48.0),
+    /// #     longitudes: (11.0, 13.0),
+    /// #     data_source: DataSource::Srtm,
+    /// #     data_quality: None,
+    /// # };
+    /// let (lat, lon) = grid.cell_center((3, 7));
+    /// assert_eq!(grid.index_of(lat, lon), Some((3, 7)));
+    /// ```
+    pub fn cell_center(&self, ix: (usize, usize)) -> (f32, f32) {
+        self.coordinates_at(ix.0 as f32, ix.1 as f32)
+    }
+
+    /// Closed ring around a cell as `[longitude, latitude]` positions in
+    /// counterclockwise order, like the rings of GeoJSON polygons.
+    ///
+    /// ```
+    /// # use backend_rust::height_data::{DataSource, HeightGrid};
+    /// # use ndarray::Array2;
+    /// # let grid = HeightGrid {
+    /// #     heights: Array2::zeros((10, 20)),
+    /// #     cell_size: 100.0,
+    /// #     min_cell_size: 100.0,
+    /// #     latitudes: (47.0, 48.0),
+    /// #     longitudes: (11.0, 13.0),
+    /// #     data_source: DataSource::Srtm,
+    /// #     data_quality: None,
+    /// # };
+    /// let ring = grid.cell_polygon((0, 0));
+    /// assert_eq!(ring[0], [10.95, 46.95]);
+    /// assert_eq!(ring[2], [11.05, 47.05]);
+    /// assert_eq!(ring[0], ring[4]);
+    /// ```
+    pub fn cell_polygon(&self, ix: (usize, usize)) -> [[f32; 2]; 5] {
+        let (row, col) = (ix.0 as f32, ix.1 as f32);
+        let corner = |d_row: f32, d_col: f32| {
+            let (lat, lon) = self.coordinates_at(row + d_row, col + d_col);
+            [lon, lat]
+        };
+        [
+            corner(-0.5, -0.5),
+            corner(-0.5, 0.5),
+            corner(0.5, 0.5),
+            corner(0.5, -0.5),
+            corner(-0.5, -0.5),
+        ]
+    }
+
+    /// Cells whose centers lie within the given latitude and longitude
+    /// bounds, row by row from the south.
+    ///
+    /// ```
+    /// # use backend_rust::height_data::{DataSource, HeightGrid};
+    /// # use ndarray::Array2;
+    /// # let grid = HeightGrid {
+    /// #     heights: Array2::zeros((10, 20)),
+    /// #     cell_size: 100.0,
+    /// #     min_cell_size: 100.0,
+    /// #     latitudes: (47.0, 48.0),
+    /// #     longitudes: (11.0, 13.0),
+    /// #     data_source: DataSource::Srtm,
+    /// #     data_quality: None,
+    /// # };
+    /// let cells = grid.cells_in_bounds((46.0, 47.15), (11.05, 11.25)).collect::<Vec<_>>();
+    /// assert_eq!(cells, vec![(0, 1), (0, 2), (1, 1), (1, 2)]);
+    /// ```
+    pub fn cells_in_bounds(
+        &self,
+        latitudes: (f32, f32),
+        longitudes: (f32, f32),
+    ) -> impl Iterator<Item = (usize, usize)> {
+        let (lat_resolution, lon_resolution) = self.get_angular_resolution();
+        let range = |bounds: (f32, f32), origin: f32, resolution: f32, len: usize| {
+            let first = ((bounds.0 - origin) / resolution)
+                .ceil()
+                .clamp(0.0, len as f32);
+            let end = ((bounds.1 - origin) / resolution + 1.0)
+                .floor()
+                .clamp(first, len as f32);
+            first as usize..end as usize
+        };
+        let rows = range(
+            latitudes,
+            self.latitudes.0,
+            lat_resolution,
+            self.heights.shape()[0],
+        );
+        let cols = range(
+            longitudes,
+            self.longitudes.0,
+            lon_resolution,
+            self.heights.shape()[1],
+        );
+        rows.flat_map(move |row| cols.clone().map(move |col| (row, col)))
+    }
+
+    /// Grid position of a point, `None` if it is outside of the grid.
+    pub fn index_of(&self, latitude: f32, longitude: f32) -> Option<(usize, usize)> {
+        let (lat_resolution, lon_resolution) = self.get_angular_resolution();
+        let row = ((latitude - self.latitudes.0) / lat_resolution).round();
+        let col = ((longitude - self.longitudes.0) / lon_resolution).round();
+        let in_grid = row >= 0.0
+            && col >= 0.0
+            && (row as usize) < self.heights.shape()[0]
+            && (col as usize) < self.heights.shape()[1];
+        in_grid.then_some((row as usize, col as usize))
+    }
+
+    /// Latitudes of the rows and longitudes of the columns, spread evenly
+    /// from the lower to the upper bounds. These differ slightly from
+    /// `coordinates_at`, but airspaces and wind on the grid are sampled at
+    /// them and changing that would change searches.
     pub fn get_coordinates_for_indices(&self) -> (Vec<f32>, Vec<f32>) {
         let lats = linspace(self.latitudes.0, self.latitudes.1, self.heights.shape()[0]);
         let lons = linspace(
@@ -175,10 +862,14 @@ impl HeightGrid {
 }
 
 pub fn get_height_at_point(latitude: f32, longitude: f32) -> i16 {
+    get_height_at_point_from(DataSource::Srtm, latitude, longitude)
+}
+
+pub fn get_height_at_point_from(source: DataSource, latitude: f32, longitude: f32) -> i16 {
     let lat_i = latitude.floor();
     let lon_i = longitude.floor();
 
-    let data = load_hgt(lat_i as i32, lon_i as i32);
+    let data = load_tile(source, lat_i as i32, lon_i as i32).heights;
 
     let lat_ix = ((latitude - lat_i) * usize_f32(data.shape()[0])).trunc() as usize;
     let lon_ix = ((longitude - lon_i) * usize_f32(data.shape()[1])).trunc() as usize;
@@ -186,21 +877,90 @@ pub fn get_height_at_point(latitude: f32, longitude: f32) -> i16 {
     *data.get((data.shape()[0] - lat_ix - 1, lon_ix)).unwrap()
 }
 
-pub fn get_height_data_around_point(
+/// Height at a point, sampled between the points of the DEM tile with the
+/// given interpolation. The points of a tile lie on its edges, so
+/// neighbouring tiles share a row or column.
+pub fn get_interpolated_height_at_point_from(
+    source: DataSource,
     latitude: f32,
     longitude: f32,
-    distance_m_opt: Option<f32>,
-) -> HeightGrid {
-    let distance_m = distance_m_opt.unwrap_or(15000.0);
+    interpolation: Interpolation,
+) -> f32 {
+    let lat_i = latitude.floor();
+    let lon_i = longitude.floor();
 
+    let data = load_tile(source, lat_i as i32, lon_i as i32).heights;
+
+    // Rows of a tile go from north to south.
+    let row = (1.0 - (latitude - lat_i)) * usize_f32(data.shape()[0] - 1);
+    let col = (longitude - lon_i) * usize_f32(data.shape()[1] - 1);
+
+    interpolate_2d_array(&data.view(), row, col, interpolation)
+}
+
+pub fn get_bounds_around_point(
+    latitude: f32,
+    longitude: f32,
+    distance_m: f32,
+) -> (f32, f32, f32, f32) {
     let distance_degree_lat = distance_m * ARC_SECOND_IN_DEGREE / ARC_SECOND_IN_M_EQUATOR;
     let distance_degree_lon = meter_in_arcseconds(latitude) * distance_m * ARC_SECOND_IN_DEGREE;
 
-    let lower_latitude = latitude - distance_degree_lat;
-    let upper_latitude = latitude + distance_degree_lat;
+    (
+        latitude - distance_degree_lat,
+        latitude + distance_degree_lat,
+        longitude - distance_degree_lon,
+        longitude + distance_degree_lon,
+    )
+}
+
+/// Point `north` and `east` meters away from the given one.
+pub fn offset_point(latitude: f32, longitude: f32, north: f32, east: f32) -> (f32, f32) {
+    (
+        latitude + north * ARC_SECOND_IN_DEGREE / ARC_SECOND_IN_M_EQUATOR,
+        longitude + east * meter_in_arcseconds(latitude) * ARC_SECOND_IN_DEGREE,
+    )
+}
+
+/// Approximate distance in meters between two nearby points.
+pub fn point_distance(latitude_1: f32, longitude_1: f32, latitude_2: f32, longitude_2: f32) -> f32 {
+    let north = (latitude_2 - latitude_1) / ARC_SECOND_IN_DEGREE * ARC_SECOND_IN_M_EQUATOR;
+    let east = (longitude_2 - longitude_1) / ARC_SECOND_IN_DEGREE
+        * arcsecond_in_meters((latitude_1 + latitude_2) / 2.0);
+    north.hypot(east)
+}
+
+pub fn get_height_data_around_point(
+    latitude: f32,
+    longitude: f32,
+    distance_m_opt: Option<f32>,
+) -> Option<HeightGrid> {
+    get_height_data_around_point_from(DataSource::Srtm, latitude, longitude, distance_m_opt)
+}
+
+pub fn get_height_data_around_point_from(
+    source: DataSource,
+    latitude: f32,
+    longitude: f32,
+    distance_m_opt: Option<f32>,
+) -> Option<HeightGrid> {
+    let distance_m = distance_m_opt.unwrap_or(15000.0);
+
+    get_height_data_in_bounds_from(
+        source,
+        get_bounds_around_point(latitude, longitude, distance_m),
+    )
+}
 
-    let lower_longitude = longitude - distance_degree_lon;
-    let upper_longitude = longitude + distance_degree_lon;
+/// Height grid of the area within `(lower_latitude, upper_latitude,
+/// lower_longitude, upper_longitude)`, with square cells at the latitude of
+/// its center. `None` if no tile of `source` covers the area.
+pub fn get_height_data_in_bounds_from(
+    source: DataSource,
+    bounds: (f32, f32, f32, f32),
+) -> Option<HeightGrid> {
+    let (lower_latitude, upper_latitude, lower_longitude, upper_longitude) = bounds;
+    let latitude = (lower_latitude + upper_latitude) / 2.0;
 
     let lower_lat_i = lower_latitude.floor() as i32;
     let upper_lat_i = upper_latitude.floor() as i32;
@@ -211,10 +971,16 @@ pub fn get_height_data_around_point(
     let n_lat = upper_lat_i - lower_lat_i + 1;
     let n_lon = upper_lon_i - lower_lon_i + 1;
 
-    let arr_0 = load_hgt(lower_lat_i, lower_lon_i);
-    let shape = arr_0.shape()[0];
+    let tiles = (lower_lat_i..upper_lat_i + 1)
+        .flat_map(|lat_i| (lower_lon_i..upper_lon_i + 1).map(move |lon_i| (lat_i, lon_i)))
+        .filter_map(|(lat_i, lon_i)| Some(((lat_i, lon_i), tile_side(source, lat_i, lon_i)?)))
+        .collect::<HashMap<_, _>>();
+    // Tiles with a lower resolution are resampled to the finest one.
+    let shape = tiles.values().copied().max()?;
 
     let mut arr = Array2::zeros(((n_lat as usize) * shape, (n_lon as usize) * shape));
+    // Only allocated once a cell turns out not to be measured.
+    let mut quality: Option<Array2<DataQuality>> = None;
 
     for lat_i in lower_lat_i..upper_lat_i + 1 {
         for lon_i in lower_lon_i..upper_lon_i + 1 {
@@ -222,13 +988,34 @@ pub fn get_height_data_around_point(
             let lat_ix = (lat_i - lower_lat_i) as usize;
             let lon_ix = (lon_i - lower_lon_i) as usize;
 
-            let mut sub_slice = arr.slice_mut(s![
+            let tile_slice = s![
                 lat_ix * shape..(lat_ix + 1) * shape;-1,
                 lon_ix * shape..(lon_ix + 1) * shape
-            ]);
+            ];
 
-            let data = load_hgt(lat_i, lon_i);
-            sub_slice.assign(&data);
+            // Missing tiles are usually sea, so they keep a height of 0.
+            let tile = tiles
+                .contains_key(&(lat_i, lon_i))
+                .then(|| load_tile(source, lat_i, lon_i).resample(shape));
+            if let Some(tile) = tile.as_ref() {
+                arr.slice_mut(tile_slice).assign(&tile.heights);
+                if tile.void_filled.is_empty() {
+                    continue;
+                }
+            }
+
+            let mut quality_slice = quality
+                .get_or_insert_with(|| Array2::from_elem(arr.raw_dim(), DataQuality::Measured))
+                .slice_mut(tile_slice);
+            match tile {
+                Some(tile) => {
+                    for ix in tile.void_filled {
+                        let ix = ix as usize;
+                        quality_slice[(ix / shape, ix % shape)] = DataQuality::VoidFilled;
+                    }
+                }
+                None => quality_slice.fill(DataQuality::Synthesized),
+            }
         }
     }
     let degree_per_lat_ix = i32_f32((upper_lat_i + 1) - lower_lat_i) / usize_f32(arr.shape()[0]);
@@ -244,7 +1031,8 @@ pub fn get_height_data_around_point(
     let upper_lon_ix =
         f32_usize(((upper_longitude - i32_f32(lower_lon_i)) / degree_per_lon_ix).trunc());
 
-    let result_arr = arr.slice(s![lower_lat_ix..upper_lat_ix, lower_lon_ix..upper_lon_ix]);
+    let result_slice = s![lower_lat_ix..upper_lat_ix, lower_lon_ix..upper_lon_ix];
+    let result_arr = arr.slice(result_slice);
 
     let lat_resolution_degree =
         (upper_latitude - lower_latitude) / usize_f32(result_arr.shape()[0]);
@@ -258,21 +1046,22 @@ pub fn get_height_data_around_point(
 
     let max_resolution = f32::max(lat_resolution_meters, lon_resolution_meters);
 
-    let final_grid = scale_2d_array(
-        &result_arr,
-        (
-            lat_resolution_meters / max_resolution,
-            lon_resolution_meters / max_resolution,
-        ),
+    let scales = (
+        lat_resolution_meters / max_resolution,
+        lon_resolution_meters / max_resolution,
     );
+    let final_grid = scale_2d_array(&result_arr, scales);
+    let data_quality = quality.map(|quality| scale_2d_array(&quality.slice(result_slice), scales));
 
-    HeightGrid {
+    Some(HeightGrid {
         heights: final_grid,
         cell_size: max_resolution,
         min_cell_size: max_resolution,
         latitudes: (lower_latitude, upper_latitude),
         longitudes: (lower_longitude, upper_longitude),
-    }
+        data_source: source,
+        data_quality,
+    })
 }
 
 #[cfg(test)]
@@ -1,12 +1,17 @@
 use approx::assert_relative_eq;
+use ndarray::Array2;
 
 use crate::height_data::get_height_at_point;
 
-use super::{get_height_data_around_point, load_hgt};
+use super::{
+    get_coarse_file_name, get_file_name, get_height_data_around_point, interpolate_2d_array,
+    load_hgt, missing_tiles, offset_point, point_distance, read_hgt_file, scale_heights,
+    snap_cell_size, DataQuality, DataSource, HeightGrid, Interpolation, Tile,
+};
 
 #[test]
 fn test_load_hgt() {
-    let data = load_hgt(47, 11);
+    let data = load_hgt(47, 11).value.heights;
 
     assert_eq!(data.get((22, 35)).unwrap().clone(), 644i16);
     assert_eq!(data.get((1065, 2354)).unwrap().clone(), 1067i16);
@@ -25,7 +30,7 @@ fn test_height_data_at_point() {
 
 #[test]
 fn test_get_height_data_around_point() {
-    let height_grid = get_height_data_around_point(47.05, 11.05, None);
+    let height_grid = get_height_data_around_point(47.05, 11.05, None).unwrap();
 
     assert_eq!(height_grid.heights.shape()[0], 973);
     assert_eq!(height_grid.heights.shape()[1], 973);
@@ -44,3 +49,204 @@ fn test_get_height_data_around_point() {
     assert_eq!(height_grid.heights.get((15, 956)).unwrap().clone(), 2131);
     assert_eq!(height_grid.heights.get((970, 967)).unwrap().clone(), 2085);
 }
+
+#[test]
+fn test_file_names() {
+    assert_eq!(get_file_name(47, 11), "./data/N47E011.hgt");
+    assert_eq!(get_file_name(-3, -72), "./data/S03W072.hgt");
+    assert_eq!(get_coarse_file_name(47, 11), "./data/coarse/N47E011.hgt");
+}
+
+#[test]
+fn test_missing_tiles() {
+    // There is no DEM data this far south.
+    assert_eq!(
+        missing_tiles((-89.5, -88.5), (10.5, 11.5)),
+        vec![(-90, 10), (-90, 11), (-89, 10), (-89, 11)]
+    );
+}
+
+#[test]
+fn test_scale_data_quality() {
+    let mut data_quality = Array2::from_elem((4, 4), DataQuality::Measured);
+    data_quality[[0, 0]] = DataQuality::VoidFilled;
+    data_quality[[2, 2]] = DataQuality::Synthesized;
+    let grid = HeightGrid {
+        heights: Array2::zeros((4, 4)),
+        cell_size: 30.0,
+        min_cell_size: 30.0,
+        latitudes: (47.0, 47.1),
+        longitudes: (11.0, 11.1),
+        data_source: DataSource::Srtm,
+        data_quality: Some(data_quality),
+    };
+
+    let scaled = grid.scale(0.5);
+    assert_eq!(scaled.heights.shape(), &[2, 2]);
+    assert_eq!(scaled.data_quality_at((0, 0)), DataQuality::VoidFilled);
+    assert_eq!(scaled.data_quality_at((0, 1)), DataQuality::Measured);
+    assert_eq!(scaled.data_quality_at((1, 1)), DataQuality::Synthesized);
+}
+
+#[test]
+fn test_snap_cell_size() {
+    assert_eq!(snap_cell_size(100.0, 30.0), 90.0);
+    assert_eq!(snap_cell_size(110.0, 30.0), 120.0);
+    assert_eq!(snap_cell_size(10.0, 30.0), 30.0);
+    assert_eq!(snap_cell_size(30.0, 30.0), 30.0);
+}
+
+#[test]
+fn test_decimate() {
+    // Each row is higher than the one before by 10 m.
+    let grid = HeightGrid {
+        heights: Array2::from_shape_fn((7, 7), |(row, _)| row as i16 * 10),
+        cell_size: 30.0,
+        min_cell_size: 30.0,
+        latitudes: (47.0, 47.7),
+        longitudes: (11.0, 11.7),
+        data_source: DataSource::Srtm,
+        data_quality: None,
+    };
+
+    let decimated = grid.scale(1.0 / 3.0);
+    assert_eq!(decimated.heights.shape(), &[3, 3]);
+    assert_eq!(decimated.cell_size, 90.0);
+    // Cells keep their position, so the bounds grow to 9 of the old cells.
+    assert_relative_eq!(decimated.latitudes.1, 47.9, max_relative = 1e-5);
+    assert_relative_eq!(decimated.longitudes.1, 11.9, max_relative = 1e-5);
+    // Means of the rows around every third row, clipped at the border.
+    assert_eq!(decimated.heights.column(0).to_vec(), vec![5, 30, 55]);
+}
+
+#[test]
+fn test_read_hgt_void_filled() {
+    let path = std::env::temp_dir().join(format!("void_{}.hgt", std::process::id()));
+    let values: [i16; 9] = [100, 200, 300, 400, -32768, 600, 700, 800, 900];
+    let bytes = values
+        .iter()
+        .flat_map(|v| v.to_be_bytes())
+        .collect::<Vec<_>>();
+    std::fs::write(&path, bytes).unwrap();
+
+    let tile = read_hgt_file(path.to_str().unwrap());
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(tile.heights[[1, 1]], 400);
+    assert_eq!(tile.void_filled, vec![4]);
+}
+
+#[test]
+fn test_offset_point() {
+    let (lat, lon) = offset_point(47.0, 11.0, 1852.0, 0.0);
+    assert_relative_eq!(lat, 47.0 + 1.0 / 60.0, epsilon = 1e-5);
+    assert_relative_eq!(lon, 11.0);
+
+    let (lat, lon) = offset_point(60.0, 11.0, 0.0, 926.0);
+    assert_relative_eq!(lat, 60.0);
+    assert_relative_eq!(lon, 11.0 + 1.0 / 60.0, epsilon = 1e-4);
+}
+
+#[test]
+fn test_point_distance() {
+    let (lat, lon) = offset_point(47.0, 11.0, 3000.0, 4000.0);
+    assert_relative_eq!(point_distance(47.0, 11.0, lat, lon), 5000.0, epsilon = 5.0);
+    assert_eq!(point_distance(47.0, 11.0, 47.0, 11.0), 0.0);
+}
+
+#[test]
+fn test_index_of() {
+    let grid = HeightGrid {
+        heights: Array2::zeros((10, 20)),
+        cell_size: 100.0,
+        min_cell_size: 100.0,
+        latitudes: (47.0, 47.1),
+        longitudes: (11.0, 11.2),
+        data_source: DataSource::Srtm,
+        data_quality: None,
+    };
+    assert_eq!(grid.index_of(47.0, 11.0), Some((0, 0)));
+    assert_eq!(grid.index_of(47.052, 11.031), Some((5, 3)));
+    assert_eq!(grid.index_of(46.9, 11.0), None);
+    assert_eq!(grid.index_of(47.0, 11.2), None);
+}
+
+#[test]
+fn test_raise_by_vertical_error() {
+    let mut data_quality = Array2::from_elem((2, 2), DataQuality::Measured);
+    data_quality[[1, 0]] = DataQuality::VoidFilled;
+    let mut grid = HeightGrid {
+        heights: Array2::from_elem((2, 2), 1000),
+        cell_size: 30.0,
+        min_cell_size: 30.0,
+        latitudes: (47.0, 47.1),
+        longitudes: (11.0, 11.1),
+        data_source: DataSource::Srtm,
+        data_quality: Some(data_quality),
+    };
+    grid.heights[[1, 1]] = i16::MAX;
+
+    grid.raise_by_vertical_error();
+    assert_eq!(grid.heights[[0, 0]], 1016);
+    assert_eq!(grid.heights[[1, 0]], 1050);
+    assert_eq!(grid.heights[[1, 1]], i16::MAX);
+
+    grid.data_source = DataSource::Synthetic;
+    grid.data_quality = None;
+    assert_eq!(grid.vertical_error_at((0, 0)), 0.0);
+}
+
+#[test]
+fn test_interpolate_2d_array() {
+    let values = Array2::from_shape_vec((2, 2), vec![100, 200, 300, 500]).unwrap();
+    let at =
+        |row, col, interpolation| interpolate_2d_array(&values.view(), row, col, interpolation);
+
+    assert_relative_eq!(at(0.0, 0.0, Interpolation::Bilinear), 100.0);
+    assert_relative_eq!(at(0.0, 0.5, Interpolation::Bilinear), 150.0);
+    assert_relative_eq!(at(0.5, 0.5, Interpolation::Bilinear), 275.0);
+    assert_relative_eq!(at(1.0, 0.25, Interpolation::Bilinear), 350.0);
+    assert_relative_eq!(at(0.4, 0.6, Interpolation::Nearest), 200.0);
+    // Clamped to the border.
+    assert_relative_eq!(at(2.0, -1.0, Interpolation::Bilinear), 300.0);
+}
+
+#[test]
+fn test_scale_heights() {
+    let values = Array2::from_shape_fn((5, 5), |(row, col)| (row * 100 + col * 10) as i16);
+
+    let nearest = scale_heights(&values.view(), (0.6, 0.6), Interpolation::Nearest);
+    let bilinear = scale_heights(&values.view(), (0.6, 0.6), Interpolation::Bilinear);
+    assert_eq!(nearest.shape(), &[3, 3]);
+    assert_eq!(bilinear.shape(), &[3, 3]);
+    assert_eq!(bilinear[(0, 0)], 0);
+    assert_eq!(bilinear[(2, 2)], 440);
+    // A plane is sampled exactly, without the steps of nearest neighbours.
+    assert_eq!(bilinear[(1, 1)], 220);
+    assert_eq!(bilinear[(1, 0)], 200);
+
+    let values = Array2::from_shape_vec((1, 4), vec![0, 10, 20, 90]).unwrap();
+    let bilinear = scale_heights(&values.view(), (1.0, 0.75), Interpolation::Bilinear);
+    assert_eq!(bilinear.as_slice().unwrap(), &[0, 15, 90]);
+}
+
+#[test]
+fn test_resample_tile() {
+    let tile = Tile {
+        heights: Array2::from_shape_vec((3, 3), vec![0, 100, 200, 100, 200, 300, 200, 300, 400])
+            .unwrap(),
+        void_filled: vec![8],
+    };
+
+    let resampled = tile.resample(5);
+    assert_eq!(resampled.heights.shape(), &[5, 5]);
+    assert_eq!(resampled.heights[(0, 0)], 0);
+    assert_eq!(resampled.heights[(0, 1)], 50);
+    assert_eq!(resampled.heights[(2, 2)], 200);
+    assert_eq!(resampled.heights[(3, 4)], 350);
+    assert_eq!(resampled.heights[(4, 4)], 400);
+    // Points closest to the void filled corner.
+    assert_eq!(resampled.void_filled, vec![18, 19, 23, 24]);
+
+    assert_eq!(tile.resample(3).heights, tile.heights);
+}
@@ -0,0 +1,341 @@
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use crate::height_data::DataSource;
+
+pub const MANIFEST_FILE_NAME: &str = "hgt_manifest.txt";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ManifestEntry {
+    /// Path of the tile relative to the data directory, e.g. `N47E011.hgt`.
+    pub file: String,
+    pub size: u64,
+    pub checksum: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TileProblem {
+    Missing {
+        file: String,
+    },
+    Unreadable {
+        file: String,
+        error: String,
+    },
+    BadShape {
+        file: String,
+        size: u64,
+    },
+    SizeMismatch {
+        file: String,
+        expected: u64,
+        actual: u64,
+    },
+    ChecksumMismatch {
+        file: String,
+        expected: u32,
+        actual: u32,
+    },
+}
+
+impl TileProblem {
+    pub fn file(&self) -> &str {
+        match self {
+            TileProblem::Missing { file }
+            | TileProblem::Unreadable { file, .. }
+            | TileProblem::BadShape { file, .. }
+            | TileProblem::SizeMismatch { file, .. }
+            | TileProblem::ChecksumMismatch { file, .. } => file,
+        }
+    }
+}
+
+/// HGT files are square grids of big endian i16 values.
+pub fn is_valid_hgt_size(size: u64) -> bool {
+    if size == 0 || !size.is_multiple_of(2) {
+        return false;
+    }
+    let n_entries = size / 2;
+    let shape = (n_entries as f64).sqrt().round() as u64;
+    shape * shape == n_entries
+}
+
+pub fn compute_checksum(path: &Path) -> std::io::Result<u32> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buffer = vec![0u8; 1 << 16];
+    loop {
+        let n = reader.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+    }
+    Ok(hasher.finalize())
+}
+
+pub fn compute_entry(data_dir: &Path, file: &str) -> std::io::Result<ManifestEntry> {
+    let path = data_dir.join(file);
+    Ok(ManifestEntry {
+        file: file.to_string(),
+        size: fs::metadata(&path)?.len(),
+        checksum: compute_checksum(&path)?,
+    })
+}
+
+/// Lists all `.hgt` files in the data directory and its `coarse` subdirectory,
+/// relative to the data directory and sorted by name.
+pub fn list_tiles(data_dir: &Path) -> std::io::Result<Vec<String>> {
+    let mut result = vec![];
+    for sub_dir in ["", "coarse"] {
+        let dir = data_dir.join(sub_dir);
+        if !dir.is_dir() {
+            continue;
+        }
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|s| s.to_str()) == Some("hgt") {
+                if let Some(name) = path.file_name().and_then(|s| s.to_str()) {
+                    if sub_dir.is_empty() {
+                        result.push(name.to_string());
+                    } else {
+                        result.push(format!("{sub_dir}/{name}"));
+                    }
+                }
+            }
+        }
+    }
+    result.sort();
+    Ok(result)
+}
+
+/// Formats the manifest with one `<crc32> <size> <file>` line per tile.
+pub fn format_manifest(entries: &[ManifestEntry]) -> String {
+    entries
+        .iter()
+        .map(|e| format!("{:08x} {} {}\n", e.checksum, e.size, e.file))
+        .collect()
+}
+
+pub fn parse_manifest(content: &str) -> Result<Vec<ManifestEntry>, String> {
+    let mut result = vec![];
+    for (line_number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut parts = line.splitn(3, ' ');
+        let (Some(checksum), Some(size), Some(file)) = (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(format!("Line {}: expected 3 fields", line_number + 1));
+        };
+        result.push(ManifestEntry {
+            file: file.to_string(),
+            size: size
+                .parse()
+                .map_err(|e| format!("Line {}: bad size: {e}", line_number + 1))?,
+            checksum: u32::from_str_radix(checksum, 16)
+                .map_err(|e| format!("Line {}: bad checksum: {e}", line_number + 1))?,
+        });
+    }
+    Ok(result)
+}
+
+pub fn load_manifest(data_dir: &Path) -> Option<Result<Vec<ManifestEntry>, String>> {
+    let content = fs::read_to_string(data_dir.join(MANIFEST_FILE_NAME)).ok()?;
+    Some(parse_manifest(&content))
+}
+
+/// Version of the DEM data a server is running with.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct DemVersion {
+    pub dataset: String,
+    pub release_date: String,
+    pub license: String,
+    pub attribution: String,
+    /// Short fingerprint of the tiles, changing whenever a tile is added,
+    /// removed or replaced.
+    pub checksum_epoch: String,
+}
+
+impl std::fmt::Display for DemVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}@{}#{}",
+            self.dataset, self.release_date, self.checksum_epoch
+        )
+    }
+}
+
+/// Header lines naming the dataset and its terms of use, read back by
+/// `dem_version`.
+pub fn format_manifest_header(
+    dataset: &str,
+    release_date: &str,
+    license: &str,
+    attribution: &str,
+) -> String {
+    format!(
+        "# dataset: {dataset}\n# release_date: {release_date}\n# license: {license}\n\
+         # attribution: {attribution}\n"
+    )
+}
+
+fn manifest_header_value(content: &str, name: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let value = line.trim().strip_prefix('#')?.trim().strip_prefix(name)?;
+        Some(value.strip_prefix(':')?.trim().to_string())
+    })
+}
+
+/// Determines the DEM version from the manifest if there is one, otherwise
+/// from the tile names and sizes only.
+pub fn dem_version(data_dir: &Path) -> DemVersion {
+    let content = fs::read_to_string(data_dir.join(MANIFEST_FILE_NAME)).ok();
+    let entries = match content.as_deref().map(parse_manifest) {
+        Some(Ok(entries)) => entries,
+        _ => list_tiles(data_dir)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|file| ManifestEntry {
+                size: fs::metadata(data_dir.join(&file))
+                    .map(|m| m.len())
+                    .unwrap_or(0),
+                file,
+                checksum: 0,
+            })
+            .collect(),
+    };
+    let header = |name| {
+        content
+            .as_deref()
+            .and_then(|c| manifest_header_value(c, name))
+    };
+
+    DemVersion {
+        dataset: header("dataset").unwrap_or(DataSource::Srtm.dataset().to_string()),
+        release_date: header("release_date").unwrap_or("unknown".to_string()),
+        license: header("license").unwrap_or(DataSource::Srtm.license().to_string()),
+        attribution: header("attribution").unwrap_or(DataSource::Srtm.attribution().to_string()),
+        checksum_epoch: format!(
+            "{:08x}",
+            crc32fast::hash(format_manifest(&entries).as_bytes())
+        ),
+    }
+}
+
+/// Origin and terms of use of the heights of a search, embedded in exports
+/// since derived products have to credit the DEM.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Provenance {
+    pub source: DataSource,
+    pub dataset: String,
+    pub release_date: String,
+    pub license: String,
+    pub attribution: String,
+}
+
+/// Provenance of heights from `source`. The manifest in `dem` only describes
+/// the full resolution tiles, the other sources have their defaults.
+pub fn provenance(source: DataSource, dem: &DemVersion) -> Provenance {
+    match source {
+        DataSource::Srtm => Provenance {
+            source,
+            dataset: dem.dataset.clone(),
+            release_date: dem.release_date.clone(),
+            license: dem.license.clone(),
+            attribution: dem.attribution.clone(),
+        },
+        _ => Provenance {
+            source,
+            dataset: source.dataset().to_string(),
+            release_date: "unknown".to_string(),
+            license: source.license().to_string(),
+            attribution: source.attribution().to_string(),
+        },
+    }
+}
+
+/// Checks the tiles listed in the manifest. Sizes are always compared, the
+/// (slow) checksums only if `check_checksums` is set.
+pub fn verify_tiles(
+    data_dir: &Path,
+    manifest: &[ManifestEntry],
+    check_checksums: bool,
+) -> Vec<TileProblem> {
+    let mut problems = vec![];
+    for entry in manifest {
+        let path = data_dir.join(&entry.file);
+        let size = match fs::metadata(&path) {
+            Ok(m) => m.len(),
+            Err(_) => {
+                problems.push(TileProblem::Missing {
+                    file: entry.file.clone(),
+                });
+                continue;
+            }
+        };
+        if size != entry.size {
+            problems.push(TileProblem::SizeMismatch {
+                file: entry.file.clone(),
+                expected: entry.size,
+                actual: size,
+            });
+            continue;
+        }
+        if check_checksums {
+            match compute_checksum(&path) {
+                Ok(checksum) if checksum != entry.checksum => {
+                    problems.push(TileProblem::ChecksumMismatch {
+                        file: entry.file.clone(),
+                        expected: entry.checksum,
+                        actual: checksum,
+                    });
+                }
+                Ok(_) => {}
+                Err(e) => problems.push(TileProblem::Unreadable {
+                    file: entry.file.clone(),
+                    error: e.to_string(),
+                }),
+            }
+        }
+    }
+    problems
+}
+
+/// Checks that all tiles in the data directory have a plausible size. Works
+/// without a manifest and catches truncated downloads.
+pub fn verify_tile_shapes(data_dir: &Path) -> Vec<TileProblem> {
+    let tiles = match list_tiles(data_dir) {
+        Ok(tiles) => tiles,
+        Err(e) => {
+            return vec![TileProblem::Unreadable {
+                file: data_dir.to_string_lossy().to_string(),
+                error: e.to_string(),
+            }]
+        }
+    };
+
+    tiles
+        .into_iter()
+        .filter_map(|file| match fs::metadata(data_dir.join(&file)) {
+            Ok(m) if !is_valid_hgt_size(m.len()) => Some(TileProblem::BadShape {
+                file,
+                size: m.len(),
+            }),
+            Ok(_) => None,
+            Err(e) => Some(TileProblem::Unreadable {
+                file,
+                error: e.to_string(),
+            }),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+#[path = "./hgt_manifest_test.rs"]
+mod hgt_manifest_test;
@@ -0,0 +1,141 @@
+use std::fs;
+use std::path::PathBuf;
+
+use crate::height_data::DataSource;
+
+use super::{
+    compute_entry, dem_version, format_manifest, format_manifest_header, is_valid_hgt_size,
+    list_tiles, parse_manifest, provenance, verify_tile_shapes, verify_tiles, DemVersion,
+    ManifestEntry, TileProblem, MANIFEST_FILE_NAME,
+};
+
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("hgt_manifest_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("coarse")).unwrap();
+    dir
+}
+
+#[test]
+fn test_is_valid_hgt_size() {
+    assert!(is_valid_hgt_size(3601 * 3601 * 2));
+    assert!(is_valid_hgt_size(1201 * 1201 * 2));
+    assert!(!is_valid_hgt_size(3601 * 3601 * 2 - 2));
+    assert!(!is_valid_hgt_size(7));
+    assert!(!is_valid_hgt_size(0));
+}
+
+#[test]
+fn test_manifest_roundtrip() {
+    let entries = vec![
+        ManifestEntry {
+            file: "N47E011.hgt".to_string(),
+            size: 25934402,
+            checksum: 0xdeadbeef,
+        },
+        ManifestEntry {
+            file: "coarse/N47E011.hgt".to_string(),
+            size: 116162,
+            checksum: 0x12,
+        },
+    ];
+
+    let parsed = parse_manifest(&format_manifest(&entries)).unwrap();
+    assert_eq!(parsed, entries);
+
+    assert!(parse_manifest("abc 12").is_err());
+    assert!(parse_manifest("xyz 12 N47E011.hgt").is_err());
+}
+
+#[test]
+fn test_verify_tiles() {
+    let dir = test_dir("verify");
+    fs::write(dir.join("N47E011.hgt"), [0u8, 1, 0, 2, 0, 3, 0, 4]).unwrap();
+    fs::write(dir.join("coarse/N47E011.hgt"), [0u8, 1]).unwrap();
+
+    let tiles = list_tiles(&dir).unwrap();
+    assert_eq!(tiles, vec!["N47E011.hgt", "coarse/N47E011.hgt"]);
+
+    let manifest = tiles
+        .iter()
+        .map(|t| compute_entry(&dir, t).unwrap())
+        .collect::<Vec<_>>();
+    assert!(verify_tiles(&dir, &manifest, true).is_empty());
+
+    fs::write(dir.join("N47E011.hgt"), [0u8, 1, 0, 2, 0, 3, 0, 5]).unwrap();
+    fs::remove_file(dir.join("coarse/N47E011.hgt")).unwrap();
+
+    let problems = verify_tiles(&dir, &manifest, true);
+    assert_eq!(problems.len(), 2);
+    assert!(matches!(problems[0], TileProblem::ChecksumMismatch { .. }));
+    assert!(matches!(problems[1], TileProblem::Missing { .. }));
+
+    // Sizes still match, so the corruption is only found with checksums.
+    assert_eq!(verify_tiles(&dir, &manifest, false).len(), 1);
+
+    fs::write(dir.join("S01W001.hgt"), [0u8, 1, 0]).unwrap();
+    assert_eq!(
+        verify_tile_shapes(&dir),
+        vec![TileProblem::BadShape {
+            file: "S01W001.hgt".to_string(),
+            size: 3
+        }]
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_dem_version() {
+    let dir = test_dir("dem_version");
+    fs::write(dir.join("N47E011.hgt"), vec![0u8; 8]).unwrap();
+    let before = dem_version(&dir);
+    assert_eq!(before, dem_version(&dir));
+    assert_eq!(before.dataset, "SRTM");
+    assert_eq!(before.release_date, "unknown");
+
+    fs::write(dir.join("coarse/N40E000.hgt"), vec![0u8; 8]).unwrap();
+    let after = dem_version(&dir);
+    assert_ne!(before.checksum_epoch, after.checksum_epoch);
+
+    let entries = vec![compute_entry(&dir, "N47E011.hgt").unwrap()];
+    fs::write(
+        dir.join(MANIFEST_FILE_NAME),
+        format_manifest_header("SRTMGL1 v3", "2015-09-23", "Public domain", "NASA JPL")
+            + format_manifest(&entries).as_str(),
+    )
+    .unwrap();
+    let with_manifest = dem_version(&dir);
+    assert_ne!(after.checksum_epoch, with_manifest.checksum_epoch);
+    assert_eq!(with_manifest.dataset, "SRTMGL1 v3");
+    assert_eq!(with_manifest.release_date, "2015-09-23");
+    assert_eq!(with_manifest.attribution, "NASA JPL");
+    assert_eq!(
+        with_manifest.to_string(),
+        format!("SRTMGL1 v3@2015-09-23#{}", with_manifest.checksum_epoch)
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_provenance() {
+    let dem = DemVersion {
+        dataset: "SRTMGL1 v3".to_string(),
+        release_date: "2015-09-23".to_string(),
+        license: "Public domain".to_string(),
+        attribution: "NASA JPL".to_string(),
+        checksum_epoch: "abc".to_string(),
+    };
+
+    let srtm = provenance(DataSource::Srtm, &dem);
+    assert_eq!(srtm.dataset, "SRTMGL1 v3");
+    assert_eq!(srtm.release_date, "2015-09-23");
+    assert_eq!(srtm.attribution, "NASA JPL");
+
+    // The manifest only describes the SRTM tiles.
+    let coarse = provenance(DataSource::Coarse, &dem);
+    assert_eq!(coarse.dataset, DataSource::Coarse.dataset());
+    assert_eq!(coarse.license, DataSource::Coarse.license());
+    assert_eq!(coarse.release_date, "unknown");
+}
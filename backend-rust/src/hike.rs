@@ -0,0 +1,124 @@
+use ndarray::Array2;
+use serde::Serialize;
+
+use crate::{
+    contours::contour_lines,
+    geojson::{Feature, FeatureCollection, Geometry},
+    height_data::HeightGrid,
+    pqueue::PriorityQueue,
+};
+
+/// Longest hike of an isochrone search in hours.
+pub const MAX_HIKE_HOURS: f32 = 8.0;
+/// Fastest speed of Tobler's hiking function in km/h, slightly downhill.
+pub const MAX_HIKE_SPEED_KMH: f32 = 6.0;
+
+/// Hiking speed in km/h on a slope of `gradient`, the rise per meter in the
+/// direction of travel, after Tobler's hiking function.
+pub fn tobler_speed(gradient: f32) -> f32 {
+    MAX_HIKE_SPEED_KMH * (-3.5 * (gradient + 0.05).abs()).exp()
+}
+
+/// Hours to reach each cell of `grid` on foot from `start`, over the eight
+/// neighbours of every cell at the speed of `tobler_speed`. Cells that take
+/// longer than `max_hours` are infinite.
+pub fn hiking_times(grid: &HeightGrid, start: (usize, usize), max_hours: f32) -> Array2<f32> {
+    let (rows, cols) = (grid.heights.shape()[0], grid.heights.shape()[1]);
+    let mut times = Array2::from_elem((rows, cols), f32::INFINITY);
+    let mut done = Array2::from_elem((rows, cols), false);
+    let mut queue = PriorityQueue::<f32, (usize, usize)>::new();
+    times[start] = 0.0;
+    queue.push(start, 0.0);
+
+    while let Some(current) = queue.pop() {
+        let (row, col) = current.key;
+        done[(row, col)] = true;
+        let height = grid.heights[(row, col)] as f32;
+        for (d_row, d_col) in [
+            (-1, -1),
+            (-1, 0),
+            (-1, 1),
+            (0, -1),
+            (0, 1),
+            (1, -1),
+            (1, 0),
+            (1, 1),
+        ] {
+            let neighbor = (
+                row.wrapping_add_signed(d_row),
+                col.wrapping_add_signed(d_col),
+            );
+            if neighbor.0 >= rows || neighbor.1 >= cols || done[neighbor] {
+                continue;
+            }
+            let distance = ((d_row * d_row + d_col * d_col) as f32).sqrt() * grid.cell_size;
+            let gradient = (grid.heights[neighbor] as f32 - height) / distance;
+            let time = current.priority + distance / 1000.0 / tobler_speed(gradient);
+            if time > max_hours || time >= times[neighbor] {
+                continue;
+            }
+            if queue.contains_key(&neighbor) {
+                queue.update_priority_if_less(neighbor, time);
+            } else {
+                queue.push(neighbor, time);
+            }
+            times[neighbor] = time;
+        }
+    }
+    times
+}
+
+/// Properties of an isochrone.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct IsochroneProperties {
+    /// Hiking time in hours to the line.
+    pub hours: f32,
+}
+
+/// Lines around the area reachable on foot within every `step` hours up to
+/// `max_hours`, one feature per time. `times` are the hours of
+/// `hiking_times` on `grid`.
+pub fn hike_isochrones(
+    times: &Array2<f32>,
+    grid: &HeightGrid,
+    max_hours: f32,
+    step: f32,
+) -> FeatureCollection<IsochroneProperties> {
+    // Remaining hours, so the lines close around the reachable area.
+    let remaining = times.mapv(|time| {
+        if time.is_finite() {
+            max_hours - time
+        } else {
+            -1.0
+        }
+    });
+    let position = |(row, col): (f32, f32)| {
+        let (lat, lon) = grid.coordinates_at(row, col);
+        [lon, lat]
+    };
+
+    let count = if step > 0.0 {
+        (max_hours / step).floor() as usize
+    } else {
+        0
+    };
+    FeatureCollection {
+        features: (1..=count)
+            .map(|i| i as f32 * step)
+            .map(|hours| Feature {
+                geometry: Geometry::MultiLineString {
+                    coordinates: contour_lines(&remaining, max_hours - hours)
+                        .into_iter()
+                        .map(|line| line.into_iter().map(position).collect())
+                        .collect(),
+                },
+                properties: IsochroneProperties { hours },
+            })
+            .collect(),
+        metadata: None,
+    }
+}
+
+#[cfg(test)]
+#[path = "./hike_test.rs"]
+mod hike_test;
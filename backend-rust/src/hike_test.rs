@@ -0,0 +1,65 @@
+use approx::assert_relative_eq;
+use ndarray::Array2;
+
+use crate::{geojson::Geometry, height_data::HeightGrid};
+
+use super::{hike_isochrones, hiking_times, tobler_speed, MAX_HIKE_SPEED_KMH};
+
+fn grid(heights: Array2<i16>) -> HeightGrid {
+    let shape = heights.shape().to_vec();
+    HeightGrid::for_test(
+        heights,
+        (47.0, 47.0 + 0.0009 * shape[0] as f32),
+        (11.0, 11.0 + 0.0013 * shape[1] as f32),
+    )
+}
+
+#[test]
+fn test_tobler_speed() {
+    assert_relative_eq!(tobler_speed(-0.05), MAX_HIKE_SPEED_KMH);
+    assert_relative_eq!(tobler_speed(0.0), 5.036, epsilon = 0.001);
+    // Steep uphill is much slower than the same slope downhill.
+    assert!(tobler_speed(0.3) < tobler_speed(-0.3));
+}
+
+#[test]
+fn test_hiking_times() {
+    let flat = grid(Array2::zeros((4, 11)));
+    let times = hiking_times(&flat, (0, 0), 1.0);
+
+    assert_eq!(times[(0, 0)], 0.0);
+    assert_relative_eq!(times[(0, 10)], 1.0 / tobler_speed(0.0), epsilon = 1e-4);
+    let diagonal = 3.0 * 2f32.sqrt() * 0.1;
+    assert_relative_eq!(times[(3, 3)], diagonal / tobler_speed(0.0), epsilon = 1e-4);
+
+    // Up a 30% slope takes longer than down it.
+    let slope = grid(Array2::from_shape_fn((1, 5), |(_, col)| col as i16 * 30));
+    let up = hiking_times(&slope, (0, 0), 1.0)[(0, 4)];
+    let down = hiking_times(&slope, (0, 4), 1.0)[(0, 0)];
+    assert!(up > down);
+
+    // Cells further than the hiking time are not reached.
+    let times = hiking_times(&flat, (0, 0), 0.1);
+    assert!(times[(0, 10)].is_infinite());
+    assert!(times[(0, 4)].is_finite());
+}
+
+#[test]
+fn test_hike_isochrones() {
+    let flat = grid(Array2::zeros((31, 31)));
+    let times = hiking_times(&flat, (15, 15), 0.2);
+
+    let isochrones = hike_isochrones(&times, &flat, 0.2, 0.1);
+
+    assert_eq!(isochrones.features.len(), 2);
+    assert_relative_eq!(isochrones.features[0].properties.hours, 0.1);
+    assert_relative_eq!(isochrones.features[1].properties.hours, 0.2);
+    for feature in &isochrones.features {
+        let Geometry::MultiLineString { coordinates } = &feature.geometry else {
+            panic!("Isochrones are lines");
+        };
+        assert_eq!(coordinates.len(), 1);
+        // Closed around the start.
+        assert_eq!(coordinates[0].first(), coordinates[0].last());
+    }
+}
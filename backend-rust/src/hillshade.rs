@@ -0,0 +1,78 @@
+use ndarray::Array2;
+
+use crate::height_data::HeightGrid;
+
+/// Position of the sun lighting the terrain, in degrees.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sun {
+    /// Clockwise from north.
+    pub azimuth: f32,
+    /// Above the horizon.
+    pub elevation: f32,
+}
+
+impl Default for Sun {
+    /// From the north west, as usual for maps, so that valleys look like
+    /// valleys.
+    fn default() -> Self {
+        Sun {
+            azimuth: 315.0,
+            elevation: 45.0,
+        }
+    }
+}
+
+/// Share of the light that also reaches slopes facing away from the sun.
+pub const AMBIENT_LIGHT: f32 = 0.4;
+
+/// Brightness of every cell of the grid lit by `sun`, from `AMBIENT_LIGHT`
+/// for slopes in full shadow to 1 for slopes facing the sun. Slopes are
+/// estimated from the eight neighbours of a cell (Horn's method), at the
+/// border of the grid from the neighbours that exist.
+pub fn hillshade(grid: &HeightGrid, sun: Sun) -> Array2<f32> {
+    let shape = grid.heights.shape();
+    let (rows, cols) = (shape[0], shape[1]);
+    let height = |row: usize, col: usize| grid.heights[(row, col)] as f32;
+
+    let (azimuth, elevation) = (sun.azimuth.to_radians(), sun.elevation.to_radians());
+    // Towards the sun, in east, north and up.
+    let light = (
+        azimuth.sin() * elevation.cos(),
+        azimuth.cos() * elevation.cos(),
+        elevation.sin(),
+    );
+
+    Array2::from_shape_fn((rows, cols), |(row, col)| {
+        let (below, above) = (row.saturating_sub(1), (row + 1).min(rows - 1));
+        let (left, right) = (col.saturating_sub(1), (col + 1).min(cols - 1));
+
+        // Neighbours in the direction of the slope count twice.
+        let east = if left == right {
+            0.0
+        } else {
+            (height(below, right) + 2.0 * height(row, right) + height(above, right)
+                - height(below, left)
+                - 2.0 * height(row, left)
+                - height(above, left))
+                / (4.0 * (right - left) as f32 * grid.cell_size)
+        };
+        // Rows grow northwards.
+        let north = if below == above {
+            0.0
+        } else {
+            (height(above, left) + 2.0 * height(above, col) + height(above, right)
+                - height(below, left)
+                - 2.0 * height(below, col)
+                - height(below, right))
+                / (4.0 * (above - below) as f32 * grid.cell_size)
+        };
+
+        let normal_length = (east * east + north * north + 1.0).sqrt();
+        let lit = (-east * light.0 - north * light.1 + light.2) / normal_length;
+        AMBIENT_LIGHT + (1.0 - AMBIENT_LIGHT) * lit.max(0.0)
+    })
+}
+
+#[cfg(test)]
+#[path = "./hillshade_test.rs"]
+mod hillshade_test;
@@ -0,0 +1,46 @@
+use approx::assert_relative_eq;
+use ndarray::Array2;
+
+use crate::height_data::HeightGrid;
+
+use super::{hillshade, Sun, AMBIENT_LIGHT};
+
+fn grid(heights: Array2<i16>) -> HeightGrid {
+    HeightGrid::for_test(heights, (47.0, 47.01), (11.0, 11.01))
+}
+
+#[test]
+fn test_hillshade_flat() {
+    let shade = hillshade(&grid(Array2::from_elem((4, 4), 1000)), Sun::default());
+    let expected = AMBIENT_LIGHT + (1.0 - AMBIENT_LIGHT) * 45f32.to_radians().sin();
+    for value in shade.iter() {
+        assert_relative_eq!(*value, expected, epsilon = 1e-5);
+    }
+
+    let zenith = Sun {
+        azimuth: 0.0,
+        elevation: 90.0,
+    };
+    let shade = hillshade(&grid(Array2::from_elem((4, 4), 1000)), zenith);
+    assert_relative_eq!(shade[(1, 1)], 1.0, epsilon = 1e-5);
+}
+
+#[test]
+fn test_hillshade_slopes() {
+    // Rises 100 m per cell to the east, so it faces west.
+    let heights = Array2::from_shape_fn((5, 5), |(_, col)| col as i16 * 100);
+    let low_sun = |azimuth| Sun {
+        azimuth,
+        elevation: 10.0,
+    };
+
+    let from_west = hillshade(&grid(heights.clone()), low_sun(270.0));
+    let from_east = hillshade(&grid(heights.clone()), low_sun(90.0));
+    let from_south = hillshade(&grid(heights), low_sun(180.0));
+    assert!(from_west[(2, 2)] > from_south[(2, 2)]);
+    assert!(from_south[(2, 2)] > from_east[(2, 2)]);
+    // A 45 degree slope facing away from a sun 10 degrees high is in shadow.
+    assert_relative_eq!(from_east[(2, 2)], AMBIENT_LIGHT);
+    // At the border the slope continues.
+    assert_relative_eq!(from_west[(0, 0)], from_west[(2, 2)]);
+}
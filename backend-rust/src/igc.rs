@@ -0,0 +1,191 @@
+use serde::Serialize;
+
+use crate::{
+    height_data::{point_distance, HeightGrid},
+    profile::glide_heights,
+    search::Node,
+};
+
+/// Length of a B record up to the GNSS altitude.
+const B_RECORD_LENGTH: usize = 35;
+
+/// Position fix of an IGC track.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct IgcFix {
+    /// UTC time of the fix in seconds since midnight.
+    pub time: u32,
+    pub lat: f32,
+    pub lon: f32,
+    /// GNSS altitude in meters, the pressure altitude for fixes without a
+    /// 3D fix or GNSS altitude.
+    pub altitude: f32,
+}
+
+fn parse_number(field: &str, line: usize) -> Result<u32, String> {
+    field
+        .parse()
+        .map_err(|_| format!("Bad number {field} in line {line}"))
+}
+
+/// Degrees of an angle given as degrees, minutes and thousandths of minutes
+/// followed by the hemisphere, e.g. `4728123N`.
+fn parse_angle(field: &str, negative: char, line: usize) -> Result<f32, String> {
+    let (digits, hemisphere) = field.split_at(field.len() - 1);
+    let (degrees, minutes) = digits.split_at(digits.len() - 5);
+    let angle =
+        parse_number(degrees, line)? as f32 + parse_number(minutes, line)? as f32 / 60_000.0;
+    Ok(if hemisphere.starts_with(negative) {
+        -angle
+    } else {
+        angle
+    })
+}
+
+/// Fixes of the B records of an IGC file, in the order of the file. Other
+/// records are ignored.
+pub fn parse_igc(content: &str) -> Result<Vec<IgcFix>, String> {
+    let mut fixes = vec![];
+    for (i, record) in content.lines().enumerate() {
+        let line = i + 1;
+        if !record.starts_with('B') {
+            continue;
+        }
+        if record.len() < B_RECORD_LENGTH || !record.is_ascii() {
+            return Err(format!("Line {line} is not a valid B record"));
+        }
+        let time = &record[1..7];
+        let (hours, minutes, seconds) = (
+            parse_number(&time[0..2], line)?,
+            parse_number(&time[2..4], line)?,
+            parse_number(&time[4..6], line)?,
+        );
+        let lat = parse_angle(&record[7..15], 'S', line)?;
+        let lon = parse_angle(&record[15..24], 'W', line)?;
+        if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+            return Err(format!("B record in line {line} is not a valid location"));
+        }
+        let valid = &record[24..25] == "A";
+        let pressure_altitude = record[25..30]
+            .parse::<i32>()
+            .map_err(|_| format!("Bad altitude in line {line}"))?;
+        let gnss_altitude = record[30..35]
+            .parse::<i32>()
+            .map_err(|_| format!("Bad altitude in line {line}"))?;
+        fixes.push(IgcFix {
+            time: hours * 3600 + minutes * 60 + seconds,
+            lat,
+            lon,
+            altitude: if valid && gnss_altitude != 0 {
+                gnss_altitude
+            } else {
+                pressure_altitude
+            } as f32,
+        });
+    }
+    if fixes.is_empty() {
+        return Err("The track has no fixes".to_string());
+    }
+    Ok(fixes)
+}
+
+/// Fix of a track compared to the cone.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TrackPoint {
+    #[serde(flatten)]
+    pub fix: IgcFix,
+    /// Height of the predicted glide above sea level at the fix, `None`
+    /// outside of the cone.
+    pub predicted_height: Option<f32>,
+}
+
+/// Consecutive fixes outside of the cone.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct OutsideSegment {
+    pub from_time: u32,
+    pub to_time: u32,
+    /// `(lat, lon)` of the first and the last fix.
+    pub from: (f32, f32),
+    pub to: (f32, f32),
+    pub fixes: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TrackComparison {
+    /// Share of the fixes inside of the cone.
+    pub inside_share: f32,
+    /// Straight-line distance from the first to the last fix in meters.
+    pub distance: f32,
+    /// Glide ratio from the first to the last fix, `None` without a loss
+    /// of altitude.
+    pub achieved_glide: Option<f32>,
+    /// Glide ratio of the cone to the last fix, `None` if the cone does not
+    /// reach it.
+    pub predicted_glide: Option<f32>,
+    pub outside: Vec<OutsideSegment>,
+    pub points: Vec<TrackPoint>,
+}
+
+/// Compares a track to the cone in `explored` and `grid`, searched from the
+/// first fix at `start_height` meters above sea level. `fixes` must not be
+/// empty.
+pub fn compare_track(
+    fixes: &[IgcFix],
+    explored: &[Node],
+    grid: &HeightGrid,
+    start_height: f32,
+) -> TrackComparison {
+    let heights = glide_heights(explored, grid);
+    let points = fixes
+        .iter()
+        .map(|fix| TrackPoint {
+            fix: fix.clone(),
+            predicted_height: grid.index_of(fix.lat, fix.lon).and_then(|ix| heights[ix]),
+        })
+        .collect::<Vec<_>>();
+
+    let mut outside: Vec<OutsideSegment> = vec![];
+    let mut previous_inside = true;
+    for point in &points {
+        let inside = point.predicted_height.is_some();
+        let position = (point.fix.lat, point.fix.lon);
+        if !inside {
+            match outside.last_mut() {
+                Some(segment) if !previous_inside => {
+                    segment.to_time = point.fix.time;
+                    segment.to = position;
+                    segment.fixes += 1;
+                }
+                _ => outside.push(OutsideSegment {
+                    from_time: point.fix.time,
+                    to_time: point.fix.time,
+                    from: position,
+                    to: position,
+                    fixes: 1,
+                }),
+            }
+        }
+        previous_inside = inside;
+    }
+
+    let inside_fixes = points
+        .iter()
+        .filter(|point| point.predicted_height.is_some())
+        .count();
+    let (first, last) = (&points[0], &points[points.len() - 1]);
+    let distance = point_distance(first.fix.lat, first.fix.lon, last.fix.lat, last.fix.lon);
+    let glide = |from: f32, to: f32| (from > to).then(|| distance / (from - to));
+    TrackComparison {
+        inside_share: inside_fixes as f32 / points.len() as f32,
+        distance,
+        achieved_glide: glide(first.fix.altitude, last.fix.altitude),
+        predicted_glide: last
+            .predicted_height
+            .and_then(|height| glide(start_height, height)),
+        outside,
+        points,
+    }
+}
+
+#[cfg(test)]
+#[path = "./igc_test.rs"]
+mod igc_test;
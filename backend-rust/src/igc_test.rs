@@ -0,0 +1,106 @@
+use approx::assert_relative_eq;
+use ndarray::Array2;
+
+use crate::{
+    height_data::{point_distance, HeightGrid},
+    search::Node,
+};
+
+use super::{compare_track, parse_igc, IgcFix};
+
+const TRACK: &str = "AXXX001 test
+HFDTE150726
+B1012304728123N01122456EA0150001520
+LXXX comment
+B1012354728500S01122000WV0148000000
+";
+
+#[test]
+fn test_parse_igc() {
+    let fixes = parse_igc(TRACK).unwrap();
+
+    assert_eq!(fixes.len(), 2);
+    assert_eq!(fixes[0].time, 10 * 3600 + 12 * 60 + 30);
+    assert_relative_eq!(fixes[0].lat, 47.0 + 28.123 / 60.0);
+    assert_relative_eq!(fixes[0].lon, 11.0 + 22.456 / 60.0);
+    assert_eq!(fixes[0].altitude, 1520.0);
+    // Southern and western hemispheres, no 3D fix.
+    assert_relative_eq!(fixes[1].lat, -(47.0 + 28.5 / 60.0));
+    assert_relative_eq!(fixes[1].lon, -(11.0 + 22.0 / 60.0));
+    assert_eq!(fixes[1].altitude, 1480.0);
+}
+
+#[test]
+fn test_parse_igc_errors() {
+    assert!(parse_igc("AXXX001 test\n").is_err());
+    assert!(parse_igc("B1012304728123N01122456EA01500\n").is_err());
+    assert!(parse_igc("B10123047281x3N01122456EA0150001520\n").is_err());
+    assert!(parse_igc("B1012309928123N01122456EA0150001520\n").is_err());
+}
+
+fn grid() -> HeightGrid {
+    HeightGrid::for_test(Array2::zeros((3, 10)), (47.0, 47.003), (11.0, 11.01))
+}
+
+fn fix(time: u32, col: usize, altitude: f32) -> IgcFix {
+    IgcFix {
+        time,
+        lat: 47.001,
+        lon: 11.0 + col as f32 * 0.001,
+        altitude,
+    }
+}
+
+#[test]
+fn test_compare_track() {
+    let grid = grid();
+    // The cone reaches the columns 0 to 2 and 5 to 7 of the middle row,
+    // losing 10 m per column.
+    let explored = [0, 1, 2, 5, 6, 7]
+        .into_iter()
+        .map(|col| Node {
+            height: 1000.0 - col as f32 * 10.0,
+            ix: (1, col),
+            reachable: true,
+            ..Node::new()
+        })
+        .collect::<Vec<_>>();
+    let fixes = (0..8)
+        .map(|col| fix(col as u32 * 10, col, 1000.0 - col as f32 * 20.0))
+        .collect::<Vec<_>>();
+
+    let comparison = compare_track(&fixes, &explored, &grid, 1000.0);
+
+    assert_relative_eq!(comparison.inside_share, 6.0 / 8.0);
+    assert_eq!(comparison.outside.len(), 1);
+    let outside = &comparison.outside[0];
+    assert_eq!((outside.from_time, outside.to_time), (30, 40));
+    assert_eq!(outside.fixes, 2);
+    assert_eq!(comparison.points[1].predicted_height, Some(990.0));
+    assert_eq!(comparison.points[3].predicted_height, None);
+
+    let distance = point_distance(47.001, 11.0, 47.001, 11.007);
+    assert_relative_eq!(comparison.distance, distance);
+    assert_relative_eq!(comparison.achieved_glide.unwrap(), distance / 140.0);
+    assert_relative_eq!(comparison.predicted_glide.unwrap(), distance / 70.0);
+}
+
+#[test]
+fn test_compare_track_outside() {
+    let grid = grid();
+    // Climbing away from a cone that only contains the start.
+    let explored = vec![Node {
+        height: 1000.0,
+        ix: (1, 0),
+        reachable: true,
+        ..Node::new()
+    }];
+    let fixes = [fix(0, 0, 1000.0), fix(10, 3, 1100.0)];
+
+    let comparison = compare_track(&fixes, &explored, &grid, 1000.0);
+
+    assert_eq!(comparison.achieved_glide, None);
+    assert_eq!(comparison.predicted_glide, None);
+    assert_eq!(comparison.outside.len(), 1);
+    assert_eq!(comparison.outside[0].from, (47.001, 11.003));
+}
@@ -0,0 +1,112 @@
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+/// Where a job is.
+#[derive(Clone, Debug, PartialEq)]
+pub enum JobState<R> {
+    /// Waiting for a worker, behind `position` other jobs.
+    Queued {
+        position: usize,
+    },
+    Running,
+    Finished(R),
+}
+
+/// Jobs waiting for workers, and the results of the last finished ones.
+///
+/// Jobs are taken in the order of their sequence number plus their weight,
+/// so a job of weight `w` is overtaken by at most `w` jobs submitted after
+/// it: cheap jobs pass expensive ones, which still can't starve.
+pub struct JobQueue<T, R> {
+    max_queued: usize,
+    max_finished: usize,
+    next_sequence: u64,
+    /// Queued jobs by `(sequence + weight, sequence)`.
+    queued: BTreeMap<(u64, u64), (String, T)>,
+    queued_keys: HashMap<String, (u64, u64)>,
+    running: HashSet<String>,
+    finished: HashMap<String, R>,
+    /// Finished jobs, oldest first.
+    finished_order: VecDeque<String>,
+}
+
+impl<T, R: Clone> JobQueue<T, R> {
+    /// Queue holding at most `max_queued` waiting jobs and the results of
+    /// the last `max_finished` jobs.
+    pub fn new(max_queued: usize, max_finished: usize) -> JobQueue<T, R> {
+        JobQueue {
+            max_queued,
+            max_finished,
+            next_sequence: 0,
+            queued: BTreeMap::new(),
+            queued_keys: HashMap::new(),
+            running: HashSet::new(),
+            finished: HashMap::new(),
+            finished_order: VecDeque::new(),
+        }
+    }
+
+    /// Enqueues `job` under `id`. Fails if the queue is full or the id is
+    /// taken.
+    pub fn submit(&mut self, id: String, job: T, weight: u64) -> Result<(), String> {
+        if self.queued.len() >= self.max_queued {
+            return Err("The job queue is full, try again later".to_string());
+        }
+        if self.state(&id).is_some() {
+            return Err(format!("Job {id} exists already"));
+        }
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+        let key = (sequence.saturating_add(weight), sequence);
+        self.queued_keys.insert(id.clone(), key);
+        self.queued.insert(key, (id, job));
+        Ok(())
+    }
+
+    /// Takes the next job and marks it as running.
+    pub fn take(&mut self) -> Option<(String, T)> {
+        let (_, (id, job)) = self.queued.pop_first()?;
+        self.queued_keys.remove(&id);
+        self.running.insert(id.clone());
+        Some((id, job))
+    }
+
+    /// Stores the result of a running job, dropping the oldest results
+    /// beyond `max_finished`.
+    pub fn finish(&mut self, id: &str, result: R) {
+        if !self.running.remove(id) {
+            return;
+        }
+        self.finished.insert(id.to_string(), result);
+        self.finished_order.push_back(id.to_string());
+        while self.finished_order.len() > self.max_finished {
+            if let Some(oldest) = self.finished_order.pop_front() {
+                self.finished.remove(&oldest);
+            }
+        }
+    }
+
+    /// `None` for unknown jobs and jobs whose result was dropped.
+    pub fn state(&self, id: &str) -> Option<JobState<R>> {
+        if let Some(key) = self.queued_keys.get(id) {
+            return Some(JobState::Queued {
+                position: self.queued.range(..key).count(),
+            });
+        }
+        if self.running.contains(id) {
+            return Some(JobState::Running);
+        }
+        self.finished.get(id).cloned().map(JobState::Finished)
+    }
+
+    pub fn queued(&self) -> usize {
+        self.queued.len()
+    }
+
+    pub fn running(&self) -> usize {
+        self.running.len()
+    }
+}
+
+#[cfg(test)]
+#[path = "./job_queue_test.rs"]
+mod job_queue_test;
@@ -0,0 +1,74 @@
+use super::{JobQueue, JobState};
+
+fn submit_all(queue: &mut JobQueue<u32, u32>, weights: &[u64]) {
+    for (i, weight) in weights.iter().enumerate() {
+        queue.submit(format!("job{i}"), i as u32, *weight).unwrap();
+    }
+}
+
+fn take_all(queue: &mut JobQueue<u32, u32>) -> Vec<u32> {
+    std::iter::from_fn(|| queue.take().map(|(_, job)| job)).collect()
+}
+
+#[test]
+fn test_jobs_of_equal_weight_are_taken_in_order() {
+    let mut queue = JobQueue::new(10, 10);
+    submit_all(&mut queue, &[1, 1, 1, 1]);
+    assert_eq!(take_all(&mut queue), vec![0, 1, 2, 3]);
+}
+
+#[test]
+fn test_heavy_jobs_are_overtaken_at_most_weight_times() {
+    let mut queue = JobQueue::new(10, 10);
+    submit_all(&mut queue, &[3, 0, 0, 0, 0, 0]);
+    assert_eq!(take_all(&mut queue), vec![1, 2, 0, 3, 4, 5]);
+}
+
+#[test]
+fn test_state() {
+    let mut queue = JobQueue::new(10, 10);
+    submit_all(&mut queue, &[2, 0, 0]);
+    assert_eq!(queue.state("job0"), Some(JobState::Queued { position: 1 }));
+    assert_eq!(queue.state("job1"), Some(JobState::Queued { position: 0 }));
+    assert_eq!(queue.state("job2"), Some(JobState::Queued { position: 2 }));
+    assert_eq!(queue.state("unknown"), None);
+
+    let (id, _) = queue.take().unwrap();
+    assert_eq!(id, "job1");
+    assert_eq!(queue.state("job1"), Some(JobState::Running));
+    assert_eq!(queue.state("job0"), Some(JobState::Queued { position: 0 }));
+    assert_eq!(queue.state("job2"), Some(JobState::Queued { position: 1 }));
+    assert_eq!((queue.queued(), queue.running()), (2, 1));
+
+    queue.finish("job1", 42);
+    assert_eq!(queue.state("job1"), Some(JobState::Finished(42)));
+    assert_eq!((queue.queued(), queue.running()), (2, 0));
+}
+
+#[test]
+fn test_full_queue_and_duplicate_ids_are_rejected() {
+    let mut queue = JobQueue::new(2, 10);
+    submit_all(&mut queue, &[0, 0]);
+    assert!(queue.submit("job2".to_string(), 2, 0).is_err());
+    assert!(queue.submit("job0".to_string(), 0, 0).is_err());
+
+    queue.take();
+    assert!(queue.submit("job0".to_string(), 0, 0).is_err());
+    assert!(queue.submit("job2".to_string(), 2, 0).is_ok());
+}
+
+#[test]
+fn test_oldest_results_are_dropped() {
+    let mut queue = JobQueue::new(10, 2);
+    submit_all(&mut queue, &[0, 0, 0]);
+    while let Some((id, job)) = queue.take() {
+        queue.finish(&id, job * 10);
+    }
+    assert_eq!(queue.state("job0"), None);
+    assert_eq!(queue.state("job1"), Some(JobState::Finished(10)));
+    assert_eq!(queue.state("job2"), Some(JobState::Finished(20)));
+
+    // Only running jobs can finish.
+    queue.finish("job0", 0);
+    assert_eq!(queue.state("job0"), None);
+}
@@ -0,0 +1,134 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use ndarray::Array2;
+use serde::Serialize;
+
+use crate::{
+    height_data::{tile_name, HeightGrid},
+    search::Node,
+};
+
+/// Directory in the data directory with the land cover tiles.
+pub const LAND_COVER_DIR: &str = "land_cover";
+/// Edge length in degrees of the ESA WorldCover tiles.
+pub const LAND_COVER_TILE_DEGREES: i32 = 3;
+
+/// Surface type of a cell, the classes of ESA WorldCover merged where they
+/// make no difference for landing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LandCover {
+    /// Tree cover and mangroves.
+    Forest,
+    Shrubland,
+    /// Grassland and moss.
+    Meadow,
+    Cropland,
+    /// Built-up areas.
+    Urban,
+    /// Bare or sparse vegetation, e.g. scree.
+    Bare,
+    Snow,
+    Water,
+    Wetland,
+}
+
+impl LandCover {
+    /// Class of an ESA WorldCover code, `None` for no data and unknown codes.
+    pub fn from_worldcover(code: u8) -> Option<LandCover> {
+        match code {
+            10 | 95 => Some(LandCover::Forest),
+            20 => Some(LandCover::Shrubland),
+            30 | 100 => Some(LandCover::Meadow),
+            40 => Some(LandCover::Cropland),
+            50 => Some(LandCover::Urban),
+            60 => Some(LandCover::Bare),
+            70 => Some(LandCover::Snow),
+            80 => Some(LandCover::Water),
+            90 => Some(LandCover::Wetland),
+            _ => None,
+        }
+    }
+}
+
+/// WorldCover codes of one tile, the first row at the north edge like in
+/// the file.
+pub struct LandCoverTile {
+    /// Latitude of the south and longitude of the west edge in degrees.
+    pub south: i32,
+    pub west: i32,
+    pub codes: Array2<u8>,
+}
+
+impl LandCoverTile {
+    /// Code of the pixel containing `lat` and `lon`, `None` outside of the
+    /// tile.
+    pub fn code_at(&self, lat: f32, lon: f32) -> Option<u8> {
+        let (rows, cols) = (self.codes.shape()[0], self.codes.shape()[1]);
+        let size = LAND_COVER_TILE_DEGREES as f32;
+        let row = ((self.south as f32 + size - lat) / size * rows as f32).floor();
+        let col = ((lon - self.west as f32) / size * cols as f32).floor();
+        if row < 0.0 || col < 0.0 {
+            return None;
+        }
+        self.codes.get((row as usize, col as usize)).copied()
+    }
+}
+
+/// South west corner of the tile containing `lat` and `lon`.
+pub fn land_cover_tile_origin(lat: f32, lon: f32) -> (i32, i32) {
+    let size = LAND_COVER_TILE_DEGREES as f32;
+    (
+        ((lat / size).floor() * size) as i32,
+        ((lon / size).floor() * size) as i32,
+    )
+}
+
+/// File of the tile with the south west corner `south` and `west` in `dir`,
+/// named like the WorldCover downloads, e.g.
+/// `ESA_WorldCover_10m_2021_v200_N45E006_Map.tif`, whatever the version.
+pub fn land_cover_file(dir: &Path, south: i32, west: i32) -> Option<PathBuf> {
+    let suffix = format!("_{}_Map.tif", tile_name(south, west));
+    fs::read_dir(dir)
+        .ok()?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .find(|path| {
+            path.file_name()
+                .is_some_and(|name| name.to_string_lossy().ends_with(&suffix))
+        })
+}
+
+/// Land cover at the reachable cells of a search, `None` elsewhere and
+/// where no tile is available. `tile` loads the tile with the given south
+/// west corner.
+pub fn land_cover_grid<T: AsRef<LandCoverTile>>(
+    explored: &[Node],
+    grid: &HeightGrid,
+    mut tile: impl FnMut(i32, i32) -> Option<T>,
+) -> Array2<Option<LandCover>> {
+    let mut result = Array2::from_elem((grid.heights.shape()[0], grid.heights.shape()[1]), None);
+    let mut current: Option<((i32, i32), Option<T>)> = None;
+    for node in explored.iter().filter(|node| node.reachable) {
+        let ix = (node.ix.0 as usize, node.ix.1 as usize);
+        let (lat, lon) = grid.cell_center(ix);
+        let origin = land_cover_tile_origin(lat, lon);
+        // Neighbouring cells are mostly on the same tile.
+        if current.as_ref().is_none_or(|(o, _)| *o != origin) {
+            current = Some((origin, tile(origin.0, origin.1)));
+        }
+        result[ix] = current
+            .as_ref()
+            .and_then(|(_, tile)| tile.as_ref())
+            .and_then(|tile| tile.as_ref().code_at(lat, lon))
+            .and_then(LandCover::from_worldcover);
+    }
+    result
+}
+
+#[cfg(test)]
+#[path = "./land_cover_test.rs"]
+mod land_cover_test;
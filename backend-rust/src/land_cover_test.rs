@@ -0,0 +1,80 @@
+use std::fs;
+
+use ndarray::Array2;
+
+use crate::{height_data::HeightGrid, search::Node};
+
+use super::{land_cover_file, land_cover_grid, land_cover_tile_origin, LandCover, LandCoverTile};
+
+/// Tile at N45E006 with forest in the northern and water in the southern
+/// half.
+fn tile() -> LandCoverTile {
+    LandCoverTile {
+        south: 45,
+        west: 6,
+        codes: Array2::from_shape_fn((6, 6), |(row, _)| if row < 3 { 10 } else { 80 }),
+    }
+}
+
+#[test]
+fn test_from_worldcover() {
+    assert_eq!(LandCover::from_worldcover(10), Some(LandCover::Forest));
+    assert_eq!(LandCover::from_worldcover(30), Some(LandCover::Meadow));
+    assert_eq!(LandCover::from_worldcover(80), Some(LandCover::Water));
+    assert_eq!(LandCover::from_worldcover(0), None);
+}
+
+#[test]
+fn test_code_at() {
+    let tile = tile();
+    assert_eq!(tile.code_at(47.9, 6.1), Some(10));
+    assert_eq!(tile.code_at(45.1, 8.9), Some(80));
+    assert_eq!(tile.code_at(48.1, 6.1), None);
+    assert_eq!(tile.code_at(46.0, 5.9), None);
+
+    assert_eq!(land_cover_tile_origin(47.3, 7.2), (45, 6));
+    assert_eq!(land_cover_tile_origin(-0.5, -1.0), (-3, -3));
+}
+
+#[test]
+fn test_land_cover_file() {
+    let dir = std::env::temp_dir().join(format!("land_cover_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("ESA_WorldCover_10m_2021_v200_N45E006_Map.tif");
+    fs::write(&path, []).unwrap();
+
+    assert_eq!(land_cover_file(&dir, 45, 6), Some(path));
+    assert_eq!(land_cover_file(&dir, 45, 9), None);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_land_cover_grid() {
+    let grid = HeightGrid::for_test(Array2::zeros((5, 1)), (44.5, 49.5), (7.0, 8.0));
+    let explored = (0..5)
+        .map(|row| Node {
+            ix: (row, 0),
+            reachable: row != 4,
+            ..Node::new()
+        })
+        .collect::<Vec<_>>();
+
+    let mut loaded = vec![];
+    let land_cover = land_cover_grid(&explored, &grid, |south, west| {
+        loaded.push((south, west));
+        (south, west).eq(&(45, 6)).then(|| Box::new(tile()))
+    });
+    // The cell at 44.5 degrees lies on the tile to the south, which is
+    // missing.
+    assert_eq!(
+        land_cover.column(0).to_vec(),
+        vec![
+            None,
+            Some(LandCover::Water),
+            Some(LandCover::Water),
+            Some(LandCover::Forest),
+            None
+        ]
+    );
+    assert_eq!(loaded, vec![(42, 6), (45, 6)]);
+}
@@ -0,0 +1,211 @@
+use std::collections::{HashMap, HashSet};
+
+use ndarray::Array2;
+use serde::Serialize;
+
+use crate::{
+    cone_stats::agl_of,
+    height_data::HeightGrid,
+    land_cover::LandCover,
+    search::{GridIx, GridIxType, Node},
+};
+
+/// Steepest slope, as rise per meter, that is still considered landable.
+pub const MAX_LANDING_SLOPE: f32 = 0.15;
+/// Steepest slope, as rise per meter, that an experienced pilot can still
+/// land on, e.g. a top landing into the wind.
+pub const MAX_MARGINAL_LANDING_SLOPE: f32 = 0.3;
+/// Smallest area in square meters a landing zone has to cover.
+pub const MIN_LANDING_AREA_M2: f32 = 20_000.0;
+/// Landing zones returned at most, the highest arrivals are kept.
+pub const MAX_LANDING_ZONES: usize = 50;
+
+/// Connected area of flat, reachable cells.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct LandingZone {
+    /// Cell of the zone with the highest arrival above ground.
+    pub ix: GridIx,
+    pub latitude: f32,
+    pub longitude: f32,
+    /// Terrain height of that cell in meters.
+    pub elevation: f32,
+    /// Height above ground when arriving at that cell in meters.
+    pub arrival_agl: f32,
+    pub area_m2: f32,
+}
+
+/// Rise of the terrain per meter to the north and to the east at a cell,
+/// from the height differences to its neighbors. Cells at the border of the
+/// grid use the difference to their one inner neighbor.
+fn gradient_at(grid: &HeightGrid, row: usize, col: usize) -> (f32, f32) {
+    let (rows, cols) = (grid.heights.shape()[0], grid.heights.shape()[1]);
+    let height = |row: usize, col: usize| grid.heights[(row, col)] as f32;
+    let gradient = |before: (usize, usize), after: (usize, usize), steps: usize| {
+        if steps == 0 {
+            0.0
+        } else {
+            (height(after.0, after.1) - height(before.0, before.1))
+                / (steps as f32 * grid.cell_size)
+        }
+    };
+
+    let (up, down) = (row.saturating_sub(1), (row + 1).min(rows - 1));
+    let (left, right) = (col.saturating_sub(1), (col + 1).min(cols - 1));
+    (
+        gradient((up, col), (down, col), down - up),
+        gradient((row, left), (row, right), right - left),
+    )
+}
+
+/// Slope of the terrain at a cell as rise per meter, see `gradient_at`.
+pub fn slope_at(grid: &HeightGrid, row: usize, col: usize) -> f32 {
+    let (d_row, d_col) = gradient_at(grid, row, col);
+    (d_row * d_row + d_col * d_col).sqrt()
+}
+
+/// Bearing in degrees clockwise from north that the terrain at a cell
+/// faces, i.e. the direction downhill. `None` on flat terrain.
+pub fn aspect_at(grid: &HeightGrid, row: usize, col: usize) -> Option<f32> {
+    let (north, east) = gradient_at(grid, row, col);
+    if north == 0.0 && east == 0.0 {
+        return None;
+    }
+    Some((-east).atan2(-north).to_degrees().rem_euclid(360.0))
+}
+
+/// How suitable the terrain of a cell is for landing, by its slope.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Landability {
+    /// At most `MAX_LANDING_SLOPE`.
+    Landable,
+    /// At most `MAX_MARGINAL_LANDING_SLOPE`.
+    Marginal,
+    Unlandable,
+}
+
+impl Landability {
+    /// Landability limited by the surface: forests, buildings, water,
+    /// wetlands and snow can't be landed on whatever the slope, shrubs only
+    /// with care.
+    pub fn with_land_cover(self, land_cover: Option<LandCover>) -> Landability {
+        match land_cover {
+            Some(
+                LandCover::Forest
+                | LandCover::Urban
+                | LandCover::Water
+                | LandCover::Wetland
+                | LandCover::Snow,
+            ) => Landability::Unlandable,
+            Some(LandCover::Shrubland) if self == Landability::Landable => Landability::Marginal,
+            _ => self,
+        }
+    }
+}
+
+/// Landability of the cell at `row` and `col`, see `slope_at`.
+pub fn landability_at(grid: &HeightGrid, row: usize, col: usize) -> Landability {
+    let slope = slope_at(grid, row, col);
+    if slope <= MAX_LANDING_SLOPE {
+        Landability::Landable
+    } else if slope <= MAX_MARGINAL_LANDING_SLOPE {
+        Landability::Marginal
+    } else {
+        Landability::Unlandable
+    }
+}
+
+/// Reachable areas flat enough to land, largest arrival height first. Cells
+/// where landing is prohibited are never part of a landing zone.
+pub fn detect_landing_zones(
+    explored: &[Node],
+    grid: &HeightGrid,
+    landing_prohibited: Option<&Array2<bool>>,
+) -> Vec<LandingZone> {
+    let shape = (grid.heights.shape()[0], grid.heights.shape()[1]);
+    let mut arrival = Array2::from_elem(shape, f32::NAN);
+    for node in explored.iter().filter(|node| node.reachable) {
+        let ix = (node.ix.0 as usize, node.ix.1 as usize);
+        let prohibited = landing_prohibited.is_some_and(|mask| mask[ix]);
+        if !prohibited && slope_at(grid, ix.0, ix.1) <= MAX_LANDING_SLOPE {
+            arrival[ix] = agl_of(node, grid);
+        }
+    }
+
+    let cell_area = grid.cell_size * grid.cell_size;
+    let mut visited = Array2::from_elem(shape, false);
+    let mut zones = vec![];
+    for ((row, col), agl) in arrival.indexed_iter() {
+        if agl.is_nan() || visited[(row, col)] {
+            continue;
+        }
+        visited[(row, col)] = true;
+        let mut stack = vec![(row, col)];
+        let mut cells = 0;
+        let mut best = (row, col);
+        while let Some((row, col)) = stack.pop() {
+            cells += 1;
+            if arrival[(row, col)] > arrival[best] {
+                best = (row, col);
+            }
+            let neighbors = [
+                (row.wrapping_sub(1), col),
+                (row + 1, col),
+                (row, col.wrapping_sub(1)),
+                (row, col + 1),
+            ];
+            for neighbor in neighbors {
+                if arrival.get(neighbor).is_some_and(|agl| !agl.is_nan()) && !visited[neighbor] {
+                    visited[neighbor] = true;
+                    stack.push(neighbor);
+                }
+            }
+        }
+
+        let area_m2 = cells as f32 * cell_area;
+        if area_m2 >= MIN_LANDING_AREA_M2 {
+            let (latitude, longitude) = grid.cell_center(best);
+            zones.push(LandingZone {
+                ix: (best.0 as GridIxType, best.1 as GridIxType),
+                latitude,
+                longitude,
+                elevation: grid.heights[best] as f32,
+                arrival_agl: arrival[best],
+                area_m2,
+            });
+        }
+    }
+
+    zones.sort_by(|a, b| b.arrival_agl.total_cmp(&a.arrival_agl));
+    zones.truncate(MAX_LANDING_ZONES);
+    zones
+}
+
+/// Reachable node at each of the `points` given as (latitude, longitude),
+/// `None` for points outside of the grid or the cone. Used to look up known
+/// landing zones in a cone.
+pub fn reachable_nodes_at<'a>(
+    explored: &'a [Node],
+    grid: &HeightGrid,
+    points: &[(f32, f32)],
+) -> Vec<Option<&'a Node>> {
+    let cells = points
+        .iter()
+        .map(|&(lat, lon)| grid.index_of(lat, lon))
+        .collect::<Vec<_>>();
+    let wanted = cells.iter().flatten().copied().collect::<HashSet<_>>();
+    let nodes = explored
+        .iter()
+        .filter(|node| node.reachable)
+        .map(|node| ((node.ix.0 as usize, node.ix.1 as usize), node))
+        .filter(|(ix, _)| wanted.contains(ix))
+        .collect::<HashMap<_, _>>();
+    cells
+        .iter()
+        .map(|cell| cell.and_then(|ix| nodes.get(&ix).copied()))
+        .collect()
+}
+
+#[cfg(test)]
+#[path = "./landing_test.rs"]
+mod landing_test;
@@ -0,0 +1,160 @@
+use approx::assert_relative_eq;
+use ndarray::Array2;
+
+use crate::{height_data::HeightGrid, land_cover::LandCover, search::Node};
+
+use super::{
+    aspect_at, detect_landing_zones, landability_at, reachable_nodes_at, slope_at, Landability,
+    MAX_LANDING_SLOPE,
+};
+
+fn grid(heights: Array2<i16>) -> HeightGrid {
+    let shape = heights.shape().to_vec();
+    HeightGrid::for_test(
+        heights,
+        (47.0, 47.0 + 0.001 * shape[0] as f32),
+        (11.0, 11.0 + 0.001 * shape[1] as f32),
+    )
+}
+
+#[test]
+fn test_slope_at() {
+    let heights = Array2::from_shape_fn((3, 3), |(row, col)| (row * 10 + col * 20) as i16);
+    let grid = grid(heights);
+
+    assert_relative_eq!(slope_at(&grid, 1, 1), (0.01f32 + 0.04).sqrt());
+    // One-sided at the border.
+    assert_relative_eq!(slope_at(&grid, 0, 0), (0.01f32 + 0.04).sqrt());
+
+    let single = self::grid(Array2::from_elem((1, 1), 500));
+    assert_relative_eq!(slope_at(&single, 0, 0), 0.0);
+}
+
+#[test]
+fn test_aspect_at() {
+    // Rising to the north, so facing south.
+    let heights = Array2::from_shape_fn((3, 3), |(row, _)| (row * 10) as i16);
+    assert_relative_eq!(aspect_at(&grid(heights), 1, 1).unwrap(), 180.0);
+
+    // Rising to the west, so facing east.
+    let heights = Array2::from_shape_fn((3, 3), |(_, col)| (100 - col * 10) as i16);
+    assert_relative_eq!(aspect_at(&grid(heights), 1, 1).unwrap(), 90.0);
+
+    assert_eq!(aspect_at(&grid(Array2::from_elem((3, 3), 500)), 1, 1), None);
+}
+
+#[test]
+fn test_detect_landing_zones() {
+    // A flat valley on the left, a steep slope in the middle column and a
+    // small flat spot on the right.
+    let heights = Array2::from_shape_fn((4, 5), |(_, col)| match col {
+        0 | 1 => 500,
+        2 => 700,
+        _ => 900,
+    });
+    let grid = grid(heights);
+    assert!(slope_at(&grid, 0, 2) > MAX_LANDING_SLOPE);
+
+    let mut explored = vec![];
+    for row in 0..4 {
+        for col in 0..5 {
+            let agl = if (row, col) == (2, 0) { 400.0 } else { 100.0 };
+            explored.push(Node::for_test(
+                (row, col),
+                grid.heights[(row as usize, col as usize)] as f32 + agl,
+                col < 4 || row == 0,
+            ));
+        }
+    }
+
+    let zones = detect_landing_zones(&explored, &grid, None);
+    assert_eq!(zones.len(), 1);
+    assert_eq!(zones[0].ix, (2, 0));
+    assert_relative_eq!(zones[0].latitude, 47.002);
+    assert_relative_eq!(zones[0].longitude, 11.0);
+    assert_relative_eq!(zones[0].elevation, 500.0);
+    assert_relative_eq!(zones[0].arrival_agl, 400.0);
+    // Column 1 is next to the slope and too steep itself, only one cell of
+    // the flat spot is reachable.
+    assert_relative_eq!(zones[0].area_m2, 40_000.0);
+
+    // Prohibited cells split the valley into pieces too small to land.
+    let mut prohibited = Array2::from_elem((4, 5), false);
+    prohibited[(1, 0)] = true;
+    prohibited[(2, 0)] = true;
+    assert!(detect_landing_zones(&explored, &grid, Some(&prohibited)).is_empty());
+
+    let unreachable = explored
+        .into_iter()
+        .map(|node| Node {
+            reachable: false,
+            ..node
+        })
+        .collect::<Vec<_>>();
+    assert!(detect_landing_zones(&unreachable, &grid, None).is_empty());
+}
+
+#[test]
+fn test_landability_at() {
+    // Flat to the west, 20 m and 50 m per 100 m cell further east.
+    let mut heights = Array2::zeros((3, 9));
+    for col in 3..9 {
+        let rise = if col < 6 { 20 } else { 50 };
+        for row in 0..3 {
+            heights[(row, col)] = heights[(row, col - 1)] + rise;
+        }
+    }
+    let grid = grid(heights);
+
+    assert_eq!(landability_at(&grid, 1, 1), Landability::Landable);
+    assert_eq!(landability_at(&grid, 1, 4), Landability::Marginal);
+    assert_eq!(landability_at(&grid, 1, 7), Landability::Unlandable);
+}
+
+#[test]
+fn test_landability_with_land_cover() {
+    assert_eq!(
+        Landability::Landable.with_land_cover(None),
+        Landability::Landable
+    );
+    assert_eq!(
+        Landability::Landable.with_land_cover(Some(LandCover::Meadow)),
+        Landability::Landable
+    );
+    assert_eq!(
+        Landability::Landable.with_land_cover(Some(LandCover::Shrubland)),
+        Landability::Marginal
+    );
+    assert_eq!(
+        Landability::Landable.with_land_cover(Some(LandCover::Forest)),
+        Landability::Unlandable
+    );
+    assert_eq!(
+        Landability::Unlandable.with_land_cover(Some(LandCover::Cropland)),
+        Landability::Unlandable
+    );
+}
+
+#[test]
+fn test_reachable_nodes_at() {
+    let grid = grid(Array2::from_elem((4, 5), 500));
+    let explored = vec![
+        Node::for_test((1, 2), 800.0, true),
+        Node::for_test((2, 3), 600.0, false),
+    ];
+
+    let nodes = reachable_nodes_at(
+        &explored,
+        &grid,
+        &[
+            (47.001, 11.002),
+            (47.002, 11.003),
+            (47.0, 11.0),
+            (48.0, 11.0),
+        ],
+    );
+
+    assert_eq!(nodes.len(), 4);
+    assert_eq!(nodes[0].map(|node| node.ix), Some((1, 2)));
+    assert!(nodes[1..].iter().all(Option::is_none));
+}
@@ -0,0 +1,105 @@
+use ndarray::Array2;
+use serde::Serialize;
+
+use crate::height_data::HeightGrid;
+
+/// Launches searched for one score map at most, every launch is a search
+/// of its own.
+pub const MAX_SCORED_LAUNCHES: usize = 25;
+/// Hiking time counted at least in hours, so launches right at the start
+/// do not score infinitely.
+pub const MIN_HIKE_HOURS: f32 = 0.25;
+
+/// Launch a glide was searched from.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ScoredLaunch {
+    pub lat: f32,
+    pub lon: f32,
+    /// Hiking time from the start in hours.
+    pub hours: f32,
+    /// Area reachable by air from the launch in square kilometers.
+    pub area_km2: f32,
+    /// `area_km2` per hiking hour, see `MIN_HIKE_HOURS`.
+    pub score: f32,
+}
+
+pub struct LaunchScores {
+    /// Score of every cell reached on foot, the glide area of the nearest
+    /// searched launch per hiking hour to the cell. `None` where the cell
+    /// was not reached or its nearest launch was not searched.
+    pub scores: Array2<Option<f32>>,
+    /// The searched launches, best first.
+    pub launches: Vec<ScoredLaunch>,
+}
+
+impl LaunchScores {
+    /// Estimated memory of the scores.
+    pub fn bytes(&self) -> usize {
+        self.scores.len() * std::mem::size_of::<Option<f32>>()
+            + self.launches.len() * std::mem::size_of::<ScoredLaunch>()
+    }
+}
+
+/// Glide area per hiking hour over the cells reached on foot. `times` are
+/// the hours of `hiking_times` from `start` on `grid`. Launches are searched
+/// on a lattice through `start`, as dense as `max_launches` allows, and
+/// `glide_area` computes the area in square kilometers reachable by air from
+/// a launch at `(latitude, longitude)`.
+pub fn launch_scores<E>(
+    grid: &HeightGrid,
+    times: &Array2<f32>,
+    start: (usize, usize),
+    max_launches: usize,
+    mut glide_area: impl FnMut(f32, f32) -> Result<f32, E>,
+) -> Result<LaunchScores, E> {
+    let (rows, cols) = (times.shape()[0], times.shape()[1]);
+    let on_lattice = |spacing: usize, (row, col): (usize, usize)| {
+        row.abs_diff(start.0) % spacing == 0 && col.abs_diff(start.1) % spacing == 0
+    };
+    let lattice = |spacing: usize| {
+        times
+            .indexed_iter()
+            .filter(move |(ix, time)| time.is_finite() && on_lattice(spacing, *ix))
+            .map(|(ix, _)| ix)
+    };
+    let spacing = (1..rows.max(cols).max(1))
+        .find(|&spacing| lattice(spacing).count() <= max_launches)
+        .unwrap_or(rows.max(cols).max(1));
+
+    let mut areas = Array2::from_elem((rows, cols), None);
+    let mut launches = vec![];
+    for ix in lattice(spacing).collect::<Vec<_>>() {
+        let (lat, lon) = grid.cell_center(ix);
+        let area_km2 = glide_area(lat, lon)?;
+        areas[ix] = Some(area_km2);
+        launches.push(ScoredLaunch {
+            lat,
+            lon,
+            hours: times[ix],
+            area_km2,
+            score: area_km2 / times[ix].max(MIN_HIKE_HOURS),
+        });
+    }
+    launches.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+    // Nearest lattice point along one axis, `None` outside of the grid.
+    let nearest = |index: usize, start: usize, size: usize| {
+        let steps = ((index as f32 - start as f32) / spacing as f32).round() as isize;
+        start
+            .checked_add_signed(steps * spacing as isize)
+            .filter(|&index| index < size)
+    };
+    let scores = Array2::from_shape_fn((rows, cols), |(row, col)| {
+        let time = times[(row, col)];
+        if !time.is_finite() {
+            return None;
+        }
+        let launch = (nearest(row, start.0, rows)?, nearest(col, start.1, cols)?);
+        areas[launch].map(|area| area / time.max(MIN_HIKE_HOURS))
+    });
+    Ok(LaunchScores { scores, launches })
+}
+
+#[cfg(test)]
+#[path = "./launch_score_test.rs"]
+mod launch_score_test;
@@ -0,0 +1,66 @@
+use approx::assert_relative_eq;
+use ndarray::Array2;
+
+use crate::height_data::HeightGrid;
+
+use super::{launch_scores, MIN_HIKE_HOURS};
+
+fn grid(rows: usize, cols: usize) -> HeightGrid {
+    HeightGrid::for_test(
+        Array2::zeros((rows, cols)),
+        (47.0, 47.0 + 0.0009 * rows as f32),
+        (11.0, 11.0 + 0.0013 * cols as f32),
+    )
+}
+
+#[test]
+fn test_launch_scores() {
+    let grid = grid(9, 9);
+    // One hour per cell away from the start, the column 7 is not reached.
+    let times = Array2::from_shape_fn((9, 9), |(row, col)| {
+        if col == 7 {
+            f32::INFINITY
+        } else {
+            row.abs_diff(4).max(col.abs_diff(4)) as f32
+        }
+    });
+
+    let mut searched = vec![];
+    let scores = launch_scores::<()>(&grid, &times, (4, 4), 9, |lat, lon| {
+        searched.push((lat, lon));
+        // Launches further north glide further.
+        Ok((lat - 47.0) * 1000.0)
+    })
+    .unwrap();
+
+    // Every third cell, the ones of column 7 are not reached.
+    assert_eq!(searched.len(), 6);
+    assert_eq!(scores.launches.len(), 6);
+    assert!(scores
+        .launches
+        .windows(2)
+        .all(|pair| pair[0].score >= pair[1].score));
+    // The start is scored with the shortest hiking time and beats the
+    // launches further north.
+    let start_area = (grid.cell_center((4, 4)).0 - 47.0) * 1000.0;
+    let best = &scores.launches[0];
+    assert_relative_eq!(best.hours, 0.0);
+    assert_relative_eq!(best.score, start_area / MIN_HIKE_HOURS);
+    assert_relative_eq!(scores.scores[(4, 4)].unwrap(), best.score);
+    let north = &scores.launches[1];
+    assert_relative_eq!(north.hours, 3.0);
+    assert_relative_eq!(north.score, north.area_km2 / 3.0);
+    // Neighbouring cells take the area of the start and their own time.
+    assert_relative_eq!(scores.scores[(5, 5)].unwrap(), start_area);
+    assert_eq!(scores.scores[(4, 7)], None);
+    // Reached, but the nearest launch is not.
+    assert_eq!(scores.scores[(4, 8)], None);
+}
+
+#[test]
+fn test_launch_scores_error() {
+    let grid = grid(3, 3);
+    let times = Array2::zeros((3, 3));
+    let result = launch_scores(&grid, &times, (1, 1), 25, |_, _| Err("no data"));
+    assert!(matches!(result, Err("no data")));
+}
@@ -0,0 +1,140 @@
+use crate::colors::{f32_color_to_u8, ColorRamp};
+
+/// Width of the color bar in pixels.
+pub const BAR_WIDTH: usize = 256;
+const BAR_HEIGHT: usize = 16;
+const TICK_LENGTH: usize = 4;
+/// Space around the bar, wide enough for the labels of the outer ticks.
+const MARGIN: usize = 20;
+/// Glyphs are drawn with this many pixels per font pixel.
+const SCALE: usize = 2;
+const GLYPH_WIDTH: usize = 3;
+const GLYPH_HEIGHT: usize = 5;
+/// At most this many ticks are labeled, so the labels do not overlap.
+const MAX_TICKS: usize = 6;
+const INK: [u8; 4] = [40, 40, 40, 255];
+
+/// Rows of the 3x5 pixel glyphs of the characters of tick labels, the most
+/// significant of the 3 bits is the leftmost pixel.
+fn glyph(character: char) -> Option<[u8; GLYPH_HEIGHT]> {
+    Some(match character {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        _ => return None,
+    })
+}
+
+/// RGBA image, row by row from the top.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Raster {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<[u8; 4]>,
+}
+
+impl Raster {
+    fn new(width: usize, height: usize) -> Raster {
+        Raster {
+            width,
+            height,
+            pixels: vec![[0, 0, 0, 0]; width * height],
+        }
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> [u8; 4] {
+        self.pixels[y * self.width + x]
+    }
+
+    fn put(&mut self, x: usize, y: usize, color: [u8; 4]) {
+        if x < self.width && y < self.height {
+            self.pixels[y * self.width + x] = color;
+        }
+    }
+
+    /// Draws `text` centered on `x` with its top at `y`, characters without
+    /// a glyph are left out.
+    fn text(&mut self, text: &str, x: usize, y: usize) {
+        let glyphs = text.chars().filter_map(glyph).collect::<Vec<_>>();
+        let advance = (GLYPH_WIDTH + 1) * SCALE;
+        let width = (glyphs.len() * advance).saturating_sub(SCALE);
+        let left = x.saturating_sub(width / 2);
+        for (i, rows) in glyphs.iter().enumerate() {
+            for (row, bits) in rows.iter().enumerate() {
+                for col in 0..GLYPH_WIDTH {
+                    if bits & (1 << (GLYPH_WIDTH - 1 - col)) == 0 {
+                        continue;
+                    }
+                    for dy in 0..SCALE {
+                        for dx in 0..SCALE {
+                            self.put(
+                                left + i * advance + col * SCALE + dx,
+                                y + row * SCALE + dy,
+                                INK,
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Whole values between `min` and `max`, at most `max_ticks` of them, spaced
+/// by 1, 2 or 5 times a power of 10.
+pub fn legend_ticks(min: f32, max: f32, max_ticks: usize) -> Vec<f32> {
+    if min.is_nan() || max.is_nan() || max <= min || max_ticks == 0 {
+        return vec![];
+    }
+    let raw_step = ((max - min) / max_ticks as f32).max(1.0);
+    let magnitude = 10f32.powf(raw_step.log10().floor());
+    let step = [1.0, 2.0, 5.0, 10.0]
+        .into_iter()
+        .map(|factor| factor * magnitude)
+        .find(|step| *step >= raw_step)
+        .unwrap_or(10.0 * magnitude);
+
+    let first = (min / step).ceil() as i64;
+    let last = (max / step).floor() as i64;
+    (first..=last).map(|i| i as f32 * step).collect()
+}
+
+/// Color scale of `ramp` from `min` on the left to `max` on the right, with
+/// tick marks and labels at round values below it.
+pub fn legend(ramp: &ColorRamp, min: f32, max: f32) -> Raster {
+    let label_top = MARGIN / 2 + BAR_HEIGHT + TICK_LENGTH + SCALE;
+    let mut raster = Raster::new(
+        BAR_WIDTH + 2 * MARGIN,
+        label_top + GLYPH_HEIGHT * SCALE + MARGIN / 2,
+    );
+
+    let bar_top = MARGIN / 2;
+    for x in 0..BAR_WIDTH {
+        let color = f32_color_to_u8(ramp.color_at(x as f32 / (BAR_WIDTH - 1) as f32));
+        for y in bar_top..bar_top + BAR_HEIGHT {
+            raster.put(MARGIN + x, y, color);
+        }
+    }
+
+    for tick in legend_ticks(min, max, MAX_TICKS) {
+        let s = (tick - min) / (max - min);
+        let x = MARGIN + (s * (BAR_WIDTH - 1) as f32).round() as usize;
+        for y in bar_top + BAR_HEIGHT..bar_top + BAR_HEIGHT + TICK_LENGTH {
+            raster.put(x, y, INK);
+        }
+        raster.text(&format!("{tick:.0}"), x, label_top);
+    }
+    raster
+}
+
+#[cfg(test)]
+#[path = "./legend_test.rs"]
+mod legend_test;
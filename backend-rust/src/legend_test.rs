@@ -0,0 +1,48 @@
+use crate::colors::ColorRamp;
+
+use super::{legend, legend_ticks, BAR_WIDTH};
+
+#[test]
+fn test_legend_ticks() {
+    assert_eq!(
+        legend_ticks(0.0, 1000.0, 6),
+        vec![0.0, 200.0, 400.0, 600.0, 800.0, 1000.0]
+    );
+    assert_eq!(
+        legend_ticks(130.0, 1270.0, 6),
+        vec![200.0, 400.0, 600.0, 800.0, 1000.0, 1200.0]
+    );
+    assert_eq!(
+        legend_ticks(-30.0, 70.0, 6),
+        vec![-20.0, 0.0, 20.0, 40.0, 60.0]
+    );
+    assert_eq!(legend_ticks(0.0, 8.0, 4), vec![0.0, 2.0, 4.0, 6.0, 8.0]);
+    // Ticks are whole meters.
+    assert_eq!(legend_ticks(0.0, 2.0, 6), vec![0.0, 1.0, 2.0]);
+    assert!(legend_ticks(100.0, 100.0, 6).is_empty());
+    assert!(legend_ticks(0.0, f32::NAN, 6).is_empty());
+}
+
+#[test]
+fn test_legend() {
+    let ramp = ColorRamp::default();
+    let raster = legend(&ramp, 0.0, 1000.0);
+    assert_eq!(raster.pixels.len(), raster.width * raster.height);
+    assert!(raster.width > BAR_WIDTH);
+
+    // The bar goes from the lowest to the highest color of the ramp.
+    let bar_row = 10;
+    let first = (0..raster.width)
+        .find(|x| raster.pixel(*x, bar_row)[3] > 0)
+        .unwrap();
+    assert_eq!(raster.pixel(first, bar_row), [255, 0, 0, 255]);
+    assert_eq!(
+        raster.pixel(first + BAR_WIDTH - 1, bar_row),
+        [0, 150, 255, 255]
+    );
+
+    // Ticks and labels are drawn below the bar, nothing next to it.
+    let below = (bar_row + 8) * raster.width;
+    assert!(raster.pixels[below..].iter().any(|pixel| pixel[3] > 0));
+    assert_eq!(raster.pixel(0, bar_row), [0, 0, 0, 0]);
+}
@@ -1,8 +1,57 @@
+pub mod airspace;
+pub mod boundary;
+pub mod brute_force;
 pub mod btree;
 pub mod colors;
+pub mod compare;
+pub mod cone_cache;
+pub mod cone_stats;
+pub mod config;
+pub mod contours;
+pub mod coordinates;
+pub mod czml;
+pub mod datasets;
+pub mod dem_download;
+pub mod distance_transform;
+pub mod drift;
+pub mod flight_path;
+pub mod geojson;
 pub mod height_data;
+pub mod hgt_manifest;
+pub mod hike;
+pub mod hillshade;
+pub mod igc;
+pub mod job_queue;
+pub mod land_cover;
+pub mod landing;
+pub mod launch_score;
+pub mod legend;
 pub mod line;
+pub mod load_test;
+pub mod lru_cache;
+pub mod margin;
+pub mod metrics;
+pub mod mvt;
+pub mod node_frame;
+pub mod open_elevation;
+pub mod polar;
 pub mod pqueue;
+pub mod profile;
+pub mod raster_tile;
+pub mod region;
+pub mod replay;
+pub mod reverse;
+pub mod route;
+#[cfg(feature = "scripting")]
+pub mod scripting;
 pub mod search;
+pub mod sectors;
+pub mod storage;
+pub mod synthetic;
 pub mod textsearch;
 pub mod types;
+pub mod usage;
+pub mod view;
+pub mod waypoints;
+pub mod wind;
+pub mod zip;
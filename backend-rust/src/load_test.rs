@@ -0,0 +1,43 @@
+/// Starts of the searches of a load test. Few enough that concurrent
+/// requests often miss the same cache entry at the same time.
+const STARTS: [(f32, f32); 3] = [(47.0, 11.0), (46.5, 8.0), (45.9, 6.9)];
+const TERRAINS: [&str; 3] = ["cone", "ridge", "flat"];
+/// Kinds of requests in the mix, see `load_test_uris`.
+const REQUEST_KINDS: usize = 8;
+
+/// Requests of a load test on synthetic terrain, so no DEM files are needed.
+/// Mixes repeated searches, searches that differ in the cell size, paged
+/// results, elevation lookups and cheap endpoints reading the global state.
+/// The same `count` always gives the same requests.
+pub fn load_test_uris(count: usize) -> Vec<String> {
+    (0..count)
+        .map(|i| {
+            let (lat, lon) = STARTS[(i / REQUEST_KINDS) % STARTS.len()];
+            let terrain = TERRAINS[(i / (REQUEST_KINDS * STARTS.len())) % TERRAINS.len()];
+            let search = |cell_size: usize| {
+                format!(
+                    "/flight_cone?lat={lat}&lon={lon}&cell_size={cell_size}&synthetic={terrain}"
+                )
+            };
+            match i % REQUEST_KINDS {
+                0 | 1 => search(200),
+                2 => format!("{}&offset=0&limit=100", search(200)),
+                // Misses the cache, there are 299 other cell sizes.
+                3 => search(201 + (i / REQUEST_KINDS) % 299),
+                4 => format!("/api/v1/lookup?locations={lat},{lon}|{},{lon}", lat + 0.5),
+                5 => format!("/legend?hmin=0&hmax={}&colormap=viridis", 1000 + i),
+                6 => "/metrics".to_string(),
+                _ => "/stats".to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Path of `uri` without the query, to group the results of a load test.
+pub fn uri_path(uri: &str) -> &str {
+    uri.split_once('?').map_or(uri, |(path, _)| path)
+}
+
+#[cfg(test)]
+#[path = "./load_test_test.rs"]
+mod load_test_test;
@@ -0,0 +1,44 @@
+use std::collections::HashSet;
+
+use super::{load_test_uris, uri_path};
+
+#[test]
+fn test_load_test_uris() {
+    let uris = load_test_uris(200);
+    assert_eq!(uris.len(), 200);
+    assert_eq!(uris, load_test_uris(200));
+    assert_eq!(
+        uris[0],
+        "/flight_cone?lat=47&lon=11&cell_size=200&synthetic=cone"
+    );
+    assert_eq!(uris[0], uris[1]);
+
+    let paths = uris.iter().map(|uri| uri_path(uri)).collect::<HashSet<_>>();
+    assert_eq!(
+        paths,
+        HashSet::from([
+            "/flight_cone",
+            "/api/v1/lookup",
+            "/legend",
+            "/metrics",
+            "/stats"
+        ])
+    );
+    // Every terrain and start is searched.
+    for part in ["synthetic=ridge", "synthetic=flat", "lat=46.5", "lat=45.9"] {
+        assert!(uris.iter().any(|uri| uri.contains(part)), "{part}");
+    }
+    // The searches with other cell sizes all miss the cache.
+    let misses = uris
+        .iter()
+        .filter(|uri| uri.contains("synthetic") && !uri.contains("cell_size=200"))
+        .collect::<Vec<_>>();
+    assert_eq!(misses.len(), 25);
+    assert_eq!(misses.iter().collect::<HashSet<_>>().len(), 25);
+}
+
+#[test]
+fn test_uri_path() {
+    assert_eq!(uri_path("/stats"), "/stats");
+    assert_eq!(uri_path("/legend?hmin=0"), "/legend");
+}
@@ -0,0 +1,324 @@
+use std::{
+    borrow::Borrow,
+    collections::{BTreeMap, HashMap},
+    hash::Hash,
+    time::{Duration, Instant},
+};
+
+use cached::Cached;
+use serde::Serialize;
+
+/// Statistics of a `ByteLimitedCache`, e.g. to monitor its memory use.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct CacheStats {
+    pub entries: usize,
+    /// Estimated size of all values.
+    pub bytes: usize,
+    pub max_entries: usize,
+    pub max_bytes: usize,
+    pub ttl_secs: Option<u64>,
+    pub hits: u64,
+    pub misses: u64,
+    /// Entries removed to stay within the limits or because they expired.
+    pub evictions: u64,
+}
+
+struct Entry<V> {
+    value: V,
+    bytes: usize,
+    inserted: Instant,
+    last_use: u64,
+}
+
+/// Least recently used cache bounded by the number of entries and the
+/// estimated size of its values, which entries may expire from. The newest
+/// entry is never evicted, so a value larger than `max_bytes` stays until
+/// the next one is inserted.
+pub struct ByteLimitedCache<K, V> {
+    entries: HashMap<K, Entry<V>>,
+    /// Keys by their last use, least recent first.
+    order: BTreeMap<u64, K>,
+    uses: u64,
+    bytes: usize,
+    max_entries: usize,
+    max_bytes: usize,
+    ttl: Option<Duration>,
+    size_of: fn(&V) -> usize,
+    /// Called with whether a lookup was a hit, e.g. to export metrics.
+    observer: Option<fn(bool)>,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+}
+
+impl<K: Hash + Eq + Clone, V> ByteLimitedCache<K, V> {
+    /// `size_of` estimates the bytes a value occupies.
+    pub fn new(
+        max_entries: usize,
+        max_bytes: usize,
+        ttl: Option<Duration>,
+        size_of: fn(&V) -> usize,
+    ) -> Self {
+        ByteLimitedCache {
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+            uses: 0,
+            bytes: 0,
+            max_entries,
+            max_bytes,
+            ttl,
+            size_of,
+            observer: None,
+            hits: 0,
+            misses: 0,
+            evictions: 0,
+        }
+    }
+
+    /// Calls `observer` on every lookup with whether it was a hit.
+    pub fn with_observer(mut self, observer: fn(bool)) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    fn observe(&mut self, hit: bool) {
+        if hit {
+            self.hits += 1;
+        } else {
+            self.misses += 1;
+        }
+        if let Some(observer) = self.observer {
+            observer(hit);
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            entries: self.entries.len(),
+            bytes: self.bytes,
+            max_entries: self.max_entries,
+            max_bytes: self.max_bytes,
+            ttl_secs: self.ttl.map(|ttl| ttl.as_secs()),
+            hits: self.hits,
+            misses: self.misses,
+            evictions: self.evictions,
+        }
+    }
+
+    fn is_expired(&self, inserted: Instant, now: Instant) -> bool {
+        self.ttl
+            .is_some_and(|ttl| now.saturating_duration_since(inserted) > ttl)
+    }
+
+    fn remove<Q>(&mut self, k: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let entry = self.entries.remove(k)?;
+        self.order.remove(&entry.last_use);
+        self.bytes -= entry.bytes;
+        Some(entry.value)
+    }
+
+    /// Marks an entry as used, removing it if it expired. Returns whether
+    /// the entry is present.
+    fn touch<Q>(&mut self, k: &Q, now: Instant) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let Some((last_use, inserted)) = self
+            .entries
+            .get(k)
+            .map(|entry| (entry.last_use, entry.inserted))
+        else {
+            self.observe(false);
+            return false;
+        };
+        if self.is_expired(inserted, now) {
+            self.remove(k);
+            self.evictions += 1;
+            self.observe(false);
+            return false;
+        }
+
+        self.uses += 1;
+        let key = self
+            .order
+            .remove(&last_use)
+            .expect("Every entry is in the order");
+        self.order.insert(self.uses, key);
+        self.entries.get_mut(k).unwrap().last_use = self.uses;
+        self.observe(true);
+        true
+    }
+
+    /// Removes expired entries, then the least recently used ones until the
+    /// cache is within its limits.
+    fn evict(&mut self, now: Instant) {
+        let expired = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| {
+                entry.last_use != self.uses && self.is_expired(entry.inserted, now)
+            })
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+        for key in expired {
+            self.remove(&key);
+            self.evictions += 1;
+        }
+
+        while (self.entries.len() > self.max_entries || self.bytes > self.max_bytes)
+            && self.entries.len() > 1
+        {
+            let (_, key) = self.order.pop_first().expect("The cache is not empty");
+            let entry = self.entries.remove(&key).expect("Every key is an entry");
+            self.bytes -= entry.bytes;
+            self.evictions += 1;
+        }
+    }
+
+    /// Whether a live entry exists, without marking it as used or counting
+    /// a hit or miss.
+    pub fn contains_at<Q>(&self, k: &Q, now: Instant) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.entries
+            .get(k)
+            .is_some_and(|entry| !self.is_expired(entry.inserted, now))
+    }
+
+    pub fn get_at<Q>(&mut self, k: &Q, now: Instant) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if self.touch(k, now) {
+            self.entries.get(k).map(|entry| &entry.value)
+        } else {
+            None
+        }
+    }
+
+    /// Inserts a value as the most recently used entry and returns the
+    /// previous value of the key.
+    pub fn set_at(&mut self, k: K, v: V, now: Instant) -> Option<V> {
+        let previous = self.remove(&k);
+        let bytes = (self.size_of)(&v);
+        self.uses += 1;
+        self.order.insert(self.uses, k.clone());
+        self.entries.insert(
+            k,
+            Entry {
+                value: v,
+                bytes,
+                inserted: now,
+                last_use: self.uses,
+            },
+        );
+        self.bytes += bytes;
+        self.evict(now);
+        previous
+    }
+}
+
+impl<K: Hash + Eq + Clone, V> Cached<K, V> for ByteLimitedCache<K, V> {
+    fn cache_get<Q>(&mut self, k: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_at(k, Instant::now())
+    }
+
+    /// Changes of the size of the value are not accounted for.
+    fn cache_get_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if self.touch(k, Instant::now()) {
+            self.entries.get_mut(k).map(|entry| &mut entry.value)
+        } else {
+            None
+        }
+    }
+
+    fn cache_set(&mut self, k: K, v: V) -> Option<V> {
+        self.set_at(k, v, Instant::now())
+    }
+
+    fn cache_get_or_set_with<F: FnOnce() -> V>(&mut self, k: K, f: F) -> &mut V {
+        let now = Instant::now();
+        if !self.touch(&k, now) {
+            self.set_at(k.clone(), f(), now);
+        }
+        &mut self.entries.get_mut(&k).unwrap().value
+    }
+
+    fn cache_try_get_or_set_with<F: FnOnce() -> Result<V, E>, E>(
+        &mut self,
+        k: K,
+        f: F,
+    ) -> Result<&mut V, E> {
+        let now = Instant::now();
+        if !self.touch(&k, now) {
+            self.set_at(k.clone(), f()?, now);
+        }
+        Ok(&mut self.entries.get_mut(&k).unwrap().value)
+    }
+
+    fn cache_remove<Q>(&mut self, k: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.remove(k)
+    }
+
+    fn cache_clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+        self.bytes = 0;
+    }
+
+    fn cache_reset(&mut self) {
+        self.entries = HashMap::new();
+        self.order = BTreeMap::new();
+        self.bytes = 0;
+    }
+
+    fn cache_reset_metrics(&mut self) {
+        self.hits = 0;
+        self.misses = 0;
+        self.evictions = 0;
+    }
+
+    fn cache_size(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn cache_hits(&self) -> Option<u64> {
+        Some(self.hits)
+    }
+
+    fn cache_misses(&self) -> Option<u64> {
+        Some(self.misses)
+    }
+
+    fn cache_capacity(&self) -> Option<usize> {
+        Some(self.max_entries)
+    }
+
+    fn cache_lifespan(&self) -> Option<Duration> {
+        self.ttl
+    }
+}
+
+#[cfg(test)]
+#[path = "./lru_cache_test.rs"]
+mod lru_cache_test;
@@ -0,0 +1,131 @@
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use cached::Cached;
+
+use super::ByteLimitedCache;
+
+fn cache(max_entries: usize, max_bytes: usize) -> ByteLimitedCache<&'static str, Vec<u8>> {
+    ByteLimitedCache::new(max_entries, max_bytes, None, Vec::len)
+}
+
+#[test]
+fn test_evicts_least_recently_used() {
+    let mut cache = cache(3, 100);
+    let now = Instant::now();
+    cache.set_at("a", vec![0; 10], now);
+    cache.set_at("b", vec![0; 10], now);
+    cache.set_at("c", vec![0; 10], now);
+    // "a" is used again, so "b" is the least recently used entry.
+    assert!(cache.get_at("a", now).is_some());
+    cache.set_at("d", vec![0; 10], now);
+
+    assert!(cache.get_at("b", now).is_none());
+    assert!(cache.get_at("a", now).is_some());
+    assert!(cache.get_at("c", now).is_some());
+    assert!(cache.get_at("d", now).is_some());
+
+    let stats = cache.stats();
+    assert_eq!(stats.entries, 3);
+    assert_eq!(stats.bytes, 30);
+    assert_eq!(stats.evictions, 1);
+    assert_eq!(stats.hits, 4);
+    assert_eq!(stats.misses, 1);
+}
+
+#[test]
+fn test_evicts_by_size() {
+    let mut cache = cache(10, 100);
+    let now = Instant::now();
+    cache.set_at("a", vec![0; 40], now);
+    cache.set_at("b", vec![0; 40], now);
+    cache.set_at("c", vec![0; 40], now);
+    assert_eq!(cache.cache_size(), 2);
+    assert_eq!(cache.stats().bytes, 80);
+    assert!(cache.get_at("a", now).is_none());
+
+    // Replacing a value updates the size.
+    assert_eq!(cache.set_at("b", vec![0; 10], now), Some(vec![0; 40]));
+    assert_eq!(cache.stats().bytes, 50);
+
+    // The newest entry is kept even if it is too large on its own.
+    cache.set_at("huge", vec![0; 200], now);
+    assert_eq!(cache.cache_size(), 1);
+    assert_eq!(cache.get_at("huge", now).map(Vec::len), Some(200));
+}
+
+#[test]
+fn test_expires_entries() {
+    let mut cache = ByteLimitedCache::new(10, 100, Some(Duration::from_secs(60)), Vec::len);
+    let now = Instant::now();
+    cache.set_at("a", vec![0; 10], now);
+    cache.set_at("b", vec![0; 10], now + Duration::from_secs(30));
+
+    assert!(cache.get_at("a", now + Duration::from_secs(59)).is_some());
+    assert!(cache.get_at("a", now + Duration::from_secs(61)).is_none());
+    assert!(cache.get_at("b", now + Duration::from_secs(61)).is_some());
+
+    // Expired entries are dropped when inserting.
+    cache.set_at("c", vec![0; 10], now + Duration::from_secs(100));
+    assert_eq!(cache.cache_size(), 1);
+    assert_eq!(cache.stats().bytes, 10);
+    assert_eq!(cache.stats().evictions, 2);
+}
+
+#[test]
+fn test_cached_trait() {
+    let mut cache = cache(2, 100);
+    assert_eq!(cache.cache_get_or_set_with("a", || vec![1]), &vec![1]);
+    assert_eq!(cache.cache_get_or_set_with("a", || vec![2]), &vec![1]);
+    assert_eq!(
+        cache.cache_try_get_or_set_with("b", || Err::<Vec<u8>, _>("failed")),
+        Err("failed")
+    );
+    cache.cache_get_mut("a").unwrap().push(3);
+    assert_eq!(cache.cache_get("a"), Some(&vec![1, 3]));
+    assert_eq!(cache.cache_remove("a"), Some(vec![1, 3]));
+    assert_eq!(cache.stats().bytes, 0);
+
+    cache.cache_set("c", vec![0; 10]);
+    cache.cache_clear();
+    assert_eq!(cache.cache_size(), 0);
+    assert_eq!(cache.stats().bytes, 0);
+}
+
+#[test]
+fn test_observer() {
+    static HITS: AtomicUsize = AtomicUsize::new(0);
+    static MISSES: AtomicUsize = AtomicUsize::new(0);
+    let mut cache = cache(3, 100).with_observer(|hit| {
+        let counter = if hit { &HITS } else { &MISSES };
+        counter.fetch_add(1, Ordering::Relaxed);
+    });
+    let now = Instant::now();
+    cache.set_at("a", vec![0; 10], now);
+    assert!(cache.get_at("a", now).is_some());
+    assert!(cache.get_at("b", now).is_none());
+    assert!(cache.cache_get("a").is_some());
+
+    assert_eq!(HITS.load(Ordering::Relaxed), 2);
+    assert_eq!(MISSES.load(Ordering::Relaxed), 1);
+    assert_eq!(cache.stats().hits, 2);
+}
+
+#[test]
+fn test_contains_does_not_use_the_entry() {
+    let mut cache = cache(2, 100);
+    let now = Instant::now();
+    cache.set_at("a", vec![0; 10], now);
+    cache.set_at("b", vec![0; 10], now);
+    assert!(cache.contains_at("a", now));
+    assert!(!cache.contains_at("c", now));
+    // "a" is still the least recently used entry.
+    cache.set_at("c", vec![0; 10], now);
+    assert!(!cache.contains_at("a", now));
+
+    let stats = cache.stats();
+    assert_eq!(stats.hits, 0);
+    assert_eq!(stats.misses, 0);
+}
@@ -0,0 +1,48 @@
+/// Safety margin at a distance from the start.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MarginPoint {
+    /// Distance from the start in meters.
+    pub distance: f32,
+    /// Height in meters to keep above the terrain.
+    pub margin: f32,
+}
+
+/// Parses a safety margin ramp given as comma separated `distance:margin`
+/// pairs in meters, e.g. `0:0,3000:150` for no margin at the start that
+/// grows to 150 m at 3 km. Distances must be increasing.
+pub fn parse_safety_margin_ramp(value: &str) -> Result<Vec<MarginPoint>, String> {
+    let mut result: Vec<MarginPoint> = vec![];
+    for pair in value.split(',') {
+        let (distance, margin) = pair
+            .split_once(':')
+            .ok_or_else(|| format!("Ramp point {pair} is not of the form distance:margin"))?;
+        let point = MarginPoint {
+            distance: distance
+                .trim()
+                .parse()
+                .map_err(|e| format!("Bad ramp distance {distance}: {e}"))?,
+            margin: margin
+                .trim()
+                .parse()
+                .map_err(|e| format!("Bad ramp margin {margin}: {e}"))?,
+        };
+        if ![point.distance, point.margin]
+            .iter()
+            .all(|value| value.is_finite() && *value >= 0.0)
+        {
+            return Err(format!("Ramp point {pair} must not be negative"));
+        }
+        if result
+            .last()
+            .is_some_and(|last| last.distance >= point.distance)
+        {
+            return Err("Ramp distances must be increasing".to_string());
+        }
+        result.push(point);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+#[path = "./margin_test.rs"]
+mod margin_test;
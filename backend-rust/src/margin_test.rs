@@ -0,0 +1,23 @@
+use super::{parse_safety_margin_ramp, MarginPoint};
+
+#[test]
+fn test_parse_safety_margin_ramp() {
+    assert_eq!(
+        parse_safety_margin_ramp("0:0, 3000:150").unwrap(),
+        vec![
+            MarginPoint {
+                distance: 0.0,
+                margin: 0.0
+            },
+            MarginPoint {
+                distance: 3000.0,
+                margin: 150.0
+            }
+        ]
+    );
+    assert!(parse_safety_margin_ramp("").is_err());
+    assert!(parse_safety_margin_ramp("3000:150,1000:50").is_err());
+    assert!(parse_safety_margin_ramp("0:-10").is_err());
+    assert!(parse_safety_margin_ramp("0:inf").is_err());
+    assert!(parse_safety_margin_ramp("1000").is_err());
+}
@@ -0,0 +1,302 @@
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+
+/// Caches that save work at different stages of a request.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum CacheTier {
+    /// Searches in memory.
+    Search,
+    /// Images rendered from searches.
+    Raster,
+    /// Map tiles in memory.
+    Png,
+    /// DEM tiles read from disk.
+    Hgt,
+    /// Map tiles in the cache storage, a miss fetches them upstream.
+    Tiles,
+}
+
+impl CacheTier {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CacheTier::Search => "search",
+            CacheTier::Raster => "raster",
+            CacheTier::Png => "png",
+            CacheTier::Hgt => "hgt",
+            CacheTier::Tiles => "tiles",
+        }
+    }
+}
+
+/// Label of accesses outside of a request, e.g. from worker threads.
+pub const OTHER_ENDPOINT: &str = "other";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct HitsAndMisses {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// Hits and misses of the caches per tier and endpoint.
+#[derive(Default)]
+pub struct CacheMetrics {
+    counters: Mutex<BTreeMap<(CacheTier, String), HitsAndMisses>>,
+}
+
+impl CacheMetrics {
+    pub fn record(&self, tier: CacheTier, endpoint: &str, hit: bool) {
+        let mut counters = self.counters.lock().unwrap();
+        let entry = counters.entry((tier, endpoint.to_string())).or_default();
+        if hit {
+            entry.hits += 1;
+        } else {
+            entry.misses += 1;
+        }
+    }
+
+    pub fn get(&self, tier: CacheTier, endpoint: &str) -> HitsAndMisses {
+        self.counters
+            .lock()
+            .unwrap()
+            .get(&(tier, endpoint.to_string()))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Counters in the Prometheus text exposition format.
+    pub fn to_prometheus(&self) -> String {
+        let counters = self.counters.lock().unwrap();
+        let mut result = String::new();
+        for (name, help, hits) in [
+            (
+                "hikeandfly_cache_hits_total",
+                "Lookups answered from the cache.",
+                true,
+            ),
+            (
+                "hikeandfly_cache_misses_total",
+                "Lookups that had to compute or fetch the value.",
+                false,
+            ),
+        ] {
+            writeln!(result, "# HELP {name} {help}").unwrap();
+            writeln!(result, "# TYPE {name} counter").unwrap();
+            for ((tier, endpoint), counts) in counters.iter() {
+                writeln!(
+                    result,
+                    "{name}{{tier=\"{}\",endpoint=\"{}\"}} {}",
+                    tier.name(),
+                    endpoint.replace('\\', "\\\\").replace('"', "\\\""),
+                    if hits { counts.hits } else { counts.misses }
+                )
+                .unwrap();
+            }
+        }
+        result
+    }
+}
+
+/// Metrics of the caches of this process.
+pub fn cache_metrics() -> &'static CacheMetrics {
+    static INSTANCE: OnceLock<CacheMetrics> = OnceLock::new();
+    INSTANCE.get_or_init(CacheMetrics::default)
+}
+
+static CURRENT_ENDPOINT: OnceLock<fn() -> Option<String>> = OnceLock::new();
+
+/// Sets how the endpoint of the request being handled is found, e.g. by the
+/// server. Only the first call has an effect.
+pub fn set_current_endpoint(current_endpoint: fn() -> Option<String>) {
+    let _ = CURRENT_ENDPOINT.set(current_endpoint);
+}
+
+/// Counts a cache lookup for the endpoint of the current request.
+pub fn record_cache_access(tier: CacheTier, hit: bool) {
+    let endpoint = CURRENT_ENDPOINT.get().and_then(|current| current());
+    cache_metrics().record(tier, endpoint.as_deref().unwrap_or(OTHER_ENDPOINT), hit);
+    REQUEST_METRICS.with_borrow_mut(|metrics| {
+        if let Some(metrics) = metrics {
+            let entry = metrics.cache.entry(tier.name()).or_default();
+            if hit {
+                entry.hits += 1;
+            } else {
+                entry.misses += 1;
+            }
+        }
+    });
+}
+
+/// Upper bounds in seconds of the buckets of the request latencies.
+pub const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LatencyHistogram {
+    /// Requests per bucket of `LATENCY_BUCKETS`, not cumulative.
+    pub buckets: [u64; LATENCY_BUCKETS.len()],
+    pub count: u64,
+    /// Sum of the latencies in seconds.
+    pub sum: f64,
+}
+
+/// Latencies of the requests per endpoint.
+#[derive(Default)]
+pub struct LatencyMetrics {
+    histograms: Mutex<BTreeMap<String, LatencyHistogram>>,
+}
+
+impl LatencyMetrics {
+    pub fn record(&self, endpoint: &str, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        let mut histograms = self.histograms.lock().unwrap();
+        let histogram = histograms.entry(endpoint.to_string()).or_default();
+        if let Some(bucket) = LATENCY_BUCKETS.iter().position(|bound| seconds <= *bound) {
+            histogram.buckets[bucket] += 1;
+        }
+        histogram.count += 1;
+        histogram.sum += seconds;
+    }
+
+    pub fn get(&self, endpoint: &str) -> LatencyHistogram {
+        self.histograms
+            .lock()
+            .unwrap()
+            .get(endpoint)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Histograms in the Prometheus text exposition format.
+    pub fn to_prometheus(&self) -> String {
+        let name = "hikeandfly_request_duration_seconds";
+        let mut result = String::new();
+        writeln!(result, "# HELP {name} Time to handle a request.").unwrap();
+        writeln!(result, "# TYPE {name} histogram").unwrap();
+        for (endpoint, histogram) in self.histograms.lock().unwrap().iter() {
+            let endpoint = endpoint.replace('\\', "\\\\").replace('"', "\\\"");
+            let mut cumulative = 0;
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(histogram.buckets) {
+                cumulative += count;
+                writeln!(
+                    result,
+                    "{name}_bucket{{endpoint=\"{endpoint}\",le=\"{bound}\"}} {cumulative}"
+                )
+                .unwrap();
+            }
+            writeln!(
+                result,
+                "{name}_bucket{{endpoint=\"{endpoint}\",le=\"+Inf\"}} {}",
+                histogram.count
+            )
+            .unwrap();
+            writeln!(
+                result,
+                "{name}_sum{{endpoint=\"{endpoint}\"}} {}",
+                histogram.sum
+            )
+            .unwrap();
+            writeln!(
+                result,
+                "{name}_count{{endpoint=\"{endpoint}\"}} {}",
+                histogram.count
+            )
+            .unwrap();
+        }
+        result
+    }
+}
+
+/// Request latencies of this process.
+pub fn latency_metrics() -> &'static LatencyMetrics {
+    static INSTANCE: OnceLock<LatencyMetrics> = OnceLock::new();
+    INSTANCE.get_or_init(LatencyMetrics::default)
+}
+
+/// Stages of computing a cone, timed for `RequestMetrics`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stage {
+    /// Reading the heights of the grid from the DEM tiles.
+    DemLoad,
+    /// Scaling the grid to the cell size.
+    Scaling,
+    Search,
+    /// Cropping the grid to the explored cells.
+    Reindex,
+    /// Converting the nodes for the response.
+    Serialization,
+}
+
+impl Stage {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Stage::DemLoad => "dem_load",
+            Stage::Scaling => "scaling",
+            Stage::Search => "search",
+            Stage::Reindex => "reindex",
+            Stage::Serialization => "serialization",
+        }
+    }
+}
+
+/// Where the time of one request went, see `collect_request_metrics`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct RequestMetrics {
+    /// Milliseconds per stage by `Stage::name`, summed if a stage ran
+    /// several times. Stages that did not run, e.g. because the search was
+    /// cached, are missing.
+    pub timings_ms: BTreeMap<&'static str, f64>,
+    /// Cache lookups per `CacheTier::name`.
+    pub cache: BTreeMap<&'static str, HitsAndMisses>,
+}
+
+thread_local! {
+    static REQUEST_METRICS: RefCell<Option<RequestMetrics>> = const { RefCell::new(None) };
+}
+
+/// Runs `f` and collects the stages timed and the caches looked up on this
+/// thread meanwhile. Work on other threads, e.g. of parallel searches, is
+/// not included.
+pub fn collect_request_metrics<T>(f: impl FnOnce() -> T) -> (T, RequestMetrics) {
+    let outer = REQUEST_METRICS.replace(Some(RequestMetrics::default()));
+    let result = f();
+    let metrics = REQUEST_METRICS.replace(outer).unwrap_or_default();
+    (result, metrics)
+}
+
+/// Adds the time until it is dropped to its stage, see `time_stage`.
+pub struct StageTimer {
+    stage: Stage,
+    start: Instant,
+}
+
+impl Drop for StageTimer {
+    fn drop(&mut self) {
+        let elapsed = self.start.elapsed().as_secs_f64() * 1000.0;
+        REQUEST_METRICS.with_borrow_mut(|metrics| {
+            if let Some(metrics) = metrics {
+                *metrics.timings_ms.entry(self.stage.name()).or_default() += elapsed;
+            }
+        });
+    }
+}
+
+/// Times `stage` until the returned timer is dropped, for
+/// `collect_request_metrics`.
+pub fn time_stage(stage: Stage) -> StageTimer {
+    StageTimer {
+        stage,
+        start: Instant::now(),
+    }
+}
+
+#[cfg(test)]
+#[path = "./metrics_test.rs"]
+mod metrics_test;
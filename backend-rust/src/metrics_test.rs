@@ -0,0 +1,109 @@
+use std::time::Duration;
+
+use super::{
+    collect_request_metrics, record_cache_access, time_stage, CacheMetrics, CacheTier,
+    HitsAndMisses, LatencyMetrics, Stage,
+};
+
+#[test]
+fn test_cache_metrics() {
+    let metrics = CacheMetrics::default();
+    metrics.record(CacheTier::Search, "/agl_image", true);
+    metrics.record(CacheTier::Search, "/agl_image", false);
+    metrics.record(CacheTier::Search, "/agl_image", true);
+    metrics.record(CacheTier::Hgt, "/flight_cone", false);
+
+    assert_eq!(
+        metrics.get(CacheTier::Search, "/agl_image"),
+        HitsAndMisses { hits: 2, misses: 1 }
+    );
+    assert_eq!(
+        metrics.get(CacheTier::Search, "/flight_cone"),
+        HitsAndMisses::default()
+    );
+
+    let text = metrics.to_prometheus();
+    assert!(text.contains("# TYPE hikeandfly_cache_hits_total counter\n"));
+    assert!(
+        text.contains("hikeandfly_cache_hits_total{tier=\"search\",endpoint=\"/agl_image\"} 2\n")
+    );
+    assert!(
+        text.contains("hikeandfly_cache_misses_total{tier=\"search\",endpoint=\"/agl_image\"} 1\n")
+    );
+    assert!(
+        text.contains("hikeandfly_cache_misses_total{tier=\"hgt\",endpoint=\"/flight_cone\"} 1\n")
+    );
+}
+
+#[test]
+fn test_cache_metrics_escape_labels() {
+    let metrics = CacheMetrics::default();
+    metrics.record(CacheTier::Raster, "a\"b", true);
+    assert!(metrics
+        .to_prometheus()
+        .contains("{tier=\"raster\",endpoint=\"a\\\"b\"} 1\n"));
+}
+
+#[test]
+fn test_latency_metrics() {
+    let metrics = LatencyMetrics::default();
+    metrics.record("/flight_cone", Duration::from_millis(20));
+    metrics.record("/flight_cone", Duration::from_millis(300));
+    metrics.record("/flight_cone", Duration::from_secs(60));
+
+    let histogram = metrics.get("/flight_cone");
+    assert_eq!(histogram.count, 3);
+    assert!((histogram.sum - 60.32).abs() < 1e-9);
+    assert_eq!(histogram.buckets.iter().sum::<u64>(), 2);
+
+    let text = metrics.to_prometheus();
+    assert!(text.contains("# TYPE hikeandfly_request_duration_seconds histogram\n"));
+    assert!(text.contains(
+        "hikeandfly_request_duration_seconds_bucket{endpoint=\"/flight_cone\",le=\"0.01\"} 0\n"
+    ));
+    assert!(text.contains(
+        "hikeandfly_request_duration_seconds_bucket{endpoint=\"/flight_cone\",le=\"0.025\"} 1\n"
+    ));
+    assert!(text.contains(
+        "hikeandfly_request_duration_seconds_bucket{endpoint=\"/flight_cone\",le=\"10\"} 2\n"
+    ));
+    assert!(text.contains(
+        "hikeandfly_request_duration_seconds_bucket{endpoint=\"/flight_cone\",le=\"+Inf\"} 3\n"
+    ));
+    assert!(
+        text.contains("hikeandfly_request_duration_seconds_count{endpoint=\"/flight_cone\"} 3\n")
+    );
+}
+
+#[test]
+fn test_collect_request_metrics() {
+    // Outside of a collection nothing is recorded.
+    drop(time_stage(Stage::Search));
+
+    let (result, metrics) = collect_request_metrics(|| {
+        {
+            let _timer = time_stage(Stage::DemLoad);
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        drop(time_stage(Stage::Search));
+        drop(time_stage(Stage::Search));
+        record_cache_access(CacheTier::Hgt, true);
+        record_cache_access(CacheTier::Hgt, false);
+        record_cache_access(CacheTier::Search, false);
+        // Nested collections are kept apart.
+        let (_, inner) = collect_request_metrics(|| record_cache_access(CacheTier::Png, true));
+        assert_eq!(inner.cache.len(), 1);
+        42
+    });
+
+    assert_eq!(result, 42);
+    assert!(metrics.timings_ms["dem_load"] >= 5.0);
+    assert!(metrics.timings_ms.contains_key("search"));
+    assert!(!metrics.timings_ms.contains_key("reindex"));
+    assert_eq!(metrics.cache["hgt"], HitsAndMisses { hits: 1, misses: 1 });
+    assert_eq!(
+        metrics.cache["search"],
+        HitsAndMisses { hits: 0, misses: 1 }
+    );
+    assert!(!metrics.cache.contains_key("png"));
+}
@@ -0,0 +1,212 @@
+use std::{collections::HashMap, f32::consts::PI};
+
+use crate::{
+    cone_stats::agl_of,
+    height_data::HeightGrid,
+    search::Node,
+    view::{mercator_y, MAX_ZOOM},
+};
+
+/// Coordinates of a tile go from 0 to `EXTENT` in both directions.
+pub const EXTENT: u32 = 4096;
+/// Features are clipped this far outside of the tile, so polygons of
+/// neighbouring tiles overlap and no seams show.
+const BUFFER: i32 = 64;
+/// Cells smaller than this in tile coordinates are merged into blocks, so
+/// zoomed out tiles stay small.
+const MIN_BLOCK_SIZE: f32 = 4.0;
+/// Name of the layer holding the reachable area.
+pub const LAYER_NAME: &str = "cone";
+
+const MVT_VERSION: u32 = 2;
+const GEOM_TYPE_POLYGON: u32 = 3;
+const CMD_MOVE_TO: u32 = 1;
+const CMD_LINE_TO: u32 = 2;
+const CMD_CLOSE_PATH: u32 = 7;
+
+/// Position of a tile in the Web Mercator tile pyramid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TileIndex {
+    pub z: u8,
+    pub x: u32,
+    pub y: u32,
+}
+
+impl TileIndex {
+    /// Checks that the tile exists at its zoom level.
+    pub fn new(z: u8, x: u32, y: u32) -> Option<TileIndex> {
+        if z > MAX_ZOOM || x >= 1 << z || y >= 1 << z {
+            return None;
+        }
+        Some(TileIndex { z, x, y })
+    }
+
+    /// Position of a longitude in tile coordinates.
+    fn x_of(&self, longitude: f32) -> f32 {
+        let world = (longitude + 180.0) / 360.0 * (1u32 << self.z) as f32;
+        (world - self.x as f32) * EXTENT as f32
+    }
+
+    /// Position of a latitude in tile coordinates, growing southwards.
+    fn y_of(&self, latitude: f32) -> f32 {
+        let world = (1.0 - mercator_y(latitude) / PI) / 2.0 * (1u32 << self.z) as f32;
+        (world - self.y as f32) * EXTENT as f32
+    }
+
+    /// Longitude at `x`, a fraction of the tile width from its west edge.
+    pub fn longitude_at(&self, x: f32) -> f32 {
+        (self.x as f32 + x) / (1u32 << self.z) as f32 * 360.0 - 180.0
+    }
+
+    /// Latitude at `y`, a fraction of the tile height from its north edge.
+    pub fn latitude_at(&self, y: f32) -> f32 {
+        let world = (self.y as f32 + y) / (1u32 << self.z) as f32;
+        (PI * (1.0 - 2.0 * world)).sinh().atan().to_degrees()
+    }
+}
+
+/// Minimal protocol buffer writer for the few field types of the vector
+/// tile schema.
+#[derive(Default)]
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.bytes.push((value as u8 & 0x7f) | 0x80);
+            value >>= 7;
+        }
+        self.bytes.push(value as u8);
+    }
+
+    fn key(&mut self, field: u32, wire_type: u32) {
+        self.varint(((field << 3) | wire_type) as u64);
+    }
+
+    fn uint(&mut self, field: u32, value: u64) {
+        self.key(field, 0);
+        self.varint(value);
+    }
+
+    fn bytes(&mut self, field: u32, value: &[u8]) {
+        self.key(field, 2);
+        self.varint(value.len() as u64);
+        self.bytes.extend_from_slice(value);
+    }
+
+    fn packed(&mut self, field: u32, values: &[u32]) {
+        let mut packed = Writer::default();
+        for value in values {
+            packed.varint(*value as u64);
+        }
+        self.bytes(field, &packed.bytes);
+    }
+}
+
+fn zigzag(value: i32) -> u32 {
+    ((value << 1) ^ (value >> 31)) as u32
+}
+
+fn command(id: u32, count: u32) -> u32 {
+    (id & 0x7) | (count << 3)
+}
+
+/// Geometry commands of a rectangle, clockwise with y growing downwards as
+/// the exterior rings of vector tiles need to be.
+fn rectangle_geometry(left: i32, top: i32, right: i32, bottom: i32) -> Vec<u32> {
+    vec![
+        command(CMD_MOVE_TO, 1),
+        zigzag(left),
+        zigzag(top),
+        command(CMD_LINE_TO, 3),
+        zigzag(right - left),
+        zigzag(0),
+        zigzag(0),
+        zigzag(bottom - top),
+        zigzag(left - right),
+        zigzag(0),
+        command(CMD_CLOSE_PATH, 1),
+    ]
+}
+
+/// Reachable area of a search as a Mapbox Vector Tile with one layer,
+/// `LAYER_NAME`, holding a rectangle per cell with its height above ground
+/// in whole meters as `agl`. Where cells are smaller than a few tile units,
+/// blocks of cells are merged and get the highest AGL of their cells.
+///
+/// Cells are rectangles in Web Mercator as well, so clipping them to the
+/// tile is exact.
+pub fn cone_tile(explored: &[Node], grid: &HeightGrid, tile: TileIndex) -> Vec<u8> {
+    let shape = grid.heights.shape();
+    let (_, lon_resolution) = grid.get_angular_resolution();
+    let cell_width = tile.x_of(grid.longitudes.0 + lon_resolution) - tile.x_of(grid.longitudes.0);
+    let block = (MIN_BLOCK_SIZE / cell_width).ceil().max(1.0) as usize;
+
+    let mut blocks = HashMap::<(usize, usize), f32>::new();
+    for node in explored.iter().filter(|node| node.reachable) {
+        let agl = agl_of(node, grid);
+        let key = (node.ix.0 as usize / block, node.ix.1 as usize / block);
+        let entry = blocks.entry(key).or_insert(agl);
+        *entry = entry.max(agl);
+    }
+
+    // Keeps the rows and columns of the grid in order, so tiles are
+    // reproducible.
+    let mut blocks = blocks.into_iter().collect::<Vec<_>>();
+    blocks.sort_by_key(|(key, _)| *key);
+
+    let clamp = |value: f32| (value.round() as i32).clamp(-BUFFER, EXTENT as i32 + BUFFER);
+    let mut values = Vec::<i64>::new();
+    let mut value_indices = HashMap::<i64, usize>::new();
+    let mut features = Vec::new();
+    for ((row, col), agl) in blocks {
+        // Cells are centered on their coordinates.
+        let rows = (row * block, ((row + 1) * block).min(shape[0]));
+        let cols = (col * block, ((col + 1) * block).min(shape[1]));
+        let (south, west) = grid.coordinates_at(rows.0 as f32 - 0.5, cols.0 as f32 - 0.5);
+        let (north, east) = grid.coordinates_at(rows.1 as f32 - 0.5, cols.1 as f32 - 0.5);
+
+        let (left, right) = (clamp(tile.x_of(west)), clamp(tile.x_of(east)));
+        let (top, bottom) = (clamp(tile.y_of(north)), clamp(tile.y_of(south)));
+        if left == right || top == bottom {
+            continue;
+        }
+
+        let agl = agl.round() as i64;
+        let value = *value_indices.entry(agl).or_insert_with(|| {
+            values.push(agl);
+            values.len() - 1
+        });
+
+        let mut feature = Writer::default();
+        feature.packed(2, &[0, value as u32]);
+        feature.uint(3, GEOM_TYPE_POLYGON as u64);
+        feature.packed(4, &rectangle_geometry(left, top, right, bottom));
+        features.push(feature.bytes);
+    }
+
+    let mut layer = Writer::default();
+    layer.uint(15, MVT_VERSION as u64);
+    layer.bytes(1, LAYER_NAME.as_bytes());
+    for feature in features.iter() {
+        layer.bytes(2, feature);
+    }
+    layer.bytes(3, b"agl");
+    for agl in values {
+        let mut value = Writer::default();
+        // sint_value
+        value.uint(6, zigzag(agl as i32) as u64);
+        layer.bytes(4, &value.bytes);
+    }
+    layer.uint(5, EXTENT as u64);
+
+    let mut tile = Writer::default();
+    tile.bytes(3, &layer.bytes);
+    tile.bytes
+}
+
+#[cfg(test)]
+#[path = "./mvt_test.rs"]
+mod mvt_test;
@@ -0,0 +1,194 @@
+use ndarray::Array2;
+
+use crate::{height_data::HeightGrid, search::Node};
+
+use super::{cone_tile, rectangle_geometry, zigzag, TileIndex, EXTENT, LAYER_NAME};
+
+/// Field number and content of the fields of a message, varints as numbers
+/// and length delimited fields as bytes.
+enum Field {
+    Varint(u32, u64),
+    Bytes(u32, Vec<u8>),
+}
+
+fn read_varint(data: &[u8], position: &mut usize) -> u64 {
+    let mut value = 0;
+    let mut shift = 0;
+    loop {
+        let byte = data[*position];
+        *position += 1;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte < 0x80 {
+            return value;
+        }
+        shift += 7;
+    }
+}
+
+fn read_fields(data: &[u8]) -> Vec<Field> {
+    let mut fields = vec![];
+    let mut position = 0;
+    while position < data.len() {
+        let key = read_varint(data, &mut position);
+        let field = (key >> 3) as u32;
+        match key & 0x7 {
+            0 => fields.push(Field::Varint(field, read_varint(data, &mut position))),
+            2 => {
+                let length = read_varint(data, &mut position) as usize;
+                fields.push(Field::Bytes(
+                    field,
+                    data[position..position + length].to_vec(),
+                ));
+                position += length;
+            }
+            wire_type => panic!("Unexpected wire type {wire_type}"),
+        }
+    }
+    fields
+}
+
+fn read_packed(data: &[u8]) -> Vec<u32> {
+    let mut values = vec![];
+    let mut position = 0;
+    while position < data.len() {
+        values.push(read_varint(data, &mut position) as u32);
+    }
+    values
+}
+
+fn bytes_of(fields: &[Field], number: u32) -> Vec<Vec<u8>> {
+    fields
+        .iter()
+        .filter_map(|field| match field {
+            Field::Bytes(n, bytes) if *n == number => Some(bytes.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn varint_of(fields: &[Field], number: u32) -> Option<u64> {
+    fields.iter().find_map(|field| match field {
+        Field::Varint(n, value) if *n == number => Some(*value),
+        _ => None,
+    })
+}
+
+fn grid() -> HeightGrid {
+    HeightGrid::for_test(
+        Array2::from_elem((10, 10), 1000),
+        (47.0, 47.01),
+        (11.0, 11.01),
+    )
+}
+
+/// Tile containing 47.005, 11.005 at zoom level `z`.
+fn tile_at(z: u8) -> TileIndex {
+    let n = (1u32 << z) as f64;
+    let x = (11.005 + 180.0) / 360.0 * n;
+    let lat = 47.005f64.to_radians();
+    let y = (1.0 - (lat.tan() + 1.0 / lat.cos()).ln() / std::f64::consts::PI) / 2.0 * n;
+    TileIndex::new(z, x as u32, y as u32).unwrap()
+}
+
+#[test]
+fn test_tile_index() {
+    assert!(TileIndex::new(0, 0, 0).is_some());
+    assert!(TileIndex::new(2, 3, 3).is_some());
+    assert!(TileIndex::new(2, 4, 0).is_none());
+    assert!(TileIndex::new(19, 0, 0).is_none());
+
+    let world = TileIndex::new(0, 0, 0).unwrap();
+    assert_eq!(world.x_of(-180.0), 0.0);
+    assert_eq!(world.x_of(0.0), EXTENT as f32 / 2.0);
+    assert!((world.y_of(0.0) - EXTENT as f32 / 2.0).abs() < 0.01);
+    assert!(world.y_of(47.0) < world.y_of(46.0));
+
+    let tile = tile_at(12);
+    assert!((tile.longitude_at(tile.x_of(11.005) / EXTENT as f32) - 11.005).abs() < 1e-4);
+    assert!((tile.latitude_at(tile.y_of(47.005) / EXTENT as f32) - 47.005).abs() < 1e-4);
+    assert_eq!(world.longitude_at(0.5), 0.0);
+    assert!(world.latitude_at(0.5).abs() < 1e-4);
+    assert!(world.latitude_at(0.0) > 85.0);
+}
+
+#[test]
+fn test_rectangle_geometry() {
+    assert_eq!(zigzag(0), 0);
+    assert_eq!(zigzag(-1), 1);
+    assert_eq!(zigzag(1), 2);
+    assert_eq!(zigzag(-2), 3);
+
+    let geometry = rectangle_geometry(10, 20, 30, 50);
+    // MoveTo(10, 20), LineTo(+20, 0), (0, +30), (-20, 0), ClosePath.
+    assert_eq!(geometry, vec![9, 20, 40, 26, 40, 0, 0, 60, 39, 0, 15]);
+}
+
+#[test]
+fn test_cone_tile() {
+    let grid = grid();
+    let explored = vec![
+        Node::for_test((5, 5), 1100.0, true),
+        Node::for_test((5, 6), 1250.4, true),
+        Node::for_test((6, 5), 1100.0, true),
+        Node::for_test((0, 0), 1300.0, false),
+    ];
+
+    let tile = cone_tile(&explored, &grid, tile_at(14));
+    let layers = bytes_of(&read_fields(&tile), 3);
+    assert_eq!(layers.len(), 1);
+    let layer = read_fields(&layers[0]);
+    assert_eq!(varint_of(&layer, 15), Some(2));
+    assert_eq!(bytes_of(&layer, 1), vec![LAYER_NAME.as_bytes().to_vec()]);
+    assert_eq!(varint_of(&layer, 5), Some(EXTENT as u64));
+    assert_eq!(bytes_of(&layer, 3), vec![b"agl".to_vec()]);
+
+    // sint values of 100 and 250 m AGL.
+    let values = bytes_of(&layer, 4)
+        .iter()
+        .map(|value| varint_of(&read_fields(value), 6).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(values, vec![200, 500]);
+
+    let features = bytes_of(&layer, 2);
+    assert_eq!(features.len(), 3);
+    let tags = features
+        .iter()
+        .map(|feature| read_packed(&bytes_of(&read_fields(feature), 2)[0]))
+        .collect::<Vec<_>>();
+    assert_eq!(tags, vec![vec![0, 0], vec![0, 1], vec![0, 0]]);
+    for feature in features.iter() {
+        let fields = read_fields(feature);
+        assert_eq!(varint_of(&fields, 3), Some(3));
+        let geometry = read_packed(&bytes_of(&fields, 4)[0]);
+        assert_eq!(geometry.len(), 11);
+        // Width and height of the cell in tile units.
+        assert!(geometry[4] > 0 && geometry[7] > 0);
+    }
+}
+
+#[test]
+fn test_cone_tile_merges_small_cells() {
+    let grid = grid();
+    let explored = (0..10)
+        .flat_map(|row| {
+            (0..10).map(move |col| Node::for_test((row, col), 1000.0 + col as f32, true))
+        })
+        .collect::<Vec<_>>();
+
+    let count_features = |tile: &[u8]| {
+        let layer = read_fields(&bytes_of(&read_fields(tile), 3)[0]);
+        bytes_of(&layer, 2).len()
+    };
+    assert_eq!(
+        count_features(&cone_tile(&explored, &grid, tile_at(13))),
+        100
+    );
+    // At zoom level 6 a cell is less than a tile unit wide, so blocks of
+    // 6x6 cells are merged.
+    let zoomed_out = cone_tile(&explored, &grid, tile_at(6));
+    assert_eq!(count_features(&zoomed_out), 4);
+
+    // Tiles away from the cone are empty.
+    let elsewhere = TileIndex::new(8, 0, 0).unwrap();
+    assert_eq!(count_features(&cone_tile(&explored, &grid, elsewhere)), 0);
+}
@@ -0,0 +1,118 @@
+use std::io::{Cursor, Read};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use crate::search::{GridIx, GridIxType};
+
+/// Bytes of one grid index, 2 or 4 with the `wide_grid_index` feature.
+const INDEX_WIDTH: u8 = std::mem::size_of::<GridIxType>() as u8;
+
+/// Node of the flight cone stream, with a reference only where it differs
+/// from the one of the previous node.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StreamNode {
+    pub ix: GridIx,
+    pub reference: Option<GridIx>,
+}
+
+#[allow(clippy::useless_conversion)]
+fn write_index(result: &mut Vec<u8>, value: GridIxType) {
+    // Writing to a vec can not fail.
+    if INDEX_WIDTH == 2 {
+        result
+            .write_u16::<LittleEndian>(value.try_into().unwrap())
+            .unwrap();
+    } else {
+        result.write_u32::<LittleEndian>(value.into()).unwrap();
+    }
+}
+
+fn read_index(reader: &mut Cursor<&[u8]>, width: u8) -> Option<GridIxType> {
+    let value = match width {
+        2 => u32::from(reader.read_u16::<LittleEndian>().ok()?),
+        4 => reader.read_u32::<LittleEndian>().ok()?,
+        _ => return None,
+    };
+    GridIxType::try_from(value).ok()
+}
+
+/// Binary frame of the flight cone stream, all values little endian:
+///
+/// - `u8` width of the grid indices in bytes, 2 or 4
+/// - `u32` number of nodes `n`
+/// - `ceil(n / 8)` bytes with one bit per node, least significant first,
+///   set for the nodes that carry a reference
+/// - `n` pairs of grid indices
+/// - one pair of grid indices for every node that carries a reference
+///
+/// A node without a reference is usually 4 bytes, compared to about 15
+/// characters of JSON.
+pub fn encode_node_frame(nodes: &[StreamNode]) -> Vec<u8> {
+    let width = INDEX_WIDTH as usize;
+    let mut result = Vec::with_capacity(5 + nodes.len().div_ceil(8) + nodes.len() * 2 * width);
+    result.push(INDEX_WIDTH);
+    result
+        .write_u32::<LittleEndian>(nodes.len() as u32)
+        .unwrap();
+
+    let mut has_reference = vec![0u8; nodes.len().div_ceil(8)];
+    for (i, node) in nodes.iter().enumerate() {
+        if node.reference.is_some() {
+            has_reference[i / 8] |= 1 << (i % 8);
+        }
+    }
+    result.extend_from_slice(&has_reference);
+
+    for node in nodes {
+        write_index(&mut result, node.ix.0);
+        write_index(&mut result, node.ix.1);
+    }
+    for reference in nodes.iter().filter_map(|node| node.reference) {
+        write_index(&mut result, reference.0);
+        write_index(&mut result, reference.1);
+    }
+    result
+}
+
+/// Reads a frame written by `encode_node_frame`, also one written by a build
+/// with a different index width if its indices fit.
+pub fn decode_node_frame(data: &[u8]) -> Option<Vec<StreamNode>> {
+    let mut reader = Cursor::new(data);
+    let width = reader.read_u8().ok()?;
+    let count = reader.read_u32::<LittleEndian>().ok()? as usize;
+    // Guards the allocations below against corrupt counts.
+    if data.len() < 5 + count.div_ceil(8) + count * 2 * width as usize {
+        return None;
+    }
+
+    let mut has_reference = vec![0u8; count.div_ceil(8)];
+    reader.read_exact(&mut has_reference).ok()?;
+
+    let mut nodes = Vec::with_capacity(count);
+    for _ in 0..count {
+        nodes.push(StreamNode {
+            ix: (
+                read_index(&mut reader, width)?,
+                read_index(&mut reader, width)?,
+            ),
+            reference: None,
+        });
+    }
+    for (i, node) in nodes.iter_mut().enumerate() {
+        if has_reference[i / 8] & (1 << (i % 8)) != 0 {
+            node.reference = Some((
+                read_index(&mut reader, width)?,
+                read_index(&mut reader, width)?,
+            ));
+        }
+    }
+
+    if reader.position() as usize != data.len() {
+        return None;
+    }
+    Some(nodes)
+}
+
+#[cfg(test)]
+#[path = "./node_frame_test.rs"]
+mod node_frame_test;
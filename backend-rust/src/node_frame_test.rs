@@ -0,0 +1,52 @@
+use super::{decode_node_frame, encode_node_frame, StreamNode, INDEX_WIDTH};
+
+fn nodes() -> Vec<StreamNode> {
+    (0..10)
+        .map(|i| StreamNode {
+            ix: (i, 300 + i),
+            reference: (i % 4 == 0).then_some((i + 1, 299)),
+        })
+        .collect()
+}
+
+#[test]
+fn test_node_frame_roundtrip() {
+    let nodes = nodes();
+    let frame = encode_node_frame(&nodes);
+
+    let width = INDEX_WIDTH as usize;
+    assert_eq!(frame[0], INDEX_WIDTH);
+    assert_eq!(frame.len(), 5 + 2 + 10 * 2 * width + 3 * 2 * width);
+    // References of nodes 0, 4 and 8.
+    assert_eq!(&frame[5..7], &[0b0001_0001, 0b0000_0001]);
+
+    assert_eq!(decode_node_frame(&frame), Some(nodes));
+    assert_eq!(decode_node_frame(&encode_node_frame(&[])), Some(vec![]));
+}
+
+#[test]
+fn test_decode_node_frame_reads_u16_frames() {
+    let frame = [2, 1, 0, 0, 0, 1, 7, 0, 9, 1, 6, 0, 8, 1];
+    assert_eq!(
+        decode_node_frame(&frame),
+        Some(vec![StreamNode {
+            ix: (7, 265),
+            reference: Some((6, 264)),
+        }])
+    );
+}
+
+#[test]
+fn test_decode_node_frame_rejects_invalid_frames() {
+    let frame = encode_node_frame(&nodes());
+    assert_eq!(decode_node_frame(&frame[..frame.len() - 1]), None);
+    assert_eq!(decode_node_frame(&[frame.clone(), vec![0]].concat()), None);
+    assert_eq!(decode_node_frame(&[]), None);
+
+    let mut corrupt_width = frame;
+    corrupt_width[0] = 3;
+    assert_eq!(decode_node_frame(&corrupt_width), None);
+
+    // A count far beyond the data is rejected before allocating.
+    assert_eq!(decode_node_frame(&[2, 255, 255, 255, 255]), None);
+}
@@ -0,0 +1,96 @@
+use serde::{Deserialize, Serialize};
+
+use crate::height_data::{
+    get_interpolated_height_at_point_from, select_data_source, Interpolation,
+};
+
+/// Maximum number of locations of one lookup, so a single request can not
+/// load a large part of the DEM.
+pub const LOOKUP_LOCATIONS_MAXIMUM: usize = 1000;
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+pub struct LookupLocation {
+    pub latitude: f32,
+    pub longitude: f32,
+}
+
+/// Body of a `POST /api/v1/lookup` request of the Open-Elevation API.
+#[derive(Debug, Deserialize)]
+pub struct LookupRequest {
+    pub locations: Vec<LookupLocation>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct LookupResult {
+    pub latitude: f32,
+    pub longitude: f32,
+    pub elevation: f32,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct LookupResponse {
+    pub results: Vec<LookupResult>,
+}
+
+/// Parses the `locations` parameter of a `GET /api/v1/lookup` request, e.g.
+/// `47.2692,11.4041|46.5,8.0`.
+pub fn parse_locations(locations: &str) -> Result<Vec<LookupLocation>, String> {
+    locations
+        .split('|')
+        .map(|location| {
+            let (latitude, longitude) = location
+                .split_once(',')
+                .ok_or(format!("Location {location} is not <lat>,<lon>"))?;
+            let parse = |value: &str| {
+                value
+                    .trim()
+                    .parse::<f32>()
+                    .map_err(|_| format!("Invalid coordinate {value}"))
+            };
+            Ok(LookupLocation {
+                latitude: parse(latitude)?,
+                longitude: parse(longitude)?,
+            })
+        })
+        .collect()
+}
+
+/// Elevations of the locations from the local DEM, interpolated bilinearly.
+/// SRTM is used where present, the coarse DEM elsewhere. Fails if a location
+/// is invalid or not covered by either.
+pub fn lookup(locations: &[LookupLocation]) -> Result<LookupResponse, String> {
+    if locations.len() > LOOKUP_LOCATIONS_MAXIMUM {
+        return Err(format!(
+            "At most {LOOKUP_LOCATIONS_MAXIMUM} locations are supported"
+        ));
+    }
+    let results = locations
+        .iter()
+        .map(|location| {
+            let LookupLocation {
+                latitude,
+                longitude,
+            } = *location;
+            if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+                return Err(format!("Invalid location {latitude},{longitude}"));
+            }
+            let source = select_data_source(latitude, longitude, 0.0)
+                .ok_or(format!("No elevation data for {latitude},{longitude}"))?;
+            Ok(LookupResult {
+                latitude,
+                longitude,
+                elevation: get_interpolated_height_at_point_from(
+                    source,
+                    latitude,
+                    longitude,
+                    Interpolation::Bilinear,
+                ),
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    Ok(LookupResponse { results })
+}
+
+#[cfg(test)]
+#[path = "./open_elevation_test.rs"]
+mod open_elevation_test;
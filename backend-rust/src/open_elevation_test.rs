@@ -0,0 +1,44 @@
+use super::{lookup, parse_locations, LookupLocation, LOOKUP_LOCATIONS_MAXIMUM};
+
+#[test]
+fn test_parse_locations() {
+    assert_eq!(
+        parse_locations("47.2692,11.4041|-33.5, 151").unwrap(),
+        vec![
+            LookupLocation {
+                latitude: 47.2692,
+                longitude: 11.4041
+            },
+            LookupLocation {
+                latitude: -33.5,
+                longitude: 151.0
+            }
+        ]
+    );
+
+    assert!(parse_locations("47.2692")
+        .unwrap_err()
+        .contains("<lat>,<lon>"));
+    assert!(parse_locations("47.2692,11.4041|").is_err());
+    assert!(parse_locations("47.2692,east")
+        .unwrap_err()
+        .contains("east"));
+}
+
+#[test]
+fn test_lookup_errors() {
+    let location = |latitude, longitude| LookupLocation {
+        latitude,
+        longitude,
+    };
+    assert!(lookup(&[location(95.0, 11.0)])
+        .unwrap_err()
+        .contains("Invalid location"));
+    assert!(lookup(&[location(47.0, -181.0)]).is_err());
+    assert!(
+        lookup(&vec![location(47.0, 11.0); LOOKUP_LOCATIONS_MAXIMUM + 1])
+            .unwrap_err()
+            .contains("At most")
+    );
+    assert_eq!(lookup(&[]).unwrap().results, vec![]);
+}
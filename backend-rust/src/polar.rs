@@ -0,0 +1,67 @@
+/// Point of the speed polar of a wing.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PolarPoint {
+    /// Airspeed in km/h.
+    pub speed: f32,
+    /// Sink rate in m/s.
+    pub sink: f32,
+}
+
+/// Number of speeds evaluated per segment between two points of a polar.
+const SAMPLES_PER_SEGMENT: usize = 4;
+
+/// Parses a polar given as comma separated `speed:sink` pairs, e.g.
+/// `25:1.1,35:1.2,50:2.2`. Speeds must be increasing.
+pub fn parse_polar(value: &str) -> Result<Vec<PolarPoint>, String> {
+    let mut result: Vec<PolarPoint> = vec![];
+    for pair in value.split(',') {
+        let (speed, sink) = pair
+            .split_once(':')
+            .ok_or_else(|| format!("Polar point {pair} is not of the form speed:sink"))?;
+        let point = PolarPoint {
+            speed: speed
+                .trim()
+                .parse()
+                .map_err(|e| format!("Bad polar speed {speed}: {e}"))?,
+            sink: sink
+                .trim()
+                .parse()
+                .map_err(|e| format!("Bad polar sink {sink}: {e}"))?,
+        };
+        if !(point.speed > 0.0 && point.sink > 0.0) {
+            return Err(format!("Polar point {pair} must have positive values"));
+        }
+        if result.last().is_some_and(|last| last.speed >= point.speed) {
+            return Err("Polar speeds must be increasing".to_string());
+        }
+        result.push(point);
+    }
+    Ok(result)
+}
+
+/// Meters of height lost per meter flown in still air at an airspeed of
+/// `speed` km/h and a sink rate of `sink` m/s.
+pub fn glide_ratio_at(speed: f32, sink: f32) -> f32 {
+    sink * 3.6 / speed
+}
+
+/// `(airspeed, glide ratio in still air)` at the points of the polar and
+/// linearly interpolated speeds between them.
+pub fn polar_samples(polar: &[PolarPoint]) -> impl Iterator<Item = (f32, f32)> + '_ {
+    let segments = polar.windows(2).flat_map(|pair| {
+        (0..SAMPLES_PER_SEGMENT).map(move |i| {
+            let t = i as f32 / SAMPLES_PER_SEGMENT as f32;
+            PolarPoint {
+                speed: pair[0].speed + t * (pair[1].speed - pair[0].speed),
+                sink: pair[0].sink + t * (pair[1].sink - pair[0].sink),
+            }
+        })
+    });
+    segments
+        .chain(polar.last().copied())
+        .map(|point| (point.speed, glide_ratio_at(point.speed, point.sink)))
+}
+
+#[cfg(test)]
+#[path = "./polar_test.rs"]
+mod polar_test;
@@ -0,0 +1,43 @@
+use approx::assert_relative_eq;
+
+use super::{glide_ratio_at, parse_polar, polar_samples, PolarPoint};
+
+#[test]
+fn test_parse_polar() {
+    assert_eq!(
+        parse_polar("25:1.1, 36:1.5").unwrap(),
+        vec![
+            PolarPoint {
+                speed: 25.0,
+                sink: 1.1
+            },
+            PolarPoint {
+                speed: 36.0,
+                sink: 1.5
+            }
+        ]
+    );
+    assert!(parse_polar("").is_err());
+    assert!(parse_polar("25:1.1,20:1.5").is_err());
+    assert!(parse_polar("25:-1").is_err());
+    assert!(parse_polar("25").is_err());
+}
+
+#[test]
+fn test_polar_samples() {
+    let polar = parse_polar("36:1.0,72:3.0").unwrap();
+    let samples = polar_samples(&polar).collect::<Vec<_>>();
+
+    assert_eq!(samples.len(), 5);
+    assert_relative_eq!(samples[0].0, 36.0);
+    assert_relative_eq!(samples[0].1, 0.1);
+    assert_relative_eq!(samples[2].0, 54.0);
+    assert_relative_eq!(samples[2].1, 2.0 * 3.6 / 54.0);
+    assert_relative_eq!(samples[4].1, 3.0 * 3.6 / 72.0);
+}
+
+#[test]
+fn test_glide_ratio_at() {
+    // 10 m/s forward and 1.25 m/s down.
+    assert_relative_eq!(glide_ratio_at(36.0, 1.25), 0.125);
+}
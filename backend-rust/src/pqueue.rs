@@ -51,38 +51,25 @@ impl<K, V> Default for HashMapWrap<K, V> {
     }
 }
 
+/// Entry of the queue. Only the priority is stored here, the values belong
+/// to the caller and are looked up by key.
 #[derive(Debug)]
-pub struct HeapNode<V, K> {
-    pub item: V,
+pub struct HeapNode<P, K> {
+    pub priority: P,
     pub key: K,
 }
 
-pub trait HasPriority {
-    type Priority: PartialOrd + Copy;
-
-    fn priority(&self) -> &Self::Priority;
-    fn priority_mut(&mut self) -> &mut Self::Priority;
-}
-
-impl HasPriority for f32 {
-    type Priority = f32;
-
-    fn priority(&self) -> &Self::Priority {
-        self
-    }
-
-    fn priority_mut(&mut self) -> &mut Self::Priority {
-        self
-    }
-}
-
 #[derive(Debug)]
-pub struct PriorityQueue<V: HasPriority, K, MapType: MapLike<K, usize> = HashMapWrap<K, usize>> {
-    heap: Vec<HeapNode<V, K>>,
+pub struct PriorityQueue<
+    P: PartialOrd + Copy,
+    K,
+    MapType: MapLike<K, usize> = HashMapWrap<K, usize>,
+> {
+    heap: Vec<HeapNode<P, K>>,
     positions: MapType,
 }
 
-impl<V: HasPriority, K, MapType: MapLike<K, usize>> PriorityQueue<V, K, MapType> {
+impl<P: PartialOrd + Copy, K, MapType: MapLike<K, usize>> PriorityQueue<P, K, MapType> {
     pub fn len(&self) -> usize {
         self.heap.len()
     }
@@ -96,7 +83,7 @@ impl<V: HasPriority, K, MapType: MapLike<K, usize>> PriorityQueue<V, K, MapType>
     }
 }
 
-impl<V: HasPriority, K, MapType: Default + MapLike<K, usize>> PriorityQueue<V, K, MapType> {
+impl<P: PartialOrd + Copy, K, MapType: Default + MapLike<K, usize>> PriorityQueue<P, K, MapType> {
     pub fn new() -> Self {
         Self {
             heap: Vec::new(),
@@ -112,15 +99,15 @@ impl<V: HasPriority, K, MapType: Default + MapLike<K, usize>> PriorityQueue<V, K
     }
 }
 
-impl<V: HasPriority, K, MapType: Default + MapLike<K, usize>> Default
-    for PriorityQueue<V, K, MapType>
+impl<P: PartialOrd + Copy, K, MapType: Default + MapLike<K, usize>> Default
+    for PriorityQueue<P, K, MapType>
 {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<V: HasPriority, K, MapType: MapLike<K, usize>> PriorityQueue<V, K, MapType> {
+impl<P: PartialOrd + Copy, K, MapType: MapLike<K, usize>> PriorityQueue<P, K, MapType> {
     pub fn new_with_map(map: MapType) -> Self {
         Self {
             heap: Vec::new(),
@@ -136,111 +123,58 @@ impl<V: HasPriority, K, MapType: MapLike<K, usize>> PriorityQueue<V, K, MapType>
     }
 }
 
-impl<V: HasPriority, K: Eq + Hash + Copy, MapType: MapLike<K, usize>> PriorityQueue<V, K, MapType> {
-    pub fn push(&mut self, key: K, item: V) {
-        self.heap.push(HeapNode { item, key });
+impl<P: PartialOrd + Copy, K: Eq + Hash + Copy, MapType: MapLike<K, usize>>
+    PriorityQueue<P, K, MapType>
+{
+    pub fn push(&mut self, key: K, priority: P) {
+        self.heap.push(HeapNode { priority, key });
         let ix = self.heap.len() - 1;
         // Position gets set by siftup
         self.siftup(ix);
     }
 
-    pub fn update_priority(&mut self, key: K, priority: V::Priority) -> &mut HeapNode<V, K> {
+    pub fn update_priority(&mut self, key: K, priority: P) {
         let ix = self
             .positions
             .get(&key)
             .expect("Update priority called with invalid key");
 
-        // Safety: Positions only contains valid indices.
-        let node = unsafe { self.heap.get_unchecked_mut(ix) };
-        let old_priority = *node.item.priority();
-        *node.item.priority_mut() = priority;
-
-        let new_ix = if old_priority > priority {
-            self.siftup(ix)
-        } else {
-            self.siftdown(ix)
-        };
-
-        // Safety: Siftup/Siftdown return a valid index.
-        unsafe { self.heap.get_unchecked_mut(new_ix) }
+        self.set_priority(ix, priority);
     }
 
     /**
      * # Safety
      * Only call this when you know the queue contains the respective key.
      */
-    pub unsafe fn update_priority_unsafe(
-        &mut self,
-        key: K,
-        priority: V::Priority,
-    ) -> &mut HeapNode<V, K> {
+    pub unsafe fn update_priority_unsafe(&mut self, key: K, priority: P) {
         let ix = self.positions.get_unsafe(&key);
 
-        // Safety: Positions only contains valid indices.
-        let node = unsafe { self.heap.get_unchecked_mut(ix) };
-        let old_priority = *node.item.priority();
-        *node.item.priority_mut() = priority;
-
-        let new_ix = if old_priority > priority {
-            self.siftup(ix)
-        } else {
-            self.siftdown(ix)
-        };
-
-        // Safety: Siftup/Siftdown return a valid index.
-        unsafe { self.heap.get_unchecked_mut(new_ix) }
+        self.set_priority(ix, priority);
     }
 
     /**
+     * Returns whether the priority was updated.
+     *
      * # Safety
      * Only call this when you know the queue contains the respective key.
      */
-    pub unsafe fn update_priority_if_less_unsafe(
-        &mut self,
-        key: K,
-        priority: V::Priority,
-    ) -> Option<&mut HeapNode<V, K>> {
+    pub unsafe fn update_priority_if_less_unsafe(&mut self, key: K, priority: P) -> bool {
         let ix = self.positions.get_unsafe(&key);
 
-        // Safety: Positions only contains valid indices.
-        let node = unsafe { self.heap.get_unchecked_mut(ix) };
-        let old_priority = *node.item.priority();
-
-        if old_priority <= priority {
-            return None;
-        }
-        *node.item.priority_mut() = priority;
-        let new_ix = self.siftup(ix);
-
-        // Safety: Siftup returns a valid index.
-        unsafe { Some(self.heap.get_unchecked_mut(new_ix)) }
+        self.decrease_priority(ix, priority)
     }
 
-    pub fn update_priority_if_less(
-        &mut self,
-        key: K,
-        priority: V::Priority,
-    ) -> Option<&mut HeapNode<V, K>> {
+    /// Returns whether the priority was updated.
+    pub fn update_priority_if_less(&mut self, key: K, priority: P) -> bool {
         let ix = self
             .positions
             .get(&key)
             .expect("Update priority called with invalid key");
 
-        // Safety: Positions only contains valid indices.
-        let node = unsafe { self.heap.get_unchecked_mut(ix) };
-        let old_priority = *node.item.priority();
-
-        if old_priority <= priority {
-            return None;
-        }
-        *node.item.priority_mut() = priority;
-        let new_ix = self.siftup(ix);
-
-        // Safety: Siftup returns a valid index.
-        unsafe { Some(self.heap.get_unchecked_mut(new_ix)) }
+        self.decrease_priority(ix, priority)
     }
 
-    pub fn pop(&mut self) -> Option<HeapNode<V, K>> {
+    pub fn pop(&mut self) -> Option<HeapNode<P, K>> {
         let len = self.len();
         if len == 0 {
             return None;
@@ -266,23 +200,40 @@ impl<V: HasPriority, K: Eq + Hash + Copy, MapType: MapLike<K, usize>> PriorityQu
         self.positions.contains_key(key)
     }
 
-    pub fn get(&self, key: &K) -> Option<&HeapNode<V, K>> {
-        // TODO: Get unsafe variant?
+    pub fn priority(&self, key: &K) -> Option<P> {
         let position = self.positions.get(key);
         // Safety: Positions only contains valid indices.
-        unsafe { Some(self.heap.get_unchecked(position?)) }
+        unsafe { Some(self.heap.get_unchecked(position?).priority) }
     }
 
-    pub fn get_mut(&mut self, key: &K) -> Option<&mut HeapNode<V, K>> {
-        let position = self.positions.get(key);
+    fn set_priority(&mut self, ix: usize, priority: P) {
         // Safety: Positions only contains valid indices.
-        unsafe { Some(self.heap.get_unchecked_mut(position?)) }
+        let node = unsafe { self.heap.get_unchecked_mut(ix) };
+        let old_priority = node.priority;
+        node.priority = priority;
+
+        if old_priority > priority {
+            self.siftup(ix);
+        } else {
+            self.siftdown(ix);
+        }
+    }
+
+    fn decrease_priority(&mut self, ix: usize, priority: P) -> bool {
+        // Safety: Positions only contains valid indices.
+        let node = unsafe { self.heap.get_unchecked_mut(ix) };
+        if node.priority <= priority {
+            return false;
+        }
+        node.priority = priority;
+        self.siftup(ix);
+        true
     }
 
     fn siftup(&mut self, mut ix: usize) -> usize {
         let newitem = unsafe { self.heap.get_unchecked(ix) };
         let key = newitem.key;
-        let priority = *newitem.item.priority();
+        let priority = newitem.priority;
 
         while ix > 0 {
             let parent_ix = (ix - 1) >> 1;
@@ -290,7 +241,7 @@ impl<V: HasPriority, K: Eq + Hash + Copy, MapType: MapLike<K, usize>> PriorityQu
             // and positions only contains valid indices.
             let parent = unsafe { self.heap.get_unchecked(parent_ix) };
             let parent_key = parent.key;
-            if priority >= *parent.item.priority() {
+            if priority >= parent.priority {
                 break;
             }
 
@@ -308,7 +259,7 @@ impl<V: HasPriority, K: Eq + Hash + Copy, MapType: MapLike<K, usize>> PriorityQu
     fn siftdown(&mut self, mut ix: usize) -> usize {
         let end_ix = self.len();
         let newitem = unsafe { self.heap.get_unchecked(ix) };
-        let newitem_priority = *newitem.item.priority();
+        let newitem_priority = newitem.priority;
         let newitem_key = newitem.key;
 
         // Bubble up the smaller child until hitting a leaf.
@@ -321,8 +272,8 @@ impl<V: HasPriority, K: Eq + Hash + Copy, MapType: MapLike<K, usize>> PriorityQu
             // Safety: We already checked that child_ix is less than end_ix.
             if right_ix < end_ix
                 && unsafe {
-                    self.heap.get_unchecked(right_ix).item.priority()
-                        < self.heap.get_unchecked(child_ix).item.priority()
+                    self.heap.get_unchecked(right_ix).priority
+                        < self.heap.get_unchecked(child_ix).priority
                 }
             {
                 child_ix = right_ix
@@ -331,7 +282,7 @@ impl<V: HasPriority, K: Eq + Hash + Copy, MapType: MapLike<K, usize>> PriorityQu
             // Safety: We already checked that child_ix is less than end_ix.
             let child = unsafe { self.heap.get_unchecked(child_ix) };
             let child_key = child.key;
-            let child_priority = *child.item.priority();
+            let child_priority = child.priority;
 
             if child_priority >= newitem_priority {
                 break;
@@ -351,15 +302,15 @@ impl<V: HasPriority, K: Eq + Hash + Copy, MapType: MapLike<K, usize>> PriorityQu
     }
 }
 
-pub struct PriorityQueueIterator<V: HasPriority, K, MapType: MapLike<K, usize>> {
-    priority_queue: PriorityQueue<V, K, MapType>,
+pub struct PriorityQueueIterator<P: PartialOrd + Copy, K, MapType: MapLike<K, usize>> {
+    priority_queue: PriorityQueue<P, K, MapType>,
 }
 
-impl<V: HasPriority, K: Eq + Hash + Copy, MapType: MapLike<K, usize>> IntoIterator
-    for PriorityQueue<V, K, MapType>
+impl<P: PartialOrd + Copy, K: Eq + Hash + Copy, MapType: MapLike<K, usize>> IntoIterator
+    for PriorityQueue<P, K, MapType>
 {
-    type Item = HeapNode<V, K>;
-    type IntoIter = PriorityQueueIterator<V, K, MapType>;
+    type Item = HeapNode<P, K>;
+    type IntoIter = PriorityQueueIterator<P, K, MapType>;
 
     fn into_iter(self) -> Self::IntoIter {
         PriorityQueueIterator {
@@ -368,10 +319,10 @@ impl<V: HasPriority, K: Eq + Hash + Copy, MapType: MapLike<K, usize>> IntoIterat
     }
 }
 
-impl<V: HasPriority, K: Eq + Hash + Copy, MapType: MapLike<K, usize>> Iterator
-    for PriorityQueueIterator<V, K, MapType>
+impl<P: PartialOrd + Copy, K: Eq + Hash + Copy, MapType: MapLike<K, usize>> Iterator
+    for PriorityQueueIterator<P, K, MapType>
 {
-    type Item = HeapNode<V, K>;
+    type Item = HeapNode<P, K>;
 
     fn next(&mut self) -> Option<Self::Item> {
         self.priority_queue.pop()
@@ -1,21 +1,7 @@
 use crate::pqueue::PriorityQueue;
 
-use super::HasPriority;
-
 type TestPQueue = PriorityQueue<usize, usize>;
 
-impl HasPriority for usize {
-    type Priority = usize;
-
-    fn priority(&self) -> &Self::Priority {
-        self
-    }
-
-    fn priority_mut(&mut self) -> &mut Self::Priority {
-        self
-    }
-}
-
 #[test]
 fn test_pqueue_starts_empty() {
     let pqueue: TestPQueue = PriorityQueue::new();
@@ -39,7 +25,10 @@ fn test_pqueue_only_inserts_in_order() {
     pqueue.push(3, 3);
     pqueue.push(4, 4);
 
-    let ordered = pqueue.into_iter().map(|x| x.item).collect::<Vec<usize>>();
+    let ordered = pqueue
+        .into_iter()
+        .map(|x| x.priority)
+        .collect::<Vec<usize>>();
     assert_eq!(ordered, vec![0, 1, 2, 3, 4])
 }
 
@@ -59,7 +48,10 @@ fn test_pqueue_inserts_out_of_order() {
     pqueue.push(10, 10);
     pqueue.push(11, 11);
 
-    let ordered = pqueue.into_iter().map(|x| x.item).collect::<Vec<usize>>();
+    let ordered = pqueue
+        .into_iter()
+        .map(|x| x.priority)
+        .collect::<Vec<usize>>();
     assert_eq!(ordered, vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11])
 }
 
@@ -74,7 +66,10 @@ fn test_pqueue_inserts_out_of_order_2() {
     pqueue.push(4, 4);
     pqueue.push(8, 8);
 
-    let ordered = pqueue.into_iter().map(|x| x.item).collect::<Vec<usize>>();
+    let ordered = pqueue
+        .into_iter()
+        .map(|x| x.priority)
+        .collect::<Vec<usize>>();
     assert_eq!(ordered, vec![0, 1, 2, 3, 4, 8])
 }
 
@@ -96,7 +91,10 @@ fn test_pqueue_inserts_and_pops() {
     pqueue.push(7, 7);
     pqueue.push(1, 1);
 
-    let ordered = pqueue.into_iter().map(|x| x.item).collect::<Vec<usize>>();
+    let ordered = pqueue
+        .into_iter()
+        .map(|x| x.priority)
+        .collect::<Vec<usize>>();
     assert_eq!(ordered, vec![1, 4, 5, 6, 7, 9, 22])
 }
 
@@ -121,7 +119,10 @@ fn test_pqueue_update_priority() {
     pqueue.push(1, 1);
     pqueue.update_priority(22, 2);
 
-    let ordered = pqueue.into_iter().map(|x| x.item).collect::<Vec<usize>>();
+    let ordered = pqueue
+        .into_iter()
+        .map(|x| x.priority)
+        .collect::<Vec<usize>>();
     assert_eq!(ordered, vec![1, 2, 3, 6, 7, 8, 9])
 }
 
@@ -135,17 +136,59 @@ fn test_pqueue_update_priority_if_less() {
     pqueue.pop();
     pqueue.push(4, 4);
     pqueue.push(2, 2);
-    assert!(pqueue.update_priority_if_less(3, 0).is_some());
+    assert!(pqueue.update_priority_if_less(3, 0));
     pqueue.pop();
     pqueue.push(9, 9);
     pqueue.push(22, 22);
     pqueue.push(6, 6);
-    assert!(pqueue.update_priority_if_less(4, 7).is_none());
+    assert!(!pqueue.update_priority_if_less(4, 7));
     pqueue.pop();
     pqueue.push(3, 3);
     pqueue.push(0, 0);
-    assert!(pqueue.update_priority_if_less(22, 1).is_some());
+    assert!(pqueue.update_priority_if_less(22, 1));
 
-    let ordered = pqueue.into_iter().map(|x| x.item).collect::<Vec<usize>>();
+    let ordered = pqueue
+        .into_iter()
+        .map(|x| x.priority)
+        .collect::<Vec<usize>>();
     assert_eq!(ordered, vec![0, 1, 3, 4, 5, 6, 9]);
 }
+
+#[test]
+fn test_pqueue_priority() {
+    let mut pqueue: TestPQueue = PriorityQueue::new();
+
+    pqueue.push(1, 5);
+    pqueue.push(2, 3);
+    pqueue.update_priority(1, 2);
+
+    assert_eq!(pqueue.priority(&1), Some(2));
+    assert_eq!(pqueue.priority(&3), None);
+    assert_eq!(pqueue.pop().map(|x| x.key), Some(1));
+}
+
+#[test]
+fn test_pqueue_keys_differ_from_priorities() {
+    let mut pqueue: TestPQueue = PriorityQueue::new();
+
+    pqueue.push(1, 10);
+    pqueue.push(2, 5);
+    pqueue.push(3, 7);
+    pqueue.push(4, 12);
+
+    let first = pqueue.pop().unwrap();
+    assert_eq!((first.key, first.priority), (2, 5));
+
+    pqueue.update_priority(1, 1);
+    assert_eq!(pqueue.priority(&1), Some(1));
+    assert_eq!(pqueue.priority(&3), Some(7));
+    assert!(!pqueue.update_priority_if_less(3, 8));
+    assert!(pqueue.update_priority_if_less(4, 6));
+    assert!(!pqueue.contains_key(&2));
+
+    let ordered = pqueue
+        .into_iter()
+        .map(|x| (x.key, x.priority))
+        .collect::<Vec<_>>();
+    assert_eq!(ordered, vec![(1, 1), (4, 6), (3, 7)]);
+}
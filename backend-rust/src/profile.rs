@@ -0,0 +1,154 @@
+use ndarray::Array2;
+use serde::Serialize;
+
+use crate::{
+    height_data::{interpolate_2d_array, point_distance, HeightGrid, Interpolation},
+    line::Line,
+    search::{parse_starts, Node, SearchExtent},
+};
+
+/// Points of a profile line at most.
+pub const MAX_PROFILE_POINTS: usize = 100;
+
+/// Terrain, and where a cone reaches the glide, at one point of a profile.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ProfileSample {
+    pub lat: f32,
+    pub lon: f32,
+    /// Distance along the line from its first point in meters.
+    pub distance: f32,
+    /// Height of the terrain above sea level in meters.
+    pub elevation: f32,
+    /// Height of the glide above sea level in meters, only set where the
+    /// cone reaches.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub glide_height: Option<f32>,
+}
+
+/// Parses a comma separated list of `latitude:longitude` points, like the
+/// starts of `parse_starts` but without heights.
+pub fn parse_polyline(value: &str) -> Result<Vec<(f32, f32)>, String> {
+    let points = parse_starts(value)?
+        .into_iter()
+        .map(|(latitude, longitude, height)| match height {
+            None => Ok((latitude, longitude)),
+            Some(_) => Err(format!(
+                "Point {latitude}:{longitude} is not of the form latitude:longitude"
+            )),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if !(2..=MAX_PROFILE_POINTS).contains(&points.len()) {
+        return Err(format!(
+            "A line needs between 2 and {MAX_PROFILE_POINTS} points"
+        ));
+    }
+    Ok(points)
+}
+
+/// Area around the first of `points` that contains all of them.
+pub fn polyline_extent(points: &[(f32, f32)]) -> SearchExtent {
+    let mut extent = SearchExtent::around(0.0);
+    let Some(&(latitude, longitude)) = points.first() else {
+        return extent;
+    };
+    for &(lat, lon) in points {
+        let north = point_distance(latitude, longitude, lat, longitude);
+        let east = point_distance(lat, longitude, lat, lon);
+        if lat >= latitude {
+            extent.north = extent.north.max(north);
+        } else {
+            extent.south = extent.south.max(north);
+        }
+        if lon >= longitude {
+            extent.east = extent.east.max(east);
+        } else {
+            extent.west = extent.west.max(east);
+        }
+    }
+    extent
+}
+
+/// Terrain along the line through `points`, one sample per cell of `grid`
+/// the line crosses. The cells of each segment are traced with `Line`, and
+/// the terrain is interpolated bilinearly at the point of the segment
+/// closest to the cell center. Parts of the line outside of the grid take
+/// the heights at its border.
+pub fn elevation_profile(grid: &HeightGrid, points: &[(f32, f32)]) -> Vec<ProfileSample> {
+    let (rows, cols) = (grid.heights.shape()[0], grid.heights.shape()[1]);
+    let (lat_resolution, lon_resolution) = grid.get_angular_resolution();
+    let position = |(lat, lon): (f32, f32)| {
+        (
+            ((lat - grid.latitudes.0) / lat_resolution).clamp(0.0, (rows - 1) as f32),
+            ((lon - grid.longitudes.0) / lon_resolution).clamp(0.0, (cols - 1) as f32),
+        )
+    };
+
+    let mut samples = vec![];
+    let mut distance = 0.0;
+    for (i, segment) in points.windows(2).enumerate() {
+        let (from, to) = (position(segment[0]), position(segment[1]));
+        let length = point_distance(segment[0].0, segment[0].1, segment[1].0, segment[1].1);
+        let direction = (to.0 - from.0, to.1 - from.1);
+        let squared_length = direction.0 * direction.0 + direction.1 * direction.1;
+
+        let line = Line::new(
+            (from.0.round() as i16, to.0.round() as i16),
+            (from.1.round() as i16, to.1.round() as i16),
+        );
+        let mut cells = line.iter().collect::<Vec<_>>();
+        if line.iterator_reversed() {
+            cells.reverse();
+        }
+        // The first cell is the last one of the previous segment.
+        for (row, col) in cells.into_iter().skip(usize::from(i > 0)) {
+            let t = if squared_length > 0.0 {
+                (((row as f32 - from.0) * direction.0 + (col as f32 - from.1) * direction.1)
+                    / squared_length)
+                    .clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            let (row, col) = (from.0 + t * direction.0, from.1 + t * direction.1);
+            let (lat, lon) = grid.coordinates_at(row, col);
+            samples.push(ProfileSample {
+                lat,
+                lon,
+                distance: distance + t * length,
+                elevation: interpolate_2d_array(
+                    &grid.heights.view(),
+                    row,
+                    col,
+                    Interpolation::Bilinear,
+                ),
+                glide_height: None,
+            });
+        }
+        distance += length;
+    }
+    samples
+}
+
+/// Height of the glide above sea level at every cell of `grid` reachable in
+/// `explored`, `None` elsewhere.
+pub fn glide_heights(explored: &[Node], grid: &HeightGrid) -> Array2<Option<f32>> {
+    let mut heights = Array2::from_elem((grid.heights.shape()[0], grid.heights.shape()[1]), None);
+    for node in explored.iter().filter(|node| node.reachable) {
+        heights[(node.ix.0 as usize, node.ix.1 as usize)] = Some(node.height);
+    }
+    heights
+}
+
+/// Sets the glide height of the samples in the cells of `grid` reachable
+/// in `explored`.
+pub fn add_glide_heights(samples: &mut [ProfileSample], explored: &[Node], grid: &HeightGrid) {
+    let heights = glide_heights(explored, grid);
+    for sample in samples {
+        sample.glide_height = grid
+            .index_of(sample.lat, sample.lon)
+            .and_then(|ix| heights[ix]);
+    }
+}
+
+#[cfg(test)]
+#[path = "./profile_test.rs"]
+mod profile_test;
@@ -0,0 +1,98 @@
+use approx::assert_relative_eq;
+use ndarray::Array2;
+
+use crate::{
+    height_data::{point_distance, HeightGrid},
+    search::Node,
+};
+
+use super::{add_glide_heights, elevation_profile, parse_polyline, polyline_extent};
+
+/// Rising 10 m per column to the east, cells 0.001° apart.
+fn grid() -> HeightGrid {
+    HeightGrid::for_test(
+        Array2::from_shape_fn((5, 11), |(_, col)| col as i16 * 10),
+        (47.0, 47.005),
+        (11.0, 11.011),
+    )
+}
+
+#[test]
+fn test_parse_polyline() {
+    assert_eq!(
+        parse_polyline("47.1:11.2, 47.3:11.4").unwrap(),
+        vec![(47.1, 11.2), (47.3, 11.4)]
+    );
+    assert!(parse_polyline("47.1:11.2").is_err());
+    assert!(parse_polyline("47.1:11.2,47.3:11.4:1000").is_err());
+    assert!(parse_polyline("47.1:11.2,north").is_err());
+}
+
+#[test]
+fn test_polyline_extent() {
+    let extent = polyline_extent(&[(47.0, 11.0), (47.01, 10.99), (46.99, 11.0)]);
+
+    assert_relative_eq!(extent.north, point_distance(47.0, 11.0, 47.01, 11.0));
+    assert_relative_eq!(extent.south, point_distance(47.0, 11.0, 46.99, 11.0));
+    assert_relative_eq!(extent.west, point_distance(47.01, 11.0, 47.01, 10.99));
+    assert_eq!(extent.east, 0.0);
+}
+
+#[test]
+fn test_elevation_profile() {
+    let grid = grid();
+    let points = [(47.002, 11.0), (47.002, 11.01), (47.004, 11.01)];
+
+    let samples = elevation_profile(&grid, &points);
+
+    // Every cell of the row and two more to the north, the corner once.
+    assert_eq!(samples.len(), 13);
+    for (col, sample) in samples[..11].iter().enumerate() {
+        assert_relative_eq!(sample.elevation, col as f32 * 10.0, epsilon = 1e-3);
+        assert_relative_eq!(sample.lat, 47.002, epsilon = 1e-5);
+    }
+    assert_eq!(samples[0].distance, 0.0);
+    let first_leg = point_distance(47.002, 11.0, 47.002, 11.01);
+    assert_relative_eq!(samples[10].distance, first_leg, epsilon = 1e-2);
+    assert_relative_eq!(
+        samples[12].distance,
+        first_leg + point_distance(47.002, 11.01, 47.004, 11.01),
+        epsilon = 1e-2
+    );
+    assert_relative_eq!(samples[12].lat, 47.004, epsilon = 1e-5);
+    assert!(samples.iter().all(|sample| sample.glide_height.is_none()));
+}
+
+#[test]
+fn test_elevation_profile_interpolates() {
+    let grid = grid();
+    // One row north over four columns east.
+    let samples = elevation_profile(&grid, &[(47.0, 11.0), (47.001, 11.004)]);
+
+    assert_eq!(samples.len(), 5);
+    // The point of the line closest to the center of the cell (0, 1).
+    assert_relative_eq!(samples[1].elevation, 160.0 / 17.0, epsilon = 1e-2);
+    assert_relative_eq!(samples[4].elevation, 40.0, epsilon = 1e-2);
+}
+
+#[test]
+fn test_add_glide_heights() {
+    let grid = grid();
+    let mut samples = elevation_profile(&grid, &[(47.002, 11.0), (47.002, 11.004)]);
+    let explored = (0..3)
+        .map(|col| Node {
+            height: 500.0 - col as f32 * 10.0,
+            ix: (2, col),
+            reachable: col < 2,
+            ..Node::new()
+        })
+        .collect::<Vec<_>>();
+
+    add_glide_heights(&mut samples, &explored, &grid);
+
+    let heights = samples
+        .iter()
+        .map(|sample| sample.glide_height)
+        .collect::<Vec<_>>();
+    assert_eq!(heights, vec![Some(500.0), Some(490.0), None, None, None]);
+}
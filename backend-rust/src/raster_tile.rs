@@ -0,0 +1,49 @@
+use crate::{height_data::HeightGrid, mvt::TileIndex};
+
+/// Width and height of raster tiles in pixels.
+pub const TILE_PIXELS: usize = 256;
+
+/// Cell of `grid` under the center of each pixel of `tile`, row by row from
+/// the top left, `None` outside of the grid. Cells are centered on their
+/// coordinates like in `cone_tile`, so tiles zoomed in further than the grid
+/// resolution show them as blocks.
+pub fn tile_cells(grid: &HeightGrid, tile: TileIndex) -> Vec<Option<(usize, usize)>> {
+    let shape = grid.heights.shape();
+    let lat_resolution = (grid.latitudes.1 - grid.latitudes.0) / shape[0] as f32;
+    let lon_resolution = (grid.longitudes.1 - grid.longitudes.0) / shape[1] as f32;
+    let index = |value: f32, start: f32, resolution: f32, cells: usize| {
+        let ix = ((value - start) / resolution).round();
+        (ix >= 0.0 && ix < cells as f32).then_some(ix as usize)
+    };
+    let center = |pixel: usize| (pixel as f32 + 0.5) / TILE_PIXELS as f32;
+
+    // All pixels of a row have the same latitude, all of a column the same
+    // longitude.
+    let rows = (0..TILE_PIXELS)
+        .map(|y| {
+            index(
+                tile.latitude_at(center(y)),
+                grid.latitudes.0,
+                lat_resolution,
+                shape[0],
+            )
+        })
+        .collect::<Vec<_>>();
+    let cols = (0..TILE_PIXELS)
+        .map(|x| {
+            index(
+                tile.longitude_at(center(x)),
+                grid.longitudes.0,
+                lon_resolution,
+                shape[1],
+            )
+        })
+        .collect::<Vec<_>>();
+    rows.iter()
+        .flat_map(|row| cols.iter().map(move |col| Some(((*row)?, (*col)?))))
+        .collect()
+}
+
+#[cfg(test)]
+#[path = "./raster_tile_test.rs"]
+mod raster_tile_test;
@@ -0,0 +1,35 @@
+use ndarray::Array2;
+
+use crate::{height_data::HeightGrid, mvt::TileIndex};
+
+use super::{tile_cells, TILE_PIXELS};
+
+fn grid() -> HeightGrid {
+    HeightGrid::for_test(
+        Array2::from_elem((10, 10), 1000),
+        (47.0, 47.01),
+        (11.0, 11.01),
+    )
+}
+
+#[test]
+fn test_tile_cells() {
+    // Zoom level 12 tile containing the whole grid.
+    let tile = TileIndex::new(12, 2173, 1440).unwrap();
+    let cells = tile_cells(&grid(), tile);
+    assert_eq!(cells.len(), TILE_PIXELS * TILE_PIXELS);
+
+    let covered = cells.iter().flatten().collect::<Vec<_>>();
+    // The grid is about 29 x 43 pixels at this zoom level.
+    assert!(covered.len() > 1000 && covered.len() < 1500);
+    assert!(covered.contains(&&(0, 0)) && covered.contains(&&(9, 9)));
+
+    // Rows grow northwards, so the first covered pixel is in the last row.
+    let first = cells.iter().position(Option::is_some).unwrap();
+    assert_eq!(cells[first].unwrap().0, 9);
+    let last = cells.iter().rposition(Option::is_some).unwrap();
+    assert_eq!(cells[last].unwrap(), (0, 9));
+
+    let elsewhere = TileIndex::new(12, 0, 0).unwrap();
+    assert!(tile_cells(&grid(), elsewhere).iter().all(Option::is_none));
+}
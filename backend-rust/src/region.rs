@@ -0,0 +1,205 @@
+use ndarray::Array2;
+use serde::Deserialize;
+
+use crate::{height_data::HeightGrid, search::Node};
+
+/// GeoJSON file in the data directory with the areas where landing is
+/// prohibited, e.g. nature reserves.
+pub const NO_LANDING_FILE_NAME: &str = "no_landing_zones.geojson";
+
+/// GeoJSON describing a region like a national park or a country. Only
+/// polygons are used, other geometries are ignored.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(tag = "type")]
+pub enum RegionGeoJson {
+    FeatureCollection {
+        features: Vec<RegionGeoJson>,
+    },
+    Feature {
+        geometry: Option<Box<RegionGeoJson>>,
+    },
+    Polygon {
+        coordinates: Vec<Vec<[f32; 2]>>,
+    },
+    MultiPolygon {
+        coordinates: Vec<Vec<Vec<[f32; 2]>>>,
+    },
+    #[serde(other)]
+    Other,
+}
+
+impl RegionGeoJson {
+    fn into_polygons(self) -> Vec<Vec<Vec<[f32; 2]>>> {
+        match self {
+            RegionGeoJson::FeatureCollection { features } => features
+                .into_iter()
+                .flat_map(RegionGeoJson::into_polygons)
+                .collect(),
+            RegionGeoJson::Feature { geometry } => geometry
+                .map(|geometry| geometry.into_polygons())
+                .unwrap_or_default(),
+            RegionGeoJson::Polygon { coordinates } => vec![coordinates],
+            RegionGeoJson::MultiPolygon { coordinates } => coordinates,
+            RegionGeoJson::Other => vec![],
+        }
+    }
+}
+
+/// Area made of polygons with `[longitude, latitude]` rings. The first ring
+/// of each polygon is its outer boundary, the others are holes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Region {
+    pub polygons: Vec<Vec<Vec<[f32; 2]>>>,
+}
+
+impl Region {
+    pub fn from_geojson(geojson: RegionGeoJson) -> Result<Region, String> {
+        let polygons = geojson
+            .into_polygons()
+            .into_iter()
+            .filter(|rings| rings.first().is_some_and(|ring| ring.len() >= 3))
+            .collect::<Vec<_>>();
+        if polygons.is_empty() {
+            return Err("The GeoJSON contains no polygon".to_string());
+        }
+        Ok(Region { polygons })
+    }
+
+    pub fn contains(&self, latitude: f32, longitude: f32) -> bool {
+        self.polygons.iter().any(|rings| {
+            ring_contains(&rings[0], latitude, longitude)
+                && !rings[1..]
+                    .iter()
+                    .any(|hole| ring_contains(hole, latitude, longitude))
+        })
+    }
+
+    /// Polygons whose outer boundary overlaps the given ranges, so large
+    /// regions only need to be tested against the polygons near a grid.
+    pub fn clipped_to(&self, latitudes: (f32, f32), longitudes: (f32, f32)) -> Region {
+        let polygons = self
+            .polygons
+            .iter()
+            .filter(|rings| {
+                rings[0].iter().any(|p| p[0] >= longitudes.0)
+                    && rings[0].iter().any(|p| p[0] <= longitudes.1)
+                    && rings[0].iter().any(|p| p[1] >= latitudes.0)
+                    && rings[0].iter().any(|p| p[1] <= latitudes.1)
+            })
+            .cloned()
+            .collect();
+        Region { polygons }
+    }
+
+    fn edges(&self) -> impl Iterator<Item = ([f32; 2], [f32; 2])> + '_ {
+        self.polygons
+            .iter()
+            .flatten()
+            .flat_map(|ring| ring_edges(ring))
+    }
+}
+
+/// Whether the point lies inside a ring of `[longitude, latitude]`
+/// positions, by ray casting. The ring may or may not be closed.
+pub fn ring_contains(ring: &[[f32; 2]], latitude: f32, longitude: f32) -> bool {
+    ring_edges(ring).fold(false, |inside, ([x1, y1], [x2, y2])| {
+        let crosses = (y1 > latitude) != (y2 > latitude)
+            && longitude < x1 + (latitude - y1) / (y2 - y1) * (x2 - x1);
+        inside != crosses
+    })
+}
+
+/// Edges of a ring, including the one closing it.
+fn ring_edges(ring: &[[f32; 2]]) -> impl Iterator<Item = ([f32; 2], [f32; 2])> + '_ {
+    ring.iter()
+        .zip(ring.iter().cycle().skip(1))
+        .map(|(a, b)| (*a, *b))
+}
+
+/// Intersection of the segments `a` and `b`, if they cross. Segments include
+/// their start but not their end, so a crossing through a vertex of a ring is
+/// only found once.
+fn segment_intersection(a: ([f32; 2], [f32; 2]), b: ([f32; 2], [f32; 2])) -> Option<[f32; 2]> {
+    let r = [a.1[0] - a.0[0], a.1[1] - a.0[1]];
+    let s = [b.1[0] - b.0[0], b.1[1] - b.0[1]];
+    let cross = |u: [f32; 2], v: [f32; 2]| u[0] * v[1] - u[1] * v[0];
+    let denominator = cross(r, s);
+    if denominator == 0.0 {
+        return None;
+    }
+    let offset = [b.0[0] - a.0[0], b.0[1] - a.0[1]];
+    let t = cross(offset, s) / denominator;
+    let u = cross(offset, r) / denominator;
+    ((0.0..1.0).contains(&t) && (0.0..1.0).contains(&u))
+        .then(|| [a.0[0] + t * r[0], a.0[1] + t * r[1]])
+}
+
+/// `[longitude, latitude]` points where the boundary of a cone, given as a
+/// ring of `[longitude, latitude]` positions, crosses the boundary of the
+/// region.
+pub fn boundary_crossings(cone: &[[f32; 2]], region: &Region) -> Vec<[f32; 2]> {
+    let (min, max) = cone.iter().fold(
+        ([f32::INFINITY; 2], [f32::NEG_INFINITY; 2]),
+        |(min, max), p| {
+            (
+                [min[0].min(p[0]), min[1].min(p[1])],
+                [max[0].max(p[0]), max[1].max(p[1])],
+            )
+        },
+    );
+    // Most edges of a large region are far away from the cone.
+    let near_edges = region
+        .edges()
+        .filter(|(a, b)| {
+            a[0].max(b[0]) >= min[0]
+                && a[0].min(b[0]) <= max[0]
+                && a[1].max(b[1]) >= min[1]
+                && a[1].min(b[1]) <= max[1]
+        })
+        .collect::<Vec<_>>();
+
+    ring_edges(cone)
+        .flat_map(|edge| {
+            near_edges
+                .iter()
+                .filter_map(move |region_edge| segment_intersection(edge, *region_edge))
+        })
+        .collect()
+}
+
+fn node_in_region(node: &Node, grid: &HeightGrid, region: &Region) -> bool {
+    let (lat, lon) = grid.cell_center((node.ix.0 as usize, node.ix.1 as usize));
+    region.contains(lat, lon)
+}
+
+/// Reachable nodes with the center of their cell inside and outside of the
+/// region.
+pub fn split_by_region(
+    explored: &[Node],
+    grid: &HeightGrid,
+    region: &Region,
+) -> (Vec<Node>, Vec<Node>) {
+    explored
+        .iter()
+        .filter(|node| node.reachable)
+        .cloned()
+        .partition(|node| node_in_region(node, grid, region))
+}
+
+/// Marks the reachable cells of the grid with their center inside the
+/// region, e.g. to flag cells where landing is prohibited.
+pub fn region_mask(explored: &[Node], grid: &HeightGrid, region: &Region) -> Array2<bool> {
+    let mut mask = Array2::from_elem((grid.heights.shape()[0], grid.heights.shape()[1]), false);
+    let region = region.clipped_to(grid.latitudes, grid.longitudes);
+    if region.polygons.is_empty() {
+        return mask;
+    }
+    for node in explored.iter().filter(|node| node.reachable) {
+        mask[(node.ix.0 as usize, node.ix.1 as usize)] = node_in_region(node, grid, &region);
+    }
+    mask
+}
+
+#[cfg(test)]
+#[path = "./region_test.rs"]
+mod region_test;
@@ -0,0 +1,159 @@
+use ndarray::Array2;
+
+use crate::{
+    height_data::HeightGrid,
+    search::{GridIx, Node},
+};
+
+use super::{boundary_crossings, region_mask, split_by_region, Region, RegionGeoJson};
+
+fn square(min: [f32; 2], max: [f32; 2]) -> Vec<[f32; 2]> {
+    vec![
+        [min[0], min[1]],
+        [max[0], min[1]],
+        [max[0], max[1]],
+        [min[0], max[1]],
+        [min[0], min[1]],
+    ]
+}
+
+#[test]
+fn test_region_from_geojson() {
+    let geojson = RegionGeoJson::FeatureCollection {
+        features: vec![
+            RegionGeoJson::Feature {
+                geometry: Some(Box::new(RegionGeoJson::Polygon {
+                    coordinates: vec![
+                        square([0.0, 0.0], [4.0, 4.0]),
+                        square([1.0, 1.0], [2.0, 2.0]),
+                    ],
+                })),
+            },
+            RegionGeoJson::Feature {
+                geometry: Some(Box::new(RegionGeoJson::MultiPolygon {
+                    coordinates: vec![vec![square([10.0, 10.0], [11.0, 11.0])]],
+                })),
+            },
+            RegionGeoJson::Feature {
+                geometry: Some(Box::new(RegionGeoJson::Other)),
+            },
+            RegionGeoJson::Feature { geometry: None },
+        ],
+    };
+    let region = Region::from_geojson(geojson).unwrap();
+
+    assert_eq!(region.polygons.len(), 2);
+    assert!(region.contains(3.0, 0.5));
+    // In the hole.
+    assert!(!region.contains(1.5, 1.5));
+    assert!(region.contains(10.5, 10.5));
+    assert!(!region.contains(5.0, 5.0));
+
+    assert!(Region::from_geojson(RegionGeoJson::Other).is_err());
+    assert!(Region::from_geojson(RegionGeoJson::FeatureCollection { features: vec![] }).is_err());
+}
+
+#[test]
+fn test_boundary_crossings() {
+    let region = Region {
+        polygons: vec![vec![square([1.0, -1.0], [3.0, 3.0])]],
+    };
+
+    let mut crossings = boundary_crossings(&square([0.0, 0.0], [2.0, 2.0]), &region);
+    crossings.sort_by(|a, b| a[1].total_cmp(&b[1]));
+    assert_eq!(crossings, vec![[1.0, 0.0], [1.0, 2.0]]);
+
+    assert!(boundary_crossings(&square([5.0, 5.0], [6.0, 6.0]), &region).is_empty());
+    // Entirely inside.
+    assert!(boundary_crossings(&square([1.5, 0.0], [2.0, 1.0]), &region).is_empty());
+}
+
+#[test]
+fn test_split_by_region() {
+    let grid = HeightGrid::for_test(Array2::zeros((2, 2)), (47.0, 47.2), (11.0, 11.2));
+    let node = |ix: GridIx, reachable| Node {
+        height: 500.0,
+        ix,
+        reference: None,
+        distance: 0.0,
+        reachable,
+        clearance: f32::INFINITY,
+        min_agl: f32::INFINITY,
+        ground_speed: 0.0,
+        explored: true,
+    };
+    // Covers the western column of cell centers.
+    let region = Region {
+        polygons: vec![vec![square([10.95, 46.95], [11.05, 47.15])]],
+    };
+
+    let (inside, outside) = split_by_region(
+        &[
+            node((0, 0), true),
+            node((1, 0), false),
+            node((0, 1), true),
+            node((1, 1), true),
+        ],
+        &grid,
+        &region,
+    );
+    assert_eq!(
+        inside.iter().map(|n| n.ix).collect::<Vec<_>>(),
+        vec![(0, 0)]
+    );
+    assert_eq!(
+        outside.iter().map(|n| n.ix).collect::<Vec<_>>(),
+        vec![(0, 1), (1, 1)]
+    );
+}
+
+#[test]
+fn test_region_mask() {
+    let grid = HeightGrid::for_test(Array2::zeros((2, 2)), (47.0, 47.2), (11.0, 11.2));
+    let node = |ix: GridIx, reachable| Node {
+        height: 500.0,
+        ix,
+        reference: None,
+        distance: 0.0,
+        reachable,
+        clearance: f32::INFINITY,
+        min_agl: f32::INFINITY,
+        ground_speed: 0.0,
+        explored: true,
+    };
+    let nodes = [
+        node((0, 0), true),
+        node((1, 0), false),
+        node((0, 1), true),
+        node((1, 1), true),
+    ];
+    // Covers the southern row of cell centers, the second polygon is far
+    // away from the grid.
+    let region = Region {
+        polygons: vec![
+            vec![square([10.95, 46.95], [11.15, 47.05])],
+            vec![square([20.0, 20.0], [21.0, 21.0])],
+        ],
+    };
+    assert_eq!(
+        region
+            .clipped_to(grid.latitudes, grid.longitudes)
+            .polygons
+            .len(),
+        1
+    );
+
+    let mask = region_mask(&nodes, &grid, &region);
+    assert_eq!(
+        mask,
+        Array2::from_shape_vec((2, 2), vec![true, true, false, false]).unwrap()
+    );
+
+    let far_away = Region {
+        polygons: vec![vec![square([20.0, 20.0], [21.0, 21.0])]],
+    };
+    assert_eq!(
+        region_mask(&nodes, &grid, &far_away),
+        Array2::from_elem((2, 2), false)
+    );
+}
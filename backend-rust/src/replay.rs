@@ -0,0 +1,107 @@
+/// Log of served requests in the data directory, written when `replay_log` is
+/// enabled in the server config.
+pub const REPLAY_LOG_FILE_NAME: &str = "replay.log";
+
+/// One served request, stored as a tab separated line
+/// `<unix time ms>\t<status>\t<duration us>\t<path>?<query>`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReplayEntry {
+    pub timestamp_ms: u64,
+    pub status: u16,
+    pub duration_us: u64,
+    pub path: String,
+    /// Query string in canonical form, see [`canonicalize_query`].
+    pub query: String,
+}
+
+impl ReplayEntry {
+    pub fn uri(&self) -> String {
+        if self.query.is_empty() {
+            self.path.clone()
+        } else {
+            format!("{}?{}", self.path, self.query)
+        }
+    }
+
+    pub fn to_line(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}",
+            self.timestamp_ms,
+            self.status,
+            self.duration_us,
+            self.uri()
+        )
+    }
+
+    pub fn parse_line(line: &str) -> Result<ReplayEntry, String> {
+        let parts = line.split('\t').collect::<Vec<_>>();
+        if parts.len() != 4 {
+            return Err("expected 4 fields".to_string());
+        }
+        let number =
+            |part: &str, name: &str| part.parse::<u64>().map_err(|e| format!("bad {name}: {e}"));
+        let (path, query) = parts[3].split_once('?').unwrap_or((parts[3], ""));
+        Ok(ReplayEntry {
+            timestamp_ms: number(parts[0], "timestamp")?,
+            status: number(parts[1], "status")? as u16,
+            duration_us: number(parts[2], "duration")?,
+            path: path.to_string(),
+            query: canonicalize_query(query),
+        })
+    }
+}
+
+/// Sorts the parameters of a query string and drops empty ones, so equal
+/// requests are logged identically regardless of the parameter order.
+pub fn canonicalize_query(query: &str) -> String {
+    let mut parameters = query
+        .split('&')
+        .filter(|p| !p.is_empty() && !p.ends_with('='))
+        .collect::<Vec<_>>();
+    parameters.sort();
+    parameters.join("&")
+}
+
+pub fn parse_replay_log(content: &str) -> Result<Vec<ReplayEntry>, String> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(line_number, line)| {
+            ReplayEntry::parse_line(line).map_err(|e| format!("Line {}: {e}", line_number + 1))
+        })
+        .collect()
+}
+
+/// Latency distribution of all requests to one path, in microseconds.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LatencySummary {
+    pub count: usize,
+    pub p50: u64,
+    pub p95: u64,
+    pub max: u64,
+}
+
+/// Nearest rank percentile, `durations` has to be sorted.
+fn percentile(durations: &[u64], p: f32) -> u64 {
+    let rank = (p * durations.len() as f32).ceil() as usize;
+    durations[rank.clamp(1, durations.len()) - 1]
+}
+
+pub fn latency_summary(durations: &[u64]) -> Option<LatencySummary> {
+    if durations.is_empty() {
+        return None;
+    }
+    let mut sorted = durations.to_vec();
+    sorted.sort_unstable();
+    Some(LatencySummary {
+        count: sorted.len(),
+        p50: percentile(&sorted, 0.5),
+        p95: percentile(&sorted, 0.95),
+        max: sorted[sorted.len() - 1],
+    })
+}
+
+#[cfg(test)]
+#[path = "./replay_test.rs"]
+mod replay_test;
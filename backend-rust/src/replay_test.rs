@@ -0,0 +1,42 @@
+use super::{canonicalize_query, latency_summary, parse_replay_log, ReplayEntry};
+
+#[test]
+fn test_canonicalize_query() {
+    assert_eq!(
+        canonicalize_query("lon=11.2&lat=47.1&wind_speed=&cell_size=100"),
+        "cell_size=100&lat=47.1&lon=11.2"
+    );
+    assert_eq!(canonicalize_query(""), "");
+}
+
+#[test]
+fn test_replay_entry_roundtrip() {
+    let entry = ReplayEntry {
+        timestamp_ms: 1700000000000,
+        status: 200,
+        duration_us: 15320,
+        path: "/flight_cone".to_string(),
+        query: "lat=47.1&lon=11.2".to_string(),
+    };
+    let line = entry.to_line();
+    assert_eq!(
+        line,
+        "1700000000000\t200\t15320\t/flight_cone?lat=47.1&lon=11.2"
+    );
+    assert_eq!(ReplayEntry::parse_line(&line), Ok(entry));
+
+    let entries = parse_replay_log("1\t404\t20\t/stats\n\n2\t200\t30\t/search?q=ab\n").unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].uri(), "/stats");
+    assert!(parse_replay_log("1\t200\t/stats").is_err());
+}
+
+#[test]
+fn test_latency_summary() {
+    let summary = latency_summary(&[50, 10, 40, 20, 30]).unwrap();
+    assert_eq!(summary.count, 5);
+    assert_eq!(summary.p50, 30);
+    assert_eq!(summary.p95, 50);
+    assert_eq!(summary.max, 50);
+    assert!(latency_summary(&[]).is_none());
+}
@@ -0,0 +1,154 @@
+use ndarray::Array2;
+
+use crate::{
+    pqueue::PriorityQueue,
+    search::{
+        get_effective_glide_ratio_from_to, get_neighbor_indices, l2_distance, Explored, GridIx,
+        GridIxType, GridMap, Node, SearchConfig,
+    },
+};
+
+/// Samples per cell when checking the line of sight of a glide.
+const SAMPLES_PER_CELL: f32 = 4.0;
+
+/// Backwards search from a landing spot: the lowest height above each cell
+/// from which `goal` can be reached arriving at `goal_height`. Node heights
+/// are these required heights, references point towards the goal and
+/// distances are the remaining glide distance. Cells needing more than
+/// `max_height` are not explored.
+///
+/// Glides pass every cell at least the safety margin above the terrain,
+/// except within `start_distance` of the goal. Ridge lift, airspace and start
+/// sectors are not taken into account.
+pub fn reverse_search(
+    goal: GridIx,
+    goal_height: f32,
+    max_height: f32,
+    config: &SearchConfig,
+) -> Explored {
+    let shape = config.grid.heights.shape();
+    let mut required = Array2::from_elem((shape[0], shape[1]), f32::INFINITY);
+    let mut nodes = Array2::from_elem((shape[0], shape[1]), Node::new());
+    let mut queue = PriorityQueue::<f32, GridIx>::new();
+
+    required[(goal.0 as usize, goal.1 as usize)] = goal_height;
+    nodes[(goal.0 as usize, goal.1 as usize)] = Node {
+        height: goal_height,
+        ix: goal,
+        reference: None,
+        distance: 0.0,
+        reachable: true,
+        clearance: f32::INFINITY,
+        min_agl: f32::INFINITY,
+        ground_speed: 0.0,
+        explored: false,
+    };
+    queue.push(goal, goal_height);
+
+    // Required heights only increase away from the goal, so popping the
+    // lowest cell first settles it, like Dijkstra.
+    while let Some(first) = queue.pop() {
+        if config.is_cancelled() {
+            break;
+        }
+        let to = first.key;
+        nodes[(to.0 as usize, to.1 as usize)].explored = true;
+        let to_node = nodes[(to.0 as usize, to.1 as usize)].clone();
+
+        for from in get_neighbor_indices(&to, &config.grid) {
+            if nodes[(from.0 as usize, from.1 as usize)].explored {
+                continue;
+            }
+
+            // Like in the forward search, glide straight to the reference of
+            // the neighbor if the terrain allows it.
+            let candidate = to_node
+                .reference
+                .map(|r| &nodes[(r.0 as usize, r.1 as usize)])
+                .and_then(|r| glide_from(&from, r, config, false).map(|g| (g, r)))
+                .or_else(|| glide_from(&from, &to_node, config, true).map(|g| (g, &to_node)));
+            let Some(((height, distance), target)) = candidate else {
+                continue;
+            };
+            if height > max_height || height >= required[(from.0 as usize, from.1 as usize)] {
+                continue;
+            }
+
+            let grid_height = config.grid.heights[(from.0 as usize, from.1 as usize)] as f32;
+            let clearance = config.clearance_at_distance(distance, height, grid_height);
+            let node = Node {
+                height,
+                ix: from,
+                reference: Some(target.ix),
+                distance,
+                reachable: true,
+                clearance: clearance.min(target.clearance),
+                min_agl: f32::INFINITY,
+                ground_speed: 0.0,
+                explored: false,
+            };
+            required[(from.0 as usize, from.1 as usize)] = height;
+            nodes[(from.0 as usize, from.1 as usize)] = node;
+            if queue.contains_key(&from) {
+                queue.update_priority_if_less(from, height);
+            } else {
+                queue.push(from, height);
+            }
+        }
+    }
+
+    let mut explored = GridMap::new((shape[0] as GridIxType, shape[1] as GridIxType));
+    for node in nodes.into_iter().filter(|node| node.explored) {
+        explored.insert(node.ix, node);
+    }
+    explored
+}
+
+/// Height needed above `from` to glide straight to `to`, and the total
+/// distance to the goal, or `None` if the glide is not possible. With
+/// `clamp`, a glide that would start below the terrain (plus safety margin)
+/// starts at it instead, otherwise it is rejected like any glide through
+/// the terrain.
+fn glide_from(from: &GridIx, to: &Node, config: &SearchConfig, clamp: bool) -> Option<(f32, f32)> {
+    let glide_ratio =
+        get_effective_glide_ratio_from_to(config, &to.ix, from, to.height).glide_ratio;
+    if glide_ratio.is_infinite() {
+        return None;
+    }
+
+    let length = l2_distance(from, &to.ix);
+    let distance = to.distance + length * config.grid.cell_size;
+    let mut height = to.height + length * config.grid.cell_size * glide_ratio;
+    let clearance_needed = |distance: f32| config.query.safety_margin_at_distance(distance);
+
+    let from_ground =
+        config.grid.heights[(from.0 as usize, from.1 as usize)] as f32 + clearance_needed(distance);
+    if height < from_ground {
+        if !clamp {
+            return None;
+        }
+        height = from_ground;
+    }
+
+    let samples = ((length * SAMPLES_PER_CELL).ceil() as usize).max(1);
+    for i in 1..samples {
+        let t = i as f32 / samples as f32;
+        let row = (to.ix.0 as f32 + (from.0 as f32 - to.ix.0 as f32) * t).round() as usize;
+        let col = (to.ix.1 as f32 + (from.1 as f32 - to.ix.1 as f32) * t).round() as usize;
+        // The heights at the ends of the glide are already checked.
+        let cell = (row as GridIxType, col as GridIxType);
+        if cell == to.ix || cell == *from {
+            continue;
+        }
+        let line_height = to.height + (height - to.height) * t;
+        let line_distance = to.distance + (distance - to.distance) * t;
+        if line_height < config.grid.heights[(row, col)] as f32 + clearance_needed(line_distance) {
+            return None;
+        }
+    }
+    Some((height, distance))
+}
+
+#[cfg(test)]
+#[path = "./reverse_test.rs"]
+mod reverse_test;
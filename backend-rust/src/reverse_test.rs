@@ -0,0 +1,78 @@
+use approx::assert_relative_eq;
+use ndarray::Array2;
+
+use crate::{
+    height_data::HeightGrid,
+    search::{search, SearchConfig, SearchQuery},
+};
+
+use super::reverse_search;
+
+fn config(heights: Array2<i16>, wind_speed: f32, wind_direction: f32) -> SearchConfig {
+    SearchConfig {
+        grid: HeightGrid::for_test(heights, (47.0, 47.1), (11.0, 11.1)),
+        query: SearchQuery {
+            wind_direction: wind_direction.to_radians(),
+            wind_speed,
+            reverse: true,
+            ..Default::default()
+        },
+        wind_field: None,
+        airspace: None,
+        cancellation: None,
+    }
+}
+
+#[test]
+fn test_reverse_search() {
+    let config = config(Array2::zeros((21, 21)), 0.0, 0.0);
+    let explored = reverse_search((10, 10), 0.0, 100.0, &config);
+    let height_at = |ix| explored.iter().find(|n| n.ix == ix).map(|n| n.height);
+
+    assert_eq!(height_at((10, 10)), Some(0.0));
+    assert_relative_eq!(height_at((10, 14)).unwrap(), 50.0, epsilon = 0.01);
+    // Glides go straight to the goal, not along the grid.
+    assert_relative_eq!(height_at((14, 13)).unwrap(), 62.5, epsilon = 0.01);
+    // Would need 112.5 m.
+    assert_eq!(height_at((10, 19)), None);
+}
+
+#[test]
+fn test_reverse_search_over_wall() {
+    let mut heights = Array2::zeros((21, 21));
+    for row in 0..21 {
+        heights[(row, 12)] = 200;
+    }
+    let config = config(heights, 0.0, 0.0);
+    let explored = reverse_search((10, 10), 0.0, 1000.0, &config);
+    let node_at = |ix| explored.iter().find(|n| n.ix == ix).unwrap().clone();
+
+    assert_relative_eq!(node_at((10, 12)).height, 200.0);
+    let behind_wall = node_at((10, 14));
+    assert_relative_eq!(behind_wall.height, 225.0, epsilon = 0.01);
+    assert_eq!(behind_wall.reference, Some((10, 12)));
+    // The glide just clears the top of the wall.
+    assert_relative_eq!(behind_wall.clearance, 0.0, epsilon = 0.01);
+}
+
+#[test]
+fn test_reverse_search_matches_forward_search() {
+    let mut heights = Array2::zeros((21, 21));
+    for row in 4..17 {
+        heights[(row, 12)] = 150;
+    }
+    let config = config(heights, 15.0, 270.0);
+    let explored = reverse_search((10, 10), 0.0, 1000.0, &config);
+
+    // Starting at the required height from any cell reaches the goal.
+    for start in [(10, 16), (10, 4), (3, 14), (18, 18)] {
+        let required = explored.iter().find(|n| n.ix == start).unwrap().height;
+        let forward = search(start, required + 1.0, &config);
+        let goal = forward.explored.iter().find(|n| n.ix == (10, 10)).unwrap();
+        assert!(goal.reachable, "{start:?}");
+    }
+
+    // Against the wind more height is needed.
+    let height_at = |ix| explored.iter().find(|n| n.ix == ix).unwrap().height;
+    assert!(height_at((10, 16)) > height_at((10, 4)));
+}
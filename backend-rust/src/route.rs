@@ -0,0 +1,209 @@
+use ndarray::Array2;
+use serde::Serialize;
+
+use crate::{
+    cone_stats::agl_of,
+    height_data::{offset_point, point_distance, HeightGrid},
+    landing::{slope_at, MAX_LANDING_SLOPE},
+    search::Node,
+};
+
+/// Flights of a route at most, every flight is a search of its own.
+pub const MAX_FLIGHTS: usize = 8;
+/// Default of the longest hike between a landing and the next launch in
+/// meters.
+pub const MAX_HIKE_DEFAULT: f32 = 5000.0;
+/// A landing has to be this much closer to the destination than the launch
+/// in meters, so routes do not go in circles.
+pub const MIN_PROGRESS_M: f32 = 500.0;
+
+/// Hiking speeds of the DIN 33466 estimate: km/h on the flat, meters per
+/// hour up and down.
+const HIKE_SPEED_KMH: f32 = 4.0;
+const HIKE_ASCENT_M_PER_H: f32 = 300.0;
+const HIKE_DESCENT_M_PER_H: f32 = 500.0;
+
+/// Cone of one flight of a route.
+pub struct RouteCone {
+    pub explored: Vec<Node>,
+    pub grid: HeightGrid,
+    /// Height above sea level at the launch in meters.
+    pub start_height: f32,
+    /// Reachable cells where landing is prohibited.
+    pub landing_prohibited: Option<Array2<bool>>,
+}
+
+/// Part of a route, positions are `(latitude, longitude)`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum Leg {
+    Fly {
+        from: (f32, f32),
+        to: (f32, f32),
+        /// Height above sea level at the launch in meters.
+        start_height: f32,
+        /// Height above sea level and above ground on arrival in meters.
+        arrival_height: f32,
+        arrival_agl: f32,
+        /// Length of the glide path in meters.
+        distance: f32,
+    },
+    Hike {
+        from: (f32, f32),
+        to: (f32, f32),
+        /// Straight-line distance in meters.
+        distance: f32,
+        ascent: f32,
+        descent: f32,
+        /// Hiking time in hours, see `hike_hours`.
+        hours: f32,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Route {
+    /// Flights and hikes in turn, starting with a flight.
+    pub legs: Vec<Leg>,
+    /// Whether the legs end at the destination. Otherwise they end where no
+    /// further progress was found.
+    pub reached: bool,
+}
+
+/// Hiking time in hours after DIN 33466: the longer of the times for the
+/// distance and for the height difference plus half of the shorter one.
+pub fn hike_hours(distance: f32, ascent: f32, descent: f32) -> f32 {
+    let horizontal = distance / 1000.0 / HIKE_SPEED_KMH;
+    let vertical = ascent / HIKE_ASCENT_M_PER_H + descent / HIKE_DESCENT_M_PER_H;
+    horizontal.max(vertical) + horizontal.min(vertical) / 2.0
+}
+
+fn hike(grid: &HeightGrid, from: (usize, usize), to: (f32, f32)) -> Leg {
+    let from_position = grid.cell_center(from);
+    let from_height = grid.heights[from] as f32;
+    let to_height = grid
+        .index_of(to.0, to.1)
+        .map_or(from_height, |ix| grid.heights[ix] as f32);
+    let distance = point_distance(from_position.0, from_position.1, to.0, to.1);
+    let (ascent, descent) = (
+        (to_height - from_height).max(0.0),
+        (from_height - to_height).max(0.0),
+    );
+    Leg::Hike {
+        from: from_position,
+        to,
+        distance,
+        ascent,
+        descent,
+        hours: hike_hours(distance, ascent, descent),
+    }
+}
+
+/// Highest cell within `max_hike` meters of `landing` that is not farther
+/// from the destination than the landing.
+fn relaunch(
+    grid: &HeightGrid,
+    landing: (usize, usize),
+    destination: (f32, f32),
+    max_hike: f32,
+) -> Option<(usize, usize)> {
+    let (lat, lon) = grid.cell_center(landing);
+    let remaining = point_distance(lat, lon, destination.0, destination.1);
+    let south_west = offset_point(lat, lon, -max_hike, -max_hike);
+    let north_east = offset_point(lat, lon, max_hike, max_hike);
+    grid.cells_in_bounds((south_west.0, north_east.0), (south_west.1, north_east.1))
+        .filter(|&ix| {
+            let (cell_lat, cell_lon) = grid.cell_center(ix);
+            point_distance(lat, lon, cell_lat, cell_lon) <= max_hike
+                && point_distance(cell_lat, cell_lon, destination.0, destination.1) <= remaining
+        })
+        .max_by_key(|&ix| grid.heights[ix])
+        .filter(|&ix| grid.heights[ix] > grid.heights[landing])
+}
+
+/// Hike and fly route from `from` to `to`: flights to the landable cell of
+/// each cone closest to the destination, and hikes from there up to the
+/// highest point within `max_hike` meters towards the destination. The
+/// route ends with a flight or a hike to the destination, or where it gets
+/// stuck. `search` computes the cone from a launch.
+pub fn plan_route<E>(
+    from: (f32, f32),
+    to: (f32, f32),
+    max_hike: f32,
+    mut search: impl FnMut(f32, f32) -> Result<RouteCone, E>,
+) -> Result<Route, E> {
+    let mut legs = vec![];
+    let mut launch = from;
+    for _ in 0..MAX_FLIGHTS {
+        let cone = search(launch.0, launch.1)?;
+        let grid = &cone.grid;
+        let reachable = |ix: (usize, usize)| {
+            cone.explored
+                .iter()
+                .find(|node| node.reachable && (node.ix.0 as usize, node.ix.1 as usize) == ix)
+        };
+        let fly = |node: &Node, to: (f32, f32)| Leg::Fly {
+            from: launch,
+            to,
+            start_height: cone.start_height,
+            arrival_height: node.height,
+            arrival_agl: agl_of(node, grid),
+            distance: node.distance,
+        };
+
+        if let Some(node) = grid.index_of(to.0, to.1).and_then(reachable) {
+            legs.push(fly(node, to));
+            return Ok(Route {
+                legs,
+                reached: true,
+            });
+        }
+
+        let distance_to_destination = |ix: (usize, usize)| {
+            let (lat, lon) = grid.cell_center(ix);
+            point_distance(lat, lon, to.0, to.1)
+        };
+        let landing = cone
+            .explored
+            .iter()
+            .filter(|node| node.reachable)
+            .map(|node| (node, (node.ix.0 as usize, node.ix.1 as usize)))
+            .filter(|(_, ix)| {
+                !cone
+                    .landing_prohibited
+                    .as_ref()
+                    .is_some_and(|mask| mask[*ix])
+                    && slope_at(grid, ix.0, ix.1) <= MAX_LANDING_SLOPE
+            })
+            .map(|(node, ix)| (node, ix, distance_to_destination(ix)))
+            .min_by(|a, b| a.2.total_cmp(&b.2));
+        let Some((node, landing, remaining)) = landing else {
+            break;
+        };
+        if remaining > point_distance(launch.0, launch.1, to.0, to.1) - MIN_PROGRESS_M {
+            break;
+        }
+        legs.push(fly(node, grid.cell_center(landing)));
+
+        if remaining <= max_hike {
+            legs.push(hike(grid, landing, to));
+            return Ok(Route {
+                legs,
+                reached: true,
+            });
+        }
+        let Some(next) = relaunch(grid, landing, to, max_hike) else {
+            break;
+        };
+        let next_position = grid.cell_center(next);
+        legs.push(hike(grid, landing, next_position));
+        launch = next_position;
+    }
+    Ok(Route {
+        legs,
+        reached: false,
+    })
+}
+
+#[cfg(test)]
+#[path = "./route_test.rs"]
+mod route_test;
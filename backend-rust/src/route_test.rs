@@ -0,0 +1,109 @@
+use approx::assert_relative_eq;
+use ndarray::Array2;
+
+use crate::{height_data::HeightGrid, search::Node};
+
+use super::{hike_hours, plan_route, Leg, RouteCone};
+
+/// A flat valley at 500 m, 3 rows by 30 columns of about 100 m, with a
+/// 1500 m summit in the middle of column 15.
+fn grid() -> HeightGrid {
+    let mut heights = Array2::from_elem((3, 30), 500);
+    heights[(1, 15)] = 1500;
+    HeightGrid::for_test(
+        heights,
+        (47.0, 47.0 + 3.0 * 0.0009),
+        (11.0, 11.0 + 30.0 * 0.0013),
+    )
+}
+
+/// Cone reaching 10 columns to either side of the launch.
+fn search(lat: f32, lon: f32) -> Result<RouteCone, String> {
+    let grid = grid();
+    let (row, col) = grid.index_of(lat, lon).ok_or("Outside of the grid")?;
+    let start_height = grid.heights[(row, col)] as f32 + 10.0;
+    let explored = (0..3)
+        .flat_map(|r| (col.saturating_sub(10)..(col + 11).min(30)).map(move |c| (r, c)))
+        .map(|(r, c)| {
+            let distance = (c as f32 - col as f32).abs() * 100.0;
+            Node {
+                height: start_height - distance / 8.0,
+                ix: (r as _, c as _),
+                reference: None,
+                distance,
+                reachable: true,
+                clearance: f32::INFINITY,
+                min_agl: f32::INFINITY,
+                ground_speed: 0.0,
+                explored: true,
+            }
+        })
+        .collect();
+    Ok(RouteCone {
+        explored,
+        grid,
+        start_height,
+        landing_prohibited: None,
+    })
+}
+
+#[test]
+fn test_hike_hours() {
+    // 8 km flat take 2 h.
+    assert_relative_eq!(hike_hours(8000.0, 0.0, 0.0), 2.0);
+    // 4 km and 600 m up: 2 h for the ascent and half of the 1 h distance.
+    assert_relative_eq!(hike_hours(4000.0, 600.0, 0.0), 2.5);
+}
+
+#[test]
+fn test_plan_route() {
+    let grid = grid();
+    let from = grid.cell_center((1, 0));
+    let to = grid.cell_center((1, 29));
+
+    let route = plan_route(from, to, 600.0, search).unwrap();
+
+    assert!(route.reached);
+    let modes = route
+        .legs
+        .iter()
+        .map(|leg| match leg {
+            Leg::Fly { .. } => "fly",
+            Leg::Hike { .. } => "hike",
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(modes, vec!["fly", "hike", "fly", "hike"]);
+
+    // Down to the valley as close to the destination as the cone goes, then
+    // up the summit.
+    let Leg::Hike {
+        from: landing,
+        to: summit,
+        ascent,
+        ..
+    } = route.legs[1]
+    else {
+        panic!("Second leg is a hike");
+    };
+    assert_eq!(grid.index_of(landing.0, landing.1), Some((1, 10)));
+    assert_eq!(grid.index_of(summit.0, summit.1), Some((1, 15)));
+    assert_relative_eq!(ascent, 1000.0);
+
+    let Leg::Hike { to: end, .. } = route.legs[3] else {
+        panic!("Last leg is a hike");
+    };
+    assert_eq!(end, to);
+}
+
+#[test]
+fn test_plan_route_stuck() {
+    let grid = grid();
+    let from = grid.cell_center((1, 0));
+    let to = grid.cell_center((1, 29));
+
+    // Without hiking the summit can't be reached.
+    let route = plan_route(from, to, 0.0, search).unwrap();
+
+    assert!(!route.reached);
+    assert_eq!(route.legs.len(), 1);
+}
@@ -0,0 +1,161 @@
+use std::{
+    sync::{Arc, OnceLock},
+    time::{Duration, Instant},
+};
+
+use rhai::{Dynamic, Engine, Scope, AST};
+
+use crate::{
+    height_data::HeightGrid,
+    search::{Edge, EdgeHook},
+};
+
+/// Operations a script may run per glide.
+pub const MAX_OPERATIONS: u64 = 10_000;
+/// Time the script may take in one search, for all glides together.
+pub const TIME_LIMIT: Duration = Duration::from_secs(2);
+/// Longest script in bytes.
+pub const MAX_SCRIPT_LENGTH: usize = 4096;
+
+/// Engine all scripts run in, without `eval` or output and with small limits
+/// on everything a script could allocate.
+fn engine() -> &'static Engine {
+    static ENGINE: OnceLock<Engine> = OnceLock::new();
+    ENGINE.get_or_init(|| {
+        let mut engine = Engine::new();
+        engine
+            .set_max_operations(MAX_OPERATIONS)
+            .set_max_call_levels(16)
+            .set_max_expr_depths(32, 32)
+            .set_max_string_size(1024)
+            .set_max_array_size(1024)
+            .set_max_map_size(1024)
+            .disable_symbol("eval")
+            .on_print(|_| {})
+            .on_debug(|_, _, _| {});
+        engine
+    })
+}
+
+/// Rhai script adjusting the glides of a search, e.g. to penalize crossing a
+/// valley. It is run for every glide with `from_lat`, `from_lon`, `lat` and
+/// `lon` of its cells, `from_height` and `height` above sea level at them,
+/// the `ground` height and `agl` at the end and the `distance` in meters.
+/// It returns a factor on the height lost on the glide, or `false` to make
+/// the end unreachable through it:
+///
+/// ```text
+/// if lat > 47.25 && lat < 47.3 { 1.5 } else { 1.0 }
+/// ```
+///
+/// The search area is still sized for the glide of the query, factors below
+/// 1 can't extend the cone beyond it.
+#[derive(Clone)]
+pub struct EdgeScript {
+    source: String,
+    ast: Arc<AST>,
+}
+
+impl EdgeScript {
+    pub fn compile(source: &str) -> Result<EdgeScript, String> {
+        if source.len() > MAX_SCRIPT_LENGTH {
+            return Err(format!(
+                "The script is longer than {MAX_SCRIPT_LENGTH} bytes"
+            ));
+        }
+        let ast = engine()
+            .compile(source)
+            .map_err(|e| format!("Bad script: {e}"))?;
+        Ok(EdgeScript {
+            source: source.to_string(),
+            ast: Arc::new(ast),
+        })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Hook running the script in one search. Its time limit starts with the
+    /// first glide.
+    pub fn hook(&self) -> ScriptHook {
+        ScriptHook {
+            ast: self.ast.clone(),
+            started: OnceLock::new(),
+            error: OnceLock::new(),
+        }
+    }
+}
+
+pub struct ScriptHook {
+    ast: Arc<AST>,
+    started: OnceLock<Instant>,
+    /// Once set, all further glides are unreachable so the search ends soon.
+    error: OnceLock<String>,
+}
+
+impl ScriptHook {
+    fn run(&self, grid: &HeightGrid, edge: &Edge) -> Result<Option<f32>, String> {
+        if self.started.get_or_init(Instant::now).elapsed() > TIME_LIMIT {
+            return Err(format!("The script took longer than {TIME_LIMIT:?}"));
+        }
+        let (from_lat, from_lon) = grid.coordinates_at(edge.from.0 as f32, edge.from.1 as f32);
+        let (lat, lon) = grid.coordinates_at(edge.to.0 as f32, edge.to.1 as f32);
+        let mut scope = Scope::new();
+        for (name, value) in [
+            ("from_lat", from_lat),
+            ("from_lon", from_lon),
+            ("lat", lat),
+            ("lon", lon),
+            ("from_height", edge.from_height),
+            ("height", edge.to_height),
+            ("ground", edge.ground_height),
+            ("agl", edge.to_height - edge.ground_height),
+            ("distance", edge.distance),
+        ] {
+            scope.push(name, value as f64);
+        }
+
+        let result = engine()
+            .eval_ast_with_scope::<Dynamic>(&mut scope, &self.ast)
+            .map_err(|e| format!("The script failed: {e}"))?;
+        if let Ok(allowed) = result.as_bool() {
+            return Ok(allowed.then_some(1.0));
+        }
+        let factor = result
+            .as_float()
+            .or_else(|_| result.as_int().map(|factor| factor as f64))
+            .map_err(|_| {
+                format!(
+                    "The script returned a {} instead of a number or a bool",
+                    result.type_name()
+                )
+            })?;
+        if !(factor.is_finite() && factor > 0.0) {
+            return Err(format!(
+                "The script returned {factor}, factors must be positive"
+            ));
+        }
+        Ok(Some(factor as f32))
+    }
+}
+
+impl EdgeHook for ScriptHook {
+    fn height_loss_factor(&self, grid: &HeightGrid, edge: &Edge) -> Option<f32> {
+        if self.error.get().is_some() {
+            return None;
+        }
+        self.run(grid, edge).unwrap_or_else(|e| {
+            let _ = self.error.set(e);
+            None
+        })
+    }
+
+    fn error(&self) -> Option<String> {
+        self.error.get().cloned()
+    }
+}
+
+#[cfg(test)]
+#[path = "./scripting_test.rs"]
+mod scripting_test;
@@ -0,0 +1,69 @@
+use std::sync::Arc;
+
+use ndarray::Array2;
+
+use crate::{
+    height_data::HeightGrid,
+    search::{search_from_height_grid, SearchError, SearchQuery, SearchResult},
+};
+
+use super::{EdgeScript, MAX_SCRIPT_LENGTH};
+
+fn search(script: Option<&str>) -> Result<SearchResult, SearchError> {
+    let mut builder = SearchQuery::builder()
+        .glide_number(8.0)
+        .additional_height_m(100.0);
+    if let Some(script) = script {
+        builder = builder.edge_hook(Arc::new(EdgeScript::compile(script).unwrap().hook()));
+    }
+    search_from_height_grid(
+        HeightGrid::for_test(Array2::zeros((21, 21)), (47.0, 47.02), (11.0, 11.03)),
+        (10, 10),
+        builder.build().unwrap(),
+    )
+}
+
+fn reachable(result: &SearchResult) -> usize {
+    result.explored.iter().filter(|node| node.reachable).count()
+}
+
+#[test]
+fn test_compile() {
+    assert!(EdgeScript::compile("if agl > 50.0 { 1.0 } else { 2.0 }").is_ok());
+    assert!(EdgeScript::compile("if agl > { 1.0 }").is_err());
+    assert!(EdgeScript::compile(&"1.0;".repeat(MAX_SCRIPT_LENGTH)).is_err());
+}
+
+#[test]
+fn test_script_adjusts_the_glide() {
+    let plain = search(None).unwrap();
+    let neutral = search(Some("true")).unwrap();
+    assert_eq!(reachable(&neutral), reachable(&plain));
+
+    let penalized = search(Some("2")).unwrap();
+    assert!(reachable(&penalized) < reachable(&plain));
+
+    // Nothing north of the start can be reached.
+    let blocked = search(Some("lat <= 47.0101")).unwrap();
+    assert!(reachable(&blocked) < reachable(&plain));
+    for node in blocked.explored.iter().filter(|node| node.reachable) {
+        let (lat, _) = blocked
+            .height_grid
+            .coordinates_at(node.ix.0 as f32, node.ix.1 as f32);
+        assert!(lat <= 47.0101);
+    }
+}
+
+#[test]
+fn test_script_limits() {
+    let endless = search(Some("loop {}"));
+    assert!(
+        matches!(endless, Err(SearchError::EdgeHook(message)) if message.contains("operations"))
+    );
+
+    let negative = search(Some("-1.0"));
+    assert!(matches!(negative, Err(SearchError::EdgeHook(_))));
+
+    let text = search(Some("\"far\""));
+    assert!(matches!(text, Err(SearchError::EdgeHook(_))));
+}
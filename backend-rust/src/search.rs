@@ -1,20 +1,92 @@
 use core::f32;
 use std::{
     cmp::{max, min},
-    collections::HashSet,
+    collections::{HashMap, HashSet},
+    fmt::{self, Display, Formatter},
     iter::zip,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
 };
 
-use ndarray::{linspace, s};
+use ndarray::{linspace, s, Array2};
 
 use crate::{
-    height_data::{get_height_at_point, get_height_data_around_point, HeightGrid},
+    airspace::{Airspace, AirspaceGrid},
+    height_data::{
+        area_supported, get_height_data_in_bounds_from, get_interpolated_height_at_point_from,
+        offset_point, point_distance, select_data_source, snap_cell_size, DataSource, HeightGrid,
+        Interpolation,
+    },
+    margin::MarginPoint,
+    metrics::{time_stage, Stage},
+    polar::{polar_samples, PolarPoint},
     pqueue::{MapLike, PriorityQueue},
+    reverse::reverse_search,
+    sectors::{direction_angle, in_sector},
+    wind::{max_wind_speed, wind_at_altitude, WindLayer},
 };
 
+/// Version of the search algorithm. Bump this whenever a change affects
+/// search results, so cached cones computed by older versions are not
+/// served anymore. Add a line to the changelog below when doing so.
+///
+/// 1: Initial version.
+/// 2: Fall back to coarse DEM data outside SRTM coverage.
+/// 3: Bilinear interpolation of the heights when downsampling the DEM.
+/// 4: Search area sized per direction by the wind.
+/// 5: Cell sizes snapped to the DEM resolution, mean decimation.
+pub const ALGORITHM_VERSION: u32 = 5;
+
+/// Index type of grid positions. u16 keeps the search state small, the
+/// `wide_grid_index` feature switches to u32 for very large grids.
+#[cfg(not(feature = "wide_grid_index"))]
 pub type GridIxType = u16;
+#[cfg(feature = "wide_grid_index")]
+pub type GridIxType = u32;
 pub type GridIx = (GridIxType, GridIxType);
 
+#[derive(Debug, PartialEq)]
+pub enum SearchError {
+    /// The grid has more cells than `GridIxType` can address.
+    GridTooLarge { rows: usize, cols: usize },
+    /// No DEM tile covers the search area.
+    NoHeightData,
+    /// The `EdgeHook` of the query failed, e.g. a script ran too long.
+    EdgeHook(String),
+}
+
+impl Display for SearchError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SearchError::GridTooLarge { rows, cols } => write!(
+                f,
+                "Search grid of {rows}x{cols} cells is too large, increase cell_size"
+            ),
+            SearchError::NoHeightData => {
+                write!(f, "No height data available at this location")
+            }
+            SearchError::EdgeHook(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+/// Checks that all positions of a grid with the given shape fit into
+/// `GridIx`, and all queue positions into `FakeHashMapPos`.
+pub fn check_grid_shape(rows: usize, cols: usize) -> Result<(), SearchError> {
+    let max = GridIxType::MAX as usize;
+    let fits = rows <= max
+        && cols <= max
+        && matches!(rows.checked_mul(cols), Some(cells) if cells < FakeHashMapPos::MAX as usize);
+    if fits {
+        Ok(())
+    } else {
+        Err(SearchError::GridTooLarge { rows, cols })
+    }
+}
+
 #[derive(Clone)]
 pub struct Node {
     pub height: f32,
@@ -22,7 +94,20 @@ pub struct Node {
     pub reference: Option<GridIx>,
     pub distance: f32,
     pub reachable: bool,
-    pub in_safety_margin: bool,
+    /// Lowest height above ground along the path to this node, ignoring the
+    /// part within `start_distance` of the start. Infinite if the whole path
+    /// is within the start distance. See `SearchConfig::clearance_at_distance`
+    /// for searches with a safety margin ramp.
+    pub clearance: f32,
+    /// Lowest height above ground on the way to this node, over the same
+    /// cells as `clearance` but without the node itself, so a cell reached by
+    /// just clearing a ridge stands out from one that is simply far away.
+    /// Never shifted by a safety margin ramp. Infinite if no cell before the
+    /// node counts, and in reverse searches.
+    pub min_agl: f32,
+    /// Ground speed in km/h on the final glide to this node, in the wind
+    /// where it arrives. 0 for start nodes and nodes of a reverse search.
+    pub ground_speed: f32,
     pub explored: bool,
 }
 
@@ -40,15 +125,42 @@ impl Node {
             reference: None,
             distance: 0.0,
             reachable: false,
-            in_safety_margin: true,
+            clearance: f32::NEG_INFINITY,
+            min_agl: f32::INFINITY,
+            ground_speed: 0.0,
             explored: false,
         }
     }
+
+    /// Whether the path to this node gets closer to the ground than
+    /// `safety_margin`. Allows evaluating several safety margins on the
+    /// result of a single search.
+    pub fn in_safety_margin(&self, safety_margin: f32) -> bool {
+        self.clearance < safety_margin
+    }
+}
+
+#[cfg(test)]
+impl Node {
+    /// Explored node without a reference or anything in the way.
+    pub fn for_test(ix: GridIx, height: f32, reachable: bool) -> Node {
+        Node {
+            height,
+            ix,
+            reference: None,
+            distance: 0.0,
+            reachable,
+            clearance: f32::INFINITY,
+            min_agl: f32::INFINITY,
+            ground_speed: 0.0,
+            explored: true,
+        }
+    }
 }
 
 pub struct GridMap {
     values: Vec<Node>,
-    grid_shape: (u16, u16),
+    grid_shape: (GridIxType, GridIxType),
 }
 
 pub struct GridMapIter<'a> {
@@ -72,15 +184,15 @@ impl<'a> Iterator for GridMapIter<'a> {
     }
 }
 
-fn to_ix(grid_shape: (u16, u16), index: usize) -> GridIx {
+fn to_ix(grid_shape: (GridIxType, GridIxType), index: usize) -> GridIx {
     (
-        (index / grid_shape.1 as usize) as u16,
-        (index % grid_shape.1 as usize) as u16,
+        (index / grid_shape.1 as usize) as GridIxType,
+        (index % grid_shape.1 as usize) as GridIxType,
     )
 }
 
 impl GridMap {
-    fn new(grid_shape: (u16, u16)) -> GridMap {
+    pub(crate) fn new(grid_shape: (GridIxType, GridIxType)) -> GridMap {
         let size = grid_shape.0 as usize * grid_shape.1 as usize;
         let mut values = vec![Node::new(); size];
         for (index, node) in values.iter_mut().enumerate() {
@@ -90,7 +202,7 @@ impl GridMap {
     }
 
     fn ix(&self, index: &GridIx) -> usize {
-        (index.0 as u32 * self.grid_shape.1 as u32 + index.1 as u32) as usize
+        index.0 as usize * self.grid_shape.1 as usize + index.1 as usize
     }
 
     unsafe fn get_unchecked(&self, index: &GridIx) -> &Node {
@@ -98,12 +210,11 @@ impl GridMap {
     }
 
     unsafe fn get_unchecked_mut(&mut self, index: &GridIx) -> &mut Node {
-        self.values.get_unchecked_mut(
-            (index.0 as u32 * self.grid_shape.1 as u32 + index.1 as u32) as usize,
-        )
+        let ix = self.ix(index);
+        self.values.get_unchecked_mut(ix)
     }
 
-    fn insert(&mut self, index: GridIx, value: Node) {
+    pub(crate) fn insert(&mut self, index: GridIx, value: Node) {
         let ix = self.ix(&index);
         *unsafe { self.values.get_unchecked_mut(ix) } = value;
     }
@@ -126,6 +237,14 @@ impl GridMap {
         result
     }
 
+    /// Node of an explored cell, `None` for other cells and outside of the
+    /// grid.
+    pub fn get(&self, index: &GridIx) -> Option<&Node> {
+        (index.0 < self.grid_shape.0 && index.1 < self.grid_shape.1)
+            .then(|| unsafe { self.get_unchecked(index) })
+            .filter(|node| node.explored)
+    }
+
     pub fn iter(&self) -> GridMapIter<'_> {
         GridMapIter {
             gridmap: self,
@@ -140,20 +259,61 @@ impl GridMap {
     pub fn into_it(self) -> impl Iterator<Item = Node> {
         self.values.into_iter().filter(|x| x.explored)
     }
+
+    /// Bearings from `start` in degrees clockwise from north, rounded to
+    /// `BORDER_BEARING_STEP`, under which reachable cells touch the border of
+    /// the grid. The cone may continue beyond the grid there, so it is
+    /// truncated in these directions.
+    pub fn border_bearings(&self, start: &GridIx) -> Vec<u16> {
+        let (rows, cols) = self.grid_shape;
+        let mut bearings = self
+            .iter()
+            .filter(|n| n.reachable)
+            .filter(|n| n.ix.0 == 0 || n.ix.1 == 0 || n.ix.0 + 1 == rows || n.ix.1 + 1 == cols)
+            .map(|n| {
+                let bearing = (90.0 - direction_angle(start, &n.ix).to_degrees()).rem_euclid(360.0);
+                let step = BORDER_BEARING_STEP as f32;
+                ((bearing / step).round() * step) as u16 % 360
+            })
+            .collect::<Vec<_>>();
+        bearings.sort();
+        bearings.dedup();
+        bearings
+    }
+
+    /// Keeps the better node of both maps for every cell: reachable nodes
+    /// over unreachable ones, then the higher one. Both maps have to be of
+    /// the same grid.
+    #[cfg(feature = "parallel")]
+    pub(crate) fn merge_highest(mut self, other: GridMap) -> GridMap {
+        for (node, other) in self.values.iter_mut().zip(other.values) {
+            if other.explored
+                && (!node.explored
+                    || (other.reachable, other.height) > (node.reachable, node.height))
+            {
+                *node = other;
+            }
+        }
+        self
+    }
 }
 
+/// Resolution of `GridMap::border_bearings` in degrees.
+pub const BORDER_BEARING_STEP: u16 = 10;
+
 //pub type Explored = HashMap<GridIx, Node>;
 pub type Explored = GridMap;
 
-pub type FakeHashMapPos = u16;
+/// Position in the queue, `MAX` marks cells that are not queued.
+pub type FakeHashMapPos = u32;
 
 pub struct FakeHashMapForGrid {
     positions: Vec<FakeHashMapPos>,
-    grid_shape: (u16, u16),
+    grid_shape: (GridIxType, GridIxType),
 }
 
 impl FakeHashMapForGrid {
-    pub fn new(grid_shape: (u16, u16)) -> FakeHashMapForGrid {
+    pub fn new(grid_shape: (GridIxType, GridIxType)) -> FakeHashMapForGrid {
         FakeHashMapForGrid {
             grid_shape,
             positions: vec![FakeHashMapPos::MAX; grid_shape.0 as usize * grid_shape.1 as usize],
@@ -161,7 +321,7 @@ impl FakeHashMapForGrid {
     }
 
     fn gridix_to_ix(&self, key: &GridIx) -> usize {
-        (key.0 as u32 * self.grid_shape.1 as u32 + key.1 as u32) as usize
+        key.0 as usize * self.grid_shape.1 as usize + key.1 as usize
     }
 }
 
@@ -209,13 +369,12 @@ pub struct SearchState {
 pub fn put_node(state: &mut SearchState, node: Node) {
     if state.queue.contains_key(&node.ix) {
         // Safety: We already checked above that the queue contains the key
-        let item = unsafe {
+        let updated = unsafe {
             state
                 .queue
                 .update_priority_if_less_unsafe(node.ix, node.distance)
         };
-        if let Some(i) = item {
-            i.item = node.distance; // TODO: Needed?
+        if updated {
             state.explored.insert(node.ix, node);
         }
     } else {
@@ -237,12 +396,12 @@ pub fn put_or_update(
     };
     if state.queue.contains_key(&ix) {
         // Safety: We already checked above that the queue contains the key
-        let item = unsafe {
+        let updated = unsafe {
             state
                 .queue
                 .update_priority_if_less_unsafe(ix, prio_distance)
         };
-        if item.is_some() {
+        if updated {
             return Some(unsafe { state.explored.get_unchecked_mut(&ix) });
         }
     } else {
@@ -253,9 +412,11 @@ pub fn put_or_update(
 }
 
 pub struct EffectiveGlide {
-    #[allow(dead_code)]
-    speed: f32,
-    glide_ratio: f32,
+    /// Ground speed in km/h.
+    pub(crate) speed: f32,
+    /// Airspeed in km/h the glide is flown at.
+    pub(crate) airspeed: f32,
+    pub(crate) glide_ratio: f32,
 }
 
 pub fn get_effective_glide_ratio(
@@ -271,6 +432,7 @@ pub fn get_effective_glide_ratio(
     if rs <= 0.0 {
         return EffectiveGlide {
             speed: 0.0,
+            airspeed: trim_speed,
             glide_ratio: f32::INFINITY,
         };
     }
@@ -281,6 +443,7 @@ pub fn get_effective_glide_ratio(
     if effective_speed <= 0.0 {
         return EffectiveGlide {
             speed: 0.0,
+            airspeed: trim_speed,
             glide_ratio: f32::INFINITY,
         };
     }
@@ -289,32 +452,537 @@ pub fn get_effective_glide_ratio(
 
     EffectiveGlide {
         speed: effective_speed,
+        airspeed: trim_speed,
         glide_ratio: effective_glide_ratio,
     }
 }
 
+/// Effective glide at the best speed to fly of the polar, i.e. the airspeed
+/// with the flattest glide over ground for the given wind.
+pub fn get_effective_glide_ratio_polar(
+    effective_wind_angle: f32,
+    wind_speed: f32,
+    polar: &[PolarPoint],
+) -> EffectiveGlide {
+    polar_samples(polar)
+        .map(|(speed, glide_ratio)| {
+            get_effective_glide_ratio(effective_wind_angle, wind_speed, speed, glide_ratio)
+        })
+        .min_by(|a, b| a.glide_ratio.total_cmp(&b.glide_ratio))
+        .unwrap_or(EffectiveGlide {
+            speed: 0.0,
+            airspeed: 0.0,
+            glide_ratio: f32::INFINITY,
+        })
+}
+
+/// Effective glide of the query, `effective_wind_angle` 0 is full tail wind.
+fn get_effective_glide_ratio_of_query(
+    query: &SearchQuery,
+    effective_wind_angle: f32,
+    wind_speed: f32,
+) -> EffectiveGlide {
+    match &query.polar {
+        Some(polar) => get_effective_glide_ratio_polar(effective_wind_angle, wind_speed, polar),
+        None => get_effective_glide_ratio(
+            effective_wind_angle,
+            wind_speed,
+            query.trim_speed,
+            query.glide_ratio,
+        ),
+    }
+}
+
+/// Standard gravity in m/s².
+const GRAVITY: f32 = 9.81;
+
+/// How the height lost between two cells is computed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum GlideModel {
+    /// Only the glide ratio of each straight glide counts.
+    #[default]
+    Geometric,
+    /// Total energy: changing the heading where the best speed to fly
+    /// differs also costs the height needed to speed up, see
+    /// `speed_change_height`. Only differs from `Geometric` with a polar in
+    /// wind, since the airspeed is the trim speed otherwise.
+    Energy,
+}
+
+impl GlideModel {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GlideModel::Geometric => "geometric",
+            GlideModel::Energy => "energy",
+        }
+    }
+}
+
+impl FromStr for GlideModel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "geometric" => Ok(GlideModel::Geometric),
+            "energy" => Ok(GlideModel::Energy),
+            _ => Err(format!("Unknown glide model {s}")),
+        }
+    }
+}
+
+/// Height in meters it takes to speed up from `from` to `to` km/h airspeed.
+/// Slowing down gains nothing, pilots brake rather than trade the speed for
+/// height.
+pub fn speed_change_height(from: f32, to: f32) -> f32 {
+    let (from, to) = (from / 3.6, to / 3.6);
+    ((to * to - from * from) / (2.0 * GRAVITY)).max(0.0)
+}
+
+/// Glide of the search from one cell to another, as seen by an `EdgeHook`.
+pub struct Edge {
+    pub from: GridIx,
+    pub to: GridIx,
+    /// Height above sea level at `from`.
+    pub from_height: f32,
+    /// Height above sea level when arriving at `to`.
+    pub to_height: f32,
+    /// Height of the ground at `to`.
+    pub ground_height: f32,
+    /// In meters.
+    pub distance: f32,
+}
+
+/// Adjusts the cost or reachability of single glides of the search, e.g.
+/// with a script of the user, see `scripting`. Not used by reverse
+/// searches.
+pub trait EdgeHook: Send + Sync {
+    /// Factor on the height lost on `edge`, above 1 penalizes it. `None`
+    /// makes `edge.to` unreachable through it.
+    fn height_loss_factor(&self, grid: &HeightGrid, edge: &Edge) -> Option<f32>;
+
+    /// Why the hook failed during the search, which fails the search.
+    fn error(&self) -> Option<String> {
+        None
+    }
+}
+
 pub struct SearchQuery {
     pub glide_ratio: f32,
     pub trim_speed: f32,
+    /// Speed polar of the wing. If set, the best speed to fly is used for
+    /// every direction instead of `glide_ratio` and `trim_speed`.
+    pub polar: Option<Vec<PolarPoint>>,
     pub wind_direction: f32,
     pub wind_speed: f32,
+    /// Wind by altitude. If not empty, replaces `wind_direction` and
+    /// `wind_speed`.
+    pub wind_layers: Vec<WindLayer>,
     pub start_height: Option<f32>,
     pub additional_height: f32,
     pub safety_margin: f32,
     pub start_distance: f32,
+    /// Safety margin by distance from the start. If not empty, replaces
+    /// `safety_margin`, which is then the largest margin of the ramp.
+    pub safety_margin_ramp: Vec<MarginPoint>,
+    /// Model lift on windward and sink on lee slopes close to the terrain.
+    pub ridge_lift: bool,
+    /// Bearings in radians, clockwise from north, between which the cone may
+    /// leave the start (clockwise from the first to the second). Cells outside
+    /// of the sector are never explored.
+    pub start_sector: Option<(f32, f32)>,
+    /// Search backwards from a landing spot: the result holds the height
+    /// needed above each cell to reach the point of the query, arriving
+    /// `additional_height` above the ground. `start_height` limits the
+    /// height to start from. See `reverse_search`.
+    pub reverse: bool,
+    /// Raise the terrain by the vertical error of the DEM during the search,
+    /// see `HeightGrid::vertical_error_at`. The start height is still based
+    /// on the ground height in the DEM.
+    pub terrain_error: bool,
+    pub model: GlideModel,
+    pub edge_hook: Option<Arc<dyn EdgeHook>>,
+}
+
+/// A typical paraglider in calm air, see `SearchQueryBuilder`.
+impl Default for SearchQuery {
+    fn default() -> Self {
+        SearchQuery {
+            glide_ratio: 1.0 / 8.0,
+            trim_speed: 38.0,
+            polar: None,
+            wind_direction: 0.0,
+            wind_speed: 0.0,
+            wind_layers: vec![],
+            start_height: None,
+            additional_height: 0.0,
+            safety_margin: 0.0,
+            start_distance: 0.0,
+            safety_margin_ramp: vec![],
+            ridge_lift: false,
+            start_sector: None,
+            reverse: false,
+            terrain_error: false,
+            model: GlideModel::Geometric,
+            edge_hook: None,
+        }
+    }
+}
+
+impl SearchQuery {
+    /// Safety margin needed at a distance from the start. There is none
+    /// within `start_distance`. Between two points of the ramp the margin is
+    /// interpolated linearly, before the first and after the last point their
+    /// margin is used.
+    pub fn safety_margin_at_distance(&self, distance: f32) -> f32 {
+        if distance < self.start_distance {
+            return 0.0;
+        }
+        let ramp = &self.safety_margin_ramp;
+        let upper = ramp.partition_point(|point| point.distance < distance);
+        match (upper.checked_sub(1), ramp.get(upper)) {
+            (Some(lower), Some(upper)) => {
+                let lower = &ramp[lower];
+                let t = (distance - lower.distance) / (upper.distance - lower.distance);
+                lower.margin + (upper.margin - lower.margin) * t
+            }
+            (Some(lower), None) => ramp[lower].margin,
+            (None, Some(upper)) => upper.margin,
+            (None, None) => self.safety_margin,
+        }
+    }
+
+    /// Slowest airspeed the pilot may fly at.
+    pub fn min_speed(&self) -> f32 {
+        match &self.polar {
+            Some(polar) => polar[0].speed,
+            None => self.trim_speed,
+        }
+    }
+
+    /// Wind speed and direction at an altitude above sea level.
+    pub fn wind_at_altitude(&self, altitude: f32) -> (f32, f32) {
+        if self.wind_layers.is_empty() {
+            (self.wind_speed, self.wind_direction)
+        } else {
+            wind_at_altitude(&self.wind_layers, altitude)
+        }
+    }
+
+    /// Strongest wind at any altitude.
+    pub fn max_wind_speed(&self) -> f32 {
+        if self.wind_layers.is_empty() {
+            self.wind_speed
+        } else {
+            max_wind_speed(&self.wind_layers)
+        }
+    }
+
+    pub fn builder() -> SearchQueryBuilder {
+        SearchQueryBuilder::default()
+    }
+}
+
+/// Builds a `SearchQuery` with the units in the method names, so degrees
+/// and radians or glide numbers and ratios can't be mixed up. Values not set
+/// are those of a typical paraglider in calm air: glide number 8 at 38 km/h,
+/// no wind and no margins.
+#[derive(Default)]
+pub struct SearchQueryBuilder {
+    query: SearchQuery,
+}
+
+impl SearchQueryBuilder {
+    /// Meters flown per meter of height lost, e.g. 8.
+    pub fn glide_number(mut self, glide_number: f32) -> Self {
+        self.query.glide_ratio = 1.0 / glide_number;
+        self
+    }
+
+    /// Meters of height lost per meter flown, the inverse of the glide
+    /// number.
+    pub fn glide_ratio(mut self, glide_ratio: f32) -> Self {
+        self.query.glide_ratio = glide_ratio;
+        self
+    }
+
+    pub fn trim_speed_kmh(mut self, trim_speed: f32) -> Self {
+        self.query.trim_speed = trim_speed;
+        self
+    }
+
+    /// Replaces the glide number and trim speed by the best speed to fly in
+    /// every direction.
+    pub fn polar(mut self, polar: Vec<PolarPoint>) -> Self {
+        self.query.polar = Some(polar);
+        self
+    }
+
+    pub fn wind_speed_kmh(mut self, wind_speed: f32) -> Self {
+        self.query.wind_speed = wind_speed;
+        self
+    }
+
+    /// Direction the wind comes from, clockwise from north.
+    pub fn wind_direction_deg(mut self, wind_direction: f32) -> Self {
+        self.query.wind_direction = wind_direction.to_radians();
+        self
+    }
+
+    /// Replaces the wind speed and direction, see `parse_wind_layers`.
+    pub fn wind_layers(mut self, wind_layers: Vec<WindLayer>) -> Self {
+        self.query.wind_layers = wind_layers;
+        self
+    }
+
+    /// Height above sea level to start from, the ground height at the start
+    /// plus the additional height if not set.
+    pub fn start_height_m(mut self, start_height: f32) -> Self {
+        self.query.start_height = Some(start_height);
+        self
+    }
+
+    pub fn additional_height_m(mut self, additional_height: f32) -> Self {
+        self.query.additional_height = additional_height;
+        self
+    }
+
+    pub fn safety_margin_m(mut self, safety_margin: f32) -> Self {
+        self.query.safety_margin = safety_margin;
+        self
+    }
+
+    pub fn start_distance_m(mut self, start_distance: f32) -> Self {
+        self.query.start_distance = start_distance;
+        self
+    }
+
+    /// Replaces the safety margin, see `parse_safety_margin_ramp`.
+    pub fn safety_margin_ramp(mut self, safety_margin_ramp: Vec<MarginPoint>) -> Self {
+        self.query.safety_margin_ramp = safety_margin_ramp;
+        self
+    }
+
+    pub fn ridge_lift(mut self, ridge_lift: bool) -> Self {
+        self.query.ridge_lift = ridge_lift;
+        self
+    }
+
+    /// Bearings clockwise from north between which the cone may leave the
+    /// start, clockwise from `from` to `to`.
+    pub fn start_sector_deg(mut self, from: f32, to: f32) -> Self {
+        self.query.start_sector = Some((from.to_radians(), to.to_radians()));
+        self
+    }
+
+    pub fn reverse(mut self, reverse: bool) -> Self {
+        self.query.reverse = reverse;
+        self
+    }
+
+    pub fn terrain_error(mut self, terrain_error: bool) -> Self {
+        self.query.terrain_error = terrain_error;
+        self
+    }
+
+    pub fn model(mut self, model: GlideModel) -> Self {
+        self.query.model = model;
+        self
+    }
+
+    pub fn edge_hook(mut self, edge_hook: Arc<dyn EdgeHook>) -> Self {
+        self.query.edge_hook = Some(edge_hook);
+        self
+    }
+
+    /// Checks that the values are finite and within their ranges.
+    pub fn build(self) -> Result<SearchQuery, String> {
+        let mut query = self.query;
+        if !(query.glide_ratio.is_finite() && query.glide_ratio > 0.0) {
+            return Err("The glide number must be positive".to_string());
+        }
+        if !(query.trim_speed.is_finite() && query.trim_speed > 0.0) {
+            return Err("The trim speed must be positive".to_string());
+        }
+        if query.polar.as_ref().is_some_and(Vec::is_empty) {
+            return Err("The polar needs at least one point".to_string());
+        }
+        for (name, value) in [
+            ("wind speed", query.wind_speed),
+            ("additional height", query.additional_height),
+            ("safety margin", query.safety_margin),
+            ("start distance", query.start_distance),
+        ] {
+            if !(value.is_finite() && value >= 0.0) {
+                return Err(format!("The {name} must not be negative"));
+            }
+        }
+        let mut angles = [query.wind_direction].into_iter().chain(
+            query
+                .start_sector
+                .into_iter()
+                .flat_map(|(from, to)| [from, to]),
+        );
+        if !angles.all(f32::is_finite) {
+            return Err("Directions must be finite".to_string());
+        }
+        if query.start_height.is_some_and(|height| !height.is_finite()) {
+            return Err("The start height must be finite".to_string());
+        }
+        if !query.safety_margin_ramp.is_empty() {
+            query.safety_margin = query
+                .safety_margin_ramp
+                .iter()
+                .map(|point| point.margin)
+                .fold(0.0, f32::max);
+        }
+        Ok(query)
+    }
+}
+
+/// Wind varying over the search area, e.g. from a weather forecast. Has the
+/// same shape as the height grid it is used with.
+#[derive(Clone)]
+pub struct WindField {
+    /// Wind speed in km/h.
+    pub speed: Array2<f32>,
+    /// Direction the wind comes from, in radians.
+    pub direction: Array2<f32>,
+    max_speed: f32,
+}
+
+impl WindField {
+    pub fn new(speed: Array2<f32>, direction: Array2<f32>) -> WindField {
+        assert_eq!(
+            speed.shape(),
+            direction.shape(),
+            "Wind grids differ in shape"
+        );
+        let max_speed = speed.iter().copied().fold(0.0, f32::max);
+        WindField {
+            speed,
+            direction,
+            max_speed,
+        }
+    }
+
+    /// Samples `wind(latitude, longitude) -> (speed, direction)` at every cell
+    /// of the grid.
+    pub fn from_fn(grid: &HeightGrid, wind: impl Fn(f32, f32) -> (f32, f32)) -> WindField {
+        let (lats, lons) = grid.get_coordinates_for_indices();
+        let winds = Array2::from_shape_fn(grid.heights.raw_dim(), |(row, col)| {
+            wind(lats[row], lons[col])
+        });
+        WindField::new(winds.map(|w| w.0), winds.map(|w| w.1))
+    }
+
+    pub fn max_speed(&self) -> f32 {
+        self.max_speed
+    }
+}
+
+/// Flag to stop a running search early, e.g. when the client that requested
+/// it is gone. Clones share the flag.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
 }
 
 pub struct SearchConfig {
     pub grid: HeightGrid,
     pub query: SearchQuery,
+    /// Replaces the wind of the query if set.
+    pub wind_field: Option<WindField>,
+    /// Cells are unreachable where the glide would end in restricted
+    /// airspace.
+    pub airspace: Option<AirspaceGrid>,
+    /// Stops the search once cancelled, it then returns the nodes explored
+    /// so far.
+    pub cancellation: Option<CancellationToken>,
 }
 
 impl SearchConfig {
-    pub fn get_safety_margin_at_distance(&self, distance: f32) -> f32 {
+    /// Height above ground of a node at the given distance from the start,
+    /// which counts towards the clearance of the paths through it. With a
+    /// safety margin ramp, it is shifted by how much less margin is needed at
+    /// the distance than `safety_margin`, so that it can still be compared to
+    /// the latter.
+    pub fn clearance_at_distance(&self, distance: f32, height: f32, grid_height: f32) -> f32 {
         if distance < self.query.start_distance {
-            return 0.0;
+            return f32::INFINITY;
         }
-        self.query.safety_margin
+        height - grid_height + self.query.safety_margin
+            - self.query.safety_margin_at_distance(distance)
+    }
+
+    /// Wind speed and direction at a cell and altitude above sea level.
+    pub fn wind_at(&self, ix: &GridIx, altitude: f32) -> (f32, f32) {
+        match &self.wind_field {
+            Some(field) => {
+                let ix = (ix.0 as usize, ix.1 as usize);
+                (field.speed[ix], field.direction[ix])
+            }
+            None => self.query.wind_at_altitude(altitude),
+        }
+    }
+
+    pub fn in_airspace(&self, ix: &GridIx, altitude: f32) -> bool {
+        self.airspace
+            .as_ref()
+            .is_some_and(|airspace| airspace.contains(ix, altitude))
+    }
+
+    /// Whether the search from `start` may explore `ix`.
+    pub fn in_start_sector(&self, start: &GridIx, ix: &GridIx) -> bool {
+        self.query
+            .start_sector
+            .is_none_or(|sector| in_sector(sector, start, ix))
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    }
+
+    /// Height when arriving at the end of `edge` after the `EdgeHook` of the
+    /// query, and whether the hook allows the glide.
+    pub fn hooked_height(&self, edge: &Edge) -> (f32, bool) {
+        let Some(hook) = &self.query.edge_hook else {
+            return (edge.to_height, true);
+        };
+        match hook.height_loss_factor(&self.grid, edge) {
+            Some(factor) => (
+                edge.from_height - (edge.from_height - edge.to_height) * factor,
+                true,
+            ),
+            None => (edge.to_height, false),
+        }
+    }
+
+    /// Fails if the `EdgeHook` of the query failed during the search.
+    pub fn check_edge_hook(&self) -> Result<(), SearchError> {
+        match self.query.edge_hook.as_ref().and_then(|hook| hook.error()) {
+            Some(message) => Err(SearchError::EdgeHook(message)),
+            None => Ok(()),
+        }
+    }
+
+    fn max_wind_speed(&self) -> f32 {
+        self.wind_field
+            .as_ref()
+            .map_or(self.query.max_wind_speed(), WindField::max_speed)
     }
 }
 
@@ -352,29 +1020,131 @@ pub fn l2_diff(a: &GridIx, b: &GridIx) -> (i32, i32) {
 
 const PI_2: f32 = f32::consts::PI / 2.0;
 
-fn get_effective_glide_ratio_from_to(
-    query: &SearchQuery,
+/// Effective glide when arriving at `start` from `end`, in the wind at `start`
+/// and the altitude at which the glide begins.
+pub(crate) fn get_effective_glide_ratio_from_to(
+    config: &SearchConfig,
     start: &GridIx,
     end: &GridIx,
+    altitude: f32,
 ) -> EffectiveGlide {
-    if query.wind_speed == 0.0 {
-        return EffectiveGlide {
-            speed: query.trim_speed,
-            glide_ratio: query.glide_ratio,
-        };
+    let (wind_speed, wind_direction) = config.wind_at(start, altitude);
+    if wind_speed == 0.0 {
+        return get_effective_glide_ratio_of_query(&config.query, 0.0, 0.0);
     }
 
     let diff = l2_diff(end, start);
     let angle = (diff.0 as f32).atan2(diff.1 as f32);
 
-    let effective_wind_angle = (-query.wind_direction + PI_2) - angle;
+    get_effective_glide_ratio_in_wind(&config.query, angle, wind_speed, wind_direction)
+}
 
-    get_effective_glide_ratio(
-        effective_wind_angle,
-        query.wind_speed,
-        query.trim_speed,
-        query.glide_ratio,
-    )
+/// Effective glide for the grid angle `atan2(rows, cols)` of the vector from
+/// the destination back to the origin of a glide.
+pub(crate) fn get_effective_glide_ratio_for_angle(
+    query: &SearchQuery,
+    angle: f32,
+) -> EffectiveGlide {
+    get_effective_glide_ratio_in_wind(query, angle, query.wind_speed, query.wind_direction)
+}
+
+pub(crate) fn get_effective_glide_ratio_in_wind(
+    query: &SearchQuery,
+    angle: f32,
+    wind_speed: f32,
+    wind_direction: f32,
+) -> EffectiveGlide {
+    let effective_wind_angle = (-wind_direction + PI_2) - angle;
+
+    get_effective_glide_ratio_of_query(query, effective_wind_angle, wind_speed)
+}
+
+/// Height above ground (in meters) below which ridge lift and sink apply.
+pub const RIDGE_LIFT_BAND: f32 = 300.0;
+/// Minimum slope (rise per meter along the wind) for ridge lift or sink.
+const RIDGE_LIFT_MIN_SLOPE: f32 = 0.1;
+/// Share of the wind deflected up or down along the slope.
+const RIDGE_LIFT_EFFICIENCY: f32 = 0.5;
+/// Bounds of the glide ratio factor. Lift never cancels the sink completely,
+/// since climbing is not modelled.
+pub const RIDGE_LIFT_MIN_FACTOR: f32 = 0.5;
+const RIDGE_LIFT_MAX_FACTOR: f32 = 2.0;
+
+/// Factor to apply to the glide ratio when arriving at `ix` with `agl` meters
+/// above ground. Below 1 on windward slopes (lift), above 1 on lee slopes
+/// (sink) and 1 everywhere else.
+pub fn ridge_lift_factor(config: &SearchConfig, ix: &GridIx, agl: f32) -> f32 {
+    if !(0.0..=RIDGE_LIFT_BAND).contains(&agl) {
+        return 1.0;
+    }
+
+    let grid = &config.grid;
+    let shape = grid.heights.shape();
+    let row = ix.0 as usize;
+    let col = ix.1 as usize;
+    let (wind_speed, wind_direction) = config.wind_at(ix, grid.heights[(row, col)] as f32 + agl);
+    if wind_speed == 0.0 {
+        return 1.0;
+    }
+    let (south, north) = (row.saturating_sub(1), (row + 1).min(shape[0] - 1));
+    let (west, east) = (col.saturating_sub(1), (col + 1).min(shape[1] - 1));
+    if north == south || east == west {
+        return 1.0;
+    }
+
+    // Terrain gradient, rows go from south to north and columns from west to east.
+    let slope_north = (grid.heights[(north, col)] as f32 - grid.heights[(south, col)] as f32)
+        / ((north - south) as f32 * grid.cell_size);
+    let slope_east = (grid.heights[(row, east)] as f32 - grid.heights[(row, west)] as f32)
+        / ((east - west) as f32 * grid.cell_size);
+
+    // The wind direction is where the wind comes from.
+    let slope_along_wind = -slope_east * wind_direction.sin() - slope_north * wind_direction.cos();
+    if slope_along_wind.abs() < RIDGE_LIFT_MIN_SLOPE {
+        return 1.0;
+    }
+
+    let vertical_wind = wind_speed * slope_along_wind * RIDGE_LIFT_EFFICIENCY;
+    let still_air = get_effective_glide_ratio_of_query(&config.query, 0.0, 0.0);
+    let sink = still_air.speed * still_air.glide_ratio;
+
+    ((sink - vertical_wind) / sink).clamp(RIDGE_LIFT_MIN_FACTOR, RIDGE_LIFT_MAX_FACTOR)
+}
+
+/// Height needed with `GlideModel::Energy` to change to `airspeed` when
+/// turning at `reference`, from the airspeed of the glide arriving there.
+/// The start is left at the speed of the first glide.
+fn speed_change_height_at(
+    config: &SearchConfig,
+    explored: &Explored,
+    reference: &Node,
+    airspeed: f32,
+) -> f32 {
+    if config.query.model != GlideModel::Energy {
+        return 0.0;
+    }
+    let Some(previous) = reference.reference else {
+        return 0.0;
+    };
+    // Safety: References are always explored before their children.
+    let previous_height = unsafe { explored.get_unchecked(&previous) }.height;
+    let arrival =
+        get_effective_glide_ratio_from_to(config, &reference.ix, &previous, previous_height);
+    speed_change_height(arrival.airspeed, airspeed)
+}
+
+pub(crate) fn lift_adjusted_glide_ratio(
+    config: &SearchConfig,
+    ix: &GridIx,
+    glide_ratio: f32,
+    reference_height: f32,
+) -> f32 {
+    if !config.query.ridge_lift {
+        return glide_ratio;
+    }
+    // Safety: ix is guaranteed to be in the grid
+    let grid_height = *unsafe { config.grid.heights.uget([ix.0 as usize, ix.1 as usize]) } as f32;
+    glide_ratio * ridge_lift_factor(config, ix, reference_height - grid_height)
 }
 
 pub fn is_straight(a: &GridIx, b: &GridIx) -> bool {
@@ -405,6 +1175,22 @@ fn get_straight_line_ref<'a>(ix: &GridIx, neighbor: &'a Node, explored: &'a Expl
     n
 }
 
+/// `Node::min_agl` of the nodes reached through `node`, which adds its own
+/// height above ground unless it is a start or within the start distance.
+fn min_agl_through(node: &Node, config: &SearchConfig) -> f32 {
+    if node.reference.is_none() || node.distance < config.query.start_distance {
+        return node.min_agl;
+    }
+    // Safety: Nodes are always in the grid
+    let grid_height = *unsafe {
+        config
+            .grid
+            .heights
+            .uget([node.ix.0 as usize, node.ix.1 as usize])
+    } as f32;
+    node.min_agl.min(node.height - grid_height)
+}
+
 pub fn update_one_neighbor(
     neighbor_ix: GridIx,
     ix: &GridIx,
@@ -422,7 +1208,7 @@ pub fn update_one_neighbor(
 
     let mut reference = neighbor;
     if neighbor.reference.is_some()
-        & ((config.query.wind_speed >= config.query.trim_speed) | do_intersection_check)
+        & ((config.max_wind_speed() >= config.query.min_speed()) | do_intersection_check)
     {
         // We already checked neighbor.reference.is_some()
         // References are always explored before their children
@@ -441,14 +1227,16 @@ pub fn update_one_neighbor(
 
         let line_intersects = is_line_intersecting_safety(reference, ix, config);
 
-        if (!neighbor.in_safety_margin && line_intersects != LineInSafety::Clear)
+        if (!neighbor.in_safety_margin(config.query.safety_margin)
+            && line_intersects != LineInSafety::Clear)
             || line_intersects == LineInSafety::Intersecting
         {
             reference = neighbor;
         }
     }
 
-    let effective_glide = get_effective_glide_ratio_from_to(&config.query, ix, &reference.ix);
+    let effective_glide =
+        get_effective_glide_ratio_from_to(config, ix, &reference.ix, reference.height);
     let distance = l2_distance(ix, &reference.ix) * config.grid.cell_size;
 
     if f32::is_infinite(effective_glide.glide_ratio) {
@@ -457,25 +1245,39 @@ pub fn update_one_neighbor(
 
     let total_distance = distance + reference.distance;
     let straight_line_ref = Some(get_straight_line_ref(ix, reference, &state.explored).ix);
+    let reference_ix = reference.ix;
     let ref_height = reference.height;
+    let speed_change =
+        speed_change_height_at(config, &state.explored, reference, effective_glide.airspeed);
+    let glide_ratio =
+        lift_adjusted_glide_ratio(config, ix, effective_glide.glide_ratio, ref_height);
 
-    let neighbor_in_safety_margin = neighbor.in_safety_margin;
+    let neighbor_clearance = neighbor.clearance;
+    let neighbor_min_agl = min_agl_through(neighbor, config);
+    let neighbor_in_safety_margin = neighbor.in_safety_margin(config.query.safety_margin);
 
     if let Some(r) = put_or_update(state, *ix, total_distance, neighbor_in_safety_margin) {
-        let height = ref_height - distance * effective_glide.glide_ratio;
         // Safety: ix is guaranteed to be in the grid
         let grid_height =
             *unsafe { config.grid.heights.uget([ix.0 as usize, ix.1 as usize]) } as f32;
-        let safety_margin = config.get_safety_margin_at_distance(total_distance);
-
-        let reachable = grid_height <= height;
-        let in_safety_margin = grid_height + safety_margin > height && reachable;
+        let (height, allowed) = config.hooked_height(&Edge {
+            from: reference_ix,
+            to: *ix,
+            from_height: ref_height,
+            to_height: ref_height - speed_change - distance * glide_ratio,
+            ground_height: grid_height,
+            distance,
+        });
+        let reachable = allowed && grid_height <= height && !config.in_airspace(ix, height);
+        let clearance = config.clearance_at_distance(total_distance, height, grid_height);
 
         r.height = height;
         r.reference = straight_line_ref;
         r.distance = total_distance;
         r.reachable = reachable;
-        r.in_safety_margin = in_safety_margin || neighbor_in_safety_margin;
+        r.clearance = clearance.min(neighbor_clearance);
+        r.min_agl = neighbor_min_agl;
+        r.ground_speed = effective_glide.speed;
     }
 }
 
@@ -516,34 +1318,50 @@ pub fn update_two_neighbors(
 
             let distance = l2_distance(ix, rpi) * config.grid.cell_size;
 
-            let effective_glide = get_effective_glide_ratio_from_to(&config.query, ix, rpi);
+            // RPI is a (transitive) parent of both neighbors, so must have
+            // been explored already.
+            let rpi_node = unsafe { state.explored.get_unchecked(rpi) };
+
+            let effective_glide =
+                get_effective_glide_ratio_from_to(config, ix, rpi, rpi_node.height);
 
             if f32::is_infinite(effective_glide.glide_ratio) {
                 return;
             }
 
-            // RPI is a (transitive) parent of both neighbors, so must have
-            // been explored already.
-            let rpi_node = unsafe { state.explored.get_unchecked(rpi) };
             let total_distance = distance + rpi_node.distance;
+            let speed_change =
+                speed_change_height_at(config, &state.explored, rpi_node, effective_glide.airspeed);
             let ref_p_deref = *ref_path_intersection;
+            let rpi_ix = *rpi;
             let rpi_node_height = rpi_node.height;
+            let glide_ratio =
+                lift_adjusted_glide_ratio(config, ix, effective_glide.glide_ratio, rpi_node_height);
 
-            let neighbors_in_safety_margin =
-                neighbor_1.in_safety_margin | neighbor_2.in_safety_margin;
+            let neighbors_clearance = neighbor_1.clearance.min(neighbor_2.clearance);
+            let neighbors_in_safety_margin = neighbors_clearance < config.query.safety_margin;
+            let neighbors_min_agl =
+                min_agl_through(neighbor_1, config).min(min_agl_through(neighbor_2, config));
             if let Some(r) = put_or_update(state, *ix, total_distance, neighbors_in_safety_margin) {
                 let grid_height =
                     *unsafe { config.grid.heights.uget([ix.0 as usize, ix.1 as usize]) } as f32;
-                let height = rpi_node_height - distance * effective_glide.glide_ratio;
-                let reachable = grid_height < height;
-                let in_safety_margin =
-                    grid_height + config.get_safety_margin_at_distance(total_distance) > height
-                        && reachable;
+                let (height, allowed) = config.hooked_height(&Edge {
+                    from: rpi_ix,
+                    to: *ix,
+                    from_height: rpi_node_height,
+                    to_height: rpi_node_height - speed_change - distance * glide_ratio,
+                    ground_height: grid_height,
+                    distance,
+                });
+                let reachable = allowed && grid_height < height && !config.in_airspace(ix, height);
+                let clearance = config.clearance_at_distance(total_distance, height, grid_height);
                 r.height = height;
                 r.reference = ref_p_deref;
                 r.distance = total_distance;
                 r.reachable = reachable;
-                r.in_safety_margin = in_safety_margin || neighbors_in_safety_margin;
+                r.clearance = clearance.min(neighbors_clearance);
+                r.min_agl = neighbors_min_agl;
+                r.ground_speed = effective_glide.speed;
             }
         } else {
             update_two_with_different_references(neighbor_1_ix, neighbor_2_ix, ix, config, state);
@@ -629,7 +1447,8 @@ pub fn update_four_neighbors(
             r.reference = None;
             r.distance = 0.0;
             r.reachable = false;
-            r.in_safety_margin = true;
+            r.clearance = f32::NEG_INFINITY;
+            r.min_agl = f32::INFINITY;
         }
     } else if reachable.len() < 4 {
         update_three_neighbors(explored_neighbors, ix, config, state);
@@ -662,39 +1481,290 @@ pub fn update_node(ix: &GridIx, config: &SearchConfig, state: &mut SearchState)
     }
 }
 
+/// Panics if the grid does not pass `check_grid_shape`.
 pub fn search(start: GridIx, height: f32, config: &SearchConfig) -> SearchState {
-    let grid_shape = config.grid.heights.shape();
-    let mut state = SearchState {
-        explored: Explored::new((grid_shape[0] as u16, grid_shape[1] as u16)),
-        queue: PQueue::new_with_map(FakeHashMapForGrid::new((
-            grid_shape[0] as u16,
-            grid_shape[1] as u16,
-        ))),
-    };
-    put_node(
-        &mut state,
-        Node {
-            height,
-            ix: start,
-            reference: None,
-            distance: 0.0,
-            reachable: true,
-            in_safety_margin: false,
-            explored: false,
+    search_restricted(start, height, config, |_| true)
+}
+
+/// Like `search`, but cells for which `allowed` returns false are never
+/// relaxed and stay unexplored.
+pub fn search_restricted(
+    start: GridIx,
+    height: f32,
+    config: &SearchConfig,
+    allowed: impl Fn(&GridIx) -> bool,
+) -> SearchState {
+    search_observed(&[start_node(start, height, 0.0)], config, allowed, |_| {})
+}
+
+/// Maximum number of starts of `search_from_starts`.
+pub const MAX_STARTS: usize = u8::MAX as usize;
+
+/// Like `search`, but from several starts at once. Every cell is reached from
+/// the start it can be reached highest from in still air, and the cone is
+/// restricted to the start sectors of all starts. Node distances are counted
+/// from the start a node is reached from, except that `start_distance` is
+/// measured from the highest start. Panics with more than `MAX_STARTS`
+/// starts.
+pub fn search_from_starts(starts: &[(GridIx, f32)], config: &SearchConfig) -> SearchState {
+    assert!(starts.len() <= MAX_STARTS, "Too many starts");
+    // Lower starts begin as if they had already glided from the height of the
+    // highest one, so the search explores cells in the order of their height.
+    let glide_ratio = get_effective_glide_ratio_of_query(&config.query, 0.0, 0.0).glide_ratio;
+    let max_height = starts
+        .iter()
+        .map(|(_, height)| *height)
+        .fold(f32::NEG_INFINITY, f32::max);
+    let offsets = starts
+        .iter()
+        .map(|(_, height)| (max_height - height) / glide_ratio)
+        .collect::<Vec<_>>();
+    let start_nodes = zip(starts, &offsets)
+        .map(|((ix, height), offset)| start_node(*ix, *height, *offset))
+        .collect::<Vec<_>>();
+
+    let mut state = search_observed(&start_nodes, config, |_| true, |_| {});
+
+    let start_ixs = starts.iter().map(|(ix, _)| *ix).collect::<Vec<_>>();
+    let sources = start_sources(state.explored.iter(), &start_ixs);
+    for node in state.explored.values.iter_mut().filter(|n| n.explored) {
+        if let Some(source) = sources.get(&node.ix) {
+            node.distance -= offsets[*source as usize];
+        }
+    }
+    state
+}
+
+/// Maximum number of thermals of `search_with_thermals`.
+pub const MAX_THERMALS: usize = 16;
+
+/// Like `search`, with a climb in every thermal that is reached below its
+/// top. `thermals` are given as grid position and height of the top above
+/// sea level. The search is repeated with the climbed thermals as further
+/// starts at their tops, see `search_from_starts`, until no more thermals
+/// are reached, so one climb can lead to the next. Node distances include
+/// the glides to the thermals, but as in multi start searches
+/// `start_distance` is measured from the highest start. Returns the search
+/// and the thermals climbed in, in the order they were reached.
+pub fn search_with_thermals(
+    start: GridIx,
+    height: f32,
+    thermals: &[(GridIx, f32)],
+    config: &SearchConfig,
+) -> (SearchState, Vec<GridIx>) {
+    let mut starts = vec![(start, height)];
+    // Distance flown to each start.
+    let mut distances = vec![0.0];
+    loop {
+        let mut state = search_from_starts(&starts, config);
+        let start_ixs = starts.iter().map(|(ix, _)| *ix).collect::<Vec<_>>();
+        let sources = start_sources(state.explored.iter(), &start_ixs);
+        let climbs = thermals
+            .iter()
+            .filter(|(ix, _)| !start_ixs.contains(ix))
+            .filter_map(|(ix, top)| {
+                let node = state.explored.get(ix)?;
+                let source = *sources.get(ix)? as usize;
+                (node.reachable && node.height < *top)
+                    .then_some(((*ix, *top), node.distance + distances[source]))
+            })
+            .collect::<Vec<_>>();
+        if climbs.is_empty() {
+            for node in state.explored.values.iter_mut().filter(|n| n.explored) {
+                if let Some(source) = sources.get(&node.ix) {
+                    node.distance += distances[*source as usize];
+                }
+            }
+            return (state, start_ixs[1..].to_vec());
+        }
+        for (thermal, distance) in climbs {
+            starts.push(thermal);
+            distances.push(distance);
+        }
+    }
+}
+
+/// Index of the start in `starts` each reachable node is reached from, found
+/// by following the references back to a start.
+pub fn start_sources<'a>(
+    nodes: impl IntoIterator<Item = &'a Node>,
+    starts: &[GridIx],
+) -> HashMap<GridIx, u8> {
+    let references = nodes
+        .into_iter()
+        .filter(|n| n.reachable)
+        .map(|n| (n.ix, n.reference))
+        .collect::<HashMap<_, _>>();
+    // A start can be reached higher from another start, then it is not the
+    // source of its own cone.
+    let mut sources = starts
+        .iter()
+        .enumerate()
+        .filter(|(_, ix)| references.get(ix) == Some(&None))
+        .map(|(i, ix)| (*ix, i as u8))
+        .collect::<HashMap<_, _>>();
+
+    for ix in references.keys() {
+        let mut path = vec![];
+        let mut current = *ix;
+        let source = loop {
+            if let Some(source) = sources.get(&current) {
+                break Some(*source);
+            }
+            path.push(current);
+            match references.get(&current) {
+                Some(Some(reference)) => current = *reference,
+                _ => break None,
+            }
+        };
+        if let Some(source) = source {
+            for ix in path {
+                sources.insert(ix, source);
+            }
+        }
+    }
+    sources
+}
+
+fn start_node(ix: GridIx, height: f32, distance: f32) -> Node {
+    Node {
+        height,
+        ix,
+        reference: None,
+        distance,
+        reachable: true,
+        clearance: f32::INFINITY,
+        min_agl: f32::INFINITY,
+        ground_speed: 0.0,
+        explored: false,
+    }
+}
+
+/// A cell popped from the queue during a search.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExplorationStep {
+    pub ix: GridIx,
+    pub distance: f32,
+    pub height: f32,
+    pub reachable: bool,
+}
+
+/// Like `search`, additionally returning the cells in the order they were
+/// explored. Meant for visualizing and debugging the algorithm on small grids.
+pub fn search_traced(
+    start: GridIx,
+    height: f32,
+    config: &SearchConfig,
+) -> (SearchState, Vec<ExplorationStep>) {
+    let mut steps = vec![];
+    let state = search_observed(
+        &[start_node(start, height, 0.0)],
+        config,
+        |_| true,
+        |node| {
+            steps.push(ExplorationStep {
+                ix: node.ix,
+                distance: node.distance,
+                height: node.height,
+                reachable: node.reachable,
+            })
         },
     );
+    (state, steps)
+}
 
-    while let Some(first) = state.queue.pop() {
-        unsafe { state.explored.get_unchecked_mut(&first.key) }.explored = true;
+fn search_observed(
+    starts: &[Node],
+    config: &SearchConfig,
+    allowed: impl Fn(&GridIx) -> bool,
+    mut on_explored: impl FnMut(&Node),
+) -> SearchState {
+    let mut search = SearchIter::new(starts, config, allowed);
+    while let Some(node) = search.step() {
+        on_explored(node);
+    }
+    search.into_state()
+}
 
-        let neighbors = get_neighbor_indices(&first.key, &config.grid);
+/// A running search, exploring one cell per step in order of distance. The
+/// node of an explored cell does not change anymore, and its reference was
+/// explored before it, so the explored nodes can be drawn while the search
+/// is still running.
+pub struct SearchIter<'a, F: Fn(&GridIx) -> bool> {
+    state: SearchState,
+    config: &'a SearchConfig,
+    allowed: F,
+    starts: Vec<GridIx>,
+}
+
+/// Like `search`, but returns the running search, e.g. to stream the
+/// explored nodes. Panics if the grid does not pass `check_grid_shape`.
+pub fn search_iter(
+    start: GridIx,
+    height: f32,
+    config: &SearchConfig,
+) -> SearchIter<'_, impl Fn(&GridIx) -> bool> {
+    SearchIter::new(&[start_node(start, height, 0.0)], config, |_| true)
+}
+
+impl<'a, F: Fn(&GridIx) -> bool> SearchIter<'a, F> {
+    fn new(starts: &[Node], config: &'a SearchConfig, allowed: F) -> Self {
+        let grid_shape = config.grid.heights.shape();
+        if let Err(e) = check_grid_shape(grid_shape[0], grid_shape[1]) {
+            panic!("{e}");
+        }
+        let grid_shape = (grid_shape[0] as GridIxType, grid_shape[1] as GridIxType);
+        let mut state = SearchState {
+            explored: Explored::new(grid_shape),
+            queue: PQueue::new_with_map(FakeHashMapForGrid::new(grid_shape)),
+        };
+        for start in starts {
+            put_node(&mut state, start.clone());
+        }
+        SearchIter {
+            state,
+            config,
+            allowed,
+            starts: starts.iter().map(|start| start.ix).collect(),
+        }
+    }
+
+    /// Explores the next cell and returns its node, `None` once the search
+    /// is done or cancelled.
+    pub fn step(&mut self) -> Option<&Node> {
+        if self.config.is_cancelled() {
+            return None;
+        }
+        let first = self.state.queue.pop()?;
+        unsafe { self.state.explored.get_unchecked_mut(&first.key) }.explored = true;
+
+        let neighbors = get_neighbor_indices(&first.key, &self.config.grid);
         for neighbor in neighbors {
-            if !unsafe { state.explored.get_unchecked(&neighbor) }.explored {
-                update_node(&neighbor, config, &mut state);
+            if !unsafe { self.state.explored.get_unchecked(&neighbor) }.explored
+                && (self.allowed)(&neighbor)
+                && self
+                    .starts
+                    .iter()
+                    .any(|start| self.config.in_start_sector(start, &neighbor))
+            {
+                update_node(&neighbor, self.config, &mut self.state);
             }
         }
+        Some(unsafe { self.state.explored.get_unchecked(&first.key) })
+    }
+
+    /// State of the search, with all nodes explored so far.
+    pub fn into_state(self) -> SearchState {
+        self.state
+    }
+}
+
+impl<F: Fn(&GridIx) -> bool> Iterator for SearchIter<'_, F> {
+    type Item = Node;
+
+    fn next(&mut self) -> Option<Node> {
+        self.step().cloned()
     }
-    state
 }
 
 pub fn ref_paths_intersection<'a>(
@@ -722,16 +1792,16 @@ pub fn ref_paths_intersection<'a>(
     &None
 }
 
-pub fn u16_f32(x: u16) -> f32 {
-    f32::from(x)
+pub fn grid_ix_f32(x: GridIxType) -> f32 {
+    x as f32
 }
 
 pub fn f32_usize(x: f32) -> usize {
-    usize::from(x.round() as u16)
+    x.round() as usize
 }
 
 pub fn is_line_intersecting(to: &Node, ix: &GridIx, config: &SearchConfig) -> bool {
-    let effective_glide = get_effective_glide_ratio_from_to(&config.query, ix, &to.ix);
+    let effective_glide = get_effective_glide_ratio_from_to(config, ix, &to.ix, to.height);
     if f32::is_infinite(effective_glide.glide_ratio) {
         return true;
     }
@@ -740,8 +1810,8 @@ pub fn is_line_intersecting(to: &Node, ix: &GridIx, config: &SearchConfig) -> bo
 
     let i_len = length.ceil() as usize;
 
-    let x_indices = linspace(u16_f32(to.ix.0), u16_f32(ix.0), i_len);
-    let y_indices = linspace(u16_f32(to.ix.1), u16_f32(ix.1), i_len);
+    let x_indices = linspace(grid_ix_f32(to.ix.0), grid_ix_f32(ix.0), i_len);
+    let y_indices = linspace(grid_ix_f32(to.ix.1), grid_ix_f32(ix.1), i_len);
 
     let distance = length * config.grid.cell_size;
 
@@ -760,8 +1830,9 @@ pub fn is_line_intersecting(to: &Node, ix: &GridIx, config: &SearchConfig) -> bo
                 return true;
             }
         }
-    } else if (to.distance < config.query.start_distance)
-        & (to.distance + distance > config.query.start_distance)
+    } else if !config.query.safety_margin_ramp.is_empty()
+        | ((to.distance < config.query.start_distance)
+            & (to.distance + distance > config.query.start_distance))
     {
         let mut cur_distance = to.distance;
         let distance_step = distance / (i_len - 1) as f32;
@@ -769,11 +1840,7 @@ pub fn is_line_intersecting(to: &Node, ix: &GridIx, config: &SearchConfig) -> bo
         for ((x_i, y_i), real_height) in zip(zip(x_indices, y_indices), real_heights) {
             let grid_height =
                 *unsafe { config.grid.heights.uget([f32_usize(x_i), f32_usize(y_i)]) } as f32;
-            let check_height = if cur_distance < config.query.start_distance {
-                real_height
-            } else {
-                real_height - config.query.safety_margin
-            };
+            let check_height = real_height - config.query.safety_margin_at_distance(cur_distance);
             if check_height < grid_height {
                 return true;
             }
@@ -799,7 +1866,7 @@ pub enum LineInSafety {
 }
 
 pub fn is_line_intersecting_safety(to: &Node, ix: &GridIx, config: &SearchConfig) -> LineInSafety {
-    let effective_glide = get_effective_glide_ratio_from_to(&config.query, ix, &to.ix);
+    let effective_glide = get_effective_glide_ratio_from_to(config, ix, &to.ix, to.height);
     if f32::is_infinite(effective_glide.glide_ratio) {
         return LineInSafety::Intersecting;
     }
@@ -808,8 +1875,8 @@ pub fn is_line_intersecting_safety(to: &Node, ix: &GridIx, config: &SearchConfig
 
     let i_len = length.ceil() as usize;
 
-    let x_indices = linspace(u16_f32(to.ix.0), u16_f32(ix.0), i_len);
-    let y_indices = linspace(u16_f32(to.ix.1), u16_f32(ix.1), i_len);
+    let x_indices = linspace(grid_ix_f32(to.ix.0), grid_ix_f32(ix.0), i_len);
+    let y_indices = linspace(grid_ix_f32(to.ix.1), grid_ix_f32(ix.1), i_len);
 
     let distance = length * config.grid.cell_size;
 
@@ -830,8 +1897,9 @@ pub fn is_line_intersecting_safety(to: &Node, ix: &GridIx, config: &SearchConfig
                 return LineInSafety::Intersecting;
             }
         }
-    } else if (to.distance < config.query.start_distance)
-        & (to.distance + distance > config.query.start_distance)
+    } else if !config.query.safety_margin_ramp.is_empty()
+        | ((to.distance < config.query.start_distance)
+            & (to.distance + distance > config.query.start_distance))
     {
         let mut cur_distance = to.distance;
         let distance_step = distance / (i_len - 1) as f32;
@@ -842,9 +1910,7 @@ pub fn is_line_intersecting_safety(to: &Node, ix: &GridIx, config: &SearchConfig
             if real_height < grid_height {
                 return LineInSafety::Intersecting;
             }
-            if cur_distance >= config.query.start_distance
-                && real_height - config.query.safety_margin < grid_height
-            {
+            if real_height - config.query.safety_margin_at_distance(cur_distance) < grid_height {
                 ret = LineInSafety::InSafetyMargin;
             }
             cur_distance += distance_step;
@@ -874,6 +1940,7 @@ pub fn reindex(
     grid: &HeightGrid,
     start_ix: GridIx,
 ) -> (Explored, HeightGrid, GridIx) {
+    let _timer = time_stage(Stage::Reindex);
     let mut lat_min = GridIxType::MAX;
     let mut lat_max = GridIxType::MIN;
     let mut lon_min = GridIxType::MAX;
@@ -894,16 +1961,19 @@ pub fn reindex(
 
     let new_start_ix = (start_ix.0 - lat_min, start_ix.1 - lon_min);
 
+    let window = s![
+        (lat_min as usize)..(lat_max as usize + 1),
+        (lon_min as usize)..(lon_max as usize + 1)
+    ];
     let new_grid = HeightGrid {
-        heights: grid
-            .heights
-            .slice(s![
-                (lat_min as usize)..(lat_max as usize + 1),
-                (lon_min as usize)..(lon_max as usize + 1)
-            ])
-            .to_owned(),
+        heights: grid.heights.slice(window).to_owned(),
         cell_size: grid.cell_size,
         min_cell_size: grid.min_cell_size,
+        data_source: grid.data_source,
+        data_quality: grid
+            .data_quality
+            .as_ref()
+            .map(|quality| quality.slice(window).to_owned()),
         latitudes: (
             grid.latitudes.0
                 + (grid.latitudes.1 - grid.latitudes.0) / (old_shape[0] as f32) * (lat_min as f32),
@@ -923,6 +1993,127 @@ pub fn reindex(
     (new_explored, new_grid, new_start_ix)
 }
 
+/// Upper bound for the distance reachable from the given height, gliding
+/// with full tail wind (and maximal ridge lift, if enabled).
+pub fn max_search_distance(query: &SearchQuery, height: f32) -> f32 {
+    let mut max_glide_ratio =
+        get_effective_glide_ratio_of_query(query, 0.0, query.max_wind_speed()).glide_ratio;
+    if query.ridge_lift {
+        max_glide_ratio *= RIDGE_LIFT_MIN_FACTOR;
+    }
+
+    height / max_glide_ratio
+}
+
+/// Distances in meters from the start to the edges of the area a search
+/// needs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SearchExtent {
+    pub north: f32,
+    pub east: f32,
+    pub south: f32,
+    pub west: f32,
+}
+
+impl SearchExtent {
+    /// The same distance in all directions.
+    pub fn around(distance: f32) -> SearchExtent {
+        SearchExtent {
+            north: distance,
+            east: distance,
+            south: distance,
+            west: distance,
+        }
+    }
+
+    /// At least `distance` in every direction.
+    pub fn at_least(&self, distance: f32) -> SearchExtent {
+        SearchExtent {
+            north: self.north.max(distance),
+            east: self.east.max(distance),
+            south: self.south.max(distance),
+            west: self.west.max(distance),
+        }
+    }
+
+    pub fn max(&self) -> f32 {
+        self.north.max(self.east).max(self.south).max(self.west)
+    }
+
+    /// `(lower_latitude, upper_latitude, lower_longitude, upper_longitude)`
+    /// of the area around a start.
+    pub fn bounds(&self, latitude: f32, longitude: f32) -> (f32, f32, f32, f32) {
+        (
+            offset_point(latitude, longitude, -self.south, 0.0).0,
+            offset_point(latitude, longitude, self.north, 0.0).0,
+            offset_point(latitude, longitude, 0.0, -self.west).1,
+            offset_point(latitude, longitude, 0.0, self.east).1,
+        )
+    }
+}
+
+/// Headings sampled by `max_search_extent`.
+const EXTENT_HEADINGS: usize = 360;
+
+/// Like `max_search_distance`, but per direction: the reach of a straight
+/// glide in the wind of the query for every heading, projected onto the
+/// four directions. Up- and crosswind this is a lot less than the reach
+/// with full tail wind. Wind layers turn the wind with altitude, so with
+/// layers the extent is the same in all directions.
+pub fn max_search_extent(query: &SearchQuery, height: f32) -> SearchExtent {
+    if !query.wind_layers.is_empty() {
+        return SearchExtent::around(max_search_distance(query, height));
+    }
+    let ridge_lift_factor = if query.ridge_lift {
+        RIDGE_LIFT_MIN_FACTOR
+    } else {
+        1.0
+    };
+    let heading = |i: usize| i as f32 / EXTENT_HEADINGS as f32 * 2.0 * f32::consts::PI;
+    let reach = (0..EXTENT_HEADINGS)
+        .map(|i| {
+            // The angle is the one of the vector back to the start.
+            let glide_ratio =
+                get_effective_glide_ratio_for_angle(query, heading(i) + f32::consts::PI)
+                    .glide_ratio
+                    * ridge_lift_factor;
+            if glide_ratio.is_finite() {
+                height / glide_ratio
+            } else {
+                0.0
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let mut extent = SearchExtent::around(0.0);
+    for i in 0..EXTENT_HEADINGS {
+        // The reach between two samples is bounded by the reach at them.
+        let reach = reach[(i + EXTENT_HEADINGS - 1) % EXTENT_HEADINGS]
+            .max(reach[i])
+            .max(reach[(i + 1) % EXTENT_HEADINGS]);
+        let (north, east) = (reach * heading(i).sin(), reach * heading(i).cos());
+        extent.north = extent.north.max(north);
+        extent.east = extent.east.max(east);
+        extent.south = extent.south.max(-north);
+        extent.west = extent.west.max(-east);
+    }
+
+    // Reverse searches glide towards the start.
+    if query.reverse {
+        extent = SearchExtent {
+            north: extent.south,
+            east: extent.west,
+            south: extent.north,
+            west: extent.east,
+        };
+    }
+    extent
+}
+
+/// Cells kept around the start in every direction, also where the wind
+/// leaves no reach at all.
+const MIN_EXTENT_CELLS: f32 = 3.0;
+
 pub struct SearchSetup {
     pub ground_height: f32,
     pub start_height: f32,
@@ -930,50 +2121,142 @@ pub struct SearchSetup {
     pub config: SearchConfig,
 }
 
+/// Loads the grid for a search from a point, sized by `max_search_extent`.
 pub fn prepare_search(
     latitude: f32,
     longitude: f32,
     cell_size: f32,
     query: SearchQuery,
-) -> SearchSetup {
-    let mut height_at_point = get_height_at_point(latitude, longitude) as f32;
+) -> Result<SearchSetup, SearchError> {
+    prepare_search_sized(latitude, longitude, cell_size, query, max_search_extent)
+}
+
+fn prepare_search_sized(
+    latitude: f32,
+    longitude: f32,
+    cell_size: f32,
+    query: SearchQuery,
+    extent_of: impl Fn(&SearchQuery, f32) -> SearchExtent,
+) -> Result<SearchSetup, SearchError> {
+    let data_source =
+        select_data_source(latitude, longitude, cell_size).unwrap_or(DataSource::Srtm);
+
+    let mut height_at_point = get_interpolated_height_at_point_from(
+        data_source,
+        latitude,
+        longitude,
+        Interpolation::Bilinear,
+    );
     let mut height = query
         .start_height
         .unwrap_or(height_at_point + query.additional_height)
         .max(height_at_point);
 
-    let max_glide_ratio =
-        query.glide_ratio / ((query.wind_speed + query.trim_speed) / (query.trim_speed));
-
-    let max_distance = height / max_glide_ratio;
-
-    let mut grid = get_height_data_around_point(latitude, longitude, Some(max_distance + 1.0));
-
-    let mut cell_s = cell_size;
-    if cell_size < grid.cell_size {
-        cell_s = grid.cell_size;
-    }
-
-    grid = grid.scale(grid.cell_size / cell_s);
-
-    let start_ix = (
-        (grid.heights.shape()[0] / 2) as GridIxType,
-        (grid.heights.shape()[1] / 2) as GridIxType,
-    );
+    let min_extent = MIN_EXTENT_CELLS * cell_size;
+    let mut grid = load_search_grid(
+        latitude,
+        longitude,
+        cell_size,
+        extent_of(&query, height).at_least(min_extent),
+    )?;
+    let mut start_ix = start_index(&grid, latitude, longitude);
     height_at_point = grid.heights[[start_ix.0 as usize, start_ix.1 as usize]] as f32;
+    let sized_for = height;
     height = query
         .start_height
         .unwrap_or(height_at_point + query.additional_height)
         .max(height_at_point);
 
-    let config = SearchConfig { grid, query };
+    // The cell of the start can be higher than the interpolated height the
+    // grid was sized for.
+    if height > sized_for {
+        grid = load_search_grid(
+            latitude,
+            longitude,
+            cell_size,
+            extent_of(&query, height).at_least(min_extent),
+        )?;
+        start_ix = start_index(&grid, latitude, longitude);
+        height_at_point = grid.heights[[start_ix.0 as usize, start_ix.1 as usize]] as f32;
+        height = query
+            .start_height
+            .unwrap_or(height_at_point + query.additional_height)
+            .max(height_at_point);
+    }
+    if query.terrain_error {
+        grid.raise_by_vertical_error();
+    }
 
-    SearchSetup {
+    let config = SearchConfig {
+        grid,
+        query,
+        wind_field: None,
+        airspace: None,
+        cancellation: None,
+    };
+
+    Ok(SearchSetup {
         ground_height: height_at_point,
         start_height: height,
         start_ix,
         config,
+    })
+}
+
+/// Grid position of the start of a search, the grid is not centered on it
+/// if its extent differs between directions.
+fn start_index(grid: &HeightGrid, latitude: f32, longitude: f32) -> GridIx {
+    let (row, col) = grid
+        .index_of(latitude, longitude)
+        .expect("The grid contains the start");
+    (row as GridIxType, col as GridIxType)
+}
+
+/// Height grid with the given cell size covering `extent` around a point,
+/// from the DEM with the best resolution that covers it.
+pub fn load_search_grid(
+    latitude: f32,
+    longitude: f32,
+    cell_size: f32,
+    extent: SearchExtent,
+) -> Result<HeightGrid, SearchError> {
+    let mut data_source =
+        select_data_source(latitude, longitude, cell_size).unwrap_or(DataSource::Srtm);
+    let max_distance = extent.max();
+
+    // Fall back to the coarse DEM if the cone would leave the SRTM coverage.
+    if data_source == DataSource::Srtm
+        && !area_supported(DataSource::Srtm, latitude, longitude, max_distance + 1.0)
+        && area_supported(DataSource::Coarse, latitude, longitude, max_distance + 1.0)
+    {
+        data_source = DataSource::Coarse;
     }
+
+    // Reject oversized grids before loading the height data for them.
+    let extent = SearchExtent {
+        north: extent.north + 1.0,
+        east: extent.east + 1.0,
+        south: extent.south + 1.0,
+        west: extent.west + 1.0,
+    };
+    let rows = ((extent.north + extent.south) / cell_size).ceil() as usize + 1;
+    let cols = ((extent.east + extent.west) / cell_size).ceil() as usize + 1;
+    check_grid_shape(rows, cols)?;
+
+    let mut grid = {
+        let _timer = time_stage(Stage::DemLoad);
+        get_height_data_in_bounds_from(data_source, extent.bounds(latitude, longitude))
+            .ok_or(SearchError::NoHeightData)?
+    };
+
+    let cell_s = snap_cell_size(cell_size, grid.cell_size);
+    grid = {
+        let _timer = time_stage(Stage::Scaling);
+        grid.scale(grid.cell_size / cell_s)
+    };
+    check_grid_shape(grid.heights.shape()[0], grid.heights.shape()[1])?;
+
+    Ok(grid)
 }
 
 pub struct SearchResult {
@@ -981,6 +2264,8 @@ pub struct SearchResult {
     pub height_grid: HeightGrid,
     pub ground_height: f32,
     pub start_ix: GridIx,
+    /// See `GridMap::border_bearings`, empty if the cone fits into the grid.
+    pub border_bearings: Vec<u16>,
 }
 
 pub fn search_from_point(
@@ -988,54 +2273,411 @@ pub fn search_from_point(
     longitude: f32,
     cell_size: f32,
     query: SearchQuery,
-) -> SearchResult {
-    let search_setup = prepare_search(latitude, longitude, cell_size, query);
+) -> Result<SearchResult, SearchError> {
+    let search_setup = prepare_search(latitude, longitude, cell_size, query)?;
+
+    search_from_setup(search_setup)
+}
+
+/// Parses a comma separated list of `latitude:longitude` or
+/// `latitude:longitude:height` starts, the height in meters above sea level.
+pub fn parse_starts(value: &str) -> Result<Vec<(f32, f32, Option<f32>)>, String> {
+    let mut result = vec![];
+    for start in value.split(',') {
+        let parts = start
+            .split(':')
+            .map(|part| {
+                part.trim()
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|value| value.is_finite())
+                    .ok_or(format!("Bad number {part} in start {start}"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let (latitude, longitude, height) = match parts[..] {
+            [latitude, longitude] => (latitude, longitude, None),
+            [latitude, longitude, height] => (latitude, longitude, Some(height)),
+            _ => {
+                return Err(format!(
+                    "Start {start} is not of the form latitude:longitude[:height]"
+                ))
+            }
+        };
+        if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+            return Err(format!("Start {start} is not a valid location"));
+        }
+        result.push((latitude, longitude, height));
+    }
+    Ok(result)
+}
+
+/// Parses a comma separated list of `latitude:longitude:top` thermals, the
+/// top in meters above sea level.
+pub fn parse_thermals(value: &str) -> Result<Vec<(f32, f32, f32)>, String> {
+    parse_starts(value)?
+        .into_iter()
+        .map(|(latitude, longitude, top)| {
+            top.map(|top| (latitude, longitude, top)).ok_or(format!(
+                "Thermal {latitude}:{longitude} has no top, use latitude:longitude:top"
+            ))
+        })
+        .collect()
+}
+
+/// Search from a point with climbs in `(latitude, longitude, top)` thermals,
+/// see `search_with_thermals`. Thermals outside of the grid are ignored.
+/// Also returns the grid positions of the thermals climbed in.
+pub fn search_from_point_with_thermals(
+    latitude: f32,
+    longitude: f32,
+    cell_size: f32,
+    query: SearchQuery,
+    thermals: &[(f32, f32, f32)],
+) -> Result<(SearchResult, Vec<GridIx>), SearchError> {
+    let data_source =
+        select_data_source(latitude, longitude, cell_size).unwrap_or(DataSource::Srtm);
+    let start_height = |ground_height: f32| {
+        query
+            .start_height
+            .unwrap_or(ground_height + query.additional_height)
+            .max(ground_height)
+    };
+
+    // The grid is large enough for the cones from the tops of all thermals.
+    let ground_height = get_interpolated_height_at_point_from(
+        data_source,
+        latitude,
+        longitude,
+        Interpolation::Bilinear,
+    );
+    let max_distance = thermals
+        .iter()
+        .map(|(lat, lon, top)| {
+            point_distance(latitude, longitude, *lat, *lon) + max_search_distance(&query, *top)
+        })
+        .fold(
+            max_search_distance(&query, start_height(ground_height)),
+            f32::max,
+        );
+    let mut grid = load_search_grid(
+        latitude,
+        longitude,
+        cell_size,
+        SearchExtent::around(max_distance),
+    )?;
+
+    let (row, col) = grid
+        .index_of(latitude, longitude)
+        .expect("The grid contains the start");
+    let start = (row as GridIxType, col as GridIxType);
+    let ground_height = grid.heights[(row, col)] as f32;
+    let height = start_height(ground_height);
+    let thermals = thermals
+        .iter()
+        .filter_map(|(lat, lon, top)| {
+            let (row, col) = grid.index_of(*lat, *lon)?;
+            Some(((row as GridIxType, col as GridIxType), *top))
+        })
+        .collect::<Vec<_>>();
+    if query.terrain_error {
+        grid.raise_by_vertical_error();
+    }
+
+    let config = SearchConfig {
+        grid,
+        query,
+        wind_field: None,
+        airspace: None,
+        cancellation: None,
+    };
+    let (state, climbs) = search_with_thermals(start, height, &thermals, &config);
+    config.check_edge_hook()?;
 
-    let state = search(
+    let border_bearings = state.explored.border_bearings(&start);
+    let (explored, height_grid, start_ix) = reindex(state.explored, &config.grid, start);
+    let climbs = climbs
+        .iter()
+        .map(|ix| (ix.0 - (start.0 - start_ix.0), ix.1 - (start.1 - start_ix.1)))
+        .collect();
+    Ok((
+        SearchResult {
+            explored,
+            height_grid,
+            ground_height,
+            start_ix,
+            border_bearings,
+        },
+        climbs,
+    ))
+}
+
+pub struct MultiStartResult {
+    pub explored: Explored,
+    pub height_grid: HeightGrid,
+    /// Grid position and ground height of every start, in the order given.
+    pub starts: Vec<(GridIx, f32)>,
+    /// See `GridMap::border_bearings`, as seen from the first start.
+    pub border_bearings: Vec<u16>,
+}
+
+/// Search from several `(latitude, longitude, start_height)` points at once,
+/// see `search_from_starts`. Starts without a height begin
+/// `additional_height` above the ground. Panics without points.
+pub fn search_from_points(
+    points: &[(f32, f32, Option<f32>)],
+    cell_size: f32,
+    query: SearchQuery,
+) -> Result<MultiStartResult, SearchError> {
+    let start_height = |start_height: Option<f32>, ground_height: f32| {
+        start_height
+            .unwrap_or(ground_height + query.additional_height)
+            .max(ground_height)
+    };
+
+    // The grid is centered between the starts and large enough for each of
+    // their cones.
+    let latitude = points.iter().map(|p| p.0).sum::<f32>() / points.len() as f32;
+    let longitude = points.iter().map(|p| p.1).sum::<f32>() / points.len() as f32;
+    let max_distance = points
+        .iter()
+        .map(|(lat, lon, height)| {
+            let data_source = select_data_source(*lat, *lon, cell_size).unwrap_or(DataSource::Srtm);
+            let ground_height = get_interpolated_height_at_point_from(
+                data_source,
+                *lat,
+                *lon,
+                Interpolation::Bilinear,
+            );
+            point_distance(latitude, longitude, *lat, *lon)
+                + max_search_distance(&query, start_height(*height, ground_height))
+        })
+        .fold(0.0, f32::max);
+    let mut grid = load_search_grid(
+        latitude,
+        longitude,
+        cell_size,
+        SearchExtent::around(max_distance),
+    )?;
+
+    let starts = points
+        .iter()
+        .map(|(lat, lon, height)| {
+            let (row, col) = grid
+                .index_of(*lat, *lon)
+                .expect("The grid contains all starts");
+            let ground_height = grid.heights[(row, col)] as f32;
+            (
+                (row as GridIxType, col as GridIxType),
+                start_height(*height, ground_height),
+                ground_height,
+            )
+        })
+        .collect::<Vec<_>>();
+    if query.terrain_error {
+        grid.raise_by_vertical_error();
+    }
+
+    let config = SearchConfig {
+        grid,
+        query,
+        wind_field: None,
+        airspace: None,
+        cancellation: None,
+    };
+    let state = search_from_starts(
+        &starts
+            .iter()
+            .map(|(ix, height, _)| (*ix, *height))
+            .collect::<Vec<_>>(),
+        &config,
+    );
+    config.check_edge_hook()?;
+
+    let first = starts[0].0;
+    let border_bearings = state.explored.border_bearings(&first);
+    let (explored, height_grid, new_first) = reindex(state.explored, &config.grid, first);
+    Ok(MultiStartResult {
+        explored,
+        height_grid,
+        border_bearings,
+        starts: starts
+            .iter()
+            .map(|(ix, _, ground_height)| {
+                (
+                    (
+                        ix.0 - (first.0 - new_first.0),
+                        ix.1 - (first.1 - new_first.1),
+                    ),
+                    *ground_height,
+                )
+            })
+            .collect(),
+    })
+}
+
+/// Search in a spatially varying wind, `wind(latitude, longitude)` returns
+/// the wind speed and direction at a point. The wind of the query is only
+/// used as a lower bound of the wind speed when sizing the search area.
+pub fn search_from_point_in_wind_field(
+    latitude: f32,
+    longitude: f32,
+    cell_size: f32,
+    query: SearchQuery,
+    wind: impl Fn(f32, f32) -> (f32, f32),
+) -> Result<SearchResult, SearchError> {
+    // The wind of the field can blow from any direction.
+    let extent_of =
+        |query: &SearchQuery, height| SearchExtent::around(max_search_distance(query, height));
+    let mut search_setup = prepare_search_sized(latitude, longitude, cell_size, query, extent_of)?;
+    let mut wind_field = WindField::from_fn(&search_setup.config.grid, &wind);
+
+    // The area has to be large enough for a tail wind as strong as the
+    // strongest wind in the field.
+    if wind_field.max_speed() > search_setup.config.query.max_wind_speed() {
+        let mut query = search_setup.config.query;
+        query.wind_speed = wind_field.max_speed();
+        query.wind_layers.clear();
+        search_setup = prepare_search_sized(latitude, longitude, cell_size, query, extent_of)?;
+        wind_field = WindField::from_fn(&search_setup.config.grid, &wind);
+    }
+    search_setup.config.wind_field = Some(wind_field);
+
+    search_from_setup(search_setup)
+}
+
+/// Search that avoids the restricted ones of `airspaces`, see
+/// `SearchConfig::airspace`.
+pub fn search_from_point_avoiding_airspace(
+    latitude: f32,
+    longitude: f32,
+    cell_size: f32,
+    query: SearchQuery,
+    airspaces: &[Airspace],
+) -> Result<SearchResult, SearchError> {
+    let mut search_setup = prepare_search(latitude, longitude, cell_size, query)?;
+    search_setup.config.airspace = Some(AirspaceGrid::new(airspaces, &search_setup.config.grid));
+
+    search_from_setup(search_setup)
+}
+
+pub(crate) fn search_from_setup(search_setup: SearchSetup) -> Result<SearchResult, SearchError> {
+    let explored = search_for_query(
         search_setup.start_ix,
+        search_setup.ground_height,
         search_setup.start_height,
         &search_setup.config,
     );
+    search_setup.config.check_edge_hook()?;
 
-    let (explored, new_grid, new_start_ix) = reindex(
-        state.explored,
-        &search_setup.config.grid,
-        search_setup.start_ix,
-    );
+    let border_bearings = explored.border_bearings(&search_setup.start_ix);
+    let (explored, new_grid, new_start_ix) =
+        reindex(explored, &search_setup.config.grid, search_setup.start_ix);
 
-    SearchResult {
+    Ok(SearchResult {
         explored,
         height_grid: new_grid,
         ground_height: search_setup.ground_height,
         start_ix: new_start_ix,
+        border_bearings,
+    })
+}
+
+/// Runs `search` or, for reverse queries, `reverse_search` towards the start
+/// with `start_height` as the highest height to start from.
+fn search_for_query(
+    start_ix: GridIx,
+    ground_height: f32,
+    start_height: f32,
+    config: &SearchConfig,
+) -> Explored {
+    let _timer = time_stage(Stage::Search);
+    if config.query.reverse {
+        let goal_height = ground_height + config.query.additional_height;
+        reverse_search(start_ix, goal_height, start_height, config)
+    } else {
+        search(start_ix, start_height, config).explored
     }
 }
 
 pub fn search_from_height_grid(
-    height_grid: HeightGrid,
+    mut height_grid: HeightGrid,
     start_ix: GridIx,
     query: SearchQuery,
-) -> SearchResult {
+) -> Result<SearchResult, SearchError> {
+    check_grid_shape(
+        height_grid.heights.shape()[0],
+        height_grid.heights.shape()[1],
+    )?;
+
     let ground_height = height_grid.heights[[start_ix.0 as usize, start_ix.1 as usize]] as f32;
     let start_height = query
         .start_height
         .unwrap_or(ground_height + query.additional_height)
         .max(ground_height);
+    if query.terrain_error {
+        height_grid.raise_by_vertical_error();
+    }
 
     let config = SearchConfig {
         grid: height_grid,
         query,
+        wind_field: None,
+        airspace: None,
+        cancellation: None,
     };
 
-    let state = search(start_ix, start_height, &config);
-    let (explored, new_grid, new_start_ix) = reindex(state.explored, &config.grid, start_ix);
+    let explored = search_for_query(start_ix, ground_height, start_height, &config);
+    config.check_edge_hook()?;
+    let border_bearings = explored.border_bearings(&start_ix);
+    let (explored, new_grid, new_start_ix) = reindex(explored, &config.grid, start_ix);
 
-    SearchResult {
+    Ok(SearchResult {
         explored,
         height_grid: new_grid,
         ground_height,
         start_ix: new_start_ix,
+        border_bearings,
+    })
+}
+
+/// Reachable nodes in the order they are sent to clients: grouped by
+/// reference, groups ordered by the distance of their reference and nodes
+/// within a group by distance. Every prefix of this order can be drawn on its
+/// own, which allows streaming and pagination. Ties are broken by grid index
+/// so repeated requests see the same order.
+pub fn reachable_in_transfer_order(explored: impl IntoIterator<Item = Node>) -> Vec<Node> {
+    let nodes = explored
+        .into_iter()
+        .filter(|node| node.reachable)
+        .collect::<Vec<_>>();
+    let distances = nodes
+        .iter()
+        .map(|node| (node.ix, node.distance))
+        .collect::<HashMap<_, _>>();
+
+    let mut groups = HashMap::<Option<GridIx>, Vec<Node>>::new();
+    for node in nodes {
+        groups.entry(node.reference).or_default().push(node);
     }
+
+    let mut groups = groups
+        .into_iter()
+        .map(|(reference, nodes)| {
+            let reference_distance = reference
+                .and_then(|ix| distances.get(&ix).copied())
+                .unwrap_or(-1.0);
+            (reference_distance, reference, nodes)
+        })
+        .collect::<Vec<_>>();
+    groups.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+
+    groups
+        .into_iter()
+        .flat_map(|(_, _, mut nodes)| {
+            nodes.sort_by(|a, b| a.distance.total_cmp(&b.distance).then(a.ix.cmp(&b.ix)));
+            nodes
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -1,10 +1,24 @@
 use core::f32;
+use std::collections::{HashMap, HashSet};
 
-use crate::{height_data::HeightGrid, search::l2_diff};
+use crate::{
+    airspace::{parse_openair, AirspaceGrid},
+    brute_force::verify_search,
+    height_data::{DataSource, HeightGrid},
+    margin::parse_safety_margin_ramp,
+    polar::parse_polar,
+    search::l2_diff,
+    wind::parse_wind_layers,
+};
 
 use super::{
-    get_effective_glide_ratio, search, search_from_height_grid, search_from_point, SearchConfig,
-    SearchQuery,
+    check_grid_shape, get_effective_glide_ratio, get_effective_glide_ratio_polar,
+    max_search_distance, max_search_extent, parse_starts, parse_thermals,
+    reachable_in_transfer_order, ridge_lift_factor, search, search_from_height_grid,
+    search_from_point, search_from_starts, search_iter, search_traced, search_with_thermals,
+    speed_change_height, start_sources, CancellationToken, Explored, GlideModel, GridIx,
+    GridIxType, Node, SearchConfig, SearchError, SearchExtent, SearchQuery, WindField,
+    RIDGE_LIFT_BAND, RIDGE_LIFT_MIN_FACTOR,
 };
 
 use approx::assert_relative_eq;
@@ -44,6 +58,25 @@ proptest! {
     }
 }
 
+#[test]
+fn test_get_effective_glide_ratio_polar() {
+    let polar = parse_polar("30:1.0,40:1.3,55:2.5").unwrap();
+
+    // A polar with a single point behaves like a fixed trim speed.
+    let single = get_effective_glide_ratio_polar(f32::consts::PI, 15.0, &polar[..1]);
+    let trim = get_effective_glide_ratio(f32::consts::PI, 15.0, 30.0, 3.6 / 30.0);
+    assert_relative_eq!(single.glide_ratio, trim.glide_ratio);
+
+    // With tail wind, flying at minimum sink is best.
+    let tail_wind = get_effective_glide_ratio_polar(0.0, 20.0, &polar);
+    assert_relative_eq!(tail_wind.speed, 50.0);
+
+    // Against the wind, speeding up pays off.
+    let head_wind = get_effective_glide_ratio_polar(f32::consts::PI, 20.0, &polar);
+    assert_relative_eq!(head_wind.speed, 20.0);
+    assert_relative_eq!(head_wind.glide_ratio, 1.3 * 3.6 / 20.0);
+}
+
 #[test]
 fn test_l2_diff() {
     assert_eq!(l2_diff(&(5, 3), &(2, 4)), (3, -1));
@@ -53,29 +86,18 @@ fn test_l2_diff() {
 fn test_search_from_point() {
     let query = SearchQuery {
         glide_ratio: 0.5,
-        trim_speed: 38.0,
-        wind_direction: 0.0,
-        wind_speed: 0.0,
         additional_height: 10.0,
-        safety_margin: 0.0,
-        start_distance: 0.0,
-        start_height: None,
+        ..Default::default()
     };
-    let _ = search_from_point(47.6954, 11.8681, 200.0, query);
+    search_from_point(47.6954, 11.8681, 200.0, query).unwrap();
 }
 
 #[test]
 fn test_search_from_height_grid() {
     let heights = Array2::from_elem((12, 12), 1000);
     let query = SearchQuery {
-        glide_ratio: 1.0 / 8.0,
-        trim_speed: 38.0,
-        wind_direction: 0.0,
-        wind_speed: 0.0,
         additional_height: 200.0,
-        safety_margin: 0.0,
-        start_distance: 0.0,
-        start_height: None,
+        ..Default::default()
     };
     let result = search_from_height_grid(
         HeightGrid {
@@ -84,14 +106,761 @@ fn test_search_from_height_grid() {
             min_cell_size: 50.0,
             latitudes: (47.0, 47.1),
             longitudes: (11.0, 11.1),
+            data_source: DataSource::Srtm,
+            data_quality: None,
         },
         (6, 6),
         query,
-    );
+    )
+    .unwrap();
 
     assert!(result.explored.iter().any(|n| n.reachable));
-    assert!(result.start_ix.0 < result.height_grid.heights.shape()[0] as u16);
-    assert!(result.start_ix.1 < result.height_grid.heights.shape()[1] as u16);
+    assert!((result.start_ix.0 as usize) < result.height_grid.heights.shape()[0]);
+    assert!((result.start_ix.1 as usize) < result.height_grid.heights.shape()[1]);
+}
+
+#[test]
+fn test_search_border_bearings() {
+    let query = |additional_height| SearchQuery {
+        additional_height,
+        ..Default::default()
+    };
+    let grid = HeightGrid {
+        heights: Array2::from_elem((21, 41), 1000),
+        cell_size: 50.0,
+        min_cell_size: 50.0,
+        latitudes: (47.0, 47.1),
+        longitudes: (11.0, 11.1),
+        data_source: DataSource::Srtm,
+        data_quality: None,
+    };
+
+    // 160 m of glide stay within the grid.
+    let result = search_from_height_grid(grid.clone(), (10, 5), query(20.0)).unwrap();
+    assert!(result.border_bearings.is_empty());
+
+    // 400 m of glide cross the western border 250 m from the start.
+    let result = search_from_height_grid(grid, (10, 5), query(50.0)).unwrap();
+    assert!(result.border_bearings.contains(&270));
+    assert!(result
+        .border_bearings
+        .iter()
+        .all(|bearing| (220..=320).contains(bearing)));
+}
+
+#[test]
+fn test_max_search_extent() {
+    let query = |wind_speed, wind_layers: &str| SearchQuery {
+        wind_speed,
+        wind_layers: if wind_layers.is_empty() {
+            vec![]
+        } else {
+            parse_wind_layers(wind_layers).unwrap()
+        },
+        ..Default::default()
+    };
+
+    let calm = max_search_extent(&query(0.0, ""), 1000.0);
+    assert_relative_eq!(calm.north, 8000.0, max_relative = 1e-4);
+    assert_relative_eq!(
+        calm.max(),
+        calm.north.min(calm.east).min(calm.south).min(calm.west)
+    );
+
+    // Wind from the north carries the cone south.
+    let windy = query(20.0, "");
+    let extent = max_search_extent(&windy, 1000.0);
+    assert_relative_eq!(
+        extent.south,
+        max_search_distance(&windy, 1000.0),
+        max_relative = 1e-4
+    );
+    assert!(extent.north < extent.south / 2.0);
+    assert_relative_eq!(extent.east, extent.west, max_relative = 1e-4);
+    assert!(extent.east < extent.south);
+
+    let reverse = max_search_extent(
+        &SearchQuery {
+            reverse: true,
+            ..query(20.0, "")
+        },
+        1000.0,
+    );
+    assert_relative_eq!(reverse.north, extent.south);
+    assert_relative_eq!(reverse.south, extent.north);
+
+    let layered = query(0.0, "0:20:0");
+    assert_eq!(
+        max_search_extent(&layered, 1000.0),
+        SearchExtent::around(max_search_distance(&layered, 1000.0))
+    );
+
+    let bounds = SearchExtent {
+        north: 1000.0,
+        east: 0.0,
+        south: 2000.0,
+        west: 500.0,
+    }
+    .bounds(47.0, 11.0);
+    assert!(bounds.0 < 47.0 && bounds.1 > 47.0);
+    assert_relative_eq!(
+        47.0 - bounds.0,
+        2.0 * (bounds.1 - 47.0),
+        max_relative = 1e-3
+    );
+    assert!(bounds.2 < 11.0);
+    assert_eq!(bounds.3, 11.0);
+}
+
+#[test]
+fn test_search_with_terrain_error() {
+    let query = |terrain_error| SearchQuery {
+        additional_height: 50.0,
+        terrain_error,
+        ..Default::default()
+    };
+    let grid = HeightGrid {
+        heights: Array2::from_elem((31, 31), 1000),
+        cell_size: 50.0,
+        min_cell_size: 50.0,
+        latitudes: (47.0, 47.1),
+        longitudes: (11.0, 11.1),
+        data_source: DataSource::Srtm,
+        data_quality: None,
+    };
+    let search = |terrain_error| {
+        search_from_height_grid(grid.clone(), (15, 15), query(terrain_error)).unwrap()
+    };
+
+    let result = search(false);
+    let raised = search(true);
+    let reachable = |explored: &Explored| explored.iter().filter(|n| n.reachable).count();
+    assert!(reachable(&raised.explored) < reachable(&result.explored));
+    // The start is still above the measured ground.
+    assert_eq!(raised.ground_height, 1000.0);
+    let start = (raised.start_ix.0 as usize, raised.start_ix.1 as usize);
+    assert_eq!(raised.height_grid.heights[start], 1016);
+}
+
+#[test]
+fn test_ridge_lift_factor() {
+    // Slope rising 30 m per 100 m to the east.
+    let grid = HeightGrid {
+        heights: Array2::from_shape_fn((5, 5), |(_, col)| col as i16 * 30),
+        cell_size: 100.0,
+        min_cell_size: 100.0,
+        latitudes: (47.0, 47.1),
+        longitudes: (11.0, 11.1),
+        data_source: DataSource::Srtm,
+        data_quality: None,
+    };
+    let query = |wind_direction: f32, wind_speed: f32| SearchQuery {
+        wind_direction,
+        wind_speed,
+        ridge_lift: true,
+        ..Default::default()
+    };
+    let factor = |query, agl| {
+        let config = SearchConfig {
+            grid: grid.clone(),
+            query,
+            wind_field: None,
+            airspace: None,
+            cancellation: None,
+        };
+        ridge_lift_factor(&config, &(2, 2), agl)
+    };
+    let west = 3.0 * f32::consts::PI / 2.0;
+    let east = f32::consts::PI / 2.0;
+
+    // Wind from the west blows up the slope.
+    let windward = factor(query(west, 10.0), 50.0);
+    assert!((RIDGE_LIFT_MIN_FACTOR..1.0).contains(&windward));
+    let lee = factor(query(east, 10.0), 50.0);
+    assert!(lee > 1.0);
+
+    // Parallel to the slope, high above ground or without wind nothing changes.
+    assert_eq!(factor(query(0.0, 10.0), 50.0), 1.0);
+    assert_eq!(factor(query(west, 10.0), RIDGE_LIFT_BAND + 1.0), 1.0);
+    assert_eq!(factor(query(west, 0.0), 50.0), 1.0);
+
+    assert!(
+        max_search_distance(&query(west, 10.0), 1000.0)
+            > max_search_distance(
+                &SearchQuery {
+                    ridge_lift: false,
+                    polar: None,
+                    ..query(west, 10.0)
+                },
+                1000.0
+            )
+    );
+}
+
+#[test]
+fn test_search_in_wind_field() {
+    let grid = HeightGrid {
+        heights: Array2::zeros((21, 21)),
+        cell_size: 100.0,
+        min_cell_size: 100.0,
+        latitudes: (47.0, 47.1),
+        longitudes: (11.0, 11.1),
+        data_source: DataSource::Srtm,
+        data_quality: None,
+    };
+    let south = f32::consts::PI;
+    let reachable = |wind_speed, wind_direction, wind_field| {
+        let config = SearchConfig {
+            grid: grid.clone(),
+            query: SearchQuery {
+                wind_direction,
+                wind_speed,
+                start_height: Some(100.0),
+                ..Default::default()
+            },
+            wind_field,
+            airspace: None,
+            cancellation: None,
+        };
+        search((10, 10), 100.0, &config)
+            .explored
+            .iter()
+            .filter(|n| n.reachable)
+            .map(|n| n.ix)
+            .collect::<HashSet<_>>()
+    };
+
+    // A uniform field behaves like the wind of the query.
+    let uniform = WindField::from_fn(&grid, |_, _| (15.0, south));
+    assert_eq!(uniform.max_speed(), 15.0);
+    assert_eq!(
+        reachable(0.0, 0.0, Some(uniform)),
+        reachable(15.0, south, None)
+    );
+
+    // Wind from the south in the northern part of the grid only.
+    let northern = WindField::from_fn(&grid, |lat, _| {
+        if lat > 47.06 {
+            (15.0, south)
+        } else {
+            (0.0, 0.0)
+        }
+    });
+    assert_eq!(northern.speed[[20, 0]], 15.0);
+    assert_eq!(northern.speed[[10, 0]], 0.0);
+
+    let still_air = reachable(0.0, 0.0, None);
+    let in_field = reachable(0.0, 0.0, Some(northern));
+    assert!(in_field.len() > still_air.len());
+    let southern_part = |cells: &HashSet<GridIx>| {
+        cells
+            .iter()
+            .filter(|ix| ix.0 <= 10)
+            .copied()
+            .collect::<HashSet<_>>()
+    };
+    assert_eq!(southern_part(&in_field), southern_part(&still_air));
+}
+
+#[test]
+fn test_search_ground_speed() {
+    let config = SearchConfig {
+        grid: HeightGrid {
+            heights: Array2::zeros((21, 21)),
+            cell_size: 100.0,
+            min_cell_size: 100.0,
+            latitudes: (47.0, 47.1),
+            longitudes: (11.0, 11.1),
+            data_source: DataSource::Srtm,
+            data_quality: None,
+        },
+        query: SearchQuery {
+            wind_direction: f32::consts::PI,
+            wind_speed: 15.0,
+            start_height: Some(100.0),
+            ..Default::default()
+        },
+        wind_field: None,
+        airspace: None,
+        cancellation: None,
+    };
+    let explored = search((10, 10), 100.0, &config).explored;
+    let ground_speed = |ix: GridIx| explored.iter().find(|n| n.ix == ix).unwrap().ground_speed;
+
+    assert_eq!(ground_speed((10, 10)), 0.0);
+    // Wind from the south, so arrivals in the north are downwind.
+    assert_relative_eq!(ground_speed((15, 10)), 38.0 + 15.0, epsilon = 0.01);
+    assert_relative_eq!(ground_speed((5, 10)), 38.0 - 15.0, epsilon = 0.01);
+    assert_relative_eq!(
+        ground_speed((10, 15)),
+        (38.0f32 * 38.0 - 15.0 * 15.0).sqrt(),
+        epsilon = 0.01
+    );
+}
+
+#[test]
+fn test_search_with_wind_layers() {
+    let grid = HeightGrid {
+        heights: Array2::zeros((21, 21)),
+        cell_size: 100.0,
+        min_cell_size: 100.0,
+        latitudes: (47.0, 47.1),
+        longitudes: (11.0, 11.1),
+        data_source: DataSource::Srtm,
+        data_quality: None,
+    };
+    let query = |wind_speed, wind_layers: &str| SearchQuery {
+        wind_direction: f32::consts::PI,
+        wind_speed,
+        wind_layers: if wind_layers.is_empty() {
+            vec![]
+        } else {
+            parse_wind_layers(wind_layers).unwrap()
+        },
+        start_height: Some(100.0),
+        ..Default::default()
+    };
+    let reachable = |query| {
+        let config = SearchConfig {
+            grid: grid.clone(),
+            query,
+            wind_field: None,
+            airspace: None,
+            cancellation: None,
+        };
+        search((10, 10), 100.0, &config)
+            .explored
+            .iter()
+            .filter(|n| n.reachable)
+            .map(|n| n.ix)
+            .collect::<HashSet<_>>()
+    };
+
+    assert_eq!(
+        reachable(query(0.0, "0:15:180,3000:15:180")),
+        reachable(query(15.0, ""))
+    );
+    // Strong wind aloft doesn't matter when flying low.
+    assert_eq!(
+        reachable(query(15.0, "1000:0:0,2000:40:180")),
+        reachable(query(0.0, ""))
+    );
+    assert!(
+        max_search_distance(&query(0.0, "1000:0:0,2000:40:180"), 1000.0)
+            > max_search_distance(&query(15.0, ""), 1000.0)
+    );
+}
+
+#[test]
+fn test_search_with_start_sector() {
+    let grid = HeightGrid {
+        heights: Array2::zeros((21, 21)),
+        cell_size: 100.0,
+        min_cell_size: 100.0,
+        latitudes: (47.0, 47.1),
+        longitudes: (11.0, 11.1),
+        data_source: DataSource::Srtm,
+        data_quality: None,
+    };
+    // Only westerly departures, between south west and north west.
+    let config = SearchConfig {
+        grid,
+        query: SearchQuery {
+            start_sector: Some((225.0f32.to_radians(), 315.0f32.to_radians())),
+            start_height: Some(100.0),
+            ..Default::default()
+        },
+        wind_field: None,
+        airspace: None,
+        cancellation: None,
+    };
+    let reachable = search((10, 10), 100.0, &config)
+        .explored
+        .iter()
+        .filter(|n| n.reachable)
+        .map(|n| n.ix)
+        .collect::<Vec<_>>();
+
+    assert!(reachable.contains(&(10, 3)));
+    assert!(reachable.contains(&(13, 4)));
+    assert!(reachable
+        .iter()
+        .all(|ix| ix.1 < 10 || (ix.0.abs_diff(10) <= 2 && ix.1 <= 12)));
+    // Along the edges of the sector the search may miss a few cells, but it
+    // never reaches cells the reference can't.
+    assert!(verify_search((10, 10), 100.0, &config)
+        .only_search
+        .is_empty());
+}
+
+#[test]
+fn test_search_clearance() {
+    // A 30 m wall two cells east of the start.
+    let mut heights = Array2::zeros((21, 21));
+    square((0, 12), (20, 12), 30, &mut heights);
+    let grid = HeightGrid {
+        heights,
+        cell_size: 100.0,
+        min_cell_size: 100.0,
+        latitudes: (47.0, 47.1),
+        longitudes: (11.0, 11.1),
+        data_source: DataSource::Srtm,
+        data_quality: None,
+    };
+    let mut config = SearchConfig {
+        grid,
+        query: SearchQuery {
+            start_height: Some(100.0),
+            ..Default::default()
+        },
+        wind_field: None,
+        airspace: None,
+        cancellation: None,
+    };
+    let node_at = |config: &SearchConfig, ix: GridIx| {
+        search((10, 10), 100.0, config)
+            .explored
+            .iter()
+            .find(|n| n.ix == ix)
+            .unwrap()
+            .clone()
+    };
+
+    let start = node_at(&config, (10, 10));
+    assert_eq!(start.clearance, f32::INFINITY);
+    assert_eq!(start.min_agl, f32::INFINITY);
+
+    // 75 m high over the wall, 50 m above the ground two cells behind it.
+    let behind_wall = node_at(&config, (10, 14));
+    assert!(behind_wall.reachable);
+    assert_relative_eq!(behind_wall.clearance, 45.0, epsilon = 0.1);
+    assert_relative_eq!(behind_wall.min_agl, 45.0, epsilon = 0.1);
+    assert!(!behind_wall.in_safety_margin(40.0));
+    assert!(behind_wall.in_safety_margin(48.0));
+
+    // Within the start distance the wall does not count.
+    config.query.start_distance = 250.0;
+    let behind_wall = node_at(&config, (10, 14));
+    assert_relative_eq!(behind_wall.clearance, 50.0, epsilon = 0.1);
+    // Only the cell between the wall and the node counts.
+    assert_relative_eq!(behind_wall.min_agl, 62.5, epsilon = 0.1);
+
+    // With a ramp the 40 m margin needed at 400 m is 60 m less than the
+    // largest margin, and 20 m are needed over the wall at 200 m.
+    config.query.start_distance = 0.0;
+    config.query.safety_margin_ramp = parse_safety_margin_ramp("0:0,1000:100").unwrap();
+    config.query.safety_margin = 100.0;
+    let behind_wall = node_at(&config, (10, 14));
+    assert_relative_eq!(behind_wall.clearance, 110.0, epsilon = 0.1);
+    assert!(!behind_wall.in_safety_margin(config.query.safety_margin));
+    assert_relative_eq!(behind_wall.min_agl, 45.0, epsilon = 0.1);
+}
+
+#[test]
+fn test_safety_margin_at_distance() {
+    let query = SearchQuery::builder()
+        .safety_margin_m(50.0)
+        .start_distance_m(200.0)
+        .build()
+        .unwrap();
+    assert_eq!(query.safety_margin_at_distance(100.0), 0.0);
+    assert_eq!(query.safety_margin_at_distance(300.0), 50.0);
+
+    let query = SearchQuery::builder()
+        .safety_margin_ramp(parse_safety_margin_ramp("500:0,3000:150").unwrap())
+        .build()
+        .unwrap();
+    assert_eq!(query.safety_margin, 150.0);
+    assert_eq!(query.safety_margin_at_distance(0.0), 0.0);
+    assert_relative_eq!(query.safety_margin_at_distance(1750.0), 75.0);
+    assert_eq!(query.safety_margin_at_distance(5000.0), 150.0);
+}
+
+#[test]
+fn test_search_from_starts() {
+    let grid = HeightGrid {
+        heights: Array2::zeros((21, 41)),
+        cell_size: 20.0,
+        min_cell_size: 20.0,
+        latitudes: (47.0, 47.1),
+        longitudes: (11.0, 11.2),
+        data_source: DataSource::Srtm,
+        data_quality: None,
+    };
+    let config = SearchConfig {
+        grid,
+        query: SearchQuery::default(),
+        wind_field: None,
+        airspace: None,
+        cancellation: None,
+    };
+    let starts = [(10, 10), (10, 30)];
+    let state = search_from_starts(&[(starts[0], 100.0), (starts[1], 60.0)], &config);
+    let sources = start_sources(state.explored.iter(), &starts);
+    let node_at = |ix| state.explored.iter().find(|n| n.ix == ix).unwrap().clone();
+
+    // Halfway between the starts the higher one is better.
+    let middle = node_at((10, 20));
+    assert_relative_eq!(middle.height, 75.0, epsilon = 0.01);
+    assert_relative_eq!(middle.distance, 200.0, epsilon = 0.01);
+    assert_eq!(sources[&(10, 20)], 0);
+
+    // Close to the lower start it is better, with distances counted from it.
+    let close = node_at((10, 29));
+    assert_relative_eq!(close.height, 57.5, epsilon = 0.01);
+    assert_relative_eq!(close.distance, 20.0, epsilon = 0.01);
+    assert_eq!(sources[&(10, 29)], 1);
+    assert_eq!(sources[&(10, 30)], 1);
+}
+
+#[test]
+fn test_search_with_thermals() {
+    let config = SearchConfig {
+        grid: HeightGrid {
+            heights: Array2::zeros((21, 41)),
+            cell_size: 20.0,
+            min_cell_size: 20.0,
+            latitudes: (47.0, 47.1),
+            longitudes: (11.0, 11.2),
+            data_source: DataSource::Srtm,
+            data_quality: None,
+        },
+        query: SearchQuery::builder().build().unwrap(),
+        wind_field: None,
+        airspace: None,
+        cancellation: None,
+    };
+    // The first thermal is reached at 7.5 m and leads to the second one, the
+    // third is reached above its top.
+    let thermals = [((10, 15), 40.0), ((10, 28), 30.0), ((10, 12), 5.0)];
+    let (state, climbs) = search_with_thermals((10, 10), 20.0, &thermals, &config);
+    assert_eq!(climbs, vec![(10, 15), (10, 28)]);
+
+    let node = state.explored.get(&(10, 36)).unwrap();
+    assert!(node.reachable);
+    assert_relative_eq!(node.height, 10.0, epsilon = 0.01);
+    // Along the glides to both thermals.
+    assert_relative_eq!(node.distance, 520.0, epsilon = 0.01);
+
+    // Without the thermals the cone ends after 160 m.
+    let (state, climbs) = search_with_thermals((10, 10), 20.0, &[], &config);
+    assert!(climbs.is_empty());
+    assert!(state
+        .explored
+        .get(&(10, 19))
+        .is_none_or(|node| !node.reachable));
+}
+
+#[test]
+fn test_parse_thermals() {
+    assert_eq!(
+        parse_thermals("47.1:11.2:2500, 47.3:11.4:2000"),
+        Ok(vec![(47.1, 11.2, 2500.0), (47.3, 11.4, 2000.0)])
+    );
+    assert!(parse_thermals("47.1:11.2").is_err());
+    assert!(parse_thermals("97.1:11.2:2000").is_err());
+}
+
+#[test]
+fn test_parse_starts() {
+    assert_eq!(
+        parse_starts("47.1:11.2, 47.3:11.4:2000"),
+        Ok(vec![(47.1, 11.2, None), (47.3, 11.4, Some(2000.0))])
+    );
+    assert!(parse_starts("47.1").is_err());
+    assert!(parse_starts("47.1:11.2:1:2").is_err());
+    assert!(parse_starts("47.1:east").is_err());
+    assert!(parse_starts("97.1:11.2").is_err());
+}
+
+#[test]
+fn test_search_avoiding_airspace() {
+    let grid = HeightGrid {
+        heights: Array2::zeros((21, 21)),
+        cell_size: 100.0,
+        min_cell_size: 100.0,
+        latitudes: (47.0, 47.2),
+        longitudes: (11.0, 11.2),
+        data_source: DataSource::Srtm,
+        data_quality: None,
+    };
+    // Restricted from the ground over the north, and only above 200 m over
+    // the south.
+    let airspaces = parse_openair(
+        "AC D
+AL GND
+AH FL95
+DP 47:07:48 N 011:00:00 E
+DP 47:20:00 N 011:00:00 E
+DP 47:20:00 N 011:12:00 E
+DP 47:07:48 N 011:12:00 E
+AC R
+AL 200m MSL
+AH FL95
+DP 47:00:00 N 011:00:00 E
+DP 47:04:00 N 011:00:00 E
+DP 47:04:00 N 011:12:00 E
+DP 47:00:00 N 011:12:00 E
+",
+    )
+    .unwrap();
+    let mut config = SearchConfig {
+        airspace: Some(AirspaceGrid::new(&airspaces, &grid)),
+        grid,
+        query: SearchQuery {
+            start_height: Some(100.0),
+            ..Default::default()
+        },
+        wind_field: None,
+        cancellation: None,
+    };
+    let reachable = |config: &SearchConfig| {
+        search((10, 10), 100.0, config)
+            .explored
+            .iter()
+            .filter(|n| n.reachable)
+            .map(|n| n.ix)
+            .collect::<HashSet<_>>()
+    };
+
+    let avoiding = reachable(&config);
+    assert!(avoiding.contains(&(12, 10)));
+    assert!(avoiding.contains(&(3, 10)));
+    assert!(!avoiding.iter().any(|ix| ix.0 >= 13));
+    assert!(verify_search((10, 10), 100.0, &config).is_consistent());
+
+    config.airspace = None;
+    assert!(reachable(&config).contains(&(14, 10)));
+}
+
+#[test]
+fn test_search_with_ridge_lift() {
+    // Ridge running north-south, wind from the west.
+    let heights = Array2::from_shape_fn((30, 30), |(_, col)| {
+        (1000 - (col as i32 - 15).abs() * 40).max(0) as i16
+    });
+    let query = |ridge_lift| SearchQuery {
+        wind_direction: 3.0 * f32::consts::PI / 2.0,
+        wind_speed: 15.0,
+        additional_height: 10.0,
+        ridge_lift,
+        ..Default::default()
+    };
+    let grid = HeightGrid {
+        heights,
+        cell_size: 100.0,
+        min_cell_size: 100.0,
+        latitudes: (47.0, 47.1),
+        longitudes: (11.0, 11.1),
+        data_source: DataSource::Srtm,
+        data_quality: None,
+    };
+    let reachable = |ridge_lift| {
+        search_from_height_grid(grid.clone(), (15, 12), query(ridge_lift))
+            .unwrap()
+            .explored
+            .iter()
+            .filter(|n| n.reachable)
+            .count()
+    };
+
+    assert!(reachable(true) > reachable(false));
+}
+
+#[test]
+fn test_search_traced() {
+    let config = SearchConfig {
+        grid: HeightGrid {
+            heights: Array2::zeros((9, 9)),
+            cell_size: 100.0,
+            min_cell_size: 100.0,
+            latitudes: (47.0, 47.1),
+            longitudes: (11.0, 11.1),
+            data_source: DataSource::Srtm,
+            data_quality: None,
+        },
+        query: SearchQuery {
+            glide_ratio: 0.1,
+            start_height: Some(1000.0),
+            ..Default::default()
+        },
+        wind_field: None,
+        airspace: None,
+        cancellation: None,
+    };
+
+    let (state, steps) = search_traced((4, 4), 1000.0, &config);
+
+    assert_eq!(steps.len(), 81);
+    assert_eq!(steps.len(), state.explored.iter().count());
+    assert_eq!(steps[0].ix, (4, 4));
+    assert!(steps.windows(2).all(|w| w[0].distance <= w[1].distance));
+}
+
+#[test]
+fn test_speed_change_height() {
+    // From 10 to 20 m/s.
+    assert_relative_eq!(speed_change_height(36.0, 72.0), 300.0 / (2.0 * 9.81));
+    assert_eq!(speed_change_height(72.0, 36.0), 0.0);
+    assert_eq!(speed_change_height(40.0, 40.0), 0.0);
+    assert_eq!("energy".parse::<GlideModel>(), Ok(GlideModel::Energy));
+    assert!("kinetic".parse::<GlideModel>().is_err());
+}
+
+#[test]
+fn test_search_energy_model() {
+    // A wall north of the start, the cells behind it are reached by turning
+    // at its ends.
+    let mut heights = Array2::zeros((31, 31));
+    square((18, 8), (19, 22), 2000, &mut heights);
+    let explored = |model| {
+        let config = SearchConfig {
+            grid: HeightGrid {
+                heights: heights.clone(),
+                cell_size: 50.0,
+                min_cell_size: 50.0,
+                latitudes: (47.0, 47.1),
+                longitudes: (11.0, 11.1),
+                data_source: DataSource::Srtm,
+                data_quality: None,
+            },
+            query: SearchQuery {
+                glide_ratio: 0.1,
+                wind_direction: 3.0 * f32::consts::PI / 2.0,
+                wind_speed: 20.0,
+                start_height: Some(400.0),
+                polar: Some(parse_polar("30:1.0,40:1.3,55:2.5").unwrap()),
+                model,
+                ..Default::default()
+            },
+            wind_field: None,
+            airspace: None,
+            cancellation: None,
+        };
+        search((15, 15), 400.0, &config)
+            .explored
+            .iter()
+            .map(|node| (node.ix, node.clone()))
+            .collect::<HashMap<_, _>>()
+    };
+    let geometric = explored(GlideModel::Geometric);
+    let energy = explored(GlideModel::Energy);
+
+    let mut straight = 0;
+    let mut turning_costs = 0;
+    for (ix, node) in energy.iter() {
+        let geometric_node = &geometric[ix];
+        assert!(node.height <= geometric_node.height + 0.01);
+        if node.reference == Some((15, 15)) && geometric_node.reference == node.reference {
+            // Straight glides from the start are the same in both models.
+            assert_relative_eq!(node.height, geometric_node.height);
+            straight += 1;
+        } else if node.height < geometric_node.height - 1.0 {
+            turning_costs += 1;
+        }
+    }
+    assert!(straight > 100);
+    assert!(turning_costs > 0);
 }
 
 fn square(start: (usize, usize), end: (usize, usize), height: i16, grid: &mut Array2<i16>) {
@@ -102,6 +871,68 @@ fn square(start: (usize, usize), end: (usize, usize), height: i16, grid: &mut Ar
     }
 }
 
+#[test]
+fn test_search_iter() {
+    let mut heights = Array2::zeros((30, 30));
+    square((10, 18), (20, 20), 480, &mut heights);
+    let config = SearchConfig {
+        grid: HeightGrid {
+            heights,
+            cell_size: 100.0,
+            min_cell_size: 100.0,
+            latitudes: (47.0, 47.1),
+            longitudes: (11.0, 11.1),
+            data_source: DataSource::Srtm,
+            data_quality: None,
+        },
+        query: SearchQuery {
+            glide_ratio: 0.1,
+            start_height: Some(500.0),
+            ..Default::default()
+        },
+        wind_field: None,
+        airspace: None,
+        cancellation: None,
+    };
+
+    let expected = search((15, 10), 500.0, &config).explored;
+    let mut streamed = HashSet::new();
+    let mut last_distance = 0.0;
+    for node in search_iter((15, 10), 500.0, &config) {
+        // References are streamed before the nodes referring to them.
+        if let Some(reference) = node.reference {
+            assert!(streamed.contains(&reference));
+        }
+        assert!(node.distance >= last_distance);
+        last_distance = node.distance;
+
+        let full = expected.iter().find(|n| n.ix == node.ix).unwrap();
+        assert_eq!(full.height, node.height);
+        assert_eq!(full.reference, node.reference);
+        streamed.insert(node.ix);
+    }
+    assert_eq!(streamed.len(), expected.iter().count());
+
+    // A cancelled search stops after the current step.
+    let token = CancellationToken::new();
+    let config = SearchConfig {
+        cancellation: Some(token.clone()),
+        ..config
+    };
+    let mut running = search_iter((15, 10), 500.0, &config);
+    for _ in 0..10 {
+        assert!(running.step().is_some());
+    }
+    token.cancel();
+    assert!(running.step().is_none());
+    let explored = running.into_state().explored;
+    assert_eq!(explored.iter().filter(|n| n.explored).count(), 10);
+    assert!(search((15, 10), 500.0, &config)
+        .explored
+        .iter()
+        .all(|n| !n.explored));
+}
+
 #[test]
 fn test_search_detailed() {
     let heights = Array2::zeros((10, 10));
@@ -113,17 +944,17 @@ fn test_search_detailed() {
             min_cell_size: 10.0,
             latitudes: (0.0, 30.0),
             longitudes: (0.0, 30.0),
+            data_source: DataSource::Srtm,
+            data_quality: None,
         },
         query: SearchQuery {
             glide_ratio: 0.1,
-            trim_speed: 38.0,
-            wind_direction: 0.0,
-            wind_speed: 0.0,
             start_height: Some(start_height),
-            additional_height: 0.0,
-            safety_margin: 0.0,
-            start_distance: 0.0,
+            ..Default::default()
         },
+        wind_field: None,
+        airspace: None,
+        cancellation: None,
     };
 
     square((1, 2), (1, 7), 55, &mut config.grid.heights);
@@ -217,3 +1048,92 @@ fn test_search_detailed() {
         assert_eq!(expected_ref[x.1 as usize][x.0 as usize], (0, 0));
     }
 }
+
+#[test]
+fn test_reachable_in_transfer_order() {
+    let node = |ix, reference, distance, reachable| Node {
+        height: 0.0,
+        ix,
+        reference,
+        distance,
+        reachable,
+        clearance: f32::INFINITY,
+        min_agl: f32::INFINITY,
+        ground_speed: 0.0,
+        explored: true,
+    };
+    let nodes = vec![
+        node((5, 5), Some((2, 2)), 30.0, true),
+        node((3, 3), Some((1, 1)), 25.0, true),
+        node((2, 2), Some((0, 0)), 20.0, true),
+        node((1, 1), Some((0, 0)), 10.0, true),
+        node((9, 9), Some((0, 0)), 5.0, false),
+        node((0, 0), None, 0.0, true),
+        node((4, 4), Some((1, 1)), 25.0, true),
+    ];
+
+    let ordered = reachable_in_transfer_order(nodes)
+        .iter()
+        .map(|n| n.ix)
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        ordered,
+        vec![(0, 0), (1, 1), (2, 2), (3, 3), (4, 4), (5, 5)]
+    );
+}
+
+#[test]
+fn test_check_grid_shape() {
+    assert!(check_grid_shape(1000, 1000).is_ok());
+
+    let too_large = GridIxType::MAX as usize + 2;
+    assert_eq!(
+        check_grid_shape(too_large, 10),
+        Err(SearchError::GridTooLarge {
+            rows: too_large,
+            cols: 10
+        })
+    );
+}
+
+#[test]
+fn test_search_query_builder() {
+    let query = SearchQuery::builder()
+        .glide_number(10.0)
+        .wind_speed_kmh(20.0)
+        .wind_direction_deg(90.0)
+        .start_sector_deg(180.0, 270.0)
+        .additional_height_m(50.0)
+        .build()
+        .unwrap();
+    assert_eq!(query.glide_ratio, 0.1);
+    assert_eq!(query.wind_speed, 20.0);
+    assert!((query.wind_direction - f32::consts::PI / 2.0).abs() < 1e-6);
+    let (from, to) = query.start_sector.unwrap();
+    assert!((from - f32::consts::PI).abs() < 1e-6 && (to - 1.5 * f32::consts::PI).abs() < 1e-6);
+    assert_eq!(query.additional_height, 50.0);
+    assert_eq!(query.trim_speed, 38.0);
+    assert_eq!(query.start_height, None);
+
+    let query = SearchQuery::builder().glide_ratio(0.2).build().unwrap();
+    assert_eq!(query.glide_ratio, 0.2);
+
+    assert!(SearchQuery::builder().glide_number(0.0).build().is_err());
+    assert!(SearchQuery::builder().glide_ratio(-0.1).build().is_err());
+    assert!(SearchQuery::builder().trim_speed_kmh(0.0).build().is_err());
+    assert!(SearchQuery::builder().wind_speed_kmh(-1.0).build().is_err());
+    assert!(SearchQuery::builder()
+        .wind_direction_deg(f32::NAN)
+        .build()
+        .is_err());
+    assert!(SearchQuery::builder()
+        .start_sector_deg(0.0, f32::INFINITY)
+        .build()
+        .is_err());
+    assert!(SearchQuery::builder().polar(vec![]).build().is_err());
+    assert!(SearchQuery::builder()
+        .safety_margin_m(f32::NAN)
+        .build()
+        .is_err());
+}
@@ -0,0 +1,308 @@
+use std::{f32::consts::PI, sync::Arc};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::height_data::HeightGrid;
+use crate::search::{
+    get_effective_glide_ratio_for_angle, l2_distance, prepare_search, reindex, search_restricted,
+    GlideModel, GridIx, GridIxType, Node, SearchQuery, SearchResult, SearchSetup,
+};
+#[cfg(feature = "parallel")]
+use crate::search::{search_from_setup, Explored, SearchConfig, SearchError};
+
+/// Number of angular sectors used for incremental searches.
+pub const SECTOR_COUNT: usize = 36;
+
+/// Samples per sector when comparing effective glide ratios.
+const SAMPLES_PER_SECTOR: usize = 5;
+
+/// Cells this close to the start (in cells) belong to every sector, since
+/// their direction is not meaningful.
+const START_RADIUS: f32 = 2.0;
+
+/// Direction from `start` to `ix` as grid angle `atan2(rows, cols)` in
+/// `[0, 2π)`, i.e. counter-clockwise starting east.
+pub fn direction_angle(start: &GridIx, ix: &GridIx) -> f32 {
+    let rows = ix.0 as f32 - start.0 as f32;
+    let cols = ix.1 as f32 - start.1 as f32;
+    rows.atan2(cols).rem_euclid(2.0 * PI)
+}
+
+pub fn sector_of(start: &GridIx, ix: &GridIx, n_sectors: usize) -> usize {
+    let sector = (direction_angle(start, ix) / (2.0 * PI) * n_sectors as f32) as usize;
+    sector.min(n_sectors - 1)
+}
+
+/// Whether `ix` lies in the sector `(from, to)` of bearings (clockwise from
+/// north, in radians) as seen from `start`. Cells close to the start are
+/// always inside, so narrow sectors can still be left.
+pub fn in_sector(sector: (f32, f32), start: &GridIx, ix: &GridIx) -> bool {
+    if l2_distance(start, ix) <= START_RADIUS {
+        return true;
+    }
+    let bearing = PI / 2.0 - direction_angle(start, ix);
+    let width = (sector.1 - sector.0).rem_euclid(2.0 * PI);
+    (bearing - sector.0).rem_euclid(2.0 * PI) <= width
+}
+
+/// Start sector from the `sector_from` and `sector_to` request parameters,
+/// given in degrees.
+pub fn start_sector_from_degrees(
+    from: Option<f32>,
+    to: Option<f32>,
+) -> Result<Option<(f32, f32)>, String> {
+    match (from, to) {
+        (None, None) => Ok(None),
+        (Some(from), Some(to)) if from.is_finite() && to.is_finite() => {
+            Ok(Some((from.to_radians(), to.to_radians())))
+        }
+        (Some(_), Some(_)) => Err("sector bounds have to be finite".to_string()),
+        _ => Err("sector_from and sector_to have to be given together".to_string()),
+    }
+}
+
+/// Effective glide ratio when flying from the start in direction `angle`
+/// (see `direction_angle`).
+pub fn effective_glide_ratio_in_direction(query: &SearchQuery, angle: f32) -> f32 {
+    // The search computes the glide from the vector pointing back to the origin.
+    get_effective_glide_ratio_for_angle(query, angle + PI).glide_ratio
+}
+
+/// Marks the sectors in which the effective glide ratio differs by more than
+/// `threshold` (relative) between the two queries.
+pub fn affected_sectors(
+    previous: &SearchQuery,
+    query: &SearchQuery,
+    n_sectors: usize,
+    threshold: f32,
+) -> Vec<bool> {
+    let sector_width = 2.0 * PI / n_sectors as f32;
+    (0..n_sectors)
+        .map(|sector| {
+            (0..=SAMPLES_PER_SECTOR).any(|sample| {
+                let angle =
+                    sector_width * (sector as f32 + sample as f32 / SAMPLES_PER_SECTOR as f32);
+                let a = effective_glide_ratio_in_direction(previous, angle);
+                let b = effective_glide_ratio_in_direction(query, angle);
+                if a.is_infinite() || b.is_infinite() {
+                    return a != b;
+                }
+                (a - b).abs() > threshold * a
+            })
+        })
+        .collect()
+}
+
+/// Wind layers replace the wind direction, so queries with layers never
+/// qualify. Edge hooks have to be the same instance.
+fn same_except_wind_direction(a: &SearchQuery, b: &SearchQuery) -> bool {
+    a.wind_layers.is_empty()
+        && b.wind_layers.is_empty()
+        && a.glide_ratio == b.glide_ratio
+        && a.trim_speed == b.trim_speed
+        && a.wind_speed == b.wind_speed
+        && a.start_height == b.start_height
+        && a.additional_height == b.additional_height
+        && a.safety_margin == b.safety_margin
+        && a.start_distance == b.start_distance
+        && a.safety_margin_ramp == b.safety_margin_ramp
+        && a.ridge_lift == b.ridge_lift
+        && a.polar == b.polar
+        && a.start_sector == b.start_sector
+        && a.reverse == b.reverse
+        && a.terrain_error == b.terrain_error
+        && a.model == b.model
+        && match (&a.edge_hook, &b.edge_hook) {
+            (None, None) => true,
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+}
+
+/// A finished search to reuse, as returned by `search_from_point`.
+pub struct PreviousSearch<'a> {
+    pub explored: &'a [Node],
+    /// Grid of the previous search, cropped to its reachable area.
+    pub height_grid: &'a HeightGrid,
+    pub query: &'a SearchQuery,
+}
+
+pub fn search_from_point_incremental(
+    latitude: f32,
+    longitude: f32,
+    cell_size: f32,
+    query: SearchQuery,
+    previous: PreviousSearch,
+    threshold: f32,
+) -> Option<SearchResult> {
+    if !same_except_wind_direction(previous.query, &query) {
+        return None;
+    }
+    search_incremental(
+        prepare_search(latitude, longitude, cell_size, query).ok()?,
+        previous,
+        threshold,
+    )
+}
+
+/// Runs a prepared search, reusing the nodes of a previous search that only
+/// differed in wind direction. Only sectors where the effective glide ratio
+/// changed by more than `threshold` (plus their direct neighbors) are
+/// searched again, the other sectors are copied from the previous search.
+///
+/// The result is an approximation: paths crossing from a recomputed sector
+/// into a reused one are not updated, and reused nodes can be off by up to
+/// half a cell where the grids are not aligned. Returns `None` if the
+/// previous search can not be reused or the edge hook failed. Ridge lift
+/// depends on the wind direction everywhere and the energy model on the
+/// heading changes along the paths, so searches using either are never
+/// reused.
+pub fn search_incremental(
+    setup: SearchSetup,
+    previous: PreviousSearch,
+    threshold: f32,
+) -> Option<SearchResult> {
+    if setup.config.query.ridge_lift
+        || setup.config.query.model == GlideModel::Energy
+        || !same_except_wind_direction(previous.query, &setup.config.query)
+    {
+        return None;
+    }
+
+    let affected = affected_sectors(previous.query, &setup.config.query, SECTOR_COUNT, threshold);
+    let start = setup.start_ix;
+    let grid_shape = setup.config.grid.heights.shape();
+
+    // The grids are sized by the wind, so they differ in extent. Cells of
+    // the previous grid are mapped to the closest ones of ours.
+    let grid = &setup.config.grid;
+    if (previous.height_grid.cell_size - grid.cell_size).abs() > grid.cell_size * 0.01 {
+        return None;
+    }
+    let (lat_resolution, lon_resolution) = grid.get_angular_resolution();
+    let offset = (
+        ((previous.height_grid.latitudes.0 - grid.latitudes.0) / lat_resolution).round(),
+        ((previous.height_grid.longitudes.0 - grid.longitudes.0) / lon_resolution).round(),
+    );
+    if offset.0 < 0.0 || offset.1 < 0.0 {
+        return None;
+    }
+    let offset = (offset.0 as GridIxType, offset.1 as GridIxType);
+
+    let recompute = |ix: &GridIx| {
+        if l2_distance(&start, ix) <= START_RADIUS {
+            return true;
+        }
+        let sector = sector_of(&start, ix, SECTOR_COUNT);
+        affected[sector]
+            || affected[(sector + 1) % SECTOR_COUNT]
+            || affected[(sector + SECTOR_COUNT - 1) % SECTOR_COUNT]
+    };
+
+    let mut state = search_restricted(start, setup.start_height, &setup.config, recompute);
+    // A failed hook is reported by the full search.
+    setup.config.check_edge_hook().ok()?;
+
+    for node in previous.explored.iter() {
+        let row = node.ix.0 as usize + offset.0 as usize;
+        let col = node.ix.1 as usize + offset.1 as usize;
+        let ix = (row as GridIxType, col as GridIxType);
+        // Recomputed sectors may have reached beyond our grid before.
+        if recompute(&ix) {
+            continue;
+        }
+        if row >= grid_shape[0] || col >= grid_shape[1] {
+            return None;
+        }
+        let mut node = node.clone();
+        node.ix = ix;
+        node.reference = node.reference.map(|r| (r.0 + offset.0, r.1 + offset.1));
+        state.explored.insert(ix, node);
+    }
+
+    let border_bearings = state.explored.border_bearings(&start);
+    let (explored, height_grid, start_ix) = reindex(state.explored, &setup.config.grid, start);
+
+    Some(SearchResult {
+        explored,
+        height_grid,
+        ground_height: setup.ground_height,
+        start_ix,
+        border_bearings,
+    })
+}
+
+/// Number of sectors of `search_from_point_parallel`.
+#[cfg(feature = "parallel")]
+pub const PARALLEL_SECTOR_COUNT: usize = 12;
+
+/// Like `search`, but splits the area into `n_sectors` angular sectors
+/// around the start which are searched concurrently. Each sector is searched
+/// together with its neighbors, and where searches overlap the best node is
+/// kept, so paths may bend into the neighboring sectors.
+///
+/// The result is an approximation: paths that go around an obstacle through
+/// more than one other sector are not found. Every search allocates a full
+/// grid, so memory use grows with the number of threads.
+#[cfg(feature = "parallel")]
+pub fn search_parallel(
+    start: GridIx,
+    height: f32,
+    config: &SearchConfig,
+    n_sectors: usize,
+) -> Explored {
+    if n_sectors < 3 {
+        return search_restricted(start, height, config, |_| true).explored;
+    }
+    (0..n_sectors)
+        .into_par_iter()
+        .map(|sector| {
+            search_restricted(start, height, config, |ix| {
+                if l2_distance(&start, ix) <= START_RADIUS {
+                    return true;
+                }
+                let offset = (sector_of(&start, ix, n_sectors) + n_sectors - sector) % n_sectors;
+                offset <= 1 || offset == n_sectors - 1
+            })
+            .explored
+        })
+        .reduce_with(Explored::merge_highest)
+        .unwrap()
+}
+
+/// Like `search_from_point`, but searches with `search_parallel`. Reverse
+/// searches are not split.
+#[cfg(feature = "parallel")]
+pub fn search_from_point_parallel(
+    latitude: f32,
+    longitude: f32,
+    cell_size: f32,
+    query: SearchQuery,
+) -> Result<SearchResult, SearchError> {
+    let setup = prepare_search(latitude, longitude, cell_size, query)?;
+    if setup.config.query.reverse {
+        return search_from_setup(setup);
+    }
+    let explored = search_parallel(
+        setup.start_ix,
+        setup.start_height,
+        &setup.config,
+        PARALLEL_SECTOR_COUNT,
+    );
+    setup.config.check_edge_hook()?;
+    let border_bearings = explored.border_bearings(&setup.start_ix);
+    let (explored, height_grid, start_ix) = reindex(explored, &setup.config.grid, setup.start_ix);
+
+    Ok(SearchResult {
+        explored,
+        height_grid,
+        ground_height: setup.ground_height,
+        start_ix,
+        border_bearings,
+    })
+}
+
+#[cfg(test)]
+#[path = "./sectors_test.rs"]
+mod sectors_test;
@@ -0,0 +1,360 @@
+use std::{
+    f32::consts::PI,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, OnceLock,
+    },
+};
+
+use crate::{
+    height_data::HeightGrid,
+    search::{
+        search_from_height_grid, Edge, EdgeHook, GlideModel, SearchConfig, SearchError,
+        SearchQuery, SearchSetup,
+    },
+    synthetic::{synthetic_height_grid, SyntheticTerrain},
+};
+
+use super::{
+    affected_sectors, direction_angle, in_sector, search_incremental, sector_of,
+    start_sector_from_degrees, PreviousSearch, SECTOR_COUNT,
+};
+
+fn query(wind_direction: f32) -> SearchQuery {
+    SearchQuery {
+        glide_ratio: 1.0 / 6.0,
+        wind_direction,
+        wind_speed: 15.0,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_direction_angle() {
+    assert_eq!(direction_angle(&(5, 5), &(5, 6)), 0.0);
+    assert_eq!(direction_angle(&(5, 5), &(6, 5)), PI / 2.0);
+    assert_eq!(direction_angle(&(5, 5), &(5, 4)), PI);
+    assert_eq!(direction_angle(&(5, 5), &(4, 5)), 3.0 * PI / 2.0);
+
+    assert_eq!(sector_of(&(5, 5), &(5, 6), 4), 0);
+    assert_eq!(sector_of(&(5, 5), &(6, 4), 4), 1);
+    assert_eq!(sector_of(&(5, 5), &(4, 6), 4), 3);
+}
+
+#[test]
+fn test_in_sector() {
+    // Rows grow to the north, so (9, 5) is north of (5, 5).
+    let west = start_sector_from_degrees(Some(225.0), Some(315.0))
+        .unwrap()
+        .unwrap();
+    assert!(in_sector(west, &(5, 5), &(5, 1)));
+    assert!(!in_sector(west, &(5, 5), &(9, 5)));
+    assert!(!in_sector(west, &(5, 5), &(5, 9)));
+    // Close to the start everything is inside.
+    assert!(in_sector(west, &(5, 5), &(5, 6)));
+
+    // Sectors may wrap around north.
+    let north = start_sector_from_degrees(Some(330.0), Some(30.0))
+        .unwrap()
+        .unwrap();
+    assert!(in_sector(north, &(5, 5), &(9, 5)));
+    assert!(!in_sector(north, &(5, 5), &(1, 5)));
+
+    assert_eq!(start_sector_from_degrees(None, None), Ok(None));
+    assert!(start_sector_from_degrees(Some(10.0), None).is_err());
+    assert!(start_sector_from_degrees(Some(f32::NAN), Some(10.0)).is_err());
+}
+
+#[test]
+fn test_affected_sectors() {
+    assert!(!affected_sectors(&query(0.5), &query(0.5), 36, 0.01)
+        .iter()
+        .any(|x| *x));
+
+    let affected = affected_sectors(&query(0.5), &query(0.55), 36, 0.01);
+    assert!(affected.iter().any(|x| *x));
+    assert!(!affected.iter().all(|x| *x));
+}
+
+fn setup(wind_direction: f32) -> SearchSetup {
+    let grid = synthetic_height_grid(SyntheticTerrain::Cone, 47.0, 11.0, 200.0, 20000.0);
+    let start_ix = (100, 100);
+    let ground_height = grid.heights[[100, 100]] as f32;
+    SearchSetup {
+        ground_height,
+        start_height: ground_height,
+        start_ix,
+        config: SearchConfig {
+            grid,
+            query: query(wind_direction),
+            wind_field: None,
+            airspace: None,
+            cancellation: None,
+        },
+    }
+}
+
+#[test]
+fn test_search_incremental() {
+    let previous_setup = setup(0.5);
+    let previous = search_from_height_grid(
+        previous_setup.config.grid,
+        previous_setup.start_ix,
+        previous_setup.config.query,
+    )
+    .unwrap();
+    let previous_nodes = previous.explored.into_it().collect::<Vec<_>>();
+    let previous_reachable = previous_nodes.iter().filter(|n| n.reachable).count();
+
+    // Without any change everything is reused.
+    let unchanged = search_incremental(
+        setup(0.5),
+        PreviousSearch {
+            explored: &previous_nodes,
+            height_grid: &previous.height_grid,
+            query: &query(0.5),
+        },
+        0.01,
+    )
+    .unwrap();
+    let unchanged_reachable = unchanged.explored.iter().filter(|n| n.reachable).count();
+    assert_eq!(unchanged_reachable, previous_reachable);
+
+    let full_setup = setup(0.6);
+    let full = search_from_height_grid(
+        full_setup.config.grid,
+        full_setup.start_ix,
+        full_setup.config.query,
+    )
+    .unwrap();
+    let full_reachable = full.explored.iter().filter(|n| n.reachable).count();
+
+    let incremental = search_incremental(
+        setup(0.6),
+        PreviousSearch {
+            explored: &previous_nodes,
+            height_grid: &previous.height_grid,
+            query: &query(0.5),
+        },
+        0.01,
+    )
+    .unwrap();
+    let incremental_reachable = incremental.explored.iter().filter(|n| n.reachable).count();
+
+    assert_ne!(full_reachable, previous_reachable);
+    let error = (incremental_reachable as f32 - full_reachable as f32).abs();
+    assert!(error < 0.02 * full_reachable as f32);
+
+    assert!(search_incremental(
+        setup(0.6),
+        PreviousSearch {
+            explored: &previous_nodes,
+            height_grid: &previous.height_grid,
+            query: &SearchQuery {
+                wind_speed: 10.0,
+                ..query(0.5)
+            },
+        },
+        0.01,
+    )
+    .is_none());
+
+    for previous_query in [
+        SearchQuery {
+            reverse: true,
+            ..query(0.5)
+        },
+        SearchQuery {
+            terrain_error: true,
+            ..query(0.5)
+        },
+    ] {
+        assert!(search_incremental(
+            setup(0.6),
+            PreviousSearch {
+                explored: &previous_nodes,
+                height_grid: &previous.height_grid,
+                query: &previous_query,
+            },
+            0.01,
+        )
+        .is_none());
+    }
+
+    let mut energy_setup = setup(0.6);
+    energy_setup.config.query.model = GlideModel::Energy;
+    assert!(search_incremental(
+        energy_setup,
+        PreviousSearch {
+            explored: &previous_nodes,
+            height_grid: &previous.height_grid,
+            query: &SearchQuery {
+                model: GlideModel::Energy,
+                ..query(0.5)
+            },
+        },
+        0.01,
+    )
+    .is_none());
+}
+
+#[test]
+fn test_search_incremental_recomputes_neighbor_sectors() {
+    let previous_setup = setup(0.5);
+    let previous = search_from_height_grid(
+        previous_setup.config.grid,
+        previous_setup.start_ix,
+        previous_setup.config.query,
+    )
+    .unwrap();
+    // Mark every previous node, so reused ones can be told apart.
+    const MARKER: f32 = 1.0e6;
+    let previous_nodes = previous
+        .explored
+        .into_it()
+        .map(|mut node| {
+            node.height = MARKER;
+            node
+        })
+        .collect::<Vec<_>>();
+
+    let incremental = search_incremental(
+        setup(0.55),
+        PreviousSearch {
+            explored: &previous_nodes,
+            height_grid: &previous.height_grid,
+            query: &query(0.5),
+        },
+        0.01,
+    )
+    .unwrap();
+
+    let affected = affected_sectors(&query(0.5), &query(0.55), SECTOR_COUNT, 0.01);
+    let neighbor = |sector: usize| {
+        !affected[sector]
+            && (affected[(sector + 1) % SECTOR_COUNT]
+                || affected[(sector + SECTOR_COUNT - 1) % SECTOR_COUNT])
+    };
+    let start = incremental.start_ix;
+    let (mut reused, mut neighbors) = (0, 0);
+    for node in incremental.explored.iter() {
+        let sector = sector_of(&start, &node.ix, SECTOR_COUNT);
+        if neighbor(sector) && node.ix != start {
+            neighbors += 1;
+            assert_ne!(node.height, MARKER);
+        }
+        if node.height == MARKER {
+            reused += 1;
+        }
+    }
+    assert!(neighbors > 0);
+    assert!(reused > 0);
+}
+
+/// Hook failing on every glide after the first `glides`.
+struct FailingHook {
+    glides: usize,
+    calls: AtomicUsize,
+    error: OnceLock<String>,
+}
+
+impl FailingHook {
+    fn new(glides: usize) -> Arc<FailingHook> {
+        Arc::new(FailingHook {
+            glides,
+            calls: AtomicUsize::new(0),
+            error: OnceLock::new(),
+        })
+    }
+}
+
+impl EdgeHook for FailingHook {
+    fn height_loss_factor(&self, _grid: &HeightGrid, _edge: &Edge) -> Option<f32> {
+        if self.calls.fetch_add(1, Ordering::Relaxed) < self.glides {
+            return Some(1.0);
+        }
+        let _ = self.error.set("failed".to_string());
+        None
+    }
+
+    fn error(&self) -> Option<String> {
+        self.error.get().cloned()
+    }
+}
+
+#[test]
+fn test_search_incremental_with_edge_hook() {
+    let previous_setup = setup(0.5);
+    let previous = search_from_height_grid(
+        previous_setup.config.grid,
+        previous_setup.start_ix,
+        previous_setup.config.query,
+    )
+    .unwrap();
+    let previous_nodes = previous.explored.into_it().collect::<Vec<_>>();
+    let incremental = |hook: Arc<FailingHook>, previous_hook: Arc<FailingHook>| {
+        let mut setup = setup(0.6);
+        setup.config.query.edge_hook = Some(hook);
+        search_incremental(
+            setup,
+            PreviousSearch {
+                explored: &previous_nodes,
+                height_grid: &previous.height_grid,
+                query: &SearchQuery {
+                    edge_hook: Some(previous_hook),
+                    ..query(0.5)
+                },
+            },
+            0.01,
+        )
+    };
+
+    let hook = FailingHook::new(usize::MAX);
+    assert!(incremental(hook.clone(), hook).is_some());
+    assert!(incremental(FailingHook::new(usize::MAX), FailingHook::new(usize::MAX)).is_none());
+
+    // The hook fails on a later glide of the recomputed sectors.
+    let hook = FailingHook::new(100);
+    assert!(incremental(hook.clone(), hook).is_none());
+    let mut setup = setup(0.6);
+    setup.config.query.edge_hook = Some(FailingHook::new(100));
+    assert!(matches!(
+        search_from_height_grid(setup.config.grid, setup.start_ix, setup.config.query),
+        Err(SearchError::EdgeHook(_))
+    ));
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn test_search_parallel() {
+    use std::collections::HashSet;
+
+    use super::search_parallel;
+    use crate::search::search;
+
+    let setup = setup(0.5);
+    let sequential = search(setup.start_ix, setup.start_height, &setup.config).explored;
+    let sequential_reachable = sequential.iter().filter(|n| n.reachable).count();
+
+    let parallel = search_parallel(setup.start_ix, setup.start_height, &setup.config, 12);
+    let parallel_reachable = parallel.iter().filter(|n| n.reachable).count();
+
+    let error = (parallel_reachable as f32 - sequential_reachable as f32).abs();
+    assert!(error < 0.02 * sequential_reachable as f32);
+    // Merged references still point to reachable nodes.
+    let reachable = parallel
+        .iter()
+        .filter(|n| n.reachable)
+        .map(|n| n.ix)
+        .collect::<HashSet<_>>();
+    for node in parallel.iter().filter(|n| n.reachable) {
+        assert!(reachable.contains(&node.reference.unwrap_or(setup.start_ix)));
+    }
+
+    // Too few sectors to split.
+    let single = search_parallel(setup.start_ix, setup.start_height, &setup.config, 1);
+    assert_eq!(
+        single.iter().filter(|n| n.reachable).count(),
+        sequential_reachable
+    );
+}
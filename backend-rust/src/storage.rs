@@ -0,0 +1,247 @@
+use async_trait::async_trait;
+#[cfg(feature = "server")]
+use reqwest::Client;
+use std::fs;
+use std::path::PathBuf;
+#[cfg(feature = "server")]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[cfg(feature = "server")]
+use crate::{config::StorageConfig, usage::civil_from_days};
+
+/// Key-value blob storage used for the tile cache and the persistent cone
+/// cache. Keys are relative, `/` separated paths like `tiles/a/12/2/3.png`.
+#[async_trait]
+pub trait Storage: Send + Sync {
+    async fn get(&self, key: &str) -> Option<Vec<u8>>;
+
+    async fn put(&self, key: &str, value: &[u8]) -> Result<(), String>;
+
+    async fn remove(&self, key: &str) -> Result<(), String>;
+}
+
+pub struct FileStorage {
+    root: PathBuf,
+}
+
+impl FileStorage {
+    pub fn new(root: impl Into<PathBuf>) -> FileStorage {
+        FileStorage { root: root.into() }
+    }
+
+    fn path(&self, key: &str) -> Option<PathBuf> {
+        // Keys come from request paths, never allow escaping the root.
+        if key.split('/').any(|part| part == ".." || part.is_empty()) {
+            return None;
+        }
+        Some(self.root.join(key))
+    }
+}
+
+/// Reads and writes synchronously, the cached files are small.
+#[async_trait]
+impl Storage for FileStorage {
+    async fn get(&self, key: &str) -> Option<Vec<u8>> {
+        fs::read(self.path(key)?).ok()
+    }
+
+    async fn put(&self, key: &str, value: &[u8]) -> Result<(), String> {
+        let path = self.path(key).ok_or(format!("Invalid key {key}"))?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        fs::write(path, value).map_err(|e| e.to_string())
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), String> {
+        let path = self.path(key).ok_or(format!("Invalid key {key}"))?;
+        fs::remove_file(path).map_err(|e| e.to_string())
+    }
+}
+
+/// Minimal S3 client for the cache storage, signing requests with AWS
+/// signature version 4 and path-style urls.
+#[cfg(feature = "server")]
+pub struct S3Storage {
+    host: String,
+    config: StorageConfig,
+    /// Verifies the certificates, the requests carry the credentials.
+    client: Client,
+}
+
+#[cfg(feature = "server")]
+pub fn hex_string(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(feature = "server")]
+pub fn uri_encode_path(key: &str) -> String {
+    let mut result = String::with_capacity(key.len());
+    for b in key.bytes() {
+        if b.is_ascii_alphanumeric() || b"-._~/".contains(&b) {
+            result.push(b as char);
+        } else {
+            result.push_str(&format!("%{b:02X}"));
+        }
+    }
+    result
+}
+
+#[cfg(feature = "server")]
+fn amz_date(now: SystemTime) -> String {
+    let secs = now.duration_since(UNIX_EPOCH).unwrap().as_secs();
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let rem = secs % 86_400;
+    format!(
+        "{year:04}{month:02}{day:02}T{:02}{:02}{:02}Z",
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+/// Request without a query string to be signed with AWS signature version 4.
+#[cfg(feature = "server")]
+pub struct SignedRequest<'a> {
+    pub method: &'a str,
+    /// Already uri encoded.
+    pub path: &'a str,
+    /// Signed headers with lower case names, sorted by name.
+    pub headers: &'a [(&'a str, &'a str)],
+    pub payload_hash: &'a str,
+    pub amz_date: &'a str,
+}
+
+/// `Authorization` header of `request` to `service`, signed with the keys
+/// and region of `config`.
+#[cfg(feature = "server")]
+pub fn sigv4_authorization(
+    request: &SignedRequest,
+    config: &StorageConfig,
+    service: &str,
+) -> String {
+    let canonical_headers: String = request
+        .headers
+        .iter()
+        .map(|(name, value)| format!("{name}:{}\n", value.trim()))
+        .collect();
+    let signed_headers = request
+        .headers
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(";");
+    let canonical_request = format!(
+        "{}\n{}\n\n{canonical_headers}\n{signed_headers}\n{}",
+        request.method, request.path, request.payload_hash
+    );
+    let date = &request.amz_date[..8];
+    let scope = format!("{date}/{}/{service}/aws4_request", config.region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{scope}\n{}",
+        request.amz_date,
+        hex_string(&hmac_sha256::Hash::hash(canonical_request.as_bytes()))
+    );
+
+    let k_date = hmac_sha256::HMAC::mac(date, format!("AWS4{}", config.secret_access_key));
+    let k_region = hmac_sha256::HMAC::mac(&config.region, k_date);
+    let k_service = hmac_sha256::HMAC::mac(service, k_region);
+    let k_signing = hmac_sha256::HMAC::mac("aws4_request", k_service);
+    let signature = hex_string(&hmac_sha256::HMAC::mac(string_to_sign, k_signing));
+
+    format!(
+        "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+        config.access_key_id
+    )
+}
+
+#[cfg(feature = "server")]
+impl S3Storage {
+    pub fn new(config: &StorageConfig) -> S3Storage {
+        S3Storage {
+            host: config
+                .endpoint
+                .trim_start_matches("https://")
+                .trim_start_matches("http://")
+                .trim_end_matches('/')
+                .to_string(),
+            config: config.clone(),
+            client: Client::new(),
+        }
+    }
+
+    async fn request(
+        &self,
+        method: reqwest::Method,
+        key: &str,
+        body: Vec<u8>,
+    ) -> Result<reqwest::Response, String> {
+        let config = &self.config;
+        let path = format!("/{}/{}", config.bucket, uri_encode_path(key));
+        let amz_date = amz_date(SystemTime::now());
+        let payload_hash = hex_string(&hmac_sha256::Hash::hash(&body));
+        let authorization = sigv4_authorization(
+            &SignedRequest {
+                method: method.as_str(),
+                path: &path,
+                headers: &[
+                    ("host", &self.host),
+                    ("x-amz-content-sha256", &payload_hash),
+                    ("x-amz-date", &amz_date),
+                ],
+                payload_hash: &payload_hash,
+                amz_date: &amz_date,
+            },
+            config,
+            "s3",
+        );
+
+        let url = format!("{}{}", config.endpoint.trim_end_matches('/'), path);
+        self.client
+            .request(method, url)
+            .header("x-amz-date", amz_date.as_str())
+            .header("x-amz-content-sha256", payload_hash.as_str())
+            .header("authorization", authorization)
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(feature = "server")]
+#[async_trait]
+impl Storage for S3Storage {
+    async fn get(&self, key: &str) -> Option<Vec<u8>> {
+        let response = self.request(reqwest::Method::GET, key, vec![]).await.ok()?;
+        if !response.status().is_success() {
+            return None;
+        }
+        response.bytes().await.ok().map(|b| b.to_vec())
+    }
+
+    async fn put(&self, key: &str, value: &[u8]) -> Result<(), String> {
+        let response = self
+            .request(reqwest::Method::PUT, key, value.to_vec())
+            .await?;
+        if !response.status().is_success() {
+            return Err(format!("S3 put failed with status {}", response.status()));
+        }
+        Ok(())
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), String> {
+        let response = self.request(reqwest::Method::DELETE, key, vec![]).await?;
+        if !response.status().is_success() {
+            return Err(format!(
+                "S3 delete failed with status {}",
+                response.status()
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[path = "./storage_test.rs"]
+mod storage_test;
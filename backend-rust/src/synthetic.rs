@@ -0,0 +1,130 @@
+use std::str::FromStr;
+
+use ndarray::Array2;
+
+use crate::{
+    height_data::{get_bounds_around_point, DataSource, HeightGrid},
+    search::{
+        check_grid_shape, max_search_distance, search_from_height_grid, GridIxType, SearchError,
+        SearchQuery, SearchResult,
+    },
+};
+
+const BASE_HEIGHT: f32 = 500.0;
+const PEAK_HEIGHT: f32 = 2500.0;
+const FLAT_HEIGHT: f32 = 1000.0;
+/// Height loss per meter of horizontal distance on the slopes.
+const SLOPE: f32 = 0.2;
+const RIDGE_SADDLE_DEPTH: f32 = 150.0;
+const RIDGE_SADDLE_SPACING: f32 = 4000.0;
+
+/// Generated terrain for running searches without any DEM files, e.g. in
+/// frontend development or CI.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SyntheticTerrain {
+    /// A single conical mountain with the start at its summit.
+    Cone,
+    /// A north-south ridge through the start with shallow saddles.
+    Ridge,
+    Flat,
+}
+
+impl FromStr for SyntheticTerrain {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cone" => Ok(SyntheticTerrain::Cone),
+            "ridge" => Ok(SyntheticTerrain::Ridge),
+            "flat" => Ok(SyntheticTerrain::Flat),
+            _ => Err(format!("Unknown synthetic terrain {s}")),
+        }
+    }
+}
+
+impl SyntheticTerrain {
+    pub fn max_height(&self) -> f32 {
+        match self {
+            SyntheticTerrain::Cone | SyntheticTerrain::Ridge => PEAK_HEIGHT,
+            SyntheticTerrain::Flat => FLAT_HEIGHT,
+        }
+    }
+
+    /// Height at the given offset (in meters, north/east) from the start.
+    pub fn height_at(&self, north: f32, east: f32) -> f32 {
+        match self {
+            SyntheticTerrain::Cone => {
+                let r = (north * north + east * east).sqrt();
+                (PEAK_HEIGHT - r * SLOPE).max(BASE_HEIGHT)
+            }
+            SyntheticTerrain::Ridge => {
+                let saddles = RIDGE_SADDLE_DEPTH
+                    * (1.0 - (north * 2.0 * std::f32::consts::PI / RIDGE_SADDLE_SPACING).cos())
+                    / 2.0;
+                (PEAK_HEIGHT - saddles - east.abs() * SLOPE).max(BASE_HEIGHT)
+            }
+            SyntheticTerrain::Flat => FLAT_HEIGHT,
+        }
+    }
+}
+
+/// Generates a height grid centered on the given point that covers
+/// `distance_m` in every direction.
+pub fn synthetic_height_grid(
+    terrain: SyntheticTerrain,
+    latitude: f32,
+    longitude: f32,
+    cell_size: f32,
+    distance_m: f32,
+) -> HeightGrid {
+    let half_size = (distance_m / cell_size).ceil() as usize;
+    let size = 2 * half_size + 1;
+
+    let heights = Array2::from_shape_fn((size, size), |(row, col)| {
+        let north = (row as f32 - half_size as f32) * cell_size;
+        let east = (col as f32 - half_size as f32) * cell_size;
+        terrain.height_at(north, east).round() as i16
+    });
+
+    let half_extent = half_size as f32 * cell_size;
+    let (lower_latitude, upper_latitude, lower_longitude, upper_longitude) =
+        get_bounds_around_point(latitude, longitude, half_extent);
+
+    HeightGrid {
+        heights,
+        cell_size,
+        min_cell_size: cell_size,
+        latitudes: (lower_latitude, upper_latitude),
+        longitudes: (lower_longitude, upper_longitude),
+        data_source: DataSource::Synthetic,
+        data_quality: None,
+    }
+}
+
+pub fn search_synthetic(
+    terrain: SyntheticTerrain,
+    latitude: f32,
+    longitude: f32,
+    cell_size: f32,
+    query: SearchQuery,
+) -> Result<SearchResult, SearchError> {
+    let height = query
+        .start_height
+        .unwrap_or(terrain.max_height() + query.additional_height)
+        .max(terrain.max_height());
+    let distance = max_search_distance(&query, height) + cell_size;
+    let cells_per_axis = (2.0 * distance / cell_size).ceil() as usize + 1;
+    check_grid_shape(cells_per_axis, cells_per_axis)?;
+
+    let grid = synthetic_height_grid(terrain, latitude, longitude, cell_size, distance);
+    let start_ix = (
+        (grid.heights.shape()[0] / 2) as GridIxType,
+        (grid.heights.shape()[1] / 2) as GridIxType,
+    );
+
+    search_from_height_grid(grid, start_ix, query)
+}
+
+#[cfg(test)]
+#[path = "./synthetic_test.rs"]
+mod synthetic_test;
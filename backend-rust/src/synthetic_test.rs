@@ -0,0 +1,47 @@
+use crate::{height_data::DataSource, search::SearchQuery};
+
+use super::{search_synthetic, synthetic_height_grid, SyntheticTerrain};
+
+fn query() -> SearchQuery {
+    SearchQuery::builder().build().unwrap()
+}
+
+#[test]
+fn test_parse_synthetic_terrain() {
+    assert_eq!("cone".parse(), Ok(SyntheticTerrain::Cone));
+    assert_eq!("ridge".parse(), Ok(SyntheticTerrain::Ridge));
+    assert_eq!("flat".parse(), Ok(SyntheticTerrain::Flat));
+    assert!("volcano".parse::<SyntheticTerrain>().is_err());
+}
+
+#[test]
+fn test_synthetic_height_grid() {
+    let grid = synthetic_height_grid(SyntheticTerrain::Cone, 47.0, 11.0, 100.0, 1000.0);
+
+    assert_eq!(grid.heights.shape(), &[21, 21]);
+    assert_eq!(grid.data_source, DataSource::Synthetic);
+    assert_eq!(grid.heights[[10, 10]], 2500);
+    assert_eq!(grid.heights[[10, 15]], 2400);
+    assert_eq!(grid.heights[[0, 10]], 2300);
+    assert!(grid.latitudes.0 < 47.0 && grid.latitudes.1 > 47.0);
+    assert!(grid.longitudes.0 < 11.0 && grid.longitudes.1 > 11.0);
+
+    let ridge = synthetic_height_grid(SyntheticTerrain::Ridge, 47.0, 11.0, 100.0, 1000.0);
+    assert_eq!(ridge.heights[[10, 10]], 2500);
+    assert_eq!(ridge.heights[[10, 0]], 2300);
+    assert!(ridge.heights[[0, 10]] < 2500);
+}
+
+#[test]
+fn test_search_synthetic() {
+    let flat = search_synthetic(SyntheticTerrain::Flat, 47.0, 11.0, 200.0, query()).unwrap();
+    let flat_reachable = flat.explored.iter().filter(|n| n.reachable).count();
+    assert_eq!(flat.ground_height, 1000.0);
+    assert_eq!(flat_reachable, 1);
+
+    let cone = search_synthetic(SyntheticTerrain::Cone, 47.0, 11.0, 200.0, query()).unwrap();
+    let cone_reachable = cone.explored.iter().filter(|n| n.reachable).count();
+    assert_eq!(cone.ground_height, 2500.0);
+    assert!(cone_reachable > 100);
+    assert_eq!(cone.height_grid.data_source, DataSource::Synthetic);
+}
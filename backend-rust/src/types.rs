@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use std::collections::HashMap;
+
 use crate::textsearch::{PrefixTrie, SearchIndex};
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -48,3 +50,37 @@ pub struct SearchLocation {
     pub index: SearchIndex<PrefixTrie<LocationInfo, (), u32>>,
     pub additional_info: Vec<String>,
 }
+
+impl SearchLocation {
+    /// Location search index of all locations, by name. Equal additional
+    /// infos are only stored once.
+    pub fn from_locations(locations: impl IntoIterator<Item = Location>) -> SearchLocation {
+        let mut ix = SearchIndex::new();
+        let mut additional_info_map = HashMap::<String, usize>::new();
+        let mut additional_info_vec = vec![];
+
+        for location in locations {
+            let ad = location.additional_info.unwrap_or_default();
+            let additional_info_ix = if let Some(&ix) = additional_info_map.get(&ad) {
+                ix
+            } else {
+                let ix = additional_info_vec.len();
+                additional_info_map.insert(ad.clone(), ix);
+                additional_info_vec.push(ad);
+                ix
+            };
+
+            ix.insert(
+                location.name.as_str(),
+                LocationInfo {
+                    center: location.center,
+                    additional_info_ix,
+                },
+            );
+        }
+        SearchLocation {
+            index: ix.finalize(),
+            additional_info: additional_info_vec,
+        }
+    }
+}
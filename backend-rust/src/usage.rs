@@ -0,0 +1,246 @@
+use std::{
+    cmp::Reverse,
+    collections::{hash_map::RandomState, BTreeMap},
+    hash::BuildHasher,
+    sync::{Mutex, OnceLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+
+/// Days of usage kept and reported.
+pub const USAGE_DAYS: u64 = 30;
+/// Edge length in degrees of the regions starts are counted in.
+pub const REGION_DEGREES: f32 = 1.0;
+/// Scale of the Laplace noise added to each count. One search changes a
+/// count by one, so this is the inverse of the privacy parameter epsilon.
+pub const NOISE_SCALE: f64 = 5.0;
+/// Noisy counts are rounded to multiples of this.
+pub const COUNT_ROUNDING: u64 = 10;
+/// Regions with fewer searches, after noise and rounding, are not reported.
+pub const MIN_REGION_SEARCHES: u64 = 50;
+/// Regions reported at most, the busiest first.
+pub const TOP_REGIONS: usize = 10;
+
+/// Ranges the averaged parameters of a search are clamped to, so a single
+/// search can only move their sums by the width of the range.
+pub const GLIDE_NUMBER_RANGE: (f32, f32) = (1.0, 15.0);
+pub const WIND_SPEED_RANGE: (f32, f32) = (0.0, 50.0);
+pub const ADDITIONAL_HEIGHT_RANGE: (f32, f32) = (0.0, 1000.0);
+
+/// Flight parameters of a search that are averaged.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SearchParameters {
+    pub glide_number: f32,
+    /// Wind speed in km/h.
+    pub wind_speed: f32,
+    /// Height above the start in meters.
+    pub additional_height: f32,
+}
+
+#[derive(Default)]
+struct DayUsage {
+    searches: u64,
+    regions: BTreeMap<(i32, i32), u64>,
+    glide_number: f64,
+    wind_speed: f64,
+    additional_height: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct DaySearches {
+    /// `YYYY-MM-DD` in UTC.
+    pub date: String,
+    pub searches: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RegionSearches {
+    /// South west corner of the region in degrees.
+    pub lat: f32,
+    pub lon: f32,
+    pub searches: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct AverageParameters {
+    /// Rounded to 0.5.
+    pub glide_number: f32,
+    /// Rounded to 5 km/h.
+    pub wind_speed: f32,
+    /// Rounded to 50 m.
+    pub additional_height: f32,
+}
+
+/// Usage of the last `USAGE_DAYS` days that can be published, see
+/// `UsageStats::public`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct PublicUsage {
+    pub searches_per_day: Vec<DaySearches>,
+    pub top_regions: Vec<RegionSearches>,
+    /// Unset while there are too few searches to hide single ones.
+    pub averages: Option<AverageParameters>,
+}
+
+/// Searches of the last `USAGE_DAYS` days by day and coarse region. Nothing
+/// identifying single requests is kept, and only noisy counts leave it.
+pub struct UsageStats {
+    /// Seeds the noise together with the count, so the noise of a count is
+    /// the same every time it is published and can't be averaged away by
+    /// asking repeatedly, but changes with every new search counted.
+    secret: u64,
+    days: Mutex<BTreeMap<u64, DayUsage>>,
+}
+
+impl UsageStats {
+    pub fn new(secret: u64) -> UsageStats {
+        UsageStats {
+            secret,
+            days: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Counts a search starting at `lat` and `lon` on `day`, in days since
+    /// the unix epoch. Days older than `USAGE_DAYS` are dropped.
+    pub fn record(&self, day: u64, lat: f32, lon: f32, parameters: SearchParameters) {
+        let mut days = self.days.lock().unwrap();
+        days.retain(|d, _| d + USAGE_DAYS > day);
+        let usage = days.entry(day).or_default();
+        usage.searches += 1;
+        *usage.regions.entry(region_of(lat, lon)).or_default() += 1;
+        let clamp = |value: f32, (min, max): (f32, f32)| value.clamp(min, max) as f64;
+        usage.glide_number += clamp(parameters.glide_number, GLIDE_NUMBER_RANGE);
+        usage.wind_speed += clamp(parameters.wind_speed, WIND_SPEED_RANGE);
+        usage.additional_height += clamp(parameters.additional_height, ADDITIONAL_HEIGHT_RANGE);
+    }
+
+    /// Searches per day, the busiest regions and the average parameters of
+    /// the `USAGE_DAYS` days up to `today`. Counts get Laplace noise and are
+    /// rounded, regions with few searches are left out. The sums of the
+    /// averages get Laplace noise scaled by the range of their parameter,
+    /// and the averages are rounded and left out while there are few
+    /// searches.
+    pub fn public(&self, today: u64) -> PublicUsage {
+        let days = self.days.lock().unwrap();
+        let recent = days.range(today.saturating_sub(USAGE_DAYS - 1)..=today);
+
+        let mut searches_per_day = vec![];
+        let mut regions: BTreeMap<(i32, i32), u64> = BTreeMap::new();
+        let (mut total, mut sums) = (0, [0.0; 3]);
+        for (day, usage) in recent {
+            let (year, month, day_of_month) = civil_from_days(*day as i64);
+            searches_per_day.push(DaySearches {
+                date: format!("{year:04}-{month:02}-{day_of_month:02}"),
+                searches: self.noisy_count(usage.searches, *day),
+            });
+            for (region, searches) in &usage.regions {
+                *regions.entry(*region).or_default() += searches;
+            }
+            total += usage.searches;
+            sums[0] += usage.glide_number;
+            sums[1] += usage.wind_speed;
+            sums[2] += usage.additional_height;
+        }
+
+        let mut top_regions = regions
+            .into_iter()
+            .map(|((lat, lon), searches)| RegionSearches {
+                lat: lat as f32 * REGION_DEGREES,
+                lon: lon as f32 * REGION_DEGREES,
+                searches: self.noisy_count(searches, region_key(lat, lon)),
+            })
+            .filter(|region| region.searches >= MIN_REGION_SEARCHES)
+            .collect::<Vec<_>>();
+        top_regions.sort_by_key(|region| Reverse(region.searches));
+        top_regions.truncate(TOP_REGIONS);
+
+        let averages = (self.noisy_count(total, u64::MAX) >= MIN_REGION_SEARCHES).then(|| {
+            let average = |i: usize, (min, max): (f32, f32), step: f64| {
+                let noise = (max - min) as f64 * self.noise(total, u64::MAX - 1 - i as u64);
+                let mean = ((sums[i] + noise) / total as f64).clamp(min as f64, max as f64);
+                ((mean / step).round() * step) as f32
+            };
+            AverageParameters {
+                glide_number: average(0, GLIDE_NUMBER_RANGE, 0.5),
+                wind_speed: average(1, WIND_SPEED_RANGE, 5.0),
+                additional_height: average(2, ADDITIONAL_HEIGHT_RANGE, 50.0),
+            }
+        });
+
+        PublicUsage {
+            searches_per_day,
+            top_regions,
+            averages,
+        }
+    }
+
+    /// `count` with the noise of `key` and `count` added, rounded to
+    /// `COUNT_ROUNDING`. Drawing new noise for every count keeps snapshots
+    /// from being differenced to spot single searches.
+    fn noisy_count(&self, count: u64, key: u64) -> u64 {
+        let rounding = COUNT_ROUNDING as f64;
+        ((count as f64 + self.noise(count, key)).max(0.0) / rounding).round() as u64
+            * COUNT_ROUNDING
+    }
+
+    /// Laplace noise with scale `NOISE_SCALE`, seeded by `key` and `count`.
+    fn noise(&self, count: u64, key: u64) -> f64 {
+        let seed = self.secret ^ key ^ count.rotate_left(32);
+        // Uniform in (-0.5, 0.5), from the top 53 bits of the hash.
+        let u = ((splitmix64(seed) >> 11) as f64 + 0.5) / (1u64 << 53) as f64 - 0.5;
+        -NOISE_SCALE * u.signum() * (1.0 - 2.0 * u.abs()).ln()
+    }
+}
+
+/// Usage of this process, with a new secret for the noise on every start.
+pub fn usage_stats() -> &'static UsageStats {
+    static INSTANCE: OnceLock<UsageStats> = OnceLock::new();
+    INSTANCE.get_or_init(|| UsageStats::new(RandomState::new().hash_one(0u64)))
+}
+
+/// Days since the unix epoch in UTC.
+pub fn current_day() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        / 86_400
+}
+
+/// Converts days since the unix epoch to a (year, month, day) date.
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = if z >= 0 { z } else { z - 146_096 } / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+fn region_of(lat: f32, lon: f32) -> (i32, i32) {
+    (
+        (lat / REGION_DEGREES).floor() as i32,
+        (lon / REGION_DEGREES).floor() as i32,
+    )
+}
+
+/// Noise key of a region, hashed so it does not collide with the keys of
+/// days.
+fn region_key(lat: i32, lon: i32) -> u64 {
+    splitmix64(((lat as u32 as u64) << 32) | lon as u32 as u64)
+}
+
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+#[path = "./usage_test.rs"]
+mod usage_test;
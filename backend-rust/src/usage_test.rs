@@ -0,0 +1,86 @@
+use super::{
+    civil_from_days, SearchParameters, UsageStats, ADDITIONAL_HEIGHT_RANGE, COUNT_ROUNDING,
+    GLIDE_NUMBER_RANGE, MIN_REGION_SEARCHES, USAGE_DAYS, WIND_SPEED_RANGE,
+};
+
+const PARAMETERS: SearchParameters = SearchParameters {
+    glide_number: 8.2,
+    wind_speed: 12.0,
+    additional_height: 30.0,
+};
+
+#[test]
+fn test_civil_from_days() {
+    assert_eq!(civil_from_days(0), (1970, 1, 1));
+    assert_eq!(civil_from_days(20_742), (2026, 10, 16));
+    assert_eq!(civil_from_days(-1), (1969, 12, 31));
+}
+
+#[test]
+fn test_public_usage() {
+    let stats = UsageStats::new(42);
+    let today = 20_742;
+    for _ in 0..500 {
+        stats.record(today, 47.3, 11.4, PARAMETERS);
+    }
+    for _ in 0..3 {
+        stats.record(today - 1, -33.9, 18.4, PARAMETERS);
+    }
+
+    let usage = stats.public(today);
+    assert_eq!(usage.searches_per_day.len(), 2);
+    assert_eq!(usage.searches_per_day[1].date, "2026-10-16");
+    for day in &usage.searches_per_day {
+        assert_eq!(day.searches % COUNT_ROUNDING, 0);
+    }
+    let busy = usage.searches_per_day[1].searches;
+    assert!((450..=550).contains(&busy));
+
+    // The region with 3 searches is hidden, the other one is reported.
+    assert_eq!(usage.top_regions.len(), 1);
+    assert_eq!(
+        (usage.top_regions[0].lat, usage.top_regions[0].lon),
+        (47.0, 11.0)
+    );
+    assert!(usage.top_regions[0].searches >= MIN_REGION_SEARCHES);
+
+    // The averages are noised, but stay within a rounding step or two.
+    let averages = usage.averages.clone().unwrap();
+    assert!((7.5..=9.0).contains(&averages.glide_number));
+    assert!((5.0..=15.0).contains(&averages.wind_speed));
+    assert!((0.0..=100.0).contains(&averages.additional_height));
+
+    // Asking again gives the same noise.
+    assert_eq!(stats.public(today), usage);
+}
+
+#[test]
+fn test_clamped_parameters() {
+    let stats = UsageStats::new(7);
+    for _ in 0..500 {
+        stats.record(
+            20_742,
+            47.3,
+            11.4,
+            SearchParameters {
+                glide_number: 1e9,
+                wind_speed: -1e9,
+                additional_height: 1e9,
+            },
+        );
+    }
+    let averages = stats.public(20_742).averages.unwrap();
+    assert_eq!(averages.glide_number, GLIDE_NUMBER_RANGE.1);
+    assert_eq!(averages.wind_speed, WIND_SPEED_RANGE.0);
+    assert_eq!(averages.additional_height, ADDITIONAL_HEIGHT_RANGE.1);
+}
+
+#[test]
+fn test_usage_days() {
+    let stats = UsageStats::new(1);
+    stats.record(100, 47.3, 11.4, PARAMETERS);
+    stats.record(100 + USAGE_DAYS, 47.3, 11.4, PARAMETERS);
+    let usage = stats.public(100 + USAGE_DAYS);
+    assert_eq!(usage.searches_per_day.len(), 1);
+    assert_eq!(usage.averages, None);
+}
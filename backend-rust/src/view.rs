@@ -0,0 +1,113 @@
+use std::{f32::consts::PI, str::FromStr};
+
+use serde::Serialize;
+
+use crate::height_data::get_bounds_around_point;
+
+/// Size of a web map tile in pixels.
+const TILE_SIZE: f32 = 256.0;
+pub const MAX_ZOOM: u8 = 18;
+
+/// Map viewport suggested to a frontend before the cone is computed.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct MapView {
+    pub lat: (f32, f32),
+    pub lon: (f32, f32),
+    pub zoom: u8,
+}
+
+pub(crate) fn mercator_y(latitude: f32) -> f32 {
+    (PI / 4.0 + latitude.to_radians() / 2.0).tan().ln()
+}
+
+/// Largest web mercator zoom level at which the bounds fit into a viewport of
+/// `width` x `height` pixels.
+pub fn zoom_to_fit(latitudes: (f32, f32), longitudes: (f32, f32), width: u32, height: u32) -> u8 {
+    let lon_fraction = (longitudes.1 - longitudes.0) / 360.0;
+    let lat_fraction = (mercator_y(latitudes.1) - mercator_y(latitudes.0)) / (2.0 * PI);
+
+    let zoom_lon = (width as f32 / (TILE_SIZE * lon_fraction)).log2();
+    let zoom_lat = (height as f32 / (TILE_SIZE * lat_fraction)).log2();
+
+    zoom_lon.min(zoom_lat).floor().clamp(0.0, MAX_ZOOM as f32) as u8
+}
+
+fn latitude_of_mercator_y(y: f32) -> f32 {
+    y.sinh().atan().to_degrees()
+}
+
+/// Projection of the images of a search.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum RasterProjection {
+    /// Rows evenly spaced in latitude, like the search grid. Stretched
+    /// north-south when drawn on a web map, the more the further north.
+    #[default]
+    PlateCarree,
+    /// Rows evenly spaced in Web Mercator (EPSG:3857), so the image can be
+    /// placed on a web map by its corners.
+    WebMercator,
+}
+
+impl FromStr for RasterProjection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "plate_carree" => Ok(RasterProjection::PlateCarree),
+            "web_mercator" => Ok(RasterProjection::WebMercator),
+            _ => Err(format!("Unknown projection {s}")),
+        }
+    }
+}
+
+/// For each of `rows` rows from the north of an image spanning `latitudes`
+/// in Web Mercator, the row of the same image in plate carree that is shown
+/// there. Both have the same number of rows.
+pub fn mercator_rows(latitudes: (f32, f32), rows: usize) -> Vec<usize> {
+    let (south, north) = (mercator_y(latitudes.0), mercator_y(latitudes.1));
+    (0..rows)
+        .map(|row| {
+            let y = north - (row as f32 + 0.5) / rows as f32 * (north - south);
+            let fraction = (latitudes.1 - latitude_of_mercator_y(y)) / (latitudes.1 - latitudes.0);
+            ((fraction * rows as f32) as usize).min(rows - 1)
+        })
+        .collect()
+}
+
+/// Web map tiles `(x, y)` at zoom level `z` that cover the bounds, row by
+/// row from the north west.
+pub fn tiles_covering(latitudes: (f32, f32), longitudes: (f32, f32), z: u8) -> Vec<(u32, u32)> {
+    let n = (1u32 << z) as f32;
+    let tile = |value: f32| (value * n).floor().clamp(0.0, n - 1.0) as u32;
+    let x = |longitude: f32| tile((longitude + 180.0) / 360.0);
+    let y = |latitude: f32| tile((1.0 - mercator_y(latitude.clamp(-85.0, 85.0)) / PI) / 2.0);
+
+    (y(latitudes.1)..=y(latitudes.0))
+        .flat_map(|row| (x(longitudes.0)..=x(longitudes.1)).map(move |col| (col, row)))
+        .collect()
+}
+
+/// View containing everything within `max_distance` meters of the start, which
+/// is an upper bound for the extent of the cone.
+pub fn suggest_view(
+    latitude: f32,
+    longitude: f32,
+    max_distance: f32,
+    width: u32,
+    height: u32,
+) -> MapView {
+    let (lat_min, lat_max, lon_min, lon_max) =
+        get_bounds_around_point(latitude, longitude, max_distance);
+    let lat = (lat_min.max(-85.0), lat_max.min(85.0));
+    let lon = (lon_min, lon_max);
+
+    MapView {
+        lat,
+        lon,
+        zoom: zoom_to_fit(lat, lon, width, height),
+    }
+}
+
+#[cfg(test)]
+#[path = "./view_test.rs"]
+mod view_test;
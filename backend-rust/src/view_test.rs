@@ -0,0 +1,67 @@
+use super::{mercator_rows, suggest_view, tiles_covering, zoom_to_fit, RasterProjection, MAX_ZOOM};
+
+#[test]
+fn test_zoom_to_fit() {
+    // The whole world fits into a single tile at zoom 0.
+    assert_eq!(zoom_to_fit((-85.0, 85.0), (-180.0, 180.0), 256, 256), 0);
+    assert_eq!(zoom_to_fit((-85.0, 85.0), (-180.0, 180.0), 512, 512), 1);
+    assert_eq!(
+        zoom_to_fit((47.0, 47.0001), (11.0, 11.0001), 1024, 768),
+        MAX_ZOOM
+    );
+}
+
+#[test]
+fn test_suggest_view() {
+    let view = suggest_view(47.0, 11.0, 20000.0, 1024, 768);
+
+    assert!(view.lat.0 < 47.0 && view.lat.1 > 47.0);
+    assert!(view.lon.0 < 11.0 && view.lon.1 > 11.0);
+
+    let far = suggest_view(47.0, 11.0, 40000.0, 1024, 768);
+    assert_eq!(far.zoom + 1, view.zoom);
+}
+
+#[test]
+fn test_tiles_covering() {
+    assert_eq!(
+        tiles_covering((-85.0, 85.0), (-180.0, 180.0), 0),
+        vec![(0, 0)]
+    );
+    assert_eq!(tiles_covering((-85.0, 85.0), (-180.0, 180.0), 1).len(), 4);
+
+    // Innsbruck is in tile 4355/2872 at zoom 13.
+    assert_eq!(
+        tiles_covering((47.26, 47.27), (11.39, 11.40), 13),
+        vec![(4355, 2872)]
+    );
+    // Rows grow southwards.
+    let tiles = tiles_covering((47.0, 47.5), (11.0, 11.5), 10);
+    assert_eq!(tiles.first(), Some(&(543, 358)));
+    assert_eq!(tiles.last(), Some(&(544, 360)));
+    assert_eq!(tiles.len(), 6);
+}
+
+#[test]
+fn test_mercator_rows() {
+    assert_eq!("web_mercator".parse(), Ok(RasterProjection::WebMercator));
+    assert_eq!("plate_carree".parse(), Ok(RasterProjection::PlateCarree));
+    assert!("utm".parse::<RasterProjection>().is_err());
+
+    // Small areas are hardly distorted.
+    assert_eq!(
+        mercator_rows((47.0, 47.001), 10),
+        (0..10).collect::<Vec<_>>()
+    );
+
+    // In the north of large areas, Web Mercator rows cover less latitude, so
+    // rows of the source are repeated there and skipped in the south.
+    let rows = mercator_rows((40.0, 70.0), 100);
+    assert_eq!(rows.len(), 100);
+    assert_eq!(rows[0], 0);
+    assert_eq!(rows[99], 99);
+    assert!(rows.windows(2).all(|pair| pair[0] <= pair[1]));
+    assert!(rows[..10].windows(2).any(|pair| pair[0] == pair[1]));
+    assert!(rows[90..].windows(2).any(|pair| pair[1] - pair[0] > 1));
+    assert!(rows[50] < 50);
+}
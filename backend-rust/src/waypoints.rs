@@ -0,0 +1,80 @@
+/// Feet per meter, OziExplorer stores altitudes in feet.
+const FEET_PER_METER: f32 = 3.280_84;
+/// SeeYou waypoint style of an outlanding field.
+const CUP_STYLE_OUTLANDING: u8 = 3;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Waypoint {
+    pub name: String,
+    pub latitude: f32,
+    pub longitude: f32,
+    /// Elevation in meters.
+    pub elevation: f32,
+    pub description: String,
+}
+
+/// Degrees and decimal minutes as used by SeeYou, e.g. `4716.150N` for a
+/// latitude and `01124.250E` for a longitude.
+fn cup_coordinate(value: f32, degree_digits: usize, hemispheres: [char; 2]) -> String {
+    let hemisphere = if value < 0.0 {
+        hemispheres[1]
+    } else {
+        hemispheres[0]
+    };
+    let thousandths = (value.abs() as f64 * 60_000.0).round() as u64;
+    let (degrees, minutes) = (thousandths / 60_000, thousandths % 60_000);
+    format!(
+        "{degrees:0degree_digits$}{:02}.{:03}{hemisphere}",
+        minutes / 1000,
+        minutes % 1000
+    )
+}
+
+fn cup_quoted(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "'"))
+}
+
+/// Waypoints in the SeeYou `.cup` format, all of them as outlanding fields.
+pub fn format_cup(waypoints: &[Waypoint]) -> String {
+    let mut cup = String::from("name,code,country,lat,lon,elev,style,rwdir,rwlen,freq,desc\r\n");
+    for waypoint in waypoints {
+        cup += &format!(
+            "{},{},,{},{},{:.1}m,{CUP_STYLE_OUTLANDING},,,,{}\r\n",
+            cup_quoted(&waypoint.name),
+            cup_quoted(&waypoint.name),
+            cup_coordinate(waypoint.latitude, 2, ['N', 'S']),
+            cup_coordinate(waypoint.longitude, 3, ['E', 'W']),
+            waypoint.elevation,
+            cup_quoted(&waypoint.description),
+        );
+    }
+    cup
+}
+
+/// OziExplorer fields are separated by commas and can not escape them.
+fn wpt_field(value: &str) -> String {
+    value.replace(',', ";")
+}
+
+/// Waypoints in the OziExplorer `.wpt` format with WGS 84 coordinates.
+pub fn format_wpt(waypoints: &[Waypoint]) -> String {
+    let mut wpt = String::from(
+        "OziExplorer Waypoint File Version 1.1\r\nWGS 84\r\nReserved 2\r\nReserved 3\r\n",
+    );
+    for (number, waypoint) in waypoints.iter().enumerate() {
+        wpt += &format!(
+            "{},{},{:.6},{:.6},,0,1,3,0,65535,{},0,0,0,{:.0}\r\n",
+            number + 1,
+            wpt_field(&waypoint.name),
+            waypoint.latitude,
+            waypoint.longitude,
+            wpt_field(&waypoint.description),
+            waypoint.elevation * FEET_PER_METER,
+        );
+    }
+    wpt
+}
+
+#[cfg(test)]
+#[path = "./waypoints_test.rs"]
+mod waypoints_test;
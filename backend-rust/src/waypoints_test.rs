@@ -0,0 +1,54 @@
+use super::{format_cup, format_wpt, Waypoint};
+
+fn waypoints() -> Vec<Waypoint> {
+    vec![
+        Waypoint {
+            name: "LZ01".to_string(),
+            latitude: 47.269_17,
+            longitude: 11.404_167,
+            elevation: 560.0,
+            description: "Arrival 350 m AGL, \"flat\"".to_string(),
+        },
+        Waypoint {
+            name: "LZ02".to_string(),
+            latitude: -33.5,
+            longitude: -70.75,
+            elevation: 1200.4,
+            description: "Arrival 20 m AGL".to_string(),
+        },
+    ]
+}
+
+#[test]
+fn test_format_cup() {
+    let cup = format_cup(&waypoints());
+    let lines = cup.split("\r\n").collect::<Vec<_>>();
+
+    assert_eq!(
+        lines,
+        vec![
+            "name,code,country,lat,lon,elev,style,rwdir,rwlen,freq,desc",
+            "\"LZ01\",\"LZ01\",,4716.150N,01124.250E,560.0m,3,,,,\"Arrival 350 m AGL, 'flat'\"",
+            "\"LZ02\",\"LZ02\",,3330.000S,07045.000W,1200.4m,3,,,,\"Arrival 20 m AGL\"",
+            "",
+        ]
+    );
+}
+
+#[test]
+fn test_format_wpt() {
+    let wpt = format_wpt(&waypoints());
+    let lines = wpt.split("\r\n").collect::<Vec<_>>();
+
+    assert_eq!(lines[0], "OziExplorer Waypoint File Version 1.1");
+    assert_eq!(lines[1], "WGS 84");
+    assert_eq!(
+        lines[4],
+        "1,LZ01,47.269169,11.404167,,0,1,3,0,65535,Arrival 350 m AGL; \"flat\",0,0,0,1837"
+    );
+    assert_eq!(
+        lines[5],
+        "2,LZ02,-33.500000,-70.750000,,0,1,3,0,65535,Arrival 20 m AGL,0,0,0,3938"
+    );
+    assert_eq!(lines.len(), 7);
+}
@@ -0,0 +1,73 @@
+use std::f32::consts::PI;
+
+/// Wind at one altitude, e.g. from a forecast for a pressure level.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WindLayer {
+    /// Altitude above sea level in meters.
+    pub altitude: f32,
+    /// Wind speed in km/h.
+    pub speed: f32,
+    /// Direction the wind comes from, in radians.
+    pub direction: f32,
+}
+
+/// Parses comma separated `altitude:speed:direction` triples, e.g.
+/// `1500:10:270,2500:25:300`, with the direction in degrees. Altitudes must be
+/// increasing.
+pub fn parse_wind_layers(value: &str) -> Result<Vec<WindLayer>, String> {
+    let mut result: Vec<WindLayer> = vec![];
+    for layer in value.split(',') {
+        let parts = layer.split(':').map(str::trim).collect::<Vec<_>>();
+        let [altitude, speed, direction] = parts[..] else {
+            return Err(format!(
+                "Wind layer {layer} is not of the form altitude:speed:direction"
+            ));
+        };
+        let number = |part: &str, name: &str| {
+            part.parse::<f32>()
+                .map_err(|e| format!("Bad wind layer {name} {part}: {e}"))
+        };
+        let layer = WindLayer {
+            altitude: number(altitude, "altitude")?,
+            speed: number(speed, "speed")?,
+            direction: number(direction, "direction")?.to_radians(),
+        };
+        if layer.speed.is_nan() || layer.speed < 0.0 {
+            return Err(format!("Wind layer at {altitude} m has a negative speed"));
+        }
+        if result
+            .last()
+            .is_some_and(|last| last.altitude >= layer.altitude)
+        {
+            return Err("Wind layer altitudes must be increasing".to_string());
+        }
+        result.push(layer);
+    }
+    Ok(result)
+}
+
+/// Wind speed and direction at `altitude`. Between two layers the wind vectors
+/// are interpolated linearly, below the lowest and above the highest layer
+/// their wind is used.
+pub fn wind_at_altitude(layers: &[WindLayer], altitude: f32) -> (f32, f32) {
+    let upper = layers.partition_point(|layer| layer.altitude < altitude);
+    let (a, b) = match (upper.checked_sub(1), layers.get(upper)) {
+        (Some(lower), Some(upper)) => (&layers[lower], upper),
+        (Some(lower), None) => return (layers[lower].speed, layers[lower].direction),
+        (None, Some(upper)) => return (upper.speed, upper.direction),
+        (None, None) => return (0.0, 0.0),
+    };
+
+    let t = (altitude - a.altitude) / (b.altitude - a.altitude);
+    let east = a.speed * a.direction.sin() * (1.0 - t) + b.speed * b.direction.sin() * t;
+    let north = a.speed * a.direction.cos() * (1.0 - t) + b.speed * b.direction.cos() * t;
+    (east.hypot(north), east.atan2(north).rem_euclid(2.0 * PI))
+}
+
+pub fn max_wind_speed(layers: &[WindLayer]) -> f32 {
+    layers.iter().map(|layer| layer.speed).fold(0.0, f32::max)
+}
+
+#[cfg(test)]
+#[path = "./wind_test.rs"]
+mod wind_test;
@@ -0,0 +1,39 @@
+use std::f32::consts::PI;
+
+use approx::assert_relative_eq;
+
+use super::{max_wind_speed, parse_wind_layers, wind_at_altitude};
+
+#[test]
+fn test_parse_wind_layers() {
+    let layers = parse_wind_layers("1500:10:270, 2500:25:0").unwrap();
+    assert_eq!(layers.len(), 2);
+    assert_eq!(layers[0].altitude, 1500.0);
+    assert_eq!(layers[1].speed, 25.0);
+    assert_relative_eq!(layers[0].direction, 3.0 * PI / 2.0);
+    assert_eq!(max_wind_speed(&layers), 25.0);
+
+    assert!(parse_wind_layers("").is_err());
+    assert!(parse_wind_layers("1500:10").is_err());
+    assert!(parse_wind_layers("1500:-10:0").is_err());
+    assert!(parse_wind_layers("2500:10:0,1500:10:0").is_err());
+}
+
+#[test]
+fn test_wind_at_altitude() {
+    let layers = parse_wind_layers("1000:10:90,2000:30:90,3000:10:270").unwrap();
+
+    // Outside of the layers the closest one is used.
+    assert_eq!(wind_at_altitude(&layers, 500.0), (10.0, PI / 2.0));
+    assert_eq!(wind_at_altitude(&layers, 4000.0), (10.0, 3.0 * PI / 2.0));
+
+    let (speed, direction) = wind_at_altitude(&layers, 1500.0);
+    assert_relative_eq!(speed, 20.0, epsilon = 1e-4);
+    assert_relative_eq!(direction, PI / 2.0, epsilon = 1e-4);
+
+    // Opposite winds partly cancel out.
+    let (speed, _) = wind_at_altitude(&layers, 2750.0);
+    assert_relative_eq!(speed, 30.0 * 0.25 - 10.0 * 0.75, epsilon = 1e-4);
+
+    assert_eq!(wind_at_altitude(&[], 1000.0), (0.0, 0.0));
+}
@@ -0,0 +1,80 @@
+/// 1980-01-01 00:00 in MS-DOS format, the earliest date a zip entry can have.
+/// Exports are generated on the fly, so no meaningful date exists.
+const DOS_DATE: u16 = (1 << 5) | 1;
+const DOS_TIME: u16 = 0;
+/// File names are UTF-8.
+const FLAGS: u16 = 1 << 11;
+const VERSION: u16 = 10;
+
+fn put_u16(out: &mut Vec<u8>, value: u16) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+/// Zip archive of the given `(name, content)` files. The files are stored
+/// without compression, most exports (PNG) are compressed already. Archives
+/// must stay below 4 GiB.
+pub fn zip_stored(files: &[(String, Vec<u8>)]) -> Vec<u8> {
+    let mut out = vec![];
+    let mut central_directory = vec![];
+
+    for (name, content) in files {
+        let offset = out.len() as u32;
+        let crc = crc32fast::hash(content);
+
+        put_u32(&mut out, 0x04034b50);
+        put_u16(&mut out, VERSION);
+        put_u16(&mut out, FLAGS);
+        put_u16(&mut out, 0);
+        put_u16(&mut out, DOS_TIME);
+        put_u16(&mut out, DOS_DATE);
+        put_u32(&mut out, crc);
+        put_u32(&mut out, content.len() as u32);
+        put_u32(&mut out, content.len() as u32);
+        put_u16(&mut out, name.len() as u16);
+        put_u16(&mut out, 0);
+        out.extend_from_slice(name.as_bytes());
+        out.extend_from_slice(content);
+
+        let entry = &mut central_directory;
+        put_u32(entry, 0x02014b50);
+        put_u16(entry, VERSION);
+        put_u16(entry, VERSION);
+        put_u16(entry, FLAGS);
+        put_u16(entry, 0);
+        put_u16(entry, DOS_TIME);
+        put_u16(entry, DOS_DATE);
+        put_u32(entry, crc);
+        put_u32(entry, content.len() as u32);
+        put_u32(entry, content.len() as u32);
+        put_u16(entry, name.len() as u16);
+        // Extra field, comment, disk, internal and external attributes.
+        put_u16(entry, 0);
+        put_u16(entry, 0);
+        put_u16(entry, 0);
+        put_u16(entry, 0);
+        put_u32(entry, 0);
+        put_u32(entry, offset);
+        entry.extend_from_slice(name.as_bytes());
+    }
+
+    let central_directory_offset = out.len() as u32;
+    out.extend_from_slice(&central_directory);
+
+    put_u32(&mut out, 0x06054b50);
+    put_u16(&mut out, 0);
+    put_u16(&mut out, 0);
+    put_u16(&mut out, files.len() as u16);
+    put_u16(&mut out, files.len() as u16);
+    put_u32(&mut out, central_directory.len() as u32);
+    put_u32(&mut out, central_directory_offset);
+    put_u16(&mut out, 0);
+    out
+}
+
+#[cfg(test)]
+#[path = "./zip_test.rs"]
+mod zip_test;
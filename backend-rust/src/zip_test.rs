@@ -0,0 +1,44 @@
+use super::zip_stored;
+
+fn u16_at(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+#[test]
+fn test_zip_stored() {
+    let zip = zip_stored(&[
+        ("a.txt".to_string(), b"hello".to_vec()),
+        ("b.json".to_string(), b"{}".to_vec()),
+    ]);
+
+    // Local header of the first file, followed by its name and content.
+    assert_eq!(u32_at(&zip, 0), 0x04034b50);
+    assert_eq!(u32_at(&zip, 14), 0x3610a686);
+    assert_eq!(u32_at(&zip, 18), 5);
+    assert_eq!(&zip[30..35], b"a.txt");
+    assert_eq!(&zip[35..40], b"hello");
+    assert_eq!(u32_at(&zip, 40), 0x04034b50);
+
+    let end = zip.len() - 22;
+    assert_eq!(u32_at(&zip, end), 0x06054b50);
+    assert_eq!(u16_at(&zip, end + 10), 2);
+    let central_directory = u32_at(&zip, end + 16) as usize;
+    assert_eq!(central_directory + u32_at(&zip, end + 12) as usize, end);
+
+    // Central directory entry of the second file points to its local header.
+    let second = central_directory + 46 + 5;
+    assert_eq!(u32_at(&zip, second), 0x02014b50);
+    assert_eq!(u32_at(&zip, second + 42), 40);
+    assert_eq!(&zip[second + 46..second + 52], b"b.json");
+}
+
+#[test]
+fn test_zip_stored_empty() {
+    let zip = zip_stored(&[]);
+    assert_eq!(zip.len(), 22);
+    assert_eq!(u16_at(&zip, 10), 0);
+}
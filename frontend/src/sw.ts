@@ -604,8 +604,9 @@ self.addEventListener("message", (event) => {
 cleanupOutdatedCaches();
 precacheAndRoute(self.__WB_MANIFEST);
 
-// Navigation fallback: serve the SPA shell for all navigate requests that are
-// not backend API endpoints.
+// Navigation fallback: serve the SPA shell for navigations to the pages of the
+// app. Everything else on the origin, e.g. the API, exports and the debug UI,
+// is served by the backend, so new endpoints need no entry here.
 //
 // In dev/prod, the precached URL key can vary with base path handling
 // (`/index.html`, `/static/index.html`, or `/static/`). We probe the known
@@ -627,24 +628,24 @@ for (const candidate of navigationFallbackCandidates) {
   }
 }
 
+// Paths of the pages of the app: the index and the files under `/static/`.
+const APP_PATH_PATTERN = /^\/(static\/.*)?$/;
+
 if (navigationHandler !== undefined) {
   registerRoute(
     new NavigationRoute(navigationHandler, {
-      denylist: [
-        /^\/(flight_cone|flight_cone_ws|flight_cone_bounds|raw_height_image|height_map|agl_image|height_image|kml|search_ws|flying_sites|opentopomap|openstreetmap|satellite|stats)/,
-      ],
+      // Matched against the path with the query.
+      allowlist: [/^\/(static\/[^?]*)?(\?.*)?$/],
     }),
   );
 }
 
-const API_PATH_PATTERN =
-  /^\/(flight_cone|flight_cone_ws|flight_cone_bounds|raw_height_image|height_map|agl_image|height_image|kml|search_ws|flying_sites|opentopomap|openstreetmap|satellite|stats)/;
-
 function isRuntimeDependencyRequest(request: Request, url: URL): boolean {
   if (url.origin !== self.location.origin) {
     return false;
   }
-  if (API_PATH_PATTERN.test(url.pathname)) {
+
+  if (!APP_PATH_PATTERN.test(url.pathname)) {
     return false;
   }
 